@@ -0,0 +1,61 @@
+// Copyright 2018 Serde YAML Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Time to deserialize number-heavy documents into `Value`, reading either
+//! none or all of the numbers back.
+
+extern crate serde_yaml;
+
+use std::fmt::Write;
+use std::time::Instant;
+
+use serde_yaml::Value;
+
+/// Time series samples: mostly floats, a few integers.
+fn samples() -> String {
+    let mut yaml = String::new();
+    for i in 0..20000 {
+        writeln!(yaml,
+                 "- [{}, {}.{}, -{}.25e-3, {}.5]",
+                 1500000000 + i,
+                 i % 97,
+                 i % 1000,
+                 i % 13,
+                 i)
+            .unwrap();
+    }
+    yaml
+}
+
+fn sum(value: &Value) -> f64 {
+    match *value {
+        Value::Number(ref n) => n.as_f64().unwrap(),
+        Value::Sequence(ref seq) => seq.iter().map(sum).sum(),
+        _ => 0.0,
+    }
+}
+
+fn main() {
+    const ITERATIONS: u32 = 10;
+    let yaml = samples();
+
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        let value: Value = serde_yaml::from_str(&yaml).unwrap();
+        drop(value);
+    }
+    println!("parse only      {:>10.2?}/iter", start.elapsed() / ITERATIONS);
+
+    let start = Instant::now();
+    let mut total = 0.0;
+    for _ in 0..ITERATIONS {
+        let value: Value = serde_yaml::from_str(&yaml).unwrap();
+        total += sum(&value);
+    }
+    println!("parse and read  {:>10.2?}/iter ({})", start.elapsed() / ITERATIONS, total);
+}
@@ -0,0 +1,83 @@
+// Copyright 2018 Serde YAML Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Time to deserialize a small document into a large struct behind a `Box`,
+//! an `Rc` and an `Arc`, through Serde's impls for the pointers.
+
+extern crate serde;
+extern crate serde_yaml;
+
+use std::fmt;
+use std::rc::Rc;
+use std::sync::Arc;
+use std::time::Instant;
+
+use serde::de::{self, Deserialize, Deserializer, MapAccess, Visitor};
+
+/// A config with a large inline table, so that copying it costs as much as
+/// deserializing it.
+struct Config {
+    name: String,
+    table: [u64; 4096],
+}
+
+impl<'de> Deserialize<'de> for Config {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where D: Deserializer<'de>
+    {
+        struct ConfigVisitor;
+
+        impl<'de> Visitor<'de> for ConfigVisitor {
+            type Value = Config;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a config")
+            }
+
+            fn visit_map<A>(self, mut map: A) -> Result<Config, A::Error>
+                where A: MapAccess<'de>
+            {
+                let mut config = Config {
+                    name: String::new(),
+                    table: [0; 4096],
+                };
+                while let Some(key) = map.next_key::<String>()? {
+                    match key.as_str() {
+                        "name" => config.name = map.next_value()?,
+                        "fill" => config.table = [map.next_value()?; 4096],
+                        _ => return Err(de::Error::unknown_field(&key, &["name", "fill"])),
+                    }
+                }
+                Ok(config)
+            }
+        }
+
+        deserializer.deserialize_map(ConfigVisitor)
+    }
+}
+
+fn main() {
+    const ITERATIONS: u32 = 20000;
+    let yaml = "name: web\nfill: 7\n";
+
+    macro_rules! time {
+        ($label:expr, $parse:expr) => {
+            let start = Instant::now();
+            let mut total = 0;
+            for _ in 0..ITERATIONS {
+                let config = $parse;
+                total += config.table[4095] + config.name.len() as u64;
+            }
+            println!("{:<22}{:>10.2?}/iter ({})", $label, start.elapsed() / ITERATIONS, total);
+        };
+    }
+
+    time!("Box<Config>", serde_yaml::from_str::<Box<Config>>(yaml).unwrap());
+    time!("Rc<Config>", serde_yaml::from_str::<Rc<Config>>(yaml).unwrap());
+    time!("Arc<Config>", serde_yaml::from_str::<Arc<Config>>(yaml).unwrap());
+}
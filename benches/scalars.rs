@@ -0,0 +1,95 @@
+// Copyright 2018 Serde YAML Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Time and peak heap use of deserializing documents of mostly short or
+//! mostly long scalars. Compare `cargo bench --bench scalars` against
+//! `cargo bench --bench scalars --features sso`.
+
+extern crate serde;
+extern crate serde_yaml;
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::fmt::Write;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+
+use serde::de::IgnoredAny;
+
+struct Counting;
+
+static LIVE: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let live = LIVE.fetch_add(layout.size(), Ordering::SeqCst) + layout.size();
+        PEAK.fetch_max(live, Ordering::SeqCst);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        LIVE.fetch_sub(layout.size(), Ordering::SeqCst);
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Counting = Counting;
+
+/// Kubernetes-style manifests: short keys, short values.
+fn manifests() -> String {
+    let mut yaml = String::new();
+    for i in 0..2000 {
+        write!(yaml,
+               "- apiVersion: apps/v1\n  kind: Deployment\n  metadata:\n    name: service-{}\n    \
+                labels: {{app: web, tier: frontend}}\n  spec:\n    replicas: 3\n    template:\n      \
+                spec:\n        containers:\n          - name: web\n            image: nginx:1.{}\n            \
+                ports: [80, 443]\n",
+               i,
+               i % 20)
+            .unwrap();
+    }
+    yaml
+}
+
+/// Translation catalogs: short keys, sentence-length values.
+fn catalog() -> String {
+    let mut yaml = String::new();
+    for i in 0..5000 {
+        writeln!(yaml,
+                 "message_{}: \"The quick brown fox jumps over the lazy dog, message number {}.\"",
+                 i,
+                 i)
+            .unwrap();
+    }
+    yaml
+}
+
+fn bench(name: &str, yaml: &str) {
+    const ITERATIONS: u32 = 20;
+
+    let baseline = LIVE.load(Ordering::SeqCst);
+    PEAK.store(baseline, Ordering::SeqCst);
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        let IgnoredAny = serde_yaml::from_str(yaml).unwrap();
+    }
+    let elapsed = start.elapsed() / ITERATIONS;
+    let peak = PEAK.load(Ordering::SeqCst) - baseline;
+    println!("{:<10} {:>8} KiB input {:>10.2?}/iter {:>8} KiB peak heap",
+             name,
+             yaml.len() / 1024,
+             elapsed,
+             peak / 1024);
+}
+
+fn main() {
+    println!("sso feature: {}", cfg!(feature = "sso"));
+    bench("manifests", &manifests());
+    bench("catalog", &catalog());
+}
@@ -0,0 +1,144 @@
+// Copyright 2018 Serde YAML Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The `#[styled]` attribute of Serde YAML, re-exported as
+//! `serde_yaml::styled` with the `derive` feature. See there.
+
+#![deny(missing_docs)]
+
+extern crate proc_macro;
+extern crate proc_macro2;
+#[macro_use]
+extern crate quote;
+#[macro_use]
+extern crate syn;
+
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use syn::{Data, DeriveInput, Error, Field, Ident, LitStr};
+
+/// Turns the `#[yaml(...)]` attributes on the fields of a struct or enum into
+/// `#[serde(serialize_with = "...")]` attributes, with the functions they
+/// name. Goes above `#[derive(Serialize)]`.
+///
+/// - `#[yaml(flow)]` writes a sequence or mapping on one line.
+/// - `#[yaml(block_scalar)]` writes a string as a literal block scalar.
+/// - `#[yaml(comment = "...")]` writes the comment above the field.
+///
+/// As with any `serialize_with`, Serde does not infer the bounds that these
+/// fields need on type parameters, so generic types need a
+/// `#[serde(bound = "...")]`.
+#[proc_macro_attribute]
+pub fn styled(args: TokenStream, input: TokenStream) -> TokenStream {
+    if !args.is_empty() {
+        let args = TokenStream2::from(args);
+        return Error::new_spanned(args, "#[styled] takes no arguments").to_compile_error().into();
+    }
+    let mut item = parse_macro_input!(input as DeriveInput);
+    match expand(&mut item) {
+        Ok(functions) => quote!(#item #(#functions)*).into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+/// Rewrites the fields of `item` and returns the functions they serialize
+/// with.
+fn expand(item: &mut DeriveInput) -> syn::Result<Vec<TokenStream2>> {
+    let mut fields: Vec<&mut Field> = Vec::new();
+    match item.data {
+        Data::Struct(ref mut data) => fields.extend(data.fields.iter_mut()),
+        Data::Enum(ref mut data) => {
+            for variant in &mut data.variants {
+                fields.extend(variant.fields.iter_mut());
+            }
+        }
+        Data::Union(_) => return Err(Error::new(Span::call_site(), "#[styled] does not support unions")),
+    }
+
+    let mut functions = Vec::new();
+    for field in fields {
+        let attrs = match Attrs::take(field)? {
+            Some(attrs) => attrs,
+            None => continue,
+        };
+        let name = Ident::new(&format!("__serde_yaml_styled_{}_{}", item.ident, functions.len()), Span::call_site());
+        let path = LitStr::new(&name.to_string(), Span::call_site());
+        field.attrs.push(parse_quote!(#[serde(serialize_with = #path)]));
+        functions.push(attrs.function(&name));
+    }
+    Ok(functions)
+}
+
+/// What the `#[yaml(...)]` attributes of one field ask for.
+struct Attrs {
+    style: Option<Ident>,
+    comment: Option<LitStr>,
+}
+
+impl Attrs {
+    /// Removes the `#[yaml(...)]` attributes of `field` and reads them, or
+    /// returns `None` if there are none.
+    fn take(field: &mut Field) -> syn::Result<Option<Self>> {
+        let mut attrs = Attrs { style: None, comment: None };
+        let mut found = false;
+        let mut result = Ok(());
+        field.attrs.retain(|attr| {
+            if !attr.path().is_ident("yaml") {
+                return true;
+            }
+            found = true;
+            if result.is_ok() {
+                result = attr.parse_nested_meta(|meta| {
+                    let style = if meta.path.is_ident("flow") {
+                        "Flow"
+                    } else if meta.path.is_ident("block_scalar") {
+                        "Literal"
+                    } else if meta.path.is_ident("comment") {
+                        if attrs.comment.is_some() {
+                            return Err(meta.error("duplicate yaml attribute `comment`"));
+                        }
+                        attrs.comment = Some(meta.value()?.parse()?);
+                        return Ok(());
+                    } else {
+                        return Err(meta.error("unknown yaml attribute, expected `flow`, `block_scalar` or `comment`"));
+                    };
+                    if attrs.style.is_some() {
+                        return Err(meta.error("a field takes only one of `flow` and `block_scalar`"));
+                    }
+                    attrs.style = Some(Ident::new(style, Span::call_site()));
+                    Ok(())
+                });
+            }
+            false
+        });
+        result?;
+        Ok(if found { Some(attrs) } else { None })
+    }
+
+    /// The function named `name` that serializes the field as asked.
+    fn function(&self, name: &Ident) -> TokenStream2 {
+        let value = match self.style {
+            Some(ref style) => quote!(&::serde_yaml::style::Styled(::serde_yaml::style::Style::#style, value)),
+            None => quote!(value),
+        };
+        let body = match self.comment {
+            Some(ref comment) => quote!(::serde_yaml::comment::serialize(#comment, #value, serializer)),
+            None => quote!(::serde_yaml::__private::Serialize::serialize(#value, serializer)),
+        };
+        quote! {
+            #[doc(hidden)]
+            #[allow(non_snake_case)]
+            fn #name<__T: ?Sized, __S>(value: &__T, serializer: __S) -> ::std::result::Result<__S::Ok, __S::Error>
+                where __T: ::serde_yaml::__private::Serialize,
+                      __S: ::serde_yaml::__private::Serializer
+            {
+                #body
+            }
+        }
+    }
+}
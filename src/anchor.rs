@@ -0,0 +1,215 @@
+// Copyright 2018 Serde YAML Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::fmt::{self, Display};
+use std::sync::{Arc, Mutex};
+
+use error::Location;
+
+/// What a `Deserializer` does with an anchor that is defined again within a
+/// document, and with an alias to an anchor that is not defined before it.
+///
+/// ```rust
+/// # extern crate serde;
+/// # extern crate serde_yaml;
+/// #
+/// # fn main() {
+/// use serde::Deserialize;
+/// use serde_yaml::{AnchorPolicy, AnchorWarnings, Deserializer, Value};
+///
+/// let yaml = "a: &x 1\nb: &x 2\nc: *x\nd: *y\n";
+/// assert!(Value::deserialize(Deserializer::from_str(yaml)).is_err());
+///
+/// let warnings = AnchorWarnings::new();
+/// let de = Deserializer::from_str(yaml).anchor_policy(AnchorPolicy::Warn(warnings.clone()));
+/// let value = Value::deserialize(de).unwrap();
+/// assert_eq!(value["c"], 2);
+/// assert_eq!(value["d"], Value::Null);
+/// let warnings: Vec<String> = warnings.warnings().iter().map(ToString::to_string).collect();
+/// assert_eq!(warnings, [
+///     "2:4: anchor `x` is defined again",
+///     "4:4: alias `*y` refers to an undefined anchor",
+/// ]);
+///
+/// let de = Deserializer::from_str(yaml).anchor_policy(AnchorPolicy::Placeholder);
+/// assert_eq!(Value::deserialize(de).unwrap()["d"], "*y");
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub enum AnchorPolicy {
+    /// Aliases refer to the latest definition of an anchor, as YAML
+    /// specifies, and an alias to an undefined anchor is an error. This is
+    /// the default.
+    Last,
+    /// Both a redefined anchor and an alias to an undefined one are errors.
+    Error,
+    /// Carry on as with `Last`, deserializing an alias to an undefined anchor
+    /// as null, and collect a warning about each into the handle.
+    Warn(AnchorWarnings),
+    /// Carry on as with `Last`, deserializing an alias to an undefined anchor
+    /// as a placeholder: the string `*name`, tagged `!alias`, so that a
+    /// `TagPolicy` that wraps `!alias` can tell it apart from other strings.
+    Placeholder,
+}
+
+impl Default for AnchorPolicy {
+    fn default() -> Self {
+        AnchorPolicy::Last
+    }
+}
+
+/// Which anchors an alias can refer to.
+///
+/// YAML gives every document of a stream anchors of its own, so an alias to
+/// an anchor of an earlier document is an error by default. Some tools write
+/// streams that rely on such aliases anyway, which `AnchorScope::Stream`
+/// reads when going through the documents with `Deserializer::documents`.
+///
+/// ```rust
+/// # extern crate serde;
+/// # extern crate serde_yaml;
+/// #
+/// # fn main() {
+/// use serde_yaml::{AnchorScope, Deserializer, Value};
+///
+/// let yaml = "defaults: &defaults {retries: 3}\n---\njob: *defaults\n";
+/// let err = Deserializer::from_str(yaml).documents::<Vec<Value>>().unwrap_err();
+/// assert_eq!(err.to_string(),
+///            "while parsing node, found unknown anchor `defaults`, which is defined in an earlier document; \
+///             see `AnchorScope` at line 3 column 6");
+///
+/// let de = Deserializer::from_str(yaml).anchor_scope(AnchorScope::Stream);
+/// let documents: Vec<Value> = de.documents().unwrap();
+/// assert_eq!(documents[1]["job"]["retries"], 3);
+/// # }
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum AnchorScope {
+    /// Aliases refer to anchors of their own document, as YAML specifies.
+    /// This is the default.
+    Document,
+    /// Aliases may also refer to anchors of earlier documents of the stream.
+    /// The latest definition before the alias wins.
+    Stream,
+}
+
+impl Default for AnchorScope {
+    fn default() -> Self {
+        AnchorScope::Document
+    }
+}
+
+/// The warnings of `AnchorPolicy::Warn`.
+///
+/// Like `Deprecations`, this is a handle: clones of it share the same
+/// warnings, which keep adding up over every deserializer that is given one.
+#[derive(Clone, Debug, Default)]
+pub struct AnchorWarnings {
+    warnings: Arc<Mutex<Vec<AnchorWarning>>>,
+}
+
+/// An anchor that was defined again, or an alias to an undefined anchor.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AnchorWarning {
+    name: String,
+    location: Location,
+    redefined: bool,
+}
+
+impl AnchorWarnings {
+    /// Creates a handle with no warnings.
+    pub fn new() -> Self {
+        AnchorWarnings::default()
+    }
+
+    /// The warnings found so far, in the order they appear in the input.
+    pub fn warnings(&self) -> Vec<AnchorWarning> {
+        self.warnings.lock().unwrap_or_else(|err| err.into_inner()).clone()
+    }
+
+    fn push(&self, name: &str, location: Location, redefined: bool) {
+        let warning = AnchorWarning {
+            name: name.to_owned(),
+            location: location,
+            redefined: redefined,
+        };
+        self.warnings.lock().unwrap_or_else(|err| err.into_inner()).push(warning);
+    }
+}
+
+impl AnchorWarning {
+    /// The name of the anchor, without the `&` or `*`.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Where the anchor is defined again, or where the alias is.
+    pub fn location(&self) -> Location {
+        self.location
+    }
+
+    /// Whether this is about an anchor that is defined again, rather than an
+    /// alias to an undefined anchor.
+    pub fn is_redefinition(&self) -> bool {
+        self.redefined
+    }
+}
+
+impl Display for AnchorWarning {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "{}:{}: ", self.location.line(), self.location.column())?;
+        if self.redefined {
+            write!(formatter, "anchor `{}` is defined again", self.name)
+        } else {
+            write!(formatter, "alias `*{}` refers to an undefined anchor", self.name)
+        }
+    }
+}
+
+/// What becomes of an alias to an undefined anchor.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) enum Undefined {
+    Fail,
+    Null,
+    Placeholder,
+}
+
+impl AnchorPolicy {
+    /// Whether to look for redefined anchors and undefined aliases at all.
+    pub(crate) fn is_default(&self) -> bool {
+        match *self {
+            AnchorPolicy::Last => true,
+            _ => false,
+        }
+    }
+
+    /// Handles an anchor defined again at `location`. Returns whether to
+    /// carry on.
+    pub(crate) fn redefined(&self, name: &str, location: Location) -> bool {
+        match *self {
+            AnchorPolicy::Error => false,
+            AnchorPolicy::Warn(ref warnings) => {
+                warnings.push(name, location, true);
+                true
+            }
+            AnchorPolicy::Last | AnchorPolicy::Placeholder => true,
+        }
+    }
+
+    /// Handles an alias at `location` to an anchor that is not defined.
+    pub(crate) fn undefined(&self, name: &str, location: Location) -> Undefined {
+        match *self {
+            AnchorPolicy::Last | AnchorPolicy::Error => Undefined::Fail,
+            AnchorPolicy::Warn(ref warnings) => {
+                warnings.push(name, location, false);
+                Undefined::Null
+            }
+            AnchorPolicy::Placeholder => Undefined::Placeholder,
+        }
+    }
+}
@@ -0,0 +1,297 @@
+// Copyright 2018 Serde YAML Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Parsing into a bump arena, available with the `bumpalo` feature.
+//!
+//! Every node and string of the resulting `Value` lives in a `bumpalo::Bump`
+//! supplied by the caller, which makes building and dropping large documents
+//! cheap when they are only inspected briefly. Aliases share the node they
+//! refer to instead of copying it.
+//!
+//! ```rust
+//! # extern crate bumpalo;
+//! # extern crate serde_yaml;
+//! #
+//! # fn main() {
+//! use bumpalo::Bump;
+//!
+//! let bump = Bump::new();
+//! let value = serde_yaml::from_str_in("{name: demo, ports: [80, 443]}", &bump).unwrap();
+//! assert_eq!(value.get("name").and_then(|name| name.as_str()), Some("demo"));
+//! assert_eq!(value.get("ports").and_then(|ports| ports.as_sequence()).map(|ports| ports.len()),
+//!            Some(2));
+//! # }
+//! ```
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+use bumpalo::Bump;
+use bumpalo::collections::Vec as BumpVec;
+use serde::de::{self, DeserializeSeed, Visitor};
+
+use de::{Event, Loader};
+use error::{Error, Result};
+use mapping::Mapping;
+use value::{self, Number};
+
+/// A YAML value whose contents are allocated in an arena.
+#[derive(Clone, Debug, PartialEq, PartialOrd)]
+pub enum Value<'a> {
+    /// Represents a YAML null value.
+    Null,
+    /// Represents a YAML boolean.
+    Bool(bool),
+    /// Represents a YAML numerical value, whether integer or floating point.
+    Number(Number),
+    /// Represents a YAML string.
+    String(&'a str),
+    /// Represents a YAML sequence.
+    Sequence(&'a [Value<'a>]),
+    /// Represents a YAML mapping. Entries are kept in document order and
+    /// duplicate keys are preserved.
+    Mapping(&'a [(Value<'a>, Value<'a>)]),
+}
+
+impl<'a> Value<'a> {
+    /// Looks up the value of a string key in a mapping. Returns `None` if this
+    /// is not a mapping or the key is absent. If the key occurs more than once,
+    /// the last occurrence wins, as it would when deserializing.
+    pub fn get(&self, key: &str) -> Option<&'a Value<'a>> {
+        match *self {
+            Value::Mapping(entries) => {
+                entries.iter()
+                    .rev()
+                    .find(|entry| entry.0 == Value::String(key))
+                    .map(|entry| &entry.1)
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns true if the `Value` is a null.
+    pub fn is_null(&self) -> bool {
+        match *self {
+            Value::Null => true,
+            _ => false,
+        }
+    }
+
+    /// If the `Value` is a boolean, returns it.
+    pub fn as_bool(&self) -> Option<bool> {
+        match *self {
+            Value::Bool(b) => Some(b),
+            _ => None,
+        }
+    }
+
+    /// If the `Value` is an integer representable as `i64`, returns it.
+    pub fn as_i64(&self) -> Option<i64> {
+        match *self {
+            Value::Number(ref n) => n.as_i64(),
+            _ => None,
+        }
+    }
+
+    /// If the `Value` is an integer representable as `u64`, returns it.
+    pub fn as_u64(&self) -> Option<u64> {
+        match *self {
+            Value::Number(ref n) => n.as_u64(),
+            _ => None,
+        }
+    }
+
+    /// If the `Value` is a number, returns it as `f64`.
+    pub fn as_f64(&self) -> Option<f64> {
+        match *self {
+            Value::Number(ref n) => n.as_f64(),
+            _ => None,
+        }
+    }
+
+    /// If the `Value` is a string, returns it.
+    pub fn as_str(&self) -> Option<&'a str> {
+        match *self {
+            Value::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// If the `Value` is a sequence, returns its elements.
+    pub fn as_sequence(&self) -> Option<&'a [Value<'a>]> {
+        match *self {
+            Value::Sequence(seq) => Some(seq),
+            _ => None,
+        }
+    }
+
+    /// If the `Value` is a mapping, returns its entries.
+    pub fn as_mapping(&self) -> Option<&'a [(Value<'a>, Value<'a>)]> {
+        match *self {
+            Value::Mapping(entries) => Some(entries),
+            _ => None,
+        }
+    }
+
+    /// Copies the value out of the arena into a `serde_yaml::Value`.
+    pub fn to_value(&self) -> value::Value {
+        match *self {
+            Value::Null => value::Value::Null,
+            Value::Bool(b) => value::Value::Bool(b),
+            Value::Number(ref n) => value::Value::Number(n.clone()),
+            Value::String(s) => value::Value::String(s.to_owned()),
+            Value::Sequence(seq) => value::Value::Sequence(seq.iter().map(Value::to_value).collect()),
+            Value::Mapping(entries) => {
+                let mut mapping = Mapping::with_capacity(entries.len());
+                for &(ref k, ref v) in entries {
+                    mapping.insert(k.to_value(), v.to_value());
+                }
+                value::Value::Mapping(mapping)
+            }
+        }
+    }
+}
+
+/// Parse a string of YAML text into a `Value` allocated in `bump`.
+///
+/// Scalars are resolved the same way as by `serde_yaml::from_str::<Value>`.
+/// The input must contain exactly one document.
+pub fn from_str_in<'a>(s: &str, bump: &'a Bump) -> Result<Value<'a>> {
+    let loader = Loader::load(s)?;
+    if loader.events.is_empty() {
+        return Err(Error::end_of_stream());
+    }
+    // Anchor id 0 is what the parser reports for nodes without an anchor.
+    let anchored = loader.aliases
+        .iter()
+        .filter(|&(&id, _)| id != 0)
+        .map(|(_, &index)| index)
+        .collect();
+    let mut builder = Builder {
+        loader: &loader,
+        bump: bump,
+        anchored: anchored,
+        built: BTreeMap::new(),
+    };
+    let mut pos = 0;
+    let value = builder.build(&mut pos)?;
+    if pos == loader.events.len() {
+        Ok(value)
+    } else {
+        Err(Error::more_than_one_document())
+    }
+}
+
+struct Builder<'l, 'a> {
+    loader: &'l Loader,
+    bump: &'a Bump,
+    /// Indices of events that start an anchored node.
+    anchored: BTreeSet<usize>,
+    /// Anchored nodes built so far, by the index of their first event.
+    built: BTreeMap<usize, Value<'a>>,
+}
+
+impl<'l, 'a> Builder<'l, 'a> {
+    fn build(&mut self, pos: &mut usize) -> Result<Value<'a>> {
+        let start = *pos;
+        let value = match self.loader.events[start].0 {
+            Event::Alias(id) => {
+                *pos += 1;
+                match self.loader.aliases.get(&id).and_then(|index| self.built.get(index)) {
+                    Some(value) => value.clone(),
+                    None => return Err(de::Error::custom("alias refers to a node that contains it")),
+                }
+            }
+            Event::Scalar(..) => self.loader.deserialize_seed_at(pos, ScalarSeed { bump: self.bump })?,
+            Event::SequenceStart => {
+                *pos += 1;
+                let mut seq = BumpVec::new_in(self.bump);
+                while self.loader.events[*pos].0 != Event::SequenceEnd {
+                    seq.push(self.build(pos)?);
+                }
+                *pos += 1;
+                Value::Sequence(seq.into_bump_slice())
+            }
+            Event::MappingStart => {
+                *pos += 1;
+                let mut entries = BumpVec::new_in(self.bump);
+                while self.loader.events[*pos].0 != Event::MappingEnd {
+                    let k = self.build(pos)?;
+                    let v = self.build(pos)?;
+                    entries.push((k, v));
+                }
+                *pos += 1;
+                Value::Mapping(entries.into_bump_slice())
+            }
+            Event::SequenceEnd => panic!("unexpected end of sequence"),
+            Event::MappingEnd => panic!("unexpected end of mapping"),
+        };
+        if self.anchored.contains(&start) {
+            self.built.insert(start, value.clone());
+        }
+        Ok(value)
+    }
+}
+
+struct ScalarSeed<'a> {
+    bump: &'a Bump,
+}
+
+impl<'de, 'a> DeserializeSeed<'de> for ScalarSeed<'a> {
+    type Value = Value<'a>;
+
+    fn deserialize<D>(self, deserializer: D) -> ::std::result::Result<Value<'a>, D::Error>
+        where D: de::Deserializer<'de>
+    {
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de, 'a> Visitor<'de> for ScalarSeed<'a> {
+    type Value = Value<'a>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a YAML scalar")
+    }
+
+    fn visit_bool<E>(self, b: bool) -> ::std::result::Result<Value<'a>, E>
+        where E: de::Error
+    {
+        Ok(Value::Bool(b))
+    }
+
+    fn visit_i64<E>(self, i: i64) -> ::std::result::Result<Value<'a>, E>
+        where E: de::Error
+    {
+        Ok(Value::Number(i.into()))
+    }
+
+    fn visit_u64<E>(self, u: u64) -> ::std::result::Result<Value<'a>, E>
+        where E: de::Error
+    {
+        Ok(Value::Number(u.into()))
+    }
+
+    fn visit_f64<E>(self, f: f64) -> ::std::result::Result<Value<'a>, E>
+        where E: de::Error
+    {
+        Ok(Value::Number(f.into()))
+    }
+
+    fn visit_str<E>(self, s: &str) -> ::std::result::Result<Value<'a>, E>
+        where E: de::Error
+    {
+        Ok(Value::String(self.bump.alloc_str(s)))
+    }
+
+    fn visit_unit<E>(self) -> ::std::result::Result<Value<'a>, E>
+        where E: de::Error
+    {
+        Ok(Value::Null)
+    }
+}
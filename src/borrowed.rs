@@ -0,0 +1,388 @@
+// Copyright 2018 Serde YAML Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Parsing into a `Value` whose strings borrow from the input.
+//!
+//! Read-only passes over large files, like linters and indexers, spend much
+//! of their time copying keys and strings into a `serde_yaml::Value` only to
+//! look at them once. The `Value` of this module holds each string as a
+//! `Cow` that points into the input wherever the string is spelled there as
+//! it is, which is the case for plain scalars and for quoted ones without
+//! escapes. Other strings, like block scalars, are copied.
+//!
+//! ```rust
+//! use std::borrow::Cow;
+//!
+//! use serde_yaml::borrowed;
+//!
+//! let input = "name: demo\nmotd: \"line\\nbreak\"\nports: [80, 443]\n";
+//! let value = borrowed::from_str(input).unwrap();
+//! match value.get("name") {
+//!     Some(&borrowed::Value::String(Cow::Borrowed(name))) => assert_eq!(name, "demo"),
+//!     _ => unreachable!(),
+//! }
+//! match value.get("motd") {
+//!     Some(&borrowed::Value::String(Cow::Owned(ref motd))) => assert_eq!(motd, "line\nbreak"),
+//!     _ => unreachable!(),
+//! }
+//! assert_eq!(value.get("ports").and_then(|ports| ports.as_sequence()).map(|ports| ports.len()), Some(2));
+//! ```
+
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+use serde::de::{self, DeserializeSeed, Visitor};
+use yaml_rust::scanner::TScalarStyle;
+
+use de::{Event, Loader};
+use error::{Error, Result};
+use mapping::Mapping;
+use value::{self, Number};
+
+/// A YAML value whose strings borrow from the text it was parsed from where
+/// they can.
+#[derive(Clone, Debug, PartialEq, PartialOrd)]
+pub enum Value<'a> {
+    /// Represents a YAML null value.
+    Null,
+    /// Represents a YAML boolean.
+    Bool(bool),
+    /// Represents a YAML numerical value, whether integer or floating point.
+    Number(Number),
+    /// Represents a YAML string, borrowed from the input if it is spelled
+    /// there as it is.
+    String(Cow<'a, str>),
+    /// Represents a YAML sequence.
+    Sequence(Vec<Value<'a>>),
+    /// Represents a YAML mapping. Entries are kept in document order and
+    /// duplicate keys are preserved.
+    Mapping(Vec<(Value<'a>, Value<'a>)>),
+}
+
+impl<'a> Value<'a> {
+    /// Looks up the value of a string key in a mapping. Returns `None` if this
+    /// is not a mapping or the key is absent. If the key occurs more than once,
+    /// the last occurrence wins, as it would when deserializing.
+    pub fn get(&self, key: &str) -> Option<&Value<'a>> {
+        match *self {
+            Value::Mapping(ref entries) => {
+                entries.iter()
+                    .rev()
+                    .find(|entry| entry.0.as_str() == Some(key))
+                    .map(|entry| &entry.1)
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns true if the `Value` is a null.
+    pub fn is_null(&self) -> bool {
+        match *self {
+            Value::Null => true,
+            _ => false,
+        }
+    }
+
+    /// If the `Value` is a boolean, returns it.
+    pub fn as_bool(&self) -> Option<bool> {
+        match *self {
+            Value::Bool(b) => Some(b),
+            _ => None,
+        }
+    }
+
+    /// If the `Value` is an integer representable as `i64`, returns it.
+    pub fn as_i64(&self) -> Option<i64> {
+        match *self {
+            Value::Number(ref n) => n.as_i64(),
+            _ => None,
+        }
+    }
+
+    /// If the `Value` is an integer representable as `u64`, returns it.
+    pub fn as_u64(&self) -> Option<u64> {
+        match *self {
+            Value::Number(ref n) => n.as_u64(),
+            _ => None,
+        }
+    }
+
+    /// If the `Value` is a number, returns it as `f64`.
+    pub fn as_f64(&self) -> Option<f64> {
+        match *self {
+            Value::Number(ref n) => n.as_f64(),
+            _ => None,
+        }
+    }
+
+    /// If the `Value` is a string, returns it.
+    pub fn as_str(&self) -> Option<&str> {
+        match *self {
+            Value::String(ref s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// If the `Value` is a sequence, returns its elements.
+    pub fn as_sequence(&self) -> Option<&[Value<'a>]> {
+        match *self {
+            Value::Sequence(ref seq) => Some(seq),
+            _ => None,
+        }
+    }
+
+    /// If the `Value` is a mapping, returns its entries.
+    pub fn as_mapping(&self) -> Option<&[(Value<'a>, Value<'a>)]> {
+        match *self {
+            Value::Mapping(ref entries) => Some(entries),
+            _ => None,
+        }
+    }
+
+    /// Copies the strings that are borrowed, so that the value no longer
+    /// refers to the input.
+    pub fn into_owned(self) -> Value<'static> {
+        match self {
+            Value::Null => Value::Null,
+            Value::Bool(b) => Value::Bool(b),
+            Value::Number(n) => Value::Number(n),
+            Value::String(s) => Value::String(Cow::Owned(s.into_owned())),
+            Value::Sequence(seq) => Value::Sequence(seq.into_iter().map(Value::into_owned).collect()),
+            Value::Mapping(entries) => {
+                Value::Mapping(entries.into_iter().map(|(k, v)| (k.into_owned(), v.into_owned())).collect())
+            }
+        }
+    }
+
+    /// Copies the value, with every string owned, so that the copy can
+    /// outlive the input while the value itself is still in use. This is
+    /// `into_owned` for a value that is only borrowed.
+    ///
+    /// ```rust
+    /// use serde_yaml::borrowed::{self, Value};
+    ///
+    /// fn names(input: &str) -> (Value<'static>, usize) {
+    ///     let value = borrowed::from_str(input).unwrap();
+    ///     let len = value.as_sequence().map_or(0, |seq| seq.len());
+    ///     (value.to_owned_static(), len)
+    /// }
+    ///
+    /// let (value, len) = names(&String::from("[a, b]"));
+    /// assert_eq!(len, 2);
+    /// assert_eq!(value.as_sequence().unwrap()[1].as_str(), Some("b"));
+    /// ```
+    pub fn to_owned_static(&self) -> Value<'static> {
+        match *self {
+            Value::Null => Value::Null,
+            Value::Bool(b) => Value::Bool(b),
+            Value::Number(ref n) => Value::Number(n.clone()),
+            Value::String(ref s) => Value::String(Cow::Owned((**s).to_owned())),
+            Value::Sequence(ref seq) => Value::Sequence(seq.iter().map(Value::to_owned_static).collect()),
+            Value::Mapping(ref entries) => {
+                Value::Mapping(entries.iter().map(|&(ref k, ref v)| (k.to_owned_static(), v.to_owned_static())).collect())
+            }
+        }
+    }
+
+    /// Copies the value into a `serde_yaml::Value`.
+    pub fn to_value(&self) -> value::Value {
+        match *self {
+            Value::Null => value::Value::Null,
+            Value::Bool(b) => value::Value::Bool(b),
+            Value::Number(ref n) => value::Value::Number(n.clone()),
+            Value::String(ref s) => value::Value::String(s.clone().into_owned()),
+            Value::Sequence(ref seq) => value::Value::Sequence(seq.iter().map(Value::to_value).collect()),
+            Value::Mapping(ref entries) => {
+                let mut mapping = Mapping::with_capacity(entries.len());
+                for &(ref k, ref v) in entries {
+                    mapping.insert(k.to_value(), v.to_value());
+                }
+                value::Value::Mapping(mapping)
+            }
+        }
+    }
+}
+
+/// Parse a string of YAML text into a `Value` that borrows from it.
+///
+/// Scalars are resolved the same way as by `serde_yaml::from_str::<Value>`.
+/// The input must contain exactly one document.
+pub fn from_str(s: &str) -> Result<Value> {
+    let loader = Loader::load(s)?;
+    if loader.events.is_empty() {
+        return Err(Error::end_of_stream());
+    }
+    // Anchor id 0 is what the parser reports for nodes without an anchor.
+    let anchored = loader.aliases
+        .iter()
+        .filter(|&(&id, _)| id != 0)
+        .map(|(_, &index)| index)
+        .collect();
+    let mut builder = Builder {
+        loader: &loader,
+        input: s,
+        ascii: s.is_ascii(),
+        cursor: (0, 0),
+        anchored: anchored,
+        built: BTreeMap::new(),
+    };
+    let mut pos = 0;
+    let value = builder.build(&mut pos)?;
+    if pos == loader.events.len() {
+        Ok(value)
+    } else {
+        Err(Error::more_than_one_document())
+    }
+}
+
+struct Builder<'l, 'a> {
+    loader: &'l Loader,
+    input: &'a str,
+    /// Whether character indices are byte offsets.
+    ascii: bool,
+    /// The character index and byte offset of the last scalar, since the
+    /// parser counts positions in characters.
+    cursor: (usize, usize),
+    /// Indices of events that start an anchored node.
+    anchored: BTreeSet<usize>,
+    /// Anchored nodes built so far, by the index of their first event.
+    built: BTreeMap<usize, Value<'a>>,
+}
+
+impl<'l, 'a> Builder<'l, 'a> {
+    fn build(&mut self, pos: &mut usize) -> Result<Value<'a>> {
+        let start = *pos;
+        let value = match self.loader.events[start].0 {
+            Event::Alias(id) => {
+                *pos += 1;
+                match self.loader.aliases.get(&id).and_then(|index| self.built.get(index)) {
+                    Some(value) => value.clone(),
+                    None => return Err(de::Error::custom("alias refers to a node that contains it")),
+                }
+            }
+            Event::Scalar(_, style, _) => {
+                let offset = self.offset(self.loader.events[start].1.index());
+                // The text of a quoted scalar starts after its quote.
+                let text = match style {
+                    TScalarStyle::Plain => Some(&self.input[offset..]),
+                    TScalarStyle::SingleQuoted | TScalarStyle::DoubleQuoted => self.input.get(offset + 1..),
+                    _ => None,
+                };
+                self.loader.deserialize_seed_at(pos, ScalarSeed { text: text })?
+            }
+            Event::SequenceStart => {
+                *pos += 1;
+                let mut seq = Vec::new();
+                while self.loader.events[*pos].0 != Event::SequenceEnd {
+                    seq.push(self.build(pos)?);
+                }
+                *pos += 1;
+                Value::Sequence(seq)
+            }
+            Event::MappingStart => {
+                *pos += 1;
+                let mut entries = Vec::new();
+                while self.loader.events[*pos].0 != Event::MappingEnd {
+                    let k = self.build(pos)?;
+                    let v = self.build(pos)?;
+                    entries.push((k, v));
+                }
+                *pos += 1;
+                Value::Mapping(entries)
+            }
+            Event::SequenceEnd => panic!("unexpected end of sequence"),
+            Event::MappingEnd => panic!("unexpected end of mapping"),
+        };
+        if self.anchored.contains(&start) {
+            self.built.insert(start, value.clone());
+        }
+        Ok(value)
+    }
+
+    /// The byte offset of the character at `index`. Scalars come in the
+    /// order of the text, so the search goes on from the previous one.
+    fn offset(&mut self, index: usize) -> usize {
+        if self.ascii {
+            return index.min(self.input.len());
+        }
+        if index < self.cursor.0 {
+            self.cursor = (0, 0);
+        }
+        let (chars, bytes) = self.cursor;
+        let offset = self.input[bytes..]
+            .char_indices()
+            .nth(index - chars)
+            .map_or(self.input.len(), |(offset, _)| bytes + offset);
+        self.cursor = (index, offset);
+        offset
+    }
+}
+
+struct ScalarSeed<'a> {
+    /// The input from where the text of the scalar would start if it is
+    /// spelled as it is.
+    text: Option<&'a str>,
+}
+
+impl<'de, 'a> DeserializeSeed<'de> for ScalarSeed<'a> {
+    type Value = Value<'a>;
+
+    fn deserialize<D>(self, deserializer: D) -> ::std::result::Result<Value<'a>, D::Error>
+        where D: de::Deserializer<'de>
+    {
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de, 'a> Visitor<'de> for ScalarSeed<'a> {
+    type Value = Value<'a>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a YAML scalar")
+    }
+
+    fn visit_bool<E>(self, b: bool) -> ::std::result::Result<Value<'a>, E>
+        where E: de::Error
+    {
+        Ok(Value::Bool(b))
+    }
+
+    fn visit_i64<E>(self, i: i64) -> ::std::result::Result<Value<'a>, E>
+        where E: de::Error
+    {
+        Ok(Value::Number(i.into()))
+    }
+
+    fn visit_u64<E>(self, u: u64) -> ::std::result::Result<Value<'a>, E>
+        where E: de::Error
+    {
+        Ok(Value::Number(u.into()))
+    }
+
+    fn visit_f64<E>(self, f: f64) -> ::std::result::Result<Value<'a>, E>
+        where E: de::Error
+    {
+        Ok(Value::Number(f.into()))
+    }
+
+    fn visit_str<E>(self, s: &str) -> ::std::result::Result<Value<'a>, E>
+        where E: de::Error
+    {
+        match self.text {
+            Some(text) if text.starts_with(s) => Ok(Value::String(Cow::Borrowed(&text[..s.len()]))),
+            _ => Ok(Value::String(Cow::Owned(s.to_owned()))),
+        }
+    }
+
+    fn visit_unit<E>(self) -> ::std::result::Result<Value<'a>, E>
+        where E: de::Error
+    {
+        Ok(Value::Null)
+    }
+}
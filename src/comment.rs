@@ -0,0 +1,216 @@
+// Copyright 2018 Serde YAML Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Comments attached to values while serializing.
+//!
+//! Wrapping a value in `Commented` writes the comment as `#` lines above the
+//! key of the mapping entry or the `-` of the sequence entry that holds the
+//! value, or at the top of the document for the document itself. This is
+//! meant for generated files that people read and edit.
+//!
+//! ```rust
+//! #[macro_use]
+//! extern crate serde_derive;
+//! extern crate serde_yaml;
+//!
+//! use serde_yaml::comment::Commented;
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Config {
+//!     workers: Commented<u32>,
+//!     hosts: Vec<Commented<String>>,
+//! }
+//!
+//! # fn main() {
+//! let config = Config {
+//!     workers: Commented::new("Defaults to the number of CPUs.\nSet to 0 to disable.", 8),
+//!     hosts: vec![Commented::new("primary", "a".to_owned()), "b".to_owned().into()],
+//! };
+//! let yaml = serde_yaml::to_string(&config).unwrap();
+//! let expected = [
+//!     "---",
+//!     "# Defaults to the number of CPUs.",
+//!     "# Set to 0 to disable.",
+//!     "workers: 8",
+//!     "hosts:",
+//!     "  # primary",
+//!     "  - a",
+//!     "  - b",
+//! ];
+//! assert_eq!(yaml, expected.join("\n"));
+//!
+//! let config: Config = serde_yaml::from_str(&yaml).unwrap();
+//! assert_eq!(*config.workers, 8);
+//! # }
+//! ```
+//!
+//! Comments are not read back: deserializing a `Commented` reads the value
+//! and leaves the comment empty, and an empty comment writes nothing. Where a
+//! value has no line of its own to put a comment above, like a shared value
+//! written as an alias, the comment is left out. Other serializers, including
+//! `to_value`, see `Commented` as the value it holds.
+
+use std::cell::{Cell, RefCell};
+use std::ops::{Deref, DerefMut};
+
+use serde::de::{Deserialize, Deserializer};
+use serde::ser::{Serialize, Serializer};
+use yaml_rust::Yaml;
+
+use error::Result;
+use ser;
+
+/// The name of the newtype struct that `Commented` serializes as, so that
+/// this crate's serializer can recognize it.
+pub(crate) const TOKEN: &str = "$serde_yaml::private::Commented";
+
+/// A value along with a comment to write above it. See the module
+/// documentation.
+#[derive(Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Commented<T>(pub String, pub T);
+
+impl<T> Commented<T> {
+    /// Attaches `comment` to `value`. A comment of several lines is written
+    /// as that many `#` lines.
+    pub fn new<C>(comment: C, value: T) -> Self
+        where C: Into<String>
+    {
+        Commented(comment.into(), value)
+    }
+
+    /// The comment.
+    pub fn comment(&self) -> &str {
+        &self.0
+    }
+
+    /// Unwraps the value.
+    pub fn into_inner(self) -> T {
+        self.1
+    }
+}
+
+impl<T> Deref for Commented<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.1
+    }
+}
+
+impl<T> DerefMut for Commented<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.1
+    }
+}
+
+impl<T> From<T> for Commented<T> {
+    fn from(value: T) -> Self {
+        Commented(String::new(), value)
+    }
+}
+
+impl<T> Serialize for Commented<T>
+    where T: Serialize
+{
+    fn serialize<S>(&self, serializer: S) -> ::std::result::Result<S::Ok, S::Error>
+        where S: Serializer
+    {
+        serialize(&self.0, &self.1, serializer)
+    }
+}
+
+/// Serializes `value` with `comment` above it, like a `Commented` does,
+/// without taking ownership of either. This is what the fields of
+/// `#[serde_yaml::styled]` types with a `#[yaml(comment = "...")]` attribute
+/// serialize with.
+pub fn serialize<T: ?Sized, S>(comment: &str, value: &T, serializer: S) -> ::std::result::Result<S::Ok, S::Error>
+    where T: Serialize,
+          S: Serializer
+{
+    PENDING.with(|pending| *pending.borrow_mut() = Some(comment.to_owned()));
+    let result = serializer.serialize_newtype_struct(TOKEN, value);
+    // Other serializers leave the comment where it was put.
+    PENDING.with(|pending| pending.borrow_mut().take());
+    result
+}
+
+impl<'de, T> Deserialize<'de> for Commented<T>
+    where T: Deserialize<'de>
+{
+    fn deserialize<D>(deserializer: D) -> ::std::result::Result<Self, D::Error>
+        where D: Deserializer<'de>
+    {
+        T::deserialize(deserializer).map(Commented::from)
+    }
+}
+
+thread_local! {
+    /// The comment of the `Commented` being serialized, until this crate's
+    /// serializer takes it.
+    static PENDING: RefCell<Option<String>> = const { RefCell::new(None) };
+    /// How many documents are being serialized on this thread. Outside of
+    /// one, for example in `to_value`, there is nowhere to write comments.
+    static DOCUMENTS: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Leaves the document counted by `document` even if building it panics.
+struct Scope;
+
+impl Drop for Scope {
+    fn drop(&mut self) {
+        DOCUMENTS.with(|documents| documents.set(documents.get() - 1));
+    }
+}
+
+/// Runs `f`, which builds the tree of one document to be written out, so that
+/// `Commented` values in it keep their comments.
+pub(crate) fn serialize_document<T, F>(f: F) -> T
+    where F: FnOnce() -> T
+{
+    DOCUMENTS.with(|documents| documents.set(documents.get() + 1));
+    let _scope = Scope;
+    f()
+}
+
+/// Whether the tree being built is a document to be written out, with
+/// somewhere to put comments and styles.
+pub(crate) fn in_document() -> bool {
+    DOCUMENTS.with(Cell::get) > 0
+}
+
+/// Serializes the value inside a `Commented`, wrapped in a node that carries
+/// the comment if there is one and it has somewhere to go.
+pub(crate) fn serialize_node<T: ?Sized>(value: &T) -> Result<Yaml>
+    where T: Serialize
+{
+    let comment = PENDING.with(|pending| pending.borrow_mut().take());
+    let node = value.serialize(ser::Serializer)?;
+    match comment {
+        Some(comment) if !comment.is_empty() && in_document() => {
+            Ok(Yaml::Array(vec![Yaml::BadValue, Yaml::String(comment), node]))
+        }
+        _ => Ok(node),
+    }
+}
+
+/// The comment and the node of a node built by `serialize_node`, or an
+/// empty comment and the node of one built by `style::serialize_node`. The
+/// serializer never produces `BadValue` otherwise, so a sequence starting
+/// with one cannot be mistaken for data.
+pub(crate) fn split(node: &Yaml) -> Option<(&str, &Yaml)> {
+    match *node {
+        Yaml::Array(ref parts) => {
+            match parts.as_slice() {
+                [Yaml::BadValue, Yaml::String(comment), node] => Some((comment, node)),
+                [Yaml::BadValue, Yaml::Integer(_), node] => Some(("", node)),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
@@ -0,0 +1,183 @@
+// Copyright 2018 Serde YAML Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Checking whether two YAML files say the same thing.
+//!
+//! Meant for CI checks that a file which was reformatted, regenerated or
+//! checked out on another platform is unchanged, without failing on trailing
+//! spaces or line endings.
+//!
+//! ```rust
+//! # extern crate serde_yaml;
+//! #
+//! # fn main() {
+//! use serde_yaml::compare;
+//!
+//! let committed = "name: web   \r\nports: [80, 443]\r\n\r\n";
+//! let generated = "ports:\n  - 80\n  - 443\nname: web\n";
+//!
+//! assert_eq!(compare::normalize_whitespace(committed), "name: web\nports: [80, 443]\n");
+//! assert!(!compare::same_text(committed, generated));
+//! assert!(compare::same_documents(committed, generated).unwrap());
+//! # }
+//! ```
+//!
+//! `assert_snapshot_stable` does the same for a value and a golden file, as a
+//! test assertion.
+
+use serde::Serialize;
+
+use de::Loader;
+use error::Result;
+use ser::YamlFormat;
+use value::Value;
+
+/// Normalizes the whitespace of a text without parsing it.
+///
+/// Line endings become `\n`, spaces and tabs at the end of every line are
+/// removed, and the text ends in exactly one line break unless it is empty or
+/// blank, in which case the result is empty. Nothing else changes.
+///
+/// Trailing whitespace on a line inside a quoted or block scalar is part of
+/// the string, so normalizing such a text can change what it means. Use
+/// `same_documents` to compare what texts mean.
+///
+/// ```rust
+/// # extern crate serde_yaml;
+/// #
+/// # fn main() {
+/// use serde_yaml::compare::normalize_whitespace;
+///
+/// assert_eq!(normalize_whitespace("a: 1 \r\nb:\t\r\n  - 2\r\r\n\n"), "a: 1\nb:\n  - 2\n");
+/// assert_eq!(normalize_whitespace(" \n\n"), "");
+/// # }
+/// ```
+pub fn normalize_whitespace(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut blank_lines = 0;
+    for line in s.split("\r\n").flat_map(|line| line.split(&['\r', '\n'][..])) {
+        let line = line.trim_end_matches(&[' ', '\t'][..]);
+        if line.is_empty() {
+            blank_lines += 1;
+            continue;
+        }
+        // Blank lines are only written once something follows them.
+        for _ in 0..blank_lines {
+            out.push('\n');
+        }
+        blank_lines = 0;
+        out.push_str(line);
+        out.push('\n');
+    }
+    out
+}
+
+/// Whether two texts are the same after `normalize_whitespace`.
+pub fn same_text(a: &str, b: &str) -> bool {
+    normalize_whitespace(a) == normalize_whitespace(b)
+}
+
+/// Whether two texts hold the same documents.
+///
+/// Both texts are parsed as streams of any number of documents, which are
+/// compared as `Value`s one by one. Formatting, comments, quoting, anchors and
+/// the order of keys in mappings make no difference.
+///
+/// Fails if either text is not valid YAML.
+///
+/// ```rust
+/// # extern crate serde_yaml;
+/// #
+/// # fn main() {
+/// use serde_yaml::compare::same_documents;
+///
+/// let a = "# base\nbase: &b {x: 1}\nuse: *b\n---\n'two'\n";
+/// let b = "use: {x: 1}\nbase:\n  x: 1\n--- two";
+/// assert!(same_documents(a, b).unwrap());
+///
+/// assert!(!same_documents("x: 1", "x: 1.0").unwrap());
+/// assert!(!same_documents("x: 1", "x: 1\n--- y").unwrap());
+/// assert!(same_documents("x: [", "x: 1").is_err());
+/// # }
+/// ```
+pub fn same_documents(a: &str, b: &str) -> Result<bool> {
+    Ok(documents(a)? == documents(b)?)
+}
+
+/// Checks `value` against a golden file, written in `YamlFormat::stable`,
+/// that holds what it is expected to serialize to. Panics with a diff if it
+/// does not match, as a test assertion.
+///
+/// The golden text matches if it is the same as the stable output after
+/// `normalize_whitespace`, or failing that if it holds the same document.
+/// So a golden file that was written by hand, or by an older version of
+/// this crate, keeps passing as long as it means the same thing.
+///
+/// ```rust
+/// # extern crate serde_yaml;
+/// #
+/// # use std::collections::HashMap;
+/// #
+/// # fn main() {
+/// let mut limits = HashMap::new();
+/// limits.insert("memory", "512Mi");
+/// limits.insert("cpu", "2");
+///
+/// serde_yaml::assert_snapshot_stable(&limits, "---\ncpu: \"2\"\nmemory: 512Mi\n");
+/// serde_yaml::assert_snapshot_stable(&limits, "{memory: 512Mi, cpu: '2'}");
+/// # }
+/// ```
+///
+/// # Panics
+///
+/// If the golden text is not valid YAML, if `value` fails to serialize, or
+/// if they do not match.
+pub fn assert_snapshot_stable<T: ?Sized>(value: &T, golden: &str)
+    where T: Serialize
+{
+    let actual = match YamlFormat::stable().to_string(value) {
+        Ok(actual) => actual,
+        Err(err) => panic!("failed to serialize the snapshot: {}", err),
+    };
+    if same_text(&actual, golden) {
+        return;
+    }
+    let expected = match documents(golden) {
+        Ok(expected) => expected,
+        Err(err) => panic!("golden text is not valid YAML: {}", err),
+    };
+    let actual_documents = documents(&actual).expect("stable output reads back");
+    if actual_documents == expected {
+        return;
+    }
+    match (expected.as_slice(), actual_documents.as_slice()) {
+        ([expected], [actual_document]) => {
+            panic!("snapshot differs from the golden text:\n{}\nstable output:\n{}",
+                   expected.display_diff(actual_document),
+                   actual)
+        }
+        _ => {
+            panic!("golden text holds {} documents, not 1\nstable output:\n{}",
+                   expected.len(),
+                   actual)
+        }
+    }
+}
+
+fn documents(s: &str) -> Result<Vec<Value>> {
+    let loader = Loader::load(s)?;
+    let mut documents = Vec::new();
+    let mut pos = 0;
+    // Every document is a single node.
+    while pos < loader.events.len() {
+        let mut document: Value = loader.deserialize_at(&mut pos)?;
+        document.sort_keys_recursively();
+        documents.push(document);
+    }
+    Ok(documents)
+}
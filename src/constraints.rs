@@ -0,0 +1,208 @@
+// Copyright 2018 Serde YAML Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Checks on the values in a document that are easier to write down than
+//! to express in a type, like the range of a port number.
+//!
+//! Rules are run over a `SpannedValue`, usually next to deserializing the
+//! same text, so that every violation can point at the offending value.
+//!
+//! ```rust
+//! use serde_yaml::constraints::{Constraint, Rules};
+//!
+//! let rules = Rules::new()
+//!     .rule("server.port", Constraint::Range(1.0, 65535.0))
+//!     .rule("workers[*].mode", Constraint::one_of(&["fast", "safe"]));
+//! let yaml = "server: {port: 70000}\nworkers:\n- mode: safe\n- mode: quick\n";
+//! let (doc, _) = serde_yaml::parse_tolerant(yaml);
+//! let violations: Vec<String> = rules.check(&doc).iter().map(ToString::to_string).collect();
+//! assert_eq!(violations, [
+//!     "1:16: `server.port` must be between 1 and 65535, found 70000",
+//!     "4:9: `workers[1].mode` must be one of fast, safe, found quick",
+//! ]);
+//! ```
+
+use std::fmt::{self, Display};
+
+#[cfg(feature = "regex")]
+use regex::Regex;
+
+use deprecate;
+use error::Location;
+use ser;
+use spanned::{Node, Span, SpannedValue};
+use value::Value;
+
+/// What the values at some path must look like.
+#[derive(Clone, Debug)]
+pub enum Constraint {
+    /// A number from the first bound up to and including the second.
+    Range(f64, f64),
+    /// One of the given scalars.
+    OneOf(Vec<Value>),
+    /// A string in which the regex finds a match. Anchor the regex with `^`
+    /// and `$` to match the whole string.
+    #[cfg(feature = "regex")]
+    Pattern(Regex),
+}
+
+impl Constraint {
+    /// One of the given strings.
+    pub fn one_of(values: &[&str]) -> Self {
+        Constraint::OneOf(values.iter().map(|&value| Value::String(value.to_owned())).collect())
+    }
+
+    /// The reason `value` breaks this constraint, or None if it does not.
+    fn violation(&self, value: &SpannedValue) -> Option<String> {
+        match *self {
+            Constraint::Range(min, max) => {
+                match *value.node() {
+                    Node::Number(ref n) => {
+                        let f = n.as_f64().unwrap_or(0.0);
+                        if min <= f && f <= max {
+                            return None;
+                        }
+                        Some(format!("must be between {} and {}, found {}", min, max, n))
+                    }
+                    _ => Some(format!("must be a number between {} and {}", min, max)),
+                }
+            }
+            Constraint::OneOf(ref values) => {
+                let found = value.to_value();
+                if values.contains(&found) {
+                    return None;
+                }
+                let values: Vec<String> = values.iter().map(scalar_text).collect();
+                Some(format!("must be one of {}, found {}", values.join(", "), scalar_text(&found)))
+            }
+            #[cfg(feature = "regex")]
+            Constraint::Pattern(ref regex) => {
+                match *value.node() {
+                    Node::String(ref s) if regex.is_match(s) => None,
+                    Node::String(ref s) => Some(format!("must match `{}`, found {}", regex, s)),
+                    _ => Some(format!("must be a string matching `{}`", regex)),
+                }
+            }
+        }
+    }
+}
+
+/// Constraints on the values at paths of a document.
+#[derive(Clone, Debug, Default)]
+pub struct Rules {
+    rules: Vec<(String, Constraint)>,
+}
+
+impl Rules {
+    /// Creates a set of rules that every document passes.
+    pub fn new() -> Self {
+        Rules::default()
+    }
+
+    /// Requires the values at paths that match `pattern` to meet
+    /// `constraint`. Patterns are paths written the same way as in error
+    /// messages, like `server.port`, in which `*` stands for any one key or
+    /// index, like `workers[*].mode`. Paths that are not in a document are
+    /// not checked.
+    pub fn rule(mut self, pattern: &str, constraint: Constraint) -> Self {
+        self.rules.push((pattern.to_owned(), constraint));
+        self
+    }
+
+    /// Runs every rule over `doc` and returns the violations in document
+    /// order. A value that breaks more than one rule is reported once for
+    /// each.
+    pub fn check(&self, doc: &SpannedValue) -> Vec<Violation> {
+        let mut violations = Vec::new();
+        self.check_at(doc, ".", &mut violations);
+        violations
+    }
+
+    fn check_at(&self, value: &SpannedValue, path: &str, violations: &mut Vec<Violation>) {
+        for &(ref pattern, ref constraint) in &self.rules {
+            if !deprecate::matches(pattern, path) {
+                continue;
+            }
+            if let Some(message) = constraint.violation(value) {
+                violations.push(Violation {
+                                    path: path.to_owned(),
+                                    span: value.span(),
+                                    message: message,
+                                });
+            }
+        }
+        let parent = if path == "." { "" } else { path };
+        match *value.node() {
+            Node::Sequence(ref seq) => {
+                for (i, element) in seq.iter().enumerate() {
+                    self.check_at(element, &format!("{}[{}]", parent, i), violations);
+                }
+            }
+            Node::Mapping(ref entries) => {
+                for &(ref key, ref value) in entries {
+                    let key = scalar_text(&key.to_value());
+                    let path = if parent.is_empty() { key } else { format!("{}.{}", parent, key) };
+                    self.check_at(value, &path, violations);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// A value that breaks one of the `Rules`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Violation {
+    path: String,
+    span: Span,
+    message: String,
+}
+
+impl Violation {
+    /// The path of the value, like `workers[1].mode`.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// The region of the input occupied by the value.
+    pub fn span(&self) -> Span {
+        self.span
+    }
+
+    /// Where the value starts in the input.
+    pub fn location(&self) -> Location {
+        self.span.start()
+    }
+
+    /// What is wrong with the value.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl Display for Violation {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter,
+               "{}:{}: `{}` {}",
+               self.location().line(),
+               self.location().column(),
+               self.path,
+               self.message)
+    }
+}
+
+/// How a scalar is written, for messages about it.
+pub(crate) fn scalar_text(value: &Value) -> String {
+    match *value {
+        Value::String(ref s) => s.clone(),
+        ref value => {
+            let yaml = ser::to_string(value).unwrap_or_default();
+            yaml.trim_start_matches("---").trim_start().to_owned()
+        }
+    }
+}
@@ -0,0 +1,174 @@
+// Copyright 2018 Serde YAML Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+/// What an untagged plain scalar that is a date, like `2024-05-01`, is read
+/// as. Full timestamps with a time of day are not affected.
+///
+/// YAML 1.1 resolves such scalars to `!!timestamp`, so other tools may turn
+/// them into dates while this crate reads them as strings. `Dates::Error`
+/// catches them in files that are shared with such tools, so that they get
+/// quoted.
+///
+/// ```rust
+/// # extern crate serde;
+/// # extern crate serde_yaml;
+/// #
+/// # fn main() {
+/// use serde::Deserialize;
+/// use serde_yaml::{Dates, Deserializer, Value};
+///
+/// let yaml = "released: 2024-05-01";
+/// let value = Value::deserialize(Deserializer::from_str(yaml)).unwrap();
+/// assert_eq!(value["released"], "2024-05-01");
+///
+/// let value = Value::deserialize(Deserializer::from_str(yaml).dates(Dates::Timestamp)).unwrap();
+/// assert_eq!(value["released"]["!!timestamp"], "2024-05-01");
+///
+/// let err = Value::deserialize(Deserializer::from_str(yaml).dates(Dates::Error)).unwrap_err();
+/// assert_eq!(err.to_string(),
+///            "released: unquoted date `2024-05-01` is a timestamp in YAML 1.1; quote it to keep it a string \
+///             at line 1 column 11");
+/// # }
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Dates {
+    /// Read the date as a string. This is the default.
+    String,
+    /// Read the date the way an explicit `!!timestamp` tag is read with
+    /// `TagAction::Wrap`: as a mapping with one entry, from `!!timestamp` to
+    /// the date as a string, which an externally tagged enum can take apart.
+    Timestamp,
+    /// Refuse to deserialize the date.
+    Error,
+}
+
+impl Default for Dates {
+    fn default() -> Self {
+        Dates::String
+    }
+}
+
+/// What an untagged plain scalar that is a YAML 1.1 base 60 number, like
+/// `12:30:00` or `1:20`, is read as.
+///
+/// YAML 1.1 reads `12:30:00` as the integer 45000, and `190:20:30.15` as a
+/// float, which is how times of day and data like MAC addresses get
+/// corrupted by tools that follow it. This crate reads them as strings
+/// unless told otherwise. Only what YAML 1.1 would read as a number is
+/// affected: each part after the first is at most 59, and an integer does
+/// not start with 0, so `00:30` and `11:22:33:44:55:66` stay strings either
+/// way.
+///
+/// ```rust
+/// # extern crate serde;
+/// # extern crate serde_yaml;
+/// #
+/// # fn main() {
+/// use serde::Deserialize;
+/// use serde_yaml::{Deserializer, Times, Value};
+///
+/// let yaml = "[12:30:00, 00:30, -1:30.5]";
+/// let value = Value::deserialize(Deserializer::from_str(yaml).times(Times::Sexagesimal)).unwrap();
+/// assert_eq!(value[0], 45000);
+/// assert_eq!(value[1], "00:30");
+/// assert_eq!(value[2], -90.5);
+///
+/// assert!(Value::deserialize(Deserializer::from_str(yaml).times(Times::Error)).is_err());
+/// # }
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Times {
+    /// Read the scalar as a string. This is the default.
+    String,
+    /// Read the scalar as a base 60 number, like YAML 1.1.
+    Sexagesimal,
+    /// Refuse to deserialize the scalar.
+    Error,
+}
+
+impl Default for Times {
+    fn default() -> Self {
+        Times::String
+    }
+}
+
+/// Whether the scalar is a date without a time, which YAML 1.1 reads as a
+/// timestamp.
+pub(crate) fn is_date(v: &str) -> bool {
+    let b = v.as_bytes();
+    b.len() == 10 && b[4] == b'-' && b[7] == b'-' &&
+    b.iter().enumerate().all(|(i, c)| i == 4 || i == 7 || c.is_ascii_digit())
+}
+
+/// A base 60 number as YAML 1.1 reads it.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub(crate) enum Sexagesimal {
+    Unsigned(u64),
+    Negative(i64),
+    Float(f64),
+}
+
+/// Reads the scalar as a YAML 1.1 base 60 integer or float, or returns
+/// `None` if it is not one. Integers that do not fit in 64 bits are not
+/// numbers either.
+pub(crate) fn sexagesimal(v: &str) -> Option<Sexagesimal> {
+    let (negative, digits) = match v.as_bytes().first() {
+        Some(&b'-') => (true, &v[1..]),
+        Some(&b'+') => (false, &v[1..]),
+        _ => (false, v),
+    };
+    let (whole, fraction) = match digits.find('.') {
+        Some(dot) => (&digits[..dot], Some(&digits[dot + 1..])),
+        None => (digits, None),
+    };
+    let mut parts = whole.split(':');
+    let first = parts.next().unwrap_or("");
+    let valid_first = match first.as_bytes().first() {
+        Some(&c) if c.is_ascii_digit() => first.bytes().all(|c| c.is_ascii_digit() || c == b'_'),
+        _ => false,
+    };
+    if !valid_first || (fraction.is_none() && first.starts_with('0')) {
+        return None;
+    }
+    let mut sixties = Vec::new();
+    for part in parts {
+        let digits = part.len() <= 2 && part.bytes().all(|c| c.is_ascii_digit());
+        match part.parse::<u64>() {
+            Ok(n) if digits && n < 60 => sixties.push(n),
+            _ => return None,
+        }
+    }
+    if sixties.is_empty() {
+        return None;
+    }
+    let first = first.replace('_', "");
+    match fraction {
+        Some(fraction) => {
+            if !fraction.bytes().all(|c| c.is_ascii_digit() || c == b'_') {
+                return None;
+            }
+            let fraction: f64 = format!("0.{}", fraction.replace('_', "")).parse().ok()?;
+            let whole = sixties.iter().fold(first.parse::<f64>().ok()?, |n, &part| n * 60.0 + part as f64);
+            let n = whole + fraction;
+            Some(Sexagesimal::Float(if negative { -n } else { n }))
+        }
+        None => {
+            let mut n = first.parse::<u64>().ok()?;
+            for part in sixties {
+                n = n.checked_mul(60)?.checked_add(part)?;
+            }
+            if !negative {
+                Some(Sexagesimal::Unsigned(n))
+            } else if n <= i64::MAX as u64 + 1 {
+                Some(Sexagesimal::Negative((n as i64).wrapping_neg()))
+            } else {
+                None
+            }
+        }
+    }
+}
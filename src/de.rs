@@ -10,25 +10,140 @@
 //!
 //! This module provides YAML deserialization with the type `Deserializer`.
 
-use std::collections::BTreeMap;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::fmt;
 use std::io;
+use std::marker::PhantomData;
+use std::mem;
+use std::ops::Range;
+use std::rc::Rc;
+use std::result;
 use std::str;
 
 use yaml_rust::parser::{Parser, MarkedEventReceiver, Event as YamlEvent};
-use yaml_rust::scanner::{Marker, TokenType, TScalarStyle};
+use yaml_rust::scanner::{Marker, ScanError, Scanner, TokenType, TScalarStyle};
 
 use serde::de::{self, Deserialize, DeserializeOwned, DeserializeSeed, Expected,
                 Unexpected, IntoDeserializer};
 use serde::de::IgnoredAny as Ignore;
 
-use error::{Error, Result};
+use anchor::{AnchorPolicy, AnchorScope, Undefined};
+use dates::{self, Dates, Sexagesimal, Times};
+use deprecate::Deprecations;
+use error::{Error, Location, Result};
+use event;
+use merge::{self, MergeKeys};
+use number::{self, parse_float, NumberDialect, Spelling};
+#[cfg(feature = "value")]
+use number::Number;
 use path::Path;
+use profile::Profile;
+use scalar::ScalarString;
+#[cfg(feature = "shared")]
+use shared;
+use tabs::{self, Tabs};
+use tags::{self, TagAction, TagPolicy};
+#[cfg(feature = "value")]
+use mapping::Mapping;
+#[cfg(feature = "value")]
+use value::{Sequence, Value};
 
 pub struct Loader {
-    events: Vec<(Event, Marker)>,
+    pub events: Vec<(Event, Marker)>,
     /// Map from alias id to index in events.
-    aliases: BTreeMap<usize, usize>,
+    pub aliases: BTreeMap<usize, usize>,
+}
+
+impl Loader {
+    /// Runs the parser over the whole input, which may contain more than one
+    /// document.
+    pub fn load(s: &str) -> Result<Self> {
+        Loader::load_with(s, &AnchorPolicy::Last, AnchorScope::Document)
+    }
+
+    /// Like `load`, handling redefined anchors and undefined aliases
+    /// according to `anchors`, and aliases to anchors of earlier documents
+    /// according to `scope`.
+    pub fn load_with(s: &str, anchors: &AnchorPolicy, scope: AnchorScope) -> Result<Self> {
+        let mut loader = Loader {
+            events: Vec::new(),
+            aliases: BTreeMap::new(),
+        };
+        parse(s, &mut loader, true, anchors, scope)?;
+        Ok(loader)
+    }
+
+    /// Runs the parser over the first document of the input only, and
+    /// returns the byte offset at which the rest of the input begins: just
+    /// past the document's `...` marker if it has one, otherwise at the start
+    /// of the next document or the end of the input.
+    pub fn load_first(s: &str) -> Result<(Self, usize)> {
+        let mut first = FirstDocument {
+            loader: Loader {
+                events: Vec::new(),
+                aliases: BTreeMap::new(),
+            },
+            end: None,
+        };
+        parse(s, &mut first, false, &AnchorPolicy::Last, AnchorScope::Document)?;
+        let offset = match first.end {
+            Some(marker) => {
+                let offset = s.char_indices().nth(marker.index()).map_or(s.len(), |(offset, _)| offset);
+                if s[offset..].starts_with("...") {
+                    offset + 3
+                } else {
+                    offset
+                }
+            }
+            None => s.len(),
+        };
+        Ok((first.loader, offset))
+    }
+
+    /// Deserializes the node whose first event is at `*pos`, leaving `*pos`
+    /// just past the node's last event.
+    pub fn deserialize_at<T>(&self, pos: &mut usize) -> Result<T>
+        where T: DeserializeOwned
+    {
+        self.deserialize_seed_at(pos, PhantomData)
+    }
+
+    /// Like `deserialize_at` but driven by a `DeserializeSeed`.
+    pub fn deserialize_seed_at<'de, S>(&self, pos: &mut usize, seed: S) -> Result<S::Value>
+        where S: DeserializeSeed<'de>
+    {
+        self.deserialize_seed_with(pos, seed, &Options::default())
+    }
+
+    /// Like `deserialize_seed_at` with the options of a `Deserializer`.
+    fn deserialize_seed_with<'de, S>(&self, pos: &mut usize, seed: S, options: &Options) -> Result<S::Value>
+        where S: DeserializeSeed<'de>
+    {
+        document(|| {
+                     seed.deserialize(&mut DeserializerFromEvents {
+                                          events: &self.events,
+                                          aliases: &self.aliases,
+                                          pos: pos,
+                                          path: Path::Root,
+                                          options: options,
+                                      })
+                 })
+    }
+}
+
+/// Deserializes one document.
+fn document<T, F>(f: F) -> T
+    where F: FnOnce() -> T
+{
+    #[cfg(feature = "shared")]
+    {
+        shared::deserialize_document(f)
+    }
+    #[cfg(not(feature = "shared"))]
+    {
+        f()
+    }
 }
 
 impl MarkedEventReceiver for Loader {
@@ -40,7 +155,7 @@ impl MarkedEventReceiver for Loader {
             YamlEvent::Alias(id) => Event::Alias(id),
             YamlEvent::Scalar(value, style, id, tag) => {
                 self.aliases.insert(id, self.events.len());
-                Event::Scalar(value, style, tag)
+                Event::Scalar(ScalarString::from(value), style, tag.map(Box::new))
             }
             YamlEvent::SequenceStart(id) => {
                 self.aliases.insert(id, self.events.len());
@@ -57,25 +172,289 @@ impl MarkedEventReceiver for Loader {
     }
 }
 
+/// Hands the events of the input to `recv`, of the first document only
+/// unless `multi`. This is `Parser::load` without its recursion into every
+/// collection, which overflows the stack on deeply nested input.
+fn parse<R>(s: &str, recv: &mut R, multi: bool, policy: &AnchorPolicy, scope: AnchorScope) -> Result<()>
+    where R: MarkedEventReceiver
+{
+    let names = if policy.is_default() && scope == AnchorScope::Document { None } else { Some(Names::scan(s)) };
+    // The parser fails on aliases to anchors it has not seen, including those
+    // of earlier documents, so those are blanked out of its input and their
+    // events put back afterwards.
+    let blanked;
+    let input = match names {
+        Some(ref names) if !names.undefined.is_empty() => {
+            blanked = names.blank(s);
+            &blanked[..]
+        }
+        _ => s,
+    };
+    let mut parser = Parser::new(input.chars());
+    // The anchors of the current document. Like `Parser::load`, aliases
+    // cannot refer to the anchors of an earlier document.
+    let mut anchors = HashSet::new();
+    // The same by name, for finding anchors that are defined again.
+    let mut defined = HashSet::new();
+    loop {
+        let (mut event, marker) = parser.next().map_err(|err| parse_error(err, input, names.as_ref()))?;
+        let last = match event {
+            YamlEvent::StreamEnd => true,
+            YamlEvent::DocumentStart => {
+                anchors.clear();
+                defined.clear();
+                false
+            }
+            YamlEvent::DocumentEnd => !multi,
+            // The parser keeps the anchors of earlier documents, so this is
+            // where aliases to them end up unless they were blanked.
+            YamlEvent::Alias(id) if !anchors.contains(&id) => {
+                let scanned;
+                let names = match names {
+                    Some(ref names) => names,
+                    None => {
+                        scanned = Names::scan(s);
+                        &scanned
+                    }
+                };
+                event = undefined_alias(Some(names), policy, marker)?;
+                false
+            }
+            YamlEvent::Scalar(..) if is_blanked(names.as_ref(), marker) => {
+                event = match names.as_ref().and_then(|names| names.earlier.get(&marker.index())) {
+                    Some(&id) if scope == AnchorScope::Stream => YamlEvent::Alias(id),
+                    _ => undefined_alias(names.as_ref(), policy, marker)?,
+                };
+                false
+            }
+            YamlEvent::Scalar(_, _, id, _) |
+            YamlEvent::SequenceStart(id) |
+            YamlEvent::MappingStart(id) => {
+                if id > 0 {
+                    anchors.insert(id);
+                    if let Some((name, at)) = names.as_ref().and_then(|names| names.anchors.get(id - 1)) {
+                        if !defined.insert(name) && !policy.redefined(name, Location::from_marker(at)) {
+                            let msg = format!("while parsing anchor, found anchor `{}` defined again", name);
+                            return Err(Error::scanner(ScanError::new(*at, &msg)));
+                        }
+                    }
+                }
+                false
+            }
+            _ => false,
+        };
+        recv.on_event(event, marker);
+        if last {
+            return Ok(());
+        }
+    }
+}
+
+/// Whether the scalar at `marker` is an alias that `Names::blank` blanked
+/// out.
+fn is_blanked(names: Option<&Names>, marker: Marker) -> bool {
+    names.map_or(false, |names| names.undefined.contains(&marker.index()))
+}
+
+/// The error for an alias at `marker` whose anchor is not defined, naming
+/// the anchor if it is known.
+fn unknown_anchor(names: Option<&Names>, marker: Marker) -> Error {
+    let msg = match names.and_then(|names| names.aliases.get(&marker.index())) {
+        Some(name) if names.map_or(false, |names| names.earlier.contains_key(&marker.index())) => {
+            format!("while parsing node, found unknown anchor `{}`, which is defined in an earlier document; \
+                     see `AnchorScope`",
+                    name)
+        }
+        Some(name) => format!("while parsing node, found unknown anchor `{}`", name),
+        None => "while parsing node, found unknown anchor".to_owned(),
+    };
+    Error::unknown_anchor(ScanError::new(marker, &msg))
+}
+
+/// The error for a parser error in `s`. The parser does not say which
+/// anchor an alias refers to when it does not know it, so the input is
+/// scanned for the name if `names` is not there already. An error at an
+/// alias that is not defined before it is the one for that.
+fn parse_error(err: ScanError, s: &str, names: Option<&Names>) -> Error {
+    let scanned;
+    let names = match names {
+        Some(names) => names,
+        None => {
+            scanned = Names::scan(s);
+            &scanned
+        }
+    };
+    if !names.undefined.contains(&err.marker().index()) {
+        return Error::scanner_in(err, s);
+    }
+    unknown_anchor(Some(names), *err.marker())
+}
+
+/// The event for an alias at `marker` whose anchor is not defined, if the
+/// policy allows for one.
+fn undefined_alias(names: Option<&Names>, policy: &AnchorPolicy, marker: Marker) -> Result<YamlEvent> {
+    let name = names.and_then(|names| names.aliases.get(&marker.index()));
+    let action = match name {
+        Some(name) => policy.undefined(name, Location::from_marker(&marker)),
+        None => Undefined::Fail,
+    };
+    match action {
+        Undefined::Fail => Err(unknown_anchor(names, marker)),
+        Undefined::Null => Ok(YamlEvent::Scalar("~".to_owned(), TScalarStyle::Plain, 0, None)),
+        Undefined::Placeholder => {
+            let tag = TokenType::Tag("!".to_owned(), "alias".to_owned());
+            let value = format!("*{}", name.map_or("", String::as_str));
+            Ok(YamlEvent::Scalar(value, TScalarStyle::SingleQuoted, 0, Some(tag)))
+        }
+    }
+}
+
+/// The names of the anchors and aliases of an input, which the events of
+/// the parser do not carry.
+struct Names {
+    /// Every anchor with its position, in the order they appear, which is
+    /// also the order of their ids.
+    anchors: Vec<(String, Marker)>,
+    /// Every alias by its index in the input.
+    aliases: HashMap<usize, String>,
+    /// The indices of the aliases to anchors that are not defined before
+    /// them in their document.
+    undefined: BTreeSet<usize>,
+    /// The id of the latest anchor that each of those aliases refers to in
+    /// an earlier document, by the index of the alias.
+    earlier: HashMap<usize, usize>,
+}
+
+impl Names {
+    fn scan(s: &str) -> Self {
+        let mut names = Names {
+            anchors: Vec::new(),
+            aliases: HashMap::new(),
+            undefined: BTreeSet::new(),
+            earlier: HashMap::new(),
+        };
+        // The anchors of the current document, and the id of the latest
+        // definition of every anchor so far.
+        let mut seen = HashSet::new();
+        let mut ids = HashMap::new();
+        for token in Scanner::new(s.chars()) {
+            match token.1 {
+                TokenType::DocumentStart | TokenType::DocumentEnd => seen.clear(),
+                TokenType::Anchor(name) => {
+                    seen.insert(name.clone());
+                    ids.insert(name.clone(), names.anchors.len() + 1);
+                    names.anchors.push((name, token.0));
+                }
+                TokenType::Alias(name) => {
+                    if !seen.contains(&name) {
+                        names.undefined.insert(token.0.index());
+                        if let Some(&id) = ids.get(&name) {
+                            names.earlier.insert(token.0.index(), id);
+                        }
+                    }
+                    names.aliases.insert(token.0.index(), name);
+                }
+                _ => {}
+            }
+        }
+        names
+    }
+
+    /// The input with every undefined alias replaced by `~` and spaces, so
+    /// that the parser takes it for a null and nothing else moves.
+    fn blank(&self, s: &str) -> String {
+        let mut blanked = String::with_capacity(s.len());
+        let mut skip = 0;
+        for (i, c) in s.chars().enumerate() {
+            if self.undefined.contains(&i) {
+                skip = self.aliases[&i].chars().count() + 1;
+            }
+            if skip > 0 {
+                blanked.push(if self.undefined.contains(&i) { '~' } else { ' ' });
+                skip -= 1;
+            } else {
+                blanked.push(c);
+            }
+        }
+        blanked
+    }
+}
+
+/// Collects the events of a single document along with where it ends.
+struct FirstDocument {
+    loader: Loader,
+    end: Option<Marker>,
+}
+
+impl MarkedEventReceiver for FirstDocument {
+    fn on_event(&mut self, event: YamlEvent, marker: Marker) {
+        if let YamlEvent::DocumentEnd = event {
+            self.end = Some(marker);
+        }
+        self.loader.on_event(event, marker);
+    }
+}
+
 #[derive(Debug, PartialEq)]
-enum Event {
+pub enum Event {
     Alias(usize),
-    Scalar(String, TScalarStyle, Option<TokenType>),
+    // Tags are rare, so they are boxed to keep the event stream small.
+    Scalar(ScalarString, TScalarStyle, Option<Box<TokenType>>),
     SequenceStart,
     SequenceEnd,
     MappingStart,
     MappingEnd,
 }
 
-struct Deserializer<'a> {
+struct DeserializerFromEvents<'a> {
     events: &'a [(Event, Marker)],
     /// Map from alias id to index in events.
     aliases: &'a BTreeMap<usize, usize>,
     pos: &'a mut usize,
     path: Path<'a>,
+    options: &'a Options,
 }
 
-impl<'a> Deserializer<'a> {
+/// The options of a `Deserializer`, shared by every `DeserializerFromEvents`
+/// of a document.
+#[derive(Clone, Default)]
+struct Options {
+    tags: TagPolicy,
+    strict_floats: bool,
+    whole_floats: bool,
+    duplicate_keys: DuplicateKeys,
+    tabs: Tabs,
+    profile: Option<Profile>,
+    deprecations: Option<Deprecations>,
+    anchors: AnchorPolicy,
+    anchor_scope: AnchorScope,
+    error_lines: Option<usize>,
+    dates: Dates,
+    times: Times,
+    merge_keys: MergeKeys,
+    numbers: NumberDialect,
+}
+
+/// What to do when a mapping has the same key more than once.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum DuplicateKeys {
+    /// Hand every entry to the type being deserialized, which for maps like
+    /// `Mapping`, `HashMap` and `BTreeMap` means the last one wins. This is
+    /// the default.
+    Last,
+    /// Skip the entries after the first one with each key.
+    First,
+    /// Fail with an error at the second occurrence of the key.
+    Error,
+}
+
+impl Default for DuplicateKeys {
+    fn default() -> Self {
+        DuplicateKeys::Last
+    }
+}
+
+impl<'a> DeserializerFromEvents<'a> {
     fn peek(&self) -> Result<(&'a Event, Marker)> {
         match self.events.get(*self.pos) {
             Some(event) => Ok((&event.0, event.1)),
@@ -93,15 +472,19 @@ impl<'a> Deserializer<'a> {
         }
     }
 
-    fn jump(&'a self, pos: &'a mut usize) -> Result<Deserializer<'a>> {
+    fn jump(&'a self, pos: &'a mut usize) -> Result<DeserializerFromEvents<'a>> {
         match self.aliases.get(pos) {
             Some(&found) => {
+                if let Some(ref profile) = self.options.profile {
+                    profile.add_alias();
+                }
                 *pos = found;
-                Ok(Deserializer {
+                Ok(DeserializerFromEvents {
                        events: self.events,
                        aliases: self.aliases,
                        pos: pos,
                        path: Path::Alias { parent: &self.path },
+                       options: self.options,
                    })
             }
             None => panic!("unresolved alias: {}", *pos),
@@ -117,9 +500,11 @@ impl<'a> Deserializer<'a> {
                 de::Deserializer::deserialize_any(&mut self.jump(&mut pos)?, visitor)
             }
             Event::Scalar(ref v, style, ref tag) => {
-                if style != TScalarStyle::Plain {
+                if let Some((tag, action)) = self.options.tags.action(tag) {
+                    visit_tagged(visitor, v, style, tag, action, self.options.strict_floats)
+                } else if style != TScalarStyle::Plain {
                     visitor.visit_str(v)
-                } else if let Some(TokenType::Tag(ref handle, ref suffix)) = *tag {
+                } else if let Some(&TokenType::Tag(ref handle, ref suffix)) = tag.as_ref().map(|tag| &**tag) {
                     if handle == "!!" {
                         match suffix.as_ref() {
                             "bool" => {
@@ -141,9 +526,9 @@ impl<'a> Deserializer<'a> {
                                 }
                             }
                             "float" => {
-                                match v.parse::<f64>() {
-                                    Ok(v) => visitor.visit_f64(v),
-                                    Err(_) => {
+                                match parse_float(v, self.options.strict_floats) {
+                                    Some(v) => visitor.visit_f64(v),
+                                    None => {
                                         Err(de::Error::invalid_value(Unexpected::Str(v),
                                                                      &"a float"))
                                     }
@@ -161,7 +546,24 @@ impl<'a> Deserializer<'a> {
                         visitor.visit_str(v)
                     }
                 } else {
-                    visit_untagged_str(visitor, v)
+                    match implicit(v, self.options)? {
+                        Some(Implicit::Timestamp) => {
+                            visitor.visit_map(TaggedScalar {
+                                                  tag: Some(TIMESTAMP.to_owned()),
+                                                  value: v,
+                                                  style: style,
+                                                  strict_floats: self.options.strict_floats,
+                                              })
+                        }
+                        Some(Implicit::Number(Sexagesimal::Unsigned(n))) => visitor.visit_u64(n),
+                        Some(Implicit::Number(Sexagesimal::Negative(n))) => visitor.visit_i64(n),
+                        Some(Implicit::Number(Sexagesimal::Float(n))) => visitor.visit_f64(n),
+                        Some(Implicit::Digits(ref digits)) => {
+                            visit_untagged_str(visitor, digits, self.options.strict_floats)
+                        }
+                        Some(Implicit::String) => visitor.visit_str(v),
+                        None => visit_untagged_str(visitor, v, self.options.strict_floats),
+                    }
                 }
             }
             Event::SequenceStart => {
@@ -175,11 +577,7 @@ impl<'a> Deserializer<'a> {
             }
             Event::MappingStart => {
                 let (value, len) = {
-                    let mut map = MapAccess {
-                        de: &mut *self,
-                        len: 0,
-                        key: None,
-                    };
+                    let mut map = MapAccess::new(&mut *self, 0);
                     let value = visitor.visit_map(&mut map)?;
                     (value, map.len)
                 };
@@ -220,11 +618,7 @@ impl<'a> Deserializer<'a> {
 
     fn end_mapping(&mut self, len: usize) -> Result<()> {
         let total = {
-            let mut map = MapAccess {
-                de: self,
-                len: len,
-                key: None,
-            };
+            let mut map = MapAccess::new(self, len);
             while de::MapAccess::next_entry::<Ignore, Ignore>(&mut map)?.is_some() {}
             map.len
         };
@@ -248,7 +642,7 @@ impl<'a> Deserializer<'a> {
 }
 
 struct SeqAccess<'a: 'r, 'r> {
-    de: &'r mut Deserializer<'a>,
+    de: &'r mut DeserializerFromEvents<'a>,
     len: usize,
 }
 
@@ -263,7 +657,7 @@ impl<'de, 'a, 'r> de::SeqAccess<'de> for SeqAccess<'a, 'r> {
                    .0 {
             Event::SequenceEnd => Ok(None),
             _ => {
-                let mut element_de = Deserializer {
+                let mut element_de = DeserializerFromEvents {
                     events: self.de.events,
                     aliases: self.de.aliases,
                     pos: self.de.pos,
@@ -271,6 +665,7 @@ impl<'de, 'a, 'r> de::SeqAccess<'de> for SeqAccess<'a, 'r> {
                         parent: &self.de.path,
                         index: self.len,
                     },
+                    options: self.de.options,
                 };
                 self.len += 1;
                 seed.deserialize(&mut element_de).map(Some)
@@ -280,9 +675,57 @@ impl<'de, 'a, 'r> de::SeqAccess<'de> for SeqAccess<'a, 'r> {
 }
 
 struct MapAccess<'a: 'r, 'r> {
-    de: &'r mut Deserializer<'a>,
+    de: &'r mut DeserializerFromEvents<'a>,
     len: usize,
     key: Option<&'a str>,
+    /// The keys so far as `KeyText`, unless duplicates are let through.
+    seen: HashSet<String>,
+}
+
+impl<'a, 'r> MapAccess<'a, 'r> {
+    fn new(de: &'r mut DeserializerFromEvents<'a>, len: usize) -> Self {
+        MapAccess {
+            de: de,
+            len: len,
+            key: None,
+            seen: HashSet::new(),
+        }
+    }
+
+    /// Whether the entry at the current position has a key seen before in
+    /// this mapping, in which case it has been skipped or an error returned
+    /// according to the `DuplicateKeys` option.
+    fn skip_duplicate(&mut self) -> Result<bool> {
+        let policy = self.de.options.duplicate_keys;
+        if policy == DuplicateKeys::Last {
+            return Ok(false);
+        }
+        let (event, marker) = self.de.peek()?;
+        let mut pos = *self.de.pos;
+        let key = KeyText.deserialize(&mut DeserializerFromEvents {
+                                          events: self.de.events,
+                                          aliases: self.de.aliases,
+                                          pos: &mut pos,
+                                          path: Path::Unknown { parent: &self.de.path },
+                                          options: self.de.options,
+                                      })?;
+        if !self.seen.contains(&key) {
+            self.seen.insert(key);
+            return Ok(false);
+        }
+        if policy == DuplicateKeys::Error {
+            let err = match *event {
+                Event::Scalar(ref key, _, _) => {
+                    de::Error::custom(format_args!("duplicate key `{}`", &**key))
+                }
+                _ => de::Error::custom("duplicate key"),
+            };
+            return Err(Error::fix_marker(err, marker, self.de.path));
+        }
+        Ignore::deserialize(&mut *self.de)?;
+        Ignore::deserialize(&mut *self.de)?;
+        Ok(true)
+    }
 }
 
 impl<'de, 'a, 'r> de::MapAccess<'de> for MapAccess<'a, 'r> {
@@ -291,6 +734,7 @@ impl<'de, 'a, 'r> de::MapAccess<'de> for MapAccess<'a, 'r> {
     fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>>
         where K: DeserializeSeed<'de>
     {
+        while *self.de.peek()?.0 != Event::MappingEnd && self.skip_duplicate()? {}
         match *self.de
                    .peek()?
                    .0 {
@@ -298,6 +742,14 @@ impl<'de, 'a, 'r> de::MapAccess<'de> for MapAccess<'a, 'r> {
             Event::Scalar(ref key, _, _) => {
                 self.len += 1;
                 self.key = Some(key);
+                if let Some(ref deprecations) = self.de.options.deprecations {
+                    let path = Path::Map {
+                        parent: &self.de.path,
+                        key: key,
+                    };
+                    let marker = self.de.peek()?.1;
+                    deprecations.check(&path.to_string(), Location::from_marker(&marker));
+                }
                 seed.deserialize(&mut *self.de).map(Some)
             }
             _ => {
@@ -311,7 +763,7 @@ impl<'de, 'a, 'r> de::MapAccess<'de> for MapAccess<'a, 'r> {
     fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value>
         where V: DeserializeSeed<'de>
     {
-        let mut value_de = Deserializer {
+        let mut value_de = DeserializerFromEvents {
             events: self.de.events,
             aliases: self.de.aliases,
             pos: self.de.pos,
@@ -323,19 +775,130 @@ impl<'de, 'a, 'r> de::MapAccess<'de> for MapAccess<'a, 'r> {
             } else {
                 Path::Unknown { parent: &self.de.path }
             },
+            options: self.de.options,
         };
         seed.deserialize(&mut value_de)
     }
 }
 
+/// Text that two keys share exactly when they deserialize to the same data,
+/// for telling keys apart without building a `Value` of each.
+struct KeyText;
+
+impl<'de> DeserializeSeed<'de> for KeyText {
+    type Value = String;
+
+    fn deserialize<D>(self, deserializer: D) -> result::Result<String, D::Error>
+        where D: de::Deserializer<'de>
+    {
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de> de::Visitor<'de> for KeyText {
+    type Value = String;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("any YAML value")
+    }
+
+    fn visit_bool<E>(self, b: bool) -> result::Result<String, E>
+        where E: de::Error
+    {
+        Ok(format!("b{}", b))
+    }
+
+    #[allow(clippy::cast_sign_loss)]
+    fn visit_i64<E>(self, i: i64) -> result::Result<String, E>
+        where E: de::Error
+    {
+        if i < 0 {
+            Ok(format!("i{}", i))
+        } else {
+            self.visit_u64(i as u64)
+        }
+    }
+
+    fn visit_u64<E>(self, u: u64) -> result::Result<String, E>
+        where E: de::Error
+    {
+        Ok(format!("u{}", u))
+    }
+
+    fn visit_f64<E>(self, f: f64) -> result::Result<String, E>
+        where E: de::Error
+    {
+        Ok(format!("f{:?}", f))
+    }
+
+    fn visit_str<E>(self, s: &str) -> result::Result<String, E>
+        where E: de::Error
+    {
+        Ok(format!("s{}:{}", s.len(), s))
+    }
+
+    fn visit_bytes<E>(self, v: &[u8]) -> result::Result<String, E>
+        where E: de::Error
+    {
+        Ok(format!("x{:?}", v))
+    }
+
+    fn visit_unit<E>(self) -> result::Result<String, E>
+        where E: de::Error
+    {
+        Ok("~".to_owned())
+    }
+
+    fn visit_none<E>(self) -> result::Result<String, E>
+        where E: de::Error
+    {
+        self.visit_unit()
+    }
+
+    fn visit_some<D>(self, deserializer: D) -> result::Result<String, D::Error>
+        where D: de::Deserializer<'de>
+    {
+        deserializer.deserialize_any(self)
+    }
+
+    fn visit_newtype_struct<D>(self, deserializer: D) -> result::Result<String, D::Error>
+        where D: de::Deserializer<'de>
+    {
+        deserializer.deserialize_any(self)
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> result::Result<String, A::Error>
+        where A: de::SeqAccess<'de>
+    {
+        let mut text = "[".to_owned();
+        while let Some(element) = seq.next_element_seed(KeyText)? {
+            text.push_str(&element);
+        }
+        text.push(']');
+        Ok(text)
+    }
+
+    fn visit_map<A>(self, mut map: A) -> result::Result<String, A::Error>
+        where A: de::MapAccess<'de>
+    {
+        let mut text = "{".to_owned();
+        while let Some((key, value)) = map.next_entry_seed(KeyText, KeyText)? {
+            text.push_str(&key);
+            text.push_str(&value);
+        }
+        text.push('}');
+        Ok(text)
+    }
+}
+
 struct EnumAccess<'a: 'r, 'r> {
-    de: &'r mut Deserializer<'a>,
+    de: &'r mut DeserializerFromEvents<'a>,
     name: &'static str,
 }
 
 impl<'de, 'a, 'r> de::EnumAccess<'de> for EnumAccess<'a, 'r> {
     type Error = Error;
-    type Variant = Deserializer<'r>;
+    type Variant = DeserializerFromEvents<'r>;
 
     fn variant_seed<V>(self, seed: V) -> Result<(V::Value, Self::Variant)>
         where V: DeserializeSeed<'de>
@@ -368,7 +931,7 @@ impl<'de, 'a, 'r> de::EnumAccess<'de> for EnumAccess<'a, 'r> {
 
         let str_de = IntoDeserializer::<Error>::into_deserializer(variant);
         let ret = seed.deserialize(str_de)?;
-        let variant_visitor = Deserializer {
+        let variant_visitor = DeserializerFromEvents {
             events: self.de.events,
             aliases: self.de.aliases,
             pos: self.de.pos,
@@ -376,12 +939,13 @@ impl<'de, 'a, 'r> de::EnumAccess<'de> for EnumAccess<'a, 'r> {
                 parent: &self.de.path,
                 key: variant,
             },
+            options: self.de.options,
         };
         Ok((ret, variant_visitor))
     }
 }
 
-impl<'de, 'a> de::VariantAccess<'de> for Deserializer<'a> {
+impl<'de, 'a> de::VariantAccess<'de> for DeserializerFromEvents<'a> {
     type Error = Error;
 
     fn unit_variant(mut self) -> Result<()> {
@@ -408,7 +972,7 @@ impl<'de, 'a> de::VariantAccess<'de> for Deserializer<'a> {
 }
 
 struct UnitVariantAccess<'a: 'r, 'r> {
-    de: &'r mut Deserializer<'a>,
+    de: &'r mut DeserializerFromEvents<'a>,
 }
 
 impl<'de, 'a, 'r> de::EnumAccess<'de> for UnitVariantAccess<'a, 'r> {
@@ -448,91 +1012,486 @@ impl<'de, 'a, 'r> de::VariantAccess<'de> for UnitVariantAccess<'a, 'r> {
     }
 }
 
-fn visit_untagged_str<'de, V>(visitor: V, v: &str) -> Result<V::Value>
+fn visit_tagged<'de, V>(visitor: V,
+                        v: &str,
+                        style: TScalarStyle,
+                        tag: String,
+                        action: TagAction,
+                        strict_floats: bool)
+                        -> Result<V::Value>
     where V: de::Visitor<'de>
 {
-    if v == "~" || v == "null" {
-        return visitor.visit_unit();
-    }
-    if v == "true" {
-        return visitor.visit_bool(true);
-    }
-    if v == "false" {
-        return visitor.visit_bool(false);
-    }
-    if v.starts_with("0x") {
-        if let Ok(n) = u64::from_str_radix(&v[2..], 16) {
-            return visitor.visit_u64(n);
-        }
-        if let Ok(n) = i64::from_str_radix(&v[2..], 16) {
-            return visitor.visit_i64(n);
-        }
-    }
-    if v.starts_with("0o") {
-        if let Ok(n) = u64::from_str_radix(&v[2..], 8) {
-            return visitor.visit_u64(n);
-        }
-        if let Ok(n) = i64::from_str_radix(&v[2..], 8) {
-            return visitor.visit_i64(n);
-        }
-    }
-    if v.starts_with('+') {
-        if let Ok(n) = v.parse() {
-            return visitor.visit_u64(n);
+    match action {
+        TagAction::Ignore => visit_scalar(visitor, v, style, strict_floats),
+        TagAction::Base64 => {
+            match tags::decode_base64(v) {
+                Some(bytes) => visitor.visit_byte_buf(bytes),
+                None => Err(de::Error::invalid_value(Unexpected::Str(v), &"base64 data")),
+            }
         }
-        if let Ok(n) = v[1..].parse() {
-            return visitor.visit_i64(n);
+        TagAction::Wrap => {
+            visitor.visit_map(TaggedScalar {
+                                  tag: Some(tag),
+                                  value: v,
+                                  style: style,
+                                  strict_floats: strict_floats,
+                              })
         }
+        TagAction::Error => Err(de::Error::custom(format_args!("unsupported tag `{}`", tag))),
     }
-    if let Ok(n) = v.parse() {
-        return visitor.visit_u64(n);
-    }
-    if let Ok(n) = v.parse() {
-        return visitor.visit_i64(n);
-    }
-    if let Ok(n) = v.parse() {
-        return visitor.visit_f64(n);
+}
+
+fn visit_scalar<'de, V>(visitor: V, v: &str, style: TScalarStyle, strict_floats: bool) -> Result<V::Value>
+    where V: de::Visitor<'de>
+{
+    if style == TScalarStyle::Plain {
+        visit_untagged_str(visitor, v, strict_floats)
+    } else {
+        visitor.visit_str(v)
     }
-    visitor.visit_str(v)
 }
 
-impl<'de, 'a, 'r> de::Deserializer<'de> for &'r mut Deserializer<'a> {
+/// The text of a number presented as a single entry map from `number::TOKEN`.
+#[cfg(feature = "value")]
+struct LazyNumber<'a> {
+    key: bool,
+    value: &'a str,
+}
+
+#[cfg(feature = "value")]
+impl<'de, 'a> de::MapAccess<'de> for LazyNumber<'a> {
     type Error = Error;
 
-    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
-        where V: de::Visitor<'de>
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>>
+        where K: DeserializeSeed<'de>
     {
-        let marker = self.peek()?.1;
-        // The de::Error impl creates errors with unknown line and column. Fill
-        // in the position here by looking at the current index in the input.
-        self.visit(visitor).map_err(|err| err.fix_marker(marker, self.path))
+        if self.key {
+            self.key = false;
+            number::lazy_key(|| seed.deserialize(number::TOKEN.into_deserializer())).map(Some)
+        } else {
+            Ok(None)
+        }
     }
 
-    fn deserialize_str<V>(self, visitor: V) -> Result<V::Value>
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value>
+        where V: DeserializeSeed<'de>
+    {
+        seed.deserialize(self.value.into_deserializer())
+    }
+}
+
+#[cfg(feature = "value")]
+impl<'a> DeserializerFromEvents<'a> {
+    /// This is how `Value` asks for a node. Plain scalars that are certainly
+    /// numbers are handed over as text, for `Number` to convert only if the
+    /// value is ever looked at.
+    fn deserialize_number<'de, V>(&mut self, visitor: V) -> Result<V::Value>
         where V: de::Visitor<'de>
     {
-        let (next, marker) = self.peek()?;
-        match *next {
-            Event::Scalar(ref v, _, _) => {
-                *self.pos += 1;
-                visitor.visit_str(v).map_err(|err: Error| err.fix_marker(marker, self.path))
-            },
-            Event::Alias(i) => {
+        match *self.peek()?.0 {
+            Event::Scalar(ref v, TScalarStyle::Plain, None) if Number::is_lazy(v) &&
+                                                               self.options.numbers.resolve(v).is_none() => {
                 *self.pos += 1;
-                let mut pos = i;
-                self.jump(&mut pos)?.deserialize_str(visitor)
-            },
-            _ => self.deserialize_any(visitor)
+                visitor.visit_map(LazyNumber {
+                                      key: true,
+                                      value: v,
+                                  })
+            }
+            _ => de::Deserializer::deserialize_any(self, visitor),
         }
     }
+}
 
-    fn deserialize_string<V>(self, visitor: V) -> Result<V::Value>
+#[cfg(feature = "shared")]
+impl<'a> DeserializerFromEvents<'a> {
+    /// Hands a `Shared` the position of the node to read it from, followed by
+    /// the node. Aliases give the position of the node they refer to.
+    fn deserialize_shared<'de, V>(&mut self, visitor: V) -> Result<V::Value>
         where V: de::Visitor<'de>
     {
-        self.deserialize_str(visitor)
+        if let Event::Alias(i) = *self.peek()?.0 {
+            *self.pos += 1;
+            let mut pos = i;
+            let mut de = self.jump(&mut pos)?;
+            let node = *de.pos;
+            return visitor.visit_map(SharedNode {
+                                         node: Some(node),
+                                         de: &mut de,
+                                         read: false,
+                                     });
+        }
+        let mut access = SharedNode {
+            node: Some(*self.pos),
+            de: self,
+            read: false,
+        };
+        let value = visitor.visit_map(&mut access)?;
+        // A pointer built for this node already was handed out instead.
+        if !access.read {
+            Ignore::deserialize(&mut *access.de)?;
+        }
+        Ok(value)
     }
+}
 
-    /// Parses `null` as None and any other values as `Some(...)`.
+/// The position of a node presented as a single entry map from the position
+/// to the node, for `shared::Shared`.
+#[cfg(feature = "shared")]
+struct SharedNode<'a: 'r, 'r> {
+    node: Option<usize>,
+    de: &'r mut DeserializerFromEvents<'a>,
+    read: bool,
+}
+
+#[cfg(feature = "shared")]
+impl<'de, 'a, 'r> de::MapAccess<'de> for SharedNode<'a, 'r> {
+    type Error = Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>>
+        where K: DeserializeSeed<'de>
+    {
+        match self.node.take() {
+            Some(node) => seed.deserialize((node as u64).into_deserializer()).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value>
+        where V: DeserializeSeed<'de>
+    {
+        self.read = true;
+        seed.deserialize(&mut *self.de)
+    }
+}
+
+/// The tag that `Dates::Timestamp` gives untagged dates.
+const TIMESTAMP: &str = "!!timestamp";
+
+/// How the options resolve an untagged plain scalar that YAML 1.1 reads as
+/// something other than a string, if not as a string.
+enum Implicit {
+    Timestamp,
+    Number(Sexagesimal),
+    /// A number in the dialect of the `numbers` option, as the text that
+    /// `visit_untagged_str` reads as it.
+    Digits(String),
+    /// A string that would otherwise be a number.
+    String,
+}
+
+/// Resolves an untagged plain scalar according to the `dates`, `times` and
+/// `numbers` options, failing if they refuse it.
+fn implicit(v: &str, options: &Options) -> Result<Option<Implicit>> {
+    if options.dates != Dates::String && dates::is_date(v) {
+        return match options.dates {
+            Dates::Timestamp => Ok(Some(Implicit::Timestamp)),
+            _ => {
+                Err(de::Error::custom(format_args!("unquoted date `{}` is a timestamp in YAML 1.1; quote it to keep it a string",
+                                                   v)))
+            }
+        };
+    }
+    if options.times != Times::String {
+        if let Some(n) = dates::sexagesimal(v) {
+            return match options.times {
+                Times::Sexagesimal => Ok(Some(Implicit::Number(n))),
+                _ => {
+                    Err(de::Error::custom(format_args!("unquoted `{}` is a base 60 number in YAML 1.1; quote it to keep it a string",
+                                                       v)))
+                }
+            };
+        }
+    }
+    match options.numbers.resolve(v) {
+        Some(Spelling::Number(digits)) => Ok(Some(Implicit::Digits(digits))),
+        Some(Spelling::String) => Ok(Some(Implicit::String)),
+        None => Ok(None),
+    }
+}
+
+/// A tagged scalar presented as a single entry map or enum, from the tag to
+/// the scalar without its tag.
+struct TaggedScalar<'a> {
+    tag: Option<String>,
+    value: &'a str,
+    style: TScalarStyle,
+    strict_floats: bool,
+}
+
+impl<'de, 'a> de::MapAccess<'de> for TaggedScalar<'a> {
+    type Error = Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>>
+        where K: DeserializeSeed<'de>
+    {
+        match self.tag.take() {
+            Some(tag) => seed.deserialize(tag.into_deserializer()).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value>
+        where V: DeserializeSeed<'de>
+    {
+        seed.deserialize(UntaggedScalar {
+                             value: self.value,
+                             style: self.style,
+                             strict_floats: self.strict_floats,
+                         })
+    }
+}
+
+impl<'de, 'a> de::EnumAccess<'de> for TaggedScalar<'a> {
+    type Error = Error;
+    type Variant = UntaggedScalar<'a>;
+
+    fn variant_seed<V>(mut self, seed: V) -> Result<(V::Value, Self::Variant)>
+        where V: DeserializeSeed<'de>
+    {
+        let tag = self.tag.take().unwrap_or_default();
+        let variant = seed.deserialize(tag.into_deserializer())?;
+        Ok((variant,
+            UntaggedScalar {
+                value: self.value,
+                style: self.style,
+                strict_floats: self.strict_floats,
+            }))
+    }
+}
+
+struct UntaggedScalar<'a> {
+    value: &'a str,
+    style: TScalarStyle,
+    strict_floats: bool,
+}
+
+impl<'de, 'a> de::Deserializer<'de> for UntaggedScalar<'a> {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
+        where V: de::Visitor<'de>
+    {
+        visit_scalar(visitor, self.value, self.style, self.strict_floats)
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct map struct enum identifier ignored_any
+    }
+}
+
+impl<'de, 'a> de::VariantAccess<'de> for UntaggedScalar<'a> {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<()> {
+        Ok(())
+    }
+
+    fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value>
+        where T: DeserializeSeed<'de>
+    {
+        seed.deserialize(self)
+    }
+
+    fn tuple_variant<V>(self, _len: usize, visitor: V) -> Result<V::Value>
+        where V: de::Visitor<'de>
+    {
+        de::Deserializer::deserialize_any(self, visitor)
+    }
+
+    fn struct_variant<V>(self, _fields: &'static [&'static str], visitor: V) -> Result<V::Value>
+        where V: de::Visitor<'de>
+    {
+        de::Deserializer::deserialize_any(self, visitor)
+    }
+}
+
+fn visit_untagged_str<'de, V>(visitor: V, v: &str, strict_floats: bool) -> Result<V::Value>
+    where V: de::Visitor<'de>
+{
+    if v == "~" || v == "null" {
+        return visitor.visit_unit();
+    }
+    if v == "true" {
+        return visitor.visit_bool(true);
+    }
+    if v == "false" {
+        return visitor.visit_bool(false);
+    }
+    if v.starts_with("0x") {
+        if let Ok(n) = u64::from_str_radix(&v[2..], 16) {
+            return visitor.visit_u64(n);
+        }
+        if let Ok(n) = i64::from_str_radix(&v[2..], 16) {
+            return visitor.visit_i64(n);
+        }
+    }
+    if v.starts_with("0o") {
+        if let Ok(n) = u64::from_str_radix(&v[2..], 8) {
+            return visitor.visit_u64(n);
+        }
+        if let Ok(n) = i64::from_str_radix(&v[2..], 8) {
+            return visitor.visit_i64(n);
+        }
+    }
+    if v.starts_with('+') {
+        if let Ok(n) = v.parse() {
+            return visitor.visit_u64(n);
+        }
+        if let Ok(n) = v[1..].parse() {
+            return visitor.visit_i64(n);
+        }
+    }
+    if let Ok(n) = v.parse() {
+        return visitor.visit_u64(n);
+    }
+    if let Ok(n) = v.parse() {
+        return visitor.visit_i64(n);
+    }
+    if let Some(n) = parse_float(v, strict_floats) {
+        return visitor.visit_f64(n);
+    }
+    visitor.visit_str(v)
+}
+
+/// An integer written as a float with nothing but zeros after the point.
+enum Whole {
+    Unsigned(u64),
+    Signed(i64),
+}
+
+/// Reads `3.0`, `+3.` or `-3.00` as a whole number, or returns `None` if
+/// the scalar is not written that way or does not fit in 64 bits.
+fn whole_float(v: &str) -> Option<Whole> {
+    let dot = v.find('.')?;
+    let (int, zeros) = (&v[..dot], &v[dot + 1..]);
+    if !zeros.bytes().all(|b| b == b'0') {
+        return None;
+    }
+    let (negative, digits) = match int.as_bytes().first() {
+        Some(&b'-') => (true, &int[1..]),
+        Some(&b'+') => (false, &int[1..]),
+        _ => (false, int),
+    };
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    if negative {
+        int.parse().ok().map(Whole::Signed)
+    } else {
+        digits.parse().ok().map(Whole::Unsigned)
+    }
+}
+
+/// Integer methods that read floats like `3.0` as integers if the options
+/// say so, and otherwise forward to `deserialize_any`.
+macro_rules! deserialize_whole {
+    ($($method:ident)*) => {
+        $(
+            fn $method<V>(self, visitor: V) -> Result<V::Value>
+                where V: de::Visitor<'de>
+            {
+                if !self.options.whole_floats {
+                    return self.deserialize_any(visitor);
+                }
+                let (next, marker) = self.peek()?;
+                match *next {
+                    Event::Scalar(ref v, TScalarStyle::Plain, None) => {
+                        if let Some(n) = whole_float(v) {
+                            *self.pos += 1;
+                            let result = match n {
+                                Whole::Unsigned(n) => visitor.visit_u64(n),
+                                Whole::Signed(n) => visitor.visit_i64(n),
+                            };
+                            return result.map_err(|err: Error| err.fix_marker(marker, self.path));
+                        }
+                    }
+                    Event::Alias(i) => {
+                        *self.pos += 1;
+                        let mut pos = i;
+                        return self.jump(&mut pos)?.$method(visitor);
+                    }
+                    _ => {}
+                }
+                self.deserialize_any(visitor)
+            }
+        )*
+    }
+}
+
+impl<'de, 'a, 'r> de::Deserializer<'de> for &'r mut DeserializerFromEvents<'a> {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
+        where V: de::Visitor<'de>
+    {
+        let marker = self.peek()?.1;
+        // The de::Error impl creates errors with unknown line and column. Fill
+        // in the position here by looking at the current index in the input.
+        self.visit(visitor).map_err(|err| err.fix_marker(marker, self.path))
+    }
+
+    fn deserialize_str<V>(self, visitor: V) -> Result<V::Value>
+        where V: de::Visitor<'de>
+    {
+        let (next, marker) = self.peek()?;
+        match *next {
+            Event::Scalar(ref v, style, ref tag) if self.options.tags.action(tag).is_none() => {
+                *self.pos += 1;
+                // A string is what the scalar is wanted as, but a policy may
+                // still refuse it.
+                if style == TScalarStyle::Plain && tag.is_none() {
+                    implicit(v, self.options).map_err(|err| err.fix_marker(marker, self.path))?;
+                }
+                visitor.visit_str(v).map_err(|err: Error| err.fix_marker(marker, self.path))
+            },
+            Event::Alias(i) => {
+                *self.pos += 1;
+                let mut pos = i;
+                self.jump(&mut pos)?.deserialize_str(visitor)
+            },
+            _ => self.deserialize_any(visitor)
+        }
+    }
+
+    fn deserialize_string<V>(self, visitor: V) -> Result<V::Value>
+        where V: de::Visitor<'de>
+    {
+        self.deserialize_str(visitor)
+    }
+
+    /// Decodes `!!binary` scalars as base64 whatever the tag policy, which is
+    /// how the serializer writes mapping keys made of bytes.
+    fn deserialize_bytes<V>(self, visitor: V) -> Result<V::Value>
+        where V: de::Visitor<'de>
+    {
+        let (next, marker) = self.peek()?;
+        match *next {
+            Event::Scalar(ref v, _, Some(ref tag)) if is_binary(tag) => {
+                *self.pos += 1;
+                let result = match tags::decode_base64(v) {
+                    Some(bytes) => visitor.visit_byte_buf(bytes),
+                    None => Err(de::Error::invalid_value(Unexpected::Str(v), &"base64 data")),
+                };
+                result.map_err(|err: Error| err.fix_marker(marker, self.path))
+            }
+            Event::Alias(i) => {
+                *self.pos += 1;
+                let mut pos = i;
+                self.jump(&mut pos)?.deserialize_bytes(visitor)
+            }
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_byte_buf<V>(self, visitor: V) -> Result<V::Value>
+        where V: de::Visitor<'de>
+    {
+        self.deserialize_bytes(visitor)
+    }
+
+    /// Parses `null` as None and any other values as `Some(...)`.
     fn deserialize_option<V>(self, visitor: V) -> Result<V::Value>
         where V: de::Visitor<'de>
     {
@@ -545,7 +1504,7 @@ impl<'de, 'a, 'r> de::Deserializer<'de> for &'r mut Deserializer<'a> {
             Event::Scalar(ref v, style, ref tag) => {
                 if style != TScalarStyle::Plain {
                     true
-                } else if let Some(TokenType::Tag(ref handle, ref suffix)) = *tag {
+                } else if let Some(&TokenType::Tag(ref handle, ref suffix)) = tag.as_ref().map(|tag| &**tag) {
                     if handle == "!!" && suffix == "null" {
                         if v == "~" || v == "null" {
                             false
@@ -572,9 +1531,22 @@ impl<'de, 'a, 'r> de::Deserializer<'de> for &'r mut Deserializer<'a> {
     }
 
     /// Parses a newtype struct as the underlying value.
-    fn deserialize_newtype_struct<V>(self, _name: &'static str, visitor: V) -> Result<V::Value>
+    #[cfg_attr(not(any(feature = "shared", feature = "value")), allow(unused_variables))]
+    fn deserialize_newtype_struct<V>(self, name: &'static str, visitor: V) -> Result<V::Value>
         where V: de::Visitor<'de>
     {
+        #[cfg(feature = "shared")]
+        {
+            if name == shared::TOKEN {
+                return self.deserialize_shared(visitor);
+            }
+        }
+        #[cfg(feature = "value")]
+        {
+            if name == number::TOKEN {
+                return self.deserialize_number(visitor);
+            }
+        }
         visitor.visit_newtype_struct(self)
     }
 
@@ -595,7 +1567,30 @@ impl<'de, 'a, 'r> de::Deserializer<'de> for &'r mut Deserializer<'a> {
                 let mut pos = i;
                 self.jump(&mut pos)?.deserialize_enum(name, variants, visitor)
             }
-            Event::Scalar(_, _, _) => visitor.visit_enum(UnitVariantAccess { de: self }),
+            Event::Scalar(ref v, style, ref tag) => {
+                let action = match self.options.tags.action(tag) {
+                    None if style == TScalarStyle::Plain && tag.is_none() => {
+                        match implicit(v, self.options).map_err(|err| err.fix_marker(marker, self.path))? {
+                            Some(Implicit::Timestamp) => Some((TIMESTAMP.to_owned(), TagAction::Wrap)),
+                            _ => None,
+                        }
+                    }
+                    action => action,
+                };
+                match action {
+                    Some((tag, TagAction::Wrap)) => {
+                        *self.pos += 1;
+                        visitor.visit_enum(TaggedScalar {
+                                               tag: Some(tag),
+                                               value: v,
+                                               style: style,
+                                               strict_floats: self.options.strict_floats,
+                                           })
+                                .map_err(|err: Error| err.fix_marker(marker, self.path))
+                    }
+                    _ => visitor.visit_enum(UnitVariantAccess { de: self }),
+                }
+            }
             Event::MappingStart => {
                 *self.pos += 1;
                 let value = visitor.visit_enum(EnumAccess {
@@ -614,9 +1609,832 @@ impl<'de, 'a, 'r> de::Deserializer<'de> for &'r mut Deserializer<'a> {
         }
     }
 
+    fn deserialize_i128<V>(self, visitor: V) -> Result<V::Value>
+        where V: de::Visitor<'de>
+    {
+        let (next, marker) = self.peek()?;
+        match *next {
+            Event::Scalar(ref v, TScalarStyle::Plain, None) => {
+                if let Ok(n) = v.parse() {
+                    *self.pos += 1;
+                    return visitor.visit_i128(n).map_err(|err: Error| err.fix_marker(marker, self.path));
+                }
+            }
+            Event::Alias(i) => {
+                *self.pos += 1;
+                let mut pos = i;
+                return self.jump(&mut pos)?.deserialize_i128(visitor);
+            }
+            _ => {}
+        }
+        self.deserialize_any(visitor)
+    }
+
+    fn deserialize_u128<V>(self, visitor: V) -> Result<V::Value>
+        where V: de::Visitor<'de>
+    {
+        let (next, marker) = self.peek()?;
+        match *next {
+            Event::Scalar(ref v, TScalarStyle::Plain, None) => {
+                if let Ok(n) = v.parse() {
+                    *self.pos += 1;
+                    return visitor.visit_u128(n).map_err(|err: Error| err.fix_marker(marker, self.path));
+                }
+            }
+            Event::Alias(i) => {
+                *self.pos += 1;
+                let mut pos = i;
+                return self.jump(&mut pos)?.deserialize_u128(visitor);
+            }
+            _ => {}
+        }
+        self.deserialize_any(visitor)
+    }
+
+    deserialize_whole! {
+        deserialize_i8 deserialize_i16 deserialize_i32 deserialize_i64
+        deserialize_u8 deserialize_u16 deserialize_u32 deserialize_u64
+    }
+
     forward_to_deserialize_any! {
-        bool i8 i16 i32 i64 u8 u16 u32 u64 f32 f64 char bytes byte_buf unit
-        unit_struct seq tuple tuple_struct map struct identifier ignored_any
+        bool f32 f64 char unit unit_struct seq tuple tuple_struct map struct
+        identifier ignored_any
+    }
+}
+
+fn is_binary(tag: &TokenType) -> bool {
+    match *tag {
+        TokenType::Tag(ref handle, ref suffix) => handle == "!!" && suffix == "binary",
+        _ => false,
+    }
+}
+
+/// A structure that deserializes YAML into Rust values.
+///
+/// The free functions `from_str`, `from_slice` and `from_reader` are
+/// shorthand for deserializing from one of these with the default options.
+/// Use this type directly to change the options, or to hand a YAML source to
+/// something that drives a `serde::Deserializer` itself, like
+/// `serde_transcode`.
+///
+/// A deserializer is also an iterator over the documents of its input, for
+/// streams whose documents are of different types.
+pub struct Deserializer<'a> {
+    input: Input<'a>,
+    options: Options,
+}
+
+enum Input<'a> {
+    Str(&'a str),
+    Slice(&'a [u8]),
+    Read(Box<dyn io::Read + 'a>),
+    Events(Box<dyn Iterator<Item = event::Event> + 'a>),
+    /// The documents in a range of the events of a stream that has been
+    /// loaded by iterating over a deserializer.
+    Loaded(Rc<Loaded>, Range<usize>),
+    /// A stream that failed to load while iterating over a deserializer.
+    Fail(Error),
+}
+
+/// A stream whose events have been loaded, along with its text if it had
+/// any, for the lines of errors.
+struct Loaded {
+    text: Option<String>,
+    loader: Loader,
+}
+
+impl Loaded {
+    /// Adds the location of errors the way `Deserializer::load` does.
+    fn locate<T>(&self, options: &Options, result: Result<T>) -> Result<T> {
+        match (self.text.as_ref(), options.error_lines) {
+            (None, _) => result.map_err(Error::without_location),
+            (Some(text), Some(max_len)) => result.map_err(|err| err.with_line(text, max_len)),
+            (Some(_), None) => result,
+        }
+    }
+}
+
+impl<'a> Deserializer<'a> {
+    /// Creates a YAML deserializer from a `&str`.
+    pub fn from_str(s: &'a str) -> Self {
+        Deserializer::new(Input::Str(s))
+    }
+
+    /// Creates a YAML deserializer from a `&[u8]`.
+    pub fn from_slice(v: &'a [u8]) -> Self {
+        Deserializer::new(Input::Slice(v))
+    }
+
+    /// Creates a YAML deserializer from an `io::Read`. The whole input is
+    /// read before deserialization begins.
+    pub fn from_reader<R>(rdr: R) -> Self
+        where R: io::Read + 'a
+    {
+        Deserializer::new(Input::Read(Box::new(rdr)))
+    }
+
+    /// Creates a deserializer that reads the events of one document instead
+    /// of YAML text. See the `event` module.
+    ///
+    /// The events go through the same handling as those of parsed text,
+    /// except that the `tabs` option has no effect. Errors have no location,
+    /// as events do not say where they came from, but they do have a path.
+    pub fn from_events<I>(events: I) -> Self
+        where I: IntoIterator<Item = event::Event>,
+              I::IntoIter: 'a
+    {
+        Deserializer::new(Input::Events(Box::new(events.into_iter())))
+    }
+
+    fn new(input: Input<'a>) -> Self {
+        Deserializer {
+            input: input,
+            options: Options::default(),
+        }
+    }
+
+    /// Set how tagged scalars are deserialized. See `TagPolicy`.
+    #[cfg(feature = "tags")]
+    pub fn tag_policy(mut self, tags: TagPolicy) -> Self {
+        self.options.tags = tags;
+        self
+    }
+
+    /// Whether only the YAML spellings of infinity and NaN are read as
+    /// floats: `.inf`, `-.inf` and `.nan` in lowercase, capitalized or
+    /// uppercase, with an optional `+` before `.inf`. Off by default, in which
+    /// case untagged scalars like `inf`, `-Infinity` and `NaN` are read as
+    /// floats too. With strict floats they are strings.
+    ///
+    /// ```rust
+    /// # extern crate serde;
+    /// # extern crate serde_yaml;
+    /// #
+    /// # fn main() {
+    /// use serde::Deserialize;
+    /// use serde_yaml::{Deserializer, Value};
+    ///
+    /// let yaml = "[.Inf, Infinity]";
+    /// let value = Value::deserialize(Deserializer::from_str(yaml)).unwrap();
+    /// assert!(value[1].is_f64());
+    /// let value = Value::deserialize(Deserializer::from_str(yaml).strict_floats(true)).unwrap();
+    /// assert_eq!(value[0].as_f64(), Some(std::f64::INFINITY));
+    /// assert_eq!(value[1].as_str(), Some("Infinity"));
+    /// # }
+    /// ```
+    pub fn strict_floats(mut self, strict: bool) -> Self {
+        self.options.strict_floats = strict;
+        self
+    }
+
+    /// Which spellings of numbers untagged plain scalars are read as numbers
+    /// in, such as `1_000_000` or `1e5`. See `NumberDialect`. Scalars that
+    /// the dialect reads as strings are strings to every type.
+    pub fn numbers(mut self, dialect: NumberDialect) -> Self {
+        self.options.numbers = dialect;
+        self
+    }
+
+    /// Whether floats with nothing but zeros after the point, like `3.0` or
+    /// `-12.00`, deserialize into integer types as the integer they are
+    /// equal to, for input from emitters that write every number of a float
+    /// type that way. Off by default, in which case they are floats to every
+    /// type. Other floats, like `3.5` or `3e0`, are floats either way.
+    ///
+    /// ```rust
+    /// # #[macro_use] extern crate serde_derive;
+    /// # extern crate serde;
+    /// # extern crate serde_yaml;
+    /// #
+    /// # fn main() {
+    /// use serde::Deserialize;
+    /// use serde_yaml::{Deserializer, Value};
+    ///
+    /// #[derive(Deserialize)]
+    /// struct Pool {
+    ///     size: u32,
+    ///     ratio: f64,
+    /// }
+    ///
+    /// let yaml = "{size: 8.0, ratio: 2.0}";
+    /// assert!(Pool::deserialize(Deserializer::from_str(yaml)).is_err());
+    /// let pool = Pool::deserialize(Deserializer::from_str(yaml).whole_floats(true)).unwrap();
+    /// assert_eq!(pool.size, 8);
+    /// assert_eq!(pool.ratio, 2.0);
+    ///
+    /// let value = Value::deserialize(Deserializer::from_str(yaml).whole_floats(true)).unwrap();
+    /// assert!(value["size"].is_f64());
+    /// # }
+    /// ```
+    pub fn whole_floats(mut self, whole: bool) -> Self {
+        self.options.whole_floats = whole;
+        self
+    }
+
+    /// Set what happens to mappings with the same key more than once. See
+    /// `DuplicateKeys`.
+    ///
+    /// ```rust
+    /// # extern crate serde;
+    /// # extern crate serde_yaml;
+    /// #
+    /// # fn main() {
+    /// use serde::Deserialize;
+    /// use serde_yaml::{Deserializer, DuplicateKeys, Mapping};
+    ///
+    /// let yaml = "a: 1\nb: 2\na: 3";
+    /// let de = Deserializer::from_str(yaml).duplicate_keys(DuplicateKeys::Error);
+    /// let err = Mapping::deserialize(de).unwrap_err();
+    /// assert_eq!(err.to_string(), "duplicate key `a` at line 3 column 1");
+    ///
+    /// let de = Deserializer::from_str(yaml).duplicate_keys(DuplicateKeys::First);
+    /// let mapping = Mapping::deserialize(de).unwrap();
+    /// assert_eq!(serde_yaml::to_string(&mapping).unwrap(), "---\na: 1\nb: 2");
+    /// # }
+    /// ```
+    pub fn duplicate_keys(mut self, policy: DuplicateKeys) -> Self {
+        self.options.duplicate_keys = policy;
+        self
+    }
+
+    /// Set what untagged dates like `2024-05-01` are read as. See `Dates`.
+    pub fn dates(mut self, dates: Dates) -> Self {
+        self.options.dates = dates;
+        self
+    }
+
+    /// Set what untagged YAML 1.1 base 60 numbers like `12:30:00` are read
+    /// as. See `Times`.
+    pub fn times(mut self, times: Times) -> Self {
+        self.options.times = times;
+        self
+    }
+
+    /// Set what happens to `<<` merge keys. See `MergeKeys`.
+    pub fn merge_keys(mut self, policy: MergeKeys) -> Self {
+        self.options.merge_keys = policy;
+        self
+    }
+
+    /// Set what happens to tabs in indentation. See `Tabs`.
+    pub fn tabs(mut self, tabs: Tabs) -> Self {
+        self.options.tabs = tabs;
+        self
+    }
+
+    /// Set what happens to anchors that are defined again and to aliases to
+    /// undefined anchors. See `AnchorPolicy`.
+    pub fn anchor_policy(mut self, policy: AnchorPolicy) -> Self {
+        self.options.anchors = policy;
+        self
+    }
+
+    /// Set which anchors an alias can refer to. See `AnchorScope`.
+    pub fn anchor_scope(mut self, scope: AnchorScope) -> Self {
+        self.options.anchor_scope = scope;
+        self
+    }
+
+    /// Count what the deserializer does into `profile`. See `Profile`.
+    pub fn profile(mut self, profile: &Profile) -> Self {
+        self.options.profile = Some(profile.clone());
+        self
+    }
+
+    /// Collect a warning into `deprecations` for every deprecated key that
+    /// is deserialized. See `Deprecations`.
+    pub fn deprecations(mut self, deprecations: &Deprecations) -> Self {
+        if !deprecations.is_empty() {
+            self.options.deprecations = Some(deprecations.clone());
+        }
+        self
+    }
+
+    /// Put the line of the input that an error is on into the error, cut to
+    /// about `max_len` characters around where the error is, so that logs of
+    /// a service that does not keep its input still show what was wrong with
+    /// it. Errors without a location are left as they are. The line is part
+    /// of the message and is also returned by `Error::line`.
+    ///
+    /// ```rust
+    /// # extern crate serde;
+    /// # extern crate serde_yaml;
+    /// #
+    /// # use std::collections::BTreeMap;
+    /// #
+    /// # fn main() {
+    /// use serde::Deserialize;
+    /// use serde_yaml::Deserializer;
+    ///
+    /// let yaml = "http: 80\nhttps: eighty\n";
+    /// let de = Deserializer::from_str(yaml).error_lines(80);
+    /// let err = BTreeMap::<String, u16>::deserialize(de).unwrap_err();
+    /// assert_eq!(err.line(), Some("https: eighty"));
+    /// assert_eq!(err.to_string(),
+    ///            "https: invalid type: string \"eighty\", expected u16 at line 2 column 8, in `https: eighty`");
+    /// # }
+    /// ```
+    pub fn error_lines(mut self, max_len: usize) -> Self {
+        self.options.error_lines = Some(max_len);
+        self
+    }
+
+    /// Deserializes the document into a `Value` the same way as
+    /// `Value::deserialize`, but keeping the collections that are being
+    /// built on a stack of its own instead of recursing into them, so that
+    /// no depth of nesting can overflow the call stack. See the `deep`
+    /// module.
+    ///
+    /// The options of the deserializer apply as usual. Errors have no path,
+    /// only a location.
+    #[cfg(feature = "value")]
+    pub fn into_value(self) -> Result<Value> {
+        self.de(true, |state| state.value_without_recursion())
+    }
+
+    /// Deserializes every document of the input, like `from_str_documents`,
+    /// with the options of this deserializer. `T` is a tuple with one
+    /// element per document or a sequence type like `Vec`.
+    ///
+    /// ```rust
+    /// # extern crate serde_yaml;
+    /// #
+    /// # fn main() {
+    /// use serde_yaml::{Deserializer, MergeKeys, Value};
+    ///
+    /// let yaml = "base: &base {port: 80}\n---\n<<: {port: 8080}\nhost: web\n";
+    /// let de = Deserializer::from_str(yaml).merge_keys(MergeKeys::Expand);
+    /// let documents: Vec<Value> = de.documents().unwrap();
+    /// assert_eq!(documents[1]["port"], 8080);
+    /// # }
+    /// ```
+    pub fn documents<T>(self) -> Result<T>
+        where T: DeserializeOwned
+    {
+        self.run(|options, loader, range| {
+            let mut starts = Vec::new();
+            let mut pos = range.start;
+            // Every document is a single node.
+            while pos < range.end {
+                starts.push(pos);
+                loader.deserialize_at::<Ignore>(&mut pos)?;
+            }
+            let deserialize = || {
+                T::deserialize(Documents {
+                                   loader: loader,
+                                   starts: starts.into_iter(),
+                                   options: options,
+                               })
+            };
+            match options.profile {
+                Some(ref profile) => profile.time_deserialize(deserialize),
+                None => deserialize(),
+            }
+        })
+    }
+
+    /// Runs `f` on the document. Text with no document at all, only blank
+    /// lines, comments or directives, reads the same as an empty document
+    /// if `nullable`, and is an error otherwise.
+    fn de<T, F>(self, nullable: bool, f: F) -> Result<T>
+        where F: FnOnce(&mut DeserializerFromEvents) -> Result<T>
+    {
+        // Events are a single node, so an empty list is malformed rather
+        // than an empty document.
+        let nullable = match self.input {
+            Input::Events(_) => false,
+            _ => nullable,
+        };
+        self.run(|options, loader, range| de_loaded(options, loader, range, nullable, f))
+    }
+
+    /// Runs `f` with the options, the loaded events and the range of them
+    /// that this deserializer reads.
+    fn run<T, F>(self, f: F) -> Result<T>
+        where F: FnOnce(&Options, &Loader, Range<usize>) -> Result<T>
+    {
+        match self.input {
+            Input::Loaded(loaded, range) => {
+                let result = f(&self.options, &loaded.loader, range);
+                loaded.locate(&self.options, result)
+            }
+            input => {
+                let de = Deserializer {
+                    input: input,
+                    options: self.options,
+                };
+                de.load(|options, _, load| {
+                            let loader = load_events(options, load)?;
+                            f(options, &loader, 0..loader.events.len())
+                        })
+            }
+        }
+    }
+
+    /// Reads the input and runs `f` with the options, the text if there is
+    /// any and a function that loads its events, adding the line of the
+    /// input to errors if asked.
+    fn load<T, F>(self, f: F) -> Result<T>
+        where F: FnOnce(&Options, Option<&str>, Box<dyn FnOnce() -> Result<Loader> + '_>) -> Result<T>
+    {
+        let options = self.options;
+        let bytes;
+        let text = match self.input {
+            Input::Str(s) => s,
+            Input::Slice(v) => str::from_utf8(v).map_err(Error::str_utf8)?,
+            Input::Read(mut rdr) => {
+                let mut buffer = Vec::new();
+                rdr.read_to_end(&mut buffer).map_err(Error::io)?;
+                bytes = buffer;
+                str::from_utf8(&bytes).map_err(Error::str_utf8)?
+            }
+            Input::Events(events) => {
+                let load = Box::new(|| event::load(events, &options.anchors));
+                return f(&options, None, load).map_err(Error::without_location);
+            }
+            Input::Loaded(..) => unreachable!("loaded input is read by `run`"),
+            Input::Fail(err) => return Err(err),
+        };
+        let load = Box::new(|| Loader::load_with(&tabs::apply(text, options.tabs)?, &options.anchors, options.anchor_scope));
+        let result = f(&options, Some(text), load);
+        match options.error_lines {
+            Some(max_len) => result.map_err(|err| err.with_line(text, max_len)),
+            None => result,
+        }
+    }
+}
+
+/// Runs `f` on the document in `range` of the events of the loader, as
+/// `de`.
+fn de_loaded<T, F>(options: &Options, loader: &Loader, range: Range<usize>, nullable: bool, f: F) -> Result<T>
+    where F: FnOnce(&mut DeserializerFromEvents) -> Result<T>
+{
+    let empty;
+    let (events, start, end) = if range.start == range.end {
+        if !nullable {
+            return Err(Error::end_of_stream());
+        }
+        let null = Event::Scalar(ScalarString::from("~".to_owned()), TScalarStyle::Plain, None);
+        empty = [(null, Scanner::new("".chars()).mark())];
+        (&empty[..], 0, 1)
+    } else {
+        (&loader.events[..], range.start, range.end)
+    };
+    let mut pos = start;
+    let deserialize = || {
+        document(|| {
+                     f(&mut DeserializerFromEvents {
+                                events: events,
+                                aliases: &loader.aliases,
+                                pos: &mut pos,
+                                path: Path::Root,
+                                options: options,
+                            })
+                 })
+    };
+    let t = match options.profile {
+        Some(ref profile) => profile.time_deserialize(deserialize)?,
+        None => deserialize()?,
+    };
+    if pos == end {
+        Ok(t)
+    } else {
+        Err(Error::more_than_one_document())
+    }
+}
+
+/// Loads the events with `load`, timing it and counting the scalars into
+/// the profile if there is one, and expands merge keys if asked.
+fn load_events<L>(options: &Options, load: L) -> Result<Loader>
+    where L: FnOnce() -> Result<Loader>
+{
+    let loader = match options.profile {
+        Some(ref profile) => {
+            let loader = profile.time_parse(load)?;
+            for event in &loader.events {
+                if let Event::Scalar(ref v, _, _) = event.0 {
+                    profile.add_scalar(v.len());
+                }
+            }
+            loader
+        }
+        None => load()?,
+    };
+    merge::apply(loader, options.merge_keys)
+}
+
+/// A collection that `value_without_recursion` is building.
+#[cfg(feature = "value")]
+enum Partial<'a> {
+    /// A sequence, with the index of its start event.
+    Sequence(usize, Sequence),
+    /// A mapping, with the index of its start event and the key of the entry
+    /// whose value comes next.
+    Mapping(usize, Mapping, Option<PendingKey<'a>>),
+}
+
+#[cfg(feature = "value")]
+struct PendingKey<'a> {
+    key: Value,
+    /// The key as written, if it is a scalar.
+    text: Option<&'a str>,
+    /// Whether the entry is dropped because of `DuplicateKeys::First`.
+    skip: bool,
+}
+
+#[cfg(feature = "value")]
+impl<'a> DeserializerFromEvents<'a> {
+    fn value_without_recursion(&mut self) -> Result<Value> {
+        let mut open: Vec<Partial<'a>> = Vec::new();
+        // Aliases are copies of the node they refer to, which has always
+        // been built by the time it is referred to.
+        let anchored: HashSet<usize> = self.aliases.values().cloned().collect();
+        let mut built: BTreeMap<usize, Value> = BTreeMap::new();
+        loop {
+            let start = *self.pos;
+            let (event, marker) = self.peek()?;
+            let value = match *event {
+                Event::SequenceStart => {
+                    *self.pos += 1;
+                    open.push(Partial::Sequence(start, Sequence::new()));
+                    continue;
+                }
+                Event::MappingStart => {
+                    *self.pos += 1;
+                    open.push(Partial::Mapping(start, Mapping::new(), None));
+                    continue;
+                }
+                Event::SequenceEnd | Event::MappingEnd => {
+                    *self.pos += 1;
+                    let (start, value) = match open.pop() {
+                        Some(Partial::Sequence(start, seq)) => (start, Value::Sequence(seq)),
+                        Some(Partial::Mapping(start, mapping, _)) => (start, Value::Mapping(mapping)),
+                        None => panic!("unexpected end of collection"),
+                    };
+                    if anchored.contains(&start) {
+                        built.insert(start, value.clone());
+                    }
+                    value
+                }
+                Event::Alias(i) => {
+                    *self.pos += 1;
+                    if let Some(ref profile) = self.options.profile {
+                        profile.add_alias();
+                    }
+                    match self.aliases.get(&i).and_then(|found| built.get(found)) {
+                        Some(value) => value.clone(),
+                        None => {
+                            let err = de::Error::custom("alias refers to a node that contains it");
+                            return Err(Error::fix_marker(err, marker, Path::Root));
+                        }
+                    }
+                }
+                Event::Scalar(..) => {
+                    let value = Value::deserialize(&mut *self)?;
+                    if anchored.contains(&start) {
+                        built.insert(start, value.clone());
+                    }
+                    value
+                }
+            };
+            // Only the keys of mappings are checked for deprecation.
+            let path = match (&self.options.deprecations, open.last()) {
+                (&Some(_), Some(&Partial::Mapping(_, _, None))) => Some(path_of(&open)),
+                _ => None,
+            };
+            match open.last_mut() {
+                None => return Ok(value),
+                Some(&mut Partial::Sequence(_, ref mut seq)) => seq.push(value),
+                Some(&mut Partial::Mapping(_, ref mut mapping, ref mut pending)) => {
+                    match pending.take() {
+                        Some(PendingKey { skip: true, .. }) => {}
+                        Some(PendingKey { key, .. }) => {
+                            mapping.insert(key, value);
+                        }
+                        None => {
+                            let text = match *event {
+                                Event::Scalar(ref text, _, _) => Some(&**text),
+                                _ => None,
+                            };
+                            if let (Some(text), Some(path)) = (text, path) {
+                                let path = if path == "." {
+                                    text.to_owned()
+                                } else {
+                                    format!("{}.{}", path, text)
+                                };
+                                if let Some(ref deprecations) = self.options.deprecations {
+                                    deprecations.check(&path, Location::from_marker(&marker));
+                                }
+                            }
+                            let duplicate = mapping.contains_key(&value);
+                            let skip = match self.options.duplicate_keys {
+                                DuplicateKeys::Last => false,
+                                DuplicateKeys::First => duplicate,
+                                DuplicateKeys::Error if duplicate => {
+                                    let err = match text {
+                                        Some(text) => {
+                                            de::Error::custom(format_args!("duplicate key `{}`", text))
+                                        }
+                                        None => de::Error::custom("duplicate key"),
+                                    };
+                                    return Err(Error::fix_marker(err, marker, Path::Root));
+                                }
+                                DuplicateKeys::Error => false,
+                            };
+                            *pending = Some(PendingKey {
+                                                key: value,
+                                                text: text,
+                                                skip: skip,
+                                            });
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// The path of the next node to be added to the innermost open collection,
+/// written the way `Path` displays it.
+#[cfg(feature = "value")]
+fn path_of(open: &[Partial]) -> String {
+    let mut path = String::new();
+    for (i, partial) in open.iter().enumerate() {
+        // The innermost collection has no entry in progress.
+        let innermost = i + 1 == open.len();
+        match *partial {
+            Partial::Sequence(_, ref seq) if !innermost => {
+                if path.is_empty() {
+                    path.push('.');
+                }
+                path.push_str(&format!("[{}]", seq.len()));
+            }
+            Partial::Mapping(_, _, Some(ref pending)) if !innermost => {
+                if !path.is_empty() {
+                    path.push('.');
+                }
+                path.push_str(pending.text.unwrap_or("?"));
+            }
+            _ => {}
+        }
+    }
+    if path.is_empty() {
+        path.push('.');
+    }
+    path
+}
+
+/// Iterates over the documents of the input, which is loaded at the first
+/// call to `next`. Each item is a deserializer of its own, with the same
+/// options, so that every document can be deserialized into a different
+/// type. A stream that fails to load yields one deserializer that returns
+/// the error.
+///
+/// ```rust
+/// # #[macro_use] extern crate serde_derive;
+/// # extern crate serde;
+/// # extern crate serde_yaml;
+/// #
+/// # fn main() {
+/// use serde::Deserialize;
+/// use serde_yaml::Deserializer;
+///
+/// #[derive(Deserialize)]
+/// struct Header {
+///     version: u32,
+/// }
+///
+/// let yaml = "version: 2\n---\n- a\n- b\n";
+/// let mut documents = Deserializer::from_str(yaml);
+/// let header = Header::deserialize(documents.next().unwrap()).unwrap();
+/// let items = Vec::<String>::deserialize(documents.next().unwrap()).unwrap();
+/// assert_eq!(header.version, 2);
+/// assert_eq!(items, ["a", "b"]);
+/// assert!(documents.next().is_none());
+/// # }
+/// ```
+impl<'a> Iterator for Deserializer<'a> {
+    type Item = Deserializer<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (loaded, range) = match mem::replace(&mut self.input, Input::Str("")) {
+            Input::Loaded(loaded, range) => (loaded, range),
+            input => {
+                let de = Deserializer {
+                    input: input,
+                    options: self.options.clone(),
+                };
+                let result = de.load(|options, text, load| {
+                                         Ok(Loaded {
+                                                text: text.map(str::to_owned),
+                                                loader: load_events(options, load)?,
+                                            })
+                                     });
+                match result {
+                    Ok(loaded) => {
+                        let len = loaded.loader.events.len();
+                        (Rc::new(loaded), 0..len)
+                    }
+                    Err(err) => return Some(self.fail(err)),
+                }
+            }
+        };
+        if range.start == range.end {
+            self.input = Input::Loaded(loaded, range);
+            return None;
+        }
+        // Every document is a single node.
+        let mut end = range.start;
+        let result = loaded.loader.deserialize_at::<Ignore>(&mut end);
+        if let Err(err) = loaded.locate(&self.options, result) {
+            return Some(self.fail(err));
+        }
+        self.input = Input::Loaded(loaded.clone(), end..range.end);
+        Some(Deserializer {
+                 input: Input::Loaded(loaded, range.start..end),
+                 options: self.options.clone(),
+             })
+    }
+}
+
+impl<'a> Deserializer<'a> {
+    /// Ends the iteration, returning a deserializer that fails with `err`.
+    fn fail(&mut self, err: Error) -> Self {
+        let loaded = Loaded {
+            text: None,
+            loader: Loader {
+                events: Vec::new(),
+                aliases: BTreeMap::new(),
+            },
+        };
+        self.input = Input::Loaded(Rc::new(loaded), 0..0);
+        Deserializer {
+            input: Input::Fail(err),
+            options: self.options.clone(),
+        }
+    }
+}
+
+/// Parses the input of a `Deserializer`.
+macro_rules! deserialize_from_events {
+    ($nullable:expr => $($method:ident ( $($arg:ident : $ty:ty),* ))*) => {
+        $(
+            fn $method<V>(self, $($arg: $ty,)* visitor: V) -> Result<V::Value>
+                where V: de::Visitor<'de>
+            {
+                self.de($nullable, |state| de::Deserializer::$method(state, $($arg,)* visitor))
+            }
+        )*
+    };
+}
+
+impl<'de, 'a> de::Deserializer<'de> for Deserializer<'a> {
+    type Error = Error;
+
+    // An input with no document is null to these, so that `Value` and
+    // `Option` can read an empty file, but not a string "~" to `String`.
+    deserialize_from_events! {
+        true =>
+        deserialize_any()
+        deserialize_option()
+        deserialize_unit()
+        deserialize_unit_struct(name: &'static str)
+        deserialize_ignored_any()
+    }
+
+    // `Value` reads itself through a newtype struct of this name.
+    deserialize_from_events! {
+        name == number::TOKEN =>
+        deserialize_newtype_struct(name: &'static str)
+    }
+
+    deserialize_from_events! {
+        false =>
+        deserialize_bool()
+        deserialize_i8()
+        deserialize_i16()
+        deserialize_i32()
+        deserialize_i64()
+        deserialize_u8()
+        deserialize_u16()
+        deserialize_u32()
+        deserialize_u64()
+        deserialize_i128()
+        deserialize_u128()
+        deserialize_f32()
+        deserialize_f64()
+        deserialize_char()
+        deserialize_str()
+        deserialize_string()
+        deserialize_bytes()
+        deserialize_byte_buf()
+        deserialize_seq()
+        deserialize_tuple(len: usize)
+        deserialize_tuple_struct(name: &'static str, len: usize)
+        deserialize_map()
+        deserialize_struct(name: &'static str, fields: &'static [&'static str])
+        deserialize_enum(name: &'static str, variants: &'static [&'static str])
+        deserialize_identifier()
     }
 }
 
@@ -630,32 +2448,217 @@ impl<'de, 'a, 'r> de::Deserializer<'de> for &'r mut Deserializer<'a> {
 /// the YAML map or some number is too big to fit in the expected primitive
 /// type.
 ///
+/// The input must hold exactly one document. Anything after it other than
+/// whitespace, comments and a `...` marker is an error, whether it is a second
+/// document or text that does not parse; see `from_str_partial` to read the
+/// first document of a longer input. Input with no document at all, only
+/// blank lines and comments, reads like an empty document: as null to
+/// `Value`, `Option` and `()`, and as an end of input error to types that
+/// cannot be null.
+///
 /// YAML currently does not support zero-copy deserialization.
 pub fn from_str<T>(s: &str) -> Result<T>
     where T: DeserializeOwned
 {
-    let mut parser = Parser::new(s.chars());
-    let mut loader = Loader {
-        events: Vec::new(),
-        aliases: BTreeMap::new(),
-    };
-    parser.load(&mut loader, true).map_err(Error::scanner)?;
-    if loader.events.is_empty() {
-        Err(Error::end_of_stream())
-    } else {
-        let mut pos = 0;
-        let t = Deserialize::deserialize(&mut Deserializer {
-                                                  events: &loader.events,
-                                                  aliases: &loader.aliases,
-                                                  pos: &mut pos,
-                                                  path: Path::Root,
-                                              })?;
-        if pos == loader.events.len() {
-            Ok(t)
-        } else {
-            Err(Error::more_than_one_document())
+    T::deserialize(Deserializer::from_str(s))
+}
+
+/// Deserialize an instance of type `T` from a fragment of YAML text cut out
+/// of a larger document, such as the lines under a key of a template.
+///
+/// The indentation that the lines of the fragment have in common is taken
+/// off before parsing, so the fragment does not have to start at the left
+/// margin. Blank lines and comments do not count towards it. Line numbers
+/// in errors are those of the fragment, but columns are counted from the
+/// shared indentation.
+///
+/// ```rust
+/// # extern crate serde_yaml;
+/// #
+/// # fn main() {
+/// use serde_yaml::Value;
+///
+/// let template = "spec:\n  ports:\n    - 80\n    - 443\n";
+/// let fragment = &template[template.find("    -").unwrap()..];
+/// let ports: Vec<u16> = serde_yaml::from_fragment(fragment).unwrap();
+/// assert_eq!(ports, [80, 443]);
+///
+/// let value: Value = serde_yaml::from_fragment("    name: web\n  # note\n    replicas: 2").unwrap();
+/// assert_eq!(value["replicas"], 2);
+/// # }
+/// ```
+pub fn from_fragment<T>(s: &str) -> Result<T>
+    where T: DeserializeOwned
+{
+    from_str(&dedent(s))
+}
+
+/// The text with the indentation its lines have in common taken off. Lines
+/// with less, which can only be blank or comments, lose all of theirs.
+fn dedent(s: &str) -> Cow<str> {
+    let indent = |line: &str| line.len() - line.trim_start_matches(' ').len();
+    let common = s.split('\n')
+        .filter(|line| {
+            let content = line.trim();
+            !content.is_empty() && !content.starts_with('#')
+        })
+        .map(indent)
+        .min()
+        .unwrap_or(0);
+    if common == 0 {
+        return Cow::Borrowed(s);
+    }
+    let lines: Vec<&str> = s.split('\n').map(|line| &line[indent(line).min(common)..]).collect();
+    Cow::Owned(lines.join("\n"))
+}
+
+/// Deserialize an instance of type `T` from the first document of a string of
+/// YAML text, and return it along with the byte offset at which the rest of
+/// the text begins.
+///
+/// Unlike `from_str`, the text after the first document is not parsed, so it
+/// may hold more documents or anything else. The offset is just past the
+/// document's `...` marker if it ends with one, otherwise at the `---` that
+/// starts the next document or at the end of the text. Text with no document
+/// at all reads as it does for `from_str`, and the offset is its length.
+///
+/// ```rust
+/// # extern crate serde_yaml;
+/// #
+/// # fn main() {
+/// let input = "a: 1\n--- b\n--- [unclosed";
+/// let (first, offset): (serde_yaml::Value, usize) = serde_yaml::from_str_partial(input).unwrap();
+/// assert_eq!(first["a"], 1);
+/// assert_eq!(&input[offset..], "--- b\n--- [unclosed");
+///
+/// let (second, offset): (String, usize) = serde_yaml::from_str_partial(&input[5..]).unwrap();
+/// assert_eq!(second, "b");
+/// assert_eq!(offset, 6);
+/// # }
+/// ```
+pub fn from_str_partial<T>(s: &str) -> Result<(T, usize)>
+    where T: DeserializeOwned
+{
+    let (loader, offset) = Loader::load_first(s)?;
+    first_document(&loader).map(|value| (value, offset))
+}
+
+/// Deserialize each document of a string of YAML text into the matching
+/// element of a tuple, for formats that put different things in different
+/// documents, like a header followed by a body.
+///
+/// The text must hold exactly as many documents as the tuple has elements;
+/// otherwise the error says how many it expected and how many it found.
+/// Other sequence types like `Vec<T>` take any number of documents of the
+/// same type. `Deserializer::documents` does the same with options.
+///
+/// ```rust
+/// # extern crate serde_yaml;
+/// #
+/// # use std::collections::BTreeMap;
+/// #
+/// # fn main() {
+/// let input = "version: 2\n---\n- a\n- b\n";
+/// let (meta, items): (BTreeMap<String, u32>, Vec<String>) = serde_yaml::from_str_documents(input).unwrap();
+/// assert_eq!(meta["version"], 2);
+/// assert_eq!(items, ["a", "b"]);
+///
+/// let err = serde_yaml::from_str_documents::<(u32, u32, u32)>("1\n--- 2\n").unwrap_err();
+/// assert_eq!(err.to_string(), "expected 3 documents, found 2");
+/// # }
+/// ```
+pub fn from_str_documents<T>(s: &str) -> Result<T>
+    where T: DeserializeOwned
+{
+    Deserializer::from_str(s).documents()
+}
+
+/// Deserialize every document of a string of YAML text into a `T`, all at
+/// once. Text with no documents gives an empty `Vec`.
+///
+/// This is `from_str_documents::<Vec<T>>`. See `Parser` to go through the
+/// documents one at a time instead.
+///
+/// ```rust
+/// # extern crate serde_yaml;
+/// #
+/// # fn main() {
+/// use serde_yaml::Value;
+///
+/// let documents: Vec<Value> = serde_yaml::from_str_all("name: a\n---\nname: b\n").unwrap();
+/// assert_eq!(documents.len(), 2);
+/// assert_eq!(documents[1]["name"], "b");
+/// # }
+/// ```
+pub fn from_str_all<T>(s: &str) -> Result<Vec<T>>
+    where T: DeserializeOwned
+{
+    from_str_documents(s)
+}
+
+/// The documents of a stream, read as a sequence.
+struct Documents<'a> {
+    loader: &'a Loader,
+    starts: ::std::vec::IntoIter<usize>,
+    options: &'a Options,
+}
+
+impl<'de, 'a> de::Deserializer<'de> for Documents<'a> {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
+        where V: de::Visitor<'de>
+    {
+        visitor.visit_seq(self)
+    }
+
+    fn deserialize_tuple<V>(self, len: usize, visitor: V) -> Result<V::Value>
+        where V: de::Visitor<'de>
+    {
+        if self.starts.len() != len {
+            return Err(de::Error::custom(format_args!("expected {} documents, found {}", len, self.starts.len())));
+        }
+        visitor.visit_seq(self)
+    }
+
+    fn deserialize_tuple_struct<V>(self, _name: &'static str, len: usize, visitor: V) -> Result<V::Value>
+        where V: de::Visitor<'de>
+    {
+        self.deserialize_tuple(len, visitor)
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq map struct
+        enum identifier ignored_any
+    }
+}
+
+impl<'de, 'a> de::SeqAccess<'de> for Documents<'a> {
+    type Error = Error;
+
+    fn next_element_seed<S>(&mut self, seed: S) -> Result<Option<S::Value>>
+        where S: DeserializeSeed<'de>
+    {
+        match self.starts.next() {
+            Some(mut pos) => self.loader.deserialize_seed_with(&mut pos, seed, self.options).map(Some),
+            None => Ok(None),
         }
     }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.starts.len())
+    }
+}
+
+fn first_document<T>(loader: &Loader) -> Result<T>
+    where T: DeserializeOwned
+{
+    if loader.events.is_empty() {
+        // No document at all reads the way it does for `from_str`.
+        return from_str("");
+    }
+    loader.deserialize_at(&mut 0)
 }
 
 /// Deserialize an instance of type `T` from an IO stream of YAML.
@@ -667,14 +2670,11 @@ pub fn from_str<T>(s: &str) -> Result<T>
 /// is wrong with the data, for example required struct fields are missing from
 /// the YAML map or some number is too big to fit in the expected primitive
 /// type.
-pub fn from_reader<R, T>(mut rdr: R) -> Result<T>
+pub fn from_reader<R, T>(rdr: R) -> Result<T>
     where R: io::Read,
           T: DeserializeOwned
 {
-    let mut bytes = Vec::new();
-    rdr.read_to_end(&mut bytes).map_err(Error::io)?;
-    let s = str::from_utf8(&bytes).map_err(Error::str_utf8)?;
-    from_str(s)
+    T::deserialize(Deserializer::from_reader(rdr))
 }
 
 /// Deserialize an instance of type `T` from bytes of YAML text.
@@ -691,6 +2691,50 @@ pub fn from_reader<R, T>(mut rdr: R) -> Result<T>
 pub fn from_slice<T>(v: &[u8]) -> Result<T>
     where T: DeserializeOwned
 {
-    let s = str::from_utf8(v).map_err(Error::str_utf8)?;
-    from_str(s)
+    T::deserialize(Deserializer::from_slice(v))
+}
+
+/// Deserialize an instance of type `T` from the first document in bytes of
+/// YAML text, and return it along with the number of bytes it took up.
+///
+/// This is for buffers that hold documents back to back, like the frames of
+/// a protocol, and works like `from_str_partial`. The bytes after the first
+/// document need not be UTF-8. If the document itself runs into bytes that
+/// are not UTF-8, that is an error.
+///
+/// ```rust
+/// # extern crate serde_yaml;
+/// #
+/// # fn main() {
+/// let mut buffer = b"id: 1\n...\nid: 2\n...\n".to_vec();
+/// buffer.extend_from_slice(&[0xff, 0xfe]);
+///
+/// let mut ids = Vec::new();
+/// let mut rest = &buffer[..];
+/// while rest.starts_with(b"id") {
+///     let (frame, consumed): (serde_yaml::Value, usize) = serde_yaml::from_slice_partial(rest).unwrap();
+///     ids.push(frame["id"].as_u64().unwrap());
+///     // Skip the line break after `...`.
+///     rest = &rest[consumed + 1..];
+/// }
+/// assert_eq!(ids, [1, 2]);
+/// assert_eq!(rest, [0xff, 0xfe]);
+/// # }
+/// ```
+pub fn from_slice_partial<T>(v: &[u8]) -> Result<(T, usize)>
+    where T: DeserializeOwned
+{
+    let (loader, consumed) = match str::from_utf8(v) {
+        Ok(s) => Loader::load_first(s)?,
+        Err(err) => {
+            let valid = str::from_utf8(&v[..err.valid_up_to()]).expect("valid prefix");
+            // A document that reaches the invalid bytes, or fails to parse
+            // because it stops short at them, might have gone on past them.
+            match Loader::load_first(valid) {
+                Ok((loader, consumed)) if consumed < valid.len() => (loader, consumed),
+                _ => return Err(Error::str_utf8(err)),
+            }
+        }
+    };
+    first_document(&loader).map(|value| (value, consumed))
 }
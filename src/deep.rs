@@ -0,0 +1,182 @@
+// Copyright 2018 Serde YAML Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Reading, writing and dropping a `Value` without recursion, for documents
+//! that nest too deeply for the call stack, like syntax trees dumped as
+//! YAML.
+//!
+//! `Value::deserialize`, `to_string` and the drop glue of `Value` handle
+//! every level of nesting with a level of recursion, which overflows the
+//! stack of a thread somewhere in the thousands of levels. The functions in
+//! this module produce the same results while keeping the nodes they are
+//! working on in a `Vec` instead. `Limits::max_depth` is the way to reject
+//! such documents instead.
+//!
+//! ```rust
+//! use serde_yaml::deep;
+//!
+//! let yaml = format!("---\n{}{}", "- ".repeat(10000), "leaf");
+//! let value = deep::from_str(&yaml).unwrap();
+//! assert_eq!(deep::to_string(&value).unwrap(), yaml);
+//! deep::dispose(value);
+//! ```
+//!
+//! Nodes that an alias refers to are copied with `Value::clone`, which does
+//! recurse. The parser of YAML text reads at most 255 levels of flow
+//! collections, like `[[1]]`, so deep documents are written in block style.
+
+use std::fmt::{self, Write};
+use std::slice;
+
+use yaml_rust::emitter::EmitError;
+
+use de::Deserializer;
+use emitter;
+use error::{Error, Result};
+use mapping;
+use value::Value;
+
+/// Deserializes a `Value` from YAML text without recursion. The same as
+/// `Deserializer::from_str(s).into_value()`.
+pub fn from_str(s: &str) -> Result<Value> {
+    Deserializer::from_str(s).into_value()
+}
+
+/// Serializes a `Value` to YAML text without recursion. The output is the
+/// same as that of `serde_yaml::to_string`.
+pub fn to_string(value: &Value) -> Result<String> {
+    let mut string = String::with_capacity(value.estimated_yaml_len());
+    write(&mut string, value).map_err(|err| Error::emitter(EmitError::FmtError(err)))?;
+    Ok(string)
+}
+
+/// Drops a `Value` without recursion.
+pub fn dispose(value: Value) {
+    let mut pending = vec![value];
+    while let Some(value) = pending.pop() {
+        match value {
+            Value::Sequence(seq) => pending.extend(seq),
+            Value::Mapping(mapping) => {
+                for (k, v) in mapping {
+                    pending.push(k);
+                    pending.push(v);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// What is left to write, innermost last. This follows the emitter step for
+/// step, with collections written an element at a time.
+enum Task<'a> {
+    /// A node at the start of a line or after a key.
+    Node(&'a Value),
+    /// A node after `-`, `?` or `:`, which is inline if it is the element of
+    /// a sequence or the key of a complex entry.
+    Val(bool, &'a Value),
+    Str(&'static str),
+    /// A line break, followed by the indentation of the current level.
+    Indent,
+    Enter,
+    Leave,
+    Elements(slice::Iter<'a, Value>, bool),
+    Entries(mapping::Iter<'a>, bool),
+}
+
+fn write(out: &mut String, value: &Value) -> fmt::Result {
+    out.push_str("---\n");
+    let mut level: isize = -1;
+    let mut tasks = vec![Task::Node(value)];
+    while let Some(task) = tasks.pop() {
+        match task {
+            Task::Node(&Value::Sequence(ref seq)) if seq.is_empty() => out.push_str("[]"),
+            Task::Node(&Value::Mapping(ref mapping)) if mapping.is_empty() => out.push_str("{}"),
+            Task::Node(&Value::Sequence(ref seq)) => {
+                tasks.push(Task::Leave);
+                tasks.push(Task::Elements(seq.iter(), true));
+                tasks.push(Task::Enter);
+            }
+            Task::Node(&Value::Mapping(ref mapping)) => {
+                tasks.push(Task::Leave);
+                tasks.push(Task::Entries(mapping.iter(), true));
+                tasks.push(Task::Enter);
+            }
+            Task::Node(scalar) => write_scalar(out, scalar)?,
+            Task::Val(inline, value) => {
+                let nested = match *value {
+                    Value::Sequence(ref seq) => !inline && !seq.is_empty(),
+                    Value::Mapping(ref mapping) => !inline && !mapping.is_empty(),
+                    _ => false,
+                };
+                tasks.push(Task::Node(value));
+                if nested {
+                    // The indentation of the level the node is written at.
+                    tasks.push(Task::Leave);
+                    tasks.push(Task::Indent);
+                    tasks.push(Task::Enter);
+                } else {
+                    out.push(' ');
+                }
+            }
+            Task::Str(s) => out.push_str(s),
+            Task::Indent => {
+                out.push('\n');
+                for _ in 0..level.max(0) {
+                    out.push_str("  ");
+                }
+            }
+            Task::Enter => level += 1,
+            Task::Leave => level -= 1,
+            Task::Elements(mut iter, first) => {
+                if let Some(element) = iter.next() {
+                    tasks.push(Task::Elements(iter, false));
+                    tasks.push(Task::Val(true, element));
+                    tasks.push(Task::Str("-"));
+                    if !first {
+                        tasks.push(Task::Indent);
+                    }
+                }
+            }
+            Task::Entries(mut iter, first) => {
+                if let Some((key, value)) = iter.next() {
+                    tasks.push(Task::Entries(iter, false));
+                    match *key {
+                        Value::Sequence(_) | Value::Mapping(_) => {
+                            tasks.push(Task::Val(true, value));
+                            tasks.push(Task::Str(":"));
+                            tasks.push(Task::Indent);
+                            tasks.push(Task::Val(true, key));
+                            tasks.push(Task::Str("?"));
+                        }
+                        _ => {
+                            tasks.push(Task::Val(false, value));
+                            tasks.push(Task::Str(":"));
+                            tasks.push(Task::Node(key));
+                        }
+                    }
+                    if !first {
+                        tasks.push(Task::Indent);
+                    }
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn write_scalar(out: &mut String, scalar: &Value) -> fmt::Result {
+    match *scalar {
+        Value::Null => out.push('~'),
+        Value::Bool(b) => out.push_str(if b { "true" } else { "false" }),
+        Value::Number(ref n) => write!(out, "{}", n)?,
+        Value::String(ref s) => emitter::write_string(out, s)?,
+        Value::Sequence(_) | Value::Mapping(_) => unreachable!(),
+    }
+    Ok(())
+}
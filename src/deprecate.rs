@@ -0,0 +1,153 @@
+// Copyright 2018 Serde YAML Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::fmt::{self, Display};
+use std::sync::{Arc, Mutex};
+
+use error::Location;
+
+/// Keys that an application no longer wants to see in its configuration,
+/// and the warnings produced when a `Deserializer` comes across them.
+///
+/// Patterns are paths written the same way as in error messages, like
+/// `server.port` or `servers[0].host`, in which `*` stands for any one key
+/// or index: `servers[*].host` or `*.port`. A key is reported whenever the
+/// type being deserialized looks at it, which includes keys that a struct
+/// ignores because it no longer has a field for them.
+///
+/// Like `Profile`, this is a handle: clones of it share the same warnings,
+/// which keep adding up over every deserializer that is given one.
+///
+/// ```rust
+/// #[macro_use]
+/// extern crate serde_derive;
+/// extern crate serde;
+/// extern crate serde_yaml;
+///
+/// use serde::Deserialize;
+/// use serde_yaml::{Deprecations, Deserializer};
+///
+/// #[derive(Deserialize)]
+/// struct Config {
+///     servers: Vec<Server>,
+/// }
+///
+/// #[derive(Deserialize)]
+/// struct Server {
+///     listen: Option<String>,
+/// }
+///
+/// # fn main() {
+/// let deprecations = Deprecations::new().deprecate("servers[*].port", "use `listen` instead");
+/// let yaml = "servers:\n- listen: ':80'\n- port: 8080\n";
+/// let de = Deserializer::from_str(yaml).deprecations(&deprecations);
+/// let config = Config::deserialize(de).unwrap();
+/// assert_eq!(config.servers.len(), 2);
+///
+/// let warnings = deprecations.warnings();
+/// assert_eq!(warnings.len(), 1);
+/// assert_eq!(warnings[0].path(), "servers[1].port");
+/// assert_eq!(warnings[0].location().line(), 3);
+/// assert_eq!(warnings[0].to_string(), "3:3: `servers[1].port` is deprecated: use `listen` instead");
+/// # }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Deprecations {
+    patterns: Arc<Vec<(String, String)>>,
+    warnings: Arc<Mutex<Vec<Warning>>>,
+}
+
+/// A deprecated key found by a `Deserializer`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Warning {
+    path: String,
+    location: Location,
+    message: String,
+}
+
+impl Deprecations {
+    /// Creates a set of deprecations with no patterns.
+    pub fn new() -> Self {
+        Deprecations::default()
+    }
+
+    /// Reports keys whose path matches `pattern`, with `message` explaining
+    /// what to do instead. When several patterns match a key, the first one
+    /// added wins.
+    pub fn deprecate(mut self, pattern: &str, message: &str) -> Self {
+        Arc::make_mut(&mut self.patterns).push((pattern.to_owned(), message.to_owned()));
+        self
+    }
+
+    /// The warnings found so far, in the order the keys were deserialized.
+    pub fn warnings(&self) -> Vec<Warning> {
+        self.warnings.lock().unwrap_or_else(|err| err.into_inner()).clone()
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.patterns.is_empty()
+    }
+
+    pub(crate) fn check(&self, path: &str, location: Location) {
+        let pattern = self.patterns.iter().find(|pattern| matches(&pattern.0, path));
+        let message = match pattern {
+            Some(&(_, ref message)) => message.clone(),
+            None => return,
+        };
+        let warning = Warning {
+            path: path.to_owned(),
+            location: location,
+            message: message,
+        };
+        self.warnings.lock().unwrap_or_else(|err| err.into_inner()).push(warning);
+    }
+}
+
+impl Warning {
+    /// The path of the deprecated key, like `servers[1].port`.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Where in the input the key is.
+    pub fn location(&self) -> Location {
+        self.location
+    }
+
+    /// The message given for the pattern that matched the key.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl Display for Warning {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter,
+               "{}:{}: `{}` is deprecated: {}",
+               self.location.line(),
+               self.location.column(),
+               self.path,
+               self.message)
+    }
+}
+
+/// Whether `path` matches `pattern`, in which `*` matches one key or index.
+pub(crate) fn matches(pattern: &str, path: &str) -> bool {
+    matches_bytes(pattern.as_bytes(), path.as_bytes())
+}
+
+fn matches_bytes(pattern: &[u8], path: &[u8]) -> bool {
+    match pattern.split_first() {
+        None => path.is_empty(),
+        Some((&b'*', rest)) => {
+            let end = path.iter().position(|&b| b == b'.' || b == b'[' || b == b']').unwrap_or(path.len());
+            (1..end + 1).any(|len| matches_bytes(rest, &path[len..]))
+        }
+        Some((&b, rest)) => path.first() == Some(&b) && matches_bytes(rest, &path[1..]),
+    }
+}
@@ -0,0 +1,948 @@
+// Copyright 2018 Serde YAML Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Writes a `Yaml` tree as block style text.
+//!
+//! This started as a copy of yaml-rust's `YamlEmitter` and produces the same
+//! output for the same tree. In addition it writes anchors and aliases for
+//! nodes that the serializer found to be shared, and the comments of
+//! `Commented` values, which yaml-rust's emitter has no way to do.
+
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::fmt;
+
+use yaml_rust::emitter::EmitError;
+use yaml_rust::yaml::{Hash, Yaml};
+
+use comment;
+use dates;
+use style::{self, Style};
+#[cfg(feature = "value")]
+use value::Value;
+
+pub type EmitResult = Result<(), EmitError>;
+
+/// Nodes that may occur more than once in a document. The tree refers to
+/// them as `Yaml::Alias(id)`, where `id` is an index into `nodes`.
+#[derive(Default)]
+pub struct Anchors {
+    pub nodes: Vec<Yaml>,
+    /// How many times each node is referred to.
+    pub refs: Vec<usize>,
+}
+
+/// How infinite and NaN floats are written. See `Writer::non_finite_floats`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum NonFiniteSpelling {
+    /// `.inf`, `-.inf` and `.nan`, as in the YAML spec. This is the default.
+    Lowercase,
+    /// `.Inf`, `-.Inf` and `.NaN`.
+    Capitalized,
+    /// `.INF`, `-.INF` and `.NAN`.
+    Uppercase,
+    /// `Infinity`, `-Infinity` and `NaN`, as in JavaScript. These are not
+    /// YAML floats; only lenient parsers, like this crate's own by default,
+    /// read them as numbers.
+    Words,
+}
+
+impl Default for NonFiniteSpelling {
+    fn default() -> Self {
+        NonFiniteSpelling::Lowercase
+    }
+}
+
+impl NonFiniteSpelling {
+    /// Respells a float written by the serializer, which uses `Lowercase`.
+    fn spell<'s>(self, real: &'s str) -> &'s str {
+        match (self, real) {
+            (NonFiniteSpelling::Capitalized, ".inf") => ".Inf",
+            (NonFiniteSpelling::Capitalized, "-.inf") => "-.Inf",
+            (NonFiniteSpelling::Capitalized, ".nan") => ".NaN",
+            (NonFiniteSpelling::Uppercase, ".inf") => ".INF",
+            (NonFiniteSpelling::Uppercase, "-.inf") => "-.INF",
+            (NonFiniteSpelling::Uppercase, ".nan") => ".NAN",
+            (NonFiniteSpelling::Words, ".inf") => "Infinity",
+            (NonFiniteSpelling::Words, "-.inf") => "-Infinity",
+            (NonFiniteSpelling::Words, ".nan") => "NaN",
+            _ => real,
+        }
+    }
+}
+
+/// How strings are written, for `Writer::string_styles`. The default writes
+/// every string plain if it can be and double-quoted otherwise, like
+/// `to_string` does.
+///
+/// ```rust
+/// use serde_yaml::StringStyles;
+///
+/// // What `smart` turns on, one by one.
+/// let styles = StringStyles::new()
+///     .single_quoted(true)
+///     .literal(true)
+///     .folded(Some(80));
+/// assert_eq!(styles, StringStyles::smart());
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Default)]
+pub struct StringStyles {
+    single_quoted: bool,
+    literal: bool,
+    fold_width: Option<usize>,
+}
+
+impl StringStyles {
+    /// Plain or double-quoted strings.
+    pub fn new() -> Self {
+        StringStyles::default()
+    }
+
+    /// The styles a person would likely pick: single quotes for strings that
+    /// need quotes but no escapes, literal block scalars for strings of
+    /// several lines, and folded block scalars for single lines longer than
+    /// 80 characters.
+    pub fn smart() -> Self {
+        StringStyles::new().single_quoted(true).literal(true).folded(Some(80))
+    }
+
+    /// Whether strings that need quotes are put in single quotes when they
+    /// hold no line breaks or other control characters, and no `'` that
+    /// double quotes would spare doubling. Those still get double quotes.
+    pub fn single_quoted(mut self, single: bool) -> Self {
+        self.single_quoted = single;
+        self
+    }
+
+    /// Whether strings of several lines are written as literal block
+    /// scalars. The same as `Writer::block_strings`.
+    pub fn literal(mut self, literal: bool) -> Self {
+        self.literal = literal;
+        self
+    }
+
+    /// Strings of one line longer than `width` characters are written as
+    /// folded block scalars, introduced by `>-`, with their words wrapped at
+    /// `width`. `None`, the default, folds nothing. Strings whose spaces
+    /// would not read back the same, like runs of spaces, are not folded.
+    pub fn folded(mut self, width: Option<usize>) -> Self {
+        self.fold_width = width;
+        self
+    }
+}
+
+/// Decides whether a blank line goes between two top-level keys. See
+/// `Writer::blank_lines`.
+pub type BlankLine = dyn Fn(&str, &str) -> bool + Send + Sync;
+
+/// Picks the name of the anchor of a shared node. See
+/// `Writer::anchor_names`.
+#[cfg(feature = "value")]
+pub type AnchorNamer = dyn Fn(&Value) -> Option<String> + Send + Sync;
+
+pub struct Emitter<'a> {
+    writer: &'a mut dyn fmt::Write,
+    level: isize,
+    anchors: &'a Anchors,
+    non_finite: NonFiniteSpelling,
+    /// Whether to group the digits of long integers with underscores.
+    underscores: bool,
+    /// Comments to write before the start of the document.
+    prologue: Vec<&'a str>,
+    /// Whether to put a blank line between two adjacent keys of the
+    /// document's top-level mapping.
+    blank_line: Option<&'a BlankLine>,
+    /// How to write strings.
+    strings: StringStyles,
+    /// Whether to quote strings by `need_quotes_stable` rather than by
+    /// `need_quotes`.
+    stable_quoting: bool,
+    #[cfg(feature = "value")]
+    anchor_namer: Option<&'a AnchorNamer>,
+    /// The anchor name of each shared node that has been written so far.
+    names: Vec<Option<String>>,
+    /// Every anchor name given out, to keep them unique.
+    used_names: HashSet<String>,
+    next_name: usize,
+    /// Whether the last thing written is a literal block scalar with clip
+    /// chomping, whose value ends with the line break after its last line.
+    clipped: bool,
+}
+
+impl<'a> Emitter<'a> {
+    pub fn new(writer: &'a mut dyn fmt::Write, anchors: &'a Anchors) -> Self {
+        Emitter {
+            writer: writer,
+            level: -1,
+            anchors: anchors,
+            non_finite: NonFiniteSpelling::default(),
+            underscores: false,
+            prologue: Vec::new(),
+            blank_line: None,
+            strings: StringStyles::default(),
+            stable_quoting: false,
+            #[cfg(feature = "value")]
+            anchor_namer: None,
+            names: vec![None; anchors.nodes.len()],
+            used_names: HashSet::new(),
+            next_name: 1,
+            clipped: false,
+        }
+    }
+
+    pub fn non_finite(mut self, spelling: NonFiniteSpelling) -> Self {
+        self.non_finite = spelling;
+        self
+    }
+
+    pub fn underscore_integers(mut self, underscores: bool) -> Self {
+        self.underscores = underscores;
+        self
+    }
+
+    /// Adds a comment to write before the start of the document.
+    pub fn prologue(mut self, comment: &'a str) -> Self {
+        self.prologue.push(comment);
+        self
+    }
+
+    #[cfg(feature = "value")]
+    pub fn anchor_names(mut self, namer: &'a AnchorNamer) -> Self {
+        self.anchor_namer = Some(namer);
+        self
+    }
+
+    pub fn blank_lines(mut self, between: &'a BlankLine) -> Self {
+        self.blank_line = Some(between);
+        self
+    }
+
+    pub fn string_styles(mut self, styles: StringStyles) -> Self {
+        self.strings = styles;
+        self
+    }
+
+    pub fn stable_quoting(mut self, stable: bool) -> Self {
+        self.stable_quoting = stable;
+        self
+    }
+
+    pub fn dump(&mut self, doc: &Yaml) -> EmitResult {
+        for comment in &self.prologue {
+            for line in comment.lines() {
+                write_comment_line(self.writer, line)?;
+                writeln!(self.writer)?;
+            }
+        }
+        // write DocumentStart
+        writeln!(self.writer, "---")?;
+        self.level = -1;
+        let doc = self.write_comments(doc)?;
+        self.emit_node(doc)?;
+        if self.clipped {
+            // Without it the block scalar would lose its final line break.
+            writeln!(self.writer)?;
+        }
+        Ok(())
+    }
+
+    /// Whether `dump` ended the document with a line break, as it does after
+    /// a literal block scalar whose value ends with one. Callers that put a
+    /// line break after each document leave theirs out then.
+    pub fn ends_with_line_break(&self) -> bool {
+        self.clipped
+    }
+
+    /// Writes the document without a document start marker or prologue, as
+    /// a fragment of a larger document.
+    pub fn dump_fragment(&mut self, doc: &Yaml) -> EmitResult {
+        self.level = -1;
+        let doc = self.write_comments(doc)?;
+        self.emit_node(doc)
+    }
+
+    /// Writes the document on a single line in flow style, without a
+    /// document start marker. Comments are left out.
+    pub fn dump_flow(&mut self, doc: &Yaml) -> EmitResult {
+        self.emit_flow(doc)
+    }
+
+    fn write_indent(&mut self) -> EmitResult {
+        if self.level <= 0 {
+            return Ok(());
+        }
+        for _ in 0..self.level {
+            self.writer.write_str("  ")?;
+        }
+        Ok(())
+    }
+
+    fn emit_node(&mut self, node: &Yaml) -> EmitResult {
+        self.clipped = false;
+        if let Some((_, inner)) = comment::split(node) {
+            if style::of(node) == Some(Style::Flow) {
+                return self.emit_flow(inner);
+            }
+            return self.emit_node(inner);
+        }
+        match *node {
+            Yaml::Array(ref v) => self.emit_array(v),
+            Yaml::Hash(ref h) => self.emit_hash(h),
+            Yaml::String(ref v) => {
+                let quoted = if self.stable_quoting { need_quotes_stable(v) } else { need_quotes(v) };
+                if quoted && self.strings.single_quoted && fits_single_quoted(v) {
+                    write!(self.writer, "'{}'", v.replace('\'', "''"))?;
+                } else if quoted {
+                    escape_str(self.writer, v)?;
+                } else {
+                    self.writer.write_str(v)?;
+                }
+                Ok(())
+            }
+            Yaml::Boolean(v) => {
+                self.writer.write_str(if v { "true" } else { "false" })?;
+                Ok(())
+            }
+            Yaml::Integer(v) if self.underscores => {
+                write_grouped(self.writer, &v.to_string())?;
+                Ok(())
+            }
+            Yaml::Integer(v) => {
+                write!(self.writer, "{}", v)?;
+                Ok(())
+            }
+            Yaml::Real(ref v) if self.underscores => {
+                write_grouped(self.writer, self.non_finite.spell(v))?;
+                Ok(())
+            }
+            Yaml::Real(ref v) => {
+                self.writer.write_str(self.non_finite.spell(v))?;
+                Ok(())
+            }
+            Yaml::Alias(id) => {
+                let node = self.shared(id);
+                if !self.anchored(id) {
+                    return self.emit_node(node);
+                }
+                match self.names[id] {
+                    Some(ref name) => write!(self.writer, "*{}", name)?,
+                    None => {
+                        self.write_anchor(id)?;
+                        match *node {
+                            Yaml::Array(ref v) if !v.is_empty() => {
+                                writeln!(self.writer)?;
+                                self.write_indent()?;
+                            }
+                            Yaml::Hash(ref h) if !h.is_empty() => {
+                                writeln!(self.writer)?;
+                                self.write_indent()?;
+                            }
+                            _ => self.writer.write_str(" ")?,
+                        }
+                        self.emit_node(node)?;
+                    }
+                }
+                Ok(())
+            }
+            Yaml::Null | Yaml::BadValue => {
+                self.writer.write_str("~")?;
+                Ok(())
+            }
+        }
+    }
+
+    fn emit_flow(&mut self, node: &Yaml) -> EmitResult {
+        if let Some((_, node)) = comment::split(node) {
+            return self.emit_flow(node);
+        }
+        match *node {
+            Yaml::Array(ref v) => {
+                self.writer.write_str("[")?;
+                for (cnt, x) in v.iter().enumerate() {
+                    if cnt > 0 {
+                        self.writer.write_str(", ")?;
+                    }
+                    self.emit_flow(x)?;
+                }
+                self.writer.write_str("]")?;
+                Ok(())
+            }
+            Yaml::Hash(ref h) => {
+                self.writer.write_str("{")?;
+                for (cnt, (k, v)) in h.iter().enumerate() {
+                    if cnt > 0 {
+                        self.writer.write_str(", ")?;
+                    }
+                    let alias = self.is_alias(k);
+                    self.emit_flow(k)?;
+                    self.writer.write_str(if alias { " : " } else { ": " })?;
+                    self.emit_flow(v)?;
+                }
+                self.writer.write_str("}")?;
+                Ok(())
+            }
+            Yaml::Alias(id) => {
+                let node = self.shared(id);
+                if !self.anchored(id) {
+                    return self.emit_flow(node);
+                }
+                match self.names[id] {
+                    Some(ref name) => write!(self.writer, "*{}", name)?,
+                    None => {
+                        self.write_anchor(id)?;
+                        self.writer.write_str(" ")?;
+                        self.emit_flow(node)?;
+                    }
+                }
+                Ok(())
+            }
+            _ => self.emit_node(node),
+        }
+    }
+
+    fn emit_array(&mut self, v: &[Yaml]) -> EmitResult {
+        if v.is_empty() {
+            self.writer.write_str("[]")?;
+        } else {
+            self.level += 1;
+            for (cnt, x) in v.iter().enumerate() {
+                if cnt > 0 {
+                    writeln!(self.writer)?;
+                    self.write_indent()?;
+                }
+                let x = self.write_comments(x)?;
+                self.writer.write_str("-")?;
+                self.emit_val(true, x)?;
+            }
+            self.level -= 1;
+        }
+        Ok(())
+    }
+
+    fn emit_hash(&mut self, h: &Hash) -> EmitResult {
+        if h.is_empty() {
+            self.writer.write_str("{}")?;
+        } else {
+            self.level += 1;
+            // Only the mapping at the root of the document is at level 0.
+            let mut prev = None;
+            for (cnt, (k, v)) in h.iter().enumerate() {
+                if cnt > 0 {
+                    writeln!(self.writer)?;
+                    if self.level == 0 && self.blank_line(prev, k) {
+                        writeln!(self.writer)?;
+                    }
+                    self.write_indent()?;
+                }
+                prev = Some(k);
+                // The comments of both the key and the value go above the
+                // key.
+                let k = self.write_comments(k)?;
+                let v = self.write_comments(v)?;
+                let complex_key = match *self.resolve(k) {
+                    Yaml::Hash(_) | Yaml::Array(_) => true,
+                    _ => false,
+                };
+                if complex_key {
+                    self.writer.write_str("?")?;
+                    self.emit_val(true, k)?;
+                    writeln!(self.writer)?;
+                    self.write_indent()?;
+                    self.writer.write_str(":")?;
+                    self.emit_val(true, v)?;
+                } else {
+                    let alias = self.is_alias(k);
+                    self.emit_node(k)?;
+                    // A colon right after an alias would be read as part of
+                    // its name by parsers other than yaml-rust.
+                    self.writer.write_str(if alias { " :" } else { ":" })?;
+                    self.emit_val(false, v)?;
+                }
+            }
+            self.level -= 1;
+        }
+        Ok(())
+    }
+
+    /// Emit a yaml as a hash or array value: i.e., which should appear
+    /// following a ":" or "-", either after a space, or on a new line.
+    /// If `inline` is true, then the preceding characters are distinct
+    /// and short enough to use the compact notation.
+    fn emit_val(&mut self, inline: bool, val: &Yaml) -> EmitResult {
+        self.clipped = false;
+        if let Some((_, inner)) = comment::split(val) {
+            match (style::of(val), unwrap(inner)) {
+                (Some(Style::Flow), node @ &Yaml::Array(_)) | (Some(Style::Flow), node @ &Yaml::Hash(_)) => {
+                    self.writer.write_str(" ")?;
+                    return self.emit_flow(node);
+                }
+                (Some(Style::Literal), &Yaml::String(ref v)) if fits_literal(v) => return self.emit_literal(v),
+                _ => return self.emit_val(inline, inner),
+            }
+        }
+        match *val {
+            Yaml::Array(ref v) => {
+                if inline || v.is_empty() {
+                    self.writer.write_str(" ")?;
+                } else {
+                    self.write_nested_indent()?;
+                }
+                self.emit_array(v)
+            }
+            Yaml::Hash(ref h) => {
+                if inline || h.is_empty() {
+                    self.writer.write_str(" ")?;
+                } else {
+                    self.write_nested_indent()?;
+                }
+                self.emit_hash(h)
+            }
+            Yaml::Alias(id) if self.anchored(id) => {
+                if let Some(ref name) = self.names[id] {
+                    write!(self.writer, " *{}", name)?;
+                    return Ok(());
+                }
+                self.writer.write_str(" ")?;
+                self.write_anchor(id)?;
+                // Properties cannot be followed by the compact notation, so
+                // a collection starts on the next line.
+                match *self.shared(id) {
+                    Yaml::Array(ref v) if !v.is_empty() => {
+                        self.write_nested_indent()?;
+                        self.emit_array(v)
+                    }
+                    Yaml::Hash(ref h) if !h.is_empty() => {
+                        self.write_nested_indent()?;
+                        self.emit_hash(h)
+                    }
+                    ref node => {
+                        self.writer.write_str(" ")?;
+                        self.emit_node(node)
+                    }
+                }
+            }
+            Yaml::Alias(id) => {
+                let node = self.shared(id);
+                self.emit_val(inline, node)
+            }
+            Yaml::String(ref v) if self.strings.literal && fits_literal(v) => self.emit_literal(v),
+            Yaml::String(ref v) if self.strings.fold_width.map_or(false, |width| fits_folded(v, width)) => {
+                self.emit_folded(v, self.strings.fold_width.unwrap_or(0))
+            }
+            _ => {
+                self.writer.write_str(" ")?;
+                self.emit_node(val)
+            }
+        }
+    }
+
+    /// Writes a string as a literal block scalar, with its lines indented one
+    /// level deeper than the entry that holds it.
+    fn emit_literal(&mut self, v: &str) -> EmitResult {
+        let (header, content) = if v.ends_with('\n') {
+            (" |", &v[..v.len() - 1])
+        } else {
+            (" |-", v)
+        };
+        self.writer.write_str(header)?;
+        for line in content.split('\n') {
+            if line.is_empty() {
+                writeln!(self.writer)?;
+            } else {
+                self.write_nested_indent()?;
+                self.writer.write_str(line)?;
+            }
+        }
+        self.clipped = header == " |";
+        Ok(())
+    }
+
+    /// Writes a string of one line as a folded block scalar, broken at the
+    /// last space before each line would pass `width` characters.
+    fn emit_folded(&mut self, v: &str, width: usize) -> EmitResult {
+        self.writer.write_str(" >-")?;
+        let mut line = 0;
+        for (cnt, word) in v.split(' ').enumerate() {
+            let len = word.chars().count();
+            if cnt == 0 || line + 1 + len > width {
+                self.write_nested_indent()?;
+                line = 0;
+            } else {
+                self.writer.write_str(" ")?;
+                line += 1;
+            }
+            self.writer.write_str(word)?;
+            line += len;
+        }
+        Ok(())
+    }
+
+    /// Whether a blank line goes between the adjacent top-level keys `prev`
+    /// and `next`. Only scalar keys are grouped.
+    fn blank_line(&self, prev: Option<&Yaml>, next: &Yaml) -> bool {
+        match (self.blank_line, prev) {
+            (Some(between), Some(prev)) => {
+                match (scalar_text(self.resolve(prev)), scalar_text(self.resolve(next))) {
+                    (Some(prev), Some(next)) => between(&prev, &next),
+                    _ => false,
+                }
+            }
+            _ => false,
+        }
+    }
+
+    /// Writes the comments of a node that starts a line, each followed by a
+    /// line break and the indentation of the node, and returns the node
+    /// inside them. A node with a style is returned whole, so that the style
+    /// is still there to be written.
+    fn write_comments<'n>(&mut self, node: &'n Yaml) -> Result<&'n Yaml, EmitError> {
+        let mut inner = node;
+        while let Some((comment, next)) = comment::split(inner) {
+            for line in comment.lines() {
+                write_comment_line(self.writer, line)?;
+                writeln!(self.writer)?;
+                self.write_indent()?;
+            }
+            inner = next;
+        }
+        Ok(if style::of(node).is_some() { node } else { inner })
+    }
+
+    fn write_nested_indent(&mut self) -> EmitResult {
+        writeln!(self.writer)?;
+        self.level += 1;
+        self.write_indent()?;
+        self.level -= 1;
+        Ok(())
+    }
+
+    /// Writes the anchor of a shared node, named by the anchor namer if it
+    /// gives a valid name and `id001`, `id002` and so on otherwise. A name
+    /// that has been given out already gets a suffix like `_2`.
+    fn write_anchor(&mut self, id: usize) -> EmitResult {
+        let number = self.next_name;
+        self.next_name += 1;
+        let base = self.named(id).unwrap_or_else(|| format!("id{:03}", number));
+        let mut name = base.clone();
+        let mut suffix = 1;
+        while self.used_names.contains(&name) {
+            suffix += 1;
+            name = format!("{}_{}", base, suffix);
+        }
+        write!(self.writer, "&{}", name)?;
+        self.used_names.insert(name.clone());
+        self.names[id] = Some(name);
+        Ok(())
+    }
+
+    /// The name the anchor namer gives a shared node, if it is valid.
+    #[cfg(feature = "value")]
+    fn named(&self, id: usize) -> Option<String> {
+        let namer = self.anchor_namer?;
+        namer(&self.value(self.shared(id))).filter(|name| is_anchor_name(name))
+    }
+
+    #[cfg(not(feature = "value"))]
+    fn named(&self, _id: usize) -> Option<String> {
+        None
+    }
+
+    /// The value that `node` stands for, as the anchor namer sees it.
+    #[cfg(feature = "value")]
+    fn value(&self, node: &Yaml) -> Value {
+        match *self.resolve(node) {
+            Yaml::Array(ref sequence) => Value::Sequence(sequence.iter().map(|node| self.value(node)).collect()),
+            Yaml::Hash(ref hash) => Value::Mapping(hash.iter().map(|(k, v)| (self.value(k), self.value(v))).collect()),
+            ref scalar => Value::from_serialized(scalar.clone()).unwrap_or(Value::Null),
+        }
+    }
+
+    fn shared(&self, id: usize) -> &'a Yaml {
+        static NULL: Yaml = Yaml::Null;
+        self.anchors.nodes.get(id).unwrap_or(&NULL)
+    }
+
+    /// Whether the shared node is referred to more than once, and so needs an
+    /// anchor.
+    fn anchored(&self, id: usize) -> bool {
+        self.anchors.refs.get(id).map_or(false, |&refs| refs > 1)
+    }
+
+    /// Whether `node` is written as an alias to a node written earlier.
+    fn is_alias(&self, node: &Yaml) -> bool {
+        match *node {
+            Yaml::Alias(id) => self.anchored(id) && self.names[id].is_some(),
+            _ => false,
+        }
+    }
+
+    /// The node that `node` stands for.
+    fn resolve<'n>(&self, node: &'n Yaml) -> &'n Yaml
+        where 'a: 'n
+    {
+        match *node {
+            Yaml::Alias(id) => self.resolve(self.shared(id)),
+            ref node => match comment::split(node) {
+                Some((_, node)) => self.resolve(node),
+                None => node,
+            },
+        }
+    }
+}
+
+/// The node inside any comments and styles.
+fn unwrap(mut node: &Yaml) -> &Yaml {
+    while let Some((_, inner)) = comment::split(node) {
+        node = inner;
+    }
+    node
+}
+
+/// The text of a scalar as it is written, for the serializer's own spelling
+/// of floats and null.
+fn scalar_text(node: &Yaml) -> Option<Cow<str>> {
+    match *node {
+        Yaml::String(ref s) | Yaml::Real(ref s) => Some(Cow::Borrowed(s)),
+        Yaml::Integer(i) => Some(Cow::Owned(i.to_string())),
+        Yaml::Boolean(b) => Some(Cow::Borrowed(if b { "true" } else { "false" })),
+        Yaml::Null => Some(Cow::Borrowed("~")),
+        _ => None,
+    }
+}
+
+/// Whether a string of several lines reads back the same when written as a
+/// literal block scalar without an indentation indicator. Its trailing line
+/// breaks beyond the first would only survive with keep chomping, which
+/// depends on what follows the scalar, so such strings are quoted instead.
+fn fits_literal(v: &str) -> bool {
+    if !v.contains('\n') || v.ends_with("\n\n") {
+        return false;
+    }
+    if v.contains(|c: char| c.is_control() && c != '\n' && c != '\t') {
+        return false;
+    }
+    // The first line that is not empty sets the indentation of the block, and
+    // lines of only whitespace must not be indented deeper than it.
+    match v.split('\n').find(|line| !line.is_empty()) {
+        Some(first) if !first.starts_with(&[' ', '\t'][..]) => {}
+        _ => return false,
+    }
+    !v.split('\n').any(|line| !line.is_empty() && line.trim_start_matches(&[' ', '\t'][..]).is_empty())
+}
+
+/// Whether a string is worth writing as a folded block scalar wrapped at
+/// `width`, and reads back the same. Folding turns each line break into a
+/// space, unless the next line starts with whitespace, so only single spaces
+/// between words can be broken.
+fn fits_folded(v: &str, width: usize) -> bool {
+    v.chars().count() > width && v.contains(' ') && !v.starts_with(' ') && !v.ends_with(' ') &&
+    !v.contains("  ") && !v.contains(|c: char| c.is_control())
+}
+
+/// Whether single quotes suit a string that needs quotes: they cannot escape
+/// control characters, and a `'` in them has to be doubled.
+fn fits_single_quoted(v: &str) -> bool {
+    !v.contains(|c: char| c.is_control()) && (!v.contains('\'') || v.contains('"') || v.contains('\\'))
+}
+
+/// Writes a number, with an underscore between every three digits if it is
+/// an integer of more than four digits. Floats are written as they are.
+fn write_grouped(wr: &mut dyn fmt::Write, number: &str) -> fmt::Result {
+    let digits = number.trim_start_matches('-');
+    if digits.len() <= 4 || !digits.bytes().all(|c| c.is_ascii_digit()) {
+        return wr.write_str(number);
+    }
+    wr.write_str(&number[..number.len() - digits.len()])?;
+    for (i, c) in digits.char_indices() {
+        if i > 0 && (digits.len() - i) % 3 == 0 {
+            wr.write_char('_')?;
+        }
+        wr.write_char(c)?;
+    }
+    Ok(())
+}
+
+/// Whether `name` can be written as an anchor: not empty, and without
+/// spaces or the characters that end a node in flow style.
+fn is_anchor_name(name: &str) -> bool {
+    !name.is_empty() &&
+    !name.contains(|c: char| c.is_whitespace() || c.is_control() || "[]{},".contains(c))
+}
+
+fn write_comment_line(wr: &mut dyn fmt::Write, line: &str) -> fmt::Result {
+    if line.is_empty() {
+        wr.write_str("#")
+    } else {
+        write!(wr, "# {}", line)
+    }
+}
+
+/// Writes a string scalar, in double quotes if it needs them.
+pub(crate) fn write_string(wr: &mut dyn fmt::Write, v: &str) -> fmt::Result {
+    if need_quotes(v) {
+        escape_str(wr, v)
+    } else {
+        wr.write_str(v)
+    }
+}
+
+fn escape_str(wr: &mut dyn fmt::Write, v: &str) -> Result<(), fmt::Error> {
+    wr.write_str("\"")?;
+    escape_body(wr, v)?;
+    wr.write_str("\"")?;
+    Ok(())
+}
+
+/// Writes a string escaped for the inside of double quotes.
+// from serialize::json
+pub(crate) fn escape_body(wr: &mut dyn fmt::Write, v: &str) -> Result<(), fmt::Error> {
+    let mut start = 0;
+
+    for (i, byte) in v.bytes().enumerate() {
+        let escaped = match byte {
+            b'"' => "\\\"",
+            b'\\' => "\\\\",
+            b'\x00' => "\\u0000",
+            b'\x01' => "\\u0001",
+            b'\x02' => "\\u0002",
+            b'\x03' => "\\u0003",
+            b'\x04' => "\\u0004",
+            b'\x05' => "\\u0005",
+            b'\x06' => "\\u0006",
+            b'\x07' => "\\u0007",
+            b'\x08' => "\\b",
+            b'\t' => "\\t",
+            b'\n' => "\\n",
+            b'\x0b' => "\\u000b",
+            b'\x0c' => "\\f",
+            b'\r' => "\\r",
+            b'\x0e' => "\\u000e",
+            b'\x0f' => "\\u000f",
+            b'\x10' => "\\u0010",
+            b'\x11' => "\\u0011",
+            b'\x12' => "\\u0012",
+            b'\x13' => "\\u0013",
+            b'\x14' => "\\u0014",
+            b'\x15' => "\\u0015",
+            b'\x16' => "\\u0016",
+            b'\x17' => "\\u0017",
+            b'\x18' => "\\u0018",
+            b'\x19' => "\\u0019",
+            b'\x1a' => "\\u001a",
+            b'\x1b' => "\\u001b",
+            b'\x1c' => "\\u001c",
+            b'\x1d' => "\\u001d",
+            b'\x1e' => "\\u001e",
+            b'\x1f' => "\\u001f",
+            b'\x7f' => "\\u007f",
+            _ => continue,
+        };
+
+        if start < i {
+            wr.write_str(&v[start..i])?;
+        }
+
+        wr.write_str(escaped)?;
+
+        start = i + 1;
+    }
+
+    if start != v.len() {
+        wr.write_str(&v[start..])?;
+    }
+    Ok(())
+}
+
+/// How many bytes `escape_str` writes for `v`, quotes included.
+pub(crate) fn quoted_len(v: &str) -> usize {
+    let escapes: usize = v
+        .bytes()
+        .map(|byte| match byte {
+            b'"' | b'\\' | b'\x08' | b'\t' | b'\n' | b'\x0c' | b'\r' => 1,
+            b'\x00'..=b'\x1f' | b'\x7f' => 5,
+            _ => 0,
+        })
+        .sum();
+    v.len() + escapes + 2
+}
+
+/// Check if the string requires quoting.
+/// Strings starting with any of the following characters must be quoted.
+/// :, &, *, ?, |, -, <, >, =, !, %, @
+/// Strings containing any of the following characters must be quoted.
+/// {, }, [, ], ,, #, `
+///
+/// If the string contains any control characters, it must be escaped with
+/// double quotes.
+///
+/// Finally, strings must be quoted if they would otherwise be read as a
+/// boolean, including the single letters `y` and `n` that libyaml and PyYAML
+/// leave as strings against the YAML 1.1 specification, null or number, or
+/// as a YAML 1.1 timestamp or number by other
+/// tools: dates like `2024-01-01`, and numbers with `_` like `1_000` or in
+/// binary like `0b101`. Times like `08:30` contain a `:` and are quoted for
+/// that.
+pub(crate) fn need_quotes(string: &str) -> bool {
+    need_quotes_syntax(string) ||
+    [// http://yaml.org/type/bool.html
+     "y", "Y", "n", "N", "yes", "Yes", "YES", "no", "No", "NO", "True", "TRUE", "true", "False", "FALSE",
+     "false", "on", "On", "ON", "off", "Off", "OFF",
+     // http://yaml.org/type/null.html
+     "null", "Null", "NULL", "~"]
+        .contains(&string) || string.starts_with('.') || string.starts_with("0x") ||
+    string.starts_with("0o") || string.starts_with("+.") ||
+    string.parse::<i64>().is_ok() || string.parse::<f64>().is_ok() ||
+    dates::is_date(string) || is_yaml11_number(string)
+}
+
+/// The quoting rules of `YamlFormat::stable`, frozen as they were when it
+/// was added: `need_quotes` without dates and the numbers Rust does not
+/// parse. Do not change what this quotes outside of a major version.
+fn need_quotes_stable(string: &str) -> bool {
+    need_quotes_syntax(string) ||
+    ["yes", "Yes", "YES", "no", "No", "NO", "True", "TRUE", "true", "False", "FALSE", "false", "on", "On",
+     "ON", "off", "Off", "OFF", "null", "Null", "NULL", "~"]
+        .contains(&string) || string.starts_with('.') || string.starts_with("0x") ||
+    string.starts_with("0o") || string.starts_with("+.") ||
+    string.parse::<i64>().is_ok() || string.parse::<f64>().is_ok()
+}
+
+/// Whether the string is a YAML 1.1 number that Rust does not parse: a
+/// binary integer, or a number with `_` between its digits.
+fn is_yaml11_number(string: &str) -> bool {
+    let unsigned = string.trim_start_matches(['-', '+']);
+    if unsigned.len() + 1 < string.len() {
+        return false;
+    }
+    if let Some(binary) = unsigned.strip_prefix("0b") {
+        return !binary.is_empty() && binary.chars().all(|c| c == '0' || c == '1' || c == '_');
+    }
+    unsigned.starts_with(|c: char| c.is_ascii_digit()) && unsigned.contains('_') &&
+    unsigned.replace('_', "").parse::<f64>().is_ok()
+}
+
+/// Whether the string cannot be written as a plain scalar whatever it would
+/// be read as: it is empty, has spaces at either end, starts with an
+/// indicator, or contains characters with a meaning in flow style, quotes,
+/// or control characters.
+pub(crate) fn need_quotes_syntax(string: &str) -> bool {
+    fn need_quotes_spaces(string: &str) -> bool {
+        string.starts_with(' ') || string.ends_with(' ')
+    }
+
+    string.is_empty() || need_quotes_spaces(string) ||
+    string.starts_with(|character: char| match character {
+                           '&' | '*' | '?' | '|' | '-' | '<' | '>' | '=' | '!' | '%' | '@' => true,
+                           _ => false,
+                       }) ||
+    string.contains(|character: char| match character {
+                        ':' | '{' | '}' | '[' | ']' | ',' | '#' | '`' | '\"' | '\'' | '\\' |
+                        '\0'..='\x06' | '\t' | '\n' | '\r' | '\x0e'..='\x1a' | '\x1c'..='\x1f' => true,
+                        _ => false,
+                    })
+}
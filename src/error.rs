@@ -12,45 +12,84 @@ use std::io;
 use std::result;
 use std::str;
 use std::string;
+use std::sync::Arc;
 
 use yaml_rust::emitter;
-use yaml_rust::scanner::{self, Marker, ScanError};
+use yaml_rust::scanner::{self, Marker};
 
 use serde::{de, ser};
 
-use path::Path;
+use path::{Path, Segment};
 
 /// This type represents all possible errors that can occur when serializing or
 /// deserializing YAML data.
-pub struct Error(Box<ErrorImpl>);
+///
+/// Cloning an error is cheap, since clones share what the error holds, so
+/// results with errors in them can be cached and handed to several callers.
+#[derive(Clone)]
+pub struct Error(Arc<ErrorImpl>);
 
 /// Alias for a `Result` with the error type `serde_yaml::Error`.
 pub type Result<T> = result::Result<T, Error>;
 
 /// This type represents all possible errors that can occur when serializing or
 /// deserializing a value using YAML.
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub enum ErrorImpl {
     Message(String, Option<Pos>),
+    /// An error raised by the serializer itself, along with the path to the
+    /// value it is about, innermost segment first.
+    Serialize(String, Vec<Segment>),
+    /// A mapping key that is a sequence or a mapping while complex keys are
+    /// turned off, along with the path to the mapping.
+    KeyNotScalar(Vec<Segment>),
 
     Emit(emitter::EmitError),
     Scan(scanner::ScanError),
-    Io(io::Error),
+    /// An alias to an anchor that is not defined, worded like the parser's
+    /// own error for it.
+    UnknownAnchor(scanner::ScanError),
+    /// A scanner error inside a block scalar, along with where the problem
+    /// is and where the block scalar starts, one of which the scanner does
+    /// not report.
+    BlockScalar(scanner::ScanError, Location, Location),
+    Io(Arc<io::Error>),
     Utf8(str::Utf8Error),
     FromUtf8(string::FromUtf8Error),
 
     EndOfStream,
     MoreThanOneDocument,
+
+    /// An error along with the line of the input that it is on, as cut by
+    /// `Error::with_line`, or two lines for an error in a block scalar.
+    Line(Error, String),
+}
+
+/// The kinds of error that callers may want to handle differently from the
+/// rest, as returned by `Error::kind`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// The serializer met a mapping key that is a sequence or a mapping
+    /// while complex keys are turned off. The path is that of the mapping,
+    /// like `servers[0].ports`, or `.` for the root.
+    KeyNotScalar {
+        /// The path of the mapping with the key.
+        path: String,
+    },
+    /// An alias refers to an anchor that is not defined before it.
+    UnknownAnchor,
+    /// Any other error.
+    Other,
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct Pos {
-    marker: Marker,
+    location: Location,
     path: String,
 }
 
 /// This type represents the location that an error occured.
-#[derive(Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub struct Location {
     index: usize,
     line: usize,
@@ -73,9 +112,17 @@ impl Location {
         self.column
     }
 
+    pub(crate) fn new(index: usize, line: usize, column: usize) -> Self {
+        Location {
+            index: index,
+            line: line,
+            column: column,
+        }
+    }
+
     // This is to keep decoupled with the yaml crate
     #[doc(hidden)]
-    fn from_marker(marker: &Marker) -> Self {
+    pub fn from_marker(marker: &Marker) -> Self {
         Location {
             // `col` returned from the `yaml` crate is 0-indexed but all error messages add + 1 to this value
             column: marker.col() + 1,
@@ -109,8 +156,71 @@ impl Error {
     /// ```
     pub fn location(&self) -> Option<Location> {
         match *self.0 {
-            ErrorImpl::Message(_, Some(ref pos)) => Some(Location::from_marker(&pos.marker)),
-            ErrorImpl::Scan(ref scan) => Some(Location::from_marker(scan.marker())),
+            ErrorImpl::Message(_, Some(ref pos)) => Some(pos.location),
+            ErrorImpl::Scan(ref scan) | ErrorImpl::UnknownAnchor(ref scan) => {
+                Some(Location::from_marker(scan.marker()))
+            }
+            ErrorImpl::BlockScalar(_, at, _) => Some(at),
+            ErrorImpl::Line(ref err, _) => err.location(),
+            _ => None,
+        }
+    }
+
+    /// Where the block scalar starts, for a scanner error inside one, like a
+    /// bad indentation indicator or a tab in the indentation of its content.
+    /// `location` is then where the problem is.
+    ///
+    /// ```rust
+    /// # extern crate serde_yaml;
+    /// # use serde_yaml::Value;
+    /// # fn main() {
+    /// let err = serde_yaml::from_str::<Value>("script: |\n  make\n\tmake test\n").unwrap_err();
+    /// assert_eq!(err.location().unwrap().line(), 3);
+    /// assert_eq!(err.block_start().unwrap().line(), 1);
+    /// assert_eq!(err.to_string(),
+    ///            "while scanning a block scalar, found a tab character where an indentation space is expected \
+    ///             at line 3 column 1, in the block scalar at line 1 column 9");
+    /// # }
+    /// ```
+    pub fn block_start(&self) -> Option<Location> {
+        match *self.0 {
+            ErrorImpl::BlockScalar(_, _, start) => Some(start),
+            ErrorImpl::Line(ref err, _) => err.block_start(),
+            _ => None,
+        }
+    }
+
+    /// The kind of this error.
+    ///
+    /// ```rust
+    /// # extern crate serde_yaml;
+    /// #
+    /// # use std::collections::BTreeMap;
+    /// #
+    /// # fn main() {
+    /// use serde_yaml::{ErrorKind, Writer};
+    ///
+    /// let mut ranges = BTreeMap::new();
+    /// ranges.insert(vec![1, 2], "low");
+    /// let mut writer = Writer::new(Vec::new()).complex_keys(false);
+    /// let err = writer.write(&ranges).unwrap_err();
+    /// assert_eq!(err.kind(), ErrorKind::KeyNotScalar { path: ".".to_owned() });
+    /// # }
+    /// ```
+    pub fn kind(&self) -> ErrorKind {
+        match *self.0 {
+            ErrorImpl::KeyNotScalar(ref path) => ErrorKind::KeyNotScalar { path: path_string(path) },
+            ErrorImpl::UnknownAnchor(_) => ErrorKind::UnknownAnchor,
+            ErrorImpl::Line(ref err, _) => err.kind(),
+            _ => ErrorKind::Other,
+        }
+    }
+
+    /// The line of the input that the error is on, as put there by
+    /// `Deserializer::error_lines`.
+    pub fn line(&self) -> Option<&str> {
+        match *self.0 {
+            ErrorImpl::Line(_, ref line) => Some(line),
             _ => None,
         }
     }
@@ -118,78 +228,195 @@ impl Error {
     // Not public API. Should be pub(crate).
     #[doc(hidden)]
     pub fn end_of_stream() -> Self {
-        Error(Box::new(ErrorImpl::EndOfStream))
+        Error(Arc::new(ErrorImpl::EndOfStream))
     }
 
     // Not public API. Should be pub(crate).
     #[doc(hidden)]
     pub fn more_than_one_document() -> Self {
-        Error(Box::new(ErrorImpl::MoreThanOneDocument))
+        Error(Arc::new(ErrorImpl::MoreThanOneDocument))
     }
 
     // Not public API. Should be pub(crate).
     #[doc(hidden)]
     pub fn io(err: io::Error) -> Error {
-        Error(Box::new(ErrorImpl::Io(err)))
+        Error(Arc::new(ErrorImpl::Io(Arc::new(err))))
     }
 
     // Not public API. Should be pub(crate).
     #[doc(hidden)]
     pub fn emitter(err: emitter::EmitError) -> Error {
-        Error(Box::new(ErrorImpl::Emit(err)))
+        Error(Arc::new(ErrorImpl::Emit(err)))
     }
 
     // Not public API. Should be pub(crate).
     #[doc(hidden)]
     pub fn scanner(err: scanner::ScanError) -> Error {
-        Error(Box::new(ErrorImpl::Scan(err)))
+        Error(Arc::new(ErrorImpl::Scan(err)))
+    }
+
+    pub(crate) fn unknown_anchor(err: scanner::ScanError) -> Error {
+        Error(Arc::new(ErrorImpl::UnknownAnchor(err)))
+    }
+
+    /// A scanner error in `text`, with both of its locations if it is inside
+    /// a block scalar. The scanner reports errors in the header of a block
+    /// scalar at its start and tabs in its content at the tab, so the other
+    /// location is found in the text.
+    pub(crate) fn scanner_in(err: scanner::ScanError, text: &str) -> Error {
+        #[allow(deprecated)]
+        let block = error::Error::description(&err).starts_with("while scanning a block scalar");
+        let marker = *err.marker();
+        let lines: Vec<&str> = text.split('\n').collect();
+        let line = match lines.get(marker.line().wrapping_sub(1)) {
+            Some(line) if block => line,
+            _ => return Error::scanner(err),
+        };
+        let here = Location::from_marker(&marker);
+        let (at, start) = if line.chars().nth(marker.col()).map_or(false, |c| c == '|' || c == '>') {
+            // After the indicator come at most an indentation digit and a
+            // chomping sign, then blanks. The problem is what follows.
+            let chars: Vec<char> = line.chars().collect();
+            let mut col = marker.col() + 1;
+            while col < chars.len() && col < marker.col() + 3 && "123456789+-".contains(chars[col]) {
+                col += 1;
+            }
+            while col < chars.len() && (chars[col] == ' ' || chars[col] == '\t') {
+                col += 1;
+            }
+            let at = Location::new(here.index + col - marker.col(), here.line, col + 1);
+            (at, here)
+        } else {
+            let start = (1..marker.line()).rev().filter_map(|n| block_header(lines[n - 1]).map(|col| (n, col))).next();
+            match start {
+                Some((n, col)) => {
+                    let index = lines[..n - 1].iter().map(|line| line.chars().count() + 1).sum::<usize>() + col;
+                    (here, Location::new(index, n, col + 1))
+                }
+                None => return Error::scanner(err),
+            }
+        };
+        Error(Arc::new(ErrorImpl::BlockScalar(err, at, start)))
     }
 
     // Not public API. Should be pub(crate).
     #[doc(hidden)]
     pub fn str_utf8(err: str::Utf8Error) -> Error {
-        Error(Box::new(ErrorImpl::Utf8(err)))
+        Error(Arc::new(ErrorImpl::Utf8(err)))
     }
 
     // Not public API. Should be pub(crate).
     #[doc(hidden)]
     pub fn string_utf8(err: string::FromUtf8Error) -> Error {
-        Error(Box::new(ErrorImpl::FromUtf8(err)))
+        Error(Arc::new(ErrorImpl::FromUtf8(err)))
+    }
+
+    pub(crate) fn serialize<T: Display>(msg: T) -> Self {
+        Error(Arc::new(ErrorImpl::Serialize(msg.to_string(), Vec::new())))
+    }
+
+    pub(crate) fn key_not_scalar() -> Self {
+        Error(Arc::new(ErrorImpl::KeyNotScalar(Vec::new())))
+    }
+
+    /// Records that the error happened within `segment` of the value being
+    /// serialized, if it is an error that knows its path.
+    pub(crate) fn within(mut self, segment: Segment) -> Self {
+        match *Arc::make_mut(&mut self.0) {
+            ErrorImpl::Serialize(_, ref mut path) |
+            ErrorImpl::KeyNotScalar(ref mut path) => path.push(segment),
+            _ => {}
+        }
+        self
     }
 
     // Not public API. Should be pub(crate).
     #[doc(hidden)]
     pub fn fix_marker(mut self, marker: Marker, path: Path) -> Self {
-        if let ErrorImpl::Message(_, ref mut none @ None) = *self.0.as_mut() {
+        if let ErrorImpl::Message(_, ref mut none @ None) = *Arc::make_mut(&mut self.0) {
             *none = Some(Pos {
-                             marker: marker,
+                             location: Location::from_marker(&marker),
                              path: path.to_string(),
                          });
         }
         self
     }
+
+    /// The same error without a location, for input that has no positions.
+    pub(crate) fn without_location(self) -> Self {
+        match *self.0 {
+            ErrorImpl::Message(ref msg, Some(ref pos)) => {
+                let msg = if pos.path == "." { msg.clone() } else { format!("{}: {}", pos.path, msg) };
+                Error(Arc::new(ErrorImpl::Message(msg, None)))
+            }
+            _ => self,
+        }
+    }
+
+    /// The same error along with the line of `text` that it is on, cut to
+    /// `max_len` characters around its column and trimmed, if it has a
+    /// location on a line that is not blank.
+    ///
+    /// For an error inside a block scalar, the line where the block scalar
+    /// starts comes first, so the snippet is two lines long.
+    pub(crate) fn with_line(self, text: &str, max_len: usize) -> Self {
+        let location = match self.location() {
+            Some(location) if self.line().is_none() => location,
+            _ => return self,
+        };
+        let mut lines = Vec::new();
+        if let Some(start) = self.block_start() {
+            if start.line != location.line {
+                lines.extend(window(text, start, max_len));
+            }
+        }
+        lines.extend(window(text, location, max_len));
+        if lines.is_empty() {
+            return self;
+        }
+        Error(Arc::new(ErrorImpl::Line(self, lines.join("\n"))))
+    }
+
+    /// An error about the input text itself, found before parsing it.
+    pub(crate) fn at<T: Display>(msg: T, location: Location) -> Self {
+        Error(Arc::new(ErrorImpl::Message(msg.to_string(),
+                                          Some(Pos {
+                                                   location: location,
+                                                   path: ".".to_owned(),
+                                               }))))
+    }
 }
 
 impl error::Error for Error {
+    #[allow(deprecated)]
     fn description(&self) -> &str {
         match *self.0 {
-            ErrorImpl::Message(ref msg, _) => msg,
+            ErrorImpl::Message(ref msg, _) | ErrorImpl::Serialize(ref msg, _) => msg,
+            ErrorImpl::KeyNotScalar(_) => KEY_NOT_SCALAR,
             ErrorImpl::Emit(_) => "emit error",
-            ErrorImpl::Scan(_) => "scan error",
+            ErrorImpl::Scan(_) | ErrorImpl::UnknownAnchor(_) | ErrorImpl::BlockScalar(..) => "scan error",
             ErrorImpl::Io(ref err) => err.description(),
             ErrorImpl::Utf8(ref err) => err.description(),
             ErrorImpl::FromUtf8(ref err) => err.description(),
             ErrorImpl::EndOfStream => "EOF while parsing a value",
             ErrorImpl::MoreThanOneDocument => "deserializing from YAML containing more than one document is not supported",
+            ErrorImpl::Line(ref err, _) => err.description(),
         }
     }
 
-    fn cause(&self) -> Option<&error::Error> {
+    /// The error from the reader or writer, the scanner, the emitter or the
+    /// UTF-8 check that this error wraps, if any. Its message is also this
+    /// error's message.
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
         match *self.0 {
-            ErrorImpl::Scan(ref err) => Some(err),
-            ErrorImpl::Io(ref err) => Some(err),
+            ErrorImpl::Emit(ref err) => Some(err),
+            ErrorImpl::Scan(ref err) | ErrorImpl::UnknownAnchor(ref err) | ErrorImpl::BlockScalar(ref err, _, _) => {
+                Some(err)
+            }
+            ErrorImpl::Io(ref err) => Some(&**err),
             ErrorImpl::Utf8(ref err) => Some(err),
             ErrorImpl::FromUtf8(ref err) => Some(err),
+            ErrorImpl::Line(ref err, _) => err.source(),
             _ => None,
         }
     }
@@ -199,16 +426,31 @@ impl Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self.0 {
             ErrorImpl::Message(ref msg, None) => Display::fmt(msg, f),
-            ErrorImpl::Message(ref msg, Some(Pos { marker, ref path })) => {
-                if path == "." {
-                    write!(f, "{}", ScanError::new(marker, msg))
-                } else {
-                    write!(f, "{}: {}", path, ScanError::new(marker, msg))
+            ErrorImpl::Message(ref msg, Some(Pos { location, ref path })) => {
+                if path != "." {
+                    write!(f, "{}: ", path)?;
                 }
+                // Worded like a `ScanError`.
+                write!(f, "{} at line {} column {}", msg, location.line, location.column)
             }
+            ErrorImpl::Serialize(ref msg, ref path) if path.is_empty() => Display::fmt(msg, f),
+            ErrorImpl::Serialize(ref msg, ref path) => write!(f, "{}: {}", path_string(path), msg),
+            ErrorImpl::KeyNotScalar(ref path) if path.is_empty() => f.write_str(KEY_NOT_SCALAR),
+            ErrorImpl::KeyNotScalar(ref path) => write!(f, "{}: {}", path_string(path), KEY_NOT_SCALAR),
             ErrorImpl::Emit(emitter::EmitError::FmtError(_)) => f.write_str("yaml-rust fmt error"),
             ErrorImpl::Emit(emitter::EmitError::BadHashmapKey) => f.write_str("bad hash map key"),
-            ErrorImpl::Scan(ref err) => Display::fmt(err, f),
+            ErrorImpl::Scan(ref err) | ErrorImpl::UnknownAnchor(ref err) => Display::fmt(err, f),
+            ErrorImpl::BlockScalar(ref err, at, start) => {
+                #[allow(deprecated)]
+                let info = error::Error::description(err);
+                write!(f,
+                       "{} at line {} column {}, in the block scalar at line {} column {}",
+                       info,
+                       at.line,
+                       at.column,
+                       start.line,
+                       start.column)
+            }
             ErrorImpl::Io(ref err) => Display::fmt(err, f),
             ErrorImpl::Utf8(ref err) => Display::fmt(err, f),
             ErrorImpl::FromUtf8(ref err) => Display::fmt(err, f),
@@ -216,6 +458,7 @@ impl Display for Error {
             ErrorImpl::MoreThanOneDocument => {
                 f.write_str("deserializing from YAML containing more than one document is not supported")
             }
+            ErrorImpl::Line(ref err, ref line) => write!(f, "{}, in `{}`", err, line),
         }
     }
 }
@@ -231,8 +474,23 @@ impl Debug for Error {
                     .field(pos)
                     .finish()
             }
+            ErrorImpl::Serialize(ref msg, ref path) => {
+                formatter.debug_tuple("Serialize")
+                    .field(msg)
+                    .field(path)
+                    .finish()
+            }
+            ErrorImpl::KeyNotScalar(ref path) => formatter.debug_tuple("KeyNotScalar").field(path).finish(),
             ErrorImpl::Emit(ref emit) => formatter.debug_tuple("Emit").field(emit).finish(),
             ErrorImpl::Scan(ref scan) => formatter.debug_tuple("Scan").field(scan).finish(),
+            ErrorImpl::UnknownAnchor(ref scan) => formatter.debug_tuple("UnknownAnchor").field(scan).finish(),
+            ErrorImpl::BlockScalar(ref scan, ref at, ref start) => {
+                formatter.debug_tuple("BlockScalar")
+                    .field(scan)
+                    .field(at)
+                    .field(start)
+                    .finish()
+            }
             ErrorImpl::Io(ref io) => formatter.debug_tuple("Io").field(io).finish(),
             ErrorImpl::Utf8(ref utf8) => formatter.debug_tuple("Utf8").field(utf8).finish(),
             ErrorImpl::FromUtf8(ref from_utf8) => {
@@ -240,18 +498,89 @@ impl Debug for Error {
             }
             ErrorImpl::EndOfStream => formatter.debug_tuple("EndOfStream").finish(),
             ErrorImpl::MoreThanOneDocument => formatter.debug_tuple("MoreThanOneDocument").finish(),
+            ErrorImpl::Line(ref err, ref line) => {
+                formatter.debug_tuple("Line")
+                    .field(err)
+                    .field(line)
+                    .finish()
+            }
+        }
+    }
+}
+
+/// The line of `text` at `location`, cut to `max_len` characters around its
+/// column and trimmed, unless it is missing or blank.
+fn window(text: &str, location: Location, max_len: usize) -> Option<String> {
+    let chars: Vec<char> = text.lines().nth(location.line.saturating_sub(1))?.chars().collect();
+    let (start, end) = if chars.len() <= max_len {
+        (0, chars.len())
+    } else {
+        let start = location.column.saturating_sub(1 + max_len / 2).min(chars.len() - max_len);
+        (start, start + max_len)
+    };
+    let mut line = String::new();
+    if start > 0 {
+        line.push_str("...");
+    }
+    line.extend(&chars[start..end]);
+    if end < chars.len() {
+        line.push_str("...");
+    }
+    let line = line.trim();
+    if line.is_empty() { None } else { Some(line.to_owned()) }
+}
+
+/// The column of the `|` or `>` that starts a block scalar at the end of
+/// `line`, after any comment is taken off.
+pub(crate) fn block_header(line: &str) -> Option<usize> {
+    let chars: Vec<char> = line.chars().collect();
+    let mut end = chars.iter()
+        .enumerate()
+        .position(|(i, &c)| c == '#' && (i == 0 || chars[i - 1] == ' ' || chars[i - 1] == '\t'))
+        .unwrap_or(chars.len());
+    while end > 0 && chars[end - 1].is_whitespace() {
+        end -= 1;
+    }
+    let start = chars[..end].iter().rposition(|&c| c == ' ' || c == '\t').map_or(0, |i| i + 1);
+    let token = &chars[start..end];
+    let header = match token.split_first() {
+        Some((&c, rest)) => (c == '|' || c == '>') && rest.len() <= 2 && rest.iter().all(|&c| "123456789+-".contains(c)),
+        None => false,
+    };
+    if header { Some(start) } else { None }
+}
+
+const KEY_NOT_SCALAR: &str = "mapping key is a sequence or a mapping, which is only written with \
+                              complex keys turned on; see `complex_keys`";
+
+/// A path collected by `Error::within`, innermost segment first, written
+/// like `a.b[0]`.
+fn path_string(path: &[Segment]) -> String {
+    let mut string = String::new();
+    for (i, segment) in path.iter().rev().enumerate() {
+        match *segment {
+            Segment::Key(ref key) if i == 0 => string.push_str(key),
+            Segment::Key(ref key) => {
+                string.push('.');
+                string.push_str(key);
+            }
+            Segment::Index(index) => string.push_str(&format!("[{}]", index)),
         }
     }
+    if string.is_empty() {
+        string.push('.');
+    }
+    string
 }
 
 impl ser::Error for Error {
     fn custom<T: Display>(msg: T) -> Self {
-        Error(Box::new(ErrorImpl::Message(msg.to_string(), None)))
+        Error(Arc::new(ErrorImpl::Message(msg.to_string(), None)))
     }
 }
 
 impl de::Error for Error {
     fn custom<T: Display>(msg: T) -> Self {
-        Error(Box::new(ErrorImpl::Message(msg.to_string(), None)))
+        Error(Arc::new(ErrorImpl::Message(msg.to_string(), None)))
     }
 }
@@ -0,0 +1,367 @@
+// Copyright 2018 Serde YAML Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The events that a YAML document is made of, for driving deserialization
+//! from something other than YAML text.
+//!
+//! A parser turns text into a flat stream of events: one for every scalar
+//! and alias, and one for the start and the end of every collection.
+//! `Deserializer::from_events` reads such a stream the same way it reads the
+//! output of this crate's own parser, so tests and other front ends get the
+//! same handling of tags, numbers, booleans and aliases.
+//!
+//! ```rust
+//! # extern crate serde;
+//! # extern crate serde_yaml;
+//! #
+//! # fn main() {
+//! use std::collections::BTreeMap;
+//!
+//! use serde::Deserialize;
+//! use serde_yaml::Deserializer;
+//! use serde_yaml::event::Event;
+//!
+//! let events = vec![
+//!     Event::MappingStart(None),
+//!     Event::plain("port"),
+//!     Event::plain("8080"),
+//!     Event::MappingEnd,
+//! ];
+//! let map = BTreeMap::<String, u16>::deserialize(Deserializer::from_events(events.clone())).unwrap();
+//! assert_eq!(map["port"], 8080);
+//! assert_eq!(serde_yaml::to_events(&map).unwrap(), events);
+//! # }
+//! ```
+//!
+//! In the other direction, `to_events` serializes a value into the events
+//! that `to_string` would write as text, for emitters other than this
+//! crate's own.
+
+#[cfg(feature = "de")]
+use std::collections::{BTreeMap, HashMap};
+
+#[cfg(feature = "de")]
+use serde::de::Error as SError;
+#[cfg(feature = "ser")]
+use yaml_rust::Yaml;
+#[cfg(feature = "de")]
+use yaml_rust::scanner::{Scanner, TScalarStyle, TokenType};
+
+#[cfg(feature = "de")]
+use anchor::{AnchorPolicy, Undefined};
+#[cfg(feature = "ser")]
+use comment;
+#[cfg(feature = "de")]
+use de::{self, Loader};
+#[cfg(feature = "ser")]
+use emitter::{self, Anchors};
+#[cfg(feature = "de")]
+use error::{Error, Location, Result};
+#[cfg(feature = "de")]
+use scalar::ScalarString;
+#[cfg(feature = "ser")]
+use tags;
+
+/// One event of a YAML document.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Event {
+    /// An alias of the node with the given anchor, written `*name`.
+    Alias(String),
+    /// A scalar.
+    Scalar(Scalar),
+    /// The start of a sequence, with its anchor if it has one.
+    SequenceStart(Option<String>),
+    /// The end of the innermost sequence.
+    SequenceEnd,
+    /// The start of a mapping, with its anchor if it has one. The events of
+    /// its keys and values follow in turn.
+    MappingStart(Option<String>),
+    /// The end of the innermost mapping.
+    MappingEnd,
+}
+
+/// A scalar event.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Scalar {
+    /// The content of the scalar, with escapes and quotes already removed.
+    pub value: String,
+    /// How the scalar is written. Only plain scalars are read as numbers,
+    /// booleans and null.
+    pub style: ScalarStyle,
+    /// The anchor of the scalar, if it has one.
+    pub anchor: Option<String>,
+    /// The tag of the scalar as it is written, like `!!str` or `!custom`.
+    pub tag: Option<String>,
+}
+
+/// How a scalar is written.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ScalarStyle {
+    /// Without quotes.
+    Plain,
+    /// In single quotes.
+    SingleQuoted,
+    /// In double quotes.
+    DoubleQuoted,
+    /// As a block scalar starting with `|`.
+    Literal,
+    /// As a block scalar starting with `>`.
+    Folded,
+}
+
+impl Event {
+    /// A plain scalar with no anchor or tag.
+    pub fn plain(value: &str) -> Self {
+        Event::Scalar(Scalar {
+                          value: value.to_owned(),
+                          style: ScalarStyle::Plain,
+                          anchor: None,
+                          tag: None,
+                      })
+    }
+}
+
+/// Collects the events of one document into the form `Deserializer` reads,
+/// checking that collections are closed, that mappings have a value for
+/// every key, and that aliases refer to a complete node defined earlier,
+/// or else handling them according to `policy`.
+#[cfg(feature = "de")]
+pub(crate) fn load<I>(events: I, policy: &AnchorPolicy) -> Result<Loader>
+    where I: Iterator<Item = Event>
+{
+    let mut loader = Loader {
+        events: Vec::new(),
+        aliases: BTreeMap::new(),
+    };
+    // Events carry no position.
+    let marker = Scanner::new("".chars()).mark();
+    let mut anchors = Defined {
+        ids: HashMap::new(),
+        complete: Vec::new(),
+        policy: policy,
+        location: Location::from_marker(&marker),
+    };
+    // Every open collection: whether it is a mapping, its number of children
+    // and its anchor id.
+    let mut open: Vec<(bool, usize, usize)> = Vec::new();
+    let mut done = false;
+    for event in events {
+        if done {
+            return Err(Error::more_than_one_document());
+        }
+        let mapping = match event {
+            Event::MappingStart(_) | Event::MappingEnd => true,
+            _ => false,
+        };
+        let event = match event {
+            Event::Alias(name) => anchors.resolve(&name)?,
+            Event::Scalar(scalar) => {
+                let id = anchors.define(scalar.anchor)?;
+                if id != 0 {
+                    loader.aliases.insert(id, loader.events.len());
+                    anchors.complete(id);
+                }
+                let style = match scalar.style {
+                    ScalarStyle::Plain => TScalarStyle::Plain,
+                    ScalarStyle::SingleQuoted => TScalarStyle::SingleQuoted,
+                    ScalarStyle::DoubleQuoted => TScalarStyle::DoubleQuoted,
+                    ScalarStyle::Literal => TScalarStyle::Literal,
+                    ScalarStyle::Folded => TScalarStyle::Foled,
+                };
+                let tag = scalar.tag.map(|tag| Box::new(token(&tag)));
+                de::Event::Scalar(ScalarString::from(scalar.value), style, tag)
+            }
+            Event::SequenceStart(anchor) | Event::MappingStart(anchor) => {
+                let id = anchors.define(anchor)?;
+                if id != 0 {
+                    loader.aliases.insert(id, loader.events.len());
+                }
+                open.push((mapping, 0, id));
+                let event = if mapping { de::Event::MappingStart } else { de::Event::SequenceStart };
+                loader.events.push((event, marker));
+                continue;
+            }
+            Event::SequenceEnd | Event::MappingEnd => {
+                match open.pop() {
+                    Some((is_mapping, _, _)) if is_mapping != mapping => {
+                        return Err(Error::custom("collection ended by the wrong kind of end event"));
+                    }
+                    Some((true, len, _)) if len % 2 == 1 => {
+                        return Err(Error::custom("mapping has a key without a value"));
+                    }
+                    Some((_, _, id)) => anchors.complete(id),
+                    None => return Err(Error::custom("end event without a collection to end")),
+                }
+                if mapping { de::Event::MappingEnd } else { de::Event::SequenceEnd }
+            }
+        };
+        loader.events.push((event, marker));
+        match open.last_mut() {
+            Some(parent) => parent.1 += 1,
+            None => done = true,
+        }
+    }
+    if !open.is_empty() {
+        return Err(Error::custom("collection without an end event"));
+    }
+    Ok(loader)
+}
+
+/// The anchors defined so far in a stream of events. Each definition gets
+/// a new id, so that an anchor that is defined again refers to the new node
+/// from then on, like in YAML text.
+#[cfg(feature = "de")]
+struct Defined<'a> {
+    ids: HashMap<String, usize>,
+    /// Whether the node with each id has ended, by id minus one.
+    complete: Vec<bool>,
+    policy: &'a AnchorPolicy,
+    /// Where every event is, as events carry no position.
+    location: Location,
+}
+
+#[cfg(feature = "de")]
+impl<'a> Defined<'a> {
+    /// The id of a new node with the given anchor, or 0 if it has none.
+    fn define(&mut self, name: Option<String>) -> Result<usize> {
+        match name {
+            Some(name) => {
+                if self.ids.contains_key(&name) && !self.policy.redefined(&name, self.location) {
+                    return Err(Error::custom(format_args!("anchor `{}` is defined again", name)));
+                }
+                self.complete.push(false);
+                self.ids.insert(name, self.complete.len());
+                Ok(self.complete.len())
+            }
+            None => Ok(0),
+        }
+    }
+
+    fn complete(&mut self, id: usize) {
+        if id != 0 {
+            self.complete[id - 1] = true;
+        }
+    }
+
+    /// The event for an alias: the alias itself, or what the policy puts
+    /// in place of an alias to an undefined anchor.
+    fn resolve(&self, name: &str) -> Result<de::Event> {
+        match self.ids.get(name) {
+            Some(&id) if self.complete[id - 1] => Ok(de::Event::Alias(id)),
+            Some(_) => Err(Error::custom(format_args!("alias `*{}` is inside the node it refers to", name))),
+            None => {
+                match self.policy.undefined(name, self.location) {
+                    Undefined::Fail => Err(Error::custom(format_args!("unknown anchor `{}`", name))),
+                    Undefined::Null => {
+                        Ok(de::Event::Scalar(ScalarString::from("~".to_owned()), TScalarStyle::Plain, None))
+                    }
+                    Undefined::Placeholder => {
+                        let tag = TokenType::Tag("!".to_owned(), "alias".to_owned());
+                        let value = ScalarString::from(format!("*{}", name));
+                        Ok(de::Event::Scalar(value, TScalarStyle::SingleQuoted, Some(Box::new(tag))))
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// The events of a document built by the serializer. Like the emitter, this
+/// gives an anchor to shared nodes that are referred to more than once,
+/// named `id001` and so on in the order they are first written, and
+/// quotes strings that would otherwise be read as something else.
+/// Comments are dropped, as there are no events for them.
+#[cfg(feature = "ser")]
+pub(crate) fn from_tree(doc: &Yaml, anchors: &Anchors) -> Vec<Event> {
+    let mut tree = Tree {
+        anchors: anchors,
+        names: vec![None; anchors.nodes.len()],
+        next_name: 1,
+        events: Vec::new(),
+    };
+    tree.node(doc, None);
+    tree.events
+}
+
+#[cfg(feature = "ser")]
+struct Tree<'a> {
+    anchors: &'a Anchors,
+    names: Vec<Option<String>>,
+    next_name: usize,
+    events: Vec<Event>,
+}
+
+#[cfg(feature = "ser")]
+impl<'a> Tree<'a> {
+    fn node(&mut self, node: &Yaml, anchor: Option<String>) {
+        if let Some((_, node)) = comment::split(node) {
+            return self.node(node, anchor);
+        }
+        let (value, style, tag) = match *node {
+            Yaml::Array(ref seq) => {
+                self.events.push(Event::SequenceStart(anchor));
+                for element in seq {
+                    self.node(element, None);
+                }
+                self.events.push(Event::SequenceEnd);
+                return;
+            }
+            Yaml::Hash(ref hash) => {
+                self.events.push(Event::MappingStart(anchor));
+                for (k, v) in hash {
+                    self.node(k, None);
+                    self.node(v, None);
+                }
+                self.events.push(Event::MappingEnd);
+                return;
+            }
+            Yaml::Alias(id) => {
+                let shared = &self.anchors.nodes[id];
+                if self.anchors.refs[id] < 2 {
+                    return self.node(shared, anchor);
+                }
+                if let Some(ref name) = self.names[id] {
+                    self.events.push(Event::Alias(name.clone()));
+                    return;
+                }
+                let name = format!("id{:03}", self.next_name);
+                self.next_name += 1;
+                self.names[id] = Some(name.clone());
+                return self.node(shared, Some(name));
+            }
+            Yaml::String(ref s) if emitter::need_quotes(s) => (s.clone(), ScalarStyle::DoubleQuoted, None),
+            Yaml::Real(ref s) => {
+                match tags::binary_key_text(s) {
+                    Some(text) => (text.to_owned(), ScalarStyle::Plain, Some("!!binary".to_owned())),
+                    None => (s.clone(), ScalarStyle::Plain, None),
+                }
+            }
+            Yaml::String(ref s) => (s.clone(), ScalarStyle::Plain, None),
+            Yaml::Integer(i) => (i.to_string(), ScalarStyle::Plain, None),
+            Yaml::Boolean(b) => ((if b { "true" } else { "false" }).to_owned(), ScalarStyle::Plain, None),
+            Yaml::Null | Yaml::BadValue => ("~".to_owned(), ScalarStyle::Plain, None),
+        };
+        self.events.push(Event::Scalar(Scalar {
+                                           value: value,
+                                           style: style,
+                                           anchor: anchor,
+                                           tag: tag,
+                                       }));
+    }
+}
+
+/// The scanner token of a tag written like `!!str`, `!local` or
+/// `!<tag:yaml.org,2002:str>`.
+#[cfg(feature = "de")]
+fn token(tag: &str) -> TokenType {
+    if tag.starts_with("!<") && tag.ends_with('>') {
+        return TokenType::Tag(String::new(), tag[2..tag.len() - 1].to_owned());
+    }
+    let split = tag.rfind('!').map_or(0, |bang| bang + 1);
+    TokenType::Tag(tag[..split].to_owned(), tag[split..].to_owned())
+}
@@ -0,0 +1,484 @@
+// Copyright 2018 Serde YAML Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The structure shared by a set of sample documents.
+//!
+//! This is the reverse of `shape`: instead of asking a type what input it
+//! expects, it looks at existing input and works out a type that would
+//! accept all of it. The result can be written out as Rust source to start
+//! a config type from.
+//!
+//! ```rust
+//! extern crate serde_yaml;
+//!
+//! use serde_yaml::infer::{self, InferredSchema};
+//!
+//! # fn main() {
+//! let samples: Vec<serde_yaml::Value> = vec![
+//!     serde_yaml::from_str("{name: web, replicas: 2, ports: [{port: 80}]}").unwrap(),
+//!     serde_yaml::from_str("{name: db, ports: [{port: 5432, tls: true}]}").unwrap(),
+//! ];
+//! let schema = infer::schema(&samples);
+//! assert_eq!(schema.fields()[1].schema(), &InferredSchema::Integer);
+//! assert!(!schema.fields()[1].required());
+//!
+//! assert_eq!(schema.to_rust_source("Service"), "\
+//! #[derive(Debug, Serialize, Deserialize)]
+//! pub struct Service {
+//!     pub name: String,
+//!     pub replicas: Option<i64>,
+//!     pub ports: Vec<Port>,
+//! }
+//!
+//! #[derive(Debug, Serialize, Deserialize)]
+//! pub struct Port {
+//!     pub port: i64,
+//!     pub tls: Option<bool>,
+//! }
+//! ");
+//! # }
+//! ```
+
+use std::collections::VecDeque;
+use std::fmt::Write;
+use std::mem;
+
+use mapping::Mapping;
+use value::Value;
+
+/// The kind of value found at some position of the samples.
+#[derive(Clone, Debug, PartialEq)]
+pub enum InferredSchema {
+    /// No sample has a value here, as for the elements of a sequence that is
+    /// always empty.
+    Unknown,
+    /// Every sample has null here.
+    Null,
+    /// A boolean.
+    Bool,
+    /// An integer.
+    Integer,
+    /// A number that is not always an integer.
+    Float,
+    /// A string.
+    String,
+    /// A value of the given kind in some samples and null in others.
+    Option(Box<InferredSchema>),
+    /// A sequence with elements of the given kind.
+    Sequence(Box<InferredSchema>),
+    /// A mapping with string keys, in the order they were first seen.
+    Struct(Vec<Field>),
+    /// A mapping with keys that are not all strings.
+    Map(Box<InferredSchema>, Box<InferredSchema>),
+    /// The samples disagree on what kind of value this is.
+    Any,
+}
+
+/// One key of an `InferredSchema::Struct`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Field {
+    name: String,
+    schema: InferredSchema,
+    required: bool,
+}
+
+impl Field {
+    /// The key.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The kind of value found under the key.
+    pub fn schema(&self) -> &InferredSchema {
+        &self.schema
+    }
+
+    /// Whether every mapping seen at this position has the key.
+    pub fn required(&self) -> bool {
+        self.required
+    }
+}
+
+/// Works out the structure that all of `values` have in common.
+///
+/// Integers and floats at the same position are merged into `Float`, and a
+/// value that is null in some samples becomes an `Option`. Any other
+/// disagreement between samples gives `Any` at that position.
+pub fn schema(values: &[Value]) -> InferredSchema {
+    values.iter().fold(InferredSchema::Unknown, |schema, value| merge(schema, of(value)))
+}
+
+impl InferredSchema {
+    /// The fields of a struct, or an empty slice for any other kind.
+    pub fn fields(&self) -> &[Field] {
+        match *self {
+            InferredSchema::Struct(ref fields) => fields,
+            _ => &[],
+        }
+    }
+
+    /// Rust source for types that can deserialize the samples with
+    /// `serde_derive`, starting with a type called `name`.
+    ///
+    /// Every struct becomes a Rust struct, named after the key it was found
+    /// under. Keys that are not valid Rust identifiers are converted to snake
+    /// case and get a `#[serde(rename)]` attribute. Keys missing from some
+    /// samples become `Option` fields. Positions that are `Unknown` or `Any`
+    /// are typed as `serde_yaml::Value`.
+    ///
+    /// The output is a starting point and is meant to be edited, for example
+    /// to pick better names or narrower integer types.
+    pub fn to_rust_source(&self, name: &str) -> String {
+        let mut generator = Generator {
+            names: Vec::new(),
+            queue: VecDeque::new(),
+        };
+        let root = type_name(name);
+        generator.names.push(root.clone());
+        let mut out = String::new();
+        match *self {
+            InferredSchema::Struct(ref fields) => {
+                generator.queue.push_back((root, fields));
+            }
+            ref schema => {
+                let ty = generator.rust_type(schema, &root);
+                let _ = writeln!(out, "pub type {} = {};", root, ty);
+            }
+        }
+        while let Some((name, fields)) = generator.queue.pop_front() {
+            if !out.is_empty() {
+                out.push('\n');
+            }
+            generator.write_struct(&mut out, &name, fields);
+        }
+        out
+    }
+
+    /// A JSON Schema, draft-07, that accepts the samples, as a `Value` that
+    /// can be serialized to JSON or YAML.
+    ///
+    /// Structs become objects whose `required` list holds the keys present in
+    /// every sample. Positions that are `Unknown` or `Any` accept anything.
+    ///
+    /// ```rust
+    /// # extern crate serde_yaml;
+    /// #
+    /// # fn yaml(i: &str) -> serde_yaml::Value { serde_yaml::from_str(i).unwrap() }
+    /// # fn main() {
+    /// use serde_yaml::infer;
+    ///
+    /// let schema = infer::schema(&[yaml("{name: web, port: 80}"), yaml("{name: db, port: ~}")]);
+    /// assert_eq!(schema.to_json_schema(), yaml(r#"
+    ///     $schema: http://json-schema.org/draft-07/schema#
+    ///     type: object
+    ///     properties:
+    ///       name: {type: string}
+    ///       port: {type: [integer, "null"]}
+    ///     required: [name, port]
+    /// "#));
+    /// # }
+    /// ```
+    pub fn to_json_schema(&self) -> Value {
+        let mut root = Mapping::new();
+        root.insert("$schema".into(), "http://json-schema.org/draft-07/schema#".into());
+        if let Value::Mapping(schema) = json_schema(self) {
+            root.extend(schema);
+        }
+        Value::Mapping(root)
+    }
+}
+
+fn json_schema(schema: &InferredSchema) -> Value {
+    let mut out = Mapping::new();
+    match *schema {
+        InferredSchema::Unknown | InferredSchema::Any => {}
+        InferredSchema::Null => {
+            out.insert("type".into(), "null".into());
+        }
+        InferredSchema::Bool => {
+            out.insert("type".into(), "boolean".into());
+        }
+        InferredSchema::Integer => {
+            out.insert("type".into(), "integer".into());
+        }
+        InferredSchema::Float => {
+            out.insert("type".into(), "number".into());
+        }
+        InferredSchema::String => {
+            out.insert("type".into(), "string".into());
+        }
+        InferredSchema::Option(ref inner) => {
+            // Every schema built here other than the empty one has a single
+            // `type`, which null is added to.
+            let mut inner = json_schema(inner);
+            if let Value::Mapping(ref mut inner) = inner {
+                if let Some(ty) = inner.get_mut(&Value::from("type")) {
+                    let single = mem::replace(ty, Value::Null);
+                    *ty = Value::Sequence(vec![single, "null".into()]);
+                }
+            }
+            return inner;
+        }
+        InferredSchema::Sequence(ref element) => {
+            out.insert("type".into(), "array".into());
+            if **element != InferredSchema::Unknown {
+                out.insert("items".into(), json_schema(element));
+            }
+        }
+        InferredSchema::Struct(ref fields) => {
+            out.insert("type".into(), "object".into());
+            let properties = fields.iter()
+                .map(|field| (Value::from(&*field.name), json_schema(&field.schema)))
+                .collect();
+            out.insert("properties".into(), Value::Mapping(properties));
+            let required: Vec<Value> = fields.iter()
+                .filter(|field| field.required)
+                .map(|field| Value::from(&*field.name))
+                .collect();
+            if !required.is_empty() {
+                out.insert("required".into(), Value::Sequence(required));
+            }
+        }
+        InferredSchema::Map(_, ref value) => {
+            // JSON object keys are always strings, so only the values can be
+            // described.
+            out.insert("type".into(), "object".into());
+            out.insert("additionalProperties".into(), json_schema(value));
+        }
+    }
+    Value::Mapping(out)
+}
+
+fn of(value: &Value) -> InferredSchema {
+    match *value {
+        Value::Null => InferredSchema::Null,
+        Value::Bool(_) => InferredSchema::Bool,
+        Value::Number(ref n) if n.is_f64() => InferredSchema::Float,
+        Value::Number(_) => InferredSchema::Integer,
+        Value::String(_) => InferredSchema::String,
+        Value::Sequence(ref seq) => {
+            let element = seq.iter().fold(InferredSchema::Unknown, |schema, v| merge(schema, of(v)));
+            InferredSchema::Sequence(Box::new(element))
+        }
+        Value::Mapping(ref mapping) => {
+            if mapping.iter().all(|(k, _)| k.is_string()) {
+                let fields = mapping.iter()
+                    .map(|(k, v)| {
+                             Field {
+                                 name: k.as_str().unwrap_or_default().to_owned(),
+                                 schema: of(v),
+                                 required: true,
+                             }
+                         })
+                    .collect();
+                InferredSchema::Struct(fields)
+            } else {
+                let mut key = InferredSchema::Unknown;
+                let mut value = InferredSchema::Unknown;
+                for (k, v) in mapping {
+                    key = merge(key, of(k));
+                    value = merge(value, of(v));
+                }
+                InferredSchema::Map(Box::new(key), Box::new(value))
+            }
+        }
+    }
+}
+
+fn merge(a: InferredSchema, b: InferredSchema) -> InferredSchema {
+    use self::InferredSchema::*;
+    match (a, b) {
+        (Unknown, other) | (other, Unknown) => other,
+        (Any, _) | (_, Any) => Any,
+        (Null, Null) => Null,
+        (Null, Option(inner)) | (Option(inner), Null) => Option(inner),
+        (Null, other) | (other, Null) => Option(Box::new(other)),
+        (Option(a), Option(b)) => Option(Box::new(merge(*a, *b))),
+        (Option(a), b) | (b, Option(a)) => Option(Box::new(merge(*a, b))),
+        (Bool, Bool) => Bool,
+        (Integer, Integer) => Integer,
+        (Float, Float) | (Integer, Float) | (Float, Integer) => Float,
+        (String, String) => String,
+        (Sequence(a), Sequence(b)) => Sequence(Box::new(merge(*a, *b))),
+        (Struct(a), Struct(b)) => Struct(merge_fields(a, b)),
+        (Map(ak, av), Map(bk, bv)) => Map(Box::new(merge(*ak, *bk)), Box::new(merge(*av, *bv))),
+        (Struct(fields), Map(key, value)) | (Map(key, value), Struct(fields)) => {
+            let value = fields.into_iter().fold(*value, |value, field| merge(value, field.schema));
+            Map(Box::new(merge(*key, String)), Box::new(value))
+        }
+        _ => Any,
+    }
+}
+
+fn merge_fields(a: Vec<Field>, mut b: Vec<Field>) -> Vec<Field> {
+    let mut fields = Vec::with_capacity(a.len());
+    for mut field in a {
+        match b.iter().position(|other| other.name == field.name) {
+            Some(i) => {
+                let other = b.remove(i);
+                field.schema = merge(field.schema, other.schema);
+                field.required = field.required && other.required;
+            }
+            None => field.required = false,
+        }
+        fields.push(field);
+    }
+    for mut field in b {
+        field.required = false;
+        fields.push(field);
+    }
+    fields
+}
+
+struct Generator<'a> {
+    /// Every struct name handed out so far.
+    names: Vec<String>,
+    /// Structs still to be written.
+    queue: VecDeque<(String, &'a [Field])>,
+}
+
+impl<'a> Generator<'a> {
+    fn write_struct(&mut self, out: &mut String, name: &str, fields: &'a [Field]) {
+        let _ = writeln!(out, "#[derive(Debug, Serialize, Deserialize)]");
+        let _ = writeln!(out, "pub struct {} {{", name);
+        let mut idents: Vec<String> = Vec::new();
+        for field in fields {
+            let mut ident = field_name(&field.name);
+            if idents.contains(&ident) {
+                let base = mem::take(&mut ident);
+                ident = (2..).map(|n| format!("{}_{}", base, n)).find(|i| !idents.contains(i)).unwrap();
+            }
+            if ident != field.name {
+                let _ = writeln!(out, "    #[serde(rename = {:?})]", field.name);
+            }
+            let mut ty = self.rust_type(&field.schema, &field.name);
+            if !field.required && !is_option(&field.schema) {
+                ty = format!("Option<{}>", ty);
+            }
+            let _ = writeln!(out, "    pub {}: {},", ident, ty);
+            idents.push(ident);
+        }
+        let _ = writeln!(out, "}}");
+    }
+
+    /// The Rust type for `schema`, found under the key `key`.
+    fn rust_type(&mut self, schema: &'a InferredSchema, key: &str) -> String {
+        match *schema {
+            InferredSchema::Unknown | InferredSchema::Any => "serde_yaml::Value".to_owned(),
+            InferredSchema::Null => "Option<serde_yaml::Value>".to_owned(),
+            InferredSchema::Bool => "bool".to_owned(),
+            InferredSchema::Integer => "i64".to_owned(),
+            InferredSchema::Float => "f64".to_owned(),
+            InferredSchema::String => "String".to_owned(),
+            InferredSchema::Option(ref inner) => format!("Option<{}>", self.rust_type(inner, key)),
+            InferredSchema::Sequence(ref element) => {
+                format!("Vec<{}>", self.rust_type(element, &singular(key)))
+            }
+            InferredSchema::Struct(ref fields) => {
+                let mut name = type_name(key);
+                if self.names.contains(&name) {
+                    let base = mem::take(&mut name);
+                    name = (2..).map(|n| format!("{}{}", base, n)).find(|n| !self.names.contains(n)).unwrap();
+                }
+                self.names.push(name.clone());
+                self.queue.push_back((name.clone(), fields));
+                name
+            }
+            InferredSchema::Map(ref k, ref v) => {
+                let k = match **k {
+                    InferredSchema::Bool => "bool",
+                    InferredSchema::Integer => "i64",
+                    InferredSchema::String => "String",
+                    // Floats and collections are not `Ord`.
+                    _ => return "serde_yaml::Mapping".to_owned(),
+                };
+                format!("std::collections::BTreeMap<{}, {}>", k, self.rust_type(v, key))
+            }
+        }
+    }
+}
+
+fn is_option(schema: &InferredSchema) -> bool {
+    match *schema {
+        InferredSchema::Option(_) | InferredSchema::Null => true,
+        _ => false,
+    }
+}
+
+/// Splits a key into lowercase words at punctuation, spaces and the start of
+/// each uppercase run, so `logLevel`, `log-level` and `LOG_LEVEL` all give
+/// `log level`.
+fn words(key: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut prev_lower = false;
+    for c in key.chars() {
+        if !c.is_alphanumeric() {
+            if !word.is_empty() {
+                words.push(mem::take(&mut word));
+            }
+            prev_lower = false;
+            continue;
+        }
+        if c.is_uppercase() && prev_lower {
+            words.push(mem::take(&mut word));
+        }
+        prev_lower = c.is_lowercase() || c.is_numeric();
+        word.extend(c.to_lowercase());
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+    words
+}
+
+fn field_name(key: &str) -> String {
+    let mut ident = words(key).join("_");
+    if ident.is_empty() || ident.starts_with(|c: char| c.is_numeric()) {
+        ident.insert_str(0, "field_");
+    }
+    if KEYWORDS.contains(&&*ident) {
+        ident.push('_');
+    }
+    ident
+}
+
+fn type_name(key: &str) -> String {
+    let mut name = String::new();
+    for word in words(key) {
+        let mut chars = word.chars();
+        if let Some(first) = chars.next() {
+            name.extend(first.to_uppercase());
+            name.push_str(chars.as_str());
+        }
+    }
+    if name.is_empty() || name.starts_with(|c: char| c.is_numeric()) {
+        name.insert_str(0, "Type");
+    }
+    if name == "Self" {
+        name.push('_');
+    }
+    name
+}
+
+/// A guess at the name of one element of a sequence found under `key`.
+fn singular(key: &str) -> String {
+    if key.ends_with('s') && !key.ends_with("ss") && !key.ends_with("us") && key.len() > 1 {
+        key[..key.len() - 1].to_owned()
+    } else {
+        format!("{}_item", key)
+    }
+}
+
+const KEYWORDS: &[&str] = &["abstract", "as", "async", "await", "become", "box", "break", "const",
+                            "continue", "crate", "do", "dyn", "else", "enum", "extern", "false",
+                            "final", "fn", "for", "gen", "if", "impl", "in", "let", "loop", "macro",
+                            "match", "mod", "move", "mut", "override", "priv", "pub", "ref",
+                            "return", "self", "static", "struct", "super", "trait", "true", "try",
+                            "type", "typeof", "unsafe", "unsized", "use", "virtual", "where",
+                            "while", "yield"];
@@ -0,0 +1,377 @@
+// Copyright 2018 Serde YAML Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Conversions between `Value` and the value types of `serde_json` and `toml`.
+//!
+//! These require the `serde_json` and `toml` features respectively.
+//! Converting into `Value` always succeeds; TOML datetimes become strings.
+//! Converting out of `Value` can hit things the other format has no way to
+//! express, and a `Policy` decides what happens then. The `TryFrom` impls use
+//! `Policy::default()`, which refuses to lose anything.
+//!
+//! ```rust
+//! # #[cfg(feature = "serde_json")]
+//! # fn main() {
+//! # extern crate serde_json;
+//! # extern crate serde_yaml;
+//! use serde_yaml::interop::{self, KeyPolicy, Policy};
+//! use serde_yaml::Value;
+//!
+//! let value: Value = serde_yaml::from_str("{1: one, 2: two}").unwrap();
+//! assert!(interop::to_json(value.clone(), &Policy::default()).is_err());
+//!
+//! let policy = Policy::new().non_string_keys(KeyPolicy::Stringify);
+//! let json = interop::to_json(value, &policy).unwrap();
+//! assert_eq!(json["1"], "one");
+//! # }
+//! #
+//! # #[cfg(not(feature = "serde_json"))]
+//! # fn main() {}
+//! ```
+
+#[cfg(any(feature = "serde_json", feature = "toml"))]
+use serde::de::Error as SError;
+
+#[cfg(feature = "serde_json")]
+use serde_json;
+#[cfg(feature = "toml")]
+use toml;
+
+#[cfg(any(feature = "serde_json", feature = "toml"))]
+use error::{Error, Result};
+#[cfg(any(feature = "serde_json", feature = "toml"))]
+use mapping::Mapping;
+#[cfg(any(feature = "serde_json", feature = "toml"))]
+use value::Value;
+
+/// What to do with decisions that a conversion out of `Value` cannot make
+/// losslessly.
+#[derive(Clone, Debug, Default)]
+pub struct Policy {
+    keys: KeyPolicy,
+    non_finite: FloatPolicy,
+    nulls: NullPolicy,
+    datetimes: DatetimePolicy,
+}
+
+/// How to convert mapping keys that are not strings. Both JSON and TOML
+/// require string keys.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum KeyPolicy {
+    /// Fail the conversion.
+    Error,
+    /// Use the string form of null, boolean and number keys, like `"1"`.
+    /// Sequence and mapping keys still fail the conversion. If the string
+    /// form collides with another key, the entry that comes later wins.
+    Stringify,
+    /// Leave out the entry.
+    Skip,
+}
+
+/// How to convert infinite and NaN floats into JSON, which cannot express
+/// them. TOML can, so this does not apply there.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum FloatPolicy {
+    /// Fail the conversion.
+    Error,
+    /// Use JSON `null`, as `JSON.stringify` does.
+    Null,
+    /// Use the YAML spelling as a string: `".inf"`, `"-.inf"` or `".nan"`.
+    String,
+}
+
+/// How to convert null into TOML, which has no null. JSON does, so this does
+/// not apply there.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum NullPolicy {
+    /// Fail the conversion.
+    Error,
+    /// Leave out the table entry or array element.
+    Skip,
+}
+
+/// How to convert strings into TOML, which has a datetime type where YAML
+/// does not.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum DatetimePolicy {
+    /// Strings stay strings.
+    String,
+    /// Strings that are valid TOML datetimes, like `1979-05-27T07:32:00Z`,
+    /// become datetimes.
+    Detect,
+}
+
+impl Default for KeyPolicy {
+    fn default() -> Self {
+        KeyPolicy::Error
+    }
+}
+
+impl Default for FloatPolicy {
+    fn default() -> Self {
+        FloatPolicy::Error
+    }
+}
+
+impl Default for NullPolicy {
+    fn default() -> Self {
+        NullPolicy::Error
+    }
+}
+
+impl Default for DatetimePolicy {
+    fn default() -> Self {
+        DatetimePolicy::String
+    }
+}
+
+impl Policy {
+    /// The policy that fails on anything that cannot be converted exactly.
+    pub fn new() -> Self {
+        Policy::default()
+    }
+
+    /// Set how mapping keys that are not strings are converted.
+    pub fn non_string_keys(mut self, keys: KeyPolicy) -> Self {
+        self.keys = keys;
+        self
+    }
+
+    /// Set how infinite and NaN floats are converted into JSON.
+    pub fn non_finite_floats(mut self, non_finite: FloatPolicy) -> Self {
+        self.non_finite = non_finite;
+        self
+    }
+
+    /// Set how null is converted into TOML.
+    pub fn nulls(mut self, nulls: NullPolicy) -> Self {
+        self.nulls = nulls;
+        self
+    }
+
+    /// Set whether strings that look like datetimes become TOML datetimes.
+    pub fn datetimes(mut self, datetimes: DatetimePolicy) -> Self {
+        self.datetimes = datetimes;
+        self
+    }
+
+    /// Converts a mapping key according to `self.keys`. `Ok(None)` means the
+    /// entry is to be skipped.
+    #[cfg(any(feature = "serde_json", feature = "toml"))]
+    fn key(&self, key: Value) -> Result<Option<String>> {
+        let key = match key {
+            Value::String(s) => return Ok(Some(s)),
+            key => key,
+        };
+        match self.keys {
+            KeyPolicy::Skip => Ok(None),
+            KeyPolicy::Error => Err(Error::custom(format_args!("mapping key {:?} is not a string", key))),
+            KeyPolicy::Stringify => {
+                match key {
+                    Value::Null => Ok(Some("null".to_owned())),
+                    Value::Bool(b) => Ok(Some(b.to_string())),
+                    Value::Number(n) => Ok(Some(n.to_string())),
+                    key => Err(Error::custom(format_args!("mapping key {:?} cannot be made a string", key))),
+                }
+            }
+        }
+    }
+}
+
+/// Converts a `Value` into a `serde_json::Value` according to `policy`.
+#[cfg(feature = "serde_json")]
+pub fn to_json(value: Value, policy: &Policy) -> Result<serde_json::Value> {
+    Ok(match value {
+           Value::Null => serde_json::Value::Null,
+           Value::Bool(b) => serde_json::Value::Bool(b),
+           Value::Number(n) => {
+               if let Some(u) = n.as_u64() {
+                   serde_json::Value::Number(u.into())
+               } else if let Some(i) = n.as_i64() {
+                   serde_json::Value::Number(i.into())
+               } else {
+                   let f = n.as_f64().unwrap_or(::std::f64::NAN);
+                   match serde_json::Number::from_f64(f) {
+                       Some(n) => serde_json::Value::Number(n),
+                       None => {
+                           match policy.non_finite {
+                               FloatPolicy::Error => {
+                                   return Err(Error::custom(format_args!("JSON cannot represent {}", n)));
+                               }
+                               FloatPolicy::Null => serde_json::Value::Null,
+                               FloatPolicy::String => serde_json::Value::String(non_finite_str(f).to_owned()),
+                           }
+                       }
+                   }
+               }
+           }
+           Value::String(s) => serde_json::Value::String(s),
+           Value::Sequence(seq) => {
+               serde_json::Value::Array(seq.into_iter()
+                                            .map(|v| to_json(v, policy))
+                                            .collect::<Result<_>>()?)
+           }
+           Value::Mapping(mapping) => {
+               let mut object = serde_json::Map::new();
+               for (k, v) in mapping {
+                   if let Some(k) = policy.key(k)? {
+                       object.insert(k, to_json(v, policy)?);
+                   }
+               }
+               serde_json::Value::Object(object)
+           }
+       })
+}
+
+/// Converts a `Value` into a `toml::Value` according to `policy`.
+///
+/// Integers outside the range of `i64` always fail the conversion, as TOML
+/// integers are 64-bit signed.
+#[cfg(feature = "toml")]
+pub fn to_toml(value: Value, policy: &Policy) -> Result<toml::Value> {
+    match to_toml_inner(value, policy)? {
+        Some(toml) => Ok(toml),
+        None => Err(Error::custom("TOML cannot represent null")),
+    }
+}
+
+#[cfg(feature = "toml")]
+fn to_toml_inner(value: Value, policy: &Policy) -> Result<Option<toml::Value>> {
+    Ok(Some(match value {
+                Value::Null => {
+                    return match policy.nulls {
+                               NullPolicy::Error => Err(Error::custom("TOML cannot represent null")),
+                               NullPolicy::Skip => Ok(None),
+                           };
+                }
+                Value::Bool(b) => toml::Value::Boolean(b),
+                Value::Number(n) => {
+                    if let Some(i) = n.as_i64() {
+                        toml::Value::Integer(i)
+                    } else if n.is_f64() {
+                        toml::Value::Float(n.as_f64().unwrap_or(::std::f64::NAN))
+                    } else {
+                        return Err(Error::custom(format_args!("TOML cannot represent the integer {}", n)));
+                    }
+                }
+                Value::String(s) => {
+                    match policy.datetimes {
+                        DatetimePolicy::Detect => {
+                            match s.parse::<toml::value::Datetime>() {
+                                Ok(datetime) => toml::Value::Datetime(datetime),
+                                Err(_) => toml::Value::String(s),
+                            }
+                        }
+                        DatetimePolicy::String => toml::Value::String(s),
+                    }
+                }
+                Value::Sequence(seq) => {
+                    let mut array = Vec::with_capacity(seq.len());
+                    for v in seq {
+                        if let Some(v) = to_toml_inner(v, policy)? {
+                            array.push(v);
+                        }
+                    }
+                    toml::Value::Array(array)
+                }
+                Value::Mapping(mapping) => {
+                    let mut table = toml::value::Table::new();
+                    for (k, v) in mapping {
+                        if let Some(k) = policy.key(k)? {
+                            if let Some(v) = to_toml_inner(v, policy)? {
+                                table.insert(k, v);
+                            }
+                        }
+                    }
+                    toml::Value::Table(table)
+                }
+            }))
+}
+
+#[cfg(feature = "serde_json")]
+fn non_finite_str(f: f64) -> &'static str {
+    if f.is_nan() {
+        ".nan"
+    } else if f > 0.0 {
+        ".inf"
+    } else {
+        "-.inf"
+    }
+}
+
+#[cfg(feature = "serde_json")]
+impl From<serde_json::Value> for Value {
+    /// Convert a `serde_json::Value` into a `Value`. This cannot fail.
+    fn from(json: serde_json::Value) -> Self {
+        match json {
+            serde_json::Value::Null => Value::Null,
+            serde_json::Value::Bool(b) => Value::Bool(b),
+            serde_json::Value::Number(n) => {
+                if let Some(u) = n.as_u64() {
+                    Value::Number(u.into())
+                } else if let Some(i) = n.as_i64() {
+                    Value::Number(i.into())
+                } else {
+                    Value::Number(n.as_f64().unwrap_or(::std::f64::NAN).into())
+                }
+            }
+            serde_json::Value::String(s) => Value::String(s),
+            serde_json::Value::Array(array) => Value::Sequence(array.into_iter().map(Value::from).collect()),
+            serde_json::Value::Object(object) => {
+                let mut mapping = Mapping::with_capacity(object.len());
+                for (k, v) in object {
+                    mapping.insert(Value::String(k), Value::from(v));
+                }
+                Value::Mapping(mapping)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "serde_json")]
+impl ::std::convert::TryFrom<Value> for serde_json::Value {
+    type Error = Error;
+
+    /// Convert a `Value` into a `serde_json::Value` using `Policy::default()`.
+    fn try_from(value: Value) -> Result<Self> {
+        to_json(value, &Policy::default())
+    }
+}
+
+#[cfg(feature = "toml")]
+impl From<toml::Value> for Value {
+    /// Convert a `toml::Value` into a `Value`. Datetimes become strings in
+    /// TOML syntax. This cannot fail.
+    fn from(toml: toml::Value) -> Self {
+        match toml {
+            toml::Value::String(s) => Value::String(s),
+            toml::Value::Integer(i) => Value::Number(i.into()),
+            toml::Value::Float(f) => Value::Number(f.into()),
+            toml::Value::Boolean(b) => Value::Bool(b),
+            toml::Value::Datetime(datetime) => Value::String(datetime.to_string()),
+            toml::Value::Array(array) => Value::Sequence(array.into_iter().map(Value::from).collect()),
+            toml::Value::Table(table) => {
+                let mut mapping = Mapping::with_capacity(table.len());
+                for (k, v) in table {
+                    mapping.insert(Value::String(k), Value::from(v));
+                }
+                Value::Mapping(mapping)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "toml")]
+impl ::std::convert::TryFrom<Value> for toml::Value {
+    type Error = Error;
+
+    /// Convert a `Value` into a `toml::Value` using `Policy::default()`.
+    fn try_from(value: Value) -> Result<Self> {
+        to_toml(value, &Policy::default())
+    }
+}
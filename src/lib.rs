@@ -30,7 +30,7 @@
 //!
 //! // Serialize it to a YAML string.
 //! let s = serde_yaml::to_string(&map).unwrap();
-//! assert_eq!(s, "---\nx: 1\n\"y\": 2");
+//! assert_eq!(s, "---\nx: 1.0\n\"y\": 2.0");
 //!
 //! // Deserialize it back to a Rust type.
 //! let deserialized_map: BTreeMap<String, f64> = serde_yaml::from_str(&s).unwrap();
@@ -53,47 +53,209 @@
 //! let point = Point { x: 1.0, y: 2.0 };
 //!
 //! let s = serde_yaml::to_string(&point).unwrap();
-//! assert_eq!(s, "---\nx: 1\n\"y\": 2");
+//! assert_eq!(s, "---\nx: 1.0\n\"y\": 2.0");
 //!
 //! let deserialized_point: Point = serde_yaml::from_str(&s).unwrap();
 //! assert_eq!(point, deserialized_point);
 //! # }
 //! ```
+//!
+//! ## Threads
+//!
+//! `Value`, `Mapping`, `Number` and `Error` are `Send` and `Sync`, and so
+//! are the types that hold options, like `YamlFormat`, `Profile`, `Limits`
+//! and `TagPolicy`. A parsed configuration or a configured format can be put
+//! in an `Arc` and shared between threads. The exception is `Deserializer`,
+//! which can wrap any reader and so is neither.
+//!
+//! ## Features
+//!
+//! Everything is built by default. A program that only reads YAML into its
+//! own types can leave out `Value` and the emitter:
+//!
+//! ```toml
+//! [dependencies]
+//! serde_yaml = { version = "0.7", default-features = false, features = ["std", "de"] }
+//! ```
+//!
+//! - `std`: required, as yaml-rust needs the standard library.
+//! - `de`: `from_str` and the other deserializing functions, and
+//!   `Deserializer`.
+//! - `ser`: `to_string` and the other serializing functions, `Writer` and
+//!   `YamlFormat`.
+//! - `value`: `Value`, `Mapping` and `Number` and the modules built on them.
+//!   Needs `ser`, which `to_value` goes through.
+//! - `limits`: `validate` and `Limits`.
+//! - `tags`: `TagPolicy`.
 
 #![doc(html_root_url = "https://docs.rs/serde_yaml/0.7.3")]
 
-#![cfg_attr(feature = "cargo-clippy", deny(clippy, clippy_pedantic))]
-// Whitelisted clippy_pedantic lints
-#![cfg_attr(feature = "cargo-clippy", allow(
+// Whitelisted clippy lints
+#![allow(
 // private Deserializer::next
-    should_implement_trait,
+    clippy::should_implement_trait,
 // things are often more readable this way
-    cast_lossless,
-    single_match_else,
-    stutter,
-    use_self,
+    clippy::cast_lossless,
+    clippy::single_match_else,
+    clippy::module_name_repetitions,
+    clippy::use_self,
+    clippy::match_like_matches_macro,
+    clippy::needless_borrowed_reference,
+    clippy::manual_strip,
+    clippy::unnecessary_map_or,
+// consistent with the style of the older code
+    clippy::redundant_field_names,
+    clippy::multiple_bound_locations,
+    clippy::legacy_numeric_constants,
+    clippy::needless_lifetimes,
+    clippy::derivable_impls,
+    mismatched_lifetime_syntaxes,
 // not practical
-    missing_docs_in_private_items,
+    clippy::missing_docs_in_private_items,
 // not stable
-    empty_enum,
-))]
+    clippy::empty_enums,
+)]
+// helpers shared by the deserializer, the serializer and `Value` go unused
+// when only some of them are built
+#![cfg_attr(not(all(feature = "de", feature = "ser", feature = "value")), allow(dead_code))]
 
+#[cfg(feature = "bumpalo")]
+extern crate bumpalo;
 extern crate linked_hash_map;
 extern crate num_traits;
+#[cfg(feature = "regex")]
+extern crate regex;
+extern crate ryu;
 #[macro_use]
 extern crate serde;
+#[cfg(feature = "schemars")]
+extern crate schemars;
+#[cfg(feature = "serde_json")]
+extern crate serde_json;
+#[cfg(feature = "derive")]
+extern crate serde_yaml_derive;
+#[cfg(feature = "toml")]
+extern crate toml;
 extern crate yaml_rust;
 
-pub use self::de::{from_reader, from_slice, from_str};
-pub use self::ser::{to_string, to_vec, to_writer};
-pub use self::value::{Sequence, Value, from_value, to_value, Number};
-pub use self::error::{Error, Location, Result};
+#[cfg(not(feature = "std"))]
+compile_error!("serde_yaml needs the `std` feature, because yaml-rust needs std");
+
+#[cfg(feature = "de")]
+pub use self::anchor::{AnchorPolicy, AnchorScope, AnchorWarning, AnchorWarnings};
+#[cfg(all(feature = "de", feature = "value"))]
+pub use self::compare::assert_snapshot_stable;
+#[cfg(feature = "de")]
+pub use self::dates::{Dates, Times};
+#[cfg(feature = "de")]
+pub use self::de::{from_fragment, from_reader, from_slice, from_slice_partial, from_str, from_str_all,
+                    from_str_documents, from_str_partial, Deserializer, DuplicateKeys};
+#[cfg(all(feature = "bumpalo", feature = "de", feature = "value"))]
+pub use self::arena::from_str_in;
+#[cfg(feature = "ser")]
+pub use self::emitter::{NonFiniteSpelling, StringStyles};
+#[cfg(feature = "derive")]
+pub use serde_yaml_derive::styled;
+#[cfg(feature = "ser")]
+pub use self::ser::{to_events, to_fmt_writer, to_string, to_string_compact, to_string_indented, to_string_pretty,
+                    to_vec, to_writer, to_writer_multi, Writer, YamlFormat};
+#[cfg(feature = "value")]
+pub use self::value::{DisplayDiff, ExactDebug, MappingBuilder, Migration, Sequence, SequenceBuilder, SequenceDefaults,
+                      Value, from_value, to_value, Number};
+#[cfg(feature = "de")]
+pub use self::deprecate::{Deprecations, Warning};
+pub use self::error::{Error, ErrorKind, Location, Result};
+#[cfg(feature = "value")]
 pub use self::mapping::Mapping;
+#[cfg(feature = "de")]
+pub use self::merge::MergeKeys;
+pub use self::number::NumberDialect;
+#[cfg(feature = "de")]
+pub use self::profile::Profile;
+#[cfg(all(feature = "de", feature = "value"))]
+pub use self::spanned::{parse_tolerant, SpannedValue};
+#[cfg(feature = "de")]
+pub use self::stream::Parser;
+#[cfg(feature = "de")]
+pub use self::tabs::Tabs;
+#[cfg(feature = "tags")]
+pub use self::tags::{TagAction, TagPolicy};
+#[cfg(feature = "limits")]
+pub use self::validate::{validate, validate_with, DocumentStats, Limits};
 
+#[cfg(feature = "de")]
+mod anchor;
+#[cfg(any(feature = "de", feature = "ser"))]
+mod dates;
+#[cfg(feature = "de")]
 mod de;
+#[cfg(feature = "de")]
+mod deprecate;
+#[cfg(feature = "ser")]
+mod emitter;
+#[cfg(feature = "ser")]
 mod ser;
-mod value;
 mod error;
 mod path;
+#[cfg(feature = "de")]
+mod profile;
+#[cfg(feature = "value")]
 mod mapping;
+#[cfg(feature = "de")]
+mod merge;
 mod number;
+#[cfg(feature = "de")]
+mod scalar;
+#[cfg(feature = "de")]
+mod stream;
+#[cfg(feature = "de")]
+mod tabs;
+mod tags;
+#[cfg(feature = "limits")]
+mod validate;
+#[cfg(all(feature = "bumpalo", feature = "de", feature = "value"))]
+pub mod arena;
+#[cfg(all(feature = "de", feature = "value"))]
+pub mod borrowed;
+#[cfg(feature = "ser")]
+pub mod comment;
+#[cfg(all(feature = "de", feature = "value"))]
+pub mod compare;
+#[cfg(all(feature = "de", feature = "value"))]
+pub mod constraints;
+#[cfg(all(feature = "de", feature = "value"))]
+pub mod deep;
+#[cfg(any(feature = "de", feature = "ser"))]
+pub mod event;
+#[cfg(feature = "value")]
+pub mod infer;
+#[cfg(feature = "value")]
+pub mod interop;
+#[cfg(all(feature = "de", feature = "value"))]
+pub mod lint;
+#[cfg(feature = "ser")]
+pub mod quoting;
+#[cfg(all(feature = "de", feature = "value"))]
+pub mod route;
+#[cfg(feature = "schemars")]
+pub mod schema;
+#[cfg(feature = "shared")]
+pub mod shared;
+pub mod shape;
+#[cfg(feature = "value")]
+pub mod slab;
+#[cfg(all(feature = "de", feature = "value"))]
+pub mod splice;
+#[cfg(all(feature = "de", feature = "value"))]
+pub mod spanned;
+#[cfg(feature = "ser")]
+pub mod style;
+#[cfg(feature = "value")]
+pub mod value;
+pub mod with;
+
+// Used by the code that `#[styled]` generates. Not public API.
+#[doc(hidden)]
+pub mod __private {
+    pub use serde::ser::{Serialize, Serializer};
+}
@@ -0,0 +1,259 @@
+// Copyright 2018 Serde YAML Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Checks for YAML that parses but is likely to be wrong or to be read
+//! differently by other tools.
+//!
+//! ```rust
+//! use serde_yaml::lint::{self, Rule};
+//!
+//! let findings = lint::check("name: web\nname: db\nenabled: yes \n");
+//! let rules: Vec<Rule> = findings.iter().map(|finding| finding.rule()).collect();
+//! assert_eq!(rules, [Rule::DuplicateKey, Rule::AmbiguousScalar, Rule::TrailingSpaces]);
+//! assert_eq!(findings[0].location().line(), 2);
+//! assert_eq!(findings[0].to_string(), "2:1: duplicate key `name`, first defined on line 1");
+//! ```
+
+use std::collections::BTreeSet;
+use std::fmt::{self, Display};
+
+use yaml_rust::scanner::{Marker, Scanner, TScalarStyle, TokenType};
+
+use anchor::{AnchorPolicy, AnchorScope};
+use de::{Event, Loader};
+use error::Location;
+use value::{key_text, Value};
+
+/// The kinds of problem that `check` looks for.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Rule {
+    /// The document could not be parsed. Structural checks are skipped for
+    /// the input.
+    Syntax,
+    /// A key occurs more than once in the same mapping. When deserializing,
+    /// the last occurrence silently wins.
+    DuplicateKey,
+    /// An alias refers to an anchor that is not defined earlier in the same
+    /// document.
+    UnresolvedAlias,
+    /// A line is indented with tabs, which YAML does not allow.
+    TabIndentation,
+    /// A line ends in spaces or tabs.
+    TrailingSpaces,
+    /// A plain scalar that YAML 1.1 tools read differently from this crate,
+    /// such as `yes`, `0755` or `1:30`, or a number whose spelling is lost
+    /// when it is read, such as the version `1.10`.
+    AmbiguousScalar,
+}
+
+/// A problem found by `check`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Finding {
+    rule: Rule,
+    location: Location,
+    message: String,
+}
+
+impl Finding {
+    /// Which check produced this finding.
+    pub fn rule(&self) -> Rule {
+        self.rule
+    }
+
+    /// Where in the input the problem is.
+    pub fn location(&self) -> Location {
+        self.location
+    }
+
+    /// A description of the problem.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl Display for Finding {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter,
+               "{}:{}: {}",
+               self.location.line(),
+               self.location.column(),
+               self.message)
+    }
+}
+
+/// Runs every check over all documents in `s` and returns the findings in
+/// the order they occur in the input.
+pub fn check(s: &str) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    check_lines(&mut findings, s);
+    check_aliases(&mut findings, s);
+    // Undefined aliases have been reported already. As placeholders they do
+    // not stop the parser, so the other checks still run on the rest.
+    match Loader::load_with(s, &AnchorPolicy::Placeholder, AnchorScope::Document) {
+        Ok(loader) => {
+            let mut pos = 0;
+            while pos < loader.events.len() {
+                walk(&mut findings, &loader, &mut pos);
+            }
+        }
+        Err(err) => {
+            findings.push(Finding {
+                              rule: Rule::Syntax,
+                              location: err.location().unwrap_or_else(|| Location::new(0, 1, 1)),
+                              message: err.to_string(),
+                          });
+        }
+    }
+    findings.sort_by_key(|finding| (finding.location.index(), finding.rule));
+    findings
+}
+
+fn check_lines(findings: &mut Vec<Finding>, s: &str) {
+    let mut index = 0;
+    for (i, raw) in s.split('\n').enumerate() {
+        let line = raw.trim_end_matches('\r');
+        let chars: Vec<char> = line.chars().collect();
+        let indent = chars.iter().take_while(|&&c| c == ' ' || c == '\t').count();
+        if indent < chars.len() {
+            if let Some(tab) = chars[..indent].iter().position(|&c| c == '\t') {
+                findings.push(Finding {
+                                  rule: Rule::TabIndentation,
+                                  location: Location::new(index + tab, i + 1, tab + 1),
+                                  message: "tab character in indentation".to_owned(),
+                              });
+            }
+        }
+        let content = chars.iter().rposition(|&c| c != ' ' && c != '\t').map_or(0, |last| last + 1);
+        if content < chars.len() {
+            findings.push(Finding {
+                              rule: Rule::TrailingSpaces,
+                              location: Location::new(index + content, i + 1, content + 1),
+                              message: "trailing whitespace".to_owned(),
+                          });
+        }
+        // Plus one for the newline; a dropped `\r` counts as well.
+        index += raw.chars().count() + 1;
+    }
+}
+
+/// Reports aliases to anchors not defined earlier in their document.
+fn check_aliases(findings: &mut Vec<Finding>, s: &str) {
+    let mut anchors = BTreeSet::new();
+    for token in Scanner::new(s.chars()) {
+        match token.1 {
+            TokenType::DocumentStart | TokenType::DocumentEnd => anchors.clear(),
+            TokenType::Anchor(name) => {
+                anchors.insert(name);
+            }
+            TokenType::Alias(ref name) if !anchors.contains(name) => {
+                findings.push(Finding {
+                                  rule: Rule::UnresolvedAlias,
+                                  location: Location::from_marker(&token.0),
+                                  message: format!("alias `*{}` does not refer to an anchor defined \
+                                                    earlier in the document",
+                                                   name),
+                              });
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Checks the node starting at `*pos` and moves `*pos` past it.
+fn walk(findings: &mut Vec<Finding>, loader: &Loader, pos: &mut usize) {
+    let (ref event, marker) = loader.events[*pos];
+    match *event {
+        Event::Alias(_) => *pos += 1,
+        Event::Scalar(ref v, style, ref tag) => {
+            *pos += 1;
+            if style == TScalarStyle::Plain && tag.is_none() {
+                if let Some(reason) = ambiguity(v) {
+                    findings.push(Finding {
+                                      rule: Rule::AmbiguousScalar,
+                                      location: Location::from_marker(&marker),
+                                      message: format!("`{}` {}; quote it if a string is meant",
+                                                       &**v,
+                                                       reason),
+                                  });
+                }
+            }
+        }
+        Event::SequenceStart => {
+            *pos += 1;
+            while loader.events[*pos].0 != Event::SequenceEnd {
+                walk(findings, loader, pos);
+            }
+            *pos += 1;
+        }
+        Event::MappingStart => {
+            *pos += 1;
+            let mut keys: Vec<(Value, Marker)> = Vec::new();
+            while loader.events[*pos].0 != Event::MappingEnd {
+                let key_marker = loader.events[*pos].1;
+                let start = *pos;
+                walk(findings, loader, pos);
+                let mut key_pos = start;
+                if let Ok(key) = loader.deserialize_at::<Value>(&mut key_pos) {
+                    match keys.iter().find(|entry| entry.0 == key) {
+                        Some(first) => {
+                            findings.push(Finding {
+                                              rule: Rule::DuplicateKey,
+                                              location: Location::from_marker(&key_marker),
+                                              message: format!("duplicate key `{}`, first defined on line {}",
+                                                               key_text(&key),
+                                                               first.1.line()),
+                                          });
+                        }
+                        None => keys.push((key, key_marker)),
+                    }
+                }
+                walk(findings, loader, pos);
+            }
+            *pos += 1;
+        }
+        Event::SequenceEnd | Event::MappingEnd => *pos += 1,
+    }
+}
+
+/// Why a plain scalar may not mean what its author intended, if it may not.
+fn ambiguity(v: &str) -> Option<&'static str> {
+    match v {
+        "y" | "Y" | "yes" | "Yes" | "YES" | "n" | "N" | "no" | "No" | "NO" | "on" | "On" | "ON" |
+        "off" | "Off" | "OFF" | "True" | "TRUE" | "False" | "FALSE" => {
+            return Some("is a string here but a boolean in YAML 1.1")
+        }
+        "Null" | "NULL" => return Some("is a string here but null in YAML 1.1"),
+        _ => {}
+    }
+    let digits = v.trim_start_matches(&['-', '+'][..]);
+    if digits.len() > 1 && digits.starts_with('0') && digits.bytes().all(|b| b.is_ascii_digit()) {
+        return Some("is a decimal integer here but octal in YAML 1.1");
+    }
+    if is_sexagesimal(digits) {
+        return Some("is a string here but a base 60 number in YAML 1.1");
+    }
+    if let Some(point) = v.find('.') {
+        let fraction = &v[point + 1..];
+        if v.parse::<f64>().is_ok() && fraction.ends_with('0') &&
+           fraction.bytes().all(|b| b.is_ascii_digit()) {
+            return Some("is read as a number, dropping its trailing zeros");
+        }
+    }
+    None
+}
+
+/// Like `1:30` or `190:20:30`.
+fn is_sexagesimal(v: &str) -> bool {
+    let mut parts = v.split(':');
+    let first = parts.next().unwrap_or("");
+    let rest: Vec<&str> = parts.collect();
+    !rest.is_empty() && !first.is_empty() && first.bytes().all(|b| b.is_ascii_digit()) &&
+    rest.iter().all(|part| {
+        part.len() <= 2 && !part.is_empty() && part.bytes().all(|b| b.is_ascii_digit()) && part < &"60"
+    })
+}
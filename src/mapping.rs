@@ -6,17 +6,25 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
+use std::cmp::Ordering;
 use std::fmt;
 use std::iter::FromIterator;
+use std::mem;
 use std::ops::{Index, IndexMut};
 
 use linked_hash_map::{self, LinkedHashMap};
 use serde::{self, Serialize, Deserialize, Deserializer};
 
-use value::Value;
+use value::{canonical_cmp, to_value, Value};
 
 /// A YAML mapping in which the keys and values are both `serde_yaml::Value`.
-#[derive(Clone, Debug, Default, Eq, Hash, PartialEq, PartialOrd)]
+///
+/// Entries keep the order in which they were inserted, or in which they
+/// appear in the document when deserialized, so a `Mapping` field holds an
+/// arbitrary section of a config file the way it was written. A key that
+/// appears again replaces the earlier value and moves to the end unless the
+/// deserializer is told otherwise with `Deserializer::duplicate_keys`.
+#[derive(Clone, Default, Eq, Hash, PartialEq, PartialOrd)]
 pub struct Mapping {
     map: LinkedHashMap<Value, Value>,
 }
@@ -57,6 +65,35 @@ impl Mapping {
         self.map.get(k)
     }
 
+    /// Looks up the entry whose key is an enum variant, as a typed key like
+    /// the variant of a `HashMap<Env, _>` is written. The variant is turned
+    /// into a `Value` the way `to_value` does, so a unit variant becomes its
+    /// name, after any `#[serde(rename)]`.
+    ///
+    /// ```rust
+    /// # #[macro_use] extern crate serde_derive;
+    /// # extern crate serde_yaml;
+    /// #
+    /// #[derive(Serialize)]
+    /// #[serde(rename_all = "lowercase")]
+    /// enum Env {
+    ///     Staging,
+    ///     Production,
+    /// }
+    ///
+    /// # fn main() {
+    /// let value: serde_yaml::Value = serde_yaml::from_str("staging: 1\nproduction: 3\n").unwrap();
+    /// let replicas = value.as_mapping().unwrap();
+    /// assert_eq!(replicas.get_by_variant(&Env::Production).unwrap(), 3);
+    /// # }
+    /// ```
+    pub fn get_by_variant<T: ?Sized>(&self, variant: &T) -> Option<&Value>
+        where T: Serialize
+    {
+        let key = to_value(variant).ok()?;
+        self.map.get(&key)
+    }
+
     #[inline]
     pub fn get_mut(&mut self, k: &Value) -> Option<&mut Value> {
         self.map.get_mut(k)
@@ -92,10 +129,65 @@ impl Mapping {
         Iter { iter: self.map.iter() }
     }
 
+    /// Iterates over the entries whose key is a string, in order, with the
+    /// key as a `&str`. Entries with other keys, like numbers, are skipped.
+    ///
+    /// ```rust
+    /// # extern crate serde_yaml;
+    /// #
+    /// # fn main() {
+    /// let value: serde_yaml::Value = serde_yaml::from_str("name: web\n80: http\nport: 8080\n").unwrap();
+    /// let keys: Vec<&str> = value.as_mapping().unwrap().iter_str().map(|(k, _)| k).collect();
+    /// assert_eq!(keys, ["name", "port"]);
+    /// # }
+    /// ```
+    #[inline]
+    pub fn iter_str(&self) -> IterStr {
+        IterStr { iter: self.map.iter() }
+    }
+
     #[inline]
     pub fn iter_mut(&mut self) -> IterMut {
         IterMut { iter: self.map.iter_mut() }
     }
+
+    /// Sorts the entries by key, in the order of
+    /// `Value::sort_keys_recursively`, leaving the values as they are.
+    ///
+    /// ```rust
+    /// # extern crate serde_yaml;
+    /// #
+    /// # fn main() {
+    /// let mut value: serde_yaml::Value = serde_yaml::from_str("{b: {z: 1, x: 2}, a: 3, 1: 4}").unwrap();
+    /// value.as_mapping_mut().unwrap().sort_keys();
+    /// assert_eq!(serde_yaml::to_string(&value).unwrap(), "---\n1: 4\na: 3\nb:\n  z: 1\n  x: 2");
+    /// # }
+    /// ```
+    pub fn sort_keys(&mut self) {
+        self.sort_by(|k1, _, k2, _| canonical_cmp(k1, k2));
+    }
+
+    /// Sorts the entries with a comparator over the key and value of two
+    /// entries. The sort is stable, so entries that compare equal keep their
+    /// order.
+    ///
+    /// ```rust
+    /// # extern crate serde_yaml;
+    /// #
+    /// # fn main() {
+    /// let mut value: serde_yaml::Value = serde_yaml::from_str("{a: 2, b: 1, c: 2}").unwrap();
+    /// value.as_mapping_mut().unwrap().sort_by(|_, v1, _, v2| v2.as_u64().cmp(&v1.as_u64()));
+    /// assert_eq!(serde_yaml::to_string(&value).unwrap(), "---\na: 2\nc: 2\nb: 1");
+    /// # }
+    /// ```
+    pub fn sort_by<F>(&mut self, mut compare: F)
+        where F: FnMut(&Value, &Value, &Value, &Value) -> Ordering
+    {
+        let map = mem::replace(&mut self.map, LinkedHashMap::new());
+        let mut entries: Vec<(Value, Value)> = map.into_iter().collect();
+        entries.sort_by(|a, b| compare(&a.0, &a.1, &b.0, &b.1));
+        self.map = entries.into_iter().collect();
+    }
 }
 
 impl<'a> Index<&'a Value> for Mapping {
@@ -165,6 +257,22 @@ impl<'a> IntoIterator for &'a Mapping {
     }
 }
 
+pub struct IterStr<'a> {
+    iter: linked_hash_map::Iter<'a, Value, Value>,
+}
+
+impl<'a> Iterator for IterStr<'a> {
+    type Item = (&'a str, &'a Value);
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.by_ref().filter_map(|(k, v)| k.as_str().map(|k| (k, v))).next()
+    }
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, self.iter.size_hint().1)
+    }
+}
+
 pub struct IterMut<'a> {
     iter: linked_hash_map::IterMut<'a, Value, Value>,
 }
@@ -0,0 +1,288 @@
+// Copyright 2018 Serde YAML Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use yaml_rust::scanner::{Marker, TScalarStyle};
+
+use de::{Event, Loader};
+use error::{Error, Location, Result};
+
+/// What to do with the YAML 1.1 merge key `<<`, which copies the entries of
+/// other mappings into the one it is in.
+///
+/// ```rust
+/// # #[macro_use] extern crate serde_derive;
+/// # extern crate serde;
+/// # extern crate serde_yaml;
+/// #
+/// # fn main() {
+/// use serde::Deserialize;
+/// use serde_yaml::{Deserializer, MergeKeys};
+///
+/// #[derive(Deserialize, Debug)]
+/// #[serde(deny_unknown_fields)]
+/// struct Server {
+///     host: String,
+///     port: u16,
+/// }
+///
+/// let yaml = "
+/// base: &base {host: localhost, port: 80}
+/// web:
+///   <<: *base
+///   port: 8080
+/// ";
+/// let de = Deserializer::from_str(yaml).merge_keys(MergeKeys::Expand);
+/// let servers = std::collections::BTreeMap::<String, Server>::deserialize(de).unwrap();
+/// assert_eq!(servers["web"].host, "localhost");
+/// assert_eq!(servers["web"].port, 8080);
+///
+/// let de = Deserializer::from_str(yaml).merge_keys(MergeKeys::Error);
+/// let err = std::collections::BTreeMap::<String, Server>::deserialize(de).unwrap_err();
+/// assert_eq!(err.to_string(), "merge keys are not allowed at line 4 column 3");
+/// # }
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum MergeKeys {
+    /// Hand `<<` to the type being deserialized as an ordinary key. This is
+    /// the default. A struct with `#[serde(deny_unknown_fields)]` rejects
+    /// it as an unknown field.
+    Keep,
+    /// Replace `<<` by the entries of the mapping it names, or of each
+    /// mapping of a sequence it names, that the mapping does not have
+    /// itself. Earlier mappings of a sequence win over later ones. Checks
+    /// like `deny_unknown_fields` then see the merged mapping, without `<<`.
+    Expand,
+    /// Fail at the first `<<`, for input that has to be readable by tools
+    /// that do not know merge keys.
+    Error,
+}
+
+impl Default for MergeKeys {
+    fn default() -> Self {
+        MergeKeys::Keep
+    }
+}
+
+/// Applies the policy to the events of the loader.
+pub(crate) fn apply(loader: Loader, policy: MergeKeys) -> Result<Loader> {
+    if policy == MergeKeys::Keep {
+        return Ok(loader);
+    }
+    let marker = match first_merge_key(&loader) {
+        Some(marker) => marker,
+        None => return Ok(loader),
+    };
+    if policy == MergeKeys::Error {
+        return Err(Error::at("merge keys are not allowed", Location::from_marker(&marker)));
+    }
+    let mut merger = Merger {
+        loader: &loader,
+        events: Vec::with_capacity(loader.events.len()),
+        moved: BTreeMap::new(),
+        open: Vec::new(),
+    };
+    let mut pos = 0;
+    while pos < loader.events.len() {
+        pos = merger.copy(pos)?;
+    }
+    // Aliases go to where their node was first copied.
+    let aliases = loader.aliases
+        .iter()
+        .filter_map(|(&id, index)| merger.moved.get(index).map(|&moved| (id, moved)))
+        .collect();
+    Ok(Loader {
+           events: merger.events,
+           aliases: aliases,
+       })
+}
+
+/// Where the first `<<` that is the key of a mapping entry is. A `<<` that
+/// is a value or a sequence element is a string like any other.
+fn first_merge_key(loader: &Loader) -> Option<Marker> {
+    // For each open collection, whether it is a mapping whose next node is
+    // a key. Sequences have None.
+    let mut open: Vec<Option<bool>> = Vec::new();
+    for &(ref event, marker) in &loader.events {
+        let is_key = open.last() == Some(&Some(true));
+        if *event != Event::SequenceEnd && *event != Event::MappingEnd {
+            if let Some(&mut Some(ref mut key)) = open.last_mut() {
+                *key = !*key;
+            }
+        }
+        match *event {
+            Event::MappingStart => open.push(Some(true)),
+            Event::SequenceStart => open.push(None),
+            Event::SequenceEnd | Event::MappingEnd => {
+                open.pop();
+            }
+            _ if is_key && is_merge_key(event) => return Some(marker),
+            _ => {}
+        }
+    }
+    None
+}
+
+fn is_merge_key(event: &Event) -> bool {
+    match *event {
+        Event::Scalar(ref v, TScalarStyle::Plain, None) => **v == *"<<",
+        _ => false,
+    }
+}
+
+struct Merger<'a> {
+    loader: &'a Loader,
+    events: Vec<(Event, Marker)>,
+    /// The index in `events` of the first copy of each node of the loader.
+    moved: BTreeMap<usize, usize>,
+    /// The mappings being copied or having their entries collected, to catch
+    /// a merge key that names a mapping containing it.
+    open: Vec<usize>,
+}
+
+impl<'a> Merger<'a> {
+    /// Copies the node at `pos`, with the merge keys of its mappings
+    /// expanded, and returns the index of the event after it.
+    fn copy(&mut self, pos: usize) -> Result<usize> {
+        self.moved.entry(pos).or_insert(self.events.len());
+        let (ref event, marker) = self.loader.events[pos];
+        match *event {
+            Event::MappingStart => {
+                self.events.push((Event::MappingStart, marker));
+                self.open.push(pos);
+                for (k, v) in self.entries(pos)? {
+                    self.copy(k)?;
+                    self.copy(v)?;
+                }
+                self.open.pop();
+                let end = self.skip(pos);
+                self.events.push((Event::MappingEnd, self.loader.events[end - 1].1));
+                Ok(end)
+            }
+            Event::SequenceStart => {
+                self.events.push((Event::SequenceStart, marker));
+                let mut pos = pos + 1;
+                while self.loader.events[pos].0 != Event::SequenceEnd {
+                    pos = self.copy(pos)?;
+                }
+                self.events.push((Event::SequenceEnd, self.loader.events[pos].1));
+                Ok(pos + 1)
+            }
+            Event::SequenceEnd | Event::MappingEnd => panic!("unexpected end of collection"),
+            Event::Alias(id) => {
+                self.events.push((Event::Alias(id), marker));
+                Ok(pos + 1)
+            }
+            Event::Scalar(ref v, style, ref tag) => {
+                self.events.push((Event::Scalar(v.clone(), style, tag.clone()), marker));
+                Ok(pos + 1)
+            }
+        }
+    }
+
+    /// The entries of the mapping at `pos` as the indices of their key and
+    /// value, with merge keys replaced by the entries they bring in.
+    fn entries(&mut self, pos: usize) -> Result<Vec<(usize, usize)>> {
+        self.open.push(pos);
+        let mut own = Vec::new();
+        let mut p = pos + 1;
+        while self.loader.events[p].0 != Event::MappingEnd {
+            let k = p;
+            let v = self.skip(k);
+            p = self.skip(v);
+            own.push((k, v));
+        }
+        let mut seen: BTreeSet<&str> = own.iter()
+            .filter(|&&(k, _)| !is_merge_key(&self.loader.events[k].0))
+            .filter_map(|&(k, _)| self.key(k))
+            .collect();
+        let mut entries = Vec::new();
+        for (k, v) in own {
+            if !is_merge_key(&self.loader.events[k].0) {
+                entries.push((k, v));
+                continue;
+            }
+            for source in self.sources(v)? {
+                if self.open.contains(&source) {
+                    let marker = self.loader.events[v].1;
+                    return Err(Error::at("merge key refers to a mapping that contains it",
+                                         Location::from_marker(&marker)));
+                }
+                for (k, v) in self.entries(source)? {
+                    match self.key(k) {
+                        Some(key) if !seen.insert(key) => {}
+                        _ => entries.push((k, v)),
+                    }
+                }
+            }
+        }
+        self.open.pop();
+        Ok(entries)
+    }
+
+    /// The mappings that the value of a merge key names.
+    fn sources(&self, pos: usize) -> Result<Vec<usize>> {
+        let pos = self.resolve(pos);
+        let mut sources = Vec::new();
+        match self.loader.events[pos].0 {
+            Event::MappingStart => sources.push(pos),
+            Event::SequenceStart => {
+                let mut p = pos + 1;
+                while self.loader.events[p].0 != Event::SequenceEnd {
+                    let element = self.resolve(p);
+                    if self.loader.events[element].0 != Event::MappingStart {
+                        return Err(not_mapping(self.loader.events[p].1));
+                    }
+                    sources.push(element);
+                    p = self.skip(p);
+                }
+            }
+            _ => return Err(not_mapping(self.loader.events[pos].1)),
+        }
+        Ok(sources)
+    }
+
+    /// The text of the key at `pos` if it is a scalar.
+    fn key(&self, pos: usize) -> Option<&'a str> {
+        match self.loader.events[self.resolve(pos)].0 {
+            Event::Scalar(ref v, _, _) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// The index of the node that the event at `pos` stands for, following
+    /// an alias.
+    fn resolve(&self, pos: usize) -> usize {
+        match self.loader.events[pos].0 {
+            Event::Alias(id) => self.loader.aliases.get(&id).cloned().unwrap_or(pos),
+            _ => pos,
+        }
+    }
+
+    /// The index of the event after the node at `pos`.
+    fn skip(&self, pos: usize) -> usize {
+        let mut depth = 0;
+        let mut pos = pos;
+        loop {
+            match self.loader.events[pos].0 {
+                Event::SequenceStart | Event::MappingStart => depth += 1,
+                Event::SequenceEnd | Event::MappingEnd => depth -= 1,
+                _ => {}
+            }
+            pos += 1;
+            if depth == 0 {
+                return pos;
+            }
+        }
+    }
+}
+
+fn not_mapping(marker: Marker) -> Error {
+    Error::at("merge key must name a mapping or a sequence of mappings", Location::from_marker(&marker))
+}
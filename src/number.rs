@@ -8,21 +8,45 @@
 
 use error::Error;
 use num_traits::NumCast;
-use serde::de::{Visitor, Unexpected};
+use ryu;
+use serde::de::{IntoDeserializer, Visitor, Unexpected};
 use serde::{Serialize, Serializer, Deserialize, Deserializer};
 use std::fmt::{self, Debug, Display};
+use std::cell::Cell;
+use std::cmp::Ordering;
 use std::hash::{Hash, Hasher};
 use std::i64;
+use std::str;
+use std::sync::OnceLock;
 
 /// Represents a YAML number, whether integer or floating point.
-#[derive(Clone, PartialEq, PartialOrd)]
+#[derive(Clone)]
 pub struct Number {
-    n: N,
+    repr: Repr,
+}
+
+#[derive(Clone)]
+enum Repr {
+    Resolved(N),
+    /// Text of a plain scalar known to be a number, converted the first time
+    /// the value is looked at.
+    Lazy(LazyText),
+}
+
+/// Longest number text kept unconverted. Covers every `u64` and `i64` and the
+/// shortest round-trip form of every `f64`.
+const LAZY_CAPACITY: usize = 24;
+
+#[derive(Clone)]
+struct LazyText {
+    len: u8,
+    buf: [u8; LAZY_CAPACITY],
+    n: OnceLock<N>,
 }
 
 // "N" is a prefix of "NegInt"... this is a false positive.
 // https://github.com/Manishearth/rust-clippy/issues/1241
-#[cfg_attr(feature = "cargo-clippy", allow(enum_variant_names))]
+#[allow(clippy::enum_variant_names)]
 #[derive(Copy, Clone, Debug, PartialEq, PartialOrd)]
 enum N {
     PosInt(u64),
@@ -64,9 +88,9 @@ impl Number {
     /// # }
     /// ```
     #[inline]
-    #[cfg_attr(feature = "cargo-clippy", allow(cast_sign_loss))]
+    #[allow(clippy::cast_sign_loss)]
     pub fn is_i64(&self) -> bool {
-        match self.n {
+        match self.n() {
             N::PosInt(v) => v <= i64::max_value() as u64,
             N::NegInt(_) => true,
             N::Float(_) => false,
@@ -101,7 +125,7 @@ impl Number {
     /// ```
     #[inline]
     pub fn is_u64(&self) -> bool {
-        match self.n {
+        match self.n() {
             N::PosInt(_) => true,
             N::NegInt(_) | N::Float(_) => false,
         }
@@ -137,7 +161,7 @@ impl Number {
     /// ```
     #[inline]
     pub fn is_f64(&self) -> bool {
-        match self.n {
+        match self.n() {
             N::Float(_) => true,
             N::PosInt(_) | N::NegInt(_) => false,
         }
@@ -169,7 +193,7 @@ impl Number {
     /// ```
     #[inline]
     pub fn as_i64(&self) -> Option<i64> {
-        match self.n {
+        match self.n() {
             N::PosInt(n) => NumCast::from(n),
             N::NegInt(n) => Some(n),
             N::Float(_) => None,
@@ -199,7 +223,7 @@ impl Number {
     /// ```
     #[inline]
     pub fn as_u64(&self) -> Option<u64> {
-        match self.n {
+        match self.n() {
             N::PosInt(n) => Some(n),
             N::NegInt(_) | N::Float(_) => None,
         }
@@ -235,7 +259,7 @@ impl Number {
     /// ```
     #[inline]
     pub fn as_f64(&self) -> Option<f64> {
-        match self.n {
+        match self.n() {
             N::PosInt(n) => NumCast::from(n),
             N::NegInt(n) => NumCast::from(n),
             N::Float(n) => Some(n),
@@ -261,7 +285,7 @@ impl Number {
     /// ```
     #[inline]
     pub fn is_nan(&self) -> bool {
-        match self.n {
+        match self.n() {
             N::PosInt(_) | N::NegInt(_) => false,
             N::Float(f) => f.is_nan(),
         }
@@ -287,7 +311,7 @@ impl Number {
     /// ```
     #[inline]
     pub fn is_infinite(&self) -> bool {
-        match self.n {
+        match self.n() {
             N::PosInt(_) | N::NegInt(_) => false,
             N::Float(f) => f.is_infinite(),
         }
@@ -312,7 +336,7 @@ impl Number {
     /// ```
     #[inline]
     pub fn is_finite(&self) -> bool {
-        match self.n {
+        match self.n() {
             N::PosInt(_) | N::NegInt(_) => true,
             N::Float(f) => f.is_finite(),
         }
@@ -321,25 +345,29 @@ impl Number {
 
 impl fmt::Display for Number {
     fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-        match self.n {
+        match self.n() {
             N::PosInt(i) => Display::fmt(&i, formatter),
             N::NegInt(i) => Display::fmt(&i, formatter),
-            N::Float(f) if f.is_nan() => formatter.write_str(".nan"),
-            N::Float(f) if f.is_infinite() => {
-                if f.is_sign_negative() {
-                    formatter.write_str("-.inf")
-                } else {
-                    formatter.write_str(".inf")
-                }
-            }
-            N::Float(f) => Display::fmt(&f, formatter),
+            N::Float(f) => formatter.write_str(&format_float(f)),
         }
     }
 }
 
+impl PartialEq for Number {
+    fn eq(&self, other: &Number) -> bool {
+        self.n() == other.n()
+    }
+}
+
+impl PartialOrd for Number {
+    fn partial_cmp(&self, other: &Number) -> Option<Ordering> {
+        self.n().partial_cmp(&other.n())
+    }
+}
+
 impl Debug for Number {
     fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-        Debug::fmt(&self.n, formatter)
+        Debug::fmt(&self.n(), formatter)
     }
 }
 
@@ -349,7 +377,7 @@ impl Serialize for Number {
     where
         S: Serializer,
     {
-        match self.n {
+        match self.n() {
             N::PosInt(i) => serializer.serialize_u64(i),
             N::NegInt(i) => serializer.serialize_i64(i),
             N::Float(f) => serializer.serialize_f64(f),
@@ -400,7 +428,7 @@ impl<'de> Deserializer<'de> for Number {
     where
         V: Visitor<'de>,
     {
-        match self.n {
+        match self.n() {
             N::PosInt(i) => visitor.visit_u64(i),
             N::NegInt(i) => visitor.visit_i64(i),
             N::Float(f) => visitor.visit_f64(f),
@@ -408,7 +436,7 @@ impl<'de> Deserializer<'de> for Number {
     }
 
     forward_to_deserialize_any! {
-        bool i8 i16 i32 i64 u8 u16 u32 u64 f32 f64 char str string bytes
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes
         byte_buf option unit unit_struct newtype_struct seq tuple
         tuple_struct map struct enum identifier ignored_any
     }
@@ -422,7 +450,7 @@ impl<'de, 'a> Deserializer<'de> for &'a Number {
     where
         V: Visitor<'de>,
     {
-        match self.n {
+        match self.n() {
             N::PosInt(i) => visitor.visit_u64(i),
             N::NegInt(i) => visitor.visit_i64(i),
             N::Float(f) => visitor.visit_f64(f),
@@ -430,23 +458,39 @@ impl<'de, 'a> Deserializer<'de> for &'a Number {
     }
 
     forward_to_deserialize_any! {
-        bool i8 i16 i32 i64 u8 u16 u32 u64 f32 f64 char str string bytes
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes
         byte_buf option unit unit_struct newtype_struct seq tuple
         tuple_struct map struct enum identifier ignored_any
     }
 }
 
+impl<'de> IntoDeserializer<'de, Error> for Number {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+impl<'de, 'a> IntoDeserializer<'de, Error> for &'a Number {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
 macro_rules! from_signed {
     ($($signed_ty:ident)*) => {
         $(
             impl From<$signed_ty> for Number {
                 #[inline]
-                #[cfg_attr(feature = "cargo-clippy", allow(cast_sign_loss))]
+                #[allow(clippy::cast_sign_loss)]
                 fn from(i: $signed_ty) -> Self {
                     if i < 0 {
-                        Number { n: N::NegInt(i as i64) }
+                        Number { repr: Repr::Resolved(N::NegInt(i as i64)) }
                     } else {
-                        Number { n: N::PosInt(i as u64) }
+                        Number { repr: Repr::Resolved(N::PosInt(i as u64)) }
                     }
                 }
             }
@@ -460,7 +504,7 @@ macro_rules! from_unsigned {
             impl From<$unsigned_ty> for Number {
                 #[inline]
                 fn from(u: $unsigned_ty) -> Self {
-                    Number { n: N::PosInt(u as u64) }
+                    Number { repr: Repr::Resolved(N::PosInt(u as u64)) }
                 }
             }
         )*
@@ -473,7 +517,7 @@ macro_rules! from_float {
             impl From<$float_ty> for Number {
                 #[inline]
                 fn from(f: $float_ty) -> Self {
-                    Number { n: N::Float(f as f64) }
+                    Number { repr: Repr::Resolved(N::Float(f as f64)) }
                 }
             }
         )*
@@ -486,10 +530,10 @@ from_float!(f32 f64);
 
 // This is fine, because we don't _really_ implement hash for floats
 // all other hash functions should work as expected
-#[cfg_attr(feature = "cargo-clippy", allow(derive_hash_xor_eq))]
+#[allow(clippy::derived_hash_with_manual_eq)]
 impl Hash for Number {
     fn hash<H: Hasher>(&self, state: &mut H) {
-        match self.n {
+        match self.n() {
             N::Float(_) => {
                 // you should feel bad for using f64 as a map key
                 3.hash(state)
@@ -504,10 +548,296 @@ impl Number {
     // Not public API. Should be pub(crate).
     #[doc(hidden)]
     pub fn unexpected(&self) -> Unexpected {
-        match self.n {
+        match self.n() {
             N::PosInt(u) => Unexpected::Unsigned(u),
             N::NegInt(i) => Unexpected::Signed(i),
             N::Float(f) => Unexpected::Float(f),
         }
     }
 }
+
+pub(crate) const TOKEN: &str = "$serde_yaml::private::Number";
+
+thread_local! {
+    /// Whether `TOKEN` is being handed over as the key of a number whose
+    /// conversion was put off, rather than read from the input.
+    static LAZY_KEY: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Clears `LAZY_KEY` even if handing over the key panics.
+struct LazyKey;
+
+impl Drop for LazyKey {
+    fn drop(&mut self) {
+        LAZY_KEY.with(|lazy| lazy.set(false));
+    }
+}
+
+/// Runs `f`, which hands `TOKEN` over as the key of a lazy number.
+pub(crate) fn lazy_key<T, F>(f: F) -> T
+    where F: FnOnce() -> T
+{
+    LAZY_KEY.with(|lazy| lazy.set(true));
+    let _key = LazyKey;
+    f()
+}
+
+/// Whether a key of `TOKEN` comes from `lazy_key`, so that a mapping with the
+/// same key in the input stays a mapping.
+pub(crate) fn is_lazy_key() -> bool {
+    LAZY_KEY.with(Cell::get)
+}
+
+impl Number {
+    #[inline]
+    fn n(&self) -> N {
+        match self.repr {
+            Repr::Resolved(n) => n,
+            Repr::Lazy(ref lazy) => *lazy.n.get_or_init(|| resolve(lazy.text())),
+        }
+    }
+
+    pub(crate) fn is_lazy(v: &str) -> bool {
+        v.len() <= LAZY_CAPACITY && is_decimal(v.as_bytes())
+    }
+
+    /// Whether `v` is the text of a decimal number of any length, which is
+    /// how it is read back when written as a plain scalar.
+    pub(crate) fn is_number(v: &str) -> bool {
+        is_decimal(v.as_bytes())
+    }
+
+    pub(crate) fn lazy(v: &str) -> Option<Number> {
+        if !Number::is_lazy(v) {
+            return None;
+        }
+        let mut buf = [0; LAZY_CAPACITY];
+        buf[..v.len()].copy_from_slice(v.as_bytes());
+        Some(Number {
+                 repr: Repr::Lazy(LazyText {
+                                      len: v.len() as u8,
+                                      buf: buf,
+                                      n: OnceLock::new(),
+                                  }),
+             })
+    }
+}
+
+impl Number {
+    /// About how many characters the number takes when written.
+    pub(crate) fn display_len(&self) -> usize {
+        match self.repr {
+            Repr::Lazy(ref lazy) => lazy.len as usize,
+            Repr::Resolved(N::PosInt(u)) => digits(u),
+            Repr::Resolved(N::NegInt(i)) => display_len(i),
+            Repr::Resolved(N::Float(f)) => ryu::Buffer::new().format(f).len(),
+        }
+    }
+}
+
+/// How many characters an integer takes when written in decimal.
+pub(crate) fn display_len(i: i64) -> usize {
+    if i < 0 {
+        1 + digits(i.wrapping_neg() as u64)
+    } else {
+        digits(i as u64)
+    }
+}
+
+fn digits(mut u: u64) -> usize {
+    let mut digits = 1;
+    while u >= 10 {
+        u /= 10;
+        digits += 1;
+    }
+    digits
+}
+
+impl LazyText {
+    fn text(&self) -> &str {
+        // Only ever filled from a `str` that passed `is_decimal`, so ASCII.
+        str::from_utf8(&self.buf[..self.len as usize]).unwrap()
+    }
+}
+
+/// Formats a float as the shortest text that parses back to the same value,
+/// always with a decimal point or exponent so that it does not read as an
+/// integer. Non-finite values come out as `.inf`, `-.inf` and `.nan`.
+pub(crate) fn format_float<F: ryu::Float>(f: F) -> String {
+    let mut buffer = ryu::Buffer::new();
+    match buffer.format(f) {
+        "inf" => ".inf",
+        "-inf" => "-.inf",
+        "NaN" => ".nan",
+        finite => finite,
+    }
+    .to_owned()
+}
+
+/// Parses a float, including the YAML spellings of infinity and NaN. Unless
+/// `strict`, also accepts the other spellings of `f64::from_str`, like `inf`,
+/// `Infinity` and `NaN`.
+pub(crate) fn parse_float(v: &str, strict: bool) -> Option<f64> {
+    match v {
+        ".inf" | ".Inf" | ".INF" | "+.inf" | "+.Inf" | "+.INF" => Some(f64::INFINITY),
+        "-.inf" | "-.Inf" | "-.INF" => Some(f64::NEG_INFINITY),
+        ".nan" | ".NaN" | ".NAN" => Some(f64::NAN),
+        // Everything else `f64::from_str` accepts starts with a digit or a
+        // point after the sign.
+        _ if strict && v.trim_start_matches(&['+', '-'][..]).starts_with(char::is_alphabetic) => None,
+        _ => v.parse().ok(),
+    }
+}
+
+/// Converts the text of a number the same way an untagged plain scalar is
+/// resolved when deserializing: `u64`, then `i64`, then `f64`. Only called on
+/// text that passed `is_decimal`.
+fn resolve(v: &str) -> N {
+    if let Ok(u) = v.parse::<u64>() {
+        return Number::from(u).n();
+    }
+    if let Ok(i) = v.parse::<i64>() {
+        return Number::from(i).n();
+    }
+    N::Float(v.parse().unwrap())
+}
+
+/// Whether `v` matches `-?digits[.digits][(e|E)[+-]digits]` with at least one
+/// digit before or after the point. Every such string parses as an `f64`, so
+/// it always resolves to a number.
+fn is_decimal(v: &[u8]) -> bool {
+    fn digits(v: &[u8], i: &mut usize) -> usize {
+        let start = *i;
+        while *i < v.len() && v[*i].is_ascii_digit() {
+            *i += 1;
+        }
+        *i - start
+    }
+
+    let mut i = 0;
+    if v.first() == Some(&b'-') {
+        i += 1;
+    }
+    let mut mantissa = digits(v, &mut i);
+    if v.get(i) == Some(&b'.') {
+        i += 1;
+        mantissa += digits(v, &mut i);
+    }
+    if mantissa == 0 {
+        return false;
+    }
+    if let Some(&b'e') | Some(&b'E') = v.get(i) {
+        i += 1;
+        if let Some(&b'+') | Some(&b'-') = v.get(i) {
+            i += 1;
+        }
+        if digits(v, &mut i) == 0 {
+            return false;
+        }
+    }
+    i == v.len()
+}
+
+/// Which spellings of numbers an untagged plain scalar is read as a number
+/// in. See `Deserializer::numbers`.
+///
+/// YAML 1.1 lets digits be grouped with underscores, so `1_000_000` is a
+/// million, while YAML 1.2 reads it as a string. Both read `1e5` as a
+/// float, which for scalars like version numbers or identifiers is rarely
+/// what was meant. The default is what this crate has always read: no
+/// underscores, and exponents.
+///
+/// ```rust
+/// # extern crate serde;
+/// # extern crate serde_yaml;
+/// #
+/// # fn main() {
+/// use serde::Deserialize;
+/// use serde_yaml::{Deserializer, NumberDialect, Value};
+///
+/// let yaml = "[1_000_000, 1e5]";
+/// let value = Value::deserialize(Deserializer::from_str(yaml)).unwrap();
+/// assert_eq!(value[0], "1_000_000");
+/// assert_eq!(value[1], 100000.0);
+///
+/// let dialect = NumberDialect::new().underscores(true).exponents(false);
+/// let value = Value::deserialize(Deserializer::from_str(yaml).numbers(dialect)).unwrap();
+/// assert_eq!(value[0], 1000000);
+/// assert_eq!(value[1], "1e5");
+/// # }
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct NumberDialect {
+    underscores: bool,
+    exponents: bool,
+}
+
+impl Default for NumberDialect {
+    fn default() -> Self {
+        NumberDialect {
+            underscores: false,
+            exponents: true,
+        }
+    }
+}
+
+impl NumberDialect {
+    /// The default dialect.
+    pub fn new() -> Self {
+        NumberDialect::default()
+    }
+
+    /// Whether the digits of a decimal integer or float may be grouped with
+    /// underscores, as in `1_000_000` or `-0.000_1`, like YAML 1.1. Off by
+    /// default, in which case such scalars are strings.
+    pub fn underscores(mut self, underscores: bool) -> Self {
+        self.underscores = underscores;
+        self
+    }
+
+    /// Whether a decimal number with an exponent, like `1e5` or `2.5E-3`, is
+    /// a float. On by default. Off, such scalars are strings.
+    pub fn exponents(mut self, exponents: bool) -> Self {
+        self.exponents = exponents;
+        self
+    }
+
+    /// How the scalar reads in this dialect, if not the way it reads in the
+    /// default one.
+    pub(crate) fn resolve(&self, v: &str) -> Option<Spelling> {
+        let digits = if self.underscores { without_underscores(v) } else { None };
+        if !self.exponents && has_exponent(digits.as_ref().map_or(v, String::as_str)) {
+            return Some(Spelling::String);
+        }
+        digits.map(Spelling::Number)
+    }
+}
+
+/// How a scalar reads in a `NumberDialect`.
+pub(crate) enum Spelling {
+    /// As a string.
+    String,
+    /// As the number written as this text in the default dialect.
+    Number(String),
+}
+
+/// The scalar without the underscores between its digits, if it is a
+/// decimal number written with some.
+fn without_underscores(v: &str) -> Option<String> {
+    let unsigned = v.trim_start_matches(&['-', '+'][..]);
+    if unsigned.len() + 1 < v.len() || !unsigned.starts_with(|c: char| c.is_ascii_digit()) || !unsigned.contains('_') {
+        return None;
+    }
+    let digits = unsigned.replace('_', "");
+    if is_decimal(digits.as_bytes()) {
+        Some(format!("{}{}", &v[..v.len() - unsigned.len()], digits))
+    } else {
+        None
+    }
+}
+
+/// Whether the scalar is a decimal number with an exponent.
+fn has_exponent(v: &str) -> bool {
+    let unsigned = v.strip_prefix('+').unwrap_or(v);
+    unsigned.contains(&['e', 'E'][..]) && is_decimal(unsigned.as_bytes())
+}
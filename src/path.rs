@@ -38,3 +38,38 @@ impl<'a> Display for Path<'a> {
         }
     }
 }
+
+#[derive(Clone, Debug)]
+pub(crate) enum Segment {
+    Key(String),
+    Index(usize),
+}
+
+/// Splits a path written the way `Path` displays it, like
+/// `spec.containers[0].image`, into keys and indices. The root is `.`.
+pub(crate) fn segments(path: &str) -> Vec<Segment> {
+    let mut segments = Vec::new();
+    if path == "." {
+        return segments;
+    }
+    for part in path.split('.') {
+        let (key, mut rest) = match part.find('[') {
+            Some(bracket) => (&part[..bracket], &part[bracket..]),
+            None => (part, ""),
+        };
+        if !key.is_empty() {
+            segments.push(Segment::Key(key.to_owned()));
+        }
+        while rest.starts_with('[') {
+            let close = match rest.find(']') {
+                Some(close) => close,
+                None => break,
+            };
+            if let Ok(index) = rest[1..close].parse() {
+                segments.push(Segment::Index(index));
+            }
+            rest = &rest[close + 1..];
+        }
+    }
+    segments
+}
@@ -0,0 +1,121 @@
+// Copyright 2018 Serde YAML Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+/// Counters that a `Deserializer` adds to while it reads input, to find out
+/// what makes a file slow to load.
+///
+/// A `Profile` is a handle: clones of it share the same counters, and the
+/// counters keep adding up over every deserializer that is given one. It
+/// may be shared between threads. Functions like `from_str` do not count
+/// anything; deserialize through a `Deserializer` to profile them.
+///
+/// ```rust
+/// # extern crate serde;
+/// # extern crate serde_yaml;
+/// #
+/// # fn main() {
+/// use serde::Deserialize;
+/// use serde_yaml::{Deserializer, Profile, Value};
+///
+/// let profile = Profile::new();
+/// let yaml = "base: &base {name: web}\ncopies: [*base, *base, *base]";
+/// Value::deserialize(Deserializer::from_str(yaml).profile(&profile)).unwrap();
+///
+/// assert_eq!(profile.scalars(), 4);
+/// assert_eq!(profile.aliases_resolved(), 3);
+/// assert_eq!(profile.string_bytes(), 17);
+/// println!("parsed in {:?}, deserialized in {:?}", profile.parse_time(), profile.deserialize_time());
+/// # }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Profile {
+    counters: Arc<Counters>,
+}
+
+#[derive(Debug, Default)]
+struct Counters {
+    scalars: AtomicU64,
+    aliases_resolved: AtomicU64,
+    string_bytes: AtomicU64,
+    parse_nanos: AtomicU64,
+    deserialize_nanos: AtomicU64,
+}
+
+impl Profile {
+    /// Creates a profile with every counter at zero.
+    pub fn new() -> Self {
+        Profile::default()
+    }
+
+    /// How many scalars the parser read from the text.
+    pub fn scalars(&self) -> u64 {
+        self.counters.scalars.load(Ordering::Relaxed)
+    }
+
+    /// How many times an alias was followed to the node it refers to. An
+    /// alias is followed every time it is deserialized, so this is the number
+    /// of copies of anchored nodes that were made.
+    pub fn aliases_resolved(&self) -> u64 {
+        self.counters.aliases_resolved.load(Ordering::Relaxed)
+    }
+
+    /// The total length in bytes of the scalars the parser read, including
+    /// keys and scalars that are not strings.
+    pub fn string_bytes(&self) -> u64 {
+        self.counters.string_bytes.load(Ordering::Relaxed)
+    }
+
+    /// The time spent parsing text into events.
+    pub fn parse_time(&self) -> Duration {
+        Duration::from_nanos(self.counters.parse_nanos.load(Ordering::Relaxed))
+    }
+
+    /// The time spent turning events into the type being deserialized,
+    /// which includes resolving scalars to numbers and booleans and
+    /// following aliases, as well as the time spent in that type's
+    /// `Deserialize` impl.
+    pub fn deserialize_time(&self) -> Duration {
+        Duration::from_nanos(self.counters.deserialize_nanos.load(Ordering::Relaxed))
+    }
+
+    pub(crate) fn add_scalar(&self, len: usize) {
+        self.counters.scalars.fetch_add(1, Ordering::Relaxed);
+        self.counters.string_bytes.fetch_add(len as u64, Ordering::Relaxed);
+    }
+
+    pub(crate) fn add_alias(&self) {
+        self.counters.aliases_resolved.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn time_parse<T, F>(&self, f: F) -> T
+        where F: FnOnce() -> T
+    {
+        time(&self.counters.parse_nanos, f)
+    }
+
+    pub(crate) fn time_deserialize<T, F>(&self, f: F) -> T
+        where F: FnOnce() -> T
+    {
+        time(&self.counters.deserialize_nanos, f)
+    }
+}
+
+fn time<T, F>(nanos: &AtomicU64, f: F) -> T
+    where F: FnOnce() -> T
+{
+    let start = Instant::now();
+    let result = f();
+    let elapsed = start.elapsed();
+    let elapsed = elapsed.as_secs() * 1_000_000_000 + u64::from(elapsed.subsec_nanos());
+    nanos.fetch_add(elapsed, Ordering::Relaxed);
+    result
+}
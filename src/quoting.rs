@@ -0,0 +1,134 @@
+// Copyright 2018 Serde YAML Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The quoting rules of the serializer, for building YAML text by hand.
+//!
+//! Templates that put strings into YAML snippets, like Helm charts, have to
+//! decide when a string needs quotes and how to escape it. These functions
+//! give the same answers as the serializer, so that a string spliced into a
+//! template reads back as the string it was.
+//!
+//! ```rust
+//! use serde_yaml::quoting::{self, QuoteStyle, Schema};
+//!
+//! assert!(!quoting::needs_quoting("nginx", Schema::Yaml11));
+//! assert!(quoting::needs_quoting("yes", Schema::Yaml11));
+//! assert!(!quoting::needs_quoting("yes", Schema::Core));
+//!
+//! assert_eq!(quoting::quote("8080", QuoteStyle::Plain), "\"8080\"");
+//! assert_eq!(quoting::quote("it's", QuoteStyle::Single), "'it''s'");
+//! assert_eq!(quoting::escape_double_quoted("a\tb"), "a\\tb");
+//! ```
+
+use emitter;
+
+/// Which strings a reader takes to be something other than a string when
+/// they are written plain.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Schema {
+    /// YAML 1.1, as read by this crate and by most YAML libraries. On top of
+    /// `Core`, words like `yes`, `off` and `NO` are booleans, and strings
+    /// that Rust parses as a number, like `inf`, are quoted too, as are
+    /// dates like `2024-01-01` and numbers like `1_000` and `0b101` that
+    /// YAML 1.1 tools read as timestamps and numbers. This is what the
+    /// serializer quotes for, so that a string like a version `1.10` or a
+    /// time `08:30` is never written in a way that reads back as something
+    /// else.
+    Yaml11,
+    /// The core schema of YAML 1.2: `true`, `false`, `null` and `~` in
+    /// their usual capitalizations, and decimal, octal `0o` and hex `0x`
+    /// numbers.
+    Core,
+}
+
+/// How `quote` writes a string.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum QuoteStyle {
+    /// Plain if that reads back as the same string under `Schema::Yaml11`,
+    /// and double-quoted otherwise. This is how the serializer writes
+    /// strings.
+    Plain,
+    /// Between single quotes, with `'` written as `''`. Single quotes
+    /// cannot escape anything, so strings with line breaks or control
+    /// characters are double-quoted instead.
+    Single,
+    /// Between double quotes, escaped like `escape_double_quoted` does.
+    Double,
+}
+
+/// Escapes a string for the inside of double quotes: `"` and `\` get a
+/// backslash, and line breaks, tabs and other control characters are
+/// written as escape sequences. The quotes themselves are left out.
+pub fn escape_double_quoted(string: &str) -> String {
+    let mut escaped = String::with_capacity(string.len());
+    emitter::escape_body(&mut escaped, string).unwrap();
+    escaped
+}
+
+/// Whether `string` has to be quoted to be read back as a string under
+/// `schema`, either because it cannot be written plain at all or because it
+/// would be read as a boolean, null or number.
+pub fn needs_quoting(string: &str, schema: Schema) -> bool {
+    match schema {
+        Schema::Yaml11 => emitter::need_quotes(string),
+        Schema::Core => emitter::need_quotes_syntax(string) || is_core_non_string(string),
+    }
+}
+
+/// Writes `string` as a scalar in `style`.
+pub fn quote(string: &str, style: QuoteStyle) -> String {
+    match style {
+        QuoteStyle::Plain if !emitter::need_quotes(string) => string.to_owned(),
+        QuoteStyle::Single if !string.contains(|c: char| c.is_control() && c != '\t') => {
+            format!("'{}'", string.replace('\'', "''"))
+        }
+        _ => format!("\"{}\"", escape_double_quoted(string)),
+    }
+}
+
+/// Whether a plain scalar is a boolean, null or number in the core schema.
+fn is_core_non_string(string: &str) -> bool {
+    match string {
+        "true" | "True" | "TRUE" | "false" | "False" | "FALSE" | "null" | "Null" | "NULL" | "~" => return true,
+        ".nan" | ".NaN" | ".NAN" => return true,
+        _ => {}
+    }
+    if string.starts_with("0o") {
+        return is_digits(&string[2..], 8);
+    }
+    if string.starts_with("0x") {
+        return is_digits(&string[2..], 16);
+    }
+    let unsigned = strip_sign(string);
+    if let ".inf" | ".Inf" | ".INF" = unsigned {
+        return true;
+    }
+    // Digits with at most one `.` among them, then an optional exponent.
+    let (mantissa, exponent) = match unsigned.find(['e', 'E']) {
+        Some(e) => (&unsigned[..e], Some(&unsigned[e + 1..])),
+        None => (unsigned, None),
+    };
+    let mantissa = match mantissa.find('.') {
+        Some(0) => is_digits(&mantissa[1..], 10),
+        Some(dot) => is_digits(&mantissa[..dot], 10) && mantissa[dot + 1..].chars().all(|c| c.is_ascii_digit()),
+        None => is_digits(mantissa, 10),
+    };
+    mantissa && exponent.map_or(true, |exponent| is_digits(strip_sign(exponent), 10))
+}
+
+fn strip_sign(string: &str) -> &str {
+    if string.starts_with('+') || string.starts_with('-') {
+        &string[1..]
+    } else {
+        string
+    }
+}
+
+fn is_digits(string: &str, radix: u32) -> bool {
+    !string.is_empty() && string.chars().all(|c| c.is_digit(radix))
+}
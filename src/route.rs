@@ -0,0 +1,151 @@
+// Copyright 2018 Serde YAML Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Reading streams whose documents are of different types, told apart by a
+//! field of each document.
+//!
+//! This is the shape of a Kubernetes manifest, where every document says
+//! what it is in its `kind` field. A `Router` maps each value of that field
+//! to the type to deserialize the document into.
+//!
+//! ```rust
+//! # #[macro_use] extern crate serde_derive;
+//! # extern crate serde_yaml;
+//! #
+//! # fn main() {
+//! use serde_yaml::route::Router;
+//!
+//! #[derive(Deserialize, Debug, PartialEq)]
+//! struct Service { port: u16 }
+//!
+//! #[derive(Deserialize, Debug, PartialEq)]
+//! struct Deployment { replicas: u32 }
+//!
+//! #[derive(Debug, PartialEq)]
+//! enum Resource {
+//!     Service(Service),
+//!     Deployment(Deployment),
+//! }
+//!
+//! let router = Router::new("kind")
+//!     .route("Service", Resource::Service)
+//!     .route("Deployment", Resource::Deployment);
+//!
+//! let manifest = "kind: Service\nport: 80\n---\nkind: Deployment\nreplicas: 3\n";
+//! assert_eq!(router.from_str(manifest).unwrap(), [
+//!     Resource::Service(Service { port: 80 }),
+//!     Resource::Deployment(Deployment { replicas: 3 }),
+//! ]);
+//! # }
+//! ```
+
+use std::any::Any;
+
+use serde::de::{DeserializeOwned, Error as SError};
+
+use de::Loader;
+use error::{Error, Result};
+use value::Value;
+
+/// Deserializes each document of a stream into the type registered for the
+/// value of its discriminant field, and passes it through a function to a
+/// common type `T`.
+///
+/// With the default `T` of `Box<dyn Any>`, types can be registered with
+/// `register` and the results picked apart with `downcast`.
+pub struct Router<T = Box<dyn Any>> {
+    field: String,
+    routes: Vec<(String, Box<Route<T>>)>,
+}
+
+/// Deserializes the document at a position of the loader.
+type Route<T> = dyn Fn(&Loader, &mut usize) -> Result<T> + Send + Sync;
+
+impl<T> Router<T> {
+    /// A router with no routes, telling documents apart by the string in
+    /// their top-level `field`.
+    pub fn new(field: &str) -> Self {
+        Router {
+            field: field.to_owned(),
+            routes: Vec::new(),
+        }
+    }
+
+    /// Deserializes documents whose field is `value` into `D`, which `wrap`
+    /// turns into a `T`. The field is part of the document as `D` sees it,
+    /// so `D` must accept or ignore it. A later route for the same value
+    /// replaces an earlier one. `wrap` must be `Send` and `Sync` so that a
+    /// router can be shared between threads.
+    pub fn route<D, F>(mut self, value: &str, wrap: F) -> Self
+        where D: DeserializeOwned,
+              F: Fn(D) -> T + Send + Sync + 'static
+    {
+        self.routes.retain(|&(ref v, _)| v != value);
+        let deserialize = move |loader: &Loader, pos: &mut usize| loader.deserialize_at(pos).map(&wrap);
+        self.routes.push((value.to_owned(), Box::new(deserialize)));
+        self
+    }
+
+    /// Deserializes every document of the text. Empty documents, like the
+    /// one between two `---` lines, are passed over. A document that is not
+    /// a mapping, has no string in the field, or has a value that was not
+    /// routed is an error, as is a document that fails to deserialize.
+    pub fn from_str(&self, s: &str) -> Result<Vec<T>> {
+        let loader = Loader::load(s)?;
+        let mut documents = Vec::new();
+        let mut number = 0;
+        let mut pos = 0;
+        // Every document is a single node.
+        while pos < loader.events.len() {
+            let start = pos;
+            number += 1;
+            let document: Value = loader.deserialize_at(&mut pos)?;
+            if document.is_null() {
+                continue;
+            }
+            let value = match document.get(&*self.field).and_then(Value::as_str) {
+                Some(value) => value,
+                None => {
+                    return Err(Error::custom(format_args!("document {} has no `{}` string", number, self.field)));
+                }
+            };
+            let deserialize = match self.routes.iter().find(|&&(ref v, _)| v == value) {
+                Some(&(_, ref deserialize)) => deserialize,
+                None => {
+                    return Err(Error::custom(format_args!("document {} has unknown {} `{}`", number, self.field, value)));
+                }
+            };
+            documents.push(deserialize(&loader, &mut { start })?);
+        }
+        Ok(documents)
+    }
+}
+
+impl Router<Box<dyn Any>> {
+    /// Deserializes documents whose field is `value` into `D`, boxed.
+    ///
+    /// ```rust
+    /// # #[macro_use] extern crate serde_derive;
+    /// # extern crate serde_yaml;
+    /// #
+    /// # fn main() {
+    /// use serde_yaml::route::Router;
+    ///
+    /// #[derive(Deserialize)]
+    /// struct Secret { data: String }
+    ///
+    /// let documents = Router::new("type").register::<Secret>("secret").from_str("type: secret\ndata: x\n").unwrap();
+    /// assert_eq!(documents[0].downcast_ref::<Secret>().unwrap().data, "x");
+    /// # }
+    /// ```
+    pub fn register<D>(self, value: &str) -> Self
+        where D: DeserializeOwned + Any
+    {
+        self.route(value, |document: D| Box::new(document) as Box<dyn Any>)
+    }
+}
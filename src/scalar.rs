@@ -0,0 +1,91 @@
+// Copyright 2018 Serde YAML Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Storage for the text of scalars between parsing and deserialization.
+//!
+//! The whole event stream of a document is held in memory while it is being
+//! deserialized. With the `sso` feature, scalars short enough to fit in the
+//! space of a `String` are stored inline rather than in their own heap
+//! allocation, which lowers peak memory use for documents made mostly of
+//! short keys and values.
+
+#[cfg(feature = "sso")]
+use std::fmt::{self, Debug};
+#[cfg(feature = "sso")]
+use std::ops::Deref;
+#[cfg(feature = "sso")]
+use std::str;
+
+#[cfg(not(feature = "sso"))]
+pub(crate) type ScalarString = String;
+
+/// Longest scalar, in bytes, stored without a heap allocation. Together with
+/// the length and the enum tag this keeps `ScalarString` as small as `String`.
+#[cfg(feature = "sso")]
+const INLINE_CAPACITY: usize = 22;
+
+#[cfg(feature = "sso")]
+#[derive(Clone, PartialEq, Eq)]
+pub(crate) enum ScalarString {
+    Inline(u8, [u8; INLINE_CAPACITY]),
+    Heap(Box<str>),
+}
+
+#[cfg(feature = "sso")]
+impl From<String> for ScalarString {
+    fn from(s: String) -> Self {
+        if s.len() <= INLINE_CAPACITY {
+            let mut buf = [0; INLINE_CAPACITY];
+            buf[..s.len()].copy_from_slice(s.as_bytes());
+            ScalarString::Inline(s.len() as u8, buf)
+        } else {
+            ScalarString::Heap(s.into_boxed_str())
+        }
+    }
+}
+
+#[cfg(feature = "sso")]
+impl Deref for ScalarString {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        match *self {
+            // Only ever filled from a whole `String`, so always UTF-8.
+            ScalarString::Inline(len, ref buf) => str::from_utf8(&buf[..len as usize]).unwrap(),
+            ScalarString::Heap(ref s) => s,
+        }
+    }
+}
+
+#[cfg(feature = "sso")]
+impl AsRef<str> for ScalarString {
+    fn as_ref(&self) -> &str {
+        self
+    }
+}
+
+#[cfg(feature = "sso")]
+impl PartialEq<str> for ScalarString {
+    fn eq(&self, other: &str) -> bool {
+        **self == *other
+    }
+}
+
+#[cfg(feature = "sso")]
+impl<'a> PartialEq<&'a str> for ScalarString {
+    fn eq(&self, other: &&'a str) -> bool {
+        **self == **other
+    }
+}
+
+#[cfg(feature = "sso")]
+impl Debug for ScalarString {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        Debug::fmt(&**self, formatter)
+    }
+}
@@ -0,0 +1,492 @@
+// Copyright 2018 Serde YAML Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Checking a `Value` against the JSON Schema of a type, as generated by
+//! [schemars]. Requires the `schemars` feature.
+//!
+//! Deserializing stops at the first thing that is wrong. Checking a parsed
+//! document against a schema finds everything that is wrong with it at once,
+//! and each violation names the path of the value, like `workers[1].mode`, so
+//! a config file can be fixed in one go.
+//!
+//! [schemars]: https://docs.rs/schemars
+//!
+//! ```rust
+//! #[macro_use]
+//! extern crate serde_derive;
+//! #[macro_use]
+//! extern crate schemars;
+//! extern crate serde_yaml;
+//!
+//! use serde_yaml::schema;
+//!
+//! #[derive(Deserialize, JsonSchema)]
+//! struct Worker {
+//!     name: String,
+//!     #[schemars(range(min = 1, max = 64))]
+//!     threads: u32,
+//! }
+//!
+//! #[derive(Deserialize, JsonSchema)]
+//! struct Config {
+//!     workers: Vec<Worker>,
+//! }
+//!
+//! # fn main() {
+//! let value = serde_yaml::from_str("workers:\n- threads: 100\n- {name: b, threads: x}\n").unwrap();
+//! let violations: Vec<String> = schema::validate::<Config>(&value).iter().map(ToString::to_string).collect();
+//! assert_eq!(violations, [
+//!     "`workers[0]` is missing `name`",
+//!     "`workers[0].threads` must be at most 64, found 100",
+//!     "`workers[1].threads` must be of type integer, found string",
+//! ]);
+//! # }
+//! ```
+//!
+//! The keywords that schemars generates are checked, along with the rest of
+//! the JSON Schema draft 7 validation keywords. `pattern` and
+//! `patternProperties` need the `regex` feature as well and are ignored
+//! without it. Only references to `definitions` in the same schema are
+//! followed. The integer formats that schemars writes, like `uint16`, are
+//! checked against the range of the Rust type. Mapping keys that are not
+//! strings are matched against properties by how they are written, so the
+//! key `1` is the property `"1"`.
+
+use std::fmt::{self, Display};
+
+#[cfg(feature = "regex")]
+use regex::Regex;
+use schemars::JsonSchema;
+use schemars::gen::SchemaGenerator;
+use schemars::schema::{InstanceType, RootSchema, Schema, SchemaObject, SingleOrVec};
+
+use constraints::scalar_text;
+use number::Number;
+use value::Value;
+
+/// Checks `value` against the schema that schemars generates for `T`, and
+/// returns the violations in document order.
+pub fn validate<T>(value: &Value) -> Vec<Violation>
+    where T: JsonSchema
+{
+    validate_schema(&SchemaGenerator::default().into_root_schema_for::<T>(), value)
+}
+
+/// Checks `value` against `schema`, and returns the violations in document
+/// order.
+pub fn validate_schema(schema: &RootSchema, value: &Value) -> Vec<Violation> {
+    let mut violations = Vec::new();
+    Checker { root: schema }.object(&schema.schema, value, ".", &mut violations);
+    violations
+}
+
+/// A value that does not match its schema.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Violation {
+    path: String,
+    message: String,
+}
+
+impl Violation {
+    /// The path of the value, like `workers[1].mode`, or `.` for the root.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// What is wrong with the value.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl Display for Violation {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "`{}` {}", self.path, self.message)
+    }
+}
+
+struct Checker<'a> {
+    root: &'a RootSchema,
+}
+
+impl<'a> Checker<'a> {
+    fn schema(&self, schema: &Schema, value: &Value, path: &str, out: &mut Vec<Violation>) {
+        match *schema {
+            Schema::Bool(true) => {}
+            Schema::Bool(false) => violation(out, path, "is not allowed here".to_owned()),
+            Schema::Object(ref object) => self.object(object, value, path, out),
+        }
+    }
+
+    /// Whether `value` matches `schema`, without reporting why not.
+    fn matches(&self, schema: &Schema, value: &Value, path: &str) -> bool {
+        let mut violations = Vec::new();
+        self.schema(schema, value, path, &mut violations);
+        violations.is_empty()
+    }
+
+    fn object(&self, schema: &SchemaObject, value: &Value, path: &str, out: &mut Vec<Violation>) {
+        if let Some(ref reference) = schema.reference {
+            let name = reference.trim_start_matches("#/definitions/");
+            match self.root.definitions.get(name) {
+                Some(definition) => self.schema(definition, value, path, out),
+                None => violation(out, path, format!("refers to unknown schema `{}`", reference)),
+            }
+        }
+        if let Some(ref types) = schema.instance_type {
+            if !type_matches(types, value) {
+                let expected: Vec<&str> = match *types {
+                    SingleOrVec::Single(ref ty) => vec![type_name(ty)],
+                    SingleOrVec::Vec(ref types) => types.iter().map(type_name).collect(),
+                };
+                let message = format!("must be of type {}, found {}", expected.join(" or "), value_type(value));
+                return violation(out, path, message);
+            }
+        }
+        if let Some(ref values) = schema.enum_values {
+            let values: Vec<Value> = values.iter().cloned().map(Value::from).collect();
+            if !values.contains(value) {
+                let values: Vec<String> = values.iter().map(scalar_text).collect();
+                violation(out, path, format!("must be one of {}, found {}", values.join(", "), scalar_text(value)));
+            }
+        }
+        if let Some(ref constant) = schema.const_value {
+            let constant = Value::from(constant.clone());
+            if constant != *value {
+                violation(out, path, format!("must be {}, found {}", scalar_text(&constant), scalar_text(value)));
+            }
+        }
+        if schema.subschemas.is_some() {
+            self.subschemas(schema, value, path, out);
+        }
+        if let (Some(ref format), &Value::Number(ref n)) = (&schema.format, value) {
+            integer_format(schema, format, n, path, out);
+        }
+        match *value {
+            Value::Number(ref n) => self.number(schema, n.as_f64().unwrap_or(0.0), path, out),
+            Value::String(ref s) => self.string(schema, s, path, out),
+            Value::Sequence(ref seq) => self.sequence(schema, seq, path, out),
+            Value::Mapping(_) => self.mapping(schema, value, path, out),
+            Value::Null | Value::Bool(_) => {}
+        }
+    }
+
+    fn subschemas(&self, schema: &SchemaObject, value: &Value, path: &str, out: &mut Vec<Violation>) {
+        let subschemas = schema.subschemas.as_ref().expect("subschemas");
+        if let Some(ref all) = subschemas.all_of {
+            for schema in all {
+                self.schema(schema, value, path, out);
+            }
+        }
+        if let Some(ref any) = subschemas.any_of {
+            self.alternatives(any, false, value, path, out);
+        }
+        if let Some(ref one) = subschemas.one_of {
+            self.alternatives(one, true, value, path, out);
+        }
+        if let Some(ref not) = subschemas.not {
+            if self.matches(not, value, path) {
+                violation(out, path, "must not match the schema in `not`".to_owned());
+            }
+        }
+        if let Some(ref condition) = subschemas.if_schema {
+            let then = if self.matches(condition, value, path) {
+                &subschemas.then_schema
+            } else {
+                &subschemas.else_schema
+            };
+            if let Some(ref then) = *then {
+                self.schema(then, value, path, out);
+            }
+        }
+    }
+
+    /// Checks `anyOf`, or `oneOf` if `exactly_one`.
+    fn alternatives(&self, schemas: &[Schema], exactly_one: bool, value: &Value, path: &str, out: &mut Vec<Violation>) {
+        let results: Vec<Vec<Violation>> = schemas.iter()
+            .map(|schema| {
+                     let mut violations = Vec::new();
+                     self.schema(schema, value, path, &mut violations);
+                     violations
+                 })
+            .collect();
+        let matched = results.iter().filter(|violations| violations.is_empty()).count();
+        if matched > 1 && exactly_one {
+            return violation(out, path, format!("must match exactly one schema in `oneOf`, matches {}", matched));
+        }
+        if matched > 0 {
+            return;
+        }
+        // An alternative that only fails inside the value, like the struct
+        // of an `Option`, is most likely the one that was meant.
+        let mut deep = results.into_iter().filter(|violations| violations.iter().all(|v| v.path != path));
+        match (deep.next(), deep.next()) {
+            (Some(violations), None) => out.extend(violations),
+            _ => {
+                let keyword = if exactly_one { "oneOf" } else { "anyOf" };
+                violation(out, path, format!("does not match any schema in `{}`", keyword));
+            }
+        }
+    }
+
+    fn number(&self, schema: &SchemaObject, n: f64, path: &str, out: &mut Vec<Violation>) {
+        let number = match schema.number {
+            Some(ref number) => number,
+            None => return,
+        };
+        if let Some(min) = number.minimum {
+            if n < min {
+                violation(out, path, format!("must be at least {}, found {}", min, n));
+            }
+        }
+        if let Some(max) = number.maximum {
+            if n > max {
+                violation(out, path, format!("must be at most {}, found {}", max, n));
+            }
+        }
+        if let Some(min) = number.exclusive_minimum {
+            if n <= min {
+                violation(out, path, format!("must be greater than {}, found {}", min, n));
+            }
+        }
+        if let Some(max) = number.exclusive_maximum {
+            if n >= max {
+                violation(out, path, format!("must be less than {}, found {}", max, n));
+            }
+        }
+        if let Some(multiple) = number.multiple_of {
+            if (n / multiple).fract() != 0.0 {
+                violation(out, path, format!("must be a multiple of {}, found {}", multiple, n));
+            }
+        }
+    }
+
+    fn string(&self, schema: &SchemaObject, s: &str, path: &str, out: &mut Vec<Violation>) {
+        let string = match schema.string {
+            Some(ref string) => string,
+            None => return,
+        };
+        let len = s.chars().count();
+        if let Some(min) = string.min_length {
+            if len < min as usize {
+                violation(out, path, format!("must be at least {} characters long, found {}", min, len));
+            }
+        }
+        if let Some(max) = string.max_length {
+            if len > max as usize {
+                violation(out, path, format!("must be at most {} characters long, found {}", max, len));
+            }
+        }
+        #[cfg(feature = "regex")]
+        {
+            if let Some(ref pattern) = string.pattern {
+                if !is_match(pattern, s) {
+                    violation(out, path, format!("must match `{}`, found {}", pattern, s));
+                }
+            }
+        }
+    }
+
+    fn sequence(&self, schema: &SchemaObject, seq: &[Value], path: &str, out: &mut Vec<Violation>) {
+        let array = match schema.array {
+            Some(ref array) => array,
+            None => return,
+        };
+        if let Some(min) = array.min_items {
+            if seq.len() < min as usize {
+                violation(out, path, format!("must have at least {} elements, found {}", min, seq.len()));
+            }
+        }
+        if let Some(max) = array.max_items {
+            if seq.len() > max as usize {
+                violation(out, path, format!("must have at most {} elements, found {}", max, seq.len()));
+            }
+        }
+        if array.unique_items == Some(true) {
+            for (i, element) in seq.iter().enumerate() {
+                if seq[..i].contains(element) {
+                    violation(out, &index_path(path, i), "is a duplicate of an earlier element".to_owned());
+                }
+            }
+        }
+        if let Some(ref contains) = array.contains {
+            if !seq.iter().enumerate().any(|(i, element)| self.matches(contains, element, &index_path(path, i))) {
+                violation(out, path, "must have an element that matches the schema in `contains`".to_owned());
+            }
+        }
+        for (i, element) in seq.iter().enumerate() {
+            let schema = match array.items {
+                Some(SingleOrVec::Single(ref schema)) => Some(&**schema),
+                Some(SingleOrVec::Vec(ref schemas)) if i < schemas.len() => Some(&schemas[i]),
+                Some(SingleOrVec::Vec(_)) => array.additional_items.as_deref(),
+                None => None,
+            };
+            if let Some(schema) = schema {
+                self.schema(schema, element, &index_path(path, i), out);
+            }
+        }
+    }
+
+    fn mapping(&self, schema: &SchemaObject, value: &Value, path: &str, out: &mut Vec<Violation>) {
+        let object = match schema.object {
+            Some(ref object) => object,
+            None => return,
+        };
+        let mapping = value.as_mapping().expect("mapping");
+        let keys: Vec<String> = mapping.iter().map(|(key, _)| scalar_text(key)).collect();
+        if let Some(min) = object.min_properties {
+            if mapping.len() < min as usize {
+                violation(out, path, format!("must have at least {} entries, found {}", min, mapping.len()));
+            }
+        }
+        if let Some(max) = object.max_properties {
+            if mapping.len() > max as usize {
+                violation(out, path, format!("must have at most {} entries, found {}", max, mapping.len()));
+            }
+        }
+        for required in &object.required {
+            if !keys.contains(required) {
+                violation(out, path, format!("is missing `{}`", required));
+            }
+        }
+        for (key, (_, value)) in keys.iter().zip(mapping.iter()) {
+            let entry = key_path(path, key);
+            if let Some(ref names) = object.property_names {
+                if !self.matches(names, &Value::String(key.clone()), &entry) {
+                    violation(out, &entry, "is not an allowed key".to_owned());
+                }
+            }
+            let mut known = false;
+            if let Some(schema) = object.properties.get(key) {
+                known = true;
+                self.schema(schema, value, &entry, out);
+            }
+            #[cfg(feature = "regex")]
+            {
+                for (pattern, schema) in &object.pattern_properties {
+                    if is_match(pattern, key) {
+                        known = true;
+                        self.schema(schema, value, &entry, out);
+                    }
+                }
+            }
+            if known {
+                continue;
+            }
+            match object.additional_properties {
+                Some(ref schema) if **schema == Schema::Bool(false) => {
+                    violation(out, &entry, "is not an allowed key".to_owned());
+                }
+                Some(ref schema) => self.schema(schema, value, &entry, out),
+                None => {}
+            }
+        }
+    }
+}
+
+fn violation(out: &mut Vec<Violation>, path: &str, message: String) {
+    out.push(Violation {
+                 path: path.to_owned(),
+                 message: message,
+             });
+}
+
+fn index_path(path: &str, index: usize) -> String {
+    let parent = if path == "." { "" } else { path };
+    format!("{}[{}]", parent, index)
+}
+
+fn key_path(path: &str, key: &str) -> String {
+    if path == "." {
+        key.to_owned()
+    } else {
+        format!("{}.{}", path, key)
+    }
+}
+
+/// Checks an integer against the range of the Rust type named by its
+/// format, like `uint16`, which is how schemars describes integer types.
+/// Bounds that `minimum` and `maximum` already narrow are left to them.
+fn integer_format(schema: &SchemaObject, format: &str, n: &Number, path: &str, out: &mut Vec<Violation>) {
+    let (min, max): (i128, i128) = match format {
+        "int8" => (i8::MIN.into(), i8::MAX.into()),
+        "int16" => (i16::MIN.into(), i16::MAX.into()),
+        "int32" => (i32::MIN.into(), i32::MAX.into()),
+        "int64" => (i64::MIN.into(), i64::MAX.into()),
+        "uint8" => (0, u8::MAX.into()),
+        "uint16" => (0, u16::MAX.into()),
+        "uint32" => (0, u32::MAX.into()),
+        "uint64" => (0, u64::MAX.into()),
+        _ => return,
+    };
+    let n = match (n.as_i64(), n.as_u64()) {
+        (Some(n), _) => i128::from(n),
+        (None, Some(n)) => i128::from(n),
+        // Floats are left to the type check.
+        (None, None) => return,
+    };
+    let number = schema.number.as_ref();
+    let minimum = number.and_then(|number| number.minimum).map_or(false, |minimum| minimum >= min as f64);
+    let maximum = number.and_then(|number| number.maximum).map_or(false, |maximum| maximum <= max as f64);
+    if n < min && !minimum {
+        violation(out, path, format!("must be at least {}, found {}", min, n));
+    } else if n > max && !maximum {
+        violation(out, path, format!("must be at most {}, found {}", max, n));
+    }
+}
+
+fn type_matches(types: &SingleOrVec<InstanceType>, value: &Value) -> bool {
+    match *types {
+        SingleOrVec::Single(ref ty) => is_type(ty, value),
+        SingleOrVec::Vec(ref types) => types.iter().any(|ty| is_type(ty, value)),
+    }
+}
+
+fn is_type(ty: &InstanceType, value: &Value) -> bool {
+    match (ty, value) {
+        (&InstanceType::Null, &Value::Null) |
+        (&InstanceType::Boolean, &Value::Bool(_)) |
+        (&InstanceType::Number, &Value::Number(_)) |
+        (&InstanceType::String, &Value::String(_)) |
+        (&InstanceType::Array, &Value::Sequence(_)) |
+        (&InstanceType::Object, &Value::Mapping(_)) => true,
+        (&InstanceType::Integer, &Value::Number(ref n)) => !n.is_f64(),
+        _ => false,
+    }
+}
+
+fn type_name(ty: &InstanceType) -> &'static str {
+    match *ty {
+        InstanceType::Null => "null",
+        InstanceType::Boolean => "boolean",
+        InstanceType::Object => "object",
+        InstanceType::Array => "array",
+        InstanceType::Number => "number",
+        InstanceType::String => "string",
+        InstanceType::Integer => "integer",
+    }
+}
+
+fn value_type(value: &Value) -> &'static str {
+    match *value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(ref n) if n.is_f64() => "number",
+        Value::Number(_) => "integer",
+        Value::String(_) => "string",
+        Value::Sequence(_) => "array",
+        Value::Mapping(_) => "object",
+    }
+}
+
+/// Whether the regex `pattern` finds a match in `s`. A pattern that does not
+/// compile matches nothing.
+#[cfg(feature = "regex")]
+fn is_match(pattern: &str, s: &str) -> bool {
+    Regex::new(pattern).map(|regex| regex.is_match(s)).unwrap_or(false)
+}
@@ -9,14 +9,34 @@
 //! YAML Serialization
 //!
 //! This module provides YAML serialization with the type `Serializer`.
+//!
+//! Floats are written as the shortest text that reads back as exactly the
+//! same value, and always with a decimal point or an exponent, so `1.0` is
+//! written as `1.0` rather than as the integer `1`.
 
-use std::{fmt, io};
+use std::{cmp, fmt, io, mem};
+use std::cell::Cell;
+use std::sync::Arc;
 
-use yaml_rust::{yaml, Yaml, YamlEmitter};
+use yaml_rust::{yaml, Yaml};
+use yaml_rust::emitter::EmitError;
 
 use serde::ser;
 
+use super::comment;
+use super::emitter::{self, Anchors, BlankLine, Emitter, NonFiniteSpelling, StringStyles};
+#[cfg(feature = "value")]
+use super::emitter::AnchorNamer;
 use super::error::{Error, Result};
+use super::event;
+use super::number::{self, format_float, Number};
+use super::path::Segment;
+use super::style;
+use super::tags;
+#[cfg(feature = "value")]
+use super::value::Value;
+#[cfg(feature = "shared")]
+use super::shared;
 
 pub struct Serializer;
 
@@ -68,12 +88,24 @@ impl ser::Serializer for Serializer {
         Ok(Yaml::Real(v.to_string()))
     }
 
+    fn serialize_i128(self, v: i128) -> Result<Yaml> {
+        if v as i64 as i128 == v {
+            self.serialize_i64(v as i64)
+        } else {
+            Ok(Yaml::Real(v.to_string()))
+        }
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<Yaml> {
+        Ok(Yaml::Real(v.to_string()))
+    }
+
     fn serialize_f32(self, v: f32) -> Result<Yaml> {
-        self.serialize_f64(v as f64)
+        Ok(Yaml::Real(format_float(v)))
     }
 
     fn serialize_f64(self, v: f64) -> Result<Yaml> {
-        Ok(Yaml::Real(v.to_string()))
+        Ok(Yaml::Real(format_float(v)))
     }
 
     fn serialize_char(self, value: char) -> Result<Yaml> {
@@ -105,9 +137,28 @@ impl ser::Serializer for Serializer {
         Ok(Yaml::String(variant.to_owned()))
     }
 
-    fn serialize_newtype_struct<T: ?Sized>(self, _name: &'static str, value: &T) -> Result<Yaml>
+        fn serialize_newtype_struct<T: ?Sized>(self, name: &'static str, value: &T) -> Result<Yaml>
         where T: ser::Serialize
     {
+        if name == number::TOKEN {
+            return match to_yaml(value)? {
+                Yaml::String(ref text) if Number::is_number(text) => Ok(Yaml::Real(text.clone())),
+                Yaml::String(text) => Err(Error::serialize(format_args!("`{}` is not a number", text))),
+                other => Ok(other),
+            };
+        }
+        if name == comment::TOKEN {
+            return comment::serialize_node(value);
+        }
+        if name == style::TOKEN {
+            return style::serialize_node(value);
+        }
+        #[cfg(feature = "shared")]
+        {
+            if name == shared::TOKEN {
+                return shared::serialize_node(value);
+            }
+        }
         value.serialize(self)
     }
 
@@ -119,7 +170,8 @@ impl ser::Serializer for Serializer {
                                             -> Result<Yaml>
         where T: ser::Serialize
     {
-        Ok(singleton_hash(to_yaml(variant)?, to_yaml(value)?))
+        let value = to_yaml(value).map_err(|err| err.within(Segment::Key(variant.to_owned())))?;
+        Ok(singleton_hash(to_yaml(variant)?, value))
     }
 
     fn serialize_none(self) -> Result<Yaml> {
@@ -184,6 +236,92 @@ impl ser::Serializer for Serializer {
     }
 }
 
+/// Serializes mapping keys. Keys are serialized like values, except that
+/// bytes become a `!!binary` scalar rather than a sequence of numbers, as a
+/// sequence would make a complex key that few YAML parsers accept.
+struct KeySerializer;
+
+macro_rules! forward_to_serializer {
+    ($($method:ident ( $($arg:ident : $ty:ty),* ) -> $ok:ty;)*) => {
+        $(
+            fn $method(self, $($arg: $ty),*) -> Result<$ok> {
+                ser::Serializer::$method(Serializer, $($arg),*)
+            }
+        )*
+    };
+}
+
+impl ser::Serializer for KeySerializer {
+    type Ok = Yaml;
+    type Error = Error;
+
+    type SerializeSeq = SerializeArray;
+    type SerializeTuple = SerializeArray;
+    type SerializeTupleStruct = SerializeArray;
+    type SerializeTupleVariant = SerializeTupleVariant;
+    type SerializeMap = SerializeMap;
+    type SerializeStruct = SerializeStruct;
+    type SerializeStructVariant = SerializeStructVariant;
+
+    forward_to_serializer! {
+        serialize_bool(v: bool) -> Yaml;
+        serialize_i8(v: i8) -> Yaml;
+        serialize_i16(v: i16) -> Yaml;
+        serialize_i32(v: i32) -> Yaml;
+        serialize_i64(v: i64) -> Yaml;
+        serialize_u8(v: u8) -> Yaml;
+        serialize_u16(v: u16) -> Yaml;
+        serialize_u32(v: u32) -> Yaml;
+        serialize_u64(v: u64) -> Yaml;
+        serialize_i128(v: i128) -> Yaml;
+        serialize_u128(v: u128) -> Yaml;
+        serialize_f32(v: f32) -> Yaml;
+        serialize_f64(v: f64) -> Yaml;
+        serialize_char(v: char) -> Yaml;
+        serialize_str(v: &str) -> Yaml;
+        serialize_unit() -> Yaml;
+        serialize_unit_struct(name: &'static str) -> Yaml;
+        serialize_unit_variant(name: &'static str, variant_index: u32, variant: &'static str) -> Yaml;
+        serialize_none() -> Yaml;
+        serialize_seq(len: Option<usize>) -> SerializeArray;
+        serialize_tuple(len: usize) -> SerializeArray;
+        serialize_tuple_struct(name: &'static str, len: usize) -> SerializeArray;
+        serialize_tuple_variant(name: &'static str, variant_index: u32, variant: &'static str, len: usize)
+            -> SerializeTupleVariant;
+        serialize_map(len: Option<usize>) -> SerializeMap;
+        serialize_struct(name: &'static str, len: usize) -> SerializeStruct;
+        serialize_struct_variant(name: &'static str, variant_index: u32, variant: &'static str, len: usize)
+            -> SerializeStructVariant;
+    }
+
+    fn serialize_bytes(self, value: &[u8]) -> Result<Yaml> {
+        Ok(tags::binary_key(value))
+    }
+
+    fn serialize_newtype_struct<T: ?Sized>(self, name: &'static str, value: &T) -> Result<Yaml>
+        where T: ser::Serialize
+    {
+        Serializer.serialize_newtype_struct(name, value)
+    }
+
+    fn serialize_newtype_variant<T: ?Sized>(self,
+                                            name: &'static str,
+                                            variant_index: u32,
+                                            variant: &'static str,
+                                            value: &T)
+                                            -> Result<Yaml>
+        where T: ser::Serialize
+    {
+        Serializer.serialize_newtype_variant(name, variant_index, variant, value)
+    }
+
+    fn serialize_some<V: ?Sized>(self, value: &V) -> Result<Yaml>
+        where V: ser::Serialize
+    {
+        value.serialize(self)
+    }
+}
+
 #[doc(hidden)]
 pub struct SerializeArray {
     array: yaml::Array,
@@ -219,7 +357,8 @@ impl ser::SerializeSeq for SerializeArray {
     fn serialize_element<T: ?Sized>(&mut self, elem: &T) -> Result<()>
         where T: ser::Serialize
     {
-        self.array.push(to_yaml(elem)?);
+        let index = self.array.len();
+        self.array.push(to_yaml(elem).map_err(|err| err.within(Segment::Index(index)))?);
         Ok(())
     }
 
@@ -265,7 +404,11 @@ impl ser::SerializeTupleVariant for SerializeTupleVariant {
     fn serialize_field<V: ?Sized>(&mut self, v: &V) -> Result<()>
         where V: ser::Serialize
     {
-        self.array.push(to_yaml(v)?);
+        let index = self.array.len();
+        let v = to_yaml(v).map_err(|err| {
+            err.within(Segment::Index(index)).within(Segment::Key(self.name.to_owned()))
+        })?;
+        self.array.push(v);
         Ok(())
     }
 
@@ -281,7 +424,7 @@ impl ser::SerializeMap for SerializeMap {
     fn serialize_key<T: ?Sized>(&mut self, key: &T) -> Result<()>
         where T: ser::Serialize
     {
-        self.next_key = Some(to_yaml(key)?);
+        self.next_key = Some(key.serialize(KeySerializer)?);
         Ok(())
     }
 
@@ -289,7 +432,10 @@ impl ser::SerializeMap for SerializeMap {
         where T: ser::Serialize
     {
         match self.next_key.take() {
-            Some(key) => self.hash.insert(key, to_yaml(value)?),
+            Some(key) => {
+                let value = to_yaml(value).map_err(|err| err.within(key_segment(&key)))?;
+                self.hash.insert(key, value)
+            }
             None => panic!("serialize_value called before serialize_key"),
         };
         Ok(())
@@ -299,15 +445,44 @@ impl ser::SerializeMap for SerializeMap {
         where K: ser::Serialize,
               V: ser::Serialize
     {
-        self.hash.insert(to_yaml(key)?, to_yaml(value)?);
+        let key = key.serialize(KeySerializer)?;
+        let value = to_yaml(value).map_err(|err| err.within(key_segment(&key)))?;
+        self.hash.insert(key, value);
         Ok(())
     }
 
     fn end(self) -> Result<Yaml> {
+        if SORT_MAPS.with(Cell::get) {
+            return Ok(Yaml::Hash(sorted(self.hash)));
+        }
         Ok(Yaml::Hash(self.hash))
     }
 }
 
+thread_local! {
+    /// Whether the document being built on this thread sorts its maps.
+    static SORT_MAPS: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Puts back the previous `SORT_MAPS` even if building a document panics.
+struct SortMaps(bool);
+
+impl Drop for SortMaps {
+    fn drop(&mut self) {
+        SORT_MAPS.with(|sort| sort.set(self.0));
+    }
+}
+
+/// The entries of a map in order of their keys, looking through comments.
+fn sorted(hash: yaml::Hash) -> yaml::Hash {
+    fn key(key: &Yaml) -> &Yaml {
+        comment::split(key).map_or(key, |(_, key)| key)
+    }
+    let mut entries: Vec<(Yaml, Yaml)> = hash.into_iter().collect();
+    entries.sort_by(|a, b| key(&a.0).cmp(key(&b.0)));
+    entries.into_iter().collect()
+}
+
 impl ser::SerializeStruct for SerializeStruct {
     type Ok = yaml::Yaml;
     type Error = Error;
@@ -315,7 +490,8 @@ impl ser::SerializeStruct for SerializeStruct {
     fn serialize_field<V: ?Sized>(&mut self, key: &'static str, value: &V) -> Result<()>
         where V: ser::Serialize
     {
-        self.hash.insert(to_yaml(key)?, to_yaml(value)?);
+        let value = to_yaml(value).map_err(|err| err.within(Segment::Key(key.to_owned())))?;
+        self.hash.insert(to_yaml(key)?, value);
         Ok(())
     }
 
@@ -331,7 +507,10 @@ impl ser::SerializeStructVariant for SerializeStructVariant {
     fn serialize_field<V: ?Sized>(&mut self, field: &'static str, v: &V) -> Result<()>
         where V: ser::Serialize
     {
-        self.hash.insert(to_yaml(field)?, to_yaml(v)?);
+        let v = to_yaml(v).map_err(|err| {
+            err.within(Segment::Key(field.to_owned())).within(Segment::Key(self.name.to_owned()))
+        })?;
+        self.hash.insert(to_yaml(field)?, v);
         Ok(())
     }
 
@@ -342,16 +521,73 @@ impl ser::SerializeStructVariant for SerializeStructVariant {
 
 /// Serialize the given data structure as YAML into the IO stream.
 ///
+/// The output is handed to the writer in chunks of a few kilobytes as it is
+/// produced, so there is no need to wrap the writer in a `BufWriter`.
+///
 /// Serialization can fail if `T`'s implementation of `Serialize` decides to
 /// return an error.
 pub fn to_writer<W, T: ?Sized>(writer: W, value: &T) -> Result<()>
     where W: io::Write,
           T: ser::Serialize
 {
-    let doc = to_yaml(value)?;
-    let mut writer_adapter = FmtToIoWriter { writer: writer };
-    YamlEmitter::new(&mut writer_adapter).dump(&doc).map_err(Error::emitter)?;
-    Ok(())
+    let mut out = ChunkedWriter::new(writer);
+    emit(&mut out, value, &Options::default(), &mut None)?;
+    out.write_chunk()
+}
+
+/// Serialize each of the values as a document of one YAML stream into the IO
+/// stream, each starting with `---`. This is `Writer` with its default
+/// options; use that directly for options like `end_markers`.
+///
+/// ```rust
+/// # extern crate serde_yaml;
+/// #
+/// # fn main() {
+/// let mut out = Vec::new();
+/// serde_yaml::to_writer_multi(&mut out, &[vec!["a"], vec!["b", "c"]]).unwrap();
+/// assert_eq!(String::from_utf8(out).unwrap(), "---\n- a\n---\n- b\n- c\n");
+/// # }
+/// ```
+///
+/// Serialization can fail if `T`'s implementation of `Serialize` decides to
+/// return an error, or if the writer does. The documents before the one
+/// that failed have been written.
+pub fn to_writer_multi<W, I>(writer: W, values: I) -> Result<()>
+    where W: io::Write,
+          I: IntoIterator,
+          I::Item: ser::Serialize
+{
+    let mut writer = Writer::new(writer);
+    for value in values {
+        writer.write(&value)?;
+    }
+    writer.flush()
+}
+
+/// Serialize the given data structure as YAML into a `fmt::Write`, such as a
+/// `String` or a `fmt::Formatter`.
+///
+/// ```rust
+/// # extern crate serde_yaml;
+/// #
+/// # use std::fmt::Write;
+/// #
+/// # fn main() {
+/// let mut out = String::from("# generated\n");
+/// serde_yaml::to_fmt_writer(&mut out, &vec!["a", "b"]).unwrap();
+/// writeln!(out).unwrap();
+/// assert_eq!(out, "# generated\n---\n- a\n- b\n");
+/// # }
+/// ```
+///
+/// Serialization can fail if `T`'s implementation of `Serialize` decides to
+/// return an error, or if the writer does.
+pub fn to_fmt_writer<W, T: ?Sized>(mut writer: W, value: &T) -> Result<()>
+    where W: fmt::Write,
+          T: ser::Serialize
+{
+    let (doc, anchors) = document(value)?;
+    Emitter::new(&mut writer, &anchors).dump(&doc).map_err(Error::emitter)
 }
 
 /// Serialize the given data structure as a YAML byte vector.
@@ -361,9 +597,7 @@ pub fn to_writer<W, T: ?Sized>(writer: W, value: &T) -> Result<()>
 pub fn to_vec<T: ?Sized>(value: &T) -> Result<Vec<u8>>
     where T: ser::Serialize
 {
-    let mut vec = Vec::with_capacity(128);
-    to_writer(&mut vec, value)?;
-    Ok(vec)
+    to_string(value).map(String::into_bytes)
 }
 
 /// Serialize the given data structure as a String of YAML.
@@ -373,33 +607,1176 @@ pub fn to_vec<T: ?Sized>(value: &T) -> Result<Vec<u8>>
 pub fn to_string<T: ?Sized>(value: &T) -> Result<String>
     where T: ser::Serialize
 {
-    Ok(String::from_utf8(to_vec(value)?).map_err(Error::string_utf8)?)
+    let (doc, anchors) = document(value)?;
+    let mut string = String::with_capacity(estimated_len(&doc, &anchors));
+    Emitter::new(&mut string, &anchors).dump(&doc).map_err(Error::emitter)?;
+    Ok(string)
+}
+
+/// Serialize the given data structure as a fragment of YAML to be spliced
+/// into a larger document, with every line indented by `base_indent` spaces.
+///
+/// This is for templates, like Helm charts, where generated values go under
+/// a handwritten key. There is no `---` before the fragment and no line
+/// break after it, so it goes at the start of a line. Blank lines inside
+/// block scalars are left empty rather than indented, which reads the same.
+///
+/// ```rust
+/// # extern crate serde_yaml;
+/// #
+/// # use std::collections::BTreeMap;
+/// #
+/// # fn main() {
+/// let mut env = BTreeMap::new();
+/// env.insert("LOG_LEVEL", "debug");
+/// env.insert("MODE", "worker");
+/// let template = format!("spec:\n  env:\n{}\n", serde_yaml::to_string_indented(&env, 4).unwrap());
+/// assert_eq!(template, "spec:\n  env:\n    LOG_LEVEL: debug\n    MODE: worker\n");
+/// # }
+/// ```
+///
+/// Serialization can fail if `T`'s implementation of `Serialize` decides to
+/// return an error.
+pub fn to_string_indented<T: ?Sized>(value: &T, base_indent: usize) -> Result<String>
+    where T: ser::Serialize
+{
+    let (doc, anchors) = document(value)?;
+    let mut fragment = String::with_capacity(estimated_len(&doc, &anchors));
+    Emitter::new(&mut fragment, &anchors).dump_fragment(&doc).map_err(Error::emitter)?;
+    let indent = " ".repeat(base_indent);
+    let mut string = String::with_capacity(fragment.len() + fragment.lines().count() * base_indent);
+    for (i, line) in fragment.split('\n').enumerate() {
+        if i > 0 {
+            string.push('\n');
+        }
+        if !line.is_empty() {
+            string.push_str(&indent);
+            string.push_str(line);
+        }
+    }
+    Ok(string)
+}
+
+/// Serialize the given data structure as a single line of YAML in flow style,
+/// such as `{a: 1, b: [2, 3]}`, for log messages and command line arguments.
+///
+/// There is no `---` before the value and no line break after it. Strings
+/// that contain line breaks are written quoted with `\n` escapes, and
+/// comments of `Commented` values are left out.
+///
+/// ```rust
+/// #[macro_use]
+/// extern crate serde_derive;
+/// extern crate serde_yaml;
+///
+/// #[derive(Serialize)]
+/// struct Request {
+///     method: &'static str,
+///     path: &'static str,
+///     headers: Vec<(&'static str, &'static str)>,
+/// }
+///
+/// # fn main() {
+/// let request = Request {
+///     method: "GET",
+///     path: "/",
+///     headers: vec![("accept", "*/*")],
+/// };
+/// let yaml = serde_yaml::to_string_compact(&request).unwrap();
+/// assert_eq!(yaml, r#"{method: GET, path: /, headers: [[accept, "*/*"]]}"#);
+/// # }
+/// ```
+///
+/// Serialization can fail if `T`'s implementation of `Serialize` decides to
+/// return an error.
+pub fn to_string_compact<T: ?Sized>(value: &T) -> Result<String>
+    where T: ser::Serialize
+{
+    let options = Options::default();
+    let (doc, anchors) = build(value, &options)?;
+    let mut string = String::with_capacity(estimated_len(&doc, &anchors));
+    emitter(&mut string, &anchors, &options).dump_flow(&doc).map_err(Error::emitter)?;
+    Ok(string)
+}
+
+/// Serialize the given data structure as the events of one YAML document,
+/// for emitters other than this crate's own. See the `event` module.
+///
+/// The events are the ones that `to_string` writes as text: strings that
+/// would read back as something else are double quoted, and values wrapped
+/// in `shared::Shared` get an anchor. Comments are left out.
+///
+/// ```rust
+/// use serde_yaml::event::Event;
+///
+/// let events = serde_yaml::to_events(&vec!["a", "1"]).unwrap();
+/// assert_eq!(events.len(), 4);
+/// assert_eq!(events[1], Event::plain("a"));
+/// match events[2] {
+///     Event::Scalar(ref scalar) => assert_eq!(scalar.style, serde_yaml::event::ScalarStyle::DoubleQuoted),
+///     _ => unreachable!(),
+/// }
+/// ```
+pub fn to_events<T: ?Sized>(value: &T) -> Result<Vec<event::Event>>
+    where T: ser::Serialize
+{
+    let (doc, anchors) = build(value, &Options::default())?;
+    Ok(event::from_tree(&doc, &anchors))
+}
+
+/// Serialize the given data structure as a String of YAML meant to be read
+/// and edited by people.
+///
+/// This is `YamlFormat::pretty().to_string(value)`, with the choices that
+/// suit most hand-maintained files:
+/// strings of several lines are written as literal block scalars, entries of
+/// mappings whose value is null are left out, and the text ends in a line
+/// break. Keys stay in the order in which they are serialized, which for a
+/// struct is the order of its fields; use `Value::sort_keys_recursively`
+/// first for sorted output. Like all output of this crate, nested
+/// collections are indented by two spaces and long lines are not wrapped.
+///
+/// ```rust
+/// #[macro_use]
+/// extern crate serde_derive;
+/// extern crate serde_yaml;
+///
+/// #[derive(Serialize)]
+/// struct Job {
+///     name: String,
+///     image: Option<String>,
+///     script: String,
+/// }
+///
+/// # fn main() {
+/// let job = Job {
+///     name: "test".to_owned(),
+///     image: None,
+///     script: "cargo build\ncargo test\n".to_owned(),
+/// };
+/// let yaml = serde_yaml::to_string_pretty(&job).unwrap();
+/// assert_eq!(yaml, "---\nname: test\nscript: |\n  cargo build\n  cargo test\n");
+/// # }
+/// ```
+///
+/// Serialization can fail if `T`'s implementation of `Serialize` decides to
+/// return an error.
+pub fn to_string_pretty<T: ?Sized>(value: &T) -> Result<String>
+    where T: ser::Serialize
+{
+    YamlFormat::pretty().to_string(value)
+}
+
+/// Writes a stream of YAML documents to an `io::Write`, one per call to
+/// `write`.
+///
+/// Output is handed to the underlying writer in chunks of a few kilobytes.
+/// A producer that keeps a stream open for a long time, for example to a pipe
+/// or socket, can use `flush_every_document` so that each document reaches
+/// the reader as soon as it is written.
+///
+/// ```rust
+/// # extern crate serde_yaml;
+/// #
+/// # fn main() {
+/// let mut writer = serde_yaml::Writer::new(Vec::new()).flush_every_document(true);
+/// writer.write(&vec![1, 2]).unwrap();
+/// writer.write("done").unwrap();
+/// let bytes = writer.into_inner().unwrap();
+/// assert_eq!(String::from_utf8(bytes).unwrap(), "---\n- 1\n- 2\n---\ndone\n");
+/// # }
+/// ```
+pub struct Writer<W>
+    where W: io::Write
+{
+    /// Taken by `into_inner`, so that dropping the `Writer` afterwards has
+    /// nothing left to flush.
+    out: Option<ChunkedWriter<W>>,
+    flush_every_document: bool,
+    end_markers: bool,
+    /// Written before the first document, then None.
+    header: Option<String>,
+    options: Options,
+}
+
+/// Options of a `Writer` that affect how each document is written.
+#[derive(Clone, Default)]
+struct Options {
+    non_finite: NonFiniteSpelling,
+    underscore_integers: bool,
+    finite_floats_only: bool,
+    prologue: String,
+    blank_lines: Option<Arc<BlankLine>>,
+    #[cfg(feature = "value")]
+    anchor_names: Option<Arc<AnchorNamer>>,
+    string_styles: StringStyles,
+    skip_null_values: bool,
+    scalar_keys_only: bool,
+    sort_maps: bool,
+    /// Set by `YamlFormat::stable` only.
+    stable_quoting: bool,
+}
+
+impl<W> Writer<W>
+    where W: io::Write
+{
+    /// Creates a writer that starts a new YAML stream on `writer`.
+    pub fn new(writer: W) -> Self {
+        Writer {
+            out: Some(ChunkedWriter::new(writer)),
+            flush_every_document: false,
+            end_markers: false,
+            header: None,
+            options: Options::default(),
+        }
+    }
+
+    /// Whether to flush the underlying writer after each document. Off by
+    /// default, in which case output is only guaranteed to have been written
+    /// after `flush` or `into_inner`. Like `BufWriter`, a `Writer` that is
+    /// dropped flushes what is still buffered and ignores any error in doing
+    /// so; call `flush` to find out whether it succeeded.
+    pub fn flush_every_document(mut self, flush: bool) -> Self {
+        self.flush_every_document = flush;
+        self
+    }
+
+    /// Whether to end every document with the end marker `...`. Off by
+    /// default. A reader of a stream that stays open, such as a pipe, knows
+    /// from the marker that a document is complete without waiting for the
+    /// `---` of the next one.
+    ///
+    /// ```rust
+    /// # extern crate serde_yaml;
+    /// #
+    /// # fn main() {
+    /// let mut writer = serde_yaml::Writer::new(Vec::new()).end_markers(true);
+    /// writer.write(&1).unwrap();
+    /// writer.write(&vec!["a"]).unwrap();
+    /// let bytes = writer.into_inner().unwrap();
+    /// assert_eq!(String::from_utf8(bytes).unwrap(), "---\n1\n...\n---\n- a\n...\n");
+    /// # }
+    /// ```
+    pub fn end_markers(mut self, markers: bool) -> Self {
+        self.end_markers = markers;
+        self
+    }
+
+    /// How infinite and NaN floats are spelled. The default is the YAML
+    /// spelling `.inf`, `-.inf` and `.nan`, which some consumers only accept
+    /// in a different case or as `Infinity` and `NaN`.
+    ///
+    /// ```rust
+    /// # extern crate serde_yaml;
+    /// #
+    /// # fn main() {
+    /// use serde_yaml::{NonFiniteSpelling, Writer};
+    ///
+    /// let mut writer = Writer::new(Vec::new()).non_finite_floats(NonFiniteSpelling::Words);
+    /// writer.write(&[1.5, std::f64::NEG_INFINITY]).unwrap();
+    /// let bytes = writer.into_inner().unwrap();
+    /// assert_eq!(String::from_utf8(bytes).unwrap(), "---\n- 1.5\n- -Infinity\n");
+    /// # }
+    /// ```
+    pub fn non_finite_floats(mut self, spelling: NonFiniteSpelling) -> Self {
+        self.options.non_finite = spelling;
+        self
+    }
+
+    /// Whether to group the digits of integers of more than four digits in
+    /// threes with underscores, like `1_000_000`, for readability. Off by
+    /// default. Only YAML 1.1 reads such scalars as numbers; this crate
+    /// reads them back with `NumberDialect::underscores`.
+    ///
+    /// ```rust
+    /// # extern crate serde_yaml;
+    /// #
+    /// # fn main() {
+    /// let mut writer = serde_yaml::Writer::new(Vec::new()).underscore_integers(true);
+    /// writer.write(&[1000, 65536, -1234567]).unwrap();
+    /// let bytes = writer.into_inner().unwrap();
+    /// assert_eq!(String::from_utf8(bytes).unwrap(), "---\n- 1000\n- 65_536\n- -1_234_567\n");
+    /// # }
+    /// ```
+    pub fn underscore_integers(mut self, underscores: bool) -> Self {
+        self.options.underscore_integers = underscores;
+        self
+    }
+
+    /// Whether to fail on infinite and NaN floats instead of writing them.
+    /// Off by default. JSON has no way to represent them, so turning this on
+    /// keeps the output convertible to JSON. The error names the path of the
+    /// offending value.
+    ///
+    /// ```rust
+    /// # extern crate serde_yaml;
+    /// #
+    /// # use std::collections::BTreeMap;
+    /// #
+    /// # fn main() {
+    /// let mut limits = BTreeMap::new();
+    /// limits.insert("max", vec![1.0, std::f64::INFINITY]);
+    /// let mut writer = serde_yaml::Writer::new(Vec::new()).finite_floats_only(true);
+    /// let err = writer.write(&limits).unwrap_err();
+    /// assert_eq!(err.to_string(), "max[1]: infinite or NaN float `.inf` is not allowed");
+    /// # }
+    /// ```
+    pub fn finite_floats_only(mut self, only: bool) -> Self {
+        self.options.finite_floats_only = only;
+        self
+    }
+
+    /// Whether mapping keys that are sequences or mappings are written as
+    /// complex keys, introduced by `?`. On by default. Many YAML parsers
+    /// other than yaml-rust do not read complex keys, so turning this off
+    /// fails on them instead, with an error whose `kind` is
+    /// `ErrorKind::KeyNotScalar`.
+    ///
+    /// ```rust
+    /// # extern crate serde_yaml;
+    /// #
+    /// # use std::collections::BTreeMap;
+    /// #
+    /// # fn main() {
+    /// let mut ports = BTreeMap::new();
+    /// ports.insert(vec![80, 443], "web");
+    /// let mut routes = BTreeMap::new();
+    /// routes.insert("by-port", ports);
+    /// let mut writer = serde_yaml::Writer::new(Vec::new()).complex_keys(false);
+    /// let err = writer.write(&routes).unwrap_err();
+    /// assert!(err.to_string().starts_with("by-port: mapping key is a sequence or a mapping"));
+    /// # }
+    /// ```
+    pub fn complex_keys(mut self, complex: bool) -> Self {
+        self.options.scalar_keys_only = !complex;
+        self
+    }
+
+    /// A comment to write once at the top of the stream, such as a license
+    /// or a note that the file is generated. It is written as `#` lines
+    /// before the first document.
+    ///
+    /// ```rust
+    /// # extern crate serde_yaml;
+    /// #
+    /// # fn main() {
+    /// let mut writer = serde_yaml::Writer::new(Vec::new())
+    ///     .header("Generated by build.rs.\nDo not edit.")
+    ///     .prologue("part");
+    /// writer.write(&1).unwrap();
+    /// writer.write(&2).unwrap();
+    /// let bytes = writer.into_inner().unwrap();
+    /// let expected = "\
+    /// ## Generated by build.rs.
+    /// ## Do not edit.
+    /// ## part
+    /// ---
+    /// 1
+    /// ## part
+    /// ---
+    /// 2
+    /// ";
+    /// assert_eq!(String::from_utf8(bytes).unwrap(), expected);
+    /// # }
+    /// ```
+    pub fn header<C>(mut self, comment: C) -> Self
+        where C: Into<String>
+    {
+        self.header = Some(comment.into());
+        self
+    }
+
+    /// A comment to write before the start of every document, as `#` lines
+    /// just above its `---`. See `header` for an example.
+    pub fn prologue<C>(mut self, comment: C) -> Self
+        where C: Into<String>
+    {
+        self.options.prologue = comment.into();
+        self
+    }
+
+    /// Where to put blank lines between the entries of a document that is a
+    /// mapping, to group the keys of a large file. `between` is called with
+    /// each two adjacent keys and a blank line goes between them if it
+    /// returns true. Only keys that are scalars are passed to it; there is no
+    /// blank line next to other keys. Nested mappings are never split.
+    ///
+    /// ```rust
+    /// # extern crate serde_yaml;
+    /// #
+    /// # use std::collections::BTreeMap;
+    /// #
+    /// # fn main() {
+    /// let mut config = BTreeMap::new();
+    /// config.insert("db.host", "localhost");
+    /// config.insert("db.port", "5432");
+    /// config.insert("log.level", "info");
+    ///
+    /// // A blank line between every two keys would be `|_, _| true`.
+    /// let group = |key: &str| key.split('.').next().unwrap().to_owned();
+    /// let mut writer = serde_yaml::Writer::new(Vec::new())
+    ///     .blank_lines(move |prev, next| group(prev) != group(next));
+    /// writer.write(&config).unwrap();
+    /// let bytes = writer.into_inner().unwrap();
+    /// let expected = "---\ndb.host: localhost\ndb.port: \"5432\"\n\nlog.level: info\n";
+    /// assert_eq!(String::from_utf8(bytes).unwrap(), expected);
+    /// # }
+    /// ```
+    pub fn blank_lines<F>(mut self, between: F) -> Self
+        where F: Fn(&str, &str) -> bool + Send + Sync + 'static
+    {
+        self.options.blank_lines = Some(Arc::new(between));
+        self
+    }
+
+    /// How to name the anchors of values that are written once and referred
+    /// to by aliases, like those wrapped in `shared::Shared`. `namer` is
+    /// called with each such value, the first time it is written, and the
+    /// anchor is named after the string it returns. Without a namer, or
+    /// when it returns `None` or a string that cannot be an anchor, such as
+    /// one with spaces, names are `id001`, `id002` and so on. A name that
+    /// has already been used in the document gets a suffix like `_2`.
+    ///
+    /// A namer that names mappings after one of their fields:
+    ///
+    /// ```rust
+    /// # extern crate serde_yaml;
+    /// #
+    /// # fn main() {
+    /// let writer = serde_yaml::Writer::new(Vec::new())
+    ///     .anchor_names(|value| value["name"].as_str().map(str::to_owned));
+    /// # let _ = writer;
+    /// # }
+    /// ```
+    #[cfg(feature = "value")]
+    pub fn anchor_names<F>(mut self, namer: F) -> Self
+        where F: Fn(&Value) -> Option<String> + Send + Sync + 'static
+    {
+        self.options.anchor_names = Some(Arc::new(namer));
+        self
+    }
+
+    /// Whether to write strings of several lines as literal block scalars,
+    /// introduced by `|`, instead of as quoted strings full of `\n`. Off by
+    /// default. Strings that would not read back the same as a block scalar,
+    /// for example because their first line is indented, are still quoted.
+    ///
+    /// ```rust
+    /// # extern crate serde_yaml;
+    /// #
+    /// # use std::collections::BTreeMap;
+    /// #
+    /// # fn main() {
+    /// let mut script = BTreeMap::new();
+    /// script.insert("run", "make\nmake install\n");
+    /// let mut writer = serde_yaml::Writer::new(Vec::new()).block_strings(true);
+    /// writer.write(&script).unwrap();
+    /// let bytes = writer.into_inner().unwrap();
+    /// assert_eq!(String::from_utf8(bytes).unwrap(), "---\nrun: |\n  make\n  make install\n");
+    /// # }
+    /// ```
+    pub fn block_strings(mut self, block: bool) -> Self {
+        self.options.string_styles = self.options.string_styles.literal(block);
+        self
+    }
+
+    /// How to pick the style of each string, from plain, single-quoted,
+    /// double-quoted, literal and folded. By default strings are plain if
+    /// they can be and double-quoted otherwise. `StringStyles::smart()`
+    /// writes them more the way a person would.
+    ///
+    /// ```rust
+    /// # extern crate serde_yaml;
+    /// #
+    /// # use std::collections::BTreeMap;
+    /// #
+    /// # fn main() {
+    /// use serde_yaml::{StringStyles, Writer};
+    ///
+    /// let mut config = BTreeMap::new();
+    /// config.insert("enabled", "yes");
+    /// config.insert("path", "C:\\Program Files");
+    /// config.insert("motd", "Welcome! This server is for authorized use only. Activity is logged and reviewed.");
+    /// let mut writer = Writer::new(Vec::new()).string_styles(StringStyles::smart());
+    /// writer.write(&config).unwrap();
+    /// let bytes = writer.into_inner().unwrap();
+    /// let expected = [
+    ///     "---",
+    ///     "enabled: 'yes'",
+    ///     "motd: >-",
+    ///     "  Welcome! This server is for authorized use only. Activity is logged and",
+    ///     "  reviewed.",
+    ///     "path: 'C:\\Program Files'",
+    ///     "",
+    /// ];
+    /// assert_eq!(String::from_utf8(bytes).unwrap(), expected.join("\n"));
+    /// # }
+    /// ```
+    pub fn string_styles(mut self, styles: StringStyles) -> Self {
+        self.options.string_styles = styles;
+        self
+    }
+
+    /// Whether to leave out the entries of mappings whose value is null, such
+    /// as struct fields that are `None`. Off by default. Null elements of
+    /// sequences are still written, as are null keys.
+    pub fn skip_null_values(mut self, skip: bool) -> Self {
+        self.options.skip_null_values = skip;
+        self
+    }
+
+    /// Whether to write the entries of maps in order of their keys rather
+    /// than in the order they are iterated, so that a `HashMap` comes out
+    /// the same on every run. Off by default. This applies to everything
+    /// serialized as a map, including a `Value` mapping or a map that keeps
+    /// its own order, but not to the fields of structs. Sets serialize like
+    /// sequences, so to sort a `HashSet` use `with::sorted` on its field.
+    ///
+    /// ```rust
+    /// # extern crate serde_yaml;
+    /// #
+    /// # use std::collections::HashMap;
+    /// #
+    /// # fn main() {
+    /// let mut replicas = HashMap::new();
+    /// replicas.insert("web", 3);
+    /// replicas.insert("db", 1);
+    /// replicas.insert("cache", 2);
+    /// let mut writer = serde_yaml::Writer::new(Vec::new()).sort_maps(true);
+    /// writer.write(&replicas).unwrap();
+    /// let bytes = writer.into_inner().unwrap();
+    /// assert_eq!(String::from_utf8(bytes).unwrap(), "---\ncache: 2\ndb: 1\nweb: 3\n");
+    /// # }
+    /// ```
+    pub fn sort_maps(mut self, sort: bool) -> Self {
+        self.options.sort_maps = sort;
+        self
+    }
+
+    /// Serializes `value` as the next document of the stream.
+    ///
+    /// Serialization can fail if `T`'s implementation of `Serialize` decides
+    /// to return an error, or if writing fails.
+    pub fn write<T: ?Sized>(&mut self, value: &T) -> Result<()>
+        where T: ser::Serialize
+    {
+        let out = self.out.as_mut().expect("writer has been taken");
+        let line_break = emit(out, value, &self.options, &mut self.header)?;
+        let end = match (self.end_markers, line_break) {
+            (true, true) => "...\n",
+            (true, false) => "\n...\n",
+            (false, true) => "",
+            (false, false) => "\n",
+        };
+        fmt::Write::write_str(out, end).map_err(|_| out.error())?;
+        if self.flush_every_document {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Writes out any buffered output and flushes the underlying writer.
+    pub fn flush(&mut self) -> Result<()> {
+        let out = self.out.as_mut().expect("writer has been taken");
+        out.write_chunk()?;
+        out.writer.flush().map_err(Error::io)
+    }
+
+    /// Flushes the stream and returns the underlying writer.
+    pub fn into_inner(mut self) -> Result<W> {
+        self.flush()?;
+        Ok(self.out.take().expect("writer has been taken").writer)
+    }
+}
+
+impl<W> Drop for Writer<W>
+    where W: io::Write
+{
+    fn drop(&mut self) {
+        if self.out.is_some() {
+            let _ = self.flush();
+        }
+    }
+}
+
+/// A configuration of how documents are written, built once and used for
+/// any number of values.
+///
+/// Cloning a `YamlFormat` is cheap, and it is `Send` and `Sync`, so a service
+/// can set one up at startup and share it between threads. The options are
+/// the same as those of `Writer`, which is the way to write a stream of
+/// documents in a format.
+///
+/// ```rust
+/// # extern crate serde_yaml;
+/// #
+/// # use std::collections::BTreeMap;
+/// # use std::thread;
+/// #
+/// # fn main() {
+/// use serde_yaml::{NonFiniteSpelling, YamlFormat};
+///
+/// let format = YamlFormat::new()
+///     .non_finite_floats(NonFiniteSpelling::Words)
+///     .prologue("metrics");
+///
+/// let handles: Vec<_> = (0..4).map(|i| {
+///     let format = format.clone();
+///     thread::spawn(move || {
+///         let mut metrics = BTreeMap::new();
+///         metrics.insert("worker", i as f64);
+///         metrics.insert("load", std::f64::INFINITY);
+///         format.to_string(&metrics).unwrap()
+///     })
+/// }).collect();
+/// let outputs: Vec<String> = handles.into_iter().map(|handle| handle.join().unwrap()).collect();
+/// assert_eq!(outputs[1], "# metrics\n---\nload: Infinity\nworker: 1.0");
+/// # }
+/// ```
+#[derive(Clone, Default)]
+pub struct YamlFormat {
+    options: Arc<Options>,
+    trailing_newline: bool,
+    capacity: usize,
+}
+
+impl YamlFormat {
+    /// The format of `to_string`.
+    pub fn new() -> Self {
+        YamlFormat::default()
+    }
+
+    /// The format of `to_string_pretty`: block strings, no null values and a
+    /// line break at the end.
+    pub fn pretty() -> Self {
+        YamlFormat::new().block_strings(true).skip_null_values(true).trailing_newline(true)
+    }
+
+    /// A format whose output is kept the same across minor versions of this
+    /// crate, for files that are generated and checked in and for golden
+    /// tests: the format of `to_string` with the entries of maps sorted by
+    /// key and a line break at the end. Changes to how `to_string` and
+    /// `pretty` write things, such as which strings are quoted or how floats
+    /// are spelled, only reach this format in a major version. It has its own
+    /// copy of the quoting rules for that, so for example it leaves dates like
+    /// `2024-01-01` plain, which `to_string` quotes.
+    ///
+    /// Further options change the output as usual, and the output they give
+    /// is not covered by the guarantee. See `compare::assert_snapshot_stable`
+    /// to check values against golden files written in this format.
+    pub fn stable() -> Self {
+        let mut format = YamlFormat::new().sort_maps(true).trailing_newline(true);
+        Arc::make_mut(&mut format.options).stable_quoting = true;
+        format
+    }
+
+    /// See `Writer::non_finite_floats`.
+    pub fn non_finite_floats(mut self, spelling: NonFiniteSpelling) -> Self {
+        Arc::make_mut(&mut self.options).non_finite = spelling;
+        self
+    }
+
+    /// See `Writer::underscore_integers`.
+    pub fn underscore_integers(mut self, underscores: bool) -> Self {
+        Arc::make_mut(&mut self.options).underscore_integers = underscores;
+        self
+    }
+
+    /// See `Writer::finite_floats_only`.
+    pub fn finite_floats_only(mut self, only: bool) -> Self {
+        Arc::make_mut(&mut self.options).finite_floats_only = only;
+        self
+    }
+
+    /// See `Writer::complex_keys`.
+    pub fn complex_keys(mut self, complex: bool) -> Self {
+        Arc::make_mut(&mut self.options).scalar_keys_only = !complex;
+        self
+    }
+
+    /// See `Writer::prologue`.
+    pub fn prologue<C>(mut self, comment: C) -> Self
+        where C: Into<String>
+    {
+        Arc::make_mut(&mut self.options).prologue = comment.into();
+        self
+    }
+
+    /// See `Writer::blank_lines`.
+    pub fn blank_lines<F>(mut self, between: F) -> Self
+        where F: Fn(&str, &str) -> bool + Send + Sync + 'static
+    {
+        Arc::make_mut(&mut self.options).blank_lines = Some(Arc::new(between));
+        self
+    }
+
+    /// See `Writer::anchor_names`.
+    #[cfg(feature = "value")]
+    pub fn anchor_names<F>(mut self, namer: F) -> Self
+        where F: Fn(&Value) -> Option<String> + Send + Sync + 'static
+    {
+        Arc::make_mut(&mut self.options).anchor_names = Some(Arc::new(namer));
+        self
+    }
+
+    /// See `Writer::block_strings`.
+    pub fn block_strings(mut self, block: bool) -> Self {
+        let options = Arc::make_mut(&mut self.options);
+        options.string_styles = options.string_styles.literal(block);
+        self
+    }
+
+    /// See `Writer::string_styles`.
+    pub fn string_styles(mut self, styles: StringStyles) -> Self {
+        Arc::make_mut(&mut self.options).string_styles = styles;
+        self
+    }
+
+    /// See `Writer::skip_null_values`.
+    pub fn skip_null_values(mut self, skip: bool) -> Self {
+        Arc::make_mut(&mut self.options).skip_null_values = skip;
+        self
+    }
+
+    /// See `Writer::sort_maps`.
+    pub fn sort_maps(mut self, sort: bool) -> Self {
+        Arc::make_mut(&mut self.options).sort_maps = sort;
+        self
+    }
+
+    /// Whether `to_string`, `to_vec`, `to_writer` and `to_fmt_writer` end
+    /// their output with a line break. Off by default. A `Writer` always
+    /// ends every document with one.
+    pub fn trailing_newline(mut self, newline: bool) -> Self {
+        self.trailing_newline = newline;
+        self
+    }
+
+    /// The least capacity that `to_string` and `to_vec` allocate for their
+    /// output. They size it from an estimate of the output length, which
+    /// counts strings as if they were double quoted and so can fall short
+    /// for block strings or single quotes. A caller that knows about how big
+    /// its documents are can give that here to still allocate only once.
+    ///
+    /// ```rust
+    /// # extern crate serde_yaml;
+    /// #
+    /// # fn main() {
+    /// let format = serde_yaml::YamlFormat::new().capacity(4096);
+    /// let yaml = format.to_string(&vec!["a", "b"]).unwrap();
+    /// assert_eq!(yaml, "---\n- a\n- b");
+    /// assert!(yaml.capacity() >= 4096);
+    /// # }
+    /// ```
+    pub fn capacity(mut self, bytes: usize) -> Self {
+        self.capacity = bytes;
+        self
+    }
+
+    /// Serialize the given data structure as a String of YAML in this format.
+    ///
+    /// Serialization can fail if `T`'s implementation of `Serialize` decides
+    /// to return an error.
+    pub fn to_string<T: ?Sized>(&self, value: &T) -> Result<String>
+        where T: ser::Serialize
+    {
+        let (doc, anchors) = build(value, &self.options)?;
+        let estimate = self.options.prologue.len() + estimated_len(&doc, &anchors) + 1;
+        let capacity = cmp::max(estimate, self.capacity);
+        let mut string = String::with_capacity(capacity);
+        self.dump(&mut string, &doc, &anchors)?;
+        Ok(string)
+    }
+
+    /// Serialize the given data structure as a YAML byte vector in this
+    /// format.
+    ///
+    /// Serialization can fail if `T`'s implementation of `Serialize` decides
+    /// to return an error.
+    pub fn to_vec<T: ?Sized>(&self, value: &T) -> Result<Vec<u8>>
+        where T: ser::Serialize
+    {
+        self.to_string(value).map(String::into_bytes)
+    }
+
+    /// Serialize the given data structure as YAML in this format into the IO
+    /// stream, in chunks like `to_writer`.
+    ///
+    /// Serialization can fail if `T`'s implementation of `Serialize` decides
+    /// to return an error, or if writing fails.
+    pub fn to_writer<W, T: ?Sized>(&self, writer: W, value: &T) -> Result<()>
+        where W: io::Write,
+              T: ser::Serialize
+    {
+        let mut out = ChunkedWriter::new(writer);
+        let line_break = emit(&mut out, value, &self.options, &mut None)?;
+        if self.trailing_newline && !line_break {
+            fmt::Write::write_str(&mut out, "\n").map_err(|_| out.error())?;
+        }
+        out.write_chunk()
+    }
+
+    /// Serialize the given data structure as YAML in this format into a
+    /// `fmt::Write`.
+    ///
+    /// Serialization can fail if `T`'s implementation of `Serialize` decides
+    /// to return an error, or if the writer does.
+    pub fn to_fmt_writer<W, T: ?Sized>(&self, mut writer: W, value: &T) -> Result<()>
+        where W: fmt::Write,
+              T: ser::Serialize
+    {
+        let (doc, anchors) = build(value, &self.options)?;
+        self.dump(&mut writer, &doc, &anchors)
+    }
+
+    fn dump(&self, writer: &mut dyn fmt::Write, doc: &Yaml, anchors: &Anchors) -> Result<()> {
+        let line_break = {
+            let mut emitter = emitter(writer, anchors, &self.options).prologue(&self.options.prologue);
+            emitter.dump(doc).map_err(Error::emitter)?;
+            emitter.ends_with_line_break()
+        };
+        if self.trailing_newline && !line_break {
+            writer.write_str("\n").map_err(|err| Error::emitter(EmitError::FmtError(err)))?;
+        }
+        Ok(())
+    }
+
+    /// Creates a `Writer` that writes a stream of documents in this format.
+    pub fn writer<W>(&self, writer: W) -> Writer<W>
+        where W: io::Write
+    {
+        let mut writer = Writer::new(writer);
+        writer.options = (*self.options).clone();
+        writer
+    }
+}
+
+/// Writes one document, preceded by the header if it has not been written yet,
+/// and returns whether it ends with a line break. See
+/// `Emitter::ends_with_line_break`.
+fn emit<W, T: ?Sized>(out: &mut ChunkedWriter<W>,
+                      value: &T,
+                      options: &Options,
+                      header: &mut Option<String>)
+                      -> Result<bool>
+    where W: io::Write,
+          T: ser::Serialize
+{
+    let (doc, anchors) = build(value, options)?;
+    let header = header.take();
+    let mut emitter = emitter(out, &anchors, options);
+    if let Some(ref header) = header {
+        emitter = emitter.prologue(header);
+    }
+    let mut emitter = emitter.prologue(&options.prologue);
+    match emitter.dump(&doc) {
+        Ok(()) => Ok(emitter.ends_with_line_break()),
+        Err(EmitError::FmtError(_)) if out.failed.is_some() => Err(out.error()),
+        Err(err) => Err(Error::emitter(err)),
+    }
+}
+
+/// Builds the tree for one document and applies the options that change
+/// what is written.
+fn build<T: ?Sized>(value: &T, options: &Options) -> Result<(Yaml, Anchors)>
+    where T: ser::Serialize
+{
+    let (mut doc, mut anchors) = {
+        let _restore = SortMaps(SORT_MAPS.with(|sort| sort.replace(options.sort_maps)));
+        document(value)?
+    };
+    if options.finite_floats_only {
+        check_finite(&doc, &anchors)?;
+    }
+    if options.scalar_keys_only {
+        check_scalar_keys(&doc, &anchors)?;
+    }
+    if options.skip_null_values {
+        skip_null_values(&mut doc, &anchors);
+        for id in 0..anchors.nodes.len() {
+            // A shared node cannot contain an alias to itself.
+            let mut node = mem::replace(&mut anchors.nodes[id], Yaml::Null);
+            skip_null_values(&mut node, &anchors);
+            anchors.nodes[id] = node;
+        }
+    }
+    Ok((doc, anchors))
+}
+
+/// An emitter that writes documents the way the options say, except for
+/// the prologue.
+fn emitter<'a>(out: &'a mut dyn fmt::Write, anchors: &'a Anchors, options: &'a Options) -> Emitter<'a> {
+    let mut emitter = Emitter::new(out, anchors)
+        .non_finite(options.non_finite)
+        .underscore_integers(options.underscore_integers)
+        .string_styles(options.string_styles)
+        .stable_quoting(options.stable_quoting);
+    if let Some(ref between) = options.blank_lines {
+        emitter = emitter.blank_lines(&**between);
+    }
+    #[cfg(feature = "value")]
+    {
+        if let Some(ref namer) = options.anchor_names {
+            emitter = emitter.anchor_names(&**namer);
+        }
+    }
+    emitter
+}
+
+/// About how long a document is when written in block style, so that the
+/// output can be allocated once. Strings are counted as if they were double
+/// quoted, escapes included, and shared nodes as if they were written out in
+/// full only the first time.
+fn estimated_len(doc: &Yaml, anchors: &Anchors) -> usize {
+    let mut estimate = Estimate {
+        anchors: anchors,
+        seen: vec![false; anchors.nodes.len()],
+    };
+    "---\n".len() + estimate.node(doc, 0, false)
 }
 
-/// The yaml-rust library uses `fmt::Write` intead of `io::Write` so this is a
-/// simple adapter.
-struct FmtToIoWriter<W> {
+struct Estimate<'a> {
+    anchors: &'a Anchors,
+    seen: Vec<bool>,
+}
+
+impl<'a> Estimate<'a> {
+    /// About how long `node` is when written at `depth`. `inline` is
+    /// whether it is the element of a sequence, where a collection starts on
+    /// the line of the `- `.
+    fn node(&mut self, node: &Yaml, depth: usize, inline: bool) -> usize {
+        if let Some((comment, node)) = comment::split(node) {
+            // Styles have an empty comment, which takes no line.
+            let lines = if comment.is_empty() { 0 } else { comment.len() + 2 * depth + 3 };
+            return lines + self.node(node, depth, inline);
+        }
+        // Each element or entry is on its own line, after the indentation
+        // and `- ` or with `: ` after the key, except the first one of an
+        // inline collection.
+        let line = 2 * depth + 3;
+        let first = if inline { 2 } else { line };
+        match *node {
+            Yaml::String(ref s) => emitter::quoted_len(s),
+            Yaml::Real(ref s) => s.len(),
+            Yaml::Integer(i) => number::display_len(i),
+            Yaml::Boolean(_) => 5,
+            Yaml::Null | Yaml::BadValue => 1,
+            Yaml::Array(ref array) if array.is_empty() => 2,
+            Yaml::Array(ref array) => {
+                let elements: usize = array.iter().map(|element| self.node(element, depth + 1, true)).sum();
+                first + line * (array.len() - 1) + elements
+            }
+            Yaml::Hash(ref hash) if hash.is_empty() => 2,
+            Yaml::Hash(ref hash) => {
+                let mut len = first + line * (hash.len() - 1);
+                for (key, value) in hash {
+                    len += self.node(key, depth + 1, false) + self.node(value, depth + 1, false);
+                }
+                len
+            }
+            Yaml::Alias(id) if self.seen[id] => "*id001".len(),
+            Yaml::Alias(id) => {
+                self.seen[id] = true;
+                "&id001 ".len() + self.node(&self.anchors.nodes[id], depth, inline)
+            }
+        }
+    }
+}
+
+/// Removes the entries of mappings whose value is null.
+fn skip_null_values(node: &mut Yaml, anchors: &Anchors) {
+    match *node {
+        Yaml::Array(ref mut array) => {
+            for element in array {
+                skip_null_values(element, anchors);
+            }
+        }
+        Yaml::Hash(ref mut hash) => {
+            for (key, mut value) in mem::replace(hash, yaml::Hash::new()) {
+                if !is_null(&value, anchors) {
+                    skip_null_values(&mut value, anchors);
+                    hash.insert(key, value);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+fn is_null(node: &Yaml, anchors: &Anchors) -> bool {
+    if let Some((_, node)) = comment::split(node) {
+        return is_null(node, anchors);
+    }
+    match *node {
+        Yaml::Null => true,
+        Yaml::Alias(id) => is_null(&anchors.nodes[id], anchors),
+        _ => false,
+    }
+}
+
+/// Fails on the first infinite or NaN float in the document.
+fn check_finite(node: &Yaml, anchors: &Anchors) -> Result<()> {
+    if let Some((_, node)) = comment::split(node) {
+        return check_finite(node, anchors);
+    }
+    match *node {
+        Yaml::Real(ref v) if v == ".inf" || v == "-.inf" || v == ".nan" => {
+            Err(Error::serialize(format_args!("infinite or NaN float `{}` is not allowed", v)))
+        }
+        Yaml::Array(ref array) => {
+            for (index, element) in array.iter().enumerate() {
+                check_finite(element, anchors).map_err(|err| err.within(Segment::Index(index)))?;
+            }
+            Ok(())
+        }
+        Yaml::Hash(ref hash) => {
+            for (key, value) in hash {
+                check_finite(key, anchors)?;
+                check_finite(value, anchors).map_err(|err| err.within(key_segment(key)))?;
+            }
+            Ok(())
+        }
+        Yaml::Alias(id) => check_finite(&anchors.nodes[id], anchors),
+        _ => Ok(()),
+    }
+}
+
+/// Fails on the first mapping key in the document that is a sequence or a
+/// mapping.
+fn check_scalar_keys(node: &Yaml, anchors: &Anchors) -> Result<()> {
+    if let Some((_, node)) = comment::split(node) {
+        return check_scalar_keys(node, anchors);
+    }
+    match *node {
+        Yaml::Array(ref array) => {
+            for (index, element) in array.iter().enumerate() {
+                check_scalar_keys(element, anchors).map_err(|err| err.within(Segment::Index(index)))?;
+            }
+            Ok(())
+        }
+        Yaml::Hash(ref hash) => {
+            for (key, value) in hash {
+                if is_collection(key, anchors) {
+                    return Err(Error::key_not_scalar());
+                }
+                check_scalar_keys(value, anchors).map_err(|err| err.within(key_segment(key)))?;
+            }
+            Ok(())
+        }
+        Yaml::Alias(id) => check_scalar_keys(&anchors.nodes[id], anchors),
+        _ => Ok(()),
+    }
+}
+
+fn is_collection(node: &Yaml, anchors: &Anchors) -> bool {
+    if let Some((_, node)) = comment::split(node) {
+        return is_collection(node, anchors);
+    }
+    match *node {
+        Yaml::Array(_) | Yaml::Hash(_) => true,
+        Yaml::Alias(id) => is_collection(&anchors.nodes[id], anchors),
+        _ => false,
+    }
+}
+
+/// Builds the tree for one document along with the nodes it shares.
+fn document<T: ?Sized>(value: &T) -> Result<(Yaml, Anchors)>
+    where T: ser::Serialize
+{
+    #[cfg(feature = "shared")]
+    {
+        comment::serialize_document(|| shared::serialize_document(|| to_yaml(value)))
+    }
+    #[cfg(not(feature = "shared"))]
+    {
+        Ok((comment::serialize_document(|| to_yaml(value))?, Anchors::default()))
+    }
+}
+
+/// Output is collected into chunks of this many bytes before being written.
+const CHUNK_SIZE: usize = 8 * 1024;
+
+/// The emitter uses `fmt::Write` instead of `io::Write`, and emits output in
+/// many small pieces. This adapter collects the pieces into chunks
+/// and keeps any I/O error for reporting once the emitter gives up.
+struct ChunkedWriter<W> {
     writer: W,
+    chunk: Vec<u8>,
+    failed: Option<io::Error>,
+}
+
+impl<W> ChunkedWriter<W>
+    where W: io::Write
+{
+    fn new(writer: W) -> Self {
+        ChunkedWriter {
+            writer: writer,
+            chunk: Vec::new(),
+            failed: None,
+        }
+    }
+
+    fn write_chunk(&mut self) -> Result<()> {
+        let result = self.writer.write_all(&self.chunk);
+        self.chunk.clear();
+        result.map_err(Error::io)
+    }
+
+    fn check(&mut self, result: io::Result<()>) -> fmt::Result {
+        result.map_err(|err| {
+            self.failed = Some(err);
+            fmt::Error
+        })
+    }
+
+    fn error(&mut self) -> Error {
+        match self.failed.take() {
+            Some(err) => Error::io(err),
+            None => Error::emitter(EmitError::FmtError(fmt::Error)),
+        }
+    }
 }
 
-impl<W> fmt::Write for FmtToIoWriter<W>
+impl<W> fmt::Write for ChunkedWriter<W>
     where W: io::Write
 {
     fn write_str(&mut self, s: &str) -> fmt::Result {
-        if self.writer.write(s.as_bytes()).is_err() {
-            return Err(fmt::Error);
+        if self.chunk.len() + s.len() > CHUNK_SIZE {
+            let result = self.writer.write_all(&self.chunk);
+            self.chunk.clear();
+            self.check(result)?;
+        }
+        if s.len() > CHUNK_SIZE {
+            let result = self.writer.write_all(s.as_bytes());
+            self.check(result)
+        } else {
+            self.chunk.extend_from_slice(s.as_bytes());
+            Ok(())
         }
-        Ok(())
     }
 }
 
-#[cfg_attr(feature = "cargo-clippy", allow(needless_pass_by_value))]
+#[allow(clippy::needless_pass_by_value)]
 fn to_yaml<T>(elem: T) -> Result<Yaml>
     where T: ser::Serialize
 {
     elem.serialize(Serializer)
 }
 
+/// How a key appears in the path of a serialization error.
+fn key_segment(key: &Yaml) -> Segment {
+    if let Some((_, key)) = comment::split(key) {
+        return key_segment(key);
+    }
+    Segment::Key(match *key {
+        Yaml::String(ref s) | Yaml::Real(ref s) => s.clone(),
+        Yaml::Integer(i) => i.to_string(),
+        Yaml::Boolean(b) => b.to_string(),
+        Yaml::Null => "~".to_owned(),
+        _ => "?".to_owned(),
+    })
+}
+
 fn singleton_hash(k: Yaml, v: Yaml) -> Yaml {
     let mut hash = yaml::Hash::new();
     hash.insert(k, v);
@@ -0,0 +1,652 @@
+// Copyright 2018 Serde YAML Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The structure a `Deserialize` type expects its input to have.
+//!
+//! This is discovered by running the type's `Deserialize` impl against a
+//! deserializer that records what it is asked for instead of reading any
+//! input. Editors can use the result to suggest the keys that are valid at
+//! some position of a config file.
+//!
+//! ```rust
+//! #[macro_use]
+//! extern crate serde_derive;
+//! extern crate serde_yaml;
+//!
+//! use serde_yaml::shape::Shape;
+//!
+//! #[derive(Deserialize)]
+//! struct Config {
+//!     server: Server,
+//!     workers: Vec<Worker>,
+//! }
+//!
+//! #[derive(Deserialize)]
+//! struct Server {
+//!     host: String,
+//!     port: u16,
+//! }
+//!
+//! #[derive(Deserialize)]
+//! struct Worker {
+//!     name: String,
+//!     threads: Option<u32>,
+//! }
+//!
+//! # fn main() {
+//! let shape = Shape::of::<Config>();
+//! assert_eq!(shape.expected_keys("."), ["server", "workers"]);
+//! assert_eq!(shape.expected_keys("server"), ["host", "port"]);
+//! assert_eq!(shape.expected_keys("workers[0]"), ["name", "threads"]);
+//! # }
+//! ```
+
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+
+use serde::de::{self, DeserializeOwned, DeserializeSeed, IntoDeserializer, Visitor};
+
+use error::{Error, Result};
+use path::{segments, Segment};
+
+/// The kind of value expected at some position.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Kind {
+    /// The type accepts anything, or could not be traced.
+    Any,
+    /// A boolean.
+    Bool,
+    /// An integer.
+    Integer,
+    /// A floating point number.
+    Float,
+    /// A string or character.
+    String,
+    /// A byte string.
+    Bytes,
+    /// A null value or unit struct.
+    Unit,
+    /// A value that may be null.
+    Option(Box<Kind>),
+    /// A sequence with elements of the given kind.
+    Sequence(Box<Kind>),
+    /// A fixed-length sequence.
+    Tuple(Vec<Kind>),
+    /// A mapping with arbitrary keys and values of the given kinds.
+    Map(Box<Kind>, Box<Kind>),
+    /// A struct, named by `Deserialize` and looked up with `Shape::fields`.
+    Struct(&'static str),
+    /// An enum together with the names of its variants.
+    Enum(&'static str, &'static [&'static str]),
+}
+
+/// The structure expected by some `Deserialize` type.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Shape {
+    root: Kind,
+    structs: BTreeMap<&'static str, Vec<(&'static str, Kind)>>,
+}
+
+impl Shape {
+    /// Traces the `Deserialize` impl of `T`.
+    ///
+    /// Structs are identified by the name their `Deserialize` impl reports, so
+    /// two different structs with the same name are merged. Types whose
+    /// `Deserialize` impl rejects every input may be only partially traced.
+    pub fn of<T>() -> Self
+        where T: DeserializeOwned
+    {
+        let registry = RefCell::new(Registry::default());
+        let slot = RefCell::new(Kind::Any);
+        // Errors are expected here, for example from structs that always
+        // contain themselves or from `Deserialize` impls that validate their
+        // input. Everything learned up to that point has already been
+        // recorded.
+        let _ = T::deserialize(Tracer {
+                                   registry: &registry,
+                                   slot: &slot,
+                               });
+        Shape {
+            root: slot.into_inner(),
+            structs: registry.into_inner().structs,
+        }
+    }
+
+    /// The kind of value expected at the root of the document.
+    pub fn root(&self) -> &Kind {
+        &self.root
+    }
+
+    /// The fields of the struct with the given name, in declaration order.
+    pub fn fields(&self, name: &str) -> Option<&[(&'static str, Kind)]> {
+        self.structs.get(name).map(|fields| &fields[..])
+    }
+
+    /// The kind of value expected at `path`, written the same way paths are
+    /// written in error messages, like `spec.containers[0].image`. The root
+    /// is written `.`.
+    pub fn kind_at(&self, path: &str) -> Option<&Kind> {
+        let mut kind = &self.root;
+        for segment in segments(path) {
+            kind = self.child(kind, &segment)?;
+        }
+        Some(kind)
+    }
+
+    /// The keys that may appear in the mapping at `path`: the fields of a
+    /// struct or the variants of an enum. Empty if the value at `path` is not
+    /// expected to be a mapping with fixed keys.
+    pub fn expected_keys(&self, path: &str) -> Vec<&'static str> {
+        let mut kind = match self.kind_at(path) {
+            Some(kind) => kind,
+            None => return Vec::new(),
+        };
+        while let Kind::Option(ref inner) = *kind {
+            kind = inner;
+        }
+        match *kind {
+            Kind::Struct(name) => {
+                self.fields(name).map_or_else(Vec::new, |fields| fields.iter().map(|field| field.0).collect())
+            }
+            Kind::Enum(_, variants) => variants.to_vec(),
+            _ => Vec::new(),
+        }
+    }
+
+    fn child<'a>(&'a self, kind: &'a Kind, segment: &Segment) -> Option<&'a Kind> {
+        match (kind, segment) {
+            (&Kind::Option(ref inner), _) => self.child(inner, segment),
+            (&Kind::Struct(name), &Segment::Key(ref key)) => {
+                self.fields(name)?.iter().find(|field| field.0 == key).map(|field| &field.1)
+            }
+            (&Kind::Map(_, ref value), &Segment::Key(_)) |
+            (&Kind::Sequence(ref value), &Segment::Index(_)) => Some(value),
+            (&Kind::Tuple(ref elements), &Segment::Index(i)) => elements.get(i),
+            (&Kind::Enum(_, variants), &Segment::Key(ref key)) if variants.contains(&&**key) => {
+                Some(&Kind::Any)
+            }
+            (&Kind::Any, _) => Some(&Kind::Any),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Default)]
+struct Registry {
+    structs: BTreeMap<&'static str, Vec<(&'static str, Kind)>>,
+    in_progress: Vec<&'static str>,
+    /// How many placeholders for recursive structs are being made up. While
+    /// there are any, options are `None`, sequences and maps are empty, and
+    /// nothing is recorded.
+    placeholders: usize,
+}
+
+/// A deserializer that hands out placeholder data and records which kind of
+/// value it was asked for into `slot`.
+#[derive(Copy, Clone)]
+struct Tracer<'a> {
+    registry: &'a RefCell<Registry>,
+    slot: &'a RefCell<Kind>,
+}
+
+impl<'a> Tracer<'a> {
+    fn record(&self, kind: Kind) {
+        *self.slot.borrow_mut() = kind;
+    }
+
+    fn placeholder(&self) -> bool {
+        self.registry.borrow().placeholders > 0
+    }
+}
+
+impl<'de, 'a> de::Deserializer<'de> for Tracer<'a> {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
+        where V: Visitor<'de>
+    {
+        self.record(Kind::Any);
+        visitor.visit_unit()
+    }
+
+    fn deserialize_bool<V>(self, visitor: V) -> Result<V::Value>
+        where V: Visitor<'de>
+    {
+        self.record(Kind::Bool);
+        visitor.visit_bool(false)
+    }
+
+    fn deserialize_i8<V>(self, visitor: V) -> Result<V::Value>
+        where V: Visitor<'de>
+    {
+        self.record(Kind::Integer);
+        visitor.visit_i8(1)
+    }
+
+    fn deserialize_i16<V>(self, visitor: V) -> Result<V::Value>
+        where V: Visitor<'de>
+    {
+        self.record(Kind::Integer);
+        visitor.visit_i16(1)
+    }
+
+    fn deserialize_i32<V>(self, visitor: V) -> Result<V::Value>
+        where V: Visitor<'de>
+    {
+        self.record(Kind::Integer);
+        visitor.visit_i32(1)
+    }
+
+    fn deserialize_i64<V>(self, visitor: V) -> Result<V::Value>
+        where V: Visitor<'de>
+    {
+        self.record(Kind::Integer);
+        visitor.visit_i64(1)
+    }
+
+    fn deserialize_u8<V>(self, visitor: V) -> Result<V::Value>
+        where V: Visitor<'de>
+    {
+        self.record(Kind::Integer);
+        visitor.visit_u8(1)
+    }
+
+    fn deserialize_u16<V>(self, visitor: V) -> Result<V::Value>
+        where V: Visitor<'de>
+    {
+        self.record(Kind::Integer);
+        visitor.visit_u16(1)
+    }
+
+    fn deserialize_u32<V>(self, visitor: V) -> Result<V::Value>
+        where V: Visitor<'de>
+    {
+        self.record(Kind::Integer);
+        visitor.visit_u32(1)
+    }
+
+    fn deserialize_u64<V>(self, visitor: V) -> Result<V::Value>
+        where V: Visitor<'de>
+    {
+        self.record(Kind::Integer);
+        visitor.visit_u64(1)
+    }
+
+    fn deserialize_f32<V>(self, visitor: V) -> Result<V::Value>
+        where V: Visitor<'de>
+    {
+        self.record(Kind::Float);
+        visitor.visit_f32(1.0)
+    }
+
+    fn deserialize_f64<V>(self, visitor: V) -> Result<V::Value>
+        where V: Visitor<'de>
+    {
+        self.record(Kind::Float);
+        visitor.visit_f64(1.0)
+    }
+
+    fn deserialize_char<V>(self, visitor: V) -> Result<V::Value>
+        where V: Visitor<'de>
+    {
+        self.record(Kind::String);
+        visitor.visit_char('_')
+    }
+
+    fn deserialize_str<V>(self, visitor: V) -> Result<V::Value>
+        where V: Visitor<'de>
+    {
+        self.record(Kind::String);
+        visitor.visit_str("")
+    }
+
+    fn deserialize_string<V>(self, visitor: V) -> Result<V::Value>
+        where V: Visitor<'de>
+    {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_bytes<V>(self, visitor: V) -> Result<V::Value>
+        where V: Visitor<'de>
+    {
+        self.record(Kind::Bytes);
+        visitor.visit_bytes(&[])
+    }
+
+    fn deserialize_byte_buf<V>(self, visitor: V) -> Result<V::Value>
+        where V: Visitor<'de>
+    {
+        self.deserialize_bytes(visitor)
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value>
+        where V: Visitor<'de>
+    {
+        if self.placeholder() {
+            return visitor.visit_none();
+        }
+        let slot = RefCell::new(Kind::Any);
+        let result = visitor.visit_some(Tracer {
+                                            registry: self.registry,
+                                            slot: &slot,
+                                        });
+        self.record(Kind::Option(Box::new(slot.into_inner())));
+        result
+    }
+
+    fn deserialize_unit<V>(self, visitor: V) -> Result<V::Value>
+        where V: Visitor<'de>
+    {
+        self.record(Kind::Unit);
+        visitor.visit_unit()
+    }
+
+    fn deserialize_unit_struct<V>(self, _name: &'static str, visitor: V) -> Result<V::Value>
+        where V: Visitor<'de>
+    {
+        self.deserialize_unit(visitor)
+    }
+
+    fn deserialize_newtype_struct<V>(self, _name: &'static str, visitor: V) -> Result<V::Value>
+        where V: Visitor<'de>
+    {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value>
+        where V: Visitor<'de>
+    {
+        let mut seq = SeqTracer {
+            tracer: self,
+            kinds: Vec::new(),
+            remaining: if self.placeholder() { 0 } else { 1 },
+            lenient: true,
+        };
+        let result = visitor.visit_seq(&mut seq);
+        self.record(Kind::Sequence(Box::new(seq.kinds.pop().unwrap_or(Kind::Any))));
+        result
+    }
+
+    fn deserialize_tuple<V>(self, len: usize, visitor: V) -> Result<V::Value>
+        where V: Visitor<'de>
+    {
+        let mut seq = SeqTracer {
+            tracer: self,
+            kinds: Vec::new(),
+            remaining: len,
+            lenient: false,
+        };
+        let result = visitor.visit_seq(&mut seq);
+        self.record(Kind::Tuple(seq.kinds));
+        result
+    }
+
+    fn deserialize_tuple_struct<V>(self, _name: &'static str, len: usize, visitor: V) -> Result<V::Value>
+        where V: Visitor<'de>
+    {
+        self.deserialize_tuple(len, visitor)
+    }
+
+    fn deserialize_map<V>(self, visitor: V) -> Result<V::Value>
+        where V: Visitor<'de>
+    {
+        let mut map = MapTracer {
+            tracer: self,
+            key: Kind::Any,
+            value: Kind::Any,
+            done: self.placeholder(),
+        };
+        let result = visitor.visit_map(&mut map);
+        self.record(Kind::Map(Box::new(map.key), Box::new(map.value)));
+        result
+    }
+
+    fn deserialize_struct<V>(self,
+                             name: &'static str,
+                             fields: &'static [&'static str],
+                             visitor: V)
+                             -> Result<V::Value>
+        where V: Visitor<'de>
+    {
+        self.record(Kind::Struct(name));
+        let recursive = {
+            let mut registry = self.registry.borrow_mut();
+            let recursive = registry.in_progress.contains(&name);
+            if recursive && registry.placeholders > 0 {
+                // The struct contains itself even when every option is
+                // `None` and every sequence empty, so it has no value.
+                return Err(de::Error::custom(format_args!("recursive struct `{}`", name)));
+            }
+            if recursive {
+                // The struct is being traced further up. Make up a value for
+                // it here without tracing it again, so that the fields after
+                // this one are traced as well.
+                registry.placeholders += 1;
+            } else {
+                registry.in_progress.push(name);
+                if registry.placeholders == 0 {
+                    registry.structs.insert(name, fields.iter().map(|&field| (field, Kind::Any)).collect());
+                }
+            }
+            recursive
+        };
+        let result = visitor.visit_map(StructTracer {
+                                           tracer: self,
+                                           name: name,
+                                           fields: fields,
+                                           next: 0,
+                                       });
+        let mut registry = self.registry.borrow_mut();
+        if recursive {
+            registry.placeholders -= 1;
+        } else {
+            registry.in_progress.retain(|&in_progress| in_progress != name);
+        }
+        result
+    }
+
+    fn deserialize_enum<V>(self,
+                           name: &'static str,
+                           variants: &'static [&'static str],
+                           visitor: V)
+                           -> Result<V::Value>
+        where V: Visitor<'de>
+    {
+        self.record(Kind::Enum(name, variants));
+        match variants.first() {
+            Some(&variant) => {
+                visitor.visit_enum(EnumTracer {
+                                       tracer: self,
+                                       variant: variant,
+                                   })
+            }
+            None => Err(de::Error::custom(format_args!("enum `{}` has no variants", name))),
+        }
+    }
+
+    fn deserialize_identifier<V>(self, visitor: V) -> Result<V::Value>
+        where V: Visitor<'de>
+    {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_ignored_any<V>(self, visitor: V) -> Result<V::Value>
+        where V: Visitor<'de>
+    {
+        visitor.visit_unit()
+    }
+}
+
+struct SeqTracer<'a> {
+    tracer: Tracer<'a>,
+    kinds: Vec<Kind>,
+    remaining: usize,
+    lenient: bool,
+}
+
+impl<'de, 'a> de::SeqAccess<'de> for SeqTracer<'a> {
+    type Error = Error;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>>
+        where T: DeserializeSeed<'de>
+    {
+        if self.remaining == 0 {
+            return Ok(None);
+        }
+        self.remaining -= 1;
+        let slot = RefCell::new(Kind::Any);
+        let result = seed.deserialize(Tracer {
+                                          registry: self.tracer.registry,
+                                          slot: &slot,
+                                      });
+        self.kinds.push(slot.into_inner());
+        match result {
+            Ok(element) => Ok(Some(element)),
+            // A sequence may be empty, so an element that cannot be made up
+            // need not hold back tracing of the rest of the enclosing type.
+            Err(_) if self.lenient => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+}
+
+struct MapTracer<'a> {
+    tracer: Tracer<'a>,
+    key: Kind,
+    value: Kind,
+    done: bool,
+}
+
+impl<'de, 'a> de::MapAccess<'de> for MapTracer<'a> {
+    type Error = Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>>
+        where K: DeserializeSeed<'de>
+    {
+        if self.done {
+            return Ok(None);
+        }
+        self.done = true;
+        let slot = RefCell::new(Kind::Any);
+        let result = seed.deserialize(Tracer {
+                                          registry: self.tracer.registry,
+                                          slot: &slot,
+                                      });
+        self.key = slot.into_inner();
+        result.map(Some)
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value>
+        where V: DeserializeSeed<'de>
+    {
+        let slot = RefCell::new(Kind::Any);
+        let result = seed.deserialize(Tracer {
+                                          registry: self.tracer.registry,
+                                          slot: &slot,
+                                      });
+        self.value = slot.into_inner();
+        result
+    }
+}
+
+struct StructTracer<'a> {
+    tracer: Tracer<'a>,
+    name: &'static str,
+    fields: &'static [&'static str],
+    next: usize,
+}
+
+impl<'de, 'a> de::MapAccess<'de> for StructTracer<'a> {
+    type Error = Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>>
+        where K: DeserializeSeed<'de>
+    {
+        match self.fields.get(self.next) {
+            Some(&field) => seed.deserialize(field.into_deserializer()).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value>
+        where V: DeserializeSeed<'de>
+    {
+        let slot = RefCell::new(Kind::Any);
+        let result = seed.deserialize(Tracer {
+                                          registry: self.tracer.registry,
+                                          slot: &slot,
+                                      });
+        if !self.tracer.placeholder() {
+            if let Some(fields) = self.tracer.registry.borrow_mut().structs.get_mut(self.name) {
+                fields[self.next].1 = slot.into_inner();
+            }
+        }
+        self.next += 1;
+        result
+    }
+}
+
+struct EnumTracer<'a> {
+    tracer: Tracer<'a>,
+    variant: &'static str,
+}
+
+impl<'de, 'a> de::EnumAccess<'de> for EnumTracer<'a> {
+    type Error = Error;
+    type Variant = Tracer<'a>;
+
+    fn variant_seed<V>(self, seed: V) -> Result<(V::Value, Tracer<'a>)>
+        where V: DeserializeSeed<'de>
+    {
+        let variant = seed.deserialize(self.variant.into_deserializer())?;
+        Ok((variant, self.tracer))
+    }
+}
+
+impl<'de, 'a> de::VariantAccess<'de> for Tracer<'a> {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<()> {
+        Ok(())
+    }
+
+    fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value>
+        where T: DeserializeSeed<'de>
+    {
+        // Keep the enum as the recorded kind; variant payloads are not traced.
+        let slot = RefCell::new(Kind::Any);
+        seed.deserialize(Tracer {
+                             registry: self.registry,
+                             slot: &slot,
+                         })
+    }
+
+    fn tuple_variant<V>(self, len: usize, visitor: V) -> Result<V::Value>
+        where V: Visitor<'de>
+    {
+        let slot = RefCell::new(Kind::Any);
+        de::Deserializer::deserialize_tuple(Tracer {
+                                                registry: self.registry,
+                                                slot: &slot,
+                                            },
+                                            len,
+                                            visitor)
+    }
+
+    fn struct_variant<V>(self, fields: &'static [&'static str], visitor: V) -> Result<V::Value>
+        where V: Visitor<'de>
+    {
+        let mut seq = SeqTracer {
+            tracer: self,
+            kinds: Vec::new(),
+            remaining: fields.len(),
+            lenient: false,
+        };
+        visitor.visit_seq(&mut seq)
+    }
+}
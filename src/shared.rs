@@ -0,0 +1,363 @@
+// Copyright 2018 Serde YAML Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Values that are shared through `Arc` or `Rc` and written only once.
+//!
+//! Wrapping a pointer in `Shared` makes serialization notice when it meets
+//! the same allocation again within a document. The first occurrence is
+//! written in full with an anchor and later ones as aliases to it. When such
+//! a document is read back into `Shared` pointers, every alias to a node
+//! yields a clone of the pointer built for that node, so the sharing
+//! survives the round trip.
+//!
+//! ```rust
+//! #[macro_use]
+//! extern crate serde_derive;
+//! extern crate serde_yaml;
+//!
+//! use std::sync::Arc;
+//! use serde_yaml::shared::Shared;
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Node {
+//!     name: String,
+//!     peers: Vec<Shared<Arc<Node>>>,
+//! }
+//!
+//! # fn main() {
+//! let db = Shared(Arc::new(Node { name: "db".to_owned(), peers: Vec::new() }));
+//! let web = Node { name: "web".to_owned(), peers: vec![db.clone(), db.clone()] };
+//!
+//! let yaml = serde_yaml::to_string(&web).unwrap();
+//! assert_eq!(yaml, "---\nname: web\npeers:\n  - &id001\n    name: db\n    peers: []\n  - *id001");
+//!
+//! let web: Node = serde_yaml::from_str(&yaml).unwrap();
+//! assert!(Arc::ptr_eq(&web.peers[0], &web.peers[1]));
+//! # }
+//! ```
+//!
+//! Fields that hold an `Arc` or `Rc` directly can get the same treatment
+//! without changing their type by naming this module in a `with` attribute.
+//!
+//! ```rust
+//! #[macro_use]
+//! extern crate serde_derive;
+//! extern crate serde_yaml;
+//!
+//! use std::rc::Rc;
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Service {
+//!     #[serde(with = "serde_yaml::shared")]
+//!     defaults: Rc<Vec<String>>,
+//! }
+//!
+//! # fn main() {
+//! let yaml = "[{defaults: &d [a, b]}, {defaults: *d}]";
+//! let services: Vec<Service> = serde_yaml::from_str(yaml).unwrap();
+//! assert!(Rc::ptr_eq(&services[0].defaults, &services[1].defaults));
+//! # }
+//! ```
+//!
+//! A `Shared` value that contains itself, for example through a `RefCell`,
+//! cannot be written as a tree. Serializing it fails with an error naming
+//! the path at which the value was met again, instead of recursing forever.
+//!
+//! Only pointers wrapped in `Shared` are tracked. Anchors do not reach across
+//! documents, so a pointer shared between two documents of a stream is
+//! written in full in each of them. Other serializers and deserializers see
+//! `Shared` as the value it points to.
+
+use std::any::Any;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::marker::PhantomData;
+use std::mem;
+use std::ops::Deref;
+use std::rc::Rc;
+use std::sync::Arc;
+
+use serde::de::{self, Deserialize, Deserializer, MapAccess, Visitor};
+use serde::ser::{Serialize, Serializer};
+use yaml_rust::Yaml;
+
+use emitter::Anchors;
+use error::{Error, Result};
+use ser;
+
+/// The name of the newtype struct that `Shared` serializes and deserializes
+/// as, so that this crate's serializer and deserializer can recognize it.
+pub(crate) const TOKEN: &str = "$serde_yaml::private::Shared";
+
+/// An `Arc` or `Rc` that is written once per document no matter how many
+/// clones of it are serialized. See the module documentation.
+#[derive(Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Shared<P>(pub P);
+
+impl<P> Deref for Shared<P> {
+    type Target = P;
+
+    fn deref(&self) -> &P {
+        &self.0
+    }
+}
+
+impl<P> From<P> for Shared<P> {
+    fn from(pointer: P) -> Self {
+        Shared(pointer)
+    }
+}
+
+impl<T: ?Sized> Serialize for Shared<Arc<T>>
+    where T: Serialize + 'static
+{
+    fn serialize<S>(&self, serializer: S) -> ::std::result::Result<S::Ok, S::Error>
+        where S: Serializer
+    {
+        keep_alive(&self.0);
+        serializer.serialize_newtype_struct(TOKEN, &*self.0)
+    }
+}
+
+impl<T: ?Sized> Serialize for Shared<Rc<T>>
+    where T: Serialize + 'static
+{
+    fn serialize<S>(&self, serializer: S) -> ::std::result::Result<S::Ok, S::Error>
+        where S: Serializer
+    {
+        keep_alive(&self.0);
+        serializer.serialize_newtype_struct(TOKEN, &*self.0)
+    }
+}
+
+impl<'de, T> Deserialize<'de> for Shared<Arc<T>>
+    where T: Deserialize<'de> + 'static
+{
+    fn deserialize<D>(deserializer: D) -> ::std::result::Result<Self, D::Error>
+        where D: Deserializer<'de>
+    {
+        deserializer.deserialize_newtype_struct(TOKEN, SharedVisitor::new(Arc::new)).map(Shared)
+    }
+}
+
+impl<'de, T> Deserialize<'de> for Shared<Rc<T>>
+    where T: Deserialize<'de> + 'static
+{
+    fn deserialize<D>(deserializer: D) -> ::std::result::Result<Self, D::Error>
+        where D: Deserializer<'de>
+    {
+        deserializer.deserialize_newtype_struct(TOKEN, SharedVisitor::new(Rc::new)).map(Shared)
+    }
+}
+
+/// Serializes an `Arc` or `Rc` field like `Shared`, for use as
+/// `#[serde(with = "serde_yaml::shared")]`.
+pub fn serialize<P, S>(pointer: &P, serializer: S) -> ::std::result::Result<S::Ok, S::Error>
+    where P: Clone,
+          Shared<P>: Serialize,
+          S: Serializer
+{
+    // Cloning the pointer keeps the address of what it points to.
+    Shared(pointer.clone()).serialize(serializer)
+}
+
+/// Deserializes an `Arc` or `Rc` field like `Shared`, for use as
+/// `#[serde(with = "serde_yaml::shared")]`.
+pub fn deserialize<'de, P, D>(deserializer: D) -> ::std::result::Result<P, D::Error>
+    where Shared<P>: Deserialize<'de>,
+          D: Deserializer<'de>
+{
+    Shared::deserialize(deserializer).map(|shared| shared.0)
+}
+
+/// Holds on to a clone of the pointer until the document being serialized
+/// is done, so that its address, which `serialize_node` tells values apart
+/// by, cannot be reused by another value in the meantime.
+fn keep_alive<P>(pointer: &P)
+    where P: Clone + 'static
+{
+    FRAMES.with(|frames| {
+        if let Some(frame) = frames.borrow_mut().last_mut() {
+            frame.kept.push(Box::new(pointer.clone()));
+        }
+    });
+}
+
+struct SharedVisitor<T, P, F> {
+    new: F,
+    marker: PhantomData<fn(T) -> P>,
+}
+
+impl<T, P, F> SharedVisitor<T, P, F> {
+    fn new(new: F) -> Self {
+        SharedVisitor {
+            new: new,
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<'de, T, P, F> Visitor<'de> for SharedVisitor<T, P, F>
+    where T: Deserialize<'de>,
+          P: Clone + 'static,
+          F: FnOnce(T) -> P
+{
+    type Value = P;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a shared value")
+    }
+
+    fn visit_newtype_struct<D>(self, deserializer: D) -> ::std::result::Result<P, D::Error>
+        where D: Deserializer<'de>
+    {
+        T::deserialize(deserializer).map(self.new)
+    }
+
+    // This crate's deserializer hands over the position of the node, which
+    // is the same for every alias to it, followed by the node itself.
+    fn visit_map<A>(self, mut map: A) -> ::std::result::Result<P, A::Error>
+        where A: MapAccess<'de>
+    {
+        let node: u64 = match map.next_key()? {
+            Some(node) => node,
+            None => return Err(de::Error::invalid_length(0, &self)),
+        };
+        let node = node as usize;
+        if let Some(pointer) = FRAMES.with(|frames| {
+            let frames = frames.borrow();
+            let built = frames.last()?.built.get(&node)?;
+            built.downcast_ref::<P>().cloned()
+        }) {
+            return Ok(pointer);
+        }
+        let pointer = (self.new)(map.next_value()?);
+        FRAMES.with(|frames| {
+            if let Some(frame) = frames.borrow_mut().last_mut() {
+                frame.built.insert(node, Box::new(pointer.clone()));
+            }
+        });
+        Ok(pointer)
+    }
+}
+
+/// What is known about shared values for the document being serialized or
+/// deserialized on this thread. A stack, because a `Serialize` or
+/// `Deserialize` impl may itself run this crate on a different document.
+#[derive(Default)]
+struct Frame {
+    /// Serializing: the id of the shared node for the address of each value
+    /// seen so far.
+    ids: HashMap<usize, usize>,
+    /// Serializing: shared nodes by id, None while a node is being built.
+    nodes: Vec<Option<Yaml>>,
+    refs: Vec<usize>,
+    /// Serializing: a clone of every pointer seen so far.
+    kept: Vec<Box<dyn Any>>,
+    /// Deserializing: the pointer built for the node at each position.
+    built: HashMap<usize, Box<dyn Any>>,
+}
+
+thread_local! {
+    static FRAMES: RefCell<Vec<Frame>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Pops the frame pushed by `scope` even if building the document panics.
+struct Scope;
+
+impl Drop for Scope {
+    fn drop(&mut self) {
+        FRAMES.with(|frames| frames.borrow_mut().pop());
+    }
+}
+
+fn scope() -> Scope {
+    FRAMES.with(|frames| frames.borrow_mut().push(Frame::default()));
+    Scope
+}
+
+/// Runs `f`, which deserializes one document, so that aliases to a node read
+/// into `Shared` all get the same pointer.
+pub(crate) fn deserialize_document<T, F>(f: F) -> T
+    where F: FnOnce() -> T
+{
+    let _scope = scope();
+    f()
+}
+
+/// Runs `f`, which serializes one document, and returns the shared nodes it
+/// refers to along with its result.
+pub(crate) fn serialize_document<F>(f: F) -> Result<(Yaml, Anchors)>
+    where F: FnOnce() -> Result<Yaml>
+{
+    let _scope = scope();
+    let doc = f()?;
+    let frame = FRAMES.with(|frames| frames.borrow_mut().last_mut().map(mem::take)).unwrap_or_default();
+    let anchors = Anchors {
+        nodes: frame.nodes.into_iter().map(|node| node.unwrap_or(Yaml::Null)).collect(),
+        refs: frame.refs,
+    };
+    Ok((doc, anchors))
+}
+
+/// Serializes the value behind a `Shared`, or refers to it if the same
+/// allocation has been serialized before.
+pub(crate) fn serialize_node<T: ?Sized>(value: &T) -> Result<Yaml>
+    where T: Serialize
+{
+    let address = value as *const T as *const u8 as usize;
+    let seen = FRAMES.with(|frames| {
+        let mut frames = frames.borrow_mut();
+        let frame = frames.last_mut()?;
+        Some(match frame.ids.get(&address) {
+            Some(&id) => {
+                match frame.nodes[id] {
+                    // A node that is only a reference to another one is
+                    // passed through, see below.
+                    Some(Yaml::Alias(inner)) => {
+                        frame.refs[inner] += 1;
+                        Ok(Some(Yaml::Alias(inner)))
+                    }
+                    Some(_) => {
+                        frame.refs[id] += 1;
+                        Ok(Some(Yaml::Alias(id)))
+                    }
+                    None => Err(Error::serialize("shared value contains itself")),
+                }
+            }
+            None => {
+                frame.ids.insert(address, frame.nodes.len());
+                frame.nodes.push(None);
+                frame.refs.push(1);
+                Ok(None)
+            }
+        })
+    });
+    match seen {
+        // Not inside this crate's serializer.
+        None => value.serialize(ser::Serializer),
+        Some(Err(err)) => Err(err),
+        Some(Ok(Some(alias))) => Ok(alias),
+        Some(Ok(None)) => {
+            let node = value.serialize(ser::Serializer)?;
+            FRAMES.with(|frames| {
+                let mut frames = frames.borrow_mut();
+                let frame = frames.last_mut().expect("frame of the document being serialized");
+                let id = frame.ids[&address];
+                // A `Shared` of a `Shared` has no content of its own.
+                let result = match node {
+                    Yaml::Alias(inner) => Yaml::Alias(inner),
+                    _ => Yaml::Alias(id),
+                };
+                frame.nodes[id] = Some(node);
+                Ok(result)
+            })
+        }
+    }
+}
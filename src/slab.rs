@@ -0,0 +1,397 @@
+// Copyright 2018 Serde YAML Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! A document whose nodes live in one slab and refer to each other by id,
+//! for tools that rearrange large documents.
+//!
+//! In a `Value` every collection owns its children, so moving a subtree
+//! means taking it apart from one parent and putting it back together under
+//! another, and there is no way to hold on to a node across edits. In a
+//! `Slab` every node has a `ValueId` that stays valid until the node is
+//! removed, knows its parent, and is moved by relinking it, which costs the
+//! same whatever the size of the subtree.
+//!
+//! ```rust
+//! use serde_yaml::Value;
+//! use serde_yaml::slab::Slab;
+//!
+//! let value: Value = serde_yaml::from_str("staging: {db: {host: a}}\nproduction: {}").unwrap();
+//! let mut slab = Slab::from_value(&value);
+//! let root = slab.root();
+//! let staging = slab.get(root, &"staging".into()).unwrap();
+//! let production = slab.get(root, &"production".into()).unwrap();
+//! let db = slab.get(staging, &"db".into()).unwrap();
+//!
+//! slab.detach(db).unwrap();
+//! slab.insert(production, "db".into(), db).unwrap();
+//! assert_eq!(slab.parent(db), Some(production));
+//! assert_eq!(serde_yaml::to_string(&slab.to_value(root)).unwrap(),
+//!            "---\nstaging: {}\nproduction:\n  db:\n    host: a");
+//! ```
+
+use linked_hash_map::{self, LinkedHashMap};
+use serde::de::Error as SError;
+
+use error::{Error, Result};
+use mapping::Mapping;
+use value::Value;
+
+/// The id of a node in a `Slab`.
+///
+/// Ids are only meaningful to the slab that handed them out. Once a node is
+/// removed its id refers to nothing, even if the slab reuses its storage for
+/// a new node.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ValueId {
+    index: usize,
+    generation: usize,
+}
+
+/// A document made of nodes that refer to each other by `ValueId`.
+#[derive(Clone, Debug)]
+pub struct Slab {
+    slots: Vec<Slot>,
+    /// Indices of the slots without a node.
+    free: Vec<usize>,
+    root: ValueId,
+}
+
+#[derive(Clone, Debug)]
+struct Slot {
+    generation: usize,
+    node: Option<Node>,
+}
+
+#[derive(Clone, Debug)]
+struct Node {
+    parent: Option<ValueId>,
+    content: Content,
+}
+
+#[derive(Clone, Debug)]
+enum Content {
+    Scalar(Value),
+    Sequence(Vec<ValueId>),
+    Mapping(LinkedHashMap<Value, ValueId>),
+}
+
+impl Slab {
+    /// Copies `value` into a new slab, as its root.
+    pub fn from_value(value: &Value) -> Self {
+        let mut slab = Slab {
+            slots: Vec::new(),
+            free: Vec::new(),
+            root: ValueId {
+                index: 0,
+                generation: 0,
+            },
+        };
+        slab.root = slab.add(value);
+        slab
+    }
+
+    /// The id of the root node, which is always there.
+    pub fn root(&self) -> ValueId {
+        self.root
+    }
+
+    /// The number of nodes in the slab, including detached ones.
+    pub fn len(&self) -> usize {
+        self.slots.len() - self.free.len()
+    }
+
+    /// Whether the slab has no nodes, which is never the case as the root
+    /// cannot be removed.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Whether `id` refers to a node of this slab that has not been removed.
+    pub fn contains(&self, id: ValueId) -> bool {
+        self.node(id).is_ok()
+    }
+
+    /// The collection that holds the node, or `None` for the root, for
+    /// detached nodes and for ids that refer to nothing.
+    pub fn parent(&self, id: ValueId) -> Option<ValueId> {
+        self.node(id).ok().and_then(|node| node.parent)
+    }
+
+    /// The value of a scalar node, or `None` if the node is a collection.
+    pub fn scalar(&self, id: ValueId) -> Option<&Value> {
+        match self.node(id).ok()?.content {
+            Content::Scalar(ref value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// The elements of a sequence node, or `None` if the node is not a
+    /// sequence.
+    pub fn elements(&self, id: ValueId) -> Option<&[ValueId]> {
+        match self.node(id).ok()?.content {
+            Content::Sequence(ref elements) => Some(elements),
+            _ => None,
+        }
+    }
+
+    /// The entries of a mapping node in order, or `None` if the node is not
+    /// a mapping.
+    pub fn entries(&self, id: ValueId) -> Option<Entries> {
+        match self.node(id).ok()?.content {
+            Content::Mapping(ref entries) => Some(Entries { iter: entries.iter() }),
+            _ => None,
+        }
+    }
+
+    /// The value of `key` in a mapping node, or `None` if the node is not a
+    /// mapping or has no such key.
+    pub fn get(&self, id: ValueId, key: &Value) -> Option<ValueId> {
+        match self.node(id).ok()?.content {
+            Content::Mapping(ref entries) => entries.get(key).cloned(),
+            _ => None,
+        }
+    }
+
+    /// Copies `value` into the slab as a detached node, to be attached with
+    /// `push` or `insert`.
+    pub fn add(&mut self, value: &Value) -> ValueId {
+        let content = match *value {
+            Value::Sequence(ref seq) => {
+                Content::Sequence(seq.iter().map(|element| self.add(element)).collect())
+            }
+            Value::Mapping(ref mapping) => {
+                Content::Mapping(mapping.iter().map(|(k, v)| (k.clone(), self.add(v))).collect())
+            }
+            ref scalar => Content::Scalar(scalar.clone()),
+        };
+        let id = self.alloc(Node {
+                                parent: None,
+                                content: content,
+                            });
+        let children: Vec<ValueId> = self.children(id).collect();
+        for child in children {
+            self.slot_mut(child).parent = Some(id);
+        }
+        id
+    }
+
+    /// Appends a detached node to the end of a sequence node.
+    pub fn push(&mut self, seq: ValueId, child: ValueId) -> Result<()> {
+        self.check_attach(seq, child)?;
+        match self.node_mut(seq)?.content {
+            Content::Sequence(ref mut elements) => elements.push(child),
+            _ => return Err(Error::custom("node is not a sequence")),
+        }
+        self.slot_mut(child).parent = Some(seq);
+        Ok(())
+    }
+
+    /// Sets the value of `key` in a mapping node to a detached node. If the
+    /// key already had a value, that node is detached and returned, so that
+    /// it can be moved elsewhere or removed.
+    pub fn insert(&mut self, mapping: ValueId, key: Value, child: ValueId) -> Result<Option<ValueId>> {
+        self.check_attach(mapping, child)?;
+        let previous = match self.node_mut(mapping)?.content {
+            Content::Mapping(ref mut entries) => {
+                match entries.get_mut(&key) {
+                    Some(value) => Some(::std::mem::replace(value, child)),
+                    None => {
+                        entries.insert(key, child);
+                        None
+                    }
+                }
+            }
+            _ => return Err(Error::custom("node is not a mapping")),
+        };
+        self.slot_mut(child).parent = Some(mapping);
+        if let Some(previous) = previous {
+            self.slot_mut(previous).parent = None;
+        }
+        Ok(previous)
+    }
+
+    /// Takes a node out of its parent, keeping it and its subtree in the slab
+    /// to be attached elsewhere. Does nothing to a node that is already
+    /// detached.
+    pub fn detach(&mut self, id: ValueId) -> Result<()> {
+        if id == self.root {
+            return Err(Error::custom("the root cannot be detached"));
+        }
+        let parent = match self.node(id)?.parent {
+            Some(parent) => parent,
+            None => return Ok(()),
+        };
+        match self.node_mut(parent)?.content {
+            Content::Sequence(ref mut elements) => elements.retain(|&element| element != id),
+            Content::Mapping(ref mut entries) => {
+                let key = entries.iter().find(|entry| *entry.1 == id).map(|entry| entry.0.clone());
+                if let Some(key) = key {
+                    entries.remove(&key);
+                }
+            }
+            Content::Scalar(_) => {}
+        }
+        self.slot_mut(id).parent = None;
+        Ok(())
+    }
+
+    /// Detaches a node and drops it along with its subtree. Their ids refer
+    /// to nothing from then on.
+    pub fn remove(&mut self, id: ValueId) -> Result<()> {
+        self.detach(id)?;
+        self.free_subtree(id);
+        Ok(())
+    }
+
+    /// Replaces the contents of a node with a copy of `value`, keeping its
+    /// id and its place in the document. The subtree it had before is
+    /// removed.
+    pub fn replace(&mut self, id: ValueId, value: &Value) -> Result<()> {
+        self.node(id)?;
+        let new = self.add(value);
+        let children: Vec<ValueId> = self.children(id).collect();
+        for child in children {
+            self.free_subtree(child);
+        }
+        let content = self.slots[new.index].node.take().map(|node| node.content);
+        self.release(new);
+        self.slot_mut(id).content = content.unwrap_or(Content::Scalar(Value::Null));
+        let children: Vec<ValueId> = self.children(id).collect();
+        for child in children {
+            self.slot_mut(child).parent = Some(id);
+        }
+        Ok(())
+    }
+
+    /// Copies the subtree of a node out of the slab. An id that refers to
+    /// nothing gives `Value::Null`.
+    pub fn to_value(&self, id: ValueId) -> Value {
+        let node = match self.node(id) {
+            Ok(node) => node,
+            Err(_) => return Value::Null,
+        };
+        match node.content {
+            Content::Scalar(ref value) => value.clone(),
+            Content::Sequence(ref elements) => {
+                Value::Sequence(elements.iter().map(|&element| self.to_value(element)).collect())
+            }
+            Content::Mapping(ref entries) => {
+                let mut mapping = Mapping::with_capacity(entries.len());
+                for (key, &value) in entries {
+                    mapping.insert(key.clone(), self.to_value(value));
+                }
+                Value::Mapping(mapping)
+            }
+        }
+    }
+
+    fn node(&self, id: ValueId) -> Result<&Node> {
+        match self.slots.get(id.index) {
+            Some(&Slot { generation, node: Some(ref node) }) if generation == id.generation => Ok(node),
+            _ => Err(Error::custom("id does not refer to a node of this slab")),
+        }
+    }
+
+    fn node_mut(&mut self, id: ValueId) -> Result<&mut Node> {
+        match self.slots.get_mut(id.index) {
+            Some(&mut Slot { generation, node: Some(ref mut node) }) if generation == id.generation => {
+                Ok(node)
+            }
+            _ => Err(Error::custom("id does not refer to a node of this slab")),
+        }
+    }
+
+    /// The node of an id that is known to be valid.
+    fn slot_mut(&mut self, id: ValueId) -> &mut Node {
+        self.slots[id.index].node.as_mut().unwrap()
+    }
+
+    fn children(&self, id: ValueId) -> Box<dyn Iterator<Item = ValueId> + '_> {
+        match self.node(id).map(|node| &node.content) {
+            Ok(&Content::Sequence(ref elements)) => Box::new(elements.iter().cloned()),
+            Ok(&Content::Mapping(ref entries)) => Box::new(entries.values().cloned()),
+            _ => Box::new(None.into_iter()),
+        }
+    }
+
+    /// Checks that `child` can be attached to `parent`: it must be detached,
+    /// and `parent` must not be inside it.
+    fn check_attach(&self, parent: ValueId, child: ValueId) -> Result<()> {
+        self.node(parent)?;
+        if self.node(child)?.parent.is_some() {
+            return Err(Error::custom("node is already attached; detach it first"));
+        }
+        if child == self.root {
+            return Err(Error::custom("the root cannot be attached"));
+        }
+        let mut ancestor = Some(parent);
+        while let Some(id) = ancestor {
+            if id == child {
+                return Err(Error::custom("node cannot be attached inside itself"));
+            }
+            ancestor = self.parent(id);
+        }
+        Ok(())
+    }
+
+    fn alloc(&mut self, node: Node) -> ValueId {
+        match self.free.pop() {
+            Some(index) => {
+                let slot = &mut self.slots[index];
+                slot.node = Some(node);
+                ValueId {
+                    index: index,
+                    generation: slot.generation,
+                }
+            }
+            None => {
+                self.slots.push(Slot {
+                                    generation: 0,
+                                    node: Some(node),
+                                });
+                ValueId {
+                    index: self.slots.len() - 1,
+                    generation: 0,
+                }
+            }
+        }
+    }
+
+    /// Empties the slot of a node, so that its id refers to nothing.
+    fn release(&mut self, id: ValueId) {
+        let slot = &mut self.slots[id.index];
+        slot.node = None;
+        slot.generation += 1;
+        self.free.push(id.index);
+    }
+
+    fn free_subtree(&mut self, id: ValueId) {
+        let mut pending = vec![id];
+        while let Some(id) = pending.pop() {
+            pending.extend(self.children(id));
+            self.release(id);
+        }
+    }
+}
+
+/// The entries of a mapping node. See `Slab::entries`.
+pub struct Entries<'a> {
+    iter: linked_hash_map::Iter<'a, Value, ValueId>,
+}
+
+impl<'a> Iterator for Entries<'a> {
+    type Item = (&'a Value, ValueId);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next().map(|(key, &value)| (key, value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
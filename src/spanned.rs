@@ -0,0 +1,884 @@
+// Copyright 2018 Serde YAML Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! YAML values that remember where in the input they came from.
+//!
+//! This module is aimed at tools like editors and linters, which care as much
+//! about the position of each node in the document as about its value.
+
+use std::cmp;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use yaml_rust::scanner::{Marker, Scanner, TScalarStyle, TokenType};
+
+use de::{Event, Loader};
+use error::{self, Error, Location};
+use mapping::Mapping;
+use value::{Number, Value};
+
+/// The region of the input occupied by a node, from the first character of
+/// the node up to but not including the character after it.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Span {
+    start: Location,
+    end: Location,
+}
+
+impl Span {
+    /// The location of the first character of the node.
+    pub fn start(&self) -> Location {
+        self.start
+    }
+
+    /// The location just past the last character of the node.
+    pub fn end(&self) -> Location {
+        self.end
+    }
+
+    /// Whether the character at `index` lies within this span.
+    pub fn contains(&self, index: usize) -> bool {
+        self.start.index() <= index && index < self.end.index()
+    }
+}
+
+/// A YAML value in which every node carries its `Span`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SpannedValue {
+    span: Span,
+    node: Node,
+    alias: Option<Box<Alias>>,
+}
+
+/// Where a value written as an alias like `*defaults` came from.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Alias {
+    name: String,
+    anchor: Span,
+}
+
+impl Alias {
+    /// The name of the anchor, without the `*`.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The region of the input occupied by the anchored node, which the
+    /// value is a copy of.
+    pub fn anchor(&self) -> Span {
+        self.anchor
+    }
+}
+
+/// The content of a `SpannedValue`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Node {
+    /// Represents a YAML null value.
+    Null,
+    /// Represents a YAML boolean.
+    Bool(bool),
+    /// Represents a YAML numerical value, whether integer or floating point.
+    Number(Number),
+    /// Represents a YAML string.
+    String(String),
+    /// Represents a YAML sequence.
+    Sequence(Vec<SpannedValue>),
+    /// Represents a YAML mapping. Entries are kept in document order and
+    /// duplicate keys are preserved.
+    Mapping(Vec<(SpannedValue, SpannedValue)>),
+    /// Placeholder for a part of the document that could not be parsed.
+    Error,
+}
+
+impl SpannedValue {
+    /// The region of the input this value was parsed from.
+    pub fn span(&self) -> Span {
+        self.span
+    }
+
+    /// The content of this value.
+    pub fn node(&self) -> &Node {
+        &self.node
+    }
+
+    /// Where this value came from if it was written as an alias, like
+    /// `*defaults`, rather than in full. Its span is that of the alias and
+    /// its content a copy of the anchored node.
+    ///
+    /// ```rust
+    /// let (doc, _) = serde_yaml::parse_tolerant("a: &x [1]\nb: *x\n");
+    /// assert!(doc.get("a").unwrap().alias().is_none());
+    /// let alias = doc.get("b").unwrap().alias().unwrap();
+    /// assert_eq!(alias.name(), "x");
+    /// assert_eq!(alias.anchor().start().line(), 1);
+    /// ```
+    pub fn alias(&self) -> Option<&Alias> {
+        self.alias.as_deref()
+    }
+
+    /// Every value in this tree that was written as an alias, outermost
+    /// first and in document order. The contents of an alias are a copy of
+    /// the anchored node and are not searched again.
+    pub fn aliases(&self) -> Vec<&SpannedValue> {
+        let mut aliases = Vec::new();
+        self.collect_aliases(&mut aliases);
+        aliases
+    }
+
+    fn collect_aliases<'a>(&'a self, aliases: &mut Vec<&'a SpannedValue>) {
+        if self.alias.is_some() {
+            aliases.push(self);
+            return;
+        }
+        match self.node {
+            Node::Sequence(ref seq) => {
+                for element in seq {
+                    element.collect_aliases(aliases);
+                }
+            }
+            Node::Mapping(ref entries) => {
+                for &(ref k, ref v) in entries {
+                    k.collect_aliases(aliases);
+                    v.collect_aliases(aliases);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// The mappings that a mapping names with the YAML 1.1 merge key `<<`,
+    /// either one mapping or a sequence of them, in order. Merges are not
+    /// applied when deserializing, so this only reports what the document
+    /// asks for; each source is usually an alias, see `alias`.
+    ///
+    /// ```rust
+    /// let yaml = "base: &base {a: 1}\nextra: &extra {b: 2}\nsite:\n  <<: [*base, *extra]\n  c: 3\n";
+    /// let (doc, _) = serde_yaml::parse_tolerant(yaml);
+    /// let sources = doc.get("site").unwrap().merge_sources();
+    /// let names: Vec<&str> = sources.iter().map(|source| source.alias().unwrap().name()).collect();
+    /// assert_eq!(names, ["base", "extra"]);
+    /// ```
+    pub fn merge_sources(&self) -> Vec<&SpannedValue> {
+        let mut sources = Vec::new();
+        if let Node::Mapping(ref entries) = self.node {
+            for &(ref k, ref v) in entries {
+                match (&k.node, &v.node) {
+                    (&Node::String(ref key), &Node::Sequence(ref seq)) if key == "<<" => {
+                        sources.extend(seq.iter().filter(|source| is_mapping(source)));
+                    }
+                    (&Node::String(ref key), _) if key == "<<" && is_mapping(v) => sources.push(v),
+                    _ => {}
+                }
+            }
+        }
+        sources
+    }
+
+    /// Whether this value is a placeholder for unparseable input.
+    pub fn is_error(&self) -> bool {
+        match self.node {
+            Node::Error => true,
+            _ => false,
+        }
+    }
+
+    /// Looks up the value of a string key in a mapping. Returns `None` if this
+    /// is not a mapping or the key is absent. If the key occurs more than once,
+    /// the last occurrence wins, as it would when deserializing.
+    ///
+    /// ```rust
+    /// let (doc, _) = serde_yaml::parse_tolerant("a:\n  b: 1\n");
+    /// let b = doc.get("a").and_then(|a| a.get("b")).unwrap();
+    /// assert_eq!(b.span().start().line(), 2);
+    /// ```
+    pub fn get(&self, key: &str) -> Option<&SpannedValue> {
+        match self.node {
+            Node::Mapping(ref entries) => {
+                entries.iter()
+                    .rev()
+                    .find(|entry| match entry.0.node {
+                        Node::String(ref s) => s == key,
+                        _ => false,
+                    })
+                    .map(|entry| &entry.1)
+            }
+            _ => None,
+        }
+    }
+
+    /// The path of the innermost mapping or sequence containing the character
+    /// at `index`, written the way `serde_yaml::shape::Shape` expects, like
+    /// `server.ports[1]`. The root is written `.`.
+    ///
+    /// ```rust
+    /// let (doc, _) = serde_yaml::parse_tolerant("a:\n  b: 1\n  c: 2\n");
+    /// assert_eq!(doc.path_at(10), "a");
+    /// assert_eq!(doc.path_at(0), ".");
+    /// ```
+    pub fn path_at(&self, index: usize) -> String {
+        let mut path = String::new();
+        let mut value = self;
+        loop {
+            let child = match value.node {
+                Node::Sequence(ref seq) => {
+                    seq.iter()
+                        .enumerate()
+                        .find(|&(_, element)| is_collection(element) && element.span.contains(index))
+                        .map(|(i, element)| (format!("[{}]", i), element))
+                }
+                Node::Mapping(ref entries) => {
+                    entries.iter()
+                        .filter(|entry| is_collection(&entry.1) && entry.1.span.contains(index))
+                        .filter_map(|entry| match entry.0.node {
+                            Node::String(ref key) => Some((key.clone(), &entry.1)),
+                            _ => None,
+                        })
+                        .next()
+                        .map(|(key, v)| (if path.is_empty() { key } else { format!(".{}", key) }, v))
+                }
+                _ => None,
+            };
+            match child {
+                Some((segment, child)) => {
+                    path.push_str(&segment);
+                    value = child;
+                }
+                None => break,
+            }
+        }
+        if path.is_empty() || path.starts_with('[') {
+            path.insert(0, '.');
+        }
+        path
+    }
+
+    /// Converts to a plain `Value`, dropping the spans. Error placeholders
+    /// become `Value::Null`.
+    pub fn to_value(&self) -> Value {
+        match self.node {
+            Node::Null | Node::Error => Value::Null,
+            Node::Bool(b) => Value::Bool(b),
+            Node::Number(ref n) => Value::Number(n.clone()),
+            Node::String(ref s) => Value::String(s.clone()),
+            Node::Sequence(ref seq) => Value::Sequence(seq.iter().map(SpannedValue::to_value).collect()),
+            Node::Mapping(ref entries) => {
+                let mut mapping = Mapping::with_capacity(entries.len());
+                for &(ref k, ref v) in entries {
+                    mapping.insert(k.to_value(), v.to_value());
+                }
+                Value::Mapping(mapping)
+            }
+        }
+    }
+}
+
+/// A document parsed with `Document::parse`: its `SpannedValue` together
+/// with the anchors it defines.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Document {
+    root: SpannedValue,
+    anchors: HashMap<String, String>,
+}
+
+impl Document {
+    /// Parses YAML text the way `parse_tolerant` does, also recording where
+    /// each anchor is defined.
+    pub fn parse(s: &str) -> (Self, Vec<Error>) {
+        let (root, errors, anchors) = parse(s);
+        let document = Document {
+            root: root,
+            anchors: anchors,
+        };
+        (document, errors)
+    }
+
+    /// The value of the document.
+    pub fn root(&self) -> &SpannedValue {
+        &self.root
+    }
+
+    /// The value of the document, without the anchors.
+    pub fn into_root(self) -> SpannedValue {
+        self.root
+    }
+
+    /// The path of the node that each anchor is given to, by the name of the
+    /// anchor without the `&`. Paths are written the way `path_at` writes
+    /// them, like `environments.production`; the anchor of a mapping key
+    /// gives the path of its entry. An anchor that is defined more than once
+    /// maps to its last definition, which is the one later aliases refer to.
+    ///
+    /// The aliases that refer to an anchor are found with
+    /// `SpannedValue::aliases`.
+    ///
+    /// ```rust
+    /// use serde_yaml::spanned::Document;
+    ///
+    /// let yaml = "defaults: &production-defaults {replicas: 3}\nservices:\n  - &web {name: web}\n";
+    /// let (doc, _) = Document::parse(yaml);
+    /// assert_eq!(doc.anchors()["production-defaults"], "defaults");
+    /// assert_eq!(doc.anchors()["web"], "services[0]");
+    /// ```
+    pub fn anchors(&self) -> &HashMap<String, String> {
+        &self.anchors
+    }
+}
+
+/// Parse YAML text into a `SpannedValue` without ever failing.
+///
+/// This is meant for documents that are in the middle of being edited. Each
+/// time the parser gives up, the error is recorded and the offending line is
+/// cut out of the input before trying again. Where a line of the form
+/// `key: ...` or `- ...` had to be cut, its value is replaced by a `Node::Error`
+/// placeholder so that the key or sequence entry is still present in the tree.
+///
+/// Only the first document of the input is returned. The returned errors are
+/// in the order they were encountered, and their locations refer to the
+/// original input.
+///
+/// ```rust
+/// let (doc, errors) = serde_yaml::parse_tolerant("name: demo\nimage: [unclosed\nreplicas: 3\n");
+/// assert_eq!(errors.len(), 1);
+/// assert!(doc.get("image").unwrap().is_error());
+/// assert_eq!(doc.get("replicas").unwrap().to_value(), 3);
+/// ```
+pub fn parse_tolerant(s: &str) -> (SpannedValue, Vec<Error>) {
+    let (value, errors, _) = parse(s);
+    (value, errors)
+}
+
+fn parse(s: &str) -> (SpannedValue, Vec<Error>, HashMap<String, String>) {
+    let original: Vec<char> = s.chars().collect();
+    let lines = line_starts(&original);
+    let mut text = original.clone();
+    let mut repairs = BTreeMap::new();
+    let mut errors = Vec::new();
+
+    loop {
+        let err = match Loader::load(&text.iter().cloned().collect::<String>()) {
+            Ok(loader) => {
+                let holes = repairs.values()
+                    .filter_map(|repair| match *repair {
+                        Repair::Value(index) => Some(index),
+                        Repair::Line => None,
+                    })
+                    .collect();
+                let mut builder = Builder {
+                    loader: &loader,
+                    text: &original,
+                    lines: &lines,
+                    holes: holes,
+                    errors: errors,
+                    names: anchor_names(&text, &loader),
+                    path: Vec::new(),
+                    quiet: 0,
+                    anchors: HashMap::new(),
+                };
+                let value = builder.document();
+                return (value, builder.errors, builder.anchors);
+            }
+            Err(err) => err,
+        };
+
+        let line = err.location().map_or(1, |location| location.line());
+        errors.push(err);
+        match best_repair(&text, &lines, &repairs, line.saturating_sub(1)) {
+            Some((line, repair)) => {
+                apply(&mut text, &lines, line, &repair);
+                repairs.insert(line, repair);
+            }
+            None => {
+                let span = Span {
+                    start: location_of(&lines, 0),
+                    end: location_of(&lines, original.len()),
+                };
+                let value = SpannedValue { span: span, node: Node::Error, alias: None };
+                return (value, errors, HashMap::new());
+            }
+        }
+    }
+}
+
+/// How many lines above a parse error to look for the cause. The parser
+/// often notices a problem, like an unclosed bracket, some lines after it.
+const REPAIR_WINDOW: usize = 8;
+
+/// How a line of the input was modified to get past a parse error.
+enum Repair {
+    /// The value after `key:` or `- ` was replaced by `~` at this index.
+    Value(usize),
+    /// The whole line was blanked out.
+    Line,
+}
+
+/// Picks the repair at or above `line` (zero-based) that gets the parser
+/// furthest, preferring the least destructive one near the error. Returns
+/// `None` if there is nothing left to cut.
+fn best_repair(text: &[char],
+               lines: &[usize],
+               repairs: &BTreeMap<usize, Repair>,
+               line: usize)
+               -> Option<(usize, Repair)> {
+    let last = if line < lines.len() { line } else { lines.len() - 1 };
+    let first = last.saturating_sub(REPAIR_WINDOW);
+
+    let mut candidates = Vec::new();
+    for line in (first..last + 1).rev() {
+        let (start, end) = line_range(text, lines, line);
+        match repairs.get(&line) {
+            Some(&Repair::Line) => {}
+            Some(&Repair::Value(_)) => candidates.push((line, Repair::Line)),
+            None if text[start..end].iter().all(|c| c.is_whitespace()) => {}
+            None => {
+                if let Some(offset) = value_start(&text[start..end]) {
+                    candidates.push((line, Repair::Value(start + offset)));
+                }
+                candidates.push((line, Repair::Line));
+            }
+        }
+    }
+
+    let mut best: Option<(usize, usize)> = None;
+    for (i, &(line, ref repair)) in candidates.iter().enumerate() {
+        let mut attempt = text.to_vec();
+        apply(&mut attempt, lines, line, repair);
+        let reached = match Loader::load(&attempt.iter().cloned().collect::<String>()) {
+            Ok(_) => {
+                best = Some((i, usize::max_value()));
+                break;
+            }
+            Err(err) => err.location().map_or(0, |location| location.index()),
+        };
+        if best.map_or(true, |(_, furthest)| reached > furthest) {
+            best = Some((i, reached));
+        }
+    }
+    best.map(|(i, _)| candidates.swap_remove(i))
+}
+
+fn apply(text: &mut [char], lines: &[usize], line: usize, repair: &Repair) {
+    let (start, end) = line_range(text, lines, line);
+    let from = match *repair {
+        Repair::Value(index) => index,
+        Repair::Line => start,
+    };
+    for c in &mut text[from..end] {
+        *c = ' ';
+    }
+    if let Repair::Value(index) = *repair {
+        text[index] = '~';
+    }
+}
+
+/// Finds the offset of the value in a line like `key: value`, `- value` or
+/// `- key: value`.
+fn value_start(line: &[char]) -> Option<usize> {
+    let skip_spaces = |mut i: usize| {
+        while i < line.len() && line[i] == ' ' {
+            i += 1;
+        }
+        i
+    };
+
+    let mut i = skip_spaces(0);
+    let mut start = None;
+    while i + 1 < line.len() && line[i] == '-' && line[i + 1] == ' ' {
+        i = skip_spaces(i + 2);
+        start = Some(i);
+    }
+
+    let mut quote = None;
+    let mut j = i;
+    while j < line.len() {
+        let c = line[j];
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None if (c == '"' || c == '\'') && j == i => quote = Some(c),
+            None if c == '#' && j > 0 && line[j - 1] == ' ' => break,
+            None if c == ':' && (j + 1 == line.len() || line[j + 1] == ' ') => {
+                start = Some(skip_spaces(j + 1));
+                break;
+            }
+            None => {}
+        }
+        j += 1;
+    }
+
+    match start {
+        Some(offset) if offset < line.len() && line[offset] != '#' => Some(offset),
+        _ => None,
+    }
+}
+
+struct Builder<'a> {
+    loader: &'a Loader,
+    text: &'a [char],
+    lines: &'a [usize],
+    holes: BTreeSet<usize>,
+    errors: Vec<Error>,
+    /// The name of the anchor of each anchored node, by event index.
+    names: HashMap<usize, String>,
+    /// The path of the node being built: keys, and indices like `[0]`.
+    path: Vec<String>,
+    /// While above zero, anchors are not recorded, as the nodes being built
+    /// are copies made for an alias or parts of a mapping key.
+    quiet: usize,
+    anchors: HashMap<String, String>,
+}
+
+impl<'a> Builder<'a> {
+    fn document(&mut self) -> SpannedValue {
+        if self.loader.events.is_empty() {
+            let start = location_of(self.lines, 0);
+            let span = Span { start: start, end: start };
+            return SpannedValue { span: span, node: Node::Null, alias: None };
+        }
+        let mut pos = 0;
+        let value = self.node(&mut pos);
+        if pos < self.loader.events.len() {
+            self.errors.push(Error::more_than_one_document());
+        }
+        value
+    }
+
+    fn node(&mut self, pos: &mut usize) -> SpannedValue {
+        let loader = self.loader;
+        let (ref event, marker) = loader.events[*pos];
+        if self.quiet == 0 {
+            if let Some(name) = self.names.get(pos) {
+                self.anchors.insert(name.clone(), join(&self.path));
+            }
+        }
+        match *event {
+            Event::Alias(id) => {
+                *pos += 1;
+                let mut target = loader.aliases[&id];
+                self.quiet += 1;
+                let mut value = self.node(&mut target);
+                self.quiet -= 1;
+                let end = self.alias_end(marker);
+                let name = self.text[marker.index() + 1..end].iter().cloned().collect();
+                value.alias = Some(Box::new(Alias {
+                                                name: name,
+                                                anchor: value.span,
+                                            }));
+                value.span = self.span(marker.index(), end);
+                value
+            }
+            Event::Scalar(ref v, style, _) => {
+                let start = marker.index();
+                if self.holes.contains(&start) {
+                    *pos += 1;
+                    let end = self.trimmed_line_end(start);
+                    return SpannedValue {
+                        span: self.span(start, end),
+                        node: Node::Error,
+                        alias: None,
+                    };
+                }
+                let mut end = self.scalar_end(marker, style, v);
+                // The parser places the start of a block scalar at its
+                // content, but the node starts at its header.
+                let mut start = start;
+                if style == TScalarStyle::Literal || style == TScalarStyle::Foled {
+                    if let Some(header) = self.block_header(start) {
+                        start = header;
+                        if v.trim().is_empty() {
+                            end = self.header_end(header);
+                        }
+                    }
+                }
+                let node = match loader.deserialize_at(pos) {
+                    Ok(value) => scalar_node(value),
+                    Err(err) => {
+                        self.errors.push(err);
+                        Node::Error
+                    }
+                };
+                SpannedValue {
+                    span: self.span(start, end),
+                    node: node,
+                    alias: None,
+                }
+            }
+            Event::SequenceStart => {
+                *pos += 1;
+                let mut seq = Vec::new();
+                while loader.events[*pos].0 != Event::SequenceEnd {
+                    self.path.push(format!("[{}]", seq.len()));
+                    seq.push(self.node(pos));
+                    self.path.pop();
+                }
+                let last = seq.last().map(|value| value.span.end.index());
+                let end = self.collection_end(loader.events[*pos].1, ']', last);
+                *pos += 1;
+                SpannedValue {
+                    span: self.span(marker.index(), end.unwrap_or(marker.index())),
+                    node: Node::Sequence(seq),
+                    alias: None,
+                }
+            }
+            Event::MappingStart => {
+                *pos += 1;
+                let mut entries = Vec::new();
+                while loader.events[*pos].0 != Event::MappingEnd {
+                    let key_pos = *pos;
+                    self.quiet += 1;
+                    let key = self.node(pos);
+                    self.quiet -= 1;
+                    self.path.push(segment(&key));
+                    if self.quiet == 0 {
+                        if let Some(name) = self.names.get(&key_pos) {
+                            self.anchors.insert(name.clone(), join(&self.path));
+                        }
+                    }
+                    let value = self.node(pos);
+                    self.path.pop();
+                    entries.push((key, value));
+                }
+                // The parser places the start of a block mapping at its first
+                // `:` rather than at the first key.
+                let start = entries.first().map_or(marker.index(), |entry| {
+                    cmp::min(marker.index(), entry.0.span.start.index())
+                });
+                let last = entries.last().map(|entry| entry.1.span.end.index());
+                let end = self.collection_end(loader.events[*pos].1, '}', last);
+                *pos += 1;
+                SpannedValue {
+                    span: self.span(start, end.unwrap_or(start)),
+                    node: Node::Mapping(entries),
+                    alias: None,
+                }
+            }
+            Event::SequenceEnd | Event::MappingEnd => unreachable!(),
+        }
+    }
+
+    fn span(&self, start: usize, end: usize) -> Span {
+        Span {
+            start: location_of(self.lines, start),
+            end: location_of(self.lines, end),
+        }
+    }
+
+    /// Flow collections end just past their closing bracket, block
+    /// collections at the end of their last entry.
+    fn collection_end(&self, marker: Marker, close: char, last: Option<usize>) -> Option<usize> {
+        if self.text.get(marker.index()) == Some(&close) {
+            Some(marker.index() + 1)
+        } else {
+            last
+        }
+    }
+
+    fn alias_end(&self, marker: Marker) -> usize {
+        let mut end = marker.index() + 1;
+        while end < self.text.len() && !is_break(self.text[end]) {
+            end += 1;
+        }
+        end
+    }
+
+    fn scalar_end(&self, marker: Marker, style: TScalarStyle, value: &str) -> usize {
+        let text = self.text;
+        let start = marker.index();
+        match style {
+            TScalarStyle::SingleQuoted => {
+                let mut i = start + 1;
+                while i < text.len() {
+                    if text[i] == '\'' {
+                        if text.get(i + 1) == Some(&'\'') {
+                            i += 1;
+                        } else {
+                            return i + 1;
+                        }
+                    }
+                    i += 1;
+                }
+                text.len()
+            }
+            TScalarStyle::DoubleQuoted => {
+                let mut i = start + 1;
+                while i < text.len() {
+                    match text[i] {
+                        '\\' => i += 1,
+                        '"' => return i + 1,
+                        _ => {}
+                    }
+                    i += 1;
+                }
+                text.len()
+            }
+            _ => {
+                // Walk the source text matching the non-whitespace characters
+                // of the value, which skips over line folding and block scalar
+                // indentation.
+                let mut end = start;
+                let mut i = start;
+                for c in value.chars().filter(|c| !c.is_whitespace()) {
+                    while i < text.len() && text[i] != c {
+                        i += 1;
+                    }
+                    if i == text.len() {
+                        break;
+                    }
+                    i += 1;
+                    end = i;
+                }
+                if end == start && style != TScalarStyle::Plain {
+                    // Header of an empty block scalar.
+                    end = start + 1;
+                }
+                end
+            }
+        }
+    }
+
+    /// The index of the `|` or `>` of the block scalar whose content starts
+    /// at `index`, on the nearest line before it that is not blank.
+    fn block_header(&self, index: usize) -> Option<usize> {
+        let mut end = cmp::min(index, self.text.len());
+        loop {
+            let mut start = end;
+            while start > 0 && self.text[start - 1] != '\n' {
+                start -= 1;
+            }
+            let line: String = self.text[start..end].iter().collect();
+            if let Some(column) = error::block_header(&line) {
+                return Some(start + column);
+            }
+            if !line.trim().is_empty() || start == 0 {
+                return None;
+            }
+            end = start - 1;
+        }
+    }
+
+    /// The index just past the indicators of the block scalar header at
+    /// `header`.
+    fn header_end(&self, header: usize) -> usize {
+        let mut end = header + 1;
+        while end < self.text.len() && "123456789+-".contains(self.text[end]) {
+            end += 1;
+        }
+        end
+    }
+
+    fn trimmed_line_end(&self, index: usize) -> usize {
+        let mut end = index;
+        while end < self.text.len() && self.text[end] != '\n' {
+            end += 1;
+        }
+        while end > index + 1 && self.text[end - 1].is_whitespace() {
+            end -= 1;
+        }
+        end
+    }
+}
+
+/// The name of the anchor of each anchored event of `loader`, which was
+/// loaded from `text`. The parser numbers anchors from 1 in the order they
+/// appear.
+fn anchor_names(text: &[char], loader: &Loader) -> HashMap<usize, String> {
+    let scanner = Scanner::new(text.iter().cloned());
+    let mut names = HashMap::new();
+    let anchors = scanner.filter_map(|token| match token.1 {
+        TokenType::Anchor(name) => Some(name),
+        _ => None,
+    });
+    for (id, name) in (1..).zip(anchors) {
+        if let Some(&pos) = loader.aliases.get(&id) {
+            names.insert(pos, name);
+        }
+    }
+    names
+}
+
+/// How a mapping key appears in a path.
+fn segment(key: &SpannedValue) -> String {
+    match key.node {
+        Node::String(ref s) => s.clone(),
+        Node::Number(ref n) => n.to_string(),
+        Node::Bool(b) => b.to_string(),
+        Node::Null => "~".to_owned(),
+        _ => "?".to_owned(),
+    }
+}
+
+/// A path written the way `SpannedValue::path_at` writes it.
+fn join(segments: &[String]) -> String {
+    let mut path = String::new();
+    for segment in segments {
+        if !path.is_empty() && !segment.starts_with('[') {
+            path.push('.');
+        }
+        path.push_str(segment);
+    }
+    if path.is_empty() || path.starts_with('[') {
+        path.insert(0, '.');
+    }
+    path
+}
+
+fn scalar_node(value: Value) -> Node {
+    match value {
+        Value::Null => Node::Null,
+        Value::Bool(b) => Node::Bool(b),
+        Value::Number(n) => Node::Number(n),
+        Value::String(s) => Node::String(s),
+        // A single scalar event never deserializes to a collection.
+        Value::Sequence(_) | Value::Mapping(_) => Node::Error,
+    }
+}
+
+fn is_break(c: char) -> bool {
+    c.is_whitespace() || c == ',' || c == ']' || c == '}'
+}
+
+/// Character index of the start of each line.
+fn line_starts(text: &[char]) -> Vec<usize> {
+    let mut starts = vec![0];
+    for (i, &c) in text.iter().enumerate() {
+        if c == '\n' {
+            starts.push(i + 1);
+        }
+    }
+    starts
+}
+
+/// The line, not including its line break.
+fn line_range(text: &[char], lines: &[usize], line: usize) -> (usize, usize) {
+    let start = lines[line];
+    let mut end = lines.get(line + 1).map_or(text.len(), |&next| next - 1);
+    if end > start && text[end - 1] == '\r' {
+        end -= 1;
+    }
+    (start, end)
+}
+
+fn location_of(lines: &[usize], index: usize) -> Location {
+    let line = match lines.binary_search(&index) {
+        Ok(line) => line,
+        Err(next) => next - 1,
+    };
+    Location::new(index, line + 1, index - lines[line] + 1)
+}
+
+fn is_mapping(value: &SpannedValue) -> bool {
+    match value.node {
+        Node::Mapping(_) => true,
+        _ => false,
+    }
+}
+
+fn is_collection(value: &SpannedValue) -> bool {
+    match value.node {
+        Node::Sequence(_) | Node::Mapping(_) => true,
+        _ => false,
+    }
+}
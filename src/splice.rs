@@ -0,0 +1,643 @@
+// Copyright 2018 Serde YAML Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Writing an edited document back over the text it was read from, changing
+//! only the parts whose values changed.
+//!
+//! Serializing an edited value with `to_string` writes the whole document
+//! anew, dropping its comments and layout. `splice::to_string` instead
+//! compares the edited value with the original text node by node and keeps
+//! every unchanged node exactly as it was written, so that changing one
+//! scalar of a large file changes one scalar of its text.
+//!
+//! ```rust
+//! # extern crate serde_yaml;
+//! #
+//! # fn main() {
+//! use serde_yaml::{splice, Value};
+//!
+//! let original = "# Web tier\nname: web   # shown in the dashboard\nreplicas: 2\n";
+//! let mut value: Value = serde_yaml::from_str(original).unwrap();
+//! value["replicas"] = 3.into();
+//! value["region"] = "eu".into();
+//! assert_eq!(splice::to_string(original, &value).unwrap(),
+//!            "# Web tier\nname: web   # shown in the dashboard\nreplicas: 3\nregion: eu\n");
+//! # }
+//! ```
+//!
+//! A changed scalar is replaced where it stands. The entries of a block
+//! mapping are matched by key, and entries that keep their key are kept in
+//! their original order. Removed entries are cut out together with their
+//! lines, and new entries are written in block style after the entry that
+//! comes before them in the edited value. The elements of a block sequence
+//! are lined up with the edited ones where they are equal, so that elements
+//! can be inserted or removed anywhere without touching the rest.
+//!
+//! A node that cannot be edited in place that way, like a flow collection
+//! that changes length or a mapping whose entries change order, is written
+//! out again as a whole: block collections in block style at their original
+//! indentation, everything else in flow style. An alias whose anchored
+//! node was edited is written out in full, as it no longer has the same
+//! value as the anchor.
+//!
+//! For editors and language servers, `edits` gives the same changes as a
+//! list of `TextEdit`s rather than as new text, and `diff` gives the line
+//! edits between any two texts, like a document and its formatted version.
+
+use std::collections::{HashMap, HashSet};
+use std::str::CharIndices;
+
+use serde::de::Error as SError;
+use serde::ser::Serialize;
+
+use error::{Error, Location, Result};
+use mapping::Mapping;
+use ser;
+use spanned::{self, Node, SpannedValue};
+use value::{self, Value};
+
+/// Serializes `edited` by editing `original`, the text it was read from,
+/// leaving the text of every unchanged node as it is.
+///
+/// Fails if `original` is not a single valid YAML document, or if the
+/// spliced text would not read back as `edited`, which can happen when a
+/// node with a tag like `!!str` changes or an anchored node is removed
+/// while its aliases are not. In that case the edited value can still be
+/// written with `to_string`.
+pub fn to_string<T: ?Sized>(original: &str, edited: &T) -> Result<String>
+    where T: Serialize
+{
+    splice(original, &value::to_value(edited)?).map(|(_, spliced)| spliced)
+}
+
+/// The edits that `to_string` makes to `original`, rather than the edited
+/// text, for editors that apply changes to an open document.
+///
+/// The edits are in order, do not overlap, and all refer to positions in
+/// `original`, the way a language server's replies to
+/// `textDocument/formatting` and the like do. Several insertions at the same
+/// place are meant to be applied in order.
+///
+/// Fails in the same cases as `to_string`.
+///
+/// ```rust
+/// # extern crate serde_yaml;
+/// #
+/// # fn main() {
+/// use serde_yaml::{splice, Value};
+///
+/// let original = "name: web\nreplicas: 2  # scaled by hand\n";
+/// let mut value: Value = serde_yaml::from_str(original).unwrap();
+/// value["replicas"] = 3.into();
+/// let edits = splice::edits(original, &value).unwrap();
+/// assert_eq!(edits.len(), 1);
+/// assert_eq!((edits[0].start.line(), edits[0].start.column()), (2, 11));
+/// assert_eq!((edits[0].end.line(), edits[0].end.column()), (2, 12));
+/// assert_eq!(edits[0].text, "3");
+/// # }
+/// ```
+pub fn edits<T: ?Sized>(original: &str, edited: &T) -> Result<Vec<TextEdit>>
+    where T: Serialize
+{
+    let (edits, _) = splice(original, &value::to_value(edited)?)?;
+    Ok(text_edits(original, edits))
+}
+
+/// The edits that turn the text `original` into `modified` by replacing
+/// whole lines, like the output of a formatter that rewrote the document,
+/// in the same form as `edits`. Lines that the two texts have in common
+/// are left alone.
+///
+/// ```rust
+/// use serde_yaml::splice;
+///
+/// let original = "a: 1\nb:   2\nc: 3\n";
+/// let formatted = "a: 1\nb: 2\nc: 3\n";
+/// let edits = splice::diff(original, formatted);
+/// assert_eq!(edits.len(), 1);
+/// assert_eq!((edits[0].start.line(), edits[0].start.column()), (2, 1));
+/// assert_eq!((edits[0].end.line(), edits[0].end.column()), (3, 1));
+/// assert_eq!(edits[0].text, "b: 2\n");
+/// ```
+pub fn diff(original: &str, modified: &str) -> Vec<TextEdit> {
+    let old = lines(original);
+    let new = lines(modified);
+    // Where each line of the original starts, and its end.
+    let mut starts = Vec::with_capacity(old.len() + 1);
+    let mut index = 0;
+    for line in &old {
+        starts.push(index);
+        index += line.chars().count();
+    }
+    starts.push(index);
+
+    let mut edits = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    for (next_i, next_j) in common(&old, &new).into_iter().chain(Some((old.len(), new.len()))) {
+        if next_i > i || next_j > j {
+            edits.push(Edit {
+                           start: starts[i],
+                           end: starts[next_i],
+                           text: new[j..next_j].concat(),
+                       });
+        }
+        i = next_i + 1;
+        j = next_j + 1;
+    }
+    text_edits(original, edits)
+}
+
+/// A change to a text: the characters from `start` up to but not including
+/// `end` are replaced by `text`. An insertion starts and ends at the same
+/// place.
+///
+/// Lines and columns count from 1 and columns count characters, while the
+/// Language Server Protocol counts from 0 and in UTF-16 code units by
+/// default.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TextEdit {
+    /// The first character replaced.
+    pub start: Location,
+    /// The character just past the last one replaced.
+    pub end: Location,
+    /// The text to put in their place.
+    pub text: String,
+}
+
+/// Splices `edited` into `original`, checking that the result reads back as
+/// `edited`.
+fn splice(original: &str, edited: &Value) -> Result<(Vec<Edit>, String)> {
+    let edits = plan(original, edited)?;
+    let spliced = apply(original, &edits);
+    match ::de::from_str::<Value>(&spliced) {
+        Ok(ref value) if equivalent(value, edited) => Ok((edits, spliced)),
+        _ => Err(Error::custom("the edits cannot be spliced into the original text")),
+    }
+}
+
+/// A replacement of the characters from `start` up to but not including
+/// `end` by `text`. Insertions have an empty range.
+struct Edit {
+    start: usize,
+    end: usize,
+    text: String,
+}
+
+/// The edits that turn `original` into a document with the value `edited`,
+/// ordered by position.
+fn plan(original: &str, edited: &Value) -> Result<Vec<Edit>> {
+    let (doc, mut errors) = spanned::parse_tolerant(original);
+    if !errors.is_empty() {
+        return Err(errors.remove(0));
+    }
+    let text: Vec<char> = original.chars().collect();
+    let mut splicer = Splicer {
+        text: &text,
+        edits: Vec::new(),
+        changed: HashSet::new(),
+    };
+    splicer.node(&doc, edited)?;
+    let mut edits = splicer.edits;
+    // Stable, so that insertions at the same place stay in the order they
+    // were made, and before a removal that starts there.
+    edits.sort_by_key(|edit| (edit.start, edit.end));
+    Ok(edits)
+}
+
+/// Gives the edits, which are in order, their lines and columns in `text`.
+fn text_edits(text: &str, edits: Vec<Edit>) -> Vec<TextEdit> {
+    let mut chars = text.chars();
+    let mut at = Location::new(0, 1, 1);
+    let mut locate = |index: usize| {
+        while at.index() < index {
+            at = match chars.next() {
+                Some('\n') => Location::new(at.index() + 1, at.line() + 1, 1),
+                Some(_) => Location::new(at.index() + 1, at.line(), at.column() + 1),
+                None => break,
+            };
+        }
+        at
+    };
+    edits.into_iter()
+        .map(|edit| {
+                 TextEdit {
+                     start: locate(edit.start),
+                     end: locate(edit.end),
+                     text: edit.text,
+                 }
+             })
+        .collect()
+}
+
+/// The lines of `text`, each with its line break.
+fn lines(text: &str) -> Vec<&str> {
+    let mut lines = Vec::new();
+    let mut rest = text;
+    while !rest.is_empty() {
+        let len = rest.find('\n').map_or(rest.len(), |newline| newline + 1);
+        lines.push(&rest[..len]);
+        rest = &rest[len..];
+    }
+    lines
+}
+
+fn apply(original: &str, edits: &[Edit]) -> String {
+    let mut out = String::with_capacity(original.len());
+    let mut offsets = Offsets {
+        chars: original.char_indices(),
+        index: 0,
+        len: original.len(),
+    };
+    let mut copied = 0;
+    for edit in edits {
+        let start = offsets.byte(edit.start);
+        out.push_str(&original[copied..start]);
+        out.push_str(&edit.text);
+        copied = offsets.byte(edit.end);
+    }
+    out.push_str(&original[copied..]);
+    out
+}
+
+/// Converts increasing character indices into byte offsets.
+struct Offsets<'a> {
+    chars: CharIndices<'a>,
+    index: usize,
+    len: usize,
+}
+
+impl<'a> Offsets<'a> {
+    fn byte(&mut self, index: usize) -> usize {
+        while self.index < index {
+            self.chars.next();
+            self.index += 1;
+        }
+        self.chars.clone().next().map_or(self.len, |(byte, _)| byte)
+    }
+}
+
+struct Splicer<'a> {
+    text: &'a [char],
+    edits: Vec<Edit>,
+    /// Where the nodes that were edited start, to find the aliases whose
+    /// anchored node changed.
+    changed: HashSet<usize>,
+}
+
+impl<'a> Splicer<'a> {
+    fn node(&mut self, old: &SpannedValue, new: &Value) -> Result<()> {
+        let before = self.edits.len();
+        if let Some(alias) = old.alias() {
+            if self.changed.contains(&alias.anchor().start().index()) || old.to_value() != *new {
+                self.replace(old, new)?;
+            }
+        } else {
+            match (old.node(), new) {
+                (&Node::Sequence(ref seq), &Value::Sequence(ref elements)) if !elements.is_empty() => {
+                    self.sequence(old, seq, elements)?;
+                }
+                (&Node::Mapping(ref entries), &Value::Mapping(ref mapping)) if !mapping.is_empty() => {
+                    self.mapping(old, entries, mapping)?;
+                }
+                _ => {
+                    if old.to_value() != *new {
+                        self.replace(old, new)?;
+                    }
+                }
+            }
+        }
+        if self.edits.len() > before {
+            self.changed.insert(old.span().start().index());
+        }
+        Ok(())
+    }
+
+    fn sequence(&mut self, old: &SpannedValue, seq: &[SpannedValue], new: &[Value]) -> Result<()> {
+        if self.is_flow(old) {
+            if seq.len() != new.len() {
+                return self.replace(old, &new_sequence(new));
+            }
+            for (old, new) in seq.iter().zip(new) {
+                self.node(old, new)?;
+            }
+            return Ok(());
+        }
+
+        let before = self.edits.len();
+        let mut done = true;
+        let indent = old.span().start().column() - 1;
+        // Elements that are not kept are paired up with the new elements
+        // in the same gap between kept ones, and the rest removed or added.
+        let (mut i, mut j) = (0, 0);
+        for (next_i, next_j) in matching(seq, new).into_iter().chain(Some((seq.len(), new.len()))) {
+            let paired = (next_i - i).min(next_j - j);
+            for (old, new) in seq[i..i + paired].iter().zip(&new[j..j + paired]) {
+                self.node(old, new)?;
+            }
+            for element in &seq[i + paired..next_i] {
+                done &= self.remove_lines(element.span().start().index(), element.span().end().index(), true);
+            }
+            let added = &new[j + paired..next_j];
+            if !added.is_empty() {
+                let text = self.render_block(&new_sequence(added), indent)?;
+                match seq.get(next_i) {
+                    // Elements go before the next kept element, or else
+                    // after the last element.
+                    Some(next) => {
+                        let start = next.span().start().index();
+                        match self.line_prefix(start, true) {
+                            Some(line) => self.insert(line, format!("{}{}\n", spaces(indent), text)),
+                            None => done = false,
+                        }
+                    }
+                    None => {
+                        let end = self.line_end(seq[seq.len() - 1].span().end().index());
+                        self.insert(end, format!("\n{}{}", spaces(indent), text));
+                    }
+                }
+            }
+            i = next_i + 1;
+            j = next_j + 1;
+        }
+        if !done {
+            self.edits.truncate(before);
+            return self.replace(old, &new_sequence(new));
+        }
+        Ok(())
+    }
+
+    // Values do not change their hash, whatever they cache.
+    #[allow(clippy::mutable_key_type)]
+    fn mapping(&mut self,
+               old: &SpannedValue,
+               entries: &[(SpannedValue, SpannedValue)],
+               new: &Mapping)
+               -> Result<()> {
+        let keys: Vec<Value> = entries.iter().map(|entry| entry.0.to_value()).collect();
+        let positions: HashMap<&Value, usize> = keys.iter().enumerate().map(|(i, key)| (key, i)).collect();
+        let unique = positions.len() == keys.len();
+        let kept: Vec<&Value> = keys.iter().filter(|key| new.contains_key(key)).collect();
+        let in_order = kept.iter()
+            .cloned()
+            .eq(new.iter().map(|entry| entry.0).filter(|key| positions.contains_key(key)));
+        let whole = Value::Mapping(new.clone());
+        if !unique || kept.is_empty() || !in_order {
+            return self.replace_changed(old, &whole);
+        }
+        if self.is_flow(old) {
+            if kept.len() != keys.len() || kept.len() != new.len() {
+                return self.replace(old, &whole);
+            }
+            for (entry, key) in entries.iter().zip(&keys) {
+                self.node(&entry.1, &new[key])?;
+            }
+            return Ok(());
+        }
+
+        let before = self.edits.len();
+        let mut done = true;
+        for (&(ref key, ref value), k) in entries.iter().zip(&keys) {
+            match new.get(k) {
+                Some(new) => self.node(value, new)?,
+                None => {
+                    done &= self.remove_lines(key.span().start().index(), value.span().end().index(), false);
+                }
+            }
+        }
+
+        // New entries, grouped by the kept entry they follow.
+        let indent = entries[0].0.span().start().column() - 1;
+        let mut groups: Vec<(Option<usize>, Mapping)> = Vec::new();
+        let mut after = None;
+        for (key, value) in new {
+            if let Some(&i) = positions.get(key) {
+                after = Some(i);
+                continue;
+            }
+            match groups.last_mut() {
+                Some(&mut (group, ref mut added)) if group == after => {
+                    added.insert(key.clone(), value.clone());
+                    continue;
+                }
+                _ => {}
+            }
+            let mut added = Mapping::new();
+            added.insert(key.clone(), value.clone());
+            groups.push((after, added));
+        }
+        for (after, added) in groups {
+            let text = self.render_block(&Value::Mapping(added), indent)?;
+            match after {
+                Some(i) => {
+                    let end = self.line_end(entries[i].1.span().end().index());
+                    self.insert(end, format!("\n{}{}", spaces(indent), text));
+                }
+                None => {
+                    let start = entries[0].0.span().start().index();
+                    self.insert(start, format!("{}\n{}", text, spaces(indent)));
+                }
+            }
+        }
+        if !done {
+            self.edits.truncate(before);
+            return self.replace(old, &whole);
+        }
+        Ok(())
+    }
+
+    fn replace_changed(&mut self, old: &SpannedValue, new: &Value) -> Result<()> {
+        if old.to_value() != *new { self.replace(old, new) } else { Ok(()) }
+    }
+
+    /// Writes `new` over the whole of `old`.
+    fn replace(&mut self, old: &SpannedValue, new: &Value) -> Result<()> {
+        let span = old.span();
+        let block = old.alias().is_none() && is_collection(old) && !self.is_flow(old) && is_nonempty(new);
+        let text = if block {
+            self.render_block(new, span.start().column() - 1)?
+        } else {
+            ser::to_string_compact(new)?
+        };
+        self.edits.push(Edit {
+                            start: span.start().index(),
+                            end: span.end().index(),
+                            text: text,
+                        });
+        Ok(())
+    }
+
+    fn insert(&mut self, index: usize, text: String) {
+        self.edits.push(Edit {
+                            start: index,
+                            end: index,
+                            text: text,
+                        });
+    }
+
+    /// Removes the lines from the one containing `start` to the one
+    /// containing `end`, if nothing but indentation, or a `-` for a sequence
+    /// element, comes before `start` on its line.
+    fn remove_lines(&mut self, start: usize, end: usize, dash: bool) -> bool {
+        let start = match self.line_prefix(start, dash) {
+            Some(start) => start,
+            None => return false,
+        };
+        let end = self.line_end(end);
+        if end < self.text.len() {
+            self.edits.push(Edit {
+                                start: start,
+                                end: end + 1,
+                                text: String::new(),
+                            });
+        } else {
+            // The last line has no line break of its own to remove.
+            self.edits.push(Edit {
+                                start: start.saturating_sub(1),
+                                end: end,
+                                text: String::new(),
+                            });
+        }
+        true
+    }
+
+    /// The start of the line containing `index`, if only indentation and,
+    /// with `dash`, one `-` come before `index` on that line.
+    fn line_prefix(&self, index: usize, dash: bool) -> Option<usize> {
+        let mut start = index;
+        while start > 0 && self.text[start - 1] != '\n' {
+            start -= 1;
+        }
+        let prefix: String = self.text[start..index].iter().collect();
+        let prefix = prefix.trim();
+        if prefix.is_empty() && !dash || prefix == "-" && dash {
+            Some(start)
+        } else {
+            None
+        }
+    }
+
+    /// The index of the line break ending the line that contains `index`.
+    fn line_end(&self, index: usize) -> usize {
+        let mut end = index;
+        while end < self.text.len() && self.text[end] != '\n' {
+            end += 1;
+        }
+        end
+    }
+
+    fn is_flow(&self, value: &SpannedValue) -> bool {
+        match self.text.get(value.span().start().index()) {
+            Some(&'[') | Some(&'{') => true,
+            _ => false,
+        }
+    }
+
+    /// `value` in block style, with every line after the first indented by
+    /// `indent` spaces.
+    fn render_block(&self, value: &Value, indent: usize) -> Result<String> {
+        let yaml = ser::to_string(value)?;
+        let yaml = if yaml.starts_with("---\n") { &yaml[4..] } else { &yaml[..] };
+        Ok(yaml.replace('\n', &format!("\n{}", spaces(indent))))
+    }
+}
+
+/// The indices of the elements that `seq` and `new` have in common, in
+/// order.
+fn matching(seq: &[SpannedValue], new: &[Value]) -> Vec<(usize, usize)> {
+    let old: Vec<Value> = seq.iter().map(SpannedValue::to_value).collect();
+    common(&old, new)
+}
+
+/// The indices of the elements that `old` and `new` have in common, as a
+/// longest common subsequence of the two, in order. Elements at both ends
+/// are matched first; the rest only if there are not too many of them.
+fn common<T: PartialEq>(old: &[T], new: &[T]) -> Vec<(usize, usize)> {
+    let mut prefix = 0;
+    while prefix < old.len() && prefix < new.len() && old[prefix] == new[prefix] {
+        prefix += 1;
+    }
+    let mut suffix = 0;
+    while suffix < old.len() - prefix && suffix < new.len() - prefix &&
+          old[old.len() - 1 - suffix] == new[new.len() - 1 - suffix] {
+        suffix += 1;
+    }
+    let mut pairs: Vec<(usize, usize)> = (0..prefix).map(|i| (i, i)).collect();
+
+    let a = &old[prefix..old.len() - suffix];
+    let b = &new[prefix..new.len() - suffix];
+    if a.len().saturating_mul(b.len()) <= MATCHING_LIMIT {
+        // lengths[i][j] is the length of the longest common subsequence of
+        // a[i..] and b[j..].
+        let width = b.len() + 1;
+        let mut lengths = vec![0; (a.len() + 1) * width];
+        for i in (0..a.len()).rev() {
+            for j in (0..b.len()).rev() {
+                lengths[i * width + j] = if a[i] == b[j] {
+                    lengths[(i + 1) * width + j + 1] + 1
+                } else {
+                    lengths[(i + 1) * width + j].max(lengths[i * width + j + 1])
+                };
+            }
+        }
+        let (mut i, mut j) = (0, 0);
+        while i < a.len() && j < b.len() {
+            if a[i] == b[j] {
+                pairs.push((prefix + i, prefix + j));
+                i += 1;
+                j += 1;
+            } else if lengths[(i + 1) * width + j] >= lengths[i * width + j + 1] {
+                i += 1;
+            } else {
+                j += 1;
+            }
+        }
+    }
+
+    pairs.extend((0..suffix).map(|k| (old.len() - suffix + k, new.len() - suffix + k)));
+    pairs
+}
+
+/// The largest table `common` fills in to align the middle of two
+/// sequences.
+const MATCHING_LIMIT: usize = 1 << 20;
+
+fn new_sequence(elements: &[Value]) -> Value {
+    Value::Sequence(elements.to_vec())
+}
+
+fn spaces(n: usize) -> String {
+    " ".repeat(n)
+}
+
+fn is_collection(value: &SpannedValue) -> bool {
+    match *value.node() {
+        Node::Sequence(_) | Node::Mapping(_) => true,
+        _ => false,
+    }
+}
+
+fn is_nonempty(value: &Value) -> bool {
+    match *value {
+        Value::Sequence(ref seq) => !seq.is_empty(),
+        Value::Mapping(ref mapping) => !mapping.is_empty(),
+        _ => false,
+    }
+}
+
+/// Whether two values are equal, not counting the order of mapping entries.
+fn equivalent(a: &Value, b: &Value) -> bool {
+    match (a, b) {
+        (&Value::Sequence(ref a), &Value::Sequence(ref b)) => {
+            a.len() == b.len() && a.iter().zip(b).all(|(a, b)| equivalent(a, b))
+        }
+        (&Value::Mapping(ref a), &Value::Mapping(ref b)) => {
+            a.len() == b.len() && a.iter().all(|(k, v)| b.get(k).map_or(false, |w| equivalent(v, w)))
+        }
+        _ => a == b,
+    }
+}
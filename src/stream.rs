@@ -0,0 +1,230 @@
+// Copyright 2018 Serde YAML Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use serde::de::DeserializeOwned;
+
+use de;
+use error::Result;
+
+/// Reads a stream of documents that arrives in pieces, such as a chunked HTTP
+/// body or the output of a subprocess, and hands out each document as soon
+/// as all of it has arrived.
+///
+/// A document is known to be complete once the line that ends it has been
+/// fed: its `...` marker, or the `---` that starts the next document. The
+/// last document of the stream is complete once `finish` is called. Nothing
+/// is parsed before a document is complete, so feeding never fails.
+///
+/// ```rust
+/// # extern crate serde_yaml;
+/// #
+/// # fn main() {
+/// use serde_yaml::{Parser, Value};
+///
+/// let mut parser = Parser::new();
+/// parser.feed(b"---\nid: 1\n--");
+/// assert_eq!(parser.next_document::<Value>().unwrap(), None);
+///
+/// parser.feed(b"-\nid: 2\n");
+/// let first: Value = parser.next_document().unwrap().unwrap();
+/// assert_eq!(first["id"], 1);
+/// assert_eq!(parser.next_document::<Value>().unwrap(), None);
+///
+/// parser.finish();
+/// let second: Value = parser.next_document().unwrap().unwrap();
+/// assert_eq!(second["id"], 2);
+/// assert_eq!(parser.next_document::<Value>().unwrap(), None);
+/// # }
+/// ```
+///
+/// A document with nothing in it, like the first one of `---\n---\nid: 1\n`,
+/// deserializes from a null, as `from_str` reads it. With `skip_empty` it
+/// is passed over instead.
+///
+/// A document that fails to deserialize is dropped after its error has been
+/// returned, and the next call goes on with the document after it. Locations
+/// in such errors count lines from the start of the document, not of the
+/// stream.
+#[derive(Clone, Debug, Default)]
+pub struct Parser {
+    /// Input that has not been handed out, starting with the document being
+    /// collected.
+    buffer: Vec<u8>,
+    /// Offset in `buffer` of the first line that has not been looked at.
+    scanned: usize,
+    /// Whether the lines looked at so far hold a document, as opposed to
+    /// only blank lines, comments and directives.
+    content: bool,
+    /// Whether the document being collected has anything in it besides its
+    /// markers.
+    nonempty: bool,
+    skip_empty: bool,
+    finished: bool,
+}
+
+impl Parser {
+    /// Creates a parser that has not been fed anything.
+    pub fn new() -> Self {
+        Parser::default()
+    }
+
+    /// Passes over documents with nothing in them besides their markers,
+    /// comments and directives, rather than handing them out as null.
+    ///
+    /// ```rust
+    /// # extern crate serde_yaml;
+    /// #
+    /// # fn main() {
+    /// use serde_yaml::{Parser, Value};
+    ///
+    /// let mut parser = Parser::new().skip_empty(true);
+    /// parser.feed(b"---\n# nothing yet\n---\nid: 1\n...\n--- \n");
+    /// parser.finish();
+    /// let first: Value = parser.next_document().unwrap().unwrap();
+    /// assert_eq!(first["id"], 1);
+    /// assert_eq!(parser.next_document::<Value>().unwrap(), None);
+    /// # }
+    /// ```
+    pub fn skip_empty(mut self, skip: bool) -> Self {
+        self.skip_empty = skip;
+        self
+    }
+
+    /// Adds the next piece of input. It may end anywhere, even in the middle
+    /// of a line or of a UTF-8 character.
+    pub fn feed(&mut self, chunk: &[u8]) {
+        if !self.finished {
+            self.buffer.extend_from_slice(chunk);
+        }
+    }
+
+    /// Marks the end of the input, so that the last document counts as
+    /// complete. Input fed afterwards is ignored.
+    pub fn finish(&mut self) {
+        if !self.finished && !self.buffer.is_empty() && !self.buffer.ends_with(b"\n") {
+            // Ends the last line, so that it is looked at like the others.
+            self.buffer.push(b'\n');
+        }
+        self.finished = true;
+    }
+
+    /// Deserializes the next complete document, or returns `Ok(None)` if no
+    /// complete document is left in the input fed so far.
+    pub fn next_document<T>(&mut self) -> Result<Option<T>>
+        where T: DeserializeOwned
+    {
+        while let Some(newline) = self.buffer[self.scanned..].iter().position(|&b| b == b'\n') {
+            let start = self.scanned;
+            let end = start + newline + 1;
+            let line = Line::new(&self.buffer[start..end]);
+            self.scanned = end;
+            if line.starts_document() {
+                if self.content {
+                    // The marker belongs to the next document.
+                    match self.take(start, true, line.has_node_after_marker())? {
+                        Some(document) => return Ok(Some(document)),
+                        None => continue,
+                    }
+                }
+                self.content = true;
+                self.nonempty = line.has_node_after_marker();
+            } else if line.ends_document() {
+                if self.content {
+                    match self.take(end, false, false)? {
+                        Some(document) => return Ok(Some(document)),
+                        None => continue,
+                    }
+                }
+                self.drain(end);
+            } else if line.is_content() {
+                self.content = true;
+                self.nonempty = true;
+            }
+        }
+        if !self.finished {
+            return Ok(None);
+        }
+        let end = self.buffer.len();
+        if !self.content {
+            self.drain(end);
+            return Ok(None);
+        }
+        self.take(end, false, false)
+    }
+
+    /// Deserializes the document that ends at `end`, unless it is empty and
+    /// `skip_empty` is set, and removes it from the buffer. `content` and
+    /// `nonempty` describe what is left of the line at `end`.
+    fn take<T>(&mut self, end: usize, content: bool, nonempty: bool) -> Result<Option<T>>
+        where T: DeserializeOwned
+    {
+        let result = if self.nonempty || !self.skip_empty {
+            Some(de::from_slice(&self.buffer[..end]))
+        } else {
+            None
+        };
+        self.drain(end);
+        self.content = content;
+        self.nonempty = nonempty;
+        match result {
+            Some(result) => result.map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn drain(&mut self, end: usize) {
+        self.buffer.drain(..end);
+        self.scanned = self.scanned.saturating_sub(end);
+    }
+}
+
+/// One line of input, without its line break.
+struct Line<'a>(&'a [u8]);
+
+impl<'a> Line<'a> {
+    fn new(mut line: &'a [u8]) -> Self {
+        if line.ends_with(b"\n") {
+            line = &line[..line.len() - 1];
+        }
+        if line.ends_with(b"\r") {
+            line = &line[..line.len() - 1];
+        }
+        if line.starts_with(b"\xef\xbb\xbf") {
+            line = &line[3..];
+        }
+        Line(line)
+    }
+
+    fn is_marker(&self, marker: &[u8]) -> bool {
+        self.0.starts_with(marker) && self.0.get(3).map_or(true, |&b| b == b' ' || b == b'\t')
+    }
+
+    fn starts_document(&self) -> bool {
+        self.is_marker(b"---")
+    }
+
+    fn ends_document(&self) -> bool {
+        self.is_marker(b"...")
+    }
+
+    /// Whether a `---` line goes on with a node, as in `--- 1` or `--- |`.
+    fn has_node_after_marker(&self) -> bool {
+        Line(&self.0[3..]).is_content()
+    }
+
+    /// Whether the line is part of a document, rather than something that
+    /// may come between documents.
+    fn is_content(&self) -> bool {
+        match self.0.iter().find(|&&b| b != b' ' && b != b'\t') {
+            Some(&b'#') | None => false,
+            // Directives start in the first column.
+            Some(&b'%') => self.0[0] != b'%',
+            Some(_) => true,
+        }
+    }
+}
@@ -0,0 +1,217 @@
+// Copyright 2018 Serde YAML Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Styles asked for by single values while serializing.
+//!
+//! Options like `Writer::block_strings` apply to a whole document. Wrapping
+//! a value in `Styled` picks how that one value is written instead: a
+//! sequence or mapping in `Style::Flow` is written on one line in brackets,
+//! and a string in `Style::Literal` is written as a `|` block scalar. The
+//! adapters `with::flow` and `with::block_scalar` do the same for a field.
+//!
+//! ```rust
+//! #[macro_use]
+//! extern crate serde_derive;
+//! extern crate serde_yaml;
+//!
+//! use serde_yaml::style::{Style, Styled};
+//!
+//! #[derive(Serialize)]
+//! struct Job {
+//!     ports: Styled<Vec<u16>>,
+//!     script: Styled<String>,
+//! }
+//!
+//! # fn main() {
+//! let job = Job {
+//!     ports: Styled::new(Style::Flow, vec![80, 443]),
+//!     script: Styled::new(Style::Literal, "make\nmake test\n".to_owned()),
+//! };
+//! let expected = [
+//!     "---",
+//!     "ports: [80, 443]",
+//!     "script: |",
+//!     "  make",
+//!     "  make test",
+//!     "",
+//! ];
+//! assert_eq!(serde_yaml::to_string(&job).unwrap(), expected.join("\n"));
+//! # }
+//! ```
+//!
+//! With the `derive` feature, the `serde_yaml::styled` attribute asks for
+//! styles and comments field by field, with `#[yaml(flow)]`,
+//! `#[yaml(block_scalar)]` and `#[yaml(comment = "...")]`. It goes above
+//! `#[derive(Serialize)]` and leaves deserializing as it is.
+//!
+//! ```rust
+//! #[macro_use]
+//! extern crate serde_derive;
+//! extern crate serde_yaml;
+//! # extern crate serde_yaml_derive;
+//! # use serde_yaml_derive::styled;
+//!
+//! #[styled]
+//! #[derive(Serialize, Deserialize)]
+//! struct Job {
+//!     #[yaml(flow, comment = "Opened in the firewall.")]
+//!     ports: Vec<u16>,
+//!     #[yaml(block_scalar)]
+//!     script: String,
+//! }
+//!
+//! # fn main() {
+//! let job = Job { ports: vec![80, 443], script: "make\nmake test\n".to_owned() };
+//! let expected = [
+//!     "---",
+//!     "# Opened in the firewall.",
+//!     "ports: [80, 443]",
+//!     "script: |",
+//!     "  make",
+//!     "  make test",
+//!     "",
+//! ];
+//! assert_eq!(serde_yaml::to_string(&job).unwrap(), expected.join("\n"));
+//! # }
+//! ```
+//!
+//! A style that does not fit the value is left out: `Style::Flow` on a
+//! scalar, or `Style::Literal` on a string of one line or one that a block
+//! scalar cannot hold exactly, like one starting with a space. Other serializers see `Styled` as
+//! the value it holds. `Styled` has no `Deserialize` impl, since the text
+//! does not say which style was meant; fields that are read back use the
+//! adapters, which read the value as usual.
+
+use std::cell::Cell;
+use std::ops::{Deref, DerefMut};
+
+use serde::ser::{Serialize, Serializer};
+use yaml_rust::Yaml;
+
+use comment;
+use error::Result;
+use ser;
+
+/// The name of the newtype struct that `Styled` serializes as, so that this
+/// crate's serializer can recognize it.
+pub(crate) const TOKEN: &str = "$serde_yaml::private::Styled";
+
+/// How a single value is written.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Style {
+    /// A sequence or mapping on one line, like `[1, 2]` or `{a: 1}`.
+    Flow,
+    /// A string as a literal block scalar, one line of YAML per line of the
+    /// string.
+    Literal,
+}
+
+impl Style {
+    fn code(self) -> i64 {
+        match self {
+            Style::Flow => 0,
+            Style::Literal => 1,
+        }
+    }
+
+    fn from_code(code: i64) -> Option<Self> {
+        match code {
+            0 => Some(Style::Flow),
+            1 => Some(Style::Literal),
+            _ => None,
+        }
+    }
+}
+
+/// A value along with the style to write it in. See the module
+/// documentation.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Styled<T>(pub Style, pub T);
+
+impl<T> Styled<T> {
+    /// Asks for `value` to be written in `style`.
+    pub fn new(style: Style, value: T) -> Self {
+        Styled(style, value)
+    }
+
+    /// The style.
+    pub fn style(&self) -> Style {
+        self.0
+    }
+
+    /// Unwraps the value.
+    pub fn into_inner(self) -> T {
+        self.1
+    }
+}
+
+impl<T> Deref for Styled<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.1
+    }
+}
+
+impl<T> DerefMut for Styled<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.1
+    }
+}
+
+impl<T> Serialize for Styled<T>
+    where T: Serialize
+{
+    fn serialize<S>(&self, serializer: S) -> ::std::result::Result<S::Ok, S::Error>
+        where S: Serializer
+    {
+        PENDING.with(|pending| pending.set(Some(self.0)));
+        let result = serializer.serialize_newtype_struct(TOKEN, &self.1);
+        // Other serializers leave the style where it was put.
+        PENDING.with(|pending| pending.set(None));
+        result
+    }
+}
+
+thread_local! {
+    /// The style of the `Styled` being serialized, until this crate's
+    /// serializer takes it.
+    static PENDING: Cell<Option<Style>> = const { Cell::new(None) };
+}
+
+/// Serializes the value inside a `Styled`, wrapped in a node that carries
+/// the style if it has somewhere to go.
+pub(crate) fn serialize_node<T: ?Sized>(value: &T) -> Result<Yaml>
+    where T: Serialize
+{
+    let style = PENDING.with(|pending| pending.take());
+    let node = value.serialize(ser::Serializer)?;
+    match style {
+        Some(style) if comment::in_document() => {
+            Ok(Yaml::Array(vec![Yaml::BadValue, Yaml::Integer(style.code()), node]))
+        }
+        _ => Ok(node),
+    }
+}
+
+/// The style asked for by a node built by `serialize_node`, looking through
+/// the comments around it.
+pub(crate) fn of(mut node: &Yaml) -> Option<Style> {
+    loop {
+        match *node {
+            Yaml::Array(ref parts) => {
+                match parts.as_slice() {
+                    [Yaml::BadValue, Yaml::Integer(code), _] => return Style::from_code(*code),
+                    [Yaml::BadValue, Yaml::String(_), inner] => node = inner,
+                    _ => return None,
+                }
+            }
+            _ => return None,
+        }
+    }
+}
@@ -0,0 +1,163 @@
+// Copyright 2018 Serde YAML Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::borrow::Cow;
+
+use error::{Error, Location, Result};
+
+/// What to do with tab characters in the indentation of a line.
+///
+/// YAML only allows spaces for indentation, but tabs make their way into
+/// hand-written files anyway. The parser does not reject them; it reads a
+/// line indented by a tab as a scalar that starts with the tab, so
+/// `"a:\n\tb: 1"` silently becomes two top-level keys `a` and `"\tb"`.
+///
+/// Only the whitespace at the start of a line is looked at, and not in the
+/// content of block scalars, where a tab after the indentation belongs to the
+/// string.
+///
+/// ```rust
+/// # extern crate serde;
+/// # extern crate serde_yaml;
+/// #
+/// # fn main() {
+/// use serde::Deserialize;
+/// use serde_yaml::{Deserializer, Tabs, Value};
+///
+/// let yaml = "a:\n\tb: 1";
+/// let err = Value::deserialize(Deserializer::from_str(yaml).tabs(Tabs::Reject)).unwrap_err();
+/// assert_eq!(err.to_string(), "found a tab character in indentation at line 2 column 1");
+///
+/// let value = Value::deserialize(Deserializer::from_str(yaml).tabs(Tabs::Expand(2))).unwrap();
+/// assert_eq!(value["a"]["b"], 1);
+/// # }
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Tabs {
+    /// Hand tabs to the parser as they are. This is the default.
+    Keep,
+    /// Fail with the location of the first tab in indentation.
+    Reject,
+    /// Replace tabs in indentation by spaces up to the next multiple of this
+    /// many columns before parsing. Locations in errors refer to the text
+    /// after the replacement.
+    Expand(usize),
+}
+
+impl Default for Tabs {
+    fn default() -> Self {
+        Tabs::Keep
+    }
+}
+
+/// Applies the policy to the whole input.
+pub(crate) fn apply(s: &str, tabs: Tabs) -> Result<Cow<str>> {
+    if tabs == Tabs::Keep || !s.contains('\t') {
+        return Ok(Cow::Borrowed(s));
+    }
+    let mut out = String::with_capacity(s.len());
+    // Character index of the start of the next line.
+    let mut index = 0;
+    // The block scalar being read: the indentation of the line that
+    // introduced it, and of its content once known.
+    let mut block_scalar: Option<(usize, Option<usize>)> = None;
+    for (number, line) in s.split('\n').enumerate() {
+        if number > 0 {
+            out.push('\n');
+        }
+        let start = index;
+        index += line.chars().count() + 1;
+        if let Some((parent, content)) = block_scalar {
+            if line.trim().is_empty() {
+                out.push_str(line);
+                continue;
+            }
+            let line = match tabs {
+                Tabs::Expand(width) => expand(line, width, content),
+                _ => Cow::Borrowed(line),
+            };
+            let indent = indent_of(&line);
+            if indent > parent {
+                block_scalar = Some((parent, Some(content.unwrap_or(indent))));
+                out.push_str(&line);
+                continue;
+            }
+            block_scalar = None;
+        }
+        let content = line.trim_start_matches(&[' ', '\t'][..]);
+        if content.is_empty() {
+            out.push_str(line);
+            continue;
+        }
+        let line = match tabs {
+            Tabs::Expand(width) => expand(line, width, None),
+            _ => {
+                if let Some(col) = line[..line.len() - content.len()].find('\t') {
+                    let location = Location::new(start + col, number + 1, col + 1);
+                    return Err(Error::at("found a tab character in indentation", location));
+                }
+                Cow::Borrowed(line)
+            }
+        };
+        if starts_block_scalar(content) {
+            block_scalar = Some((indent_of(&line), None));
+        }
+        out.push_str(&line);
+    }
+    match tabs {
+        Tabs::Expand(_) => Ok(Cow::Owned(out)),
+        _ => Ok(Cow::Borrowed(s)),
+    }
+}
+
+/// Replaces tabs at the start of `line` by spaces up to the next multiple of
+/// `width` columns, stopping at column `limit` if given.
+fn expand(line: &str, width: usize, limit: Option<usize>) -> Cow<str> {
+    let width = width.max(1);
+    let mut expanded = String::new();
+    for (i, c) in line.char_indices() {
+        if limit.map_or(false, |limit| expanded.len() >= limit) {
+            expanded.push_str(&line[i..]);
+            return Cow::Owned(expanded);
+        }
+        match c {
+            ' ' => expanded.push(' '),
+            '\t' => {
+                let next = (expanded.len() / width + 1) * width;
+                while expanded.len() < next {
+                    expanded.push(' ');
+                }
+            }
+            _ => {
+                expanded.push_str(&line[i..]);
+                return Cow::Owned(expanded);
+            }
+        }
+    }
+    Cow::Owned(expanded)
+}
+
+fn indent_of(line: &str) -> usize {
+    line.chars().take_while(|&c| c == ' ').count()
+}
+
+/// Whether the content of a line, without its indentation, ends with the
+/// header of a block scalar like `|`, `>-` or `|2`.
+fn starts_block_scalar(content: &str) -> bool {
+    let content = match content.find(" #") {
+        Some(comment) => &content[..comment],
+        None => content,
+    };
+    let content = content.trim_end();
+    let header = content.trim_end_matches(|c: char| c == '+' || c == '-' || c.is_ascii_digit());
+    if !(header.ends_with('|') || header.ends_with('>')) {
+        return false;
+    }
+    let before = &header[..header.len() - 1];
+    before.is_empty() || before.ends_with(' ') || before.ends_with('\t')
+}
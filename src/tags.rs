@@ -0,0 +1,195 @@
+// Copyright 2018 Serde YAML Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+#[cfg(feature = "tags")]
+use std::collections::BTreeMap;
+
+use yaml_rust::Yaml;
+use yaml_rust::scanner::TokenType;
+
+/// Decides how tagged scalars are presented to `Deserialize` impls.
+///
+/// The core tags `!!str`, `!!int`, `!!float`, `!!bool` and `!!null` are always
+/// understood and are not affected by the policy. Every other tag is looked
+/// up by the way it is written in the document, like `!!binary` or `!point`.
+/// Tags on sequences and mappings are not reported by the parser and so are
+/// always ignored.
+///
+/// The default policy ignores all tags, passing the scalar on as if it were
+/// untagged. Data formats with a notion of byte strings, like CBOR or
+/// MessagePack, lose information that way when fed by `serde_transcode`; use
+/// `TagPolicy::binary()` or configure the tags explicitly.
+///
+/// ```rust
+/// # extern crate serde;
+/// # extern crate serde_yaml;
+/// #
+/// # fn main() {
+/// use serde::Deserialize;
+/// use serde_yaml::{Deserializer, TagAction, TagPolicy, Value};
+///
+/// let policy = TagPolicy::new().tag("!secret", TagAction::Error);
+/// let de = Deserializer::from_str("password: !secret hunter2").tag_policy(policy);
+/// assert!(Value::deserialize(de).is_err());
+/// # }
+/// ```
+#[cfg(feature = "tags")]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TagPolicy {
+    tags: BTreeMap<String, TagAction>,
+    unknown: TagAction,
+}
+
+/// Without the `tags` feature, the policy that ignores all tags.
+#[cfg(not(feature = "tags"))]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TagPolicy;
+
+/// What to do with a scalar carrying some tag.
+#[cfg_attr(not(feature = "tags"), allow(dead_code))]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum TagAction {
+    /// Deserialize the scalar as if it had no tag.
+    Ignore,
+    /// Decode the scalar as base64 and deserialize it as a byte buffer.
+    Base64,
+    /// Deserialize the scalar as a mapping with one entry, from the tag to the
+    /// untagged scalar. This is how Serde represents externally tagged enums,
+    /// so `!point 1,2` deserializes into a variant renamed to `!point`.
+    Wrap,
+    /// Refuse to deserialize the scalar.
+    Error,
+}
+
+impl Default for TagAction {
+    fn default() -> Self {
+        TagAction::Ignore
+    }
+}
+
+#[cfg(feature = "tags")]
+impl TagPolicy {
+    /// The policy that ignores all tags.
+    pub fn new() -> Self {
+        TagPolicy::default()
+    }
+
+    /// The policy that decodes `!!binary` scalars into byte buffers and
+    /// ignores all other tags.
+    pub fn binary() -> Self {
+        TagPolicy::new().tag("!!binary", TagAction::Base64)
+    }
+
+    /// Set the action for scalars with the given tag.
+    pub fn tag<S>(mut self, tag: S, action: TagAction) -> Self
+        where S: Into<String>
+    {
+        self.tags.insert(tag.into(), action);
+        self
+    }
+
+    /// Set the action for scalars with any tag not configured by `tag`.
+    pub fn unknown(mut self, action: TagAction) -> Self {
+        self.unknown = action;
+        self
+    }
+
+    /// The action for a scalar with the given tag from the parser, along with
+    /// the tag as written. Returns `None` for untagged scalars, core tags and
+    /// ignored tags.
+    pub(crate) fn action(&self, tag: &Option<Box<TokenType>>) -> Option<(String, TagAction)> {
+        let (handle, suffix) = match tag.as_ref().map(|tag| &**tag) {
+            Some(&TokenType::Tag(ref handle, ref suffix)) => (handle, suffix),
+            _ => return None,
+        };
+        if handle == "!!" {
+            match suffix.as_ref() {
+                "str" | "int" | "float" | "bool" | "null" => return None,
+                _ => {}
+            }
+        }
+        let tag = format!("{}{}", handle, suffix);
+        match self.tags.get(&tag).cloned().unwrap_or(self.unknown) {
+            TagAction::Ignore => None,
+            action => Some((tag, action)),
+        }
+    }
+}
+
+#[cfg(not(feature = "tags"))]
+impl TagPolicy {
+    pub(crate) fn action(&self, _tag: &Option<Box<TokenType>>) -> Option<(String, TagAction)> {
+        None
+    }
+}
+
+/// Decodes standard base64, skipping whitespace. Returns `None` if the input
+/// is not valid base64.
+pub(crate) fn decode_base64(text: &str) -> Option<Vec<u8>> {
+    let mut bytes = Vec::with_capacity(text.len() * 3 / 4);
+    let mut buffer = 0u32;
+    let mut bits = 0;
+    let mut padding = 0;
+    for c in text.chars().filter(|c| !c.is_whitespace()) {
+        let digit = match c {
+            'A'..='Z' => c as u32 - 'A' as u32,
+            'a'..='z' => c as u32 - 'a' as u32 + 26,
+            '0'..='9' => c as u32 - '0' as u32 + 52,
+            '+' => 62,
+            '/' => 63,
+            '=' => {
+                padding += 1;
+                continue;
+            }
+            _ => return None,
+        };
+        if padding > 0 {
+            return None;
+        }
+        buffer = buffer << 6 | digit;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((buffer >> bits) as u8);
+            buffer &= (1 << bits) - 1;
+        }
+    }
+    if padding > 2 || buffer != 0 {
+        return None;
+    }
+    Some(bytes)
+}
+
+/// Encodes bytes as standard base64 with padding.
+pub(crate) fn encode_base64(bytes: &[u8]) -> String {
+    const DIGITS: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut text = String::with_capacity(bytes.len() / 3 * 4 + 4);
+    for chunk in bytes.chunks(3) {
+        let buffer = chunk.iter().fold(0u32, |buffer, &b| buffer << 8 | u32::from(b));
+        let buffer = buffer << (8 * (3 - chunk.len()));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                text.push(DIGITS[(buffer >> (18 - 6 * i) & 63) as usize] as char);
+            } else {
+                text.push('=');
+            }
+        }
+    }
+    text
+}
+
+/// The node of a mapping key serialized from bytes, which is written as a
+/// `!!binary` scalar. The emitter writes reals as they are, tag included.
+pub(crate) fn binary_key(bytes: &[u8]) -> Yaml {
+    Yaml::Real(format!("!!binary {}", encode_base64(bytes)))
+}
+
+/// The base64 text of a real built by `binary_key`.
+pub(crate) fn binary_key_text(real: &str) -> Option<&str> {
+    real.strip_prefix("!!binary ")
+}
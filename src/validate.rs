@@ -0,0 +1,228 @@
+// Copyright 2018 Serde YAML Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::collections::HashMap;
+use std::fmt::Display;
+
+use yaml_rust::parser::{Event, Parser};
+use yaml_rust::scanner::Marker;
+
+use error::{Error, Location, Result};
+
+/// Limits that `validate_with` enforces while reading a stream.
+///
+/// The default limits are generous for configuration files and stop the
+/// inputs that are cheap to send but expensive to load: documents nested
+/// more deeply than 128 collections, and aliases that expand to more than a
+/// million nodes, as in the "billion laughs" attack.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Limits {
+    max_documents: usize,
+    max_nodes: usize,
+    max_expanded_nodes: u64,
+    max_depth: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits {
+            max_documents: usize::MAX,
+            max_nodes: usize::MAX,
+            max_expanded_nodes: 1_000_000,
+            max_depth: 128,
+        }
+    }
+}
+
+impl Limits {
+    /// The default limits.
+    pub fn new() -> Self {
+        Limits::default()
+    }
+
+    /// How many documents the stream may hold. Not limited by default.
+    pub fn max_documents(mut self, max: usize) -> Self {
+        self.max_documents = max;
+        self
+    }
+
+    /// How many nodes the text of the stream may hold, counting every scalar,
+    /// collection and alias. Not limited by default.
+    pub fn max_nodes(mut self, max: usize) -> Self {
+        self.max_nodes = max;
+        self
+    }
+
+    /// How many nodes the documents of the stream may hold once every alias
+    /// is replaced by the node it refers to, which is what deserializing them
+    /// builds. A million by default.
+    pub fn max_expanded_nodes(mut self, max: u64) -> Self {
+        self.max_expanded_nodes = max;
+        self
+    }
+
+    /// How many collections may be nested in one another. 128 by default.
+    pub fn max_depth(mut self, max: usize) -> Self {
+        self.max_depth = max;
+        self
+    }
+}
+
+/// What `validate` found in a stream.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct DocumentStats {
+    documents: usize,
+    nodes: usize,
+    expanded_nodes: u64,
+    anchors: usize,
+    aliases: usize,
+    max_depth: usize,
+}
+
+impl DocumentStats {
+    /// The number of documents in the stream.
+    pub fn documents(&self) -> usize {
+        self.documents
+    }
+
+    /// The number of scalars, collections and aliases in the text.
+    pub fn nodes(&self) -> usize {
+        self.nodes
+    }
+
+    /// The number of nodes once every alias is replaced by the node it refers
+    /// to.
+    pub fn expanded_nodes(&self) -> u64 {
+        self.expanded_nodes
+    }
+
+    /// The number of nodes with an anchor.
+    pub fn anchors(&self) -> usize {
+        self.anchors
+    }
+
+    /// The number of aliases.
+    pub fn aliases(&self) -> usize {
+        self.aliases
+    }
+
+    /// The deepest nesting of collections: 0 for a stream of scalars, 1 if
+    /// the deepest collection holds only scalars, and so on.
+    pub fn max_depth(&self) -> usize {
+        self.max_depth
+    }
+}
+
+/// Checks that a string is well-formed YAML within the default `Limits`,
+/// without building any values, and returns what the stream holds.
+///
+/// This is meant to run before deserializing input from untrusted sources.
+/// Memory use does not grow with the size of the input beyond the largest
+/// scalar and one counter per anchor of the current document. Whether the
+/// documents have the shape some type expects is not checked.
+///
+/// ```rust
+/// # extern crate serde_yaml;
+/// #
+/// # fn main() {
+/// let stats = serde_yaml::validate("a: &x [1, 2]\nb: *x\n--- c").unwrap();
+/// assert_eq!(stats.documents(), 2);
+/// assert_eq!(stats.nodes(), 8);
+/// assert_eq!(stats.expanded_nodes(), 10);
+/// assert_eq!((stats.anchors(), stats.aliases()), (1, 1));
+/// assert_eq!(stats.max_depth(), 2);
+///
+/// let laughs = "a: &a [x, x]\nb: &b [*a, *a]\nc: &c [*b, *b]\nd: [*c, *c]";
+/// let limits = serde_yaml::Limits::new().max_expanded_nodes(20);
+/// let err = serde_yaml::validate_with(laughs, &limits).unwrap_err();
+/// assert_eq!(err.to_string(), "documents expand to more than 20 nodes at line 3 column 8");
+/// # }
+/// ```
+pub fn validate(s: &str) -> Result<DocumentStats> {
+    validate_with(s, &Limits::default())
+}
+
+/// Like `validate` but with the given limits.
+pub fn validate_with(s: &str, limits: &Limits) -> Result<DocumentStats> {
+    let mut parser = Parser::new(s.chars());
+    let mut stats = DocumentStats::default();
+    // The anchor and expanded size so far of every open collection.
+    let mut open: Vec<(usize, u64)> = Vec::new();
+    // The expanded size of every anchored node of the current document.
+    let mut anchored: HashMap<usize, u64> = HashMap::new();
+    loop {
+        let (event, marker) = parser.next().map_err(|err| Error::scanner_in(err, s))?;
+        // The expanded size and anchor of a node that ends with this event.
+        let (size, anchor) = match event {
+            Event::StreamEnd => return Ok(stats),
+            Event::DocumentStart => {
+                stats.documents += 1;
+                if stats.documents > limits.max_documents {
+                    return Err(exceeded(format_args!("more than {} documents", limits.max_documents),
+                                        marker));
+                }
+                anchored.clear();
+                continue;
+            }
+            Event::SequenceStart(anchor) | Event::MappingStart(anchor) => {
+                count_node(&mut stats, limits, marker)?;
+                open.push((anchor, 1));
+                stats.max_depth = stats.max_depth.max(open.len());
+                if open.len() > limits.max_depth {
+                    return Err(exceeded(format_args!("collections nested more than {} deep",
+                                                     limits.max_depth),
+                                        marker));
+                }
+                continue;
+            }
+            Event::SequenceEnd | Event::MappingEnd => {
+                match open.pop() {
+                    Some((anchor, size)) => (size, anchor),
+                    None => continue,
+                }
+            }
+            Event::Scalar(_, _, anchor, _) => {
+                count_node(&mut stats, limits, marker)?;
+                (1, anchor)
+            }
+            Event::Alias(id) => {
+                count_node(&mut stats, limits, marker)?;
+                stats.aliases += 1;
+                (anchored.get(&id).cloned().unwrap_or(1), 0)
+            }
+            _ => continue,
+        };
+        if anchor != 0 {
+            stats.anchors += 1;
+            anchored.insert(anchor, size);
+        }
+        match open.last_mut() {
+            Some(parent) => parent.1 = parent.1.saturating_add(size),
+            None => stats.expanded_nodes = stats.expanded_nodes.saturating_add(size),
+        }
+        // Each open collection holds the nodes in it that have ended.
+        let total = open.iter().fold(stats.expanded_nodes, |total, &(_, size)| total.saturating_add(size));
+        if total > limits.max_expanded_nodes {
+            return Err(exceeded(format_args!("documents expand to more than {} nodes",
+                                             limits.max_expanded_nodes),
+                                marker));
+        }
+    }
+}
+
+fn count_node(stats: &mut DocumentStats, limits: &Limits, marker: Marker) -> Result<()> {
+    stats.nodes += 1;
+    if stats.nodes > limits.max_nodes {
+        return Err(exceeded(format_args!("more than {} nodes", limits.max_nodes), marker));
+    }
+    Ok(())
+}
+
+fn exceeded<T: Display>(msg: T, marker: Marker) -> Error {
+    Error::at(msg, Location::from_marker(&marker))
+}
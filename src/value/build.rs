@@ -0,0 +1,119 @@
+// Copyright 2018 Serde YAML Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use super::{Sequence, Value};
+use mapping::Mapping;
+
+impl Value {
+    /// An empty mapping.
+    pub fn new_mapping() -> Self {
+        Value::Mapping(Mapping::new())
+    }
+
+    /// An empty sequence.
+    pub fn new_sequence() -> Self {
+        Value::Sequence(Sequence::new())
+    }
+
+    /// Starts building a mapping one entry at a time. Keys and values are
+    /// anything that converts into a `Value`, including other builders.
+    ///
+    /// ```rust
+    /// # extern crate serde_yaml;
+    /// #
+    /// # fn main() {
+    /// use serde_yaml::Value;
+    ///
+    /// let service = Value::mapping()
+    ///     .insert("name", "web")
+    ///     .insert("ports", [80, 443])
+    ///     .insert("env", Value::mapping().insert("DEBUG", false))
+    ///     .build();
+    /// assert_eq!(service, serde_yaml::from_str::<Value>("
+    ///     name: web
+    ///     ports: [80, 443]
+    ///     env: {DEBUG: false}
+    /// ").unwrap());
+    /// # }
+    /// ```
+    pub fn mapping() -> MappingBuilder {
+        MappingBuilder { mapping: Mapping::new() }
+    }
+
+    /// Starts building a sequence one element at a time.
+    ///
+    /// ```rust
+    /// # extern crate serde_yaml;
+    /// #
+    /// # fn main() {
+    /// use serde_yaml::Value;
+    ///
+    /// let args = Value::sequence().push("--port").push(80).push(Value::Null).build();
+    /// assert_eq!(args, serde_yaml::from_str::<Value>("[--port, 80, ~]").unwrap());
+    /// # }
+    /// ```
+    pub fn sequence() -> SequenceBuilder {
+        SequenceBuilder { sequence: Sequence::new() }
+    }
+}
+
+/// Builds a mapping `Value`. Returned by `Value::mapping`.
+#[derive(Clone, Debug, Default)]
+pub struct MappingBuilder {
+    mapping: Mapping,
+}
+
+impl MappingBuilder {
+    /// Adds an entry. A key that is already there gets the new value and
+    /// moves to the end, as with `Mapping::insert`.
+    pub fn insert<K, V>(mut self, k: K, v: V) -> Self
+        where K: Into<Value>,
+              V: Into<Value>
+    {
+        self.mapping.insert(k.into(), v.into());
+        self
+    }
+
+    /// The mapping built so far.
+    pub fn build(self) -> Value {
+        Value::Mapping(self.mapping)
+    }
+}
+
+impl From<MappingBuilder> for Value {
+    fn from(builder: MappingBuilder) -> Self {
+        builder.build()
+    }
+}
+
+/// Builds a sequence `Value`. Returned by `Value::sequence`.
+#[derive(Clone, Debug, Default)]
+pub struct SequenceBuilder {
+    sequence: Sequence,
+}
+
+impl SequenceBuilder {
+    /// Adds an element at the end.
+    pub fn push<V>(mut self, v: V) -> Self
+        where V: Into<Value>
+    {
+        self.sequence.push(v.into());
+        self
+    }
+
+    /// The sequence built so far.
+    pub fn build(self) -> Value {
+        Value::Sequence(self.sequence)
+    }
+}
+
+impl From<SequenceBuilder> for Value {
+    fn from(builder: SequenceBuilder) -> Self {
+        builder.build()
+    }
+}
@@ -7,6 +7,8 @@ use serde::de::{
     Deserializer,
     EnumAccess,
     Error as SError,
+    Expected,
+    IntoDeserializer,
     MapAccess,
     SeqAccess,
     Unexpected,
@@ -14,7 +16,8 @@ use serde::de::{
     Visitor,
 };
 
-use super::Value;
+use super::{Sequence, Value};
+use number::{self, Number};
 use mapping::Mapping;
 use error::Error;
 
@@ -85,6 +88,12 @@ impl<'de> Deserialize<'de> for Value {
                 Deserialize::deserialize(deserializer)
             }
 
+            fn visit_newtype_struct<D>(self, deserializer: D) -> Result<Value, D::Error>
+                where D: Deserializer<'de>
+            {
+                deserializer.deserialize_any(ValueVisitor)
+            }
+
             fn visit_seq<V>(self, mut visitor: V) -> Result<Value, V::Error>
                 where V: SeqAccess<'de>
             {
@@ -102,6 +111,14 @@ impl<'de> Deserialize<'de> for Value {
             {
                 let mut values = Mapping::new();
 
+                match visitor.next_key_seed(KeySeed)? {
+                    Some(Key::Number) => return visitor.next_value_seed(NumberSeed).map(Value::Number),
+                    Some(Key::Value(key)) => {
+                        values.insert(key, visitor.next_value()?);
+                    }
+                    None => return Ok(Value::Mapping(values)),
+                }
+
                 while let Some((key, value)) = visitor.next_entry()? {
                     values.insert(key, value);
                 }
@@ -110,7 +127,139 @@ impl<'de> Deserialize<'de> for Value {
             }
         }
 
-        deserializer.deserialize_any(ValueVisitor)
+        /// The first key of a map, which is `number::TOKEN` if the map stands
+        /// for a number whose conversion was put off.
+        enum Key {
+            Number,
+            Value(Value),
+        }
+
+        /// Looks for `number::TOKEN` without allocating; any other key is
+        /// built the way `ValueVisitor` would build it.
+        struct KeySeed;
+
+        impl<'de> DeserializeSeed<'de> for KeySeed {
+            type Value = Key;
+
+            fn deserialize<D>(self, deserializer: D) -> Result<Key, D::Error>
+                where D: Deserializer<'de>
+            {
+                deserializer.deserialize_newtype_struct(number::TOKEN, self)
+            }
+        }
+
+        impl<'de> Visitor<'de> for KeySeed {
+            type Value = Key;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                ValueVisitor.expecting(formatter)
+            }
+
+            fn visit_bool<E>(self, b: bool) -> Result<Key, E>
+                where E: SError,
+            {
+                ValueVisitor.visit_bool(b).map(Key::Value)
+            }
+
+            fn visit_i64<E>(self, i: i64) -> Result<Key, E>
+                where E: SError,
+            {
+                ValueVisitor.visit_i64(i).map(Key::Value)
+            }
+
+            fn visit_u64<E>(self, u: u64) -> Result<Key, E>
+                where E: SError,
+            {
+                ValueVisitor.visit_u64(u).map(Key::Value)
+            }
+
+            fn visit_f64<E>(self, f: f64) -> Result<Key, E>
+                where E: SError,
+            {
+                ValueVisitor.visit_f64(f).map(Key::Value)
+            }
+
+            fn visit_str<E>(self, s: &str) -> Result<Key, E>
+                where E: SError,
+            {
+                if s == number::TOKEN && number::is_lazy_key() {
+                    Ok(Key::Number)
+                } else {
+                    ValueVisitor.visit_str(s).map(Key::Value)
+                }
+            }
+
+            fn visit_string<E>(self, s: String) -> Result<Key, E>
+                where E: SError,
+            {
+                self.visit_str(&s)
+            }
+
+            fn visit_unit<E>(self) -> Result<Key, E>
+                where E: SError,
+            {
+                ValueVisitor.visit_unit().map(Key::Value)
+            }
+
+            fn visit_none<E>(self) -> Result<Key, E>
+                where E: SError,
+            {
+                ValueVisitor.visit_none().map(Key::Value)
+            }
+
+            fn visit_some<D>(self, deserializer: D) -> Result<Key, D::Error>
+                where D: Deserializer<'de>
+            {
+                ValueVisitor.visit_some(deserializer).map(Key::Value)
+            }
+
+            fn visit_newtype_struct<D>(self, deserializer: D) -> Result<Key, D::Error>
+                where D: Deserializer<'de>
+            {
+                ValueVisitor.visit_newtype_struct(deserializer).map(Key::Value)
+            }
+
+            fn visit_seq<V>(self, visitor: V) -> Result<Key, V::Error>
+                where V: SeqAccess<'de>
+            {
+                ValueVisitor.visit_seq(visitor).map(Key::Value)
+            }
+
+            fn visit_map<V>(self, visitor: V) -> Result<Key, V::Error>
+                where V: MapAccess<'de>
+            {
+                ValueVisitor.visit_map(visitor).map(Key::Value)
+            }
+        }
+
+        /// Keeps the text of a number to convert later.
+        struct NumberSeed;
+
+        impl<'de> DeserializeSeed<'de> for NumberSeed {
+            type Value = Number;
+
+            fn deserialize<D>(self, deserializer: D) -> Result<Number, D::Error>
+                where D: Deserializer<'de>
+            {
+                deserializer.deserialize_str(self)
+            }
+        }
+
+        impl<'de> Visitor<'de> for NumberSeed {
+            type Value = Number;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("the text of a number")
+            }
+
+            fn visit_str<E>(self, s: &str) -> Result<Number, E>
+                where E: SError,
+            {
+                Number::lazy(s).ok_or_else(|| E::invalid_value(Unexpected::Str(s), &self))
+            }
+        }
+
+        deserializer.deserialize_newtype_struct(number::TOKEN, ValueVisitor)
     }
 }
 
@@ -209,12 +358,37 @@ impl<'de> Deserializer<'de> for Value {
     }
 
     forward_to_deserialize_any! {
-        bool i8 i16 i32 i64 u8 u16 u32 u64 f32 f64 char str string bytes
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes
         byte_buf unit unit_struct seq tuple tuple_struct map struct identifier
         ignored_any
     }
 }
 
+impl<'de> IntoDeserializer<'de, Error> for Value {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+/// Deserializes a clone of the value, as `Value` only deserializes by value.
+impl<'de, 'a> IntoDeserializer<'de, Error> for &'a Value {
+    type Deserializer = Value;
+
+    fn into_deserializer(self) -> Value {
+        self.clone()
+    }
+}
+
+impl<'de> IntoDeserializer<'de, Error> for Mapping {
+    type Deserializer = Value;
+
+    fn into_deserializer(self) -> Value {
+        Value::Mapping(self)
+    }
+}
+
 struct EnumDeserializer {
     variant: Value,
     value: Option<Value>,
@@ -321,7 +495,7 @@ impl<'de> Deserializer<'de> for SeqDeserializer {
     }
 
     forward_to_deserialize_any! {
-        bool i8 i16 i32 i64 u8 u16 u32 u64 f32 f64 char str string bytes
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes
         byte_buf option unit unit_struct newtype_struct seq tuple tuple_struct
         map struct enum identifier ignored_any
     }
@@ -404,13 +578,108 @@ impl<'de> Deserializer<'de> for MapDeserializer {
     }
 
     forward_to_deserialize_any! {
-        bool i8 i16 i32 i64 u8 u16 u32 u64 f32 f64 char str string bytes
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes
         byte_buf option unit unit_struct newtype_struct seq tuple tuple_struct
         map struct enum identifier ignored_any
     }
 }
 
 impl Value {
+    /// Takes the boolean out of the `Value`, or fails with an error that says
+    /// what the value is instead.
+    ///
+    /// ```rust
+    /// # use serde_yaml::Value;
+    /// let v: Value = serde_yaml::from_str("[true]").unwrap();
+    /// let err = v.try_into_bool().unwrap_err();
+    /// assert_eq!(err.to_string(), "invalid type: sequence, expected a boolean");
+    /// ```
+    pub fn try_into_bool(self) -> Result<bool, Error> {
+        match self {
+            Value::Bool(b) => Ok(b),
+            other => Err(other.invalid_type(&"a boolean")),
+        }
+    }
+
+    /// Takes the integer out of the `Value` if it fits in an `i64`, or fails
+    /// with an error that says what the value is instead.
+    ///
+    /// ```rust
+    /// # use serde_yaml::Value;
+    /// let v: Value = serde_yaml::from_str("1.5").unwrap();
+    /// let err = v.try_into_i64().unwrap_err();
+    /// assert_eq!(err.to_string(), "invalid value: floating point `1.5`, expected an i64");
+    /// ```
+    pub fn try_into_i64(self) -> Result<i64, Error> {
+        match self {
+            Value::Number(ref n) => n.as_i64().ok_or_else(|| Error::invalid_value(n.unexpected(), &"an i64")),
+            other => Err(other.invalid_type(&"an i64")),
+        }
+    }
+
+    /// Takes the integer out of the `Value` if it fits in a `u64`, or fails
+    /// with an error that says what the value is instead.
+    pub fn try_into_u64(self) -> Result<u64, Error> {
+        match self {
+            Value::Number(ref n) => n.as_u64().ok_or_else(|| Error::invalid_value(n.unexpected(), &"a u64")),
+            other => Err(other.invalid_type(&"a u64")),
+        }
+    }
+
+    /// Takes the number out of the `Value` as an `f64`, or fails with an
+    /// error that says what the value is instead.
+    pub fn try_into_f64(self) -> Result<f64, Error> {
+        match self {
+            Value::Number(ref n) => n.as_f64().ok_or_else(|| Error::invalid_value(n.unexpected(), &"an f64")),
+            other => Err(other.invalid_type(&"an f64")),
+        }
+    }
+
+    /// Takes the string out of the `Value`, or fails with an error that says
+    /// what the value is instead.
+    ///
+    /// ```rust
+    /// # use serde_yaml::Value;
+    /// let v: Value = serde_yaml::from_str("name: web").unwrap();
+    /// let err = v.try_into_string().unwrap_err();
+    /// assert_eq!(err.to_string(), "invalid type: map, expected a string");
+    /// ```
+    pub fn try_into_string(self) -> Result<String, Error> {
+        match self {
+            Value::String(s) => Ok(s),
+            other => Err(other.invalid_type(&"a string")),
+        }
+    }
+
+    /// Takes the sequence out of the `Value`, or fails with an error that
+    /// says what the value is instead.
+    pub fn try_into_sequence(self) -> Result<Sequence, Error> {
+        match self {
+            Value::Sequence(seq) => Ok(seq),
+            other => Err(other.invalid_type(&"a sequence")),
+        }
+    }
+
+    /// Takes the mapping out of the `Value`, or fails with an error that says
+    /// what the value is instead.
+    ///
+    /// ```rust
+    /// # use serde_yaml::Value;
+    /// let v: Value = serde_yaml::from_str("'80'").unwrap();
+    /// let err = v.try_into_mapping().unwrap_err();
+    /// assert_eq!(err.to_string(), "invalid type: string \"80\", expected a mapping");
+    /// ```
+    pub fn try_into_mapping(self) -> Result<Mapping, Error> {
+        match self {
+            Value::Mapping(map) => Ok(map),
+            other => Err(other.invalid_type(&"a mapping")),
+        }
+    }
+
+    fn invalid_type(&self, exp: &dyn Expected) -> Error {
+        Error::invalid_type(self.unexpected(), exp)
+    }
+
     fn unexpected(&self) -> Unexpected {
         match *self {
             Value::Null => Unexpected::Unit,
@@ -0,0 +1,114 @@
+// Copyright 2018 Serde YAML Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::fmt::{self, Debug};
+
+use mapping::Mapping;
+use super::Value;
+
+/// Formats a value with `Debug` the way a derived impl would, returned by
+/// `Value::exact_debug`.
+pub struct ExactDebug<'a> {
+    value: &'a Value,
+}
+
+impl Value {
+    /// Formats the value with `Debug` showing every wrapper, including how
+    /// each number is stored, for when the compact `Debug` output of `Value`
+    /// hides a difference.
+    ///
+    /// ```rust
+    /// # extern crate serde_yaml;
+    /// #
+    /// # fn yaml(i: &str) -> serde_yaml::Value { serde_yaml::from_str(i).unwrap() }
+    /// # fn main() {
+    /// let value = yaml("{a: [1, -1.5]}");
+    /// assert_eq!(format!("{:?}", value), r#"Mapping {"a": Sequence [Number(1), Number(-1.5)]}"#);
+    /// let exact = concat!(r#"Mapping(Mapping { map: {String("a"): "#,
+    ///                     r#"Sequence([Number(PosInt(1)), Number(Float(-1.5))])} })"#);
+    /// assert_eq!(format!("{:?}", value.exact_debug()), exact);
+    /// # }
+    /// ```
+    pub fn exact_debug(&self) -> ExactDebug {
+        ExactDebug { value: self }
+    }
+}
+
+// Prints a structure that reads like the YAML it came from, with the type of
+// every value around it except string keys:
+// `Mapping {"a": Sequence [Number(1), Null]}`.
+impl Debug for Value {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Value::Null => formatter.write_str("Null"),
+            Value::Bool(b) => write!(formatter, "Bool({})", b),
+            Value::Number(ref n) => write!(formatter, "Number({})", n),
+            Value::String(ref s) => write!(formatter, "String({:?})", s),
+            Value::Sequence(ref seq) => {
+                formatter.write_str("Sequence ")?;
+                formatter.debug_list().entries(seq).finish()
+            }
+            Value::Mapping(ref mapping) => Debug::fmt(mapping, formatter),
+        }
+    }
+}
+
+impl Debug for Mapping {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("Mapping ")?;
+        formatter.debug_map().entries(self.iter().map(|(k, v)| (Key(k), v))).finish()
+    }
+}
+
+/// A mapping key, which is only annotated if it is not a string.
+struct Key<'a>(&'a Value);
+
+impl<'a> Debug for Key<'a> {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match *self.0 {
+            Value::String(ref s) => Debug::fmt(s, formatter),
+            ref key => Debug::fmt(key, formatter),
+        }
+    }
+}
+
+impl<'a> Debug for ExactDebug<'a> {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match *self.value {
+            Value::Null => formatter.write_str("Null"),
+            Value::Bool(b) => formatter.debug_tuple("Bool").field(&b).finish(),
+            Value::Number(ref n) => formatter.debug_tuple("Number").field(n).finish(),
+            Value::String(ref s) => formatter.debug_tuple("String").field(s).finish(),
+            Value::Sequence(ref seq) => {
+                let items: Vec<_> = seq.iter().map(Value::exact_debug).collect();
+                formatter.debug_tuple("Sequence").field(&items).finish()
+            }
+            Value::Mapping(ref mapping) => {
+                formatter.debug_tuple("Mapping").field(&ExactMapping(mapping)).finish()
+            }
+        }
+    }
+}
+
+struct ExactMapping<'a>(&'a Mapping);
+
+impl<'a> Debug for ExactMapping<'a> {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.debug_struct("Mapping").field("map", &ExactEntries(self.0)).finish()
+    }
+}
+
+struct ExactEntries<'a>(&'a Mapping);
+
+impl<'a> Debug for ExactEntries<'a> {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.debug_map()
+            .entries(self.0.iter().map(|(k, v)| (k.exact_debug(), v.exact_debug())))
+            .finish()
+    }
+}
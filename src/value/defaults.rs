@@ -0,0 +1,106 @@
+// Copyright 2018 Serde YAML Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use super::Value;
+
+/// How `Value::apply_defaults_with` treats a sequence that is present in both
+/// the target and the defaults.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SequenceDefaults {
+    /// Leave the target's sequence as it is. This is the default.
+    Keep,
+    /// Complete each element of the target from the element of the defaults
+    /// at the same index. Elements are never added or removed.
+    ByIndex,
+    /// Complete every element of the target from the first element of the
+    /// defaults, as from a template.
+    Template,
+}
+
+impl Default for SequenceDefaults {
+    fn default() -> Self {
+        SequenceDefaults::Keep
+    }
+}
+
+impl Value {
+    /// Fills in everything that `defaults` has and `self` lacks, recursively.
+    ///
+    /// Missing mapping entries, and entries whose value is null, are copied
+    /// from `defaults`. Mappings present on both sides are completed entry by
+    /// entry. Any other value already in `self` is kept, and sequences are
+    /// left alone; see `apply_defaults_with` for other ways to handle them.
+    ///
+    /// ```rust
+    /// # extern crate serde_yaml;
+    /// #
+    /// # fn yaml(i: &str) -> serde_yaml::Value { serde_yaml::from_str(i).unwrap() }
+    /// # fn main() {
+    /// let defaults = yaml("{log: {level: info, format: text}, workers: 4, tags: [default]}");
+    /// let mut config = yaml("{log: {level: debug}, workers: ~, tags: [web]}");
+    /// config.apply_defaults(&defaults);
+    /// assert_eq!(config, yaml("{log: {level: debug, format: text}, workers: 4, tags: [web]}"));
+    /// # }
+    /// ```
+    pub fn apply_defaults(&mut self, defaults: &Value) {
+        self.apply_defaults_with(defaults, SequenceDefaults::Keep);
+    }
+
+    /// Like `apply_defaults`, with a choice of how to complete sequences that
+    /// are present on both sides.
+    ///
+    /// ```rust
+    /// # extern crate serde_yaml;
+    /// #
+    /// # fn yaml(i: &str) -> serde_yaml::Value { serde_yaml::from_str(i).unwrap() }
+    /// # fn main() {
+    /// use serde_yaml::SequenceDefaults;
+    ///
+    /// let defaults = yaml("containers: [{pull: always, ports: []}]");
+    /// let mut config = yaml("containers: [{name: web}, {name: db, pull: never}]");
+    /// config.apply_defaults_with(&defaults, SequenceDefaults::Template);
+    /// assert_eq!(config, yaml("containers: [{name: web, pull: always, ports: []}, \
+    ///                                       {name: db, pull: never, ports: []}]"));
+    /// # }
+    /// ```
+    pub fn apply_defaults_with(&mut self, defaults: &Value, sequences: SequenceDefaults) {
+        if self.is_null() {
+            *self = defaults.clone();
+            return;
+        }
+        match (self, defaults) {
+            (&mut Value::Mapping(ref mut target), &Value::Mapping(ref defaults)) => {
+                for (k, default) in defaults {
+                    if target.contains_key(k) {
+                        target[k].apply_defaults_with(default, sequences);
+                    } else {
+                        target.insert(k.clone(), default.clone());
+                    }
+                }
+            }
+            (&mut Value::Sequence(ref mut target), &Value::Sequence(ref defaults)) => {
+                match sequences {
+                    SequenceDefaults::Keep => {}
+                    SequenceDefaults::ByIndex => {
+                        for (item, default) in target.iter_mut().zip(defaults) {
+                            item.apply_defaults_with(default, sequences);
+                        }
+                    }
+                    SequenceDefaults::Template => {
+                        if let Some(template) = defaults.first() {
+                            for item in target {
+                                item.apply_defaults_with(template, sequences);
+                            }
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
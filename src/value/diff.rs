@@ -0,0 +1,282 @@
+// Copyright 2018 Serde YAML Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::fmt::{self, Display};
+
+use mapping::Mapping;
+use ser;
+use super::Value;
+
+/// Two values rendered as a line-by-line diff in YAML syntax, returned by
+/// `Value::display_diff`.
+///
+/// Lines that only appear on the left start with `-`, lines that only appear
+/// on the right start with `+`, and shared context starts with a space.
+/// Collections that are equal on both sides are collapsed to a comment.
+pub struct DisplayDiff<'a> {
+    left: &'a Value,
+    right: &'a Value,
+}
+
+impl Value {
+    /// Shows how `other` differs from `self`, for example in the message of a
+    /// failed test assertion.
+    ///
+    /// ```rust
+    /// # extern crate serde_yaml;
+    /// #
+    /// # fn yaml(i: &str) -> serde_yaml::Value { serde_yaml::from_str(i).unwrap() }
+    /// # fn main() {
+    /// let expected = yaml("{name: web, replicas: 2, ports: [80, 443], labels: {app: web}}");
+    /// let actual = yaml("{name: web, replicas: 3, ports: [80, 8443], labels: {app: web}}");
+    /// let diff = expected.display_diff(&actual).to_string();
+    /// assert_eq!(diff.lines().collect::<Vec<_>>(), [
+    ///     "  name: web",
+    ///     "- replicas: 2",
+    ///     "+ replicas: 3",
+    ///     "  ports:",
+    ///     "    - 80",
+    ///     "-   - 443",
+    ///     "+   - 8443",
+    ///     "  labels: # 1 unchanged entry",
+    /// ]);
+    /// # }
+    /// ```
+    pub fn display_diff<'a>(&'a self, other: &'a Value) -> DisplayDiff<'a> {
+        DisplayDiff {
+            left: self,
+            right: other,
+        }
+    }
+}
+
+impl<'a> Display for DisplayDiff<'a> {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        let mut lines = Vec::new();
+        diff(&mut lines, 0, Lead::Root, self.left, self.right);
+        for line in lines {
+            writeln!(formatter, "{} {:indent$}{}", line.sign, "", line.text, indent = 2 * line.depth)?;
+        }
+        Ok(())
+    }
+}
+
+struct Line {
+    sign: char,
+    depth: usize,
+    text: String,
+}
+
+/// What introduces a node: nothing at the top level, otherwise its key in a
+/// mapping or its dash in a sequence.
+#[derive(Copy, Clone)]
+enum Lead<'a> {
+    Root,
+    Key(&'a Value),
+    Item,
+}
+
+/// Longest pair of sequences, by product of lengths, aligned element by
+/// element on equality. Longer ones are compared index by index.
+const MAX_ALIGN: usize = 1 << 20;
+
+fn diff(out: &mut Vec<Line>, depth: usize, lead: Lead, left: &Value, right: &Value) {
+    if left == right {
+        return context(out, depth, lead, left);
+    }
+    match (left, right) {
+        (&Value::Mapping(ref left), &Value::Mapping(ref right)) if !left.is_empty() && !right.is_empty() => {
+            let depth = open(out, ' ', depth, lead);
+            diff_mappings(out, depth, left, right);
+        }
+        (&Value::Sequence(ref left), &Value::Sequence(ref right)) if !left.is_empty() && !right.is_empty() => {
+            let depth = open(out, ' ', depth, lead);
+            diff_sequences(out, depth, left, right);
+        }
+        _ => {
+            render(out, '-', depth, lead, left);
+            render(out, '+', depth, lead, right);
+        }
+    }
+}
+
+fn diff_mappings(out: &mut Vec<Line>, depth: usize, left: &Mapping, right: &Mapping) {
+    for (k, v) in left {
+        match right.get(k) {
+            Some(other) => diff(out, depth, Lead::Key(k), v, other),
+            None => render(out, '-', depth, Lead::Key(k), v),
+        }
+    }
+    for (k, v) in right {
+        if !left.contains_key(k) {
+            render(out, '+', depth, Lead::Key(k), v);
+        }
+    }
+}
+
+fn diff_sequences(out: &mut Vec<Line>, depth: usize, left: &[Value], right: &[Value]) {
+    let prefix = left.iter().zip(right).take_while(|&(l, r)| l == r).count();
+    let suffix = left[prefix..]
+        .iter()
+        .rev()
+        .zip(right[prefix..].iter().rev())
+        .take_while(|&(l, r)| l == r)
+        .count();
+    for item in &left[..prefix] {
+        context(out, depth, Lead::Item, item);
+    }
+
+    let left_middle = &left[prefix..left.len() - suffix];
+    let right_middle = &right[prefix..right.len() - suffix];
+    let mut l = 0;
+    let mut r = 0;
+    for (next_l, next_r) in align(left_middle, right_middle) {
+        changed(out, depth, &left_middle[l..next_l], &right_middle[r..next_r]);
+        context(out, depth, Lead::Item, &left_middle[next_l]);
+        l = next_l + 1;
+        r = next_r + 1;
+    }
+    changed(out, depth, &left_middle[l..], &right_middle[r..]);
+
+    for item in &left[left.len() - suffix..] {
+        context(out, depth, Lead::Item, item);
+    }
+}
+
+/// Runs of sequence elements between two aligned ones. Elements are paired
+/// up in order so that collections changed in place show a nested diff.
+fn changed(out: &mut Vec<Line>, depth: usize, left: &[Value], right: &[Value]) {
+    let paired = left.len().min(right.len());
+    for (l, r) in left.iter().zip(right) {
+        diff(out, depth, Lead::Item, l, r);
+    }
+    for item in &left[paired..] {
+        render(out, '-', depth, Lead::Item, item);
+    }
+    for item in &right[paired..] {
+        render(out, '+', depth, Lead::Item, item);
+    }
+}
+
+/// Indices of a longest common subsequence of equal elements.
+fn align(left: &[Value], right: &[Value]) -> Vec<(usize, usize)> {
+    if left.is_empty() || right.is_empty() || left.len().saturating_mul(right.len()) > MAX_ALIGN {
+        return Vec::new();
+    }
+    // lengths[i][j] is the length of a longest common subsequence of
+    // left[i..] and right[j..].
+    let width = right.len() + 1;
+    let mut lengths = vec![0usize; (left.len() + 1) * width];
+    for i in (0..left.len()).rev() {
+        for j in (0..right.len()).rev() {
+            lengths[i * width + j] = if left[i] == right[j] {
+                lengths[(i + 1) * width + j + 1] + 1
+            } else {
+                lengths[(i + 1) * width + j].max(lengths[i * width + j + 1])
+            };
+        }
+    }
+    let mut pairs = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < left.len() && j < right.len() {
+        if left[i] == right[j] {
+            pairs.push((i, j));
+            i += 1;
+            j += 1;
+        } else if lengths[(i + 1) * width + j] >= lengths[i * width + j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    pairs
+}
+
+/// A node that is the same on both sides, with collections collapsed.
+fn context(out: &mut Vec<Line>, depth: usize, lead: Lead, value: &Value) {
+    let comment = match *value {
+        Value::Mapping(ref mapping) if mapping.len() == 1 => "# 1 unchanged entry".to_owned(),
+        Value::Mapping(ref mapping) if !mapping.is_empty() => format!("# {} unchanged entries", mapping.len()),
+        Value::Sequence(ref seq) if seq.len() == 1 => "# 1 unchanged item".to_owned(),
+        Value::Sequence(ref seq) if !seq.is_empty() => format!("# {} unchanged items", seq.len()),
+        _ => return render(out, ' ', depth, lead, value),
+    };
+    let prefix = inline(out, ' ', depth, lead);
+    push(out, ' ', depth, prefix + &comment);
+}
+
+/// A whole node on one side only.
+fn render(out: &mut Vec<Line>, sign: char, depth: usize, lead: Lead, value: &Value) {
+    match *value {
+        Value::Mapping(ref mapping) if !mapping.is_empty() => {
+            let depth = open(out, sign, depth, lead);
+            for (k, v) in mapping {
+                render(out, sign, depth, Lead::Key(k), v);
+            }
+        }
+        Value::Sequence(ref seq) if !seq.is_empty() => {
+            let depth = open(out, sign, depth, lead);
+            for item in seq {
+                render(out, sign, depth, Lead::Item, item);
+            }
+        }
+        _ => {
+            let prefix = inline(out, sign, depth, lead);
+            push(out, sign, depth, prefix + &scalar(value));
+        }
+    }
+}
+
+/// Writes the line that introduces a collection and returns the depth of its
+/// contents.
+fn open(out: &mut Vec<Line>, sign: char, depth: usize, lead: Lead) -> usize {
+    match lead {
+        Lead::Root => depth,
+        _ => {
+            let prefix = inline(out, sign, depth, lead);
+            push(out, sign, depth, prefix.trim_end().to_owned());
+            depth + 1
+        }
+    }
+}
+
+/// Returns the text that goes before a node written on the same line as its
+/// key or dash. A key that is itself a collection is written out first in
+/// `? key` form.
+fn inline(out: &mut Vec<Line>, sign: char, depth: usize, lead: Lead) -> String {
+    match lead {
+        Lead::Root => String::new(),
+        Lead::Item => "- ".to_owned(),
+        Lead::Key(key) => {
+            match *key {
+                Value::Mapping(ref mapping) if !mapping.is_empty() => {}
+                Value::Sequence(ref seq) if !seq.is_empty() => {}
+                _ => return scalar(key) + ": ",
+            }
+            push(out, sign, depth, "?".to_owned());
+            render(out, sign, depth + 1, Lead::Root, key);
+            ": ".to_owned()
+        }
+    }
+}
+
+fn push(out: &mut Vec<Line>, sign: char, depth: usize, text: String) {
+    out.push(Line {
+                 sign: sign,
+                 depth: depth,
+                 text: text,
+             });
+}
+
+/// A scalar or empty collection as it would be serialized.
+fn scalar(value: &Value) -> String {
+    match ser::to_string(value) {
+        Ok(yaml) => yaml.trim_start_matches("---").trim_start().to_owned(),
+        Err(err) => format!("# {}", err),
+    }
+}
@@ -0,0 +1,130 @@
+// Copyright 2018 Serde YAML Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::mem;
+
+use serde::de::Error as SError;
+
+use super::Value;
+use error::{Error, Result};
+use mapping::Mapping;
+use ser;
+
+impl Value {
+    /// Turns every mapping key that contains dots into nested mappings, the
+    /// way properties and HOCON files are read: `a.b.c: 1` becomes
+    /// `a: {b: {c: 1}}`.
+    ///
+    /// Entries that end up under the same key are merged, whether they were
+    /// written as dotted keys or as nested mappings, and the resulting keys
+    /// keep the order in which they first appear. Keys that are not strings,
+    /// and keys with an empty part like `a..b` or `.a`, are left as they are.
+    ///
+    /// # Errors
+    ///
+    /// Fails if two entries give a value to the same key, or if a dotted key
+    /// runs through a key whose value is not a mapping, such as `a.b` next to
+    /// `a: 1`. The value is left partly expanded.
+    ///
+    /// ```rust
+    /// # extern crate serde_yaml;
+    /// #
+    /// # fn yaml(i: &str) -> serde_yaml::Value { serde_yaml::from_str(i).unwrap() }
+    /// # fn main() {
+    /// let mut value = yaml("{db.host: localhost, db.port: 5432, log: {level: info}, log.file: out.log}");
+    /// value.expand_dotted_keys().unwrap();
+    /// assert_eq!(value, yaml("{db: {host: localhost, port: 5432}, log: {level: info, file: out.log}}"));
+    ///
+    /// let mut value = yaml("{a: 1, a.b: 2}");
+    /// let err = value.expand_dotted_keys().unwrap_err();
+    /// assert_eq!(err.to_string(), "key `a.b` runs through `a`, which is not a mapping");
+    /// # }
+    /// ```
+    pub fn expand_dotted_keys(&mut self) -> Result<()> {
+        expand(self, "")
+    }
+}
+
+fn expand(value: &mut Value, path: &str) -> Result<()> {
+    match *value {
+        Value::Sequence(ref mut seq) => {
+            for (i, element) in seq.iter_mut().enumerate() {
+                expand(element, &format!("{}[{}]", path, i))?;
+            }
+            Ok(())
+        }
+        Value::Mapping(ref mut mapping) => {
+            for (key, mut value) in mem::replace(mapping, Mapping::new()) {
+                let parts = match key {
+                    Value::String(ref key) if !key.split('.').any(str::is_empty) => {
+                        key.split('.').map(|part| Value::String(part.to_owned())).collect()
+                    }
+                    key => vec![key],
+                };
+                expand(&mut value, &join(path, &parts))?;
+                insert(mapping, path, &parts, value)?;
+            }
+            Ok(())
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Puts `value` at the nested keys `parts` of `mapping`, which is at `path`.
+fn insert(mapping: &mut Mapping, path: &str, parts: &[Value], value: Value) -> Result<()> {
+    let (first, rest) = parts.split_first().expect("at least one key");
+    if !rest.is_empty() {
+        if !mapping.contains_key(first) {
+            mapping.insert(first.clone(), Value::Mapping(Mapping::new()));
+        }
+        return match mapping[first] {
+            Value::Mapping(ref mut inner) => insert(inner, &join(path, &parts[..1]), rest, value),
+            _ => {
+                Err(Error::custom(format_args!("key `{}` runs through `{}`, which is not a mapping",
+                                               join(path, parts),
+                                               join(path, &parts[..1]))))
+            }
+        };
+    }
+    if !mapping.contains_key(first) {
+        mapping.insert(first.clone(), value);
+        return Ok(());
+    }
+    match (&mut mapping[first], value) {
+        (&mut Value::Mapping(ref mut existing), Value::Mapping(new)) => {
+            let path = join(path, parts);
+            for (key, value) in new {
+                insert(existing, &path, &[key], value)?;
+            }
+            Ok(())
+        }
+        (&mut Value::Mapping(_), _) | (_, Value::Mapping(_)) => {
+            Err(Error::custom(format_args!("key `{}` is both a mapping and not a mapping",
+                                           join(path, parts))))
+        }
+        _ => Err(Error::custom(format_args!("key `{}` is given more than one value", join(path, parts)))),
+    }
+}
+
+/// The path of the key `parts` in the mapping at `path`, written with dots.
+fn join(path: &str, parts: &[Value]) -> String {
+    let mut joined = path.to_owned();
+    for part in parts {
+        if !joined.is_empty() {
+            joined.push('.');
+        }
+        match *part {
+            Value::String(ref s) => joined.push_str(s),
+            ref key => {
+                let yaml = ser::to_string(key).unwrap_or_default();
+                joined.push_str(yaml.trim_start_matches("---").trim_start());
+            }
+        }
+    }
+    joined
+}
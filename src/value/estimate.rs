@@ -0,0 +1,69 @@
+// Copyright 2018 Serde YAML Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use emitter;
+
+use super::Value;
+
+impl Value {
+    /// About how many bytes `to_string` writes for this value, for sizing
+    /// the buffer given to `to_fmt_writer` or `Writer` ahead of time. The
+    /// estimate is cheap to compute and errs on the high side: it counts
+    /// every string as if it were double quoted, escapes included.
+    ///
+    /// `to_string` and `YamlFormat::to_string` already size their output this
+    /// way, so this is only needed when writing somewhere else.
+    ///
+    /// ```rust
+    /// # extern crate serde_yaml;
+    /// #
+    /// # fn main() {
+    /// let value: serde_yaml::Value = serde_yaml::from_str("a: [1, 2]\nb: {c: text}").unwrap();
+    /// let mut yaml = String::with_capacity(value.estimated_yaml_len());
+    /// serde_yaml::to_fmt_writer(&mut yaml, &value).unwrap();
+    /// assert_eq!(yaml, "---\na:\n  - 1\n  - 2\nb:\n  c: text");
+    /// assert!(yaml.len() <= value.estimated_yaml_len());
+    /// # }
+    /// ```
+    pub fn estimated_yaml_len(&self) -> usize {
+        // Counts like the estimate of the serializer, which works on the tree
+        // it builds instead, but with a stack of its own so that it also
+        // serves `deep::to_string`.
+        let mut len = "---\n".len();
+        // Whether a collection starts on the line of the `- ` it is an
+        // element of, as in `- - a`, rather than on a line of its own.
+        let mut pending = vec![(self, 0, false)];
+        while let Some((value, depth, inline)) = pending.pop() {
+            // Each element or entry is on its own line, after the
+            // indentation and `- ` or with `: ` after the key, except the
+            // first one of an inline collection.
+            let line = 2 * depth + 3;
+            let first = if inline { 2 } else { line };
+            len += match *value {
+                Value::Null => 1,
+                Value::Bool(_) => 5,
+                Value::Number(ref n) => n.display_len(),
+                Value::String(ref s) => emitter::quoted_len(s),
+                Value::Sequence(ref seq) if seq.is_empty() => 2,
+                Value::Mapping(ref mapping) if mapping.is_empty() => 2,
+                Value::Sequence(ref seq) => {
+                    pending.extend(seq.iter().map(|element| (element, depth + 1, true)));
+                    first + line * (seq.len() - 1)
+                }
+                Value::Mapping(ref mapping) => {
+                    for (key, value) in mapping {
+                        pending.push((key, depth + 1, false));
+                        pending.push((value, depth + 1, false));
+                    }
+                    first + line * (mapping.len() - 1)
+                }
+            };
+        }
+        len
+    }
+}
@@ -0,0 +1,97 @@
+// Copyright 2018 Serde YAML Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use super::flatten::key_text;
+use super::Value;
+
+impl Value {
+    /// Finds every value anywhere in the tree whose key is the string
+    /// `name`, along with its path, written the same way as by
+    /// `flatten_paths`. Matches come in document order, and the search goes
+    /// on inside them.
+    ///
+    /// ```rust
+    /// # extern crate serde_yaml;
+    /// #
+    /// # fn yaml(i: &str) -> serde_yaml::Value { serde_yaml::from_str(i).unwrap() }
+    /// # fn main() {
+    /// let manifest = yaml("
+    /// spec:
+    ///   initContainers: [{name: setup, image: busybox}]
+    ///   containers: [{name: web, image: nginx}, {name: log}]
+    /// ");
+    /// let images: Vec<(String, &str)> = manifest.find_key("image")
+    ///     .into_iter()
+    ///     .map(|(path, image)| (path, image.as_str().unwrap()))
+    ///     .collect();
+    /// assert_eq!(images, [
+    ///     ("spec.initContainers[0].image".to_owned(), "busybox"),
+    ///     ("spec.containers[0].image".to_owned(), "nginx"),
+    /// ]);
+    /// # }
+    /// ```
+    pub fn find_key(&self, name: &str) -> Vec<(String, &Value)> {
+        self.find_all(|key, _| key.as_str() == Some(name))
+    }
+
+    /// Finds every value anywhere in the tree whose entry `predicate`
+    /// accepts, given the key and the value, along with its path. See
+    /// `find_key`.
+    ///
+    /// ```rust
+    /// # extern crate serde_yaml;
+    /// #
+    /// # fn yaml(i: &str) -> serde_yaml::Value { serde_yaml::from_str(i).unwrap() }
+    /// # fn main() {
+    /// let config = yaml("{db: {password: hunter2, port: 5432}, api: {token: abc, timeout: 30}}");
+    /// let secrets: Vec<String> = config.find_all(|key, value| {
+    ///         value.is_string() && key.as_str().map_or(false, |key| key == "password" || key == "token")
+    ///     })
+    ///     .into_iter()
+    ///     .map(|(path, _)| path)
+    ///     .collect();
+    /// assert_eq!(secrets, ["db.password", "api.token"]);
+    /// # }
+    /// ```
+    pub fn find_all<F>(&self, mut predicate: F) -> Vec<(String, &Value)>
+        where F: FnMut(&Value, &Value) -> bool
+    {
+        let mut found = Vec::new();
+        find(&mut found, &mut String::new(), self, &mut predicate);
+        found
+    }
+}
+
+fn find<'a, F>(found: &mut Vec<(String, &'a Value)>, path: &mut String, value: &'a Value, predicate: &mut F)
+    where F: FnMut(&Value, &Value) -> bool
+{
+    let len = path.len();
+    match *value {
+        Value::Mapping(ref mapping) => {
+            for (k, v) in mapping {
+                if !path.is_empty() {
+                    path.push('.');
+                }
+                path.push_str(&key_text(k));
+                if predicate(k, v) {
+                    found.push((path.clone(), v));
+                }
+                find(found, path, v, predicate);
+                path.truncate(len);
+            }
+        }
+        Value::Sequence(ref seq) => {
+            for (i, v) in seq.iter().enumerate() {
+                path.push_str(&format!("[{}]", i));
+                find(found, path, v, predicate);
+                path.truncate(len);
+            }
+        }
+        _ => {}
+    }
+}
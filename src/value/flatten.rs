@@ -0,0 +1,192 @@
+// Copyright 2018 Serde YAML Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use serde::de::Error as SError;
+
+use super::Value;
+use error::{Error, Result};
+use mapping::Mapping;
+use path::{segments, Segment};
+use ser;
+
+impl Value {
+    /// Lists every scalar in the tree along with its path, written the same
+    /// way as in error messages: `a.b[0].c`. A scalar at the root has the path
+    /// `.`. Empty mappings and sequences are listed as values of their own so
+    /// that `from_flat_pairs` can restore them.
+    ///
+    /// Keys that are not strings are written as they would be serialized. Keys
+    /// that contain `.`, `[` or `]` make the paths ambiguous and do not
+    /// survive a round trip through `from_flat_pairs`.
+    ///
+    /// ```rust
+    /// # extern crate serde_yaml;
+    /// #
+    /// # fn yaml(i: &str) -> serde_yaml::Value { serde_yaml::from_str(i).unwrap() }
+    /// # fn main() {
+    /// let value = yaml("{db: {host: localhost, ports: [5432, 5433]}, debug: false}");
+    /// let flat: Vec<String> = value.flatten_paths()
+    ///     .iter()
+    ///     .map(|&(ref path, ref scalar)| format!("{}={}", path, serde_yaml::to_string(scalar).unwrap()))
+    ///     .collect();
+    /// assert_eq!(flat, [
+    ///     "db.host=---\nlocalhost",
+    ///     "db.ports[0]=---\n5432",
+    ///     "db.ports[1]=---\n5433",
+    ///     "debug=---\nfalse",
+    /// ]);
+    /// # }
+    /// ```
+    pub fn flatten_paths(&self) -> Vec<(String, Value)> {
+        let mut pairs = Vec::new();
+        flatten(&mut pairs, &mut String::new(), self);
+        pairs
+    }
+
+    /// Builds a tree from paths and scalars like those produced by
+    /// `flatten_paths`. Mappings are created for keys and sequences for
+    /// indices. Indices may come in any order, and elements that are skipped
+    /// over are null. If the same path occurs more than once, the last value
+    /// wins.
+    ///
+    /// All keys are built as strings.
+    ///
+    /// # Errors
+    ///
+    /// Fails if a path runs through a value set by an earlier path that is of
+    /// a different kind, such as `a.b` after `a[0]`, or if an index is not
+    /// less than the number of pairs.
+    ///
+    /// ```rust
+    /// # extern crate serde_yaml;
+    /// #
+    /// # fn yaml(i: &str) -> serde_yaml::Value { serde_yaml::from_str(i).unwrap() }
+    /// # fn main() {
+    /// use serde_yaml::Value;
+    ///
+    /// let value = Value::from_flat_pairs(vec![
+    ///     ("db.ports[1]", yaml("5433")),
+    ///     ("db.ports[0]", yaml("5432")),
+    ///     ("db.host", yaml("localhost")),
+    /// ]).unwrap();
+    /// assert_eq!(value, yaml("{db: {ports: [5432, 5433], host: localhost}}"));
+    /// # }
+    /// ```
+    pub fn from_flat_pairs<I, P>(pairs: I) -> Result<Value>
+        where I: IntoIterator<Item = (P, Value)>,
+              P: AsRef<str>
+    {
+        let pairs: Vec<_> = pairs.into_iter().collect();
+        // No index can usefully be larger than the number of pairs, and
+        // bounding it keeps a path like `a[4000000000]` from allocating.
+        let max_index = pairs.len();
+        let mut root = Value::Null;
+        for (path, value) in pairs {
+            let path = path.as_ref();
+            let slot = slot(&mut root, &segments(path), max_index).ok_or_else(|| conflict(path))?;
+            if is_collection(slot) && !is_collection(&value) {
+                return Err(conflict(path));
+            }
+            *slot = value;
+        }
+        Ok(root)
+    }
+}
+
+fn flatten(pairs: &mut Vec<(String, Value)>, path: &mut String, value: &Value) {
+    let len = path.len();
+    match *value {
+        Value::Mapping(ref mapping) if !mapping.is_empty() => {
+            for (k, v) in mapping {
+                if !path.is_empty() {
+                    path.push('.');
+                }
+                path.push_str(&key_text(k));
+                flatten(pairs, path, v);
+                path.truncate(len);
+            }
+        }
+        Value::Sequence(ref seq) if !seq.is_empty() => {
+            for (i, v) in seq.iter().enumerate() {
+                path.push_str(&format!("[{}]", i));
+                flatten(pairs, path, v);
+                path.truncate(len);
+            }
+        }
+        _ => {
+            let path = if path.is_empty() { ".".to_owned() } else { path.clone() };
+            pairs.push((path, value.clone()));
+        }
+    }
+}
+
+/// A key as it is written in a path.
+pub fn key_text(key: &Value) -> String {
+    match *key {
+        Value::String(ref key) => key.clone(),
+        ref key => {
+            let yaml = ser::to_string(key).unwrap_or_default();
+            yaml.trim_start_matches("---").trim_start().to_owned()
+        }
+    }
+}
+
+fn is_collection(value: &Value) -> bool {
+    match *value {
+        Value::Mapping(ref mapping) => !mapping.is_empty(),
+        Value::Sequence(ref seq) => !seq.is_empty(),
+        _ => false,
+    }
+}
+
+/// The place for the value at a path, creating mappings, sequences and null
+/// elements as needed. None if the path runs through a scalar or a collection
+/// of the other kind, or has an index of `max_index` or more.
+fn slot<'a>(mut value: &'a mut Value, segments: &[Segment], max_index: usize) -> Option<&'a mut Value> {
+    for segment in segments {
+        if !is_collection(value) {
+            if !value.is_null() && !is_empty_collection(value) {
+                return None;
+            }
+            *value = match *segment {
+                Segment::Key(_) => Value::Mapping(Mapping::new()),
+                Segment::Index(_) => Value::Sequence(Vec::new()),
+            };
+        }
+        value = match (value, segment) {
+            (&mut Value::Mapping(ref mut mapping), &Segment::Key(ref key)) => {
+                let key = Value::String(key.clone());
+                if !mapping.contains_key(&key) {
+                    mapping.insert(key.clone(), Value::Null);
+                }
+                &mut mapping[&key]
+            }
+            (&mut Value::Sequence(ref mut seq), &Segment::Index(index)) if index < max_index => {
+                if seq.len() <= index {
+                    seq.resize(index + 1, Value::Null);
+                }
+                &mut seq[index]
+            }
+            _ => return None,
+        };
+    }
+    Some(value)
+}
+
+fn is_empty_collection(value: &Value) -> bool {
+    match *value {
+        Value::Mapping(ref mapping) => mapping.is_empty(),
+        Value::Sequence(ref seq) => seq.is_empty(),
+        _ => false,
+    }
+}
+
+fn conflict(path: &str) -> Error {
+    Error::custom(format_args!("flat path `{}` conflicts with an earlier path or has too large an index",
+                               path))
+}
@@ -158,6 +158,25 @@ impl<T: Into<Value>> From<Vec<T>> for Value {
     }
 }
 
+impl<T: Into<Value>, const N: usize> From<[T; N]> for Value {
+    /// Convert an array to `Value`
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # extern crate serde_yaml;
+    /// #
+    /// # fn main() {
+    /// use serde_yaml::Value;
+    ///
+    /// let x: Value = [80, 443].into();
+    /// # }
+    /// ```
+    fn from(f: [T; N]) -> Self {
+        Value::Sequence(IntoIterator::into_iter(f).map(Into::into).collect())
+    }
+}
+
 impl<'a, T: Clone + Into<Value>> From<&'a [T]> for Value {
     /// Convert a slice to `Value`
     ///
@@ -174,7 +193,7 @@ impl<'a, T: Clone + Into<Value>> From<&'a [T]> for Value {
     /// # }
     /// ```
     fn from(f: &'a [T]) -> Self {
-        Value::Sequence(f.into_iter().cloned().map(Into::into).collect())
+        Value::Sequence(f.iter().cloned().map(Into::into).collect())
     }
 }
 
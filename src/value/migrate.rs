@@ -0,0 +1,245 @@
+// Copyright 2018 Serde YAML Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::mem;
+
+use serde::de::Error as SError;
+
+use super::Value;
+use error::{Error, Result};
+use mapping::Mapping;
+use path::{segments, Segment};
+
+impl Value {
+    /// Renames the key `old` of the mapping at `path` to `new`, keeping the
+    /// entry where it was. Paths are written the same way as in error
+    /// messages, like `spec.containers[0]`, and the root is `.`.
+    ///
+    /// Returns false, leaving the value alone, if there is no mapping at
+    /// `path` or it has no key `old`. An existing entry for `new` is replaced.
+    ///
+    /// ```rust
+    /// # extern crate serde_yaml;
+    /// #
+    /// # fn yaml(i: &str) -> serde_yaml::Value { serde_yaml::from_str(i).unwrap() }
+    /// # fn main() {
+    /// let mut config = yaml("server: {host: example.com, port: 80}");
+    /// assert!(config.rename_key("server", "host", "hostname"));
+    /// assert_eq!(config, yaml("server: {hostname: example.com, port: 80}"));
+    /// assert!(!config.rename_key("server", "host", "hostname"));
+    /// # }
+    /// ```
+    pub fn rename_key(&mut self, path: &str, old: &str, new: &str) -> bool {
+        let mapping = match mapping_mut(self, &segments(path)) {
+            Some(mapping) => mapping,
+            None => return false,
+        };
+        let old = Value::String(old.to_owned());
+        if !mapping.contains_key(&old) {
+            return false;
+        }
+        let new = Value::String(new.to_owned());
+        let entries = mem::replace(mapping, Mapping::new());
+        for (k, v) in entries {
+            if k == old {
+                mapping.insert(new.clone(), v);
+            } else if k != new {
+                mapping.insert(k, v);
+            }
+        }
+        true
+    }
+}
+
+/// An ordered list of changes that upgrades a configuration tree written for
+/// an older version of a program.
+///
+/// Each step that finds nothing to do is skipped, so a migration can be
+/// applied to files that are already partly or fully upgraded.
+///
+/// ```rust
+/// # extern crate serde_yaml;
+/// #
+/// # fn yaml(i: &str) -> serde_yaml::Value { serde_yaml::from_str(i).unwrap() }
+/// # fn main() {
+/// use serde_yaml::Migration;
+///
+/// let migration = Migration::new()
+///     .rename("server", "host", "hostname")
+///     .move_key("server.port", "http.port")
+///     .set_default("http.tls", false);
+///
+/// let mut config = yaml("server: {host: example.com, port: 80}");
+/// assert_eq!(migration.apply(&mut config).unwrap(), 3);
+/// assert_eq!(config, yaml("{server: {hostname: example.com}, http: {port: 80, tls: false}}"));
+///
+/// // Applying it again changes nothing.
+/// assert_eq!(migration.apply(&mut config).unwrap(), 0);
+/// # }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Migration {
+    steps: Vec<Step>,
+}
+
+#[derive(Clone, Debug)]
+enum Step {
+    Rename {
+        path: String,
+        old: String,
+        new: String,
+    },
+    Move { from: String, to: String },
+    Default { path: String, value: Value },
+}
+
+impl Migration {
+    /// A migration with no steps.
+    pub fn new() -> Self {
+        Migration::default()
+    }
+
+    /// Renames the key `old` of the mapping at `path` to `new`, as by
+    /// `Value::rename_key`.
+    pub fn rename(mut self, path: &str, old: &str, new: &str) -> Self {
+        self.steps.push(Step::Rename {
+                            path: path.to_owned(),
+                            old: old.to_owned(),
+                            new: new.to_owned(),
+                        });
+        self
+    }
+
+    /// Moves the entry at path `from` to path `to`, creating mappings along
+    /// the way to `to` where needed. Both paths must end with a key. An
+    /// existing value at `to` is replaced.
+    pub fn move_key(mut self, from: &str, to: &str) -> Self {
+        self.steps.push(Step::Move {
+                            from: from.to_owned(),
+                            to: to.to_owned(),
+                        });
+        self
+    }
+
+    /// Inserts `value` at `path` if nothing is there yet, creating mappings
+    /// along the way where needed. The path must end with a key.
+    pub fn set_default<V>(mut self, path: &str, value: V) -> Self
+        where V: Into<Value>
+    {
+        self.steps.push(Step::Default {
+                            path: path.to_owned(),
+                            value: value.into(),
+                        });
+        self
+    }
+
+    /// Applies the steps in order and returns how many of them changed
+    /// `value`.
+    ///
+    /// # Errors
+    ///
+    /// Fails if a path given to `move_key` or `set_default` does not end with
+    /// a key, or if a mapping would have to be created where there is already
+    /// a value other than null. Steps before the failing one stay applied.
+    pub fn apply(&self, value: &mut Value) -> Result<usize> {
+        let mut changed = 0;
+        for step in &self.steps {
+            let applied = match *step {
+                Step::Rename { ref path, ref old, ref new } => value.rename_key(path, old, new),
+                Step::Move { ref from, ref to } => {
+                    let (from_parents, from_key) = last_key(from)?;
+                    let (parents, key) = last_key(to)?;
+                    let found = mapping_mut(value, &from_parents)
+                        .map_or(false, |mapping| mapping.contains_key(&from_key));
+                    if found {
+                        // Create the destination before taking anything, so
+                        // that a failure leaves the source where it was.
+                        create(value, &parents, to)?;
+                        let moved = mapping_mut(value, &from_parents)
+                            .and_then(|mapping| mapping.remove(&from_key));
+                        if let Some(moved) = moved {
+                            create(value, &parents, to)?.insert(key, moved);
+                        }
+                    }
+                    found
+                }
+                Step::Default { ref path, value: ref default } => {
+                    let (parents, key) = last_key(path)?;
+                    let mapping = create(value, &parents, path)?;
+                    if mapping.contains_key(&key) {
+                        false
+                    } else {
+                        mapping.insert(key, default.clone());
+                        true
+                    }
+                }
+            };
+            if applied {
+                changed += 1;
+            }
+        }
+        Ok(changed)
+    }
+}
+
+fn get_mut<'a>(mut value: &'a mut Value, segments: &[Segment]) -> Option<&'a mut Value> {
+    for segment in segments {
+        value = match *segment {
+            Segment::Key(ref key) => value.as_mapping_mut()?.get_mut(&Value::String(key.clone()))?,
+            Segment::Index(index) => value.as_sequence_mut()?.get_mut(index)?,
+        };
+    }
+    Some(value)
+}
+
+/// Splits a path into the segments leading to a mapping and the key within
+/// it.
+fn last_key(path: &str) -> Result<(Vec<Segment>, Value)> {
+    let mut segments = segments(path);
+    match segments.pop() {
+        Some(Segment::Key(key)) => Ok((segments, Value::String(key))),
+        _ => Err(Error::custom(format_args!("migration path `{}` does not end with a key", path))),
+    }
+}
+
+fn mapping_mut<'a>(value: &'a mut Value, segments: &[Segment]) -> Option<&'a mut Mapping> {
+    get_mut(value, segments).and_then(Value::as_mapping_mut)
+}
+
+/// The mapping at a path, creating it and any missing or null parents.
+fn create<'a>(mut value: &'a mut Value, segments: &[Segment], path: &str) -> Result<&'a mut Mapping> {
+    for segment in segments {
+        if value.is_null() {
+            *value = Value::Mapping(Mapping::new());
+        }
+        value = match (value, segment) {
+            (&mut Value::Mapping(ref mut mapping), &Segment::Key(ref key)) => {
+                let key = Value::String(key.clone());
+                if !mapping.contains_key(&key) {
+                    mapping.insert(key.clone(), Value::Mapping(Mapping::new()));
+                }
+                &mut mapping[&key]
+            }
+            (&mut Value::Sequence(ref mut seq), &Segment::Index(index)) if index < seq.len() => {
+                &mut seq[index]
+            }
+            _ => return Err(not_a_mapping(path)),
+        };
+    }
+    if value.is_null() {
+        *value = Value::Mapping(Mapping::new());
+    }
+    match *value {
+        Value::Mapping(ref mut mapping) => Ok(mapping),
+        _ => Err(not_a_mapping(path)),
+    }
+}
+
+fn not_a_mapping(path: &str) -> Error {
+    Error::custom(format_args!("migration path `{}` runs into a value that is not a mapping", path))
+}
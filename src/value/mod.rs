@@ -6,6 +6,9 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
+//! The `Value` enum, a loosely typed way of representing any valid YAML
+//! value, and the `Serializer` that builds one.
+
 #![deny(unsafe_code, missing_docs)]
 
 use std::hash::{Hash, Hasher};
@@ -15,17 +18,24 @@ use serde::de::{
     Deserialize,
     DeserializeOwned,
 };
-use yaml_rust::Yaml;
 
 use error::Error;
 use mapping::Mapping;
-use ser::Serializer;
 
 use self::index::Index;
 pub use number::Number;
+pub use self::diff::DisplayDiff;
+pub use self::debug::ExactDebug;
+pub use self::migrate::Migration;
+pub use self::defaults::SequenceDefaults;
+pub use self::ser::Serializer;
+pub use self::build::{MappingBuilder, SequenceBuilder};
+pub(crate) use self::normalize::canonical_cmp;
+#[cfg(feature = "de")]
+pub(crate) use self::flatten::key_text;
 
 /// Represents any valid YAML value.
-#[derive(Clone, PartialOrd, Debug)]
+#[derive(Clone, PartialOrd)]
 pub enum Value {
     /// Represents a YAML null value.
     Null,
@@ -57,11 +67,11 @@ pub type Sequence = Vec<Value>;
 /// let val = serde_yaml::to_value("s").unwrap();
 /// assert_eq!(val, Value::String("s".to_owned()));
 /// ```
-#[cfg_attr(feature = "cargo-clippy", allow(needless_pass_by_value))]
+#[allow(clippy::needless_pass_by_value)]
 pub fn to_value<T>(value: T) -> Result<Value, Error>
     where T: Serialize
 {
-    value.serialize(Serializer).map(yaml_to_value)
+    value.serialize(Serializer)
 }
 
 /// Interpret a `serde_yaml::Value` as an instance of type `T`.
@@ -547,26 +557,41 @@ impl Value {
             _ => None,
         }
     }
-}
 
-fn yaml_to_value(yaml: Yaml) -> Value {
-    match yaml {
-        Yaml::Real(f) => {
-            match f.parse::<f64>() {
-                Ok(f) => Value::Number(f.into()),
-                Err(_) => Value::String(f),
-            }
-        }
-        Yaml::Integer(i) => Value::Number(i.into()),
-        Yaml::String(s) => Value::String(s),
-        Yaml::Boolean(b) => Value::Bool(b),
-        Yaml::Array(sequence) => Value::Sequence(sequence.into_iter().map(yaml_to_value).collect()),
-        Yaml::Hash(hash) => {
-            Value::Mapping(hash.into_iter().map(|(k, v)| (yaml_to_value(k), yaml_to_value(v))).collect())
+    /// The number of elements of a sequence or entries of a mapping. Strings
+    /// are counted too, by characters rather than bytes, so that a value can
+    /// be checked against a length limit whatever its kind. Returns None for
+    /// null, booleans and numbers.
+    ///
+    /// ```rust
+    /// # use serde_yaml::Value;
+    /// let v: Value = serde_yaml::from_str("{tags: [a, b, c], name: Zoë, port: 80}").unwrap();
+    /// assert_eq!(v.len(), Some(3));
+    /// assert_eq!(v["tags"].len(), Some(3));
+    /// assert_eq!(v["name"].len(), Some(3));
+    /// assert_eq!(v["port"].len(), None);
+    /// ```
+    pub fn len(&self) -> Option<usize> {
+        match *self {
+            Value::String(ref s) => Some(s.chars().count()),
+            Value::Sequence(ref seq) => Some(seq.len()),
+            Value::Mapping(ref map) => Some(map.len()),
+            Value::Null | Value::Bool(_) | Value::Number(_) => None,
         }
-        Yaml::Alias(_) => panic!("alias unsupported"),
-        Yaml::Null => Value::Null,
-        Yaml::BadValue => panic!("bad value"),
+    }
+
+    /// Whether `len` is zero: whether a sequence, mapping or string has
+    /// nothing in it. Returns None for null, booleans and numbers, so
+    /// `Some(true)` picks out the empty ones.
+    ///
+    /// ```rust
+    /// # use serde_yaml::Value;
+    /// let v: Value = serde_yaml::from_str("[[], {}, '', x, ~]").unwrap();
+    /// let empty: Vec<Option<bool>> = v.as_sequence().unwrap().iter().map(Value::is_empty).collect();
+    /// assert_eq!(empty, [Some(true), Some(true), Some(true), Some(false), None]);
+    /// ```
+    pub fn is_empty(&self) -> Option<bool> {
+        self.len().map(|len| len == 0)
     }
 }
 
@@ -588,6 +613,18 @@ impl Hash for Value {
 mod index;
 mod partial_eq;
 mod from;
+mod yaml;
+mod diff;
+mod debug;
+mod normalize;
+mod migrate;
+mod defaults;
+mod flatten;
+mod find;
+mod dotted;
+mod substitute;
+mod estimate;
+mod build;
 
 mod ser;
 mod de;
@@ -0,0 +1,172 @@
+// Copyright 2018 Serde YAML Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::cmp::Ordering;
+use std::mem;
+
+use super::{Number, Value};
+use mapping::Mapping;
+
+impl Value {
+    /// Sorts the keys of every mapping in the tree, including mappings nested
+    /// in keys.
+    ///
+    /// Keys of different types are ordered null, boolean, number, string,
+    /// sequence, mapping. Numbers are ordered by value with NaN last.
+    ///
+    /// ```rust
+    /// # extern crate serde_yaml;
+    /// #
+    /// # fn yaml(i: &str) -> serde_yaml::Value { serde_yaml::from_str(i).unwrap() }
+    /// # fn main() {
+    /// let mut value = yaml("{b: 1, a: {d: 2, c: 3}, 10: x, -1: z}");
+    /// value.sort_keys_recursively();
+    /// assert_eq!(serde_yaml::to_string(&value).unwrap(),
+    ///            "---\n-1: z\n10: x\na:\n  c: 3\n  d: 2\nb: 1");
+    /// # }
+    /// ```
+    pub fn sort_keys_recursively(&mut self) {
+        match *self {
+            Value::Sequence(ref mut seq) => {
+                for item in seq {
+                    item.sort_keys_recursively();
+                }
+            }
+            Value::Mapping(ref mut mapping) => {
+                let mut entries: Vec<(Value, Value)> = mem::replace(mapping, Mapping::new())
+                    .into_iter()
+                    .collect();
+                for entry in &mut entries {
+                    entry.0.sort_keys_recursively();
+                    entry.1.sort_keys_recursively();
+                }
+                entries.sort_by(|a, b| canonical_cmp(&a.0, &b.0));
+                *mapping = entries.into_iter().collect();
+            }
+            _ => {}
+        }
+    }
+
+    /// Replaces every float that holds a whole number representable as `u64`
+    /// or `i64` by that integer, so that `1.0` and `1` compare equal.
+    ///
+    /// Keys are normalized too. If two keys of a mapping become equal, the
+    /// later entry wins.
+    ///
+    /// ```rust
+    /// # extern crate serde_yaml;
+    /// #
+    /// # fn yaml(i: &str) -> serde_yaml::Value { serde_yaml::from_str(i).unwrap() }
+    /// # fn main() {
+    /// let mut value = yaml("[1.0, -2.0, 2.5, 1e3, .inf]");
+    /// value.normalize_numbers();
+    /// assert_eq!(value, yaml("[1, -2, 2.5, 1000, .inf]"));
+    /// # }
+    /// ```
+    pub fn normalize_numbers(&mut self) {
+        match *self {
+            Value::Number(ref mut n) => {
+                if let Some(integer) = whole(n) {
+                    *n = integer;
+                }
+            }
+            Value::Sequence(ref mut seq) => {
+                for item in seq {
+                    item.normalize_numbers();
+                }
+            }
+            Value::Mapping(ref mut mapping) => {
+                let entries = mem::replace(mapping, Mapping::new());
+                for (mut k, mut v) in entries {
+                    k.normalize_numbers();
+                    v.normalize_numbers();
+                    mapping.insert(k, v);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Removes every mapping entry whose value is null, at any depth.
+    ///
+    /// Null elements of sequences are kept, since removing them would shift
+    /// the position of the elements after them.
+    ///
+    /// ```rust
+    /// # extern crate serde_yaml;
+    /// #
+    /// # fn yaml(i: &str) -> serde_yaml::Value { serde_yaml::from_str(i).unwrap() }
+    /// # fn main() {
+    /// let mut value = yaml("{a: ~, b: {c: null, d: 1}, e: [~, 2]}");
+    /// value.strip_nulls();
+    /// assert_eq!(value, yaml("{b: {d: 1}, e: [~, 2]}"));
+    /// # }
+    /// ```
+    pub fn strip_nulls(&mut self) {
+        match *self {
+            Value::Sequence(ref mut seq) => {
+                for item in seq {
+                    item.strip_nulls();
+                }
+            }
+            Value::Mapping(ref mut mapping) => {
+                let entries = mem::replace(mapping, Mapping::new());
+                for (k, mut v) in entries {
+                    if !v.is_null() {
+                        v.strip_nulls();
+                        mapping.insert(k, v);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// The integer equal to `n`, if `n` is a float that has one.
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss, clippy::float_cmp)]
+fn whole(n: &Number) -> Option<Number> {
+    if !n.is_f64() {
+        return None;
+    }
+    let f = n.as_f64()?;
+    if f.fract() != 0.0 {
+        return None;
+    }
+    // 2^64 and -2^63 are exact as f64, unlike u64::MAX and i64::MIN - 1.
+    if (0.0..18446744073709551616.0).contains(&f) {
+        Some(Number::from(f as u64))
+    } else if (-9223372036854775808.0..0.0).contains(&f) {
+        Some(Number::from(f as i64))
+    } else {
+        None
+    }
+}
+
+/// The order of `sort_keys_recursively`.
+pub(crate) fn canonical_cmp(a: &Value, b: &Value) -> Ordering {
+    match (a, b) {
+        (&Value::Number(ref a), &Value::Number(ref b)) => number_cmp(a, b),
+        _ => a.partial_cmp(b).unwrap_or(Ordering::Equal),
+    }
+}
+
+fn number_cmp(a: &Number, b: &Number) -> Ordering {
+    if let (Some(a), Some(b)) = (integer(a), integer(b)) {
+        return a.cmp(&b);
+    }
+    match (a.as_f64(), b.as_f64()) {
+        (Some(a), Some(b)) if a.is_nan() || b.is_nan() => a.is_nan().cmp(&b.is_nan()),
+        (Some(a), Some(b)) => a.partial_cmp(&b).unwrap_or(Ordering::Equal),
+        _ => Ordering::Equal,
+    }
+}
+
+fn integer(n: &Number) -> Option<i128> {
+    n.as_i64().map(i128::from).or_else(|| n.as_u64().map(i128::from))
+}
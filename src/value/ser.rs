@@ -1,4 +1,10 @@
 use serde::{self, Serialize};
+use serde::ser::{SerializeMap, SerializeSeq, SerializeStruct, SerializeStructVariant, SerializeTuple,
+                 SerializeTupleStruct, SerializeTupleVariant};
+use yaml_rust::Yaml;
+
+use error::Error;
+use ser;
 
 use super::Value;
 
@@ -13,7 +19,6 @@ impl Serialize for Value {
             Value::String(ref s) => serializer.serialize_str(s),
             Value::Sequence(ref seq) => seq.serialize(serializer),
             Value::Mapping(ref hash) => {
-                use serde::ser::SerializeMap;
                 let mut map = serializer.serialize_map(Some(hash.len()))?;
                 for (k, v) in hash {
                     map.serialize_entry(k, v)?;
@@ -23,3 +28,274 @@ impl Serialize for Value {
         }
     }
 }
+
+/// Serializer whose output is a `Value`, as used by `to_value`.
+///
+/// This is the serializer to hand to a `Serialize` impl, or to a serializer
+/// adapter, that needs to produce a `Value` rather than text. The result is
+/// the same as `to_value`.
+///
+/// ```rust
+/// # extern crate serde;
+/// # extern crate serde_yaml;
+/// #
+/// # use std::collections::BTreeMap;
+/// #
+/// # fn main() {
+/// use serde::Serialize;
+/// use serde_yaml::value::Serializer;
+///
+/// let mut limits = BTreeMap::new();
+/// limits.insert("cpu", 2);
+/// let value = limits.serialize(Serializer).unwrap();
+/// assert_eq!(value["cpu"], 2);
+/// assert_eq!(value, serde_yaml::to_value(&limits).unwrap());
+/// # }
+/// ```
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Serializer;
+
+/// Serializes like `ser::Serializer` and converts the result into a `Value`.
+macro_rules! serialize_to_value {
+    ($($method:ident ( $($arg:ident : $ty:ty),* ))*) => {
+        $(
+            fn $method(self, $($arg: $ty),*) -> Result<Value, Error> {
+                Value::from_serialized(serde::Serializer::$method(ser::Serializer, $($arg),*)?)
+            }
+        )*
+    };
+}
+
+impl serde::Serializer for Serializer {
+    type Ok = Value;
+    type Error = Error;
+
+    type SerializeSeq = Compound<ser::SerializeArray>;
+    type SerializeTuple = Compound<ser::SerializeArray>;
+    type SerializeTupleStruct = Compound<ser::SerializeArray>;
+    type SerializeTupleVariant = Compound<ser::SerializeTupleVariant>;
+    type SerializeMap = Compound<ser::SerializeMap>;
+    type SerializeStruct = Compound<ser::SerializeStruct>;
+    type SerializeStructVariant = Compound<ser::SerializeStructVariant>;
+
+    serialize_to_value! {
+        serialize_bool(v: bool)
+        serialize_i8(v: i8)
+        serialize_i16(v: i16)
+        serialize_i32(v: i32)
+        serialize_i64(v: i64)
+        serialize_i128(v: i128)
+        serialize_u8(v: u8)
+        serialize_u16(v: u16)
+        serialize_u32(v: u32)
+        serialize_u64(v: u64)
+        serialize_u128(v: u128)
+        serialize_f32(v: f32)
+        serialize_f64(v: f64)
+        serialize_char(v: char)
+        serialize_str(v: &str)
+        serialize_bytes(v: &[u8])
+        serialize_none()
+        serialize_unit()
+        serialize_unit_struct(name: &'static str)
+        serialize_unit_variant(name: &'static str, variant_index: u32, variant: &'static str)
+    }
+
+    fn serialize_some<T: ?Sized>(self, value: &T) -> Result<Value, Error>
+        where T: Serialize
+    {
+        Value::from_serialized(serde::Serializer::serialize_some(ser::Serializer, value)?)
+    }
+
+    fn serialize_newtype_struct<T: ?Sized>(self, name: &'static str, value: &T) -> Result<Value, Error>
+        where T: Serialize
+    {
+        Value::from_serialized(serde::Serializer::serialize_newtype_struct(ser::Serializer, name, value)?)
+    }
+
+    fn serialize_newtype_variant<T: ?Sized>(self,
+                                            name: &'static str,
+                                            variant_index: u32,
+                                            variant: &'static str,
+                                            value: &T)
+                                            -> Result<Value, Error>
+        where T: Serialize
+    {
+        let yaml = serde::Serializer::serialize_newtype_variant(ser::Serializer, name, variant_index, variant, value)?;
+        Value::from_serialized(yaml)
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq, Error> {
+        serde::Serializer::serialize_seq(ser::Serializer, len).map(Compound)
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple, Error> {
+        serde::Serializer::serialize_tuple(ser::Serializer, len).map(Compound)
+    }
+
+    fn serialize_tuple_struct(self, name: &'static str, len: usize) -> Result<Self::SerializeTupleStruct, Error> {
+        serde::Serializer::serialize_tuple_struct(ser::Serializer, name, len).map(Compound)
+    }
+
+    fn serialize_tuple_variant(self,
+                               name: &'static str,
+                               variant_index: u32,
+                               variant: &'static str,
+                               len: usize)
+                               -> Result<Self::SerializeTupleVariant, Error> {
+        serde::Serializer::serialize_tuple_variant(ser::Serializer, name, variant_index, variant, len).map(Compound)
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<Self::SerializeMap, Error> {
+        serde::Serializer::serialize_map(ser::Serializer, len).map(Compound)
+    }
+
+    fn serialize_struct(self, name: &'static str, len: usize) -> Result<Self::SerializeStruct, Error> {
+        serde::Serializer::serialize_struct(ser::Serializer, name, len).map(Compound)
+    }
+
+    fn serialize_struct_variant(self,
+                                name: &'static str,
+                                variant_index: u32,
+                                variant: &'static str,
+                                len: usize)
+                                -> Result<Self::SerializeStructVariant, Error> {
+        serde::Serializer::serialize_struct_variant(ser::Serializer, name, variant_index, variant, len).map(Compound)
+    }
+}
+
+/// A sequence, tuple, map or struct being serialized into a `Value`.
+#[doc(hidden)]
+pub struct Compound<S>(S);
+
+impl<S> SerializeSeq for Compound<S>
+    where S: SerializeSeq<Ok = Yaml, Error = Error>
+{
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_element<T: ?Sized>(&mut self, value: &T) -> Result<(), Error>
+        where T: Serialize
+    {
+        self.0.serialize_element(value)
+    }
+
+    fn end(self) -> Result<Value, Error> {
+        Value::from_serialized(self.0.end()?)
+    }
+}
+
+impl<S> SerializeTuple for Compound<S>
+    where S: SerializeTuple<Ok = Yaml, Error = Error>
+{
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_element<T: ?Sized>(&mut self, value: &T) -> Result<(), Error>
+        where T: Serialize
+    {
+        self.0.serialize_element(value)
+    }
+
+    fn end(self) -> Result<Value, Error> {
+        Value::from_serialized(self.0.end()?)
+    }
+}
+
+impl<S> SerializeTupleStruct for Compound<S>
+    where S: SerializeTupleStruct<Ok = Yaml, Error = Error>
+{
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_field<T: ?Sized>(&mut self, value: &T) -> Result<(), Error>
+        where T: Serialize
+    {
+        self.0.serialize_field(value)
+    }
+
+    fn end(self) -> Result<Value, Error> {
+        Value::from_serialized(self.0.end()?)
+    }
+}
+
+impl<S> SerializeTupleVariant for Compound<S>
+    where S: SerializeTupleVariant<Ok = Yaml, Error = Error>
+{
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_field<T: ?Sized>(&mut self, value: &T) -> Result<(), Error>
+        where T: Serialize
+    {
+        self.0.serialize_field(value)
+    }
+
+    fn end(self) -> Result<Value, Error> {
+        Value::from_serialized(self.0.end()?)
+    }
+}
+
+impl<S> SerializeMap for Compound<S>
+    where S: SerializeMap<Ok = Yaml, Error = Error>
+{
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_key<T: ?Sized>(&mut self, key: &T) -> Result<(), Error>
+        where T: Serialize
+    {
+        self.0.serialize_key(key)
+    }
+
+    fn serialize_value<T: ?Sized>(&mut self, value: &T) -> Result<(), Error>
+        where T: Serialize
+    {
+        self.0.serialize_value(value)
+    }
+
+    fn serialize_entry<K: ?Sized, V: ?Sized>(&mut self, key: &K, value: &V) -> Result<(), Error>
+        where K: Serialize,
+              V: Serialize
+    {
+        self.0.serialize_entry(key, value)
+    }
+
+    fn end(self) -> Result<Value, Error> {
+        Value::from_serialized(self.0.end()?)
+    }
+}
+
+impl<S> SerializeStruct for Compound<S>
+    where S: SerializeStruct<Ok = Yaml, Error = Error>
+{
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_field<T: ?Sized>(&mut self, key: &'static str, value: &T) -> Result<(), Error>
+        where T: Serialize
+    {
+        self.0.serialize_field(key, value)
+    }
+
+    fn end(self) -> Result<Value, Error> {
+        Value::from_serialized(self.0.end()?)
+    }
+}
+
+impl<S> SerializeStructVariant for Compound<S>
+    where S: SerializeStructVariant<Ok = Yaml, Error = Error>
+{
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_field<T: ?Sized>(&mut self, key: &'static str, value: &T) -> Result<(), Error>
+        where T: Serialize
+    {
+        self.0.serialize_field(key, value)
+    }
+
+    fn end(self) -> Result<Value, Error> {
+        Value::from_serialized(self.0.end()?)
+    }
+}
@@ -0,0 +1,253 @@
+// Copyright 2018 Serde YAML Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::mem;
+
+use serde::de::Error as SError;
+
+use super::Value;
+use error::{Error, Result};
+use mapping::Mapping;
+use path::{segments, Segment};
+
+impl Value {
+    /// Replaces references like `${database.host}` in string scalars with
+    /// the values they refer to elsewhere in the same tree, the way HOCON
+    /// substitutions work. Paths are written the same way as in error
+    /// messages, like `servers[0].port`, and start from the root.
+    ///
+    /// A string that is nothing but one reference becomes a copy of the value
+    /// it refers to, which may be a number or a whole mapping. References
+    /// that are part of a longer string are replaced by the text of the
+    /// string, number or boolean they refer to. The values referred to may
+    /// contain references of their own. `$${` is written as a literal `${`.
+    /// Mapping keys are left alone.
+    ///
+    /// # Errors
+    ///
+    /// Fails, leaving the value unchanged, if a reference refers to a path
+    /// that does not exist, if references form a cycle, or if a reference
+    /// inside a longer string refers to null or a collection.
+    ///
+    /// ```rust
+    /// # extern crate serde_yaml;
+    /// #
+    /// # fn yaml(i: &str) -> serde_yaml::Value { serde_yaml::from_str(i).unwrap() }
+    /// # fn main() {
+    /// let mut config = yaml(concat!("database: {host: db.internal, port: 5432}\n",
+    ///                               "url: postgres://${database.host}:${database.port}/app\n",
+    ///                               "replica: ${database}\n"));
+    /// config.resolve_substitutions().unwrap();
+    /// assert_eq!(config["url"], "postgres://db.internal:5432/app");
+    /// assert_eq!(config["replica"], config["database"]);
+    ///
+    /// let mut config = yaml("a: ${b}\nb: ${a}");
+    /// let err = config.resolve_substitutions().unwrap_err();
+    /// assert_eq!(err.to_string(), "references form a cycle: `b` -> `a` -> `b`");
+    /// # }
+    /// ```
+    pub fn resolve_substitutions(&mut self) -> Result<()> {
+        let root = mem::replace(self, Value::Null);
+        let result = Resolver {
+            root: &root,
+            resolved: HashMap::new(),
+            stack: Vec::new(),
+        }.resolve(&root, ".");
+        match result {
+            Ok(value) => {
+                *self = value;
+                Ok(())
+            }
+            Err(err) => {
+                *self = root;
+                Err(err)
+            }
+        }
+    }
+}
+
+struct Resolver<'a> {
+    root: &'a Value,
+    /// Values that references have already been resolved to, by path.
+    resolved: HashMap<String, Value>,
+    /// The paths being resolved, outermost first.
+    stack: Vec<String>,
+}
+
+enum Part<'a> {
+    Text(&'a str),
+    Reference(&'a str),
+}
+
+impl<'a> Resolver<'a> {
+    /// A copy of `value`, which is at `path`, with every reference in it
+    /// replaced.
+    fn resolve(&mut self, value: &'a Value, path: &str) -> Result<Value> {
+        match *value {
+            Value::String(ref s) => self.resolve_string(s, path),
+            Value::Sequence(ref seq) => {
+                let mut resolved = Vec::with_capacity(seq.len());
+                for (i, element) in seq.iter().enumerate() {
+                    resolved.push(self.resolve(element, &child(path, &Segment::Index(i)))?);
+                }
+                Ok(Value::Sequence(resolved))
+            }
+            Value::Mapping(ref mapping) => {
+                let mut resolved = Mapping::new();
+                for (key, value) in mapping {
+                    let value_path = match *key {
+                        Value::String(ref key) => child(path, &Segment::Key(key.clone())),
+                        _ => path.to_owned(),
+                    };
+                    resolved.insert(key.clone(), self.resolve(value, &value_path)?);
+                }
+                Ok(Value::Mapping(resolved))
+            }
+            ref scalar => Ok(scalar.clone()),
+        }
+    }
+
+    fn resolve_string(&mut self, s: &'a str, path: &str) -> Result<Value> {
+        let parts = match parts(s) {
+            Some(parts) => parts?,
+            None => return Ok(Value::String(s.to_owned())),
+        };
+        if let [Part::Reference(reference)] = parts[..] {
+            return self.lookup(reference, path);
+        }
+        let mut text = String::new();
+        for part in parts {
+            match part {
+                Part::Text(literal) => text.push_str(literal),
+                Part::Reference(reference) => {
+                    match self.lookup(reference, path)? {
+                        Value::String(ref s) => text.push_str(s),
+                        Value::Number(ref n) => write!(text, "{}", n).unwrap(),
+                        Value::Bool(b) => write!(text, "{}", b).unwrap(),
+                        _ => {
+                            return Err(Error::custom(format_args!(
+                                "reference `${{{}}}` at `{}` is part of a string but refers to {}",
+                                reference,
+                                path,
+                                "null or a collection")));
+                        }
+                    }
+                }
+            }
+        }
+        Ok(Value::String(text))
+    }
+
+    /// The resolved value that `reference`, found at `path`, refers to.
+    fn lookup(&mut self, reference: &str, path: &str) -> Result<Value> {
+        let target = segments(reference.trim());
+        let key = display(&target);
+        if let Some(value) = self.resolved.get(&key) {
+            return Ok(value.clone());
+        }
+        if let Some(start) = self.stack.iter().position(|open| *open == key) {
+            let mut cycle = String::new();
+            for open in &self.stack[start..] {
+                write!(cycle, "`{}` -> ", open).unwrap();
+            }
+            write!(cycle, "`{}`", key).unwrap();
+            return Err(Error::custom(format_args!("references form a cycle: {}", cycle)));
+        }
+        self.stack.push(key.clone());
+        let mut node = self.root;
+        for (walked, segment) in target.iter().enumerate() {
+            // A path may run through a value that is itself a reference.
+            if let Value::String(ref s) = *node {
+                if let Some(Ok(parts)) = parts(s) {
+                    if let [Part::Reference(inner)] = parts[..] {
+                        let mut redirected = segments(inner.trim());
+                        redirected.extend(target[walked..].iter().cloned());
+                        let value = self.lookup(&display(&redirected), path);
+                        self.stack.pop();
+                        return value;
+                    }
+                }
+            }
+            let next = match (node, segment) {
+                (&Value::Mapping(ref mapping), &Segment::Key(ref key)) => {
+                    mapping.get(&Value::String(key.clone()))
+                }
+                (&Value::Sequence(ref seq), &Segment::Index(index)) => seq.get(index),
+                _ => None,
+            };
+            node = match next {
+                Some(next) => next,
+                None => {
+                    self.stack.pop();
+                    return Err(Error::custom(format_args!("reference `${{{}}}` at `{}` refers to {}",
+                                                          reference,
+                                                          path,
+                                                          "a path that does not exist")));
+                }
+            };
+        }
+        let value = self.resolve(node, &key);
+        self.stack.pop();
+        let value = value?;
+        self.resolved.insert(key, value.clone());
+        Ok(value)
+    }
+}
+
+/// Splits a string into text and references, or returns None if it has
+/// neither references nor escapes.
+fn parts(s: &str) -> Option<Result<Vec<Part>>> {
+    if !s.contains("${") {
+        return None;
+    }
+    let mut parts = Vec::new();
+    let mut rest = s;
+    while let Some(open) = rest.find("${") {
+        if rest[..open].ends_with('$') {
+            parts.push(Part::Text(&rest[..open - 1]));
+            parts.push(Part::Text("${"));
+            rest = &rest[open + 2..];
+            continue;
+        }
+        let close = match rest[open..].find('}') {
+            Some(close) => open + close,
+            None => {
+                return Some(Err(Error::custom(format_args!("unterminated reference in `{}`", s))));
+            }
+        };
+        if open > 0 {
+            parts.push(Part::Text(&rest[..open]));
+        }
+        parts.push(Part::Reference(&rest[open + 2..close]));
+        rest = &rest[close + 1..];
+    }
+    if !rest.is_empty() {
+        parts.push(Part::Text(rest));
+    }
+    Some(Ok(parts))
+}
+
+fn child(path: &str, segment: &Segment) -> String {
+    let mut child = if path == "." { String::new() } else { path.to_owned() };
+    match *segment {
+        Segment::Key(ref key) => {
+            if !child.is_empty() {
+                child.push('.');
+            }
+            child.push_str(key);
+        }
+        Segment::Index(index) => write!(child, "[{}]", index).unwrap(),
+    }
+    child
+}
+
+fn display(segments: &[Segment]) -> String {
+    segments.iter().fold(".".to_owned(), |path, segment| child(&path, segment))
+}
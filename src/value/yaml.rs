@@ -0,0 +1,144 @@
+// Copyright 2018 Serde YAML Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use serde::de::Error as SError;
+use yaml_rust::Yaml;
+use yaml_rust::yaml::Hash;
+
+use super::Value;
+use error::Error;
+use mapping::Mapping;
+use number::{format_float, parse_float};
+use tags;
+
+impl Value {
+    /// Convert a document loaded by `yaml_rust` into a `Value`.
+    ///
+    /// This is useful when migrating code from `yaml_rust` one piece at a
+    /// time. Integers that do not fit in `i64` are kept as `u64` where
+    /// possible, and the YAML spellings `.inf`, `-.inf` and `.nan` are
+    /// understood.
+    ///
+    /// # Errors
+    ///
+    /// Fails on `Yaml::Alias` and `Yaml::BadValue`, neither of which has a
+    /// counterpart in `Value`. `yaml_rust::YamlLoader` resolves aliases itself
+    /// so only `BadValue` is produced by it, for example for `!!int abc`.
+    ///
+    /// ```rust
+    /// # extern crate serde_yaml;
+    /// # extern crate yaml_rust;
+    /// #
+    /// # fn main() {
+    /// use serde_yaml::Value;
+    /// use yaml_rust::YamlLoader;
+    ///
+    /// let docs = YamlLoader::load_from_str("[1, .inf, x]").unwrap();
+    /// let value = Value::from_yaml(docs[0].clone()).unwrap();
+    /// assert_eq!(value[0], 1);
+    /// assert_eq!(value[1].as_f64(), Some(std::f64::INFINITY));
+    /// assert_eq!(value[2], "x");
+    /// # }
+    /// ```
+    pub fn from_yaml(yaml: Yaml) -> Result<Value, Error> {
+        from_yaml(yaml, false)
+    }
+
+    /// Converts a node built by this crate's serializer, in which a mapping
+    /// key serialized from bytes is a real holding a `!!binary` scalar. See
+    /// `tags::binary_key`.
+    pub(crate) fn from_serialized(yaml: Yaml) -> Result<Value, Error> {
+        from_yaml(yaml, true)
+    }
+}
+
+fn from_yaml(yaml: Yaml, binary_keys: bool) -> Result<Value, Error> {
+    match yaml {
+        Yaml::Real(f) => real_to_value(f, binary_keys),
+        Yaml::Integer(i) => Ok(Value::Number(i.into())),
+        Yaml::String(s) => Ok(Value::String(s)),
+        Yaml::Boolean(b) => Ok(Value::Bool(b)),
+        Yaml::Array(sequence) => {
+            sequence.into_iter()
+                .map(|node| from_yaml(node, binary_keys))
+                .collect::<Result<_, _>>()
+                .map(Value::Sequence)
+        }
+        Yaml::Hash(hash) => {
+            let mut mapping = Mapping::with_capacity(hash.len());
+            for (k, v) in hash {
+                mapping.insert(from_yaml(k, binary_keys)?, from_yaml(v, binary_keys)?);
+            }
+            Ok(Value::Mapping(mapping))
+        }
+        Yaml::Alias(_) => Err(Error::custom("cannot convert an unresolved YAML alias to a Value")),
+        Yaml::Null => Ok(Value::Null),
+        Yaml::BadValue => Err(Error::custom("cannot convert a bad YAML value to a Value")),
+    }
+}
+
+fn real_to_value(f: String, binary_keys: bool) -> Result<Value, Error> {
+    if binary_keys {
+        if let Some(bytes) = tags::binary_key_text(&f).and_then(tags::decode_base64) {
+            return Ok(Value::Sequence(bytes.into_iter().map(Value::from).collect()));
+        }
+    }
+    if let Ok(n) = f.parse::<u64>() {
+        return Ok(Value::Number(n.into()));
+    }
+    if let Ok(n) = f.parse::<i64>() {
+        return Ok(Value::Number(n.into()));
+    }
+    match parse_float(&f, false) {
+        Some(n) => Ok(Value::Number(n.into())),
+        None => Err(Error::custom(format_args!("invalid YAML float `{}`", f))),
+    }
+}
+
+impl From<Value> for Yaml {
+    /// Convert a `Value` into the document type of `yaml_rust`.
+    ///
+    /// Integers outside the range of `i64` and all floats become `Yaml::Real`,
+    /// using the YAML spellings for infinities and NaN so that
+    /// `Yaml::as_f64` understands them.
+    ///
+    /// ```rust
+    /// # extern crate serde_yaml;
+    /// # extern crate yaml_rust;
+    /// #
+    /// # fn main() {
+    /// use serde_yaml::Value;
+    /// use yaml_rust::Yaml;
+    ///
+    /// let value: Value = serde_yaml::from_str("{a: 1, b: [true, 2.5]}").unwrap();
+    /// let yaml = Yaml::from(value);
+    /// assert_eq!(yaml["a"].as_i64(), Some(1));
+    /// assert_eq!(yaml["b"][1].as_f64(), Some(2.5));
+    /// # }
+    /// ```
+    fn from(value: Value) -> Self {
+        match value {
+            Value::Null => Yaml::Null,
+            Value::Bool(b) => Yaml::Boolean(b),
+            Value::Number(n) => {
+                if let Some(i) = n.as_i64() {
+                    Yaml::Integer(i)
+                } else if let Some(u) = n.as_u64() {
+                    Yaml::Real(u.to_string())
+                } else {
+                    Yaml::Real(format_float(n.as_f64().unwrap_or(::std::f64::NAN)))
+                }
+            }
+            Value::String(s) => Yaml::String(s),
+            Value::Sequence(sequence) => Yaml::Array(sequence.into_iter().map(Yaml::from).collect()),
+            Value::Mapping(mapping) => {
+                Yaml::Hash(mapping.into_iter().map(|(k, v)| (Yaml::from(k), Yaml::from(v))).collect::<Hash>())
+            }
+        }
+    }
+}
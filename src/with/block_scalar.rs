@@ -0,0 +1,56 @@
+// Copyright 2018 Serde YAML Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Strings written as literal block scalars.
+//!
+//! This is `Writer::block_strings` for a single field, for scripts and other
+//! text whose lines should stay lines in the file. A string that a block
+//! scalar cannot hold exactly is written as usual. The field is read as
+//! usual.
+//!
+//! ```rust
+//! #[macro_use]
+//! extern crate serde_derive;
+//! extern crate serde_yaml;
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Step {
+//!     #[serde(with = "serde_yaml::with::block_scalar")]
+//!     run: String,
+//! }
+//!
+//! # fn main() {
+//! let step = Step { run: "cargo build\ncargo test".to_owned() };
+//! let yaml = "---\nrun: |-\n  cargo build\n  cargo test";
+//! assert_eq!(serde_yaml::to_string(&step).unwrap(), yaml);
+//! let step: Step = serde_yaml::from_str(yaml).unwrap();
+//! assert_eq!(step.run, "cargo build\ncargo test");
+//! # }
+//! ```
+
+use serde::de::{Deserialize, Deserializer};
+use serde::ser::{Serialize, Serializer};
+
+use style::{Style, Styled};
+
+/// Serializes `value`, as a literal block scalar if it is a string that
+/// fits in one.
+pub fn serialize<T: ?Sized, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+    where T: Serialize,
+          S: Serializer
+{
+    Styled(Style::Literal, value).serialize(serializer)
+}
+
+/// Deserializes the value as it would be without the adapter.
+pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+    where T: Deserialize<'de>,
+          D: Deserializer<'de>
+{
+    T::deserialize(deserializer)
+}
@@ -0,0 +1,134 @@
+// Copyright 2018 Serde YAML Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Byte counts in `u64` fields written like `512`, `10MiB` or `1.5 GB`.
+//!
+//! The binary units `KiB`, `MiB`, `GiB`, `TiB`, `PiB` and `EiB` are powers of
+//! 1024 and the decimal units `kB` (or `KB`), `MB`, `GB`, `TB`, `PB` and `EB`
+//! powers of 1000. A number without a unit, or with `B`, is a number of
+//! bytes. A fraction is allowed as long as the result is a whole number of
+//! bytes.
+//!
+//! ```rust
+//! #[macro_use]
+//! extern crate serde_derive;
+//! extern crate serde_yaml;
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Limits {
+//!     #[serde(with = "serde_yaml::with::byte_size")]
+//!     upload: u64,
+//!     #[serde(with = "serde_yaml::with::byte_size")]
+//!     cache: u64,
+//! }
+//!
+//! # fn main() {
+//! let limits: Limits = serde_yaml::from_str("{upload: 10MiB, cache: 1.5 GB}").unwrap();
+//! assert_eq!(limits.upload, 10 * 1024 * 1024);
+//! assert_eq!(limits.cache, 1_500_000_000);
+//! assert_eq!(serde_yaml::to_string(&limits).unwrap(), "---\nupload: 10MiB\ncache: 1500MB");
+//! # }
+//! ```
+
+use std::fmt;
+
+use serde::de::{self, Deserializer, Unexpected, Visitor};
+use serde::ser::Serializer;
+
+const EXPECTED: &str = "a byte size like `512`, `10MiB` or `1.5 GB`";
+
+/// Units from the largest down, so that serializing can pick the first one
+/// that divides the size exactly.
+const UNITS: &[(&str, u64)] = &[("EiB", 1 << 60),
+                                ("EB", 1_000_000_000_000_000_000),
+                                ("PiB", 1 << 50),
+                                ("PB", 1_000_000_000_000_000),
+                                ("TiB", 1 << 40),
+                                ("TB", 1_000_000_000_000),
+                                ("GiB", 1 << 30),
+                                ("GB", 1_000_000_000),
+                                ("MiB", 1 << 20),
+                                ("MB", 1_000_000),
+                                ("KiB", 1 << 10),
+                                ("kB", 1000),
+                                ("KB", 1000),
+                                ("B", 1)];
+
+/// Serializes `bytes` with the largest unit that divides it exactly, as in
+/// `10MiB` or `1500MB`, or as `0B`.
+pub fn serialize<S>(bytes: &u64, serializer: S) -> Result<S::Ok, S::Error>
+    where S: Serializer
+{
+    let &(unit, size) = UNITS.iter()
+        .find(|&&(_, size)| *bytes != 0 && bytes % size == 0)
+        .unwrap_or(&("B", 1));
+    serializer.collect_str(&format_args!("{}{}", bytes / size, unit))
+}
+
+/// Deserializes a byte count from a number with an optional unit.
+pub fn deserialize<'de, D>(deserializer: D) -> Result<u64, D::Error>
+    where D: Deserializer<'de>
+{
+    deserializer.deserialize_str(ByteSizeVisitor)
+}
+
+struct ByteSizeVisitor;
+
+impl<'de> Visitor<'de> for ByteSizeVisitor {
+    type Value = u64;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str(EXPECTED)
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<u64, E>
+        where E: de::Error
+    {
+        parse(v).ok_or_else(|| E::invalid_value(Unexpected::Str(v), &EXPECTED))
+    }
+
+    fn visit_u64<E>(self, v: u64) -> Result<u64, E>
+        where E: de::Error
+    {
+        Ok(v)
+    }
+}
+
+/// Parses a byte size, or returns None if it is malformed, not a whole
+/// number of bytes, or does not fit in a `u64`.
+fn parse(v: &str) -> Option<u64> {
+    let v = v.trim();
+    let number = v.find(|c: char| !c.is_ascii_digit() && c != '.').unwrap_or(v.len());
+    let unit = v[number..].trim_start();
+    let size = if unit.is_empty() {
+        1
+    } else {
+        UNITS.iter().find(|&&(name, _)| name == unit)?.1
+    };
+    let (whole, fraction) = match v[..number].find('.') {
+        Some(point) => (&v[..point], &v[point + 1..number]),
+        None => (&v[..number], ""),
+    };
+    if whole.is_empty() && fraction.is_empty() || fraction.contains('.') {
+        return None;
+    }
+    let whole: u128 = if whole.is_empty() { 0 } else { whole.parse().ok()? };
+    let mut bytes = whole.checked_mul(u128::from(size))?;
+    if !fraction.is_empty() {
+        let scale = 10u128.checked_pow(fraction.len() as u32)?;
+        let scaled = fraction.parse::<u128>().ok()?.checked_mul(u128::from(size))?;
+        if scaled % scale != 0 {
+            return None;
+        }
+        bytes += scaled / scale;
+    }
+    if bytes > u128::from(u64::MAX) {
+        return None;
+    }
+    Some(bytes as u64)
+}
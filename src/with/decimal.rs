@@ -0,0 +1,113 @@
+// Copyright 2018 Serde YAML Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Numbers written and read through their text, without passing through
+//! `f64`.
+//!
+//! Types like `rust_decimal::Decimal`, `bigdecimal::BigDecimal` and
+//! `num_bigint::BigInt` serialize as strings, which would come out quoted, and
+//! deserialize plain numbers from the `f64` they were resolved to, which loses
+//! digits. With this adapter the
+//! value is written as a plain number exactly as its `Display` impl spells
+//! it, and read back by handing the text of the scalar to its `FromStr` impl.
+//! It works for any type with those two impls, including `u128` and `i128`.
+//!
+//! ```rust
+//! #[macro_use]
+//! extern crate serde_derive;
+//! extern crate serde_yaml;
+//!
+//! #[derive(Serialize, Deserialize, PartialEq, Debug)]
+//! struct Account {
+//!     #[serde(with = "serde_yaml::with::decimal")]
+//!     balance: u128,
+//! }
+//!
+//! # fn main() {
+//! let yaml = "---\nbalance: 340282366920938463463374607431768211455";
+//! let account: Account = serde_yaml::from_str(yaml).unwrap();
+//! assert_eq!(account.balance, std::u128::MAX);
+//! assert_eq!(serde_yaml::to_string(&account).unwrap(), yaml);
+//! # }
+//! ```
+//!
+//! Quoted scalars are accepted too. When deserializing from something other
+//! than YAML text, such as a `Value`, the number may already have been
+//! converted to `f64` and is read from that.
+
+use std::fmt::{self, Display};
+use std::marker::PhantomData;
+use std::str::FromStr;
+
+use serde::de::{self, Deserializer, Unexpected, Visitor};
+use serde::ser::Serializer;
+
+use number::{self, format_float};
+
+/// Serializes `value` as a plain number spelled by its `Display` impl. The
+/// text has to be a decimal number, optionally with a fraction and exponent.
+pub fn serialize<T: ?Sized, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+    where T: Display,
+          S: Serializer
+{
+    serializer.serialize_newtype_struct(number::TOKEN, &value.to_string())
+}
+
+/// Deserializes a number by passing the text of the scalar to `T::from_str`.
+pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+    where T: FromStr,
+          D: Deserializer<'de>
+{
+    deserializer.deserialize_str(DecimalVisitor(PhantomData))
+}
+
+struct DecimalVisitor<T>(PhantomData<T>);
+
+impl<T> DecimalVisitor<T>
+    where T: FromStr
+{
+    fn parse<E>(&self, v: &str) -> Result<T, E>
+        where E: de::Error
+    {
+        v.parse().map_err(|_| E::invalid_value(Unexpected::Str(v), self))
+    }
+}
+
+impl<'de, T> Visitor<'de> for DecimalVisitor<T>
+    where T: FromStr
+{
+    type Value = T;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a number")
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<T, E>
+        where E: de::Error
+    {
+        self.parse(v)
+    }
+
+    fn visit_u64<E>(self, v: u64) -> Result<T, E>
+        where E: de::Error
+    {
+        self.parse(&v.to_string())
+    }
+
+    fn visit_i64<E>(self, v: i64) -> Result<T, E>
+        where E: de::Error
+    {
+        self.parse(&v.to_string())
+    }
+
+    fn visit_f64<E>(self, v: f64) -> Result<T, E>
+        where E: de::Error
+    {
+        self.parse(&format_float(v))
+    }
+}
@@ -0,0 +1,111 @@
+// Copyright 2018 Serde YAML Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Scalars written through `Display` and read through `FromStr`.
+//!
+//! Types like `uuid::Uuid`, `url::Url`, `std::net::IpAddr` and
+//! `std::net::SocketAddr` already have a text form that config files use.
+//! With this adapter they are written as that text, as a plain scalar unless
+//! it needs quotes, and read back by passing the text of the scalar to
+//! `FromStr`. When that fails, the error quotes the rejected text along with
+//! the reason, where their own `Deserialize` impls often give only the reason.
+//!
+//! ```rust
+//! #[macro_use]
+//! extern crate serde_derive;
+//! extern crate serde_yaml;
+//!
+//! use std::net::IpAddr;
+//!
+//! #[derive(Serialize, Deserialize, Debug)]
+//! struct Listen {
+//!     #[serde(with = "serde_yaml::with::display_fromstr")]
+//!     addr: IpAddr,
+//! }
+//!
+//! # fn main() {
+//! let listen: Listen = serde_yaml::from_str("addr: 10.0.0.1").unwrap();
+//! assert_eq!(serde_yaml::to_string(&listen).unwrap(), "---\naddr: 10.0.0.1");
+//!
+//! let err = serde_yaml::from_str::<Listen>("addr: 10.0.0.256").unwrap_err();
+//! assert_eq!(err.to_string(),
+//!            "addr: invalid value `10.0.0.256`: invalid IP address syntax at line 1 column 7");
+//! # }
+//! ```
+//!
+//! Any scalar is accepted, so a field of a type that parses from numbers also
+//! takes plain numbers like `8080`.
+
+use std::fmt::{self, Display};
+use std::marker::PhantomData;
+use std::str::FromStr;
+
+use serde::de::{self, Deserializer, Visitor};
+use serde::ser::Serializer;
+
+use number::format_float;
+
+/// Serializes `value` as a string spelled by its `Display` impl.
+pub fn serialize<T: ?Sized, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+    where T: Display,
+          S: Serializer
+{
+    serializer.collect_str(value)
+}
+
+/// Deserializes a value by passing the text of the scalar to `T::from_str`.
+pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+    where T: FromStr,
+          T::Err: Display,
+          D: Deserializer<'de>
+{
+    deserializer.deserialize_str(FromStrVisitor(PhantomData))
+}
+
+struct FromStrVisitor<T>(PhantomData<T>);
+
+impl<'de, T> Visitor<'de> for FromStrVisitor<T>
+    where T: FromStr,
+          T::Err: Display
+{
+    type Value = T;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a scalar")
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<T, E>
+        where E: de::Error
+    {
+        v.parse().map_err(|err| E::custom(format_args!("invalid value `{}`: {}", v, err)))
+    }
+
+    fn visit_bool<E>(self, v: bool) -> Result<T, E>
+        where E: de::Error
+    {
+        self.visit_str(if v { "true" } else { "false" })
+    }
+
+    fn visit_u64<E>(self, v: u64) -> Result<T, E>
+        where E: de::Error
+    {
+        self.visit_str(&v.to_string())
+    }
+
+    fn visit_i64<E>(self, v: i64) -> Result<T, E>
+        where E: de::Error
+    {
+        self.visit_str(&v.to_string())
+    }
+
+    fn visit_f64<E>(self, v: f64) -> Result<T, E>
+        where E: de::Error
+    {
+        self.visit_str(&format_float(v))
+    }
+}
@@ -0,0 +1,55 @@
+// Copyright 2018 Serde YAML Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Sequences and mappings written on one line, in flow style.
+//!
+//! Short lists like ports or coordinates read better as `[80, 443]` than as
+//! a `-` line per element. The field is read as usual. See `style::Styled`
+//! for the same outside of a field.
+//!
+//! ```rust
+//! #[macro_use]
+//! extern crate serde_derive;
+//! extern crate serde_yaml;
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Point {
+//!     name: String,
+//!     #[serde(with = "serde_yaml::with::flow")]
+//!     at: Vec<f64>,
+//! }
+//!
+//! # fn main() {
+//! let point = Point { name: "origin".to_owned(), at: vec![0.0, 0.5] };
+//! let yaml = "---\nname: origin\nat: [0.0, 0.5]";
+//! assert_eq!(serde_yaml::to_string(&point).unwrap(), yaml);
+//! let point: Point = serde_yaml::from_str(yaml).unwrap();
+//! assert_eq!(point.at, [0.0, 0.5]);
+//! # }
+//! ```
+
+use serde::de::{Deserialize, Deserializer};
+use serde::ser::{Serialize, Serializer};
+
+use style::{Style, Styled};
+
+/// Serializes `value`, in flow style if it is a sequence or mapping.
+pub fn serialize<T: ?Sized, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+    where T: Serialize,
+          S: Serializer
+{
+    Styled(Style::Flow, value).serialize(serializer)
+}
+
+/// Deserializes the value as it would be without the adapter.
+pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+    where T: Deserialize<'de>,
+          D: Deserializer<'de>
+{
+    T::deserialize(deserializer)
+}
@@ -0,0 +1,144 @@
+// Copyright 2018 Serde YAML Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! `Duration` fields written like `30s`, `5m` or `1h 30m`.
+//!
+//! A duration is one or more whole numbers, each followed by a unit and
+//! optionally separated by spaces. The units are `ns`, `us` (or `µs`), `ms`,
+//! `s`, `m`, `h`, `d` and `w`, and the longer `sec`, `min`, `hour`, `day` and
+//! `week`, including their plurals. A number without a unit is an error
+//! rather than a guess at seconds.
+//!
+//! ```rust
+//! #[macro_use]
+//! extern crate serde_derive;
+//! extern crate serde_yaml;
+//!
+//! use std::time::Duration;
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Timeouts {
+//!     #[serde(with = "serde_yaml::with::humantime_duration")]
+//!     connect: Duration,
+//!     #[serde(with = "serde_yaml::with::humantime_duration")]
+//!     idle: Duration,
+//! }
+//!
+//! # fn main() {
+//! let timeouts: Timeouts = serde_yaml::from_str("{connect: 500ms, idle: 1h30m}").unwrap();
+//! assert_eq!(timeouts.connect, Duration::from_millis(500));
+//! assert_eq!(timeouts.idle, Duration::from_secs(5400));
+//! assert_eq!(serde_yaml::to_string(&timeouts).unwrap(), "---\nconnect: 500ms\nidle: 1h 30m");
+//! # }
+//! ```
+
+use std::fmt;
+use std::time::Duration;
+
+use serde::de::{self, Deserializer, Unexpected, Visitor};
+use serde::ser::Serializer;
+
+const EXPECTED: &str = "a duration like `30s` or `1h 30m`";
+
+/// Serializes `duration` with the largest units first, like `1d 2h 3m 4s
+/// 5ms`, or as `0s` if it is zero.
+pub fn serialize<S>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error>
+    where S: Serializer
+{
+    serializer.collect_str(&Formatted(*duration))
+}
+
+/// Deserializes a duration from a string of numbers with units.
+pub fn deserialize<'de, D>(deserializer: D) -> Result<Duration, D::Error>
+    where D: Deserializer<'de>
+{
+    deserializer.deserialize_str(DurationVisitor)
+}
+
+struct Formatted(Duration);
+
+impl fmt::Display for Formatted {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        let secs = self.0.as_secs();
+        let nanos = self.0.subsec_nanos();
+        let parts = [(secs / 86400, "d"),
+                     (secs / 3600 % 24, "h"),
+                     (secs / 60 % 60, "m"),
+                     (secs % 60, "s"),
+                     (u64::from(nanos / 1_000_000), "ms"),
+                     (u64::from(nanos / 1000 % 1000), "us"),
+                     (u64::from(nanos % 1000), "ns")];
+        let mut first = true;
+        for &(n, unit) in parts.iter().filter(|part| part.0 != 0) {
+            if !first {
+                formatter.write_str(" ")?;
+            }
+            write!(formatter, "{}{}", n, unit)?;
+            first = false;
+        }
+        if first {
+            formatter.write_str("0s")?;
+        }
+        Ok(())
+    }
+}
+
+struct DurationVisitor;
+
+impl<'de> Visitor<'de> for DurationVisitor {
+    type Value = Duration;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str(EXPECTED)
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Duration, E>
+        where E: de::Error
+    {
+        parse(v).ok_or_else(|| E::invalid_value(Unexpected::Str(v), &EXPECTED))
+    }
+}
+
+/// Parses a duration, or returns None if it is malformed or too long.
+fn parse(v: &str) -> Option<Duration> {
+    let mut rest = v.trim();
+    if rest.is_empty() {
+        return None;
+    }
+    let mut total = Duration::new(0, 0);
+    while !rest.is_empty() {
+        let digits = rest.find(|c: char| !c.is_ascii_digit()).unwrap_or(rest.len());
+        if digits == 0 {
+            return None;
+        }
+        let n: u64 = rest[..digits].parse().ok()?;
+        rest = rest[digits..].trim_start();
+        let letters = rest.find(|c: char| !c.is_alphabetic()).unwrap_or(rest.len());
+        let part = match &rest[..letters] {
+            "ns" => Duration::from_nanos(n),
+            "us" | "µs" => Duration::from_micros(n),
+            "ms" => Duration::from_millis(n),
+            unit => Duration::from_secs(n.checked_mul(seconds(unit)?)?),
+        };
+        total = total.checked_add(part)?;
+        rest = rest[letters..].trim_start();
+    }
+    Some(total)
+}
+
+/// The length in seconds of a unit of a second or more.
+fn seconds(unit: &str) -> Option<u64> {
+    Some(match unit {
+             "s" | "sec" | "secs" | "second" | "seconds" => 1,
+             "m" | "min" | "mins" | "minute" | "minutes" => 60,
+             "h" | "hour" | "hours" => 3600,
+             "d" | "day" | "days" => 86400,
+             "w" | "week" | "weeks" => 604800,
+             _ => return None,
+         })
+}
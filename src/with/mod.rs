@@ -0,0 +1,22 @@
+// Copyright 2018 Serde YAML Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Adapters for use with `#[serde(with = "...")]` on fields whose YAML form
+//! differs from what their `Serialize` and `Deserialize` impls produce.
+
+#[cfg(feature = "ser")]
+pub mod block_scalar;
+pub mod byte_size;
+pub mod decimal;
+pub mod display_fromstr;
+#[cfg(feature = "ser")]
+pub mod flow;
+pub mod humantime_duration;
+pub mod seq_of_singletons;
+pub mod sorted;
+pub mod string_or_struct;
@@ -0,0 +1,195 @@
+// Copyright 2018 Serde YAML Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Ordered pairs written as a sequence of single-key mappings.
+//!
+//! Pipelines and similar lists of named steps are often written as
+//!
+//! ```yaml
+//! steps:
+//!   - build: {target: release}
+//!   - test: {jobs: 4}
+//! ```
+//!
+//! which keeps the order of the steps and allows the same name twice, unlike a
+//! mapping. This adapter reads and writes that shape for a field holding
+//! pairs: a `Vec<(K, V)>`, a map that keeps order like `indexmap::IndexMap`
+//! or `linked_hash_map::LinkedHashMap`, or any other collection built with
+//! `FromIterator<(K, V)>` whose reference iterates as `&(K, V)` or as
+//! `(&K, &V)`.
+//!
+//! ```rust
+//! #[macro_use]
+//! extern crate serde_derive;
+//! extern crate serde_yaml;
+//!
+//! use std::collections::BTreeMap;
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Pipeline {
+//!     #[serde(with = "serde_yaml::with::seq_of_singletons")]
+//!     steps: Vec<(String, BTreeMap<String, String>)>,
+//! }
+//!
+//! # fn main() {
+//! let yaml = "---\nsteps:\n  - build:\n      target: release\n  - test:\n      jobs: \"4\"";
+//! let pipeline: Pipeline = serde_yaml::from_str(yaml).unwrap();
+//! assert_eq!(pipeline.steps[0].0, "build");
+//! assert_eq!(pipeline.steps[1].1["jobs"], "4");
+//! assert_eq!(serde_yaml::to_string(&pipeline).unwrap(), yaml);
+//! # }
+//! ```
+
+use std::fmt;
+use std::iter::FromIterator;
+use std::marker::PhantomData;
+
+use serde::de::{self, Deserialize, Deserializer, IgnoredAny, MapAccess, SeqAccess, Visitor};
+use serde::ser::{Serialize, SerializeMap, Serializer};
+
+/// Serializes each pair of `entries` as a mapping with a single entry.
+pub fn serialize<'a, C: ?Sized, S>(entries: &'a C, serializer: S) -> Result<S::Ok, S::Error>
+    where &'a C: IntoIterator,
+          <&'a C as IntoIterator>::Item: Entry,
+          S: Serializer
+{
+    serializer.collect_seq(entries.into_iter().map(Singleton))
+}
+
+/// Deserializes a sequence of single-key mappings into pairs.
+pub fn deserialize<'de, C, K, V, D>(deserializer: D) -> Result<C, D::Error>
+    where C: FromIterator<(K, V)>,
+          K: Deserialize<'de>,
+          V: Deserialize<'de>,
+          D: Deserializer<'de>
+{
+    let pairs: Vec<(K, V)> = deserializer.deserialize_seq(SeqVisitor(PhantomData))?;
+    Ok(pairs.into_iter().collect())
+}
+
+/// An item of a collection of pairs as iterated by reference.
+pub trait Entry {
+    /// The first of the pair.
+    type Key: ?Sized + Serialize;
+    /// The second of the pair.
+    type Value: ?Sized + Serialize;
+
+    /// The two halves of the pair.
+    fn pair(&self) -> (&Self::Key, &Self::Value);
+}
+
+impl<'a, K, V> Entry for &'a (K, V)
+    where K: Serialize,
+          V: Serialize
+{
+    type Key = K;
+    type Value = V;
+
+    fn pair(&self) -> (&K, &V) {
+        (&self.0, &self.1)
+    }
+}
+
+impl<'a, K: ?Sized, V: ?Sized> Entry for (&'a K, &'a V)
+    where K: Serialize,
+          V: Serialize
+{
+    type Key = K;
+    type Value = V;
+
+    fn pair(&self) -> (&K, &V) {
+        (self.0, self.1)
+    }
+}
+
+struct Singleton<E>(E);
+
+impl<E> Serialize for Singleton<E>
+    where E: Entry
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where S: Serializer
+    {
+        let (key, value) = self.0.pair();
+        let mut map = serializer.serialize_map(Some(1))?;
+        map.serialize_entry(key, value)?;
+        map.end()
+    }
+}
+
+struct SeqVisitor<K, V>(PhantomData<(K, V)>);
+
+impl<'de, K, V> Visitor<'de> for SeqVisitor<K, V>
+    where K: Deserialize<'de>,
+          V: Deserialize<'de>
+{
+    type Value = Vec<(K, V)>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a sequence of single-key mappings")
+    }
+
+    fn visit_unit<E>(self) -> Result<Self::Value, E>
+        where E: de::Error
+    {
+        Ok(Vec::new())
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+        where A: SeqAccess<'de>
+    {
+        let mut pairs = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(SingletonPair(key, value)) = seq.next_element()? {
+            pairs.push((key, value));
+        }
+        Ok(pairs)
+    }
+}
+
+struct SingletonPair<K, V>(K, V);
+
+impl<'de, K, V> Deserialize<'de> for SingletonPair<K, V>
+    where K: Deserialize<'de>,
+          V: Deserialize<'de>
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where D: Deserializer<'de>
+    {
+        deserializer.deserialize_map(SingletonVisitor(PhantomData))
+    }
+}
+
+struct SingletonVisitor<K, V>(PhantomData<(K, V)>);
+
+impl<'de, K, V> Visitor<'de> for SingletonVisitor<K, V>
+    where K: Deserialize<'de>,
+          V: Deserialize<'de>
+{
+    type Value = SingletonPair<K, V>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a mapping with a single key")
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+        where A: MapAccess<'de>
+    {
+        let (key, value) = match map.next_entry()? {
+            Some(entry) => entry,
+            None => return Err(de::Error::invalid_length(0, &self)),
+        };
+        let mut len = 1;
+        while map.next_entry::<IgnoredAny, IgnoredAny>()?.is_some() {
+            len += 1;
+        }
+        if len > 1 {
+            return Err(de::Error::invalid_length(len, &self));
+        }
+        Ok(SingletonPair(key, value))
+    }
+}
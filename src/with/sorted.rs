@@ -0,0 +1,59 @@
+// Copyright 2018 Serde YAML Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Collections written as a sequence in sorted order.
+//!
+//! A `HashSet` serializes its elements in whatever order it holds them,
+//! which changes from run to run. This adapter writes them sorted, so that
+//! generated files stay the same, and reads the field as usual. Maps are
+//! sorted by `Writer::sort_maps` instead.
+//!
+//! ```rust
+//! #[macro_use]
+//! extern crate serde_derive;
+//! extern crate serde_yaml;
+//!
+//! use std::collections::HashSet;
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Team {
+//!     #[serde(with = "serde_yaml::with::sorted")]
+//!     members: HashSet<String>,
+//! }
+//!
+//! # fn main() {
+//! let members = ["mallory", "alice", "bob"].iter().map(|s| s.to_string()).collect();
+//! let team = Team { members: members };
+//! let yaml = "---\nmembers:\n  - alice\n  - bob\n  - mallory";
+//! assert_eq!(serde_yaml::to_string(&team).unwrap(), yaml);
+//! let team: Team = serde_yaml::from_str(yaml).unwrap();
+//! assert!(team.members.contains("bob"));
+//! # }
+//! ```
+
+use serde::de::{Deserialize, Deserializer};
+use serde::ser::{Serialize, Serializer};
+
+/// Serializes the elements of `collection` as a sequence, smallest first.
+pub fn serialize<'a, C: ?Sized, T: 'a, S>(collection: &'a C, serializer: S) -> Result<S::Ok, S::Error>
+    where &'a C: IntoIterator<Item = &'a T>,
+          T: Ord + Serialize,
+          S: Serializer
+{
+    let mut elements: Vec<&T> = collection.into_iter().collect();
+    elements.sort();
+    serializer.collect_seq(elements)
+}
+
+/// Deserializes the collection as it would be without the adapter.
+pub fn deserialize<'de, C, D>(deserializer: D) -> Result<C, D::Error>
+    where C: Deserialize<'de>,
+          D: Deserializer<'de>
+{
+    C::deserialize(deserializer)
+}
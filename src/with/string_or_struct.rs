@@ -0,0 +1,140 @@
+// Copyright 2018 Serde YAML Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Values written either as a scalar in a short form or as a mapping.
+//!
+//! Config formats often accept `image: nginx:1.25` as well as a mapping with
+//! the same information spelled out, plus settings that the short form has
+//! no room for. With this adapter a scalar is passed to the type's `FromStr`
+//! impl and a mapping to its `Deserialize` impl. Plain numbers and booleans
+//! count as scalars, so a short form like `port: 8080` works too. Values are
+//! always written in the long form, through `Serialize`.
+//!
+//! ```rust
+//! #[macro_use]
+//! extern crate serde_derive;
+//! extern crate serde_yaml;
+//!
+//! use std::str::FromStr;
+//!
+//! #[derive(Serialize, Deserialize, Debug, PartialEq)]
+//! struct Image {
+//!     name: String,
+//!     #[serde(default = "latest")]
+//!     tag: String,
+//! }
+//!
+//! fn latest() -> String {
+//!     "latest".to_owned()
+//! }
+//!
+//! impl FromStr for Image {
+//!     type Err = String;
+//!
+//!     fn from_str(s: &str) -> Result<Self, String> {
+//!         let mut parts = s.splitn(2, ':');
+//!         let name = parts.next().filter(|name| !name.is_empty()).ok_or("missing name")?;
+//!         Ok(Image {
+//!             name: name.to_owned(),
+//!             tag: parts.next().map_or_else(latest, str::to_owned),
+//!         })
+//!     }
+//! }
+//!
+//! #[derive(Deserialize, Debug)]
+//! struct Service {
+//!     #[serde(with = "serde_yaml::with::string_or_struct")]
+//!     image: Image,
+//! }
+//!
+//! # fn main() {
+//! let short: Service = serde_yaml::from_str("image: nginx:1.25").unwrap();
+//! let long: Service = serde_yaml::from_str("image: {name: nginx, tag: '1.25'}").unwrap();
+//! assert_eq!(short.image, long.image);
+//!
+//! let err = serde_yaml::from_str::<Service>("image: ':1.25'").unwrap_err();
+//! assert_eq!(err.to_string(), "image: invalid value `:1.25`: missing name at line 1 column 8");
+//! # }
+//! ```
+
+use std::fmt::{self, Display};
+use std::marker::PhantomData;
+use std::str::FromStr;
+
+use serde::de::{self, Deserialize, Deserializer, MapAccess, Visitor};
+use serde::de::value::MapAccessDeserializer;
+use serde::ser::{Serialize, Serializer};
+
+use number::format_float;
+
+/// Serializes `value` in its long form, like its `Serialize` impl does.
+pub fn serialize<T: ?Sized, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+    where T: Serialize,
+          S: Serializer
+{
+    value.serialize(serializer)
+}
+
+/// Deserializes a value from a scalar through `T::from_str`, or from a
+/// mapping through `T::deserialize`.
+pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+    where T: Deserialize<'de> + FromStr,
+          T::Err: Display,
+          D: Deserializer<'de>
+{
+    deserializer.deserialize_any(StringOrStructVisitor(PhantomData))
+}
+
+struct StringOrStructVisitor<T>(PhantomData<T>);
+
+impl<'de, T> Visitor<'de> for StringOrStructVisitor<T>
+    where T: Deserialize<'de> + FromStr,
+          T::Err: Display
+{
+    type Value = T;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a scalar or a mapping")
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<T, E>
+        where E: de::Error
+    {
+        v.parse().map_err(|err| E::custom(format_args!("invalid value `{}`: {}", v, err)))
+    }
+
+    fn visit_bool<E>(self, v: bool) -> Result<T, E>
+        where E: de::Error
+    {
+        self.visit_str(if v { "true" } else { "false" })
+    }
+
+    fn visit_u64<E>(self, v: u64) -> Result<T, E>
+        where E: de::Error
+    {
+        self.visit_str(&v.to_string())
+    }
+
+    fn visit_i64<E>(self, v: i64) -> Result<T, E>
+        where E: de::Error
+    {
+        self.visit_str(&v.to_string())
+    }
+
+    fn visit_f64<E>(self, v: f64) -> Result<T, E>
+        where E: de::Error
+    {
+        self.visit_str(&format_float(v))
+    }
+
+    fn visit_map<A>(self, map: A) -> Result<T, A::Error>
+        where A: MapAccess<'de>
+    {
+        T::deserialize(MapAccessDeserializer::new(map))
+    }
+}
@@ -0,0 +1,16 @@
+// Copyright 2018 Serde YAML Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Helpers shared by the tests.
+
+use serde_yaml::{self, Value};
+
+/// Parses a `Value` that the test knows to be valid YAML.
+pub fn yaml(s: &str) -> Value {
+    serde_yaml::from_str(s).unwrap()
+}
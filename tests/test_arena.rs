@@ -0,0 +1,78 @@
+// Copyright 2018 Serde YAML Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+#![cfg(all(feature = "bumpalo", feature = "de", feature = "value"))]
+
+extern crate bumpalo;
+extern crate serde_yaml;
+
+extern crate unindent;
+use unindent::unindent;
+
+use bumpalo::Bump;
+use serde_yaml::arena::Value;
+
+#[test]
+fn test_matches_value() {
+    let yaml = unindent("
+        ---
+        name: demo
+        enabled: true
+        ratio: 0.25
+        count: -3
+        big: 18446744073709551615
+        nothing: ~
+        quoted: '123'
+        list:
+          - a
+          - [1, 2]
+        1: integer key");
+    let bump = Bump::new();
+    let value = serde_yaml::from_str_in(&yaml, &bump).unwrap();
+    let expected: serde_yaml::Value = serde_yaml::from_str(&yaml).unwrap();
+    assert_eq!(value.to_value(), expected);
+
+    assert_eq!(value.get("quoted").and_then(Value::as_str), Some("123"));
+    assert_eq!(value.get("count").and_then(Value::as_i64), Some(-3));
+    assert_eq!(value.get("big").and_then(Value::as_u64), Some(u64::MAX));
+    assert!(value.get("nothing").unwrap().is_null());
+    assert!(value.get("missing").is_none());
+}
+
+#[test]
+fn test_aliases_share_nodes() {
+    let yaml = unindent("
+        base: &base
+          image: demo
+          replicas: 2
+        first: *base
+        second: *base");
+    let bump = Bump::new();
+    let value = serde_yaml::from_str_in(&yaml, &bump).unwrap();
+    let base = value.get("base").and_then(Value::as_mapping).unwrap();
+    let first = value.get("first").and_then(Value::as_mapping).unwrap();
+    let second = value.get("second").and_then(Value::as_mapping).unwrap();
+    assert!(std::ptr::eq(base, first));
+    assert!(std::ptr::eq(base, second));
+}
+
+#[test]
+fn test_duplicate_keys() {
+    let bump = Bump::new();
+    let value = serde_yaml::from_str_in("{a: 1, a: 2}", &bump).unwrap();
+    assert_eq!(value.as_mapping().unwrap().len(), 2);
+    assert_eq!(value.get("a").and_then(Value::as_i64), Some(2));
+}
+
+#[test]
+fn test_errors() {
+    let bump = Bump::new();
+    assert!(serde_yaml::from_str_in("", &bump).is_err());
+    assert!(serde_yaml::from_str_in("--- a\n--- b\n", &bump).is_err());
+    assert!(serde_yaml::from_str_in("!!int nope", &bump).is_err());
+}
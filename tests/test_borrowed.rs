@@ -0,0 +1,75 @@
+// Copyright 2018 Serde YAML Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+#![cfg(all(feature = "de", feature = "value"))]
+
+extern crate serde_yaml;
+
+use std::borrow::Cow;
+
+use serde_yaml::borrowed::{self, Value};
+
+fn is_borrowed(value: Option<&Value>) -> bool {
+    match value {
+        Some(&Value::String(Cow::Borrowed(_))) => true,
+        Some(&Value::String(Cow::Owned(_))) => false,
+        other => panic!("not a string: {:?}", other),
+    }
+}
+
+#[test]
+fn test_borrowed_strings() {
+    let input = "plain: text\nsingle: 'quoted'\ndouble: \"quoted\"\nescaped: \"a\\tb\"\ndoubled: 'it''s'\n\
+                 folded: multi\n  line\nliteral: |\n  block\n";
+    let value = borrowed::from_str(input).unwrap();
+    for key in &["plain", "single", "double"] {
+        assert!(is_borrowed(value.get(key)), "{}", key);
+    }
+    for key in &["escaped", "doubled", "folded", "literal"] {
+        assert!(!is_borrowed(value.get(key)), "{}", key);
+    }
+    assert_eq!(value.get("doubled").and_then(Value::as_str), Some("it's"));
+    assert_eq!(value.get("folded").and_then(Value::as_str), Some("multi line"));
+    for (key, _) in value.as_mapping().unwrap() {
+        assert!(is_borrowed(Some(key)));
+    }
+}
+
+#[test]
+fn test_borrowed_non_ascii() {
+    let input = "é: [ü, '日本', \"語\"]\nkey: ß\n";
+    let value = borrowed::from_str(input).unwrap();
+    let seq = value.get("é").and_then(Value::as_sequence).unwrap();
+    for element in seq {
+        assert!(is_borrowed(Some(element)));
+    }
+    assert_eq!(seq[1].as_str(), Some("日本"));
+    assert!(is_borrowed(value.get("key")));
+    assert_eq!(value.get("key").and_then(Value::as_str), Some("ß"));
+}
+
+#[test]
+fn test_borrowed_matches_value() {
+    let input = "a: &x {b: [1, -2.5, true, ~, '3']}\nc: *x\nd: !!str 4\n";
+    let value = borrowed::from_str(input).unwrap();
+    let expected: serde_yaml::Value = serde_yaml::from_str(input).unwrap();
+    assert_eq!(value.to_value(), expected);
+    assert_eq!(value.clone().into_owned(), value);
+    let owned = value.to_owned_static();
+    assert_eq!(owned, value);
+    assert!(is_borrowed(value.get("d")));
+    assert!(!is_borrowed(owned.get("d")));
+    assert_eq!(value.get("c"), value.get("a"));
+}
+
+#[test]
+fn test_borrowed_errors() {
+    assert!(borrowed::from_str("").is_err());
+    assert!(borrowed::from_str("a\n---\nb\n").is_err());
+    assert!(borrowed::from_str("[").is_err());
+}
@@ -0,0 +1,105 @@
+// Copyright 2018 Serde YAML Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+#![cfg(all(feature = "de", feature = "value"))]
+
+#[macro_use]
+extern crate serde_derive;
+extern crate serde_yaml;
+
+use std::collections::BTreeMap;
+
+use serde_yaml::comment::Commented;
+use serde_yaml::Value;
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+struct Server {
+    host: Commented<String>,
+    port: u16,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+struct Config {
+    name: String,
+    server: Commented<Server>,
+    backups: Vec<Server>,
+}
+
+fn server(comment: &str, host: &str, port: u16) -> Server {
+    Server {
+        host: Commented::new(comment, host.to_owned()),
+        port,
+    }
+}
+
+#[test]
+fn test_nested() {
+    let config = Config {
+        name: "app".to_owned(),
+        server: Commented::new("Where to listen.",
+                               server("Use 0.0.0.0 for every interface.", "localhost", 80)),
+        backups: vec![server("first\n\nof two", "a", 81), server("", "b", 82)],
+    };
+    let yaml = serde_yaml::to_string(&config).unwrap();
+    let expected = [
+        "---",
+        "name: app",
+        "# Where to listen.",
+        "server:",
+        "  # Use 0.0.0.0 for every interface.",
+        "  host: localhost",
+        "  port: 80",
+        "backups:",
+        "  - # first",
+        "    #",
+        "    # of two",
+        "    host: a",
+        "    port: 81",
+        "  - host: b",
+        "    port: 82",
+    ];
+    assert_eq!(yaml, expected.join("\n"));
+
+    let back: Config = serde_yaml::from_str(&yaml).unwrap();
+    assert_eq!(*back.server.host, "localhost");
+    assert_eq!(back.backups[0].host.comment(), "");
+    assert_eq!(serde_yaml::from_str::<Value>(&yaml).unwrap(), serde_yaml::to_value(&config).unwrap());
+}
+
+#[test]
+fn test_document_and_keys() {
+    let mut map = BTreeMap::new();
+    map.insert(Commented::new("key", "a"), Commented::new("value", vec![1]));
+    let doc = Commented::new("generated, do not edit", Commented::new("second", map));
+    let yaml = serde_yaml::to_string(&doc).unwrap();
+    assert_eq!(yaml, "---\n# generated, do not edit\n# second\n# key\n# value\na:\n  - 1");
+
+    let scalar = serde_yaml::to_string(&Commented::new("x", 1)).unwrap();
+    assert_eq!(scalar, "---\n# x\n1");
+}
+
+#[test]
+fn test_sequence_of_sequences() {
+    let value = vec![Commented::new("outer", vec![Commented::new("inner", 1), 2.into()])];
+    let yaml = serde_yaml::to_string(&value).unwrap();
+    assert_eq!(yaml, "---\n# outer\n- # inner\n  - 1\n  - 2");
+    assert_eq!(serde_yaml::from_str::<Vec<Vec<u8>>>(&yaml).unwrap(), [[1, 2]]);
+}
+
+#[test]
+fn test_to_value_is_transparent() {
+    let value = serde_yaml::to_value(Commented::new("x", vec![Commented::new("y", 1)])).unwrap();
+    assert_eq!(value, serde_yaml::from_str::<Value>("[1]").unwrap());
+}
+
+#[test]
+fn test_error_path() {
+    let value = vec![Commented::new("x", vec![1.0, f64::NAN])];
+    let err = serde_yaml::Writer::new(Vec::new()).finite_floats_only(true).write(&value).unwrap_err();
+    assert_eq!(err.to_string(), "[0][1]: infinite or NaN float `.nan` is not allowed");
+}
@@ -0,0 +1,117 @@
+// Copyright 2018 Serde YAML Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+#![cfg(all(feature = "de", feature = "value"))]
+
+#[macro_use]
+extern crate serde_derive;
+extern crate serde_yaml;
+
+use std::collections::HashMap;
+use std::panic;
+
+use serde_yaml::assert_snapshot_stable;
+use serde_yaml::compare::{normalize_whitespace, same_documents, same_text};
+
+#[test]
+fn test_normalize_whitespace() {
+    assert_eq!(normalize_whitespace(""), "");
+    assert_eq!(normalize_whitespace("a: 1"), "a: 1\n");
+    assert_eq!(normalize_whitespace("a: 1\n"), "a: 1\n");
+    assert_eq!(normalize_whitespace("\n\na: 1\n\n\nb: 2 \t\n\n"), "\n\na: 1\n\n\nb: 2\n");
+    assert_eq!(normalize_whitespace("a: 1\r\nb: 2\rc: 3\r\n"), "a: 1\nb: 2\nc: 3\n");
+    assert_eq!(normalize_whitespace("\t\r\n  \n"), "");
+}
+
+#[test]
+fn test_same_text() {
+    assert!(same_text("a: 1  \r\nb: 2\r\n\r\n", "a: 1\nb: 2"));
+    assert!(!same_text("a: 1\nb: 2", "b: 2\na: 1"));
+    assert!(!same_text("a:  1", "a: 1"));
+}
+
+#[test]
+fn test_same_documents() {
+    assert!(same_documents("", "").unwrap());
+    assert!(same_documents("{a: 1, b: [x, y]}", "b:\n- x\n- 'y'\na: 1 # one\n").unwrap());
+    assert!(same_documents("--- 1\n--- 2\n...\n", "1\n---\n2").unwrap());
+    assert!(!same_documents("--- 1\n--- 2", "--- 2\n--- 1").unwrap());
+    assert!(!same_documents("[a, b]", "[b, a]").unwrap());
+    assert!(!same_documents("a: 1", "").unwrap());
+    assert!(!same_documents("a: '1'", "a: 1").unwrap());
+}
+
+#[test]
+fn test_same_documents_nested_keys() {
+    assert!(same_documents("{[1, {b: 2, a: 1}]: x}", "? [1, {a: 1, b: 2}]\n: x").unwrap());
+}
+
+#[test]
+fn test_same_documents_aliases_across_documents() {
+    let a = "--- &a [1, 2]\n--- {x: &b 3, y: *b}";
+    let b = "--- [1, 2]\n--- {y: 3, x: 3}";
+    assert!(same_documents(a, b).unwrap());
+}
+
+#[test]
+fn test_same_documents_error() {
+    let err = same_documents("a: 1", "a: [1").unwrap_err();
+    assert!(err.to_string().contains("line"), "{}", err);
+}
+
+#[derive(Serialize)]
+struct Job {
+    name: &'static str,
+    env: HashMap<&'static str, f64>,
+}
+
+fn job() -> Job {
+    let env = vec![("b", 2.0), ("a", 1.5)].into_iter().collect();
+    Job { name: "build", env }
+}
+
+#[test]
+fn test_stable_format() {
+    let yaml = serde_yaml::YamlFormat::stable().to_string(&job()).unwrap();
+    assert_eq!(yaml, "---\nname: build\nenv:\n  a: 1.5\n  b: 2.0\n");
+}
+
+#[test]
+fn test_stable_quoting() {
+    // Quoted by `to_string` since the stable format was added, but not by it.
+    let strings = vec!["2024-01-01", "1_000", "0b101"];
+    let yaml = serde_yaml::YamlFormat::stable().to_string(&strings).unwrap();
+    assert_eq!(yaml, "---\n- 2024-01-01\n- 1_000\n- 0b101\n");
+    assert_eq!(serde_yaml::from_str::<Vec<String>>(&yaml).unwrap(), strings);
+    let yaml = serde_yaml::to_string(&strings).unwrap();
+    assert_eq!(yaml, "---\n- \"2024-01-01\"\n- \"1_000\"\n- \"0b101\"");
+}
+
+#[test]
+fn test_assert_snapshot_stable() {
+    assert_snapshot_stable(&job(), "---\r\nname: build\r\nenv:\r\n  a: 1.5\r\n  b: 2.0\r\n");
+    assert_snapshot_stable(&job(), "# golden\nenv: {b: 2.0, a: 1.5}\nname: \"build\"");
+}
+
+#[test]
+fn test_assert_snapshot_stable_mismatch() {
+    let message = |golden: &'static str| {
+        let err = panic::catch_unwind(|| assert_snapshot_stable(&job(), golden)).unwrap_err();
+        err.downcast::<String>().unwrap()
+    };
+
+    let diff = message("name: build\nenv: {a: 1.5, b: 2}\n");
+    assert!(diff.starts_with("snapshot differs from the golden text:\n"), "{}", diff);
+    assert!(diff.contains("-   b: 2\n+   b: 2.0\n"), "{}", diff);
+    assert!(diff.ends_with("stable output:\n---\nname: build\nenv:\n  a: 1.5\n  b: 2.0\n"), "{}", diff);
+
+    let count = message("");
+    assert!(count.starts_with("golden text holds 0 documents, not 1\n"), "{}", count);
+    let invalid = message("name: [");
+    assert!(invalid.starts_with("golden text is not valid YAML"), "{}", invalid);
+}
@@ -0,0 +1,74 @@
+// Copyright 2018 Serde YAML Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+#![cfg(all(feature = "de", feature = "value"))]
+
+#[cfg(feature = "regex")]
+extern crate regex;
+extern crate serde_yaml;
+
+use serde_yaml::constraints::{Constraint, Rules, Violation};
+use serde_yaml::Value;
+
+fn check(rules: &Rules, yaml: &str) -> Vec<Violation> {
+    let (doc, errors) = serde_yaml::parse_tolerant(yaml);
+    assert!(errors.is_empty());
+    rules.check(&doc)
+}
+
+fn messages(rules: &Rules, yaml: &str) -> Vec<String> {
+    check(rules, yaml).iter().map(ToString::to_string).collect()
+}
+
+#[test]
+fn test_range() {
+    let rules = Rules::new().rule("*.ratio", Constraint::Range(0.0, 1.0));
+    assert_eq!(messages(&rules, "a: {ratio: 0.5}\nb: {ratio: 1}\n"), Vec::<String>::new());
+    assert_eq!(messages(&rules, "a: {ratio: -0.5}\nb: {ratio: full}\nc: {d: {ratio: 2}}\n"), [
+        "1:12: `a.ratio` must be between 0 and 1, found -0.5",
+        "2:12: `b.ratio` must be a number between 0 and 1",
+    ]);
+}
+
+#[test]
+fn test_one_of() {
+    let levels = vec![Value::from(1), Value::from(2), Value::Null];
+    let rules = Rules::new()
+        .rule("level", Constraint::OneOf(levels))
+        .rule("tags[*]", Constraint::one_of(&["a", "b"]));
+    assert_eq!(messages(&rules, "level: ~\ntags: [a, b, a]"), Vec::<String>::new());
+    assert_eq!(messages(&rules, "level: 3\ntags: [a, c, [b]]"), [
+        "1:8: `level` must be one of 1, 2, ~, found 3",
+        "2:11: `tags[1]` must be one of a, b, found c",
+        "2:14: `tags[2]` must be one of a, b, found - b",
+    ]);
+}
+
+#[test]
+fn test_root_and_accessors() {
+    let rules = Rules::new()
+        .rule(".", Constraint::Range(0.0, 9.0))
+        .rule("[*]", Constraint::Range(0.0, 9.0));
+    let violations = check(&rules, "[1, 10]");
+    assert_eq!(violations.len(), 2);
+    assert_eq!(violations[0].path(), ".");
+    assert_eq!(violations[0].message(), "must be a number between 0 and 9");
+    assert_eq!(violations[1].path(), "[1]");
+    assert_eq!(violations[1].location().column(), 5);
+    assert_eq!(violations[1].span().end().column(), 7);
+}
+
+#[cfg(feature = "regex")]
+#[test]
+fn test_pattern() {
+    let regex = regex::Regex::new("^[a-z]+:[0-9.]+$").unwrap();
+    let rules = Rules::new().rule("image", Constraint::Pattern(regex));
+    assert_eq!(messages(&rules, "image: nginx:1.25"), Vec::<String>::new());
+    assert_eq!(messages(&rules, "image: Nginx"), ["1:8: `image` must match `^[a-z]+:[0-9.]+$`, found Nginx"]);
+    assert_eq!(messages(&rules, "image: 1"), ["1:8: `image` must be a string matching `^[a-z]+:[0-9.]+$`"]);
+}
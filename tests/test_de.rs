@@ -6,6 +6,8 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
+#![cfg(all(feature = "de", feature = "value"))]
+
 #[macro_use]
 extern crate serde_derive;
 
@@ -174,6 +176,18 @@ fn test_number_alias_as_string() {
     test_de(&yaml, &expected);
 }
 
+#[test]
+fn test_number_token_as_key() {
+    use serde_yaml::{Mapping, Value};
+    let key = Value::String("$serde_yaml::private::Number".to_owned());
+    for &(yaml, ref expected) in &[("\"$serde_yaml::private::Number\": abc", Value::String("abc".to_owned())),
+                                   ("\"$serde_yaml::private::Number\": 12", Value::Number(12.into()))] {
+        let mut mapping = Mapping::new();
+        mapping.insert(key.clone(), expected.clone());
+        assert_eq!(serde_yaml::from_str::<Value>(yaml).unwrap(), Value::Mapping(mapping));
+    }
+}
+
 #[test]
 fn test_de_mapping() {
     #[derive(Debug, Deserialize, PartialEq)]
@@ -194,3 +208,763 @@ fn test_de_mapping() {
 
     test_de(&yaml, &expected);
 }
+
+#[test]
+fn test_de_mapping_iter_str() {
+    use serde_yaml::{Mapping, Value};
+
+    let mapping: Mapping = serde_yaml::from_str("a: 1\n2: b\n[c]: 3\nd: 4\n").unwrap();
+    assert_eq!(mapping.iter().count(), 4);
+    let entries: Vec<(&str, &Value)> = mapping.iter_str().collect();
+    assert_eq!(entries, [("a", &Value::from(1)), ("d", &Value::from(4))]);
+    assert_eq!(mapping.iter_str().size_hint(), (0, Some(4)));
+    assert_eq!(Mapping::new().iter_str().next(), None);
+}
+
+#[test]
+fn test_de_mapping_sort() {
+    use serde_yaml::{Mapping, Value};
+
+    let mut mapping: Mapping = serde_yaml::from_str("b: 1\n~: 2\n10: 3\n2: 4\na: {d: 5, c: 6}\n").unwrap();
+    mapping.sort_keys();
+    let keys: Vec<&Value> = mapping.iter().map(|(k, _)| k).collect();
+    assert_eq!(keys, [&Value::Null, &Value::from(2), &Value::from(10), &Value::from("a"), &Value::from("b")]);
+    // Nested mappings are left alone.
+    assert_eq!(mapping[&Value::from("a")].as_mapping().unwrap().iter().next().unwrap().0, "d");
+
+    // Mappings last, then strings in reverse, then the rest as they were.
+    mapping.sort_by(|k1, v1, k2, v2| {
+        v1.is_mapping().cmp(&v2.is_mapping()).then(k2.as_str().cmp(&k1.as_str()))
+    });
+    let keys: Vec<&Value> = mapping.iter().map(|(k, _)| k).collect();
+    assert_eq!(keys, [&Value::from("b"), &Value::Null, &Value::from(2), &Value::from(10), &Value::from("a")]);
+    assert_eq!(mapping[&Value::from(10)], 3);
+}
+
+#[test]
+fn test_de_value_try_into() {
+    use serde_yaml::Value;
+
+    let value: Value = serde_yaml::from_str("[true, -1, 18446744073709551615, 2.5, s, [], {}, ~]").unwrap();
+    let items = value.try_into_sequence().unwrap();
+    assert!(items[0].clone().try_into_bool().unwrap());
+    assert_eq!(items[1].clone().try_into_i64().unwrap(), -1);
+    assert_eq!(items[2].clone().try_into_u64().unwrap(), u64::MAX);
+    assert_eq!(items[3].clone().try_into_f64().unwrap(), 2.5);
+    assert_eq!(items[4].clone().try_into_string().unwrap(), "s");
+    assert!(items[6].clone().try_into_mapping().unwrap().is_empty());
+
+    let err = |result: Result<(), serde_yaml::Error>| result.unwrap_err().to_string();
+    assert_eq!(err(items[1].clone().try_into_u64().map(drop)),
+               "invalid value: integer `-1`, expected a u64");
+    assert_eq!(err(items[2].clone().try_into_i64().map(drop)),
+               "invalid value: integer `18446744073709551615`, expected an i64");
+    assert_eq!(err(items[5].clone().try_into_mapping().map(drop)),
+               "invalid type: sequence, expected a mapping");
+    assert_eq!(err(items[7].clone().try_into_f64().map(drop)),
+               "invalid type: unit value, expected an f64");
+    assert_eq!(err(items[0].clone().try_into_string().map(drop)),
+               "invalid type: boolean `true`, expected a string");
+}
+
+#[test]
+fn test_de_dates_and_times() {
+    use serde::Deserialize;
+    use serde_yaml::{Dates, Deserializer, Times, Value};
+
+    #[derive(Deserialize, PartialEq, Debug)]
+    enum When {
+        #[serde(rename = "!!timestamp")]
+        Date(String),
+        #[serde(rename = "later")]
+        Later,
+    }
+
+    let de = Deserializer::from_str("[2024-05-01, later]").dates(Dates::Timestamp);
+    assert_eq!(Vec::<When>::deserialize(de).unwrap(), [When::Date("2024-05-01".to_owned()), When::Later]);
+    let de = Deserializer::from_str("'2024-05-01'").dates(Dates::Error);
+    assert_eq!(String::deserialize(de).unwrap(), "2024-05-01");
+    let de = Deserializer::from_str("2024-05-01").dates(Dates::Timestamp);
+    assert_eq!(String::deserialize(de).unwrap(), "2024-05-01");
+    let de = Deserializer::from_str("2024-05-01").dates(Dates::Error);
+    assert!(String::deserialize(de).is_err());
+    let de = Deserializer::from_str("[2024-5-1, 2024-05-01T10:00:00Z]").dates(Dates::Error);
+    assert_eq!(Value::deserialize(de).unwrap()[0], "2024-5-1");
+
+    let yaml = "[1:20, 190:20:30.15, -1_0:00, +1:00, 12:60, 1:2:3, 00:11:22, 1:20.5.1, 1::2]";
+    let value = Value::deserialize(Deserializer::from_str(yaml).times(Times::Sexagesimal)).unwrap();
+    assert_eq!(value[0], 80);
+    assert_eq!(value[1].as_f64(), Some(685230.15));
+    assert_eq!(value[2], -600);
+    assert_eq!(value[3], 60);
+    assert_eq!(value[4], "12:60");
+    assert_eq!(value[5], 3723);
+    assert_eq!(value[6], "00:11:22");
+    assert_eq!(value[7], "1:20.5.1");
+    assert_eq!(value[8], "1::2");
+
+    let de = Deserializer::from_str("timeout: 1:30").times(Times::Sexagesimal);
+    assert_eq!(std::collections::BTreeMap::<String, u32>::deserialize(de).unwrap()["timeout"], 90);
+    let de = Deserializer::from_str("mac: 10:20:30:40:50:59").times(Times::Error);
+    let err = std::collections::BTreeMap::<String, String>::deserialize(de).unwrap_err();
+    assert_eq!(err.to_string(),
+               "mac: unquoted `10:20:30:40:50:59` is a base 60 number in YAML 1.1; quote it to keep it a string \
+                at line 1 column 6");
+    let de = Deserializer::from_str("99999999999999999999:00").times(Times::Sexagesimal);
+    assert_eq!(Value::deserialize(de).unwrap(), "99999999999999999999:00");
+}
+
+#[test]
+fn test_de_merge_keys() {
+    use serde::Deserialize;
+    use serde_yaml::{Deserializer, MergeKeys, Value};
+
+    #[derive(Deserialize, PartialEq, Debug)]
+    #[serde(deny_unknown_fields)]
+    struct Server {
+        host: String,
+        port: u16,
+    }
+
+    let yaml = unindent("
+        base: &base
+          host: localhost
+          port: 80
+        web:
+          <<: *base
+          port: 8080");
+    let de = Deserializer::from_str(&yaml).merge_keys(MergeKeys::Expand);
+    let servers = BTreeMap::<String, Server>::deserialize(de).unwrap();
+    assert_eq!(servers["web"], Server { host: "localhost".to_owned(), port: 8080 });
+    let err = BTreeMap::<String, Server>::deserialize(Deserializer::from_str(&yaml)).unwrap_err();
+    assert!(err.to_string().contains("unknown field `<<`"), "{}", err);
+    let de = Deserializer::from_str(&yaml).merge_keys(MergeKeys::Error);
+    let err = BTreeMap::<String, Server>::deserialize(de).unwrap_err();
+    assert_eq!(err.to_string(), "merge keys are not allowed at line 5 column 3");
+
+    // Merged entries go where `<<` is. Keys of the mapping itself win, then
+    // earlier sources over later ones, and merged mappings are expanded first.
+    let yaml = unindent("
+        - &a {x: 1, y: 1}
+        - &b {<<: *a, y: 2, z: 2}
+        - {<<: [*b, {w: 3, x: 3}], z: 4, '<<': quoted}");
+    let value = Value::deserialize(Deserializer::from_str(&yaml).merge_keys(MergeKeys::Expand)).unwrap();
+    assert_eq!(serde_yaml::to_string(&value[1]).unwrap(), "---\nx: 1\n\"y\": 2\nz: 2");
+    assert_eq!(serde_yaml::to_string(&value[2]).unwrap(), "---\nx: 1\n\"y\": 2\nw: 3\nz: 4\n\"<<\": quoted");
+
+    let de = Deserializer::from_str("{<<: 1}").merge_keys(MergeKeys::Expand);
+    assert!(Value::deserialize(de).is_err());
+    let de = Deserializer::from_str("&a {b: {<<: *a}}").merge_keys(MergeKeys::Expand);
+    let err = Value::deserialize(de).unwrap_err();
+    assert_eq!(err.to_string(), "merge key refers to a mapping that contains it at line 1 column 13");
+
+    // A `<<` that is a value or a sequence element is a string.
+    let yaml = "{a: <<, b: [<<, {c: <<}], <<: {d: 1}}";
+    let value = Value::deserialize(Deserializer::from_str(yaml).merge_keys(MergeKeys::Expand)).unwrap();
+    assert_eq!(value["a"], "<<");
+    assert_eq!(value["b"][0], "<<");
+    assert_eq!(value["b"][1]["c"], "<<");
+    assert_eq!(value["d"], 1);
+    let value = Value::deserialize(Deserializer::from_str("[<<, {a: <<}]").merge_keys(MergeKeys::Error)).unwrap();
+    assert_eq!(value[1]["a"], "<<");
+    let err = Value::deserialize(Deserializer::from_str(yaml).merge_keys(MergeKeys::Error)).unwrap_err();
+    assert_eq!(err.to_string(), "merge keys are not allowed at line 1 column 27");
+}
+
+#[test]
+fn test_de_value_numbers() {
+    use serde_yaml::{Number, Value};
+
+    let yaml = unindent("
+        ---
+        - 1
+        - -0
+        - -3
+        - 18446744073709551615
+        - 18446744073709551616
+        - .5
+        - -2.5e-3
+        - 0x10
+        - +7
+        - .Inf
+        - '4'
+        - &n 12
+        - *n");
+    let value: Value = serde_yaml::from_str(&yaml).unwrap();
+    let expected = vec![Value::Number(Number::from(1)),
+                        Value::Number(Number::from(0)),
+                        Value::Number(Number::from(-3)),
+                        Value::Number(Number::from(u64::MAX)),
+                        Value::Number(Number::from(18446744073709551616.0)),
+                        Value::Number(Number::from(0.5)),
+                        Value::Number(Number::from(-2.5e-3)),
+                        Value::Number(Number::from(16)),
+                        Value::Number(Number::from(7)),
+                        Value::Number(Number::from(f64::INFINITY)),
+                        Value::String("4".to_owned()),
+                        Value::Number(Number::from(12)),
+                        Value::Number(Number::from(12))];
+    assert_eq!(value, Value::Sequence(expected));
+    assert_eq!(value[2].as_i64(), Some(-3));
+    assert_eq!(value[3].as_u64(), Some(u64::MAX));
+    assert_eq!(value[5].as_f64(), Some(0.5));
+    assert_eq!(serde_yaml::from_value::<f64>(value[6].clone()).unwrap(), -2.5e-3);
+
+    let mapping: Value = serde_yaml::from_str("{1: one, -2: two, 0.5: half}").unwrap();
+    assert_eq!(mapping[1].as_str(), Some("one"));
+    assert_eq!(mapping[&Value::Number(Number::from(-2))].as_str(), Some("two"));
+    assert_eq!(serde_yaml::to_string(&mapping).unwrap(), "---\n1: one\n-2: two\n0.5: half");
+}
+
+#[test]
+fn test_de_non_finite_floats() {
+    use serde::Deserialize;
+    use serde_yaml::{Deserializer, Value};
+
+    let spec = [".inf", ".Inf", ".INF", "+.inf", "-.inf", "-.Inf", "-.INF", ".nan", ".NaN", ".NAN"];
+    for &yaml in &spec {
+        let f: f64 = serde_yaml::from_str(yaml).unwrap();
+        assert!(!f.is_finite(), "{}", yaml);
+        let f = f64::deserialize(Deserializer::from_str(yaml).strict_floats(true)).unwrap();
+        assert!(!f.is_finite(), "{}", yaml);
+        let f: f64 = serde_yaml::from_str(&format!("!!float {}", yaml)).unwrap();
+        assert!(!f.is_finite(), "{}", yaml);
+    }
+    assert_eq!(serde_yaml::from_str::<f64>("-.Inf").unwrap(), f64::NEG_INFINITY);
+
+    for &yaml in &["inf", "-Infinity", "NaN", "+nan"] {
+        let value: Value = serde_yaml::from_str(yaml).unwrap();
+        assert!(value.is_f64(), "{}", yaml);
+        let value = Value::deserialize(Deserializer::from_str(yaml).strict_floats(true)).unwrap();
+        assert_eq!(value, Value::String(yaml.to_owned()));
+    }
+    assert!(f64::deserialize(Deserializer::from_str("Infinity").strict_floats(true)).is_err());
+    assert_eq!(serde_yaml::from_str::<Value>(".Nan").unwrap(), Value::String(".Nan".to_owned()));
+}
+
+#[test]
+fn test_de_whole_floats() {
+    use serde::Deserialize;
+    use serde_yaml::Deserializer;
+
+    fn whole(yaml: &str) -> Deserializer<'_> {
+        Deserializer::from_str(yaml).whole_floats(true)
+    }
+
+    assert_eq!(u8::deserialize(whole("3.0")).unwrap(), 3);
+    assert_eq!(u64::deserialize(whole("+18446744073709551615.000")).unwrap(), u64::MAX);
+    assert_eq!(i32::deserialize(whole("-12.")).unwrap(), -12);
+    assert_eq!(i64::deserialize(whole("-0.0")).unwrap(), 0);
+    assert_eq!(Vec::<u16>::deserialize(whole("[&a 7.0, *a, 8]")).unwrap(), [7, 7, 8]);
+    assert_eq!(f64::deserialize(whole("3.0")).unwrap(), 3.0);
+
+    for &yaml in &["3.5", "3e0", "1.0e2", ".0", "-.0", "'3.0'", "!!float 3.0", "18446744073709551616.0"] {
+        assert!(u64::deserialize(whole(yaml)).is_err(), "{}", yaml);
+    }
+    assert!(u8::deserialize(whole("256.0")).is_err());
+    assert!(u32::deserialize(whole("-1.0")).is_err());
+    assert!(u32::deserialize(Deserializer::from_str("3.0")).is_err());
+}
+
+#[test]
+fn test_de_number_dialect() {
+    use serde::Deserialize;
+    use serde_yaml::{Deserializer, NumberDialect, Value};
+
+    let yaml = "[1_000, +1_000_000, -2_5.0_5, 1__, _1, 1_a, 0x_ff, 1e5, 2.5E-3, 1_0e1, 1.5, '1_000']";
+    let dialect = NumberDialect::new().underscores(true);
+    let value = Value::deserialize(Deserializer::from_str(yaml).numbers(dialect)).unwrap();
+    assert_eq!(value, serde_yaml::from_str::<Value>(
+        "[1000, 1000000, -25.05, 1, _1, 1_a, 0x_ff, 1e5, 2.5E-3, 10e1, 1.5, '1_000']").unwrap());
+    assert_eq!(u32::deserialize(Deserializer::from_str("1_000").numbers(dialect)).unwrap(), 1000);
+    assert_eq!(u128::deserialize(Deserializer::from_str("1_000").numbers(dialect)).unwrap(), 1000);
+    assert_eq!(String::deserialize(Deserializer::from_str("1_000").numbers(dialect)).unwrap(), "1_000");
+
+    let dialect = NumberDialect::new().underscores(true).exponents(false);
+    let value = Value::deserialize(Deserializer::from_str(yaml).numbers(dialect)).unwrap();
+    assert_eq!(value[7], "1e5");
+    assert_eq!(value[8], "2.5E-3");
+    assert_eq!(value[9], "1_0e1");
+    assert_eq!(value[10], 1.5);
+    assert!(f64::deserialize(Deserializer::from_str("1e5").numbers(dialect)).is_err());
+    assert_eq!(f64::deserialize(Deserializer::from_str("!!float 1e5").numbers(dialect)).unwrap(), 1e5);
+
+    // Integers written with underscores read back in the same dialect.
+    let numbers = vec![12345, -1_000_000, 999];
+    let mut writer = serde_yaml::Writer::new(Vec::new()).underscore_integers(true);
+    writer.write(&numbers).unwrap();
+    let yaml = String::from_utf8(writer.into_inner().unwrap()).unwrap();
+    assert_eq!(yaml, "---\n- 12_345\n- -1_000_000\n- 999\n");
+    let dialect = NumberDialect::new().underscores(true);
+    assert_eq!(Vec::<i64>::deserialize(Deserializer::from_str(&yaml).numbers(dialect)).unwrap(), numbers);
+    assert!(serde_yaml::from_str::<Vec<i64>>(&yaml).is_err());
+}
+
+#[test]
+fn test_de_duplicate_keys() {
+    use serde::Deserialize;
+    use serde_yaml::{Deserializer, DuplicateKeys, Mapping, Value};
+
+    let yaml = unindent("
+        servers:
+          web: {port: 80}
+          db: {port: 5432}
+          web: {port: 8080}
+        ? [1, 2]
+        : a
+        ? [1, 2]
+        : b");
+
+    let mapping: Mapping = serde_yaml::from_str(&yaml).unwrap();
+    let servers = mapping[&Value::from("servers")].as_mapping().unwrap();
+    let keys: Vec<&str> = servers.iter().map(|(k, _)| k.as_str().unwrap()).collect();
+    assert_eq!(keys, ["db", "web"]);
+    assert_eq!(servers[&Value::from("web")]["port"], 8080);
+
+    let de = Deserializer::from_str(&yaml).duplicate_keys(DuplicateKeys::First);
+    let mapping = Mapping::deserialize(de).unwrap();
+    let expected = unindent("
+        ---
+        servers:
+          web:
+            port: 80
+          db:
+            port: 5432
+        ? - 1
+          - 2
+        : a");
+    assert_eq!(serde_yaml::to_string(&mapping).unwrap(), expected.trim_end());
+
+    let de = Deserializer::from_str(&yaml).duplicate_keys(DuplicateKeys::Error);
+    let err = BTreeMap::<String, Value>::deserialize(de).unwrap_err();
+    assert_eq!(err.to_string(), "servers: duplicate key `web` at line 4 column 3");
+
+    let de = Deserializer::from_str("{a: 1, [1, 2]: 2, [1, 2]: 3}").duplicate_keys(DuplicateKeys::Error);
+    let err = Value::deserialize(de).unwrap_err();
+    assert_eq!(err.to_string(), "duplicate key at line 1 column 19");
+
+    // Keys are compared as values, so different spellings of a key and
+    // aliases to it are the same key.
+    let de = Deserializer::from_str("{&k 1: a, 0x1: b, *k: c, '1': d}").duplicate_keys(DuplicateKeys::First);
+    let map = Mapping::deserialize(de).unwrap();
+    assert_eq!(map.len(), 2);
+    assert_eq!(map[&Value::from(1)], "a");
+    assert_eq!(map[&Value::from("1")], "d");
+}
+
+#[test]
+fn test_de_partial() {
+    use serde_yaml::Value;
+
+    // Offsets are in bytes, whatever the characters before them.
+    let input = "name: café\n...\n--- [1, 2]\n";
+    let (first, offset): (BTreeMap<String, String>, usize) = serde_yaml::from_str_partial(input).unwrap();
+    assert_eq!(first["name"], "café");
+    assert_eq!(&input[offset..], "\n--- [1, 2]\n");
+    assert!(serde_yaml::from_str::<Value>(input).is_err());
+
+    let rest = &input[offset..];
+    let (second, offset): (Vec<u8>, usize) = serde_yaml::from_str_partial(rest).unwrap();
+    assert_eq!(second, [1, 2]);
+    assert_eq!(offset, rest.len());
+
+    // The text after the first document is never parsed.
+    let (value, offset): (u8, usize) = serde_yaml::from_str_partial("--- 1\n--- {\n").unwrap();
+    assert_eq!((value, offset), (1, 6));
+
+    // No document reads as null, like it does for `from_str`.
+    for input in &["", "# nothing\n"] {
+        let (value, offset): (Value, usize) = serde_yaml::from_str_partial(input).unwrap();
+        assert_eq!((value, offset), (Value::Null, input.len()));
+        let (value, consumed): (Option<u8>, usize) = serde_yaml::from_slice_partial(input.as_bytes()).unwrap();
+        assert_eq!((value, consumed), (None, input.len()));
+        assert!(serde_yaml::from_str_partial::<u8>(input).is_err());
+    }
+    assert!(serde_yaml::from_str_partial::<Value>("[1, 2\n--- 3").is_err());
+}
+
+#[test]
+fn test_de_slice_partial() {
+    use serde_yaml::Value;
+
+    let (value, consumed): (Value, usize) = serde_yaml::from_slice_partial(b"[1, 2]\n---\n\xff").unwrap();
+    assert_eq!(value, serde_yaml::from_str::<Value>("[1, 2]").unwrap());
+    assert_eq!(consumed, 7);
+
+    // The document might go on past bytes that are not UTF-8.
+    for input in &[&b"a: b\n\xff"[..], b"[1, \xff]"] {
+        let err = serde_yaml::from_slice_partial::<Value>(input).unwrap_err();
+        assert!(err.to_string().starts_with("invalid utf-8"), "{}", err);
+    }
+
+    let err = serde_yaml::from_slice_partial::<u8>(b"x\n...\n\xff").unwrap_err();
+    assert_eq!(err.to_string(), "invalid type: string \"x\", expected u8 at line 1 column 1");
+}
+
+#[test]
+fn test_de_tabs() {
+    use serde::Deserialize;
+    use serde_yaml::{Deserializer, Tabs, Value};
+
+    let yaml = "server:\n  \thost: a\n  port: 1\ntext: |\n  x\n  \ty\nafter: b\n";
+    let value = Value::deserialize(Deserializer::from_str(yaml)).unwrap();
+    assert_eq!(value["server"]["\thost"], "a");
+
+    let de = Deserializer::from_str(yaml).tabs(Tabs::Reject);
+    let err = Value::deserialize(de).unwrap_err();
+    assert_eq!(err.to_string(), "found a tab character in indentation at line 2 column 3");
+    assert_eq!(err.location().unwrap().index(), 10);
+
+    // Tabs inside a block scalar are content.
+    let de = Deserializer::from_str("text: |\n  x\n  \ty\nafter: b\n").tabs(Tabs::Reject);
+    let value = Value::deserialize(de).unwrap();
+    assert_eq!(value["text"], "x\n\ty\n");
+
+    // A tab goes to the next multiple of the width, whatever is before it.
+    let yaml = "server:\n\thost: a\n  \tport: 1\n\ttext: |\n\t  x\n\t  \ty\n";
+    let de = Deserializer::from_str(yaml).tabs(Tabs::Expand(4));
+    let value = Value::deserialize(de).unwrap();
+    let expected = "server:\n    host: a\n    port: 1\n    text: |\n      x\n      \ty\n";
+    assert_eq!(value, serde_yaml::from_str::<Value>(expected).unwrap());
+}
+
+#[test]
+fn test_de_profile() {
+    use serde::Deserialize;
+    use serde_yaml::{Deserializer, Profile};
+
+    #[derive(Deserialize)]
+    struct Config {
+        defaults: BTreeMap<String, u16>,
+        services: Vec<BTreeMap<String, u16>>,
+    }
+
+    let profile = Profile::new();
+    let yaml = "defaults: &d {port: 80}\nservices: [*d, *d, {port: 443}]";
+    let config = Config::deserialize(Deserializer::from_str(yaml).profile(&profile)).unwrap();
+    assert_eq!(config.services[1], config.defaults);
+    assert_eq!(profile.scalars(), 6);
+    assert_eq!(profile.aliases_resolved(), 2);
+    assert_eq!(profile.string_bytes(), 8 + 4 + 2 + 8 + 4 + 3);
+
+    // Counters add up, also for input that fails to deserialize.
+    let copy = profile.clone();
+    assert!(u8::deserialize(Deserializer::from_reader(&b"256"[..]).profile(&copy)).is_err());
+    assert_eq!(profile.scalars(), 7);
+    assert_eq!(profile.string_bytes(), 32);
+
+    // Nothing is counted for input that does not parse.
+    assert!(u8::deserialize(Deserializer::from_str("[").profile(&profile)).is_err());
+    assert_eq!(profile.scalars(), 7);
+}
+
+#[test]
+fn test_de_deprecations() {
+    use serde::Deserialize;
+    use serde_yaml::{Deprecations, Deserializer, Value};
+
+    #[derive(Deserialize)]
+    struct Config {
+        name: String,
+    }
+
+    let deprecations = Deprecations::new()
+        .deprecate("*.timeout", "use `timeouts.connect` instead")
+        .deprecate("db.*", "the database section is gone")
+        .deprecate("hosts[*]", "never reported, hosts holds a sequence of scalars");
+    let yaml = "name: app\ndb: {timeout: 5, pool: 2}\nhosts: [a, b]\nlegacy: {timeout: 1}";
+    let config = Config::deserialize(Deserializer::from_str(yaml).deprecations(&deprecations)).unwrap();
+    assert_eq!(config.name, "app");
+    let warnings: Vec<String> = deprecations.warnings().iter().map(ToString::to_string).collect();
+    assert_eq!(warnings, [
+        "2:6: `db.timeout` is deprecated: use `timeouts.connect` instead",
+        "2:18: `db.pool` is deprecated: the database section is gone",
+        "4:10: `legacy.timeout` is deprecated: use `timeouts.connect` instead",
+    ]);
+
+    // Warnings add up, and keys inside aliases are reported at each path.
+    let yaml = "a: &x {timeout: 1}\nb: *x";
+    Value::deserialize(Deserializer::from_str(yaml).deprecations(&deprecations)).unwrap();
+    let paths: Vec<String> = deprecations.warnings().iter().map(|w| w.path().to_owned()).collect();
+    assert_eq!(paths, ["db.timeout", "db.pool", "legacy.timeout", "a.timeout", "b.timeout"]);
+}
+
+#[test]
+fn test_de_anchor_policy() {
+    use serde::Deserialize;
+    use serde_yaml::{AnchorPolicy, AnchorWarnings, Deserializer, Value};
+
+    let redefined = "a: &x 1\nb: *x\nc: &x [2]\nd: *x\n";
+    let undefined = "a: [*nothing, 1]\nb: *nothing";
+    let parse = |yaml: &str, policy: AnchorPolicy| {
+        Value::deserialize(Deserializer::from_str(yaml).anchor_policy(policy))
+    };
+
+    // By default a redefinition is fine and an undefined alias is not.
+    let value = parse(redefined, AnchorPolicy::Last).unwrap();
+    assert_eq!(value["b"], 1);
+    assert_eq!(value["d"][0], 2);
+    assert!(parse(undefined, AnchorPolicy::Last).is_err());
+
+    let err = parse(redefined, AnchorPolicy::Error).unwrap_err();
+    assert_eq!(err.to_string(),
+               "while parsing anchor, found anchor `x` defined again at line 3 column 4");
+    let err = parse(undefined, AnchorPolicy::Error).unwrap_err();
+    assert_eq!(err.location().unwrap().line(), 1);
+    // Documents have anchors of their own.
+    let err = parse("--- &x 1\n--- &x 2\n", AnchorPolicy::Error).unwrap_err();
+    assert!(!err.to_string().contains("anchor"));
+
+    let warnings = AnchorWarnings::new();
+    let value = parse(redefined, AnchorPolicy::Warn(warnings.clone())).unwrap();
+    assert_eq!(value["d"][0], 2);
+    let value = parse(undefined, AnchorPolicy::Warn(warnings.clone())).unwrap();
+    assert_eq!(value["a"][0], Value::Null);
+    assert_eq!(value["a"][1], 1);
+    assert_eq!(value["b"], Value::Null);
+    let seen: Vec<(String, usize, bool)> = warnings.warnings()
+        .iter()
+        .map(|w| (w.name().to_owned(), w.location().line(), w.is_redefinition()))
+        .collect();
+    assert_eq!(seen,
+               [("x".to_owned(), 3, true),
+                ("nothing".to_owned(), 1, false),
+                ("nothing".to_owned(), 2, false)]);
+
+    let value = parse(undefined, AnchorPolicy::Placeholder).unwrap();
+    assert_eq!(value["a"][0], "*nothing");
+    #[cfg(feature = "tags")]
+    {
+        use serde_yaml::{TagAction, TagPolicy};
+        let tags = TagPolicy::new().tag("!alias", TagAction::Wrap);
+        let de = Deserializer::from_str(undefined).anchor_policy(AnchorPolicy::Placeholder).tag_policy(tags);
+        let value = Value::deserialize(de).unwrap();
+        assert_eq!(value["b"]["!alias"], "*nothing");
+    }
+}
+
+#[test]
+fn test_de_anchor_scope() {
+    use serde_yaml::{AnchorPolicy, AnchorScope, Deserializer, Value};
+
+    let yaml = "--- &a 1\n--- &b [*a]\n--- &a 2\n--- [*a, *b, *c]\n";
+    let documents = |scope: AnchorScope, policy: AnchorPolicy| {
+        Deserializer::from_str(yaml).anchor_scope(scope).anchor_policy(policy).documents::<Vec<Value>>()
+    };
+
+    let err = documents(AnchorScope::Document, AnchorPolicy::Last).unwrap_err();
+    assert_eq!(err.to_string(),
+               "while parsing node, found unknown anchor `a`, which is defined in an earlier document; \
+                see `AnchorScope` at line 2 column 9");
+    let err = serde_yaml::from_str_all::<Value>(yaml).unwrap_err();
+    assert_eq!(err.location().unwrap().line(), 2);
+
+    // The latest definition before the alias wins, and anchors that are not
+    // defined anywhere before are still undefined.
+    let err = documents(AnchorScope::Stream, AnchorPolicy::Last).unwrap_err();
+    assert_eq!(err.to_string(), "while parsing node, found unknown anchor `c` at line 4 column 14");
+    let value = documents(AnchorScope::Stream, AnchorPolicy::Placeholder).unwrap();
+    assert_eq!(value[1][0], 1);
+    assert_eq!(value[3][0], 2);
+    assert_eq!(value[3][1][0], 1);
+    assert_eq!(value[3][2], "*c");
+
+    let value = documents(AnchorScope::Document, AnchorPolicy::Placeholder).unwrap();
+    assert_eq!(value[1][0], "*a");
+    assert_eq!(value[3][1], "*b");
+
+    // Documents have anchors of their own even when aliases can reach back.
+    let de = Deserializer::from_str("--- &x 1\n--- &x 2\n").anchor_policy(AnchorPolicy::Error);
+    assert_eq!(de.anchor_scope(AnchorScope::Stream).documents::<Vec<u32>>().unwrap(), [1, 2]);
+}
+
+#[test]
+fn test_de_document_iterator() {
+    use serde::Deserialize;
+    use serde_yaml::{AnchorScope, Deserializer, Value};
+
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Script {
+        run: String,
+    }
+
+    let yaml = "name: build\n---\nrun: |\n  ---\n  make\n--- [1, 2]\n---\n";
+    let mut documents = Deserializer::from_str(yaml);
+    let name = BTreeMap::<String, String>::deserialize(documents.next().unwrap()).unwrap();
+    assert_eq!(name["name"], "build");
+    let script = Script::deserialize(documents.next().unwrap()).unwrap();
+    assert_eq!(script, Script { run: "---\nmake\n".to_owned() });
+    assert_eq!(Vec::<u8>::deserialize(documents.next().unwrap()).unwrap(), [1, 2]);
+    assert_eq!(Value::deserialize(documents.next().unwrap()).unwrap(), Value::Null);
+    assert!(documents.next().is_none());
+    assert!(documents.next().is_none());
+
+    // Options and errors are those of the whole stream.
+    let yaml = "--- &a x\n--- [*a, 1]\n--- {a: [*a]}\n";
+    let mut documents = Deserializer::from_str(yaml).anchor_scope(AnchorScope::Stream).error_lines(80);
+    documents.next();
+    assert_eq!(Vec::<String>::deserialize(documents.next().unwrap()).unwrap(), ["x", "1"]);
+    let err = BTreeMap::<String, Vec<u8>>::deserialize(documents.next().unwrap()).unwrap_err();
+    assert_eq!(err.line(), Some("--- &a x"));
+
+    // A stream that does not load yields its error once.
+    let mut documents = Deserializer::from_str("a: 1\n---\n[b\n");
+    assert!(Value::deserialize(documents.next().unwrap()).is_err());
+    assert!(documents.next().is_none());
+}
+
+#[test]
+fn test_events_anchor_policy() {
+    use serde::Deserialize;
+    use serde_yaml::event::Event;
+    use serde_yaml::{AnchorPolicy, Deserializer, Value};
+
+    let events = vec![
+        Event::SequenceStart(None),
+        Event::Scalar(serde_yaml::event::Scalar {
+                          value: "1".to_owned(),
+                          style: serde_yaml::event::ScalarStyle::Plain,
+                          anchor: Some("x".to_owned()),
+                          tag: None,
+                      }),
+        Event::SequenceStart(Some("x".to_owned())),
+        Event::SequenceEnd,
+        Event::Alias("y".to_owned()),
+        Event::SequenceEnd,
+    ];
+    let parse = |policy: AnchorPolicy| {
+        Value::deserialize(Deserializer::from_events(events.clone()).anchor_policy(policy))
+    };
+    assert_eq!(parse(AnchorPolicy::Last).unwrap_err().to_string(), "unknown anchor `y`");
+    assert_eq!(parse(AnchorPolicy::Error).unwrap_err().to_string(), "anchor `x` is defined again");
+    assert_eq!(parse(AnchorPolicy::Placeholder).unwrap()[2], "*y");
+}
+
+#[test]
+fn test_de_no_document() {
+    use serde_yaml::{Deserializer, Value};
+
+    for yaml in &["", "  \n\n", "# only a comment\n", "%YAML 1.2\n---\n", "---\n", "---\n...\n", "--- # c\n"] {
+        assert_eq!(serde_yaml::from_str::<Value>(yaml).unwrap(), Value::Null, "{:?}", yaml);
+        assert_eq!(serde_yaml::from_str::<Option<Vec<u32>>>(yaml).unwrap(), None, "{:?}", yaml);
+        serde_yaml::from_str::<()>(yaml).unwrap();
+        assert_eq!(Deserializer::from_str(yaml).into_value().unwrap(), Value::Null, "{:?}", yaml);
+    }
+    for yaml in &["", "# only a comment\n"] {
+        let err = serde_yaml::from_str::<String>(yaml).unwrap_err();
+        assert_eq!(err.to_string(), "EOF while parsing a value");
+        assert!(serde_yaml::from_str::<Vec<u32>>(yaml).is_err());
+    }
+}
+
+#[test]
+fn test_de_documents() {
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Header {
+        kind: String,
+    }
+
+    let yaml = "kind: list\n---\n- 1\n- 2\n--- end\n";
+    let (header, items, end): (Header, Vec<u32>, String) = serde_yaml::from_str_documents(yaml).unwrap();
+    assert_eq!(header, Header { kind: "list".to_owned() });
+    assert_eq!(items, [1, 2]);
+    assert_eq!(end, "end");
+
+    let all: Vec<serde_yaml::Value> = serde_yaml::from_str_documents(yaml).unwrap();
+    assert_eq!(all.len(), 3);
+    assert_eq!(serde_yaml::from_str_documents::<Vec<u32>>("").unwrap(), Vec::<u32>::new());
+
+    let err = serde_yaml::from_str_documents::<(Header, Vec<u32>)>(yaml).unwrap_err();
+    assert_eq!(err.to_string(), "expected 2 documents, found 3");
+    let err = serde_yaml::from_str_documents::<(u32, u32)>("1\n").unwrap_err();
+    assert_eq!(err.to_string(), "expected 2 documents, found 1");
+    assert!(serde_yaml::from_str_documents::<(u32, u32)>("1\n--- x\n").is_err());
+
+    let all: Vec<u32> = serde_yaml::from_str_all("1\n--- 2\n---\n3\n").unwrap();
+    assert_eq!(all, [1, 2, 3]);
+    assert!(serde_yaml::from_str_all::<u32>("# nothing\n").unwrap().is_empty());
+    assert!(serde_yaml::from_str_all::<u32>("1\n--- x\n").is_err());
+}
+
+#[test]
+fn test_de_fragment() {
+    use serde_yaml::Value;
+
+    let fragment = "      # comment\n    a: 1\n\n    b:\n      - |\n        x\n\n        y\n  # outdented comment\n    c: \"  q\"";
+    let value: Value = serde_yaml::from_fragment(fragment).unwrap();
+    let expected: Value = serde_yaml::from_str("a: 1\nb: [\"x\\n\\ny\\n\"]\nc: \"  q\"").unwrap();
+    assert_eq!(value, expected);
+
+    assert_eq!(serde_yaml::from_fragment::<u32>("   7").unwrap(), 7);
+    assert_eq!(serde_yaml::from_fragment::<Value>("  ").unwrap(), Value::Null);
+
+    let mut map = std::collections::BTreeMap::new();
+    map.insert("script", "make\n\nmake test\n");
+    map.insert("name", "a: b");
+    let fragment = serde_yaml::to_string_indented(&map, 6).unwrap();
+    let back: std::collections::BTreeMap<String, String> = serde_yaml::from_fragment(&fragment).unwrap();
+    assert_eq!(back["script"], map["script"]);
+    assert_eq!(back["name"], map["name"]);
+
+    let err = serde_yaml::from_fragment::<Value>("  a: 1\n  b: [\n").unwrap_err();
+    assert_eq!(err.location().unwrap().line(), 3);
+}
+
+#[test]
+fn test_de_rc_arc() {
+    use std::rc::Rc;
+    use std::sync::Arc;
+
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Config {
+        name: String,
+        ports: Vec<u16>,
+    }
+
+    let yaml = "name: web\nports: [80, 443]\n";
+    let expected = Config {
+        name: "web".to_owned(),
+        ports: vec![80, 443],
+    };
+    let boxed = serde_yaml::from_str::<Box<Config>>(yaml).unwrap();
+    assert_eq!(*boxed, expected);
+    let rc = serde_yaml::from_str::<Rc<Config>>(yaml).unwrap();
+    assert_eq!(*rc, expected);
+    let arc = serde_yaml::from_str::<Arc<Config>>(yaml).unwrap();
+    assert_eq!(*arc, expected);
+    assert!(serde_yaml::from_str::<Arc<Config>>("name: web").is_err());
+}
+
+#[test]
+fn test_into_deserializer() {
+    use serde::de::{Deserialize, IntoDeserializer};
+    use serde::de::value::{MapDeserializer, SeqDeserializer};
+    use serde_yaml::{Mapping, Number, Value};
+
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct Env {
+        port: u16,
+        hosts: Vec<String>,
+    }
+
+    fn from<'de, D, T>(input: D) -> serde_yaml::Result<T>
+        where D: IntoDeserializer<'de, serde_yaml::Error>,
+              T: Deserialize<'de>
+    {
+        T::deserialize(input.into_deserializer())
+    }
+
+    let value: Value = serde_yaml::from_str("{port: 80, hosts: [a, b]}").unwrap();
+    let expected = Env { port: 80, hosts: vec!["a".to_owned(), "b".to_owned()] };
+    assert_eq!(from::<_, Env>(&value).unwrap(), expected);
+    assert_eq!(from::<_, Env>(value.as_mapping().unwrap().clone()).unwrap(), expected);
+    assert_eq!(from::<_, Env>(value.clone()).unwrap(), expected);
+    assert_eq!(from::<_, u8>(Number::from(7)).unwrap(), 7);
+    assert_eq!(from::<_, f64>(&Number::from(0.5)).unwrap(), 0.5);
+
+    let entries = vec![(Value::from("port"), Value::from(8080)), (Value::from("hosts"), value["hosts"].clone())];
+    let map = MapDeserializer::<_, serde_yaml::Error>::new(entries.into_iter());
+    assert_eq!(Env::deserialize(map).unwrap().port, 8080);
+    let seq = SeqDeserializer::<_, serde_yaml::Error>::new(vec![Mapping::new()].into_iter());
+    assert_eq!(Vec::<BTreeMap<String, u32>>::deserialize(seq).unwrap(), [BTreeMap::new()]);
+}
@@ -0,0 +1,61 @@
+// Copyright 2018 Serde YAML Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+#![cfg(all(feature = "de", feature = "value"))]
+
+extern crate serde_yaml;
+
+extern crate unindent;
+use unindent::unindent;
+
+use serde_yaml::Value;
+
+mod common;
+use common::yaml;
+
+#[test]
+fn test_scalars() {
+    assert_eq!(format!("{:?}", Value::Null), "Null");
+    assert_eq!(format!("{:?}", yaml("true")), "Bool(true)");
+    assert_eq!(format!("{:?}", yaml("-3")), "Number(-3)");
+    assert_eq!(format!("{:?}", yaml("2.0")), "Number(2.0)");
+    assert_eq!(format!("{:?}", yaml(".nan")), "Number(.nan)");
+    assert_eq!(format!("{:?}", yaml("'a \"b\"'")), r#"String("a \"b\"")"#);
+}
+
+#[test]
+fn test_compact() {
+    let value = yaml("{name: web, ports: [80, 443], tls: ~, [1, 2]: {}}");
+    assert_eq!(format!("{:?}", value),
+               "Mapping {\"name\": String(\"web\"), \"ports\": Sequence [Number(80), Number(443)], \
+                \"tls\": Null, Sequence [Number(1), Number(2)]: Mapping {}}");
+}
+
+#[test]
+fn test_alternate() {
+    let value = yaml("{name: web, ports: [80], env: {}}");
+    let expected = unindent(r#"
+        Mapping {
+            "name": String("web"),
+            "ports": Sequence [
+                Number(80),
+            ],
+            "env": Mapping {},
+        }"#);
+    assert_eq!(format!("{:#?}", value), expected.trim());
+}
+
+#[test]
+fn test_exact() {
+    let value = yaml("[1, -1, 1.0, x]");
+    assert_eq!(format!("{:?}", value.exact_debug()),
+               "Sequence([Number(PosInt(1)), Number(NegInt(-1)), Number(Float(1.0)), String(\"x\")])");
+    let value = yaml("{a: {}}");
+    assert_eq!(format!("{:?}", value.exact_debug()),
+               "Mapping(Mapping { map: {String(\"a\"): Mapping(Mapping { map: {} })} })");
+}
@@ -0,0 +1,106 @@
+// Copyright 2018 Serde YAML Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+#![cfg(all(feature = "de", feature = "value"))]
+
+extern crate serde;
+extern crate serde_yaml;
+
+use std::thread;
+
+use serde::Deserialize;
+use serde_yaml::{deep, Deprecations, Deserializer, DuplicateKeys, Value};
+
+/// Runs `f` on a thread with a stack far too small for recursing through
+/// the documents of these tests.
+fn with_small_stack<F>(f: F)
+    where F: FnOnce() + Send + 'static
+{
+    thread::Builder::new().stack_size(256 * 1024).spawn(f).unwrap().join().unwrap();
+}
+
+#[test]
+fn test_same_as_recursive() {
+    let yaml = r#"
+        name: app
+        "quoted: key": ["", "1", true, ~, 1.5, -2, .inf]
+        empty: {seq: [], map: {}}
+        nested:
+          - - a
+            - b
+          - {c: [d, {e: f}]}
+        [complex, key]: {x: 1}
+        {map: key}: [1]
+        anchored: &a {k: v}
+        alias: *a
+        text: "line\nbreak"
+    "#;
+    let value = deep::from_str(yaml).unwrap();
+    let recursive: Value = serde_yaml::from_str(yaml).unwrap();
+    assert_eq!(value, recursive);
+    assert_eq!(deep::to_string(&value).unwrap(), serde_yaml::to_string(&recursive).unwrap());
+    assert_eq!(deep::to_string(&Value::Null).unwrap(), "---\n~");
+}
+
+#[test]
+fn test_deep_sequences() {
+    with_small_stack(|| {
+        let yaml = format!("---\n{}x", "- ".repeat(50000));
+        let value = deep::from_str(&yaml).unwrap();
+        assert_eq!(deep::to_string(&value).unwrap(), yaml);
+        // Nested sequences share a line, so the estimate grows with the
+        // depth rather than with its square.
+        assert!(value.estimated_yaml_len() < 2 * yaml.len());
+        deep::dispose(value);
+    });
+}
+
+#[test]
+fn test_deep_mappings() {
+    with_small_stack(|| {
+        let mut yaml = String::from("---\n");
+        for depth in 0..2000 {
+            if depth > 0 {
+                yaml.push('\n');
+            }
+            yaml.push_str(&"  ".repeat(depth));
+            yaml.push_str("k:");
+        }
+        yaml.push_str(" leaf");
+        let value = deep::from_str(&yaml).unwrap();
+        assert_eq!(deep::to_string(&value).unwrap(), yaml);
+        deep::dispose(value);
+    });
+}
+
+#[test]
+fn test_options() {
+    let yaml = "a: 1\nb: {old: 2}\na: 3";
+    let de = Deserializer::from_str(yaml).duplicate_keys(DuplicateKeys::First);
+    assert_eq!(de.into_value().unwrap(), serde_yaml::from_str::<Value>("a: 1\nb: {old: 2}").unwrap());
+
+    let de = Deserializer::from_str(yaml).duplicate_keys(DuplicateKeys::Error);
+    assert_eq!(de.into_value().unwrap_err().to_string(), "duplicate key `a` at line 3 column 1");
+
+    let deprecations = Deprecations::new().deprecate("b.old", "use `new` instead");
+    Deserializer::from_str(yaml).deprecations(&deprecations).into_value().unwrap();
+    let warnings: Vec<String> = deprecations.warnings().iter().map(ToString::to_string).collect();
+    assert_eq!(warnings, ["2:5: `b.old` is deprecated: use `new` instead"]);
+
+    let err = deep::from_str("a: !!int x").unwrap_err();
+    let recursive = Value::deserialize(Deserializer::from_str("a: !!int x")).unwrap_err();
+    assert_eq!(err.location(), recursive.location());
+    assert!(deep::from_str("a: 1\n---\nb: 2").is_err());
+}
+
+#[test]
+fn test_alias_across_documents() {
+    let err = serde_yaml::from_str::<Value>("a: &x 1\n---\nb: *x").unwrap_err();
+    assert!(err.to_string().contains("unknown anchor"), "{}", err);
+    assert!(deep::from_str("a: &x 1\nb: *x").is_ok());
+}
@@ -0,0 +1,63 @@
+// Copyright 2018 Serde YAML Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+#![cfg(all(feature = "de", feature = "value"))]
+
+extern crate serde_yaml;
+
+use serde_yaml::SequenceDefaults;
+
+mod common;
+use common::yaml;
+
+#[test]
+fn test_empty_document() {
+    let mut config = yaml("~");
+    config.apply_defaults(&yaml("{a: 1}"));
+    assert_eq!(config, yaml("{a: 1}"));
+}
+
+#[test]
+fn test_mismatched_types_keep_target() {
+    let mut config = yaml("{a: text, b: [1], c: {x: 1}}");
+    config.apply_defaults(&yaml("{a: {nested: 1}, b: {k: v}, c: [2]}"));
+    assert_eq!(config, yaml("{a: text, b: [1], c: {x: 1}}"));
+}
+
+#[test]
+fn test_new_keys_appended_in_defaults_order() {
+    let mut config = yaml("{b: 2}");
+    config.apply_defaults(&yaml("{a: 1, b: 0, c: 3}"));
+    assert_eq!(serde_yaml::to_string(&config).unwrap(), "---\nb: 2\na: 1\nc: 3");
+}
+
+#[test]
+fn test_sequences_by_index() {
+    let defaults = yaml("[{a: 1}, {b: 2}, {c: 3}]");
+
+    let mut config = yaml("[{x: 0}, {}]");
+    config.apply_defaults_with(&defaults, SequenceDefaults::ByIndex);
+    assert_eq!(config, yaml("[{x: 0, a: 1}, {b: 2}]"));
+
+    let mut config = yaml("[{x: 0}, {}]");
+    config.apply_defaults_with(&defaults, SequenceDefaults::Keep);
+    assert_eq!(config, yaml("[{x: 0}, {}]"));
+}
+
+#[test]
+fn test_template_nested() {
+    let defaults = yaml("jobs: [{steps: [{shell: bash}], timeout: 10}]");
+    let mut config = yaml("jobs: [{steps: [{run: make}, {run: test, shell: sh}]}]");
+    config.apply_defaults_with(&defaults, SequenceDefaults::Template);
+    assert_eq!(config,
+               yaml("jobs: [{steps: [{run: make, shell: bash}, {run: test, shell: sh}], timeout: 10}]"));
+
+    let mut config = yaml("[{a: 1}]");
+    config.apply_defaults_with(&yaml("[]"), SequenceDefaults::Template);
+    assert_eq!(config, yaml("[{a: 1}]"));
+}
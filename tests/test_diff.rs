@@ -0,0 +1,92 @@
+// Copyright 2018 Serde YAML Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+#![cfg(all(feature = "de", feature = "value"))]
+
+extern crate serde_yaml;
+
+extern crate unindent;
+use unindent::unindent;
+
+use serde_yaml::Value;
+
+fn test_diff(left: &str, right: &str, expected: &[&str]) {
+    let left: Value = serde_yaml::from_str(&unindent(left)).unwrap();
+    let right: Value = serde_yaml::from_str(&unindent(right)).unwrap();
+    let diff = left.display_diff(&right).to_string();
+    assert_eq!(diff.lines().collect::<Vec<_>>(), expected);
+}
+
+#[test]
+fn test_equal() {
+    test_diff("{a: 1, b: [1, 2]}", "{a: 1, b: [1, 2]}", &["  # 2 unchanged entries"]);
+    test_diff("x", "x", &["  x"]);
+}
+
+#[test]
+fn test_nested_mapping() {
+    test_diff("
+        spec:
+          replicas: 2
+          template:
+            image: web-1
+            env: {A: 1}",
+              "
+        spec:
+          replicas: 2
+          template:
+            image: web-2
+            env: {A: 1}
+          paused: true",
+              &["  spec:",
+                "    replicas: 2",
+                "    template:",
+                "-     image: web-1",
+                "+     image: web-2",
+                "      env: # 1 unchanged entry",
+                "+   paused: true"]);
+}
+
+#[test]
+fn test_sequence_insertion() {
+    test_diff("[a, b, c]",
+              "[a, x, b, c]",
+              &["  - a", "+ - x", "  - b", "  - c"]);
+}
+
+#[test]
+fn test_sequence_of_mappings() {
+    test_diff("[{name: a, port: 80}, {name: b, port: 81}]",
+              "[{name: a, port: 80}, {name: b, port: 82}]",
+              &["  - # 2 unchanged entries",
+                "  -",
+                "    name: b",
+                "-   port: 81",
+                "+   port: 82"]);
+}
+
+#[test]
+fn test_type_change() {
+    test_diff("{a: [1, 2]}",
+              "{a: one}",
+              &["- a:", "-   - 1", "-   - 2", "+ a: one"]);
+}
+
+#[test]
+fn test_removed_and_quoted() {
+    test_diff("{a: '1', b: null, c: 'x:y'}",
+              "{a: 1, c: 'x:y'}",
+              &["- a: \"1\"", "+ a: 1", "- b: ~", "  c: \"x:y\""]);
+}
+
+#[test]
+fn test_complex_key() {
+    test_diff("{[a, b]: 1}",
+              "{[a, b]: 2}",
+              &["- ?", "-   - a", "-   - b", "- : 1", "+ ?", "+   - a", "+   - b", "+ : 2"]);
+}
@@ -0,0 +1,61 @@
+// Copyright 2018 Serde YAML Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+#![cfg(all(feature = "de", feature = "value"))]
+
+extern crate serde_yaml;
+
+use serde_yaml::Value;
+
+mod common;
+use common::yaml;
+
+fn expand(s: &str) -> Value {
+    let mut value = yaml(s);
+    value.expand_dotted_keys().unwrap();
+    value
+}
+
+fn expand_err(s: &str) -> String {
+    yaml(s).expand_dotted_keys().unwrap_err().to_string()
+}
+
+#[test]
+fn test_nested() {
+    assert_eq!(expand("{a.b.c: 1, a.b.d: 2, a.e: 3, f: 4}"), yaml("{a: {b: {c: 1, d: 2}, e: 3}, f: 4}"));
+    assert_eq!(expand("[{a.b: 1}, {c: {d.e: 2}}]"), yaml("[{a: {b: 1}}, {c: {d: {e: 2}}}]"));
+    assert_eq!(expand("a.b"), yaml("a.b"));
+}
+
+#[test]
+fn test_merge() {
+    assert_eq!(expand("{a: {x: 1}, a.y: 2}"), yaml("{a: {x: 1, y: 2}}"));
+    assert_eq!(expand("{a.y: 2, a: {x: 1, z.w: 3}}"), yaml("{a: {y: 2, x: 1, z: {w: 3}}}"));
+    assert_eq!(expand("{a.b: {c: 1}, a: {b.d: 2}}"), yaml("{a: {b: {c: 1, d: 2}}}"));
+}
+
+#[test]
+fn test_order() {
+    let value = expand("{z.a: 1, y: 2, z.b: 3}");
+    let keys: Vec<&str> = value.as_mapping().unwrap().iter().map(|(k, _)| k.as_str().unwrap()).collect();
+    assert_eq!(keys, ["z", "y"]);
+}
+
+#[test]
+fn test_literal_keys() {
+    assert_eq!(expand("{a..b: 1, .c: 2, d.: 3, 1.5: 4}"), yaml("{a..b: 1, .c: 2, d.: 3, 1.5: 4}"));
+}
+
+#[test]
+fn test_conflicts() {
+    assert_eq!(expand_err("{a: 1, a.b: 2}"), "key `a.b` runs through `a`, which is not a mapping");
+    assert_eq!(expand_err("{x: [{a.b: 1, a.b.c: 2}]}"),
+               "key `x[0].a.b.c` runs through `x[0].a.b`, which is not a mapping");
+    assert_eq!(expand_err("{a.b: 1, a: {b: 2}}"), "key `a.b` is given more than one value");
+    assert_eq!(expand_err("{a.b: 1, a: 2}"), "key `a` is both a mapping and not a mapping");
+}
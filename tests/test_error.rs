@@ -6,6 +6,10 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
+#![cfg(all(feature = "de", feature = "value"))]
+
+#![allow(dead_code)]
+
 #[macro_use]
 extern crate serde_derive;
 
@@ -81,8 +85,14 @@ fn test_unknown_anchor() {
     let yaml = unindent("
         ---
         *some");
-    let expected = "while parsing node, found unknown anchor at line 2 column 1";
+    let expected = "while parsing node, found unknown anchor `some` at line 2 column 1";
     test_error::<String>(&yaml, expected);
+
+    let err = serde_yaml::from_str::<String>(&yaml).unwrap_err();
+    assert_eq!(err.kind(), serde_yaml::ErrorKind::UnknownAnchor);
+    // Other errors at an alias are not about its anchor.
+    let err = serde_yaml::from_str::<serde_yaml::Value>("&a [1]\n*a: [").unwrap_err();
+    assert_eq!(err.kind(), serde_yaml::ErrorKind::Other);
 }
 
 #[test]
@@ -196,5 +206,145 @@ fn test_no_location() {
 
     let utf8_location = invalid_utf8.unwrap_err().location();
 
-    assert_eq!(utf8_location.is_none(), true);
-}
\ No newline at end of file
+    assert!(utf8_location.is_none());
+}
+#[test]
+fn test_trailing_content() {
+    let expected = "did not find expected <document start> at line 3 column 1";
+    test_error::<usize>("0\n...\n1", expected);
+}
+
+#[test]
+fn test_clone() {
+    use std::io;
+
+    struct Failing;
+
+    impl io::Read for Failing {
+        fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+            Err(io::Error::other("disk on fire"))
+        }
+    }
+
+    let err = serde_yaml::from_reader::<_, serde_yaml::Value>(Failing).unwrap_err();
+    let clone = err.clone();
+    assert_eq!(clone.to_string(), "disk on fire");
+    assert_eq!(format!("{:?}", clone), format!("{:?}", err));
+
+    let err = serde_yaml::from_str::<Vec<u8>>("[1, 2, 300]").unwrap_err();
+    let clones = vec![err.clone(), err.clone()];
+    for clone in clones {
+        assert_eq!(clone.to_string(), err.to_string());
+        assert_eq!(clone.location(), err.location());
+    }
+}
+
+#[test]
+fn test_source() {
+    use std::error::Error;
+    use std::io;
+    use std::str::Utf8Error;
+
+    struct Failing;
+
+    impl io::Read for Failing {
+        fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+            Err(io::Error::new(io::ErrorKind::PermissionDenied, "denied"))
+        }
+    }
+
+    impl io::Write for Failing {
+        fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+            Err(io::Error::new(io::ErrorKind::WriteZero, "full"))
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let err = serde_yaml::from_reader::<_, serde_yaml::Value>(Failing).unwrap_err();
+    let io = err.source().and_then(|source| source.downcast_ref::<io::Error>()).unwrap();
+    assert_eq!(io.kind(), io::ErrorKind::PermissionDenied);
+
+    let mut writer = serde_yaml::Writer::new(Failing);
+    let err = writer.write(&1).and_then(|()| writer.flush()).unwrap_err();
+    let io = err.source().and_then(|source| source.downcast_ref::<io::Error>()).unwrap();
+    assert_eq!(io.kind(), io::ErrorKind::WriteZero);
+
+    let err = serde_yaml::from_str::<serde_yaml::Value>("a: [").unwrap_err();
+    let source = err.source().unwrap();
+    assert_eq!(source.to_string(), err.to_string());
+    assert!(source.source().is_none());
+
+    let err = serde_yaml::from_slice::<serde_yaml::Value>(b"\xff").unwrap_err();
+    assert!(err.source().unwrap().is::<Utf8Error>());
+
+    let err = serde_yaml::from_str::<u8>("300").unwrap_err();
+    assert!(err.source().is_none());
+}
+
+#[test]
+fn test_error_lines() {
+    use serde::Deserialize;
+    use serde_yaml::{Deserializer, Value};
+    use std::collections::BTreeMap;
+
+    #[derive(Deserialize, Debug)]
+    struct Server {
+        name: String,
+        port: u16,
+    }
+
+    let yaml = "name: web\nport: 70000\n";
+    let err = Server::deserialize(Deserializer::from_str(yaml)).unwrap_err();
+    assert_eq!(err.line(), None);
+
+    let err = Server::deserialize(Deserializer::from_reader(yaml.as_bytes()).error_lines(80)).unwrap_err();
+    assert_eq!(err.line(), Some("port: 70000"));
+    assert_eq!(err.location().unwrap().line(), 2);
+    assert_eq!(err.to_string(),
+               "port: invalid value: integer `70000`, expected u16 at line 2 column 7, in `port: 70000`");
+
+    let yaml = format!("  ports: [{}80, bad, 443, {}3]\n", "1, ".repeat(50), "2, ".repeat(50));
+    let err = BTreeMap::<String, Vec<u16>>::deserialize(Deserializer::from_str(&yaml).error_lines(12)).unwrap_err();
+    assert_eq!(err.line(), Some("..., 80, bad, 4..."));
+
+    let err = Value::deserialize(Deserializer::from_str("key: value: x").error_lines(6)).unwrap_err();
+    assert_eq!(err.location().unwrap().column(), 11);
+    assert_eq!(err.line(), Some("...lue: x"));
+
+    let err = Server::deserialize(Deserializer::from_str("").error_lines(80)).unwrap_err();
+    assert_eq!(err.line(), None);
+}
+
+#[test]
+fn test_block_scalar_locations() {
+    use serde::Deserialize;
+    use serde_yaml::{Deserializer, Value};
+
+    let yaml = "steps:\n  - run: |  # build\n      make\n\t     make test\n";
+    let err = Value::deserialize(Deserializer::from_str(yaml).error_lines(80)).unwrap_err();
+    assert_eq!(err.location().unwrap().line(), 4);
+    assert_eq!(err.location().unwrap().column(), 1);
+    let start = err.block_start().unwrap();
+    assert_eq!((start.line(), start.column(), start.index()), (2, 10, 16));
+    assert_eq!(err.line(), Some("- run: |  # build\nmake test"));
+
+    let err = serde_yaml::from_str::<Value>("a: |0\n  x\n").unwrap_err();
+    let (at, start) = (err.location().unwrap(), err.block_start().unwrap());
+    assert_eq!((at.line(), at.column(), at.index()), (1, 5, 4));
+    assert_eq!((start.line(), start.column(), start.index()), (1, 4, 3));
+    assert_eq!(err.to_string(),
+               "while scanning a block scalar, found an indentation indicator equal to 0 at line 1 column 5, \
+                in the block scalar at line 1 column 4");
+
+    let err = serde_yaml::from_str::<Value>("a: >-  x\n").unwrap_err();
+    assert_eq!(err.location().unwrap().column(), 8);
+    assert_eq!(err.block_start().unwrap().column(), 4);
+    let err = Value::deserialize(Deserializer::from_str("a: >-  x\n").error_lines(80)).unwrap_err();
+    assert_eq!(err.line(), Some("a: >-  x"));
+
+    let err = serde_yaml::from_str::<Value>("key: value: x").unwrap_err();
+    assert_eq!(err.block_start(), None);
+}
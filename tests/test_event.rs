@@ -0,0 +1,163 @@
+// Copyright 2018 Serde YAML Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+#![cfg(all(feature = "de", feature = "value"))]
+
+#[macro_use]
+extern crate serde_derive;
+
+extern crate serde;
+extern crate serde_yaml;
+
+use serde::Deserialize;
+use serde_yaml::event::{Event, Scalar, ScalarStyle};
+use serde_yaml::{Deserializer, Value};
+
+fn scalar(value: &str, style: ScalarStyle, anchor: Option<&str>, tag: Option<&str>) -> Event {
+    Event::Scalar(Scalar {
+        value: value.to_owned(),
+        style,
+        anchor: anchor.map(str::to_owned),
+        tag: tag.map(str::to_owned),
+    })
+}
+
+fn value(events: Vec<Event>) -> Value {
+    Value::deserialize(Deserializer::from_events(events)).unwrap()
+}
+
+fn error(events: Vec<Event>) -> String {
+    Value::deserialize(Deserializer::from_events(events)).unwrap_err().to_string()
+}
+
+#[test]
+fn test_scalars() {
+    let events = vec![
+        Event::SequenceStart(None),
+        Event::plain("1"),
+        scalar("1", ScalarStyle::SingleQuoted, None, None),
+        Event::plain("true"),
+        Event::plain("~"),
+        scalar("2", ScalarStyle::Plain, None, Some("!!str")),
+        scalar("line\n", ScalarStyle::Literal, None, None),
+        Event::SequenceEnd,
+    ];
+    assert_eq!(value(events), serde_yaml::from_str::<Value>("[1, '1', true, ~, '2', \"line\\n\"]").unwrap());
+}
+
+#[test]
+fn test_aliases() {
+    let events = vec![
+        Event::MappingStart(None),
+        Event::plain("a"),
+        Event::SequenceStart(Some("x".to_owned())),
+        scalar("1", ScalarStyle::Plain, Some("y"), None),
+        Event::SequenceEnd,
+        Event::plain("b"),
+        Event::Alias("x".to_owned()),
+        Event::plain("c"),
+        Event::Alias("y".to_owned()),
+        Event::MappingEnd,
+    ];
+    assert_eq!(value(events), serde_yaml::from_str::<Value>("{a: [1], b: [1], c: 1}").unwrap());
+}
+
+#[test]
+fn test_struct() {
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Server {
+        host: String,
+        port: u16,
+    }
+
+    let events = vec![
+        Event::MappingStart(None),
+        Event::plain("host"),
+        Event::plain("localhost"),
+        Event::plain("port"),
+        Event::plain("99999"),
+        Event::MappingEnd,
+    ];
+    let err = Server::deserialize(Deserializer::from_events(events.clone())).unwrap_err();
+    assert_eq!(err.to_string(), "port: invalid value: integer `99999`, expected u16");
+    assert_eq!(err.location(), None);
+}
+
+#[test]
+fn test_malformed() {
+    assert_eq!(error(vec![]), "EOF while parsing a value");
+    assert_eq!(error(vec![Event::SequenceStart(None)]), "collection without an end event");
+    assert_eq!(error(vec![Event::MappingEnd]), "end event without a collection to end");
+    assert_eq!(error(vec![Event::SequenceStart(None), Event::MappingEnd]),
+               "collection ended by the wrong kind of end event");
+    assert_eq!(error(vec![Event::MappingStart(None), Event::plain("k"), Event::MappingEnd]),
+               "mapping has a key without a value");
+    assert_eq!(error(vec![Event::Alias("x".to_owned())]), "unknown anchor `x`");
+    assert_eq!(error(vec![Event::SequenceStart(Some("x".to_owned())),
+                          Event::Alias("x".to_owned()),
+                          Event::SequenceEnd]),
+               "alias `*x` is inside the node it refers to");
+    assert_eq!(error(vec![Event::plain("a"), Event::plain("b")]),
+               "deserializing from YAML containing more than one document is not supported");
+}
+
+#[test]
+fn test_to_events() {
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Build {
+        name: String,
+        version: String,
+        args: Vec<String>,
+        retries: Option<u8>,
+        ratio: f64,
+    }
+
+    let build = Build {
+        name: "app".to_owned(),
+        version: "1.10".to_owned(),
+        args: vec!["--flag".to_owned(), "".to_owned(), "a: b".to_owned()],
+        retries: None,
+        ratio: 0.5,
+    };
+    let events = serde_yaml::to_events(&build).unwrap();
+    assert_eq!(events[0], Event::MappingStart(None));
+    assert_eq!(events[2], Event::plain("app"));
+    assert_eq!(events[4], scalar("1.10", ScalarStyle::DoubleQuoted, None, None));
+    assert_eq!(events[7], scalar("--flag", ScalarStyle::DoubleQuoted, None, None));
+    assert_eq!(events[12], Event::plain("~"));
+    assert_eq!(events[14], Event::plain("0.5"));
+    assert_eq!(events.len(), 16);
+    assert_eq!(Build::deserialize(Deserializer::from_events(events)).unwrap(), build);
+}
+
+#[test]
+fn test_bytes_key_events() {
+    struct Bytes(&'static [u8]);
+
+    impl serde::Serialize for Bytes {
+        fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+            where S: serde::Serializer
+        {
+            serializer.serialize_bytes(self.0)
+        }
+    }
+
+    struct Map;
+
+    impl serde::Serialize for Map {
+        fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+            where S: serde::Serializer
+        {
+            serializer.collect_map(vec![(Bytes(b"hi"), 1)])
+        }
+    }
+
+    let events = serde_yaml::to_events(&Map).unwrap();
+    assert_eq!(events[1], scalar("aGk=", ScalarStyle::Plain, None, Some("!!binary")));
+    assert_eq!(serde_yaml::to_string(&Map).unwrap(), "---\n!!binary aGk=: 1");
+}
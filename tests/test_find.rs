@@ -0,0 +1,38 @@
+// Copyright 2018 Serde YAML Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+#![cfg(all(feature = "de", feature = "value"))]
+
+extern crate serde_yaml;
+
+use serde_yaml::Value;
+
+mod common;
+use common::yaml;
+
+fn paths(found: Vec<(String, &Value)>) -> Vec<String> {
+    found.into_iter().map(|(path, _)| path).collect()
+}
+
+#[test]
+fn test_nested_matches() {
+    let value = yaml("[{a: {a: 1, b: [{a: 2}]}}, {c: {a: 3}}]");
+    assert_eq!(paths(value.find_key("a")), ["[0].a", "[0].a.a", "[0].a.b[0].a", "[1].c.a"]);
+    assert_eq!(value.find_key("a")[3].1, &Value::from(3));
+    assert!(value.find_key("d").is_empty());
+    assert!(yaml("a").find_key("a").is_empty());
+}
+
+#[test]
+fn test_predicate() {
+    let value = yaml("{1: x, true: y, k: {2: z}, [l]: w}");
+    assert_eq!(paths(value.find_all(|key, _| key.is_number())), ["1", "k.2"]);
+    assert_eq!(paths(value.find_all(|_, value| value == "w")), ["- l"]);
+    // Keys are matched as strings only.
+    assert!(value.find_key("1").is_empty());
+}
@@ -0,0 +1,59 @@
+// Copyright 2018 Serde YAML Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+#![cfg(all(feature = "de", feature = "value"))]
+
+extern crate serde_yaml;
+
+use serde_yaml::Value;
+
+mod common;
+use common::yaml;
+
+#[test]
+fn test_round_trip() {
+    let value = yaml("{a: {b: [1, {c: x}, []], d: {}}, e: ~, f: [[1, 2], [3]]}");
+    let pairs = value.flatten_paths();
+    let paths: Vec<&str> = pairs.iter().map(|pair| &*pair.0).collect();
+    assert_eq!(paths, ["a.b[0]", "a.b[1].c", "a.b[2]", "a.d", "e", "f[0][0]", "f[0][1]", "f[1][0]"]);
+    assert_eq!(Value::from_flat_pairs(pairs).unwrap(), value);
+}
+
+#[test]
+fn test_root() {
+    assert_eq!(yaml("x").flatten_paths(), vec![(".".to_owned(), yaml("x"))]);
+    assert_eq!(Value::from_flat_pairs(vec![(".", yaml("x"))]).unwrap(), yaml("x"));
+    assert_eq!(Value::from_flat_pairs(vec![("[1]", yaml("b")), ("[0]", yaml("a"))]).unwrap(),
+               yaml("[a, b]"));
+    assert_eq!(Value::from_flat_pairs(Vec::<(String, Value)>::new()).unwrap(), Value::Null);
+}
+
+#[test]
+fn test_non_string_keys() {
+    let value = yaml("{1: a, true: b}");
+    let pairs = value.flatten_paths();
+    assert_eq!(pairs[0].0, "1");
+    assert_eq!(pairs[1].0, "true");
+    assert_eq!(Value::from_flat_pairs(pairs).unwrap(), yaml("{'1': a, 'true': b}"));
+}
+
+#[test]
+fn test_gaps_and_overwrites() {
+    let value = Value::from_flat_pairs(vec![("a[2]", yaml("z")), ("b", yaml("1")), ("b", yaml("2"))]).unwrap();
+    assert_eq!(value, yaml("{a: [~, ~, z], b: 2}"));
+}
+
+#[test]
+fn test_conflicts() {
+    let err = Value::from_flat_pairs(vec![("a[0]", yaml("1")), ("a.b", yaml("2"))]).unwrap_err();
+    assert_eq!(err.to_string(),
+               "flat path `a.b` conflicts with an earlier path or has too large an index");
+    assert!(Value::from_flat_pairs(vec![("a", yaml("1")), ("a.b", yaml("2"))]).is_err());
+    assert!(Value::from_flat_pairs(vec![("a.b", yaml("1")), ("a", yaml("2"))]).is_err());
+    assert!(Value::from_flat_pairs(vec![("a[4000000000]", yaml("1"))]).is_err());
+}
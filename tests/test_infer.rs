@@ -0,0 +1,139 @@
+// Copyright 2018 Serde YAML Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+#![cfg(all(feature = "de", feature = "value"))]
+
+extern crate serde_yaml;
+
+use serde_yaml::infer::{self, InferredSchema};
+use serde_yaml::Value;
+
+fn samples(docs: &[&str]) -> Vec<Value> {
+    docs.iter().map(|doc| serde_yaml::from_str(doc).unwrap()).collect()
+}
+
+#[test]
+fn test_scalars() {
+    assert_eq!(infer::schema(&[]), InferredSchema::Unknown);
+    assert_eq!(infer::schema(&samples(&["1", "2"])), InferredSchema::Integer);
+    assert_eq!(infer::schema(&samples(&["1", "2.5"])), InferredSchema::Float);
+    assert_eq!(infer::schema(&samples(&["~", "~"])), InferredSchema::Null);
+    assert_eq!(infer::schema(&samples(&["x", "~"])),
+               InferredSchema::Option(Box::new(InferredSchema::String)));
+    assert_eq!(infer::schema(&samples(&["x", "true"])), InferredSchema::Any);
+}
+
+#[test]
+fn test_sequences() {
+    assert_eq!(infer::schema(&samples(&["[]"])),
+               InferredSchema::Sequence(Box::new(InferredSchema::Unknown)));
+    assert_eq!(infer::schema(&samples(&["[]", "[1, ~]"])),
+               InferredSchema::Sequence(Box::new(InferredSchema::Option(Box::new(InferredSchema::Integer)))));
+}
+
+#[test]
+fn test_fields() {
+    let schema = infer::schema(&samples(&["{a: 1, b: x}", "{b: ~, c: [true]}"]));
+    let fields: Vec<(&str, bool)> = schema.fields().iter().map(|f| (f.name(), f.required())).collect();
+    assert_eq!(fields, [("a", false), ("b", true), ("c", false)]);
+    assert_eq!(schema.fields()[1].schema(),
+               &InferredSchema::Option(Box::new(InferredSchema::String)));
+}
+
+#[test]
+fn test_map_keys() {
+    let schema = infer::schema(&samples(&["{1: a, 2: b}"]));
+    assert_eq!(schema,
+               InferredSchema::Map(Box::new(InferredSchema::Integer), Box::new(InferredSchema::String)));
+}
+
+#[test]
+fn test_rust_source() {
+    let schema = infer::schema(&samples(&["{logLevel: debug, type: a, 2fa: true, server: {port: 1}, \
+                                           backup: {server: {host: x}}, tags: {1: a}, extra: ~}",
+                                          "{logLevel: info, type: b, 2fa: false, server: {port: 2}, \
+                                           backup: {server: {host: y}}, tags: {2: b}, extra: [1]}"]));
+    let expected = "\
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Config {
+    #[serde(rename = \"logLevel\")]
+    pub log_level: String,
+    #[serde(rename = \"type\")]
+    pub type_: String,
+    #[serde(rename = \"2fa\")]
+    pub field_2fa: bool,
+    pub server: Server,
+    pub backup: Backup,
+    pub tags: std::collections::BTreeMap<i64, String>,
+    pub extra: Option<Vec<i64>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Server {
+    pub port: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Backup {
+    pub server: Server2,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Server2 {
+    pub host: String,
+}
+";
+    assert_eq!(schema.to_rust_source("config"), expected);
+}
+
+#[test]
+fn test_rust_source_not_a_struct() {
+    let schema = infer::schema(&samples(&["[{name: a}]"]));
+    let expected = "\
+pub type Items = Vec<Item>;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Item {
+    pub name: String,
+}
+";
+    assert_eq!(schema.to_rust_source("Items"), expected);
+}
+
+#[test]
+fn test_json_schema() {
+    let schema = infer::schema(&samples(&["{id: 1, tags: [a], meta: {x: 1}, codes: {1: 2.5}, any: []}",
+                                          "{id: 2, meta: ~, codes: {}, any: [x, 1], score: 1.5}"]));
+    let expected: Value = serde_yaml::from_str(r#"
+        $schema: http://json-schema.org/draft-07/schema#
+        type: object
+        properties:
+          id: {type: integer}
+          tags: {type: array, items: {type: string}}
+          meta:
+            type: [object, "null"]
+            properties: {x: {type: integer}}
+            required: [x]
+          codes: {type: object, additionalProperties: {type: number}}
+          any: {type: array, items: {}}
+          score: {type: number}
+        required: [id, meta, codes, any]
+    "#)
+        .unwrap();
+    assert_eq!(schema.to_json_schema(), expected);
+}
+
+#[test]
+fn test_json_schema_unknown() {
+    let expected: Value = serde_yaml::from_str("$schema: http://json-schema.org/draft-07/schema#").unwrap();
+    assert_eq!(infer::schema(&[]).to_json_schema(), expected);
+    let expected: Value = serde_yaml::from_str("{$schema: 'http://json-schema.org/draft-07/schema#', \
+                                                type: array}")
+        .unwrap();
+    assert_eq!(infer::schema(&samples(&["[]"])).to_json_schema(), expected);
+}
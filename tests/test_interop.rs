@@ -0,0 +1,91 @@
+// Copyright 2018 Serde YAML Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+#![cfg(all(feature = "serde_json", feature = "toml", feature = "de", feature = "value"))]
+
+#[macro_use]
+extern crate serde_json;
+extern crate serde_yaml;
+extern crate toml;
+
+use std::convert::TryFrom;
+
+use serde_yaml::interop::{self, DatetimePolicy, FloatPolicy, KeyPolicy, NullPolicy, Policy};
+use serde_yaml::Value;
+
+mod common;
+use common::yaml;
+
+#[test]
+fn test_json_round_trip() {
+    let json = json!({"name": "demo", "ports": [80, 443], "ratio": 0.5, "big": u64::MAX, "none": null});
+    let value = Value::from(json.clone());
+    assert_eq!(value["ports"][1], 443);
+    assert_eq!(serde_json::Value::try_from(value).unwrap(), json);
+}
+
+#[test]
+fn test_json_keys() {
+    let value = yaml("{1: one, true: yes, [a]: seq}");
+    assert!(serde_json::Value::try_from(value.clone()).is_err());
+
+    let stringify = Policy::new().non_string_keys(KeyPolicy::Stringify);
+    assert!(interop::to_json(value.clone(), &stringify).is_err());
+
+    let skip = Policy::new().non_string_keys(KeyPolicy::Skip);
+    assert_eq!(interop::to_json(value, &skip).unwrap(), json!({}));
+
+    let value = yaml("{1: one, true: 'yes', ~: nothing}");
+    assert_eq!(interop::to_json(value, &stringify).unwrap(),
+               json!({"1": "one", "true": "yes", "null": "nothing"}));
+}
+
+#[test]
+fn test_json_non_finite() {
+    let value = Value::Sequence(vec![f64::NAN.into(), f64::NEG_INFINITY.into(), 1.5.into()]);
+    assert!(serde_json::Value::try_from(value.clone()).is_err());
+
+    let null = Policy::new().non_finite_floats(FloatPolicy::Null);
+    assert_eq!(interop::to_json(value.clone(), &null).unwrap(), json!([null, null, 1.5]));
+
+    let string = Policy::new().non_finite_floats(FloatPolicy::String);
+    assert_eq!(interop::to_json(value, &string).unwrap(), json!([".nan", "-.inf", 1.5]));
+}
+
+#[test]
+fn test_toml_round_trip() {
+    let toml: toml::Value = "title = 'demo'\nwhen = 1979-05-27T07:32:00Z\n[server]\nports = [80, 443]\nratio = 0.5\n"
+        .parse()
+        .unwrap();
+    let value = Value::from(toml.clone());
+    assert_eq!(value["when"], "1979-05-27T07:32:00Z");
+    assert_eq!(value["server"]["ports"][0], 80);
+
+    let strings = toml::Value::try_from(value.clone()).unwrap();
+    assert_eq!(strings["when"], toml::Value::String("1979-05-27T07:32:00Z".to_owned()));
+
+    let detect = Policy::new().datetimes(DatetimePolicy::Detect);
+    assert_eq!(interop::to_toml(value, &detect).unwrap(), toml);
+}
+
+#[test]
+fn test_toml_nulls() {
+    let value = yaml("{a: ~, b: [1, ~, 2], c: 3}");
+    assert!(toml::Value::try_from(value.clone()).is_err());
+
+    let skip = Policy::new().nulls(NullPolicy::Skip);
+    let expected: toml::Value = "b = [1, 2]\nc = 3\n".parse().unwrap();
+    assert_eq!(interop::to_toml(value, &skip).unwrap(), expected);
+
+    assert!(interop::to_toml(Value::Null, &skip).is_err());
+}
+
+#[test]
+fn test_toml_large_integer() {
+    assert!(toml::Value::try_from(Value::from(u64::MAX)).is_err());
+}
@@ -0,0 +1,97 @@
+// Copyright 2018 Serde YAML Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+#![cfg(all(feature = "de", feature = "value"))]
+
+extern crate serde_yaml;
+
+use serde_yaml::lint::{self, Rule};
+
+fn findings(yaml: &str) -> Vec<(Rule, usize, usize)> {
+    lint::check(yaml)
+        .iter()
+        .map(|finding| (finding.rule(), finding.location().line(), finding.location().column()))
+        .collect()
+}
+
+#[test]
+fn test_clean() {
+    let yaml = "name: web\nports: [80, 443]\nenabled: true\nversion: '1.10'\n";
+    assert_eq!(findings(yaml), []);
+}
+
+#[test]
+fn test_duplicate_keys() {
+    let yaml = "a: 1\nb:\n  c: 1\n  c: 2\na: 3\n? [1, 2]\n: x\n? [1, 2]\n: z\n";
+    assert_eq!(findings(yaml),
+               [(Rule::DuplicateKey, 4, 3), (Rule::DuplicateKey, 5, 1), (Rule::DuplicateKey, 8, 3)]);
+    let messages: Vec<String> =
+        lint::check(yaml).iter().map(|finding| finding.message().to_owned()).collect();
+    assert_eq!(messages[0], "duplicate key `c`, first defined on line 3");
+    assert_eq!(messages[2], "duplicate key `- 1\n- 2`, first defined on line 6");
+}
+
+#[test]
+fn test_same_key_in_different_mappings() {
+    let yaml = "- name: a\n- name: b\n---\nname: c\n";
+    assert_eq!(findings(yaml), []);
+}
+
+#[test]
+fn test_unresolved_alias() {
+    let yaml = "a: &x 1\nb: *x\nc: *y\n---\nd: *x\n";
+    assert_eq!(findings(yaml), [(Rule::UnresolvedAlias, 3, 4), (Rule::UnresolvedAlias, 5, 4)]);
+}
+
+#[test]
+fn test_unresolved_alias_keeps_other_checks() {
+    let yaml = "a: 1\na: 2\nb: *x\n";
+    assert_eq!(findings(yaml), [(Rule::DuplicateKey, 2, 1), (Rule::UnresolvedAlias, 3, 4)]);
+}
+
+#[test]
+fn test_whitespace() {
+    let yaml = "a:\n\t- 1\nb: 2  \r\nc: |\n  text\t\n";
+    let found = findings(yaml);
+    assert!(found.contains(&(Rule::TabIndentation, 2, 1)));
+    assert!(found.contains(&(Rule::TrailingSpaces, 3, 5)));
+    assert!(found.contains(&(Rule::TrailingSpaces, 5, 7)));
+}
+
+#[test]
+fn test_whitespace_index() {
+    let finding = &lint::check("a: 1\r\nb: 2 \n")[0];
+    assert_eq!(finding.rule(), Rule::TrailingSpaces);
+    assert_eq!(finding.location().index(), 10);
+}
+
+#[test]
+fn test_ambiguous_scalars() {
+    let yaml = "a: yes\nb: Off\nc: 0755\nd: 1:30\ne: 1.10\nf: NULL\ng: 'yes'\nh: !!str no\ni: 1.5\nj: 0\n";
+    assert_eq!(findings(yaml),
+               [(Rule::AmbiguousScalar, 1, 4),
+                (Rule::AmbiguousScalar, 2, 4),
+                (Rule::AmbiguousScalar, 3, 4),
+                (Rule::AmbiguousScalar, 4, 4),
+                (Rule::AmbiguousScalar, 5, 4),
+                (Rule::AmbiguousScalar, 6, 4)]);
+}
+
+#[test]
+fn test_syntax_error() {
+    let found = lint::check("a: 1\n b: [\n");
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].rule(), Rule::Syntax);
+}
+
+#[test]
+fn test_display() {
+    let finding = &lint::check("x: on\n")[0];
+    assert_eq!(finding.to_string(),
+               "1:4: `on` is a string here but a boolean in YAML 1.1; quote it if a string is meant");
+}
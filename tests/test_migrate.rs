@@ -0,0 +1,71 @@
+// Copyright 2018 Serde YAML Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+#![cfg(all(feature = "de", feature = "value"))]
+
+extern crate serde_yaml;
+
+use serde_yaml::Migration;
+
+mod common;
+use common::yaml;
+
+#[test]
+fn test_rename_keeps_order() {
+    let mut value = yaml("{a: 1, b: 2, c: 3}");
+    assert!(value.rename_key(".", "b", "x"));
+    assert_eq!(serde_yaml::to_string(&value).unwrap(), "---\na: 1\nx: 2\nc: 3");
+
+    assert!(value.rename_key(".", "a", "c"));
+    assert_eq!(serde_yaml::to_string(&value).unwrap(), "---\nc: 1\nx: 2");
+}
+
+#[test]
+fn test_rename_in_sequence() {
+    let mut value = yaml("containers: [{name: a, img: x}, {name: b, img: y}]");
+    assert!(value.rename_key("containers[1]", "img", "image"));
+    assert_eq!(value, yaml("containers: [{name: a, img: x}, {name: b, image: y}]"));
+    assert!(!value.rename_key("containers[2]", "img", "image"));
+    assert!(!value.rename_key("containers", "img", "image"));
+}
+
+#[test]
+fn test_move_into_null_parent() {
+    let mut value = yaml("{old: {port: 80}, http: ~}");
+    let migration = Migration::new().move_key("old.port", "http.port");
+    assert_eq!(migration.apply(&mut value).unwrap(), 1);
+    assert_eq!(value, yaml("{old: {}, http: {port: 80}}"));
+}
+
+#[test]
+fn test_move_replaces_existing() {
+    let mut value = yaml("{a: 1, b: 2}");
+    assert_eq!(Migration::new().move_key("a", "b").apply(&mut value).unwrap(), 1);
+    assert_eq!(value, yaml("{b: 1}"));
+}
+
+#[test]
+fn test_errors_leave_source() {
+    let mut value = yaml("{a: 1, b: text}");
+    let err = Migration::new().move_key("a", "b.c").apply(&mut value).unwrap_err();
+    assert_eq!(err.to_string(), "migration path `b.c` runs into a value that is not a mapping");
+    assert_eq!(value, yaml("{a: 1, b: text}"));
+
+    let err = Migration::new().set_default("list[0]", 1).apply(&mut value).unwrap_err();
+    assert_eq!(err.to_string(), "migration path `list[0]` does not end with a key");
+}
+
+#[test]
+fn test_default_does_not_overwrite() {
+    let mut value = yaml("{log: {level: debug}}");
+    let migration = Migration::new()
+        .set_default("log.level", "info")
+        .set_default("log.format", "json");
+    assert_eq!(migration.apply(&mut value).unwrap(), 1);
+    assert_eq!(value, yaml("{log: {level: debug, format: json}}"));
+}
@@ -0,0 +1,59 @@
+// Copyright 2018 Serde YAML Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+#![cfg(all(feature = "de", feature = "value"))]
+
+extern crate serde_yaml;
+
+use serde_yaml::{Number, Value};
+
+mod common;
+use common::yaml;
+
+#[test]
+fn test_sort_keys_mixed_types() {
+    let mut value = yaml("{b: 1, 2.5: x, true: t, ~: n, -3: y, a: [{z: 1, y: 2}], nan: q}");
+    value.sort_keys_recursively();
+    let keys: Vec<String> = value.as_mapping()
+        .unwrap()
+        .iter()
+        .map(|(k, _)| serde_yaml::to_string(k).unwrap())
+        .collect();
+    assert_eq!(keys, ["---\n~", "---\ntrue", "---\n-3", "---\n2.5", "---\n.nan", "---\na", "---\nb"]);
+    assert_eq!(serde_yaml::to_string(&value["a"]).unwrap(), "---\n- \"y\": 2\n  z: 1");
+}
+
+#[test]
+fn test_normalize_numbers_range() {
+    let mut value = yaml("[-0.0, 9223372036854775808.0, 18446744073709551616.0, -9223372036854775808.0, \
+                          -9223372036854777856.0, 0.5]");
+    value.normalize_numbers();
+    let expected = vec![Value::Number(Number::from(0)),
+                        Value::Number(Number::from(9223372036854775808u64)),
+                        Value::Number(Number::from(18446744073709551616.0)),
+                        Value::Number(Number::from(i64::MIN)),
+                        Value::Number(Number::from(-9223372036854777856.0)),
+                        Value::Number(Number::from(0.5))];
+    assert_eq!(value, Value::Sequence(expected));
+    assert!(value[1].is_u64());
+    assert!(value[2].is_f64());
+}
+
+#[test]
+fn test_normalize_number_keys() {
+    let mut value = yaml("{1: a, 1.0: b, 2.0: {3.0: c}}");
+    value.normalize_numbers();
+    assert_eq!(value, yaml("{1: b, 2: {3: c}}"));
+}
+
+#[test]
+fn test_strip_nulls_nested() {
+    let mut value = yaml("{a: [{b: ~, c: 1}, ~], d: {}}");
+    value.strip_nulls();
+    assert_eq!(value, yaml("{a: [{c: 1}, ~], d: {}}"));
+}
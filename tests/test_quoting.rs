@@ -0,0 +1,115 @@
+// Copyright 2018 Serde YAML Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+#![cfg(all(feature = "de", feature = "value"))]
+
+extern crate serde;
+extern crate serde_yaml;
+
+use serde_yaml::quoting::{self, QuoteStyle, Schema};
+
+const STRINGS: &[&str] = &[
+    "plain",
+    "two words",
+    "",
+    " padded ",
+    "yes",
+    "null",
+    "1.5",
+    "0x1f",
+    "- item",
+    "key: value",
+    "it's",
+    "say \"hi\"",
+    "back\\slash",
+    "line\nbreak",
+    "tab\there",
+    "bell\x07",
+    "é and 日本",
+];
+
+#[test]
+fn test_quote_round_trip() {
+    for &string in STRINGS {
+        for &style in &[QuoteStyle::Plain, QuoteStyle::Single, QuoteStyle::Double] {
+            let quoted = quoting::quote(string, style);
+            let yaml = format!("key: {}\n", quoted);
+            let value: std::collections::BTreeMap<String, String> = serde_yaml::from_str(&yaml).unwrap();
+            assert_eq!(value["key"], string, "{:?} in {:?}", quoted, style);
+        }
+    }
+}
+
+#[test]
+fn test_quote_matches_serializer() {
+    for &string in STRINGS {
+        let yaml = serde_yaml::to_string(string).unwrap();
+        assert_eq!(yaml, format!("---\n{}", quoting::quote(string, QuoteStyle::Plain)));
+    }
+}
+
+#[test]
+fn test_quote_styles() {
+    assert_eq!(quoting::quote("plain", QuoteStyle::Plain), "plain");
+    assert_eq!(quoting::quote("plain", QuoteStyle::Single), "'plain'");
+    assert_eq!(quoting::quote("plain", QuoteStyle::Double), "\"plain\"");
+    assert_eq!(quoting::quote("a\nb", QuoteStyle::Single), "\"a\\nb\"");
+    assert_eq!(quoting::escape_double_quoted("\"\\\r\x00"), "\\\"\\\\\\r\\u0000");
+}
+
+#[test]
+fn test_needs_quoting() {
+    let yaml11_only = ["y", "N", "yes", "No", "off", "ON", "inf", "NaN", ".5e", "0o8", "+.nan"];
+    for &string in &yaml11_only {
+        assert!(quoting::needs_quoting(string, Schema::Yaml11), "{}", string);
+        assert!(!quoting::needs_quoting(string, Schema::Core), "{}", string);
+    }
+    let both = ["true", "FALSE", "~", "Null", "1", "+1", "0o17", "0x1F", "1.", ".5", "1e3", "1.5E-3", ".inf", "+.Inf", ".NaN", "", "a: b"];
+    for &string in &both {
+        assert!(quoting::needs_quoting(string, Schema::Yaml11), "{}", string);
+        assert!(quoting::needs_quoting(string, Schema::Core), "{}", string);
+    }
+    let neither = ["plain", "1.2.3", "1e", "e3"];
+    for &string in &neither {
+        assert!(!quoting::needs_quoting(string, Schema::Yaml11), "{}", string);
+        assert!(!quoting::needs_quoting(string, Schema::Core), "{}", string);
+    }
+}
+
+#[test]
+fn test_lookalikes_are_quoted() {
+    use serde::Deserialize;
+    use serde_yaml::{Dates, Deserializer, Times};
+
+    let lookalikes = [
+        // Versions.
+        "1.10", "1.0", "10", "2", "1e3", ".5",
+        // Dates and timestamps.
+        "2024-01-01", "2001-12-14t21:59:43.10-05:00", "2001-12-14 21:59:43.10 -5",
+        // Times and YAML 1.1 base 60 numbers.
+        "08:30", "12:30:00", "190:20:30.15",
+        // Other YAML 1.1 numbers.
+        "1_000", "-1_000.5", "0b101", "0x1F", "0o17", "+12", "-0", "012", "+.inf", ".NaN", "inf",
+        // Booleans and null.
+        "y", "N", "yes", "Off", "TRUE", "~", "null",
+    ];
+    for &string in &lookalikes {
+        let yaml = serde_yaml::to_string(string).unwrap();
+        assert!(yaml.starts_with("---\n'") || yaml.starts_with("---\n\""), "{:?} written as {:?}", string, yaml);
+        assert!(quoting::needs_quoting(string, Schema::Yaml11), "{:?}", string);
+
+        let de = Deserializer::from_str(&yaml).dates(Dates::Timestamp).times(Times::Sexagesimal);
+        assert_eq!(serde_yaml::Value::deserialize(de).unwrap(), string);
+        let de = Deserializer::from_str(&yaml).dates(Dates::Error).times(Times::Error);
+        assert_eq!(serde_yaml::Value::deserialize(de).unwrap(), string);
+    }
+
+    for &string in &["1.2.3", "v1.10", "2024-01", "_1", "0b", "0b102", "1_0a", "a_1"] {
+        assert_eq!(serde_yaml::to_string(string).unwrap(), format!("---\n{}", string));
+    }
+}
@@ -0,0 +1,63 @@
+// Copyright 2018 Serde YAML Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+#![cfg(all(feature = "de", feature = "value"))]
+
+#[macro_use]
+extern crate serde_derive;
+extern crate serde_yaml;
+
+use std::any::Any;
+
+use serde_yaml::Value;
+use serde_yaml::route::Router;
+
+#[derive(Deserialize, Debug, PartialEq)]
+struct ConfigMap {
+    data: Vec<String>,
+}
+
+#[derive(Deserialize, Debug, PartialEq)]
+#[serde(deny_unknown_fields)]
+struct Namespace {
+    kind: String,
+    name: String,
+}
+
+fn router() -> Router {
+    Router::new("kind").register::<ConfigMap>("ConfigMap").register::<Namespace>("Namespace")
+}
+
+#[test]
+fn test_route_any() {
+    let yaml = "---\nkind: Namespace\nname: web\n---\n---\nkind: ConfigMap\ndata: [a]\n";
+    let documents = router().from_str(yaml).unwrap();
+    assert_eq!(documents.len(), 2);
+    let namespace = documents[0].downcast_ref::<Namespace>().unwrap();
+    assert_eq!(namespace.name, "web");
+    assert_eq!(documents[1].downcast_ref::<ConfigMap>().unwrap().data, ["a"]);
+    assert!(router().from_str("").unwrap().is_empty());
+}
+
+#[test]
+fn test_route_replaces() {
+    let router = Router::new("kind").route("A", |_: Value| 1).route("B", |_: Value| 2).route("A", |_: Value| 3);
+    assert_eq!(router.from_str("kind: A\n--- {kind: B}\n").unwrap(), [3, 2]);
+}
+
+#[test]
+fn test_route_errors() {
+    fn err(yaml: &str) -> String {
+        router().from_str(yaml).map(|_: Vec<Box<dyn Any>>| ()).unwrap_err().to_string()
+    }
+
+    assert_eq!(err("kind: Namespace\nname: a\n--- [1]\n"), "document 2 has no `kind` string");
+    assert_eq!(err("kind: 1\n"), "document 1 has no `kind` string");
+    assert_eq!(err("---\n--- {kind: Secret}\n"), "document 2 has unknown kind `Secret`");
+    assert!(err("kind: ConfigMap\ndata: 1\n").starts_with("data: invalid type"), "{}", err("kind: ConfigMap\ndata: 1\n"));
+}
@@ -0,0 +1,94 @@
+// Copyright 2018 Serde YAML Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+#![cfg(feature = "schemars")]
+
+#[macro_use]
+extern crate serde_derive;
+#[macro_use]
+extern crate schemars;
+extern crate serde_yaml;
+
+use std::collections::BTreeMap;
+
+use schemars::JsonSchema;
+use serde_yaml::Value;
+use serde_yaml::schema;
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+#[allow(dead_code)]
+struct Service {
+    name: String,
+    port: Option<u16>,
+    #[schemars(length(min = 1, max = 3))]
+    hosts: Vec<String>,
+    mode: Mode,
+    limits: Option<Limits>,
+    labels: BTreeMap<String, String>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+#[allow(dead_code)]
+enum Mode {
+    Fast,
+    Safe,
+    Custom { level: u8 },
+}
+
+#[derive(Deserialize, JsonSchema)]
+#[allow(dead_code)]
+struct Limits {
+    #[schemars(range(min = 0.5))]
+    cpu: f64,
+}
+
+fn violations<T: JsonSchema>(yaml: &str) -> Vec<String> {
+    let value: Value = serde_yaml::from_str(yaml).unwrap();
+    schema::validate::<T>(&value).iter().map(ToString::to_string).collect()
+}
+
+#[test]
+fn test_valid() {
+    let yaml = "name: web\nport: 80\nhosts: [a]\nmode: fast\nlimits: {cpu: 1}\nlabels: {app: web}\n";
+    assert_eq!(violations::<Service>(yaml), Vec::<String>::new());
+    let yaml = "name: web\nport: ~\nhosts: [a, b]\nmode: {custom: {level: 3}}\nlabels: {}\n";
+    assert_eq!(violations::<Service>(yaml), Vec::<String>::new());
+}
+
+#[test]
+fn test_violations() {
+    let yaml = "port: 70000\nhosts: []\nmode: quick\nlimits: {cpu: 0.1}\nlabels: {app: 1}\nextra: x\n";
+    assert_eq!(violations::<Service>(yaml), [
+        "`.` is missing `name`",
+        "`port` must be at most 65535, found 70000",
+        "`hosts` must have at least 1 elements, found 0",
+        "`mode` does not match any schema in `oneOf`",
+        "`limits.cpu` must be at least 0.5, found 0.1",
+        "`labels.app` must be of type string, found integer",
+        "`extra` is not an allowed key",
+    ]);
+}
+
+#[test]
+fn test_enum_variant() {
+    let yaml = "name: a\nhosts: [a]\nmode: {custom: {level: 300}}\nlabels: {}\n";
+    assert_eq!(violations::<Service>(yaml), ["`mode.custom.level` must be at most 255, found 300"]);
+}
+
+#[test]
+fn test_schema_keywords() {
+    let schema = schema_for!(Vec<u8>);
+    let violations = schema::validate_schema(&schema, &serde_yaml::from_str("[1, -1, 1.5, x]").unwrap());
+    assert_eq!(violations[0].path(), "[1]");
+    assert_eq!(violations[0].message(), "must be at least 0, found -1");
+    assert_eq!(violations[1].to_string(), "`[2]` must be of type integer, found number");
+    assert_eq!(violations[2].to_string(), "`[3]` must be of type integer, found string");
+    assert_eq!(violations.len(), 3);
+}
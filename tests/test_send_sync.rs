@@ -0,0 +1,68 @@
+// Copyright 2018 Serde YAML Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+#![cfg(all(feature = "de", feature = "value"))]
+
+extern crate serde_yaml;
+
+use std::sync::Arc;
+use std::thread;
+
+fn assert_send_sync<T: Send + Sync>() {}
+
+#[test]
+fn test_send_sync() {
+    assert_send_sync::<serde_yaml::Value>();
+    assert_send_sync::<serde_yaml::Number>();
+    assert_send_sync::<serde_yaml::Mapping>();
+    assert_send_sync::<serde_yaml::Error>();
+    assert_send_sync::<serde_yaml::Location>();
+    assert_send_sync::<serde_yaml::ErrorKind>();
+    assert_send_sync::<serde_yaml::YamlFormat>();
+    assert_send_sync::<serde_yaml::Writer<Vec<u8>>>();
+    assert_send_sync::<serde_yaml::StringStyles>();
+    assert_send_sync::<serde_yaml::NonFiniteSpelling>();
+    assert_send_sync::<serde_yaml::Profile>();
+    #[cfg(feature = "tags")]
+    assert_send_sync::<serde_yaml::TagPolicy>();
+    assert_send_sync::<serde_yaml::AnchorPolicy>();
+    #[cfg(feature = "limits")]
+    assert_send_sync::<serde_yaml::Limits>();
+    assert_send_sync::<serde_yaml::Tabs>();
+    assert_send_sync::<serde_yaml::DuplicateKeys>();
+    assert_send_sync::<serde_yaml::Deprecations>();
+    assert_send_sync::<serde_yaml::Migration>();
+    assert_send_sync::<serde_yaml::Parser>();
+    assert_send_sync::<serde_yaml::SpannedValue>();
+    assert_send_sync::<serde_yaml::value::Serializer>();
+    assert_send_sync::<serde_yaml::interop::Policy>();
+    assert_send_sync::<serde_yaml::constraints::Rules>();
+    assert_send_sync::<serde_yaml::slab::Slab>();
+    assert_send_sync::<serde_yaml::borrowed::Value<'static>>();
+    assert_send_sync::<serde_yaml::route::Router<u32>>();
+    assert_send_sync::<serde_yaml::route::Router>();
+}
+
+#[test]
+fn test_share_between_threads() {
+    let config: Arc<serde_yaml::Value> = Arc::new(serde_yaml::from_str("workers: 4").unwrap());
+    let format = serde_yaml::YamlFormat::stable();
+    let handles: Vec<_> = (0..2).map(|_| {
+        let config = Arc::clone(&config);
+        let format = format.clone();
+        thread::spawn(move || {
+            let err = serde_yaml::from_str::<u32>("[").unwrap_err();
+            (format.to_string(&*config).unwrap(), err)
+        })
+    }).collect();
+    for handle in handles {
+        let (yaml, err) = handle.join().unwrap();
+        assert_eq!(yaml, "---\nworkers: 4\n");
+        assert!(err.location().is_some());
+    }
+}
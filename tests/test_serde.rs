@@ -6,7 +6,9 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
-#![cfg_attr(feature = "cargo-clippy", allow(unreadable_literal))]
+#![cfg(all(feature = "de", feature = "value"))]
+
+#![allow(clippy::unreadable_literal, clippy::legacy_numeric_constants)]
 
 #[macro_use]
 extern crate serde_derive;
@@ -66,6 +68,20 @@ fn test_int_max_i64() {
     test_serde(&thing, &yaml);
 }
 
+#[test]
+fn test_int_128() {
+    test_serde(&::std::i128::MIN, "---\n-170141183460469231731687303715884105728");
+    test_serde(&::std::u128::MAX, "---\n340282366920938463463374607431768211455");
+    test_serde(&-5i128, "---\n-5");
+    test_serde(&vec![1u128 << 64, 7], "---\n- 18446744073709551616\n- 7");
+
+    let aliased: Vec<u128> = serde_yaml::from_str("[&n 18446744073709551616, *n, 0x10]").unwrap();
+    assert_eq!(aliased, [1 << 64, 1 << 64, 16]);
+    let value = serde_yaml::to_value(5u128).unwrap();
+    assert_eq!(serde_yaml::from_value::<i128>(value).unwrap(), 5);
+    assert!(serde_yaml::from_str::<u128>("-1").is_err());
+}
+
 #[test]
 fn test_float() {
     let thing = 25.6;
@@ -75,6 +91,115 @@ fn test_float() {
     test_serde(&thing, &yaml);
 }
 
+#[test]
+fn test_float_shortest() {
+    test_serde(&1.0, "---\n1.0");
+    test_serde(&-0.0, "---\n-0.0");
+    test_serde(&1e300, "---\n1e300");
+    test_serde(&0.1f32, "---\n0.1");
+    test_serde(&::std::f64::MIN_POSITIVE, "---\n2.2250738585072014e-308");
+}
+
+#[test]
+fn test_float_round_trip() {
+    let mut bits = 0x9e37_79b9_7f4a_7c15u64;
+    let mut floats = vec![::std::f64::MAX, ::std::f64::MIN, ::std::f64::EPSILON, 5e-324, -0.0, 0.1 + 0.2];
+    for _ in 0..10000 {
+        // xorshift, covering every exponent including subnormals.
+        bits ^= bits << 13;
+        bits ^= bits >> 7;
+        bits ^= bits << 17;
+        let f = f64::from_bits(bits);
+        if f.is_finite() {
+            floats.push(f);
+        }
+    }
+    for f in floats {
+        let yaml = serde_yaml::to_string(&f).unwrap();
+        let back: f64 = serde_yaml::from_str(&yaml).unwrap();
+        assert_eq!(back.to_bits(), f.to_bits(), "{}", yaml);
+        let value: serde_yaml::Value = serde_yaml::from_str(&yaml).unwrap();
+        assert_eq!(value.as_f64().map(f64::to_bits), Some(f.to_bits()), "{}", yaml);
+    }
+}
+
+#[test]
+fn test_float_non_finite() {
+    use serde_yaml::{NonFiniteSpelling, Writer};
+
+    let floats = vec![::std::f64::INFINITY, ::std::f64::NEG_INFINITY, ::std::f64::NAN];
+    assert_eq!(serde_yaml::to_string(&floats).unwrap(), "---\n- .inf\n- -.inf\n- .nan");
+    let spellings = [(NonFiniteSpelling::Lowercase, "---\n- .inf\n- -.inf\n- .nan\n"),
+                     (NonFiniteSpelling::Capitalized, "---\n- .Inf\n- -.Inf\n- .NaN\n"),
+                     (NonFiniteSpelling::Uppercase, "---\n- .INF\n- -.INF\n- .NAN\n"),
+                     (NonFiniteSpelling::Words, "---\n- Infinity\n- -Infinity\n- NaN\n")];
+    for &(spelling, expected) in &spellings {
+        let mut writer = Writer::new(Vec::new()).non_finite_floats(spelling);
+        writer.write(&floats).unwrap();
+        let yaml = String::from_utf8(writer.into_inner().unwrap()).unwrap();
+        assert_eq!(yaml, expected);
+        let back: Vec<f64> = serde_yaml::from_str(&yaml).unwrap();
+        assert_eq!(back[..2], floats[..2]);
+        assert!(back[2].is_nan());
+    }
+
+    // Strings that look like these are still quoted.
+    let strings = vec![".inf", "Infinity", "NaN"];
+    let mut writer = Writer::new(Vec::new()).non_finite_floats(NonFiniteSpelling::Words);
+    writer.write(&strings).unwrap();
+    let yaml = String::from_utf8(writer.into_inner().unwrap()).unwrap();
+    assert_eq!(yaml, "---\n- \".inf\"\n- \"Infinity\"\n- \"NaN\"\n");
+}
+
+#[test]
+fn test_finite_floats_only() {
+    #[derive(Serialize)]
+    enum Reading {
+        Sample { values: Vec<f32> },
+    }
+
+    let mut sensors = BTreeMap::new();
+    sensors.insert("a", Reading::Sample { values: vec![1.0, 2.0] });
+    sensors.insert("b", Reading::Sample { values: vec![0.5, ::std::f32::NAN] });
+    let mut writer = serde_yaml::Writer::new(Vec::new()).finite_floats_only(true);
+    let err = writer.write(&sensors).unwrap_err();
+    assert_eq!(err.to_string(), "b.Sample.values[1]: infinite or NaN float `.nan` is not allowed");
+
+    let mut writer = serde_yaml::Writer::new(Vec::new()).finite_floats_only(true);
+    writer.write(&sensors["a"]).unwrap();
+    let err = writer.write(&::std::f64::NEG_INFINITY).unwrap_err();
+    assert_eq!(err.to_string(), "infinite or NaN float `-.inf` is not allowed");
+    let yaml = String::from_utf8(writer.into_inner().unwrap()).unwrap();
+    assert_eq!(yaml, "---\nSample:\n  values:\n    - 1.0\n    - 2.0\n");
+}
+
+#[test]
+fn test_complex_keys_off() {
+    use serde_yaml::{ErrorKind, YamlFormat};
+
+    let mut ports = BTreeMap::new();
+    ports.insert(vec![80, 443], "web");
+    let mut hosts = BTreeMap::new();
+    hosts.insert("edge", vec![ports]);
+
+    let mut writer = serde_yaml::Writer::new(Vec::new()).complex_keys(false);
+    let err = writer.write(&hosts).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::KeyNotScalar { path: "edge[0]".to_owned() });
+    assert!(err.to_string().starts_with("edge[0]: mapping key is a sequence or a mapping"), "{}", err);
+    assert!(err.to_string().contains("complex_keys"), "{}", err);
+
+    let format = YamlFormat::new().complex_keys(false);
+    assert!(format.to_string(&hosts["edge"][0]).is_err());
+    assert!(format.to_string(&BTreeMap::<u8, Vec<u8>>::new()).is_ok());
+    let yaml = YamlFormat::new().to_string(&hosts).unwrap();
+    assert_eq!(yaml, "---
+edge:
+  - ? - 80
+      - 443
+    : web");
+    assert_eq!(serde_yaml::from_str::<u8>("x").unwrap_err().kind(), ErrorKind::Other);
+}
+
 #[test]
 fn test_vec() {
     let thing = vec![1, 2, 3];
@@ -186,6 +311,34 @@ fn test_unit_variant() {
     test_serde(&thing, &yaml);
 }
 
+#[test]
+fn test_unit_variant_keys() {
+    use serde_yaml::Value;
+
+    #[derive(Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Debug)]
+    enum Env {
+        Dev,
+        #[serde(rename = "prod")]
+        Production,
+    }
+    let mut thing = BTreeMap::new();
+    thing.insert(Env::Dev, 1);
+    thing.insert(Env::Production, 3);
+    let yaml = unindent(r#"
+        ---
+        Dev: 1
+        prod: 3"#);
+    test_serde(&thing, &yaml);
+
+    let value = serde_yaml::to_value(&thing).unwrap();
+    assert_eq!(value["prod"], 3);
+    let mapping = value.as_mapping().unwrap();
+    assert_eq!(mapping.get_by_variant(&Env::Dev), Some(&Value::from(1)));
+    assert_eq!(mapping.get_by_variant(&Env::Production), Some(&Value::from(3)));
+    assert_eq!(mapping.get_by_variant("Production"), None);
+    assert_eq!(serde_yaml::from_value::<BTreeMap<Env, u32>>(value).unwrap(), thing);
+}
+
 #[test]
 fn test_newtype_struct() {
     #[derive(Serialize, Deserialize, PartialEq, Debug)]
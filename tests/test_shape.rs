@@ -0,0 +1,135 @@
+// Copyright 2018 Serde YAML Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+#![cfg(all(feature = "de", feature = "value"))]
+
+#![allow(dead_code)]
+
+#[macro_use]
+extern crate serde_derive;
+
+extern crate serde_yaml;
+
+extern crate unindent;
+use unindent::unindent;
+
+use std::collections::BTreeMap;
+use std::num::NonZeroU32;
+
+use serde_yaml::shape::{Kind, Shape};
+
+#[derive(Deserialize)]
+struct Config {
+    name: String,
+    #[serde(rename = "listen")]
+    server: Option<Server>,
+    workers: Vec<Worker>,
+    labels: BTreeMap<String, String>,
+    mode: Mode,
+}
+
+#[derive(Deserialize)]
+struct Server {
+    host: String,
+    port: u16,
+}
+
+#[derive(Deserialize)]
+struct Worker {
+    id: NonZeroU32,
+    threads: Option<u32>,
+}
+
+#[derive(Deserialize)]
+enum Mode {
+    Fast,
+    Safe { retries: u8 },
+}
+
+#[derive(Deserialize)]
+struct Tree {
+    label: String,
+    children: Vec<Tree>,
+    parent: Option<Box<Tree>>,
+}
+
+#[test]
+fn test_expected_keys() {
+    let shape = Shape::of::<Config>();
+    assert_eq!(*shape.root(), Kind::Struct("Config"));
+    assert_eq!(shape.expected_keys("."), ["name", "listen", "workers", "labels", "mode"]);
+    assert_eq!(shape.expected_keys("listen"), ["host", "port"]);
+    assert_eq!(shape.expected_keys("workers[3]"), ["id", "threads"]);
+    assert_eq!(shape.expected_keys("mode"), ["Fast", "Safe"]);
+    assert!(shape.expected_keys("labels").is_empty());
+    assert!(shape.expected_keys("missing").is_empty());
+}
+
+#[test]
+fn test_kinds() {
+    let shape = Shape::of::<Config>();
+    assert_eq!(shape.kind_at("listen.port"), Some(&Kind::Integer));
+    assert_eq!(shape.kind_at("labels.anything"), Some(&Kind::String));
+    assert_eq!(shape.kind_at("workers[0].threads"),
+               Some(&Kind::Option(Box::new(Kind::Integer))));
+    assert_eq!(shape.kind_at("workers[0].id"), Some(&Kind::Integer));
+    assert_eq!(shape.kind_at("workers.id"), None);
+}
+
+#[test]
+fn test_recursive() {
+    let shape = Shape::of::<Tree>();
+    assert_eq!(shape.expected_keys("children[0].children[1]"), ["label", "children", "parent"]);
+    assert_eq!(shape.expected_keys("parent.parent"), ["label", "children", "parent"]);
+}
+
+#[derive(Deserialize)]
+struct Node {
+    name: String,
+    child: Option<Box<Node>>,
+    port: u16,
+    tags: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct Endless {
+    next: Box<Endless>,
+}
+
+#[test]
+fn test_fields_after_recursion() {
+    let shape = Shape::of::<Node>();
+    assert_eq!(shape.kind_at("child"), Some(&Kind::Option(Box::new(Kind::Struct("Node")))));
+    assert_eq!(shape.kind_at("child.child.port"), Some(&Kind::Integer));
+    assert_eq!(shape.kind_at("tags"), Some(&Kind::Sequence(Box::new(Kind::String))));
+
+    // A struct with no value is traced up to where it contains itself.
+    let shape = Shape::of::<Endless>();
+    assert_eq!(shape.kind_at("next.next"), Some(&Kind::Struct("Endless")));
+}
+
+#[test]
+fn test_completion_at_cursor() {
+    let yaml = unindent("
+        name: demo
+        workers:
+          - id: 1
+            threads: 4
+        listen:
+          host: localhost
+          port: 80
+        ");
+    let (doc, errors) = serde_yaml::parse_tolerant(&yaml);
+    assert!(errors.is_empty());
+
+    let shape = Shape::of::<Config>();
+    let cursor = |needle: &str| yaml[..yaml.find(needle).unwrap()].chars().count();
+    assert_eq!(doc.path_at(cursor("threads")), "workers[0]");
+    assert_eq!(shape.expected_keys(&doc.path_at(cursor("port"))), ["host", "port"]);
+    assert_eq!(shape.expected_keys(&doc.path_at(cursor("name"))).len(), 5);
+}
@@ -0,0 +1,343 @@
+// Copyright 2018 Serde YAML Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+#![cfg(all(feature = "shared", feature = "value"))]
+
+#[macro_use]
+extern crate serde_derive;
+extern crate serde;
+extern crate serde_yaml;
+
+extern crate unindent;
+use unindent::unindent;
+
+use std::collections::BTreeMap;
+use std::rc::Rc;
+use std::sync::Arc;
+
+use serde::Deserialize;
+use serde_yaml::shared::Shared;
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+struct Service {
+    name: String,
+    env: Shared<Arc<BTreeMap<String, String>>>,
+    ports: Shared<Arc<Vec<u16>>>,
+    owner: Shared<Arc<String>>,
+}
+
+fn env() -> Shared<Arc<BTreeMap<String, String>>> {
+    let mut env = BTreeMap::new();
+    env.insert("LOG".to_owned(), "debug".to_owned());
+    env.insert("REGION".to_owned(), "eu".to_owned());
+    Shared(Arc::new(env))
+}
+
+#[test]
+fn test_round_trip() {
+    let env = env();
+    let ports = Shared(Arc::new(vec![80, 443]));
+    let owner = Shared(Arc::new("ops".to_owned()));
+    let services = vec![Service {
+                            name: "web".to_owned(),
+                            env: env.clone(),
+                            ports: ports.clone(),
+                            owner: owner.clone(),
+                        },
+                        Service {
+                            name: "api".to_owned(),
+                            env: env.clone(),
+                            ports: ports.clone(),
+                            owner: owner.clone(),
+                        }];
+    let yaml = serde_yaml::to_string(&services).unwrap();
+    let expected = unindent("
+        ---
+        - name: web
+          env: &id001
+            LOG: debug
+            REGION: eu
+          ports: &id002
+            - 80
+            - 443
+          owner: &id003 ops
+        - name: api
+          env: *id001
+          ports: *id002
+          owner: *id003");
+    assert_eq!(yaml, expected.trim_end());
+
+    let back: Vec<Service> = serde_yaml::from_str(&yaml).unwrap();
+    assert_eq!(back, services);
+    assert!(Arc::ptr_eq(&back[0].env, &back[1].env));
+    assert!(Arc::ptr_eq(&back[0].ports, &back[1].ports));
+    assert!(Arc::ptr_eq(&back[0].owner, &back[1].owner));
+}
+
+#[test]
+fn test_single_use_is_not_anchored() {
+    let value = vec![env()];
+    let yaml = serde_yaml::to_string(&value).unwrap();
+    assert_eq!(yaml, "---\n- LOG: debug\n  REGION: eu");
+}
+
+#[test]
+fn test_equal_but_distinct() {
+    let value = vec![env(), env()];
+    let yaml = serde_yaml::to_string(&value).unwrap();
+    assert_eq!(yaml, "---\n- LOG: debug\n  REGION: eu\n- LOG: debug\n  REGION: eu");
+    let back: Vec<Shared<Arc<BTreeMap<String, String>>>> = serde_yaml::from_str(&yaml).unwrap();
+    assert!(!Arc::ptr_eq(&back[0], &back[1]));
+}
+
+#[test]
+fn test_rc_sequence_of_sequences() {
+    let inner = Shared(Rc::new(vec![1, 2]));
+    let value = vec![inner.clone(), inner.clone(), Shared(Rc::new(Vec::new())), inner];
+    let yaml = serde_yaml::to_string(&value).unwrap();
+    assert_eq!(yaml, "---\n- &id001\n  - 1\n  - 2\n- *id001\n- []\n- *id001");
+    let back: Vec<Shared<Rc<Vec<i32>>>> = serde_yaml::from_str(&yaml).unwrap();
+    assert!(Rc::ptr_eq(&back[0], &back[3]));
+    assert_eq!(*back[2].0, Vec::<i32>::new());
+}
+
+#[test]
+fn test_keys() {
+    let key = Shared(Rc::new("region".to_owned()));
+    let complex = Shared(Rc::new(vec![1, 2]));
+    let mut first = BTreeMap::new();
+    first.insert(key.clone(), 1);
+    let mut second = BTreeMap::new();
+    second.insert(key.clone(), 2);
+    let mut third = BTreeMap::new();
+    third.insert(complex.clone(), 3);
+    let mut fourth = BTreeMap::new();
+    fourth.insert(complex, 4);
+    let yaml = serde_yaml::to_string(&(first, second, third, fourth)).unwrap();
+    let expected = unindent("
+        ---
+        - &id001 region: 1
+        - *id001 : 2
+        - ? &id002
+            - 1
+            - 2
+          : 3
+        - ? *id002
+          : 4");
+    assert_eq!(yaml, expected.trim_end());
+    let back: Vec<serde_yaml::Value> = serde_yaml::from_str(&yaml).unwrap();
+    assert_eq!(back[1], serde_yaml::from_str::<serde_yaml::Value>("region: 2").unwrap());
+    assert_eq!(back[3], serde_yaml::from_str::<serde_yaml::Value>("[1, 2]: 4").unwrap());
+}
+
+#[test]
+fn test_handwritten_anchors() {
+    let yaml = unindent("
+        defaults: &defaults
+          LOG: info
+        web: *defaults
+        api: *defaults
+        other:
+          LOG: info");
+    let back: BTreeMap<String, Shared<Arc<BTreeMap<String, String>>>> =
+        serde_yaml::from_str(&yaml).unwrap();
+    assert!(Arc::ptr_eq(&back["defaults"], &back["web"]));
+    assert!(Arc::ptr_eq(&back["web"], &back["api"]));
+    assert!(!Arc::ptr_eq(&back["api"], &back["other"]));
+}
+
+#[test]
+fn test_nested_shared() {
+    let leaf = Shared(Arc::new("x".to_owned()));
+    let branch = Shared(Arc::new(vec![leaf.clone(), leaf.clone()]));
+    let yaml = serde_yaml::to_string(&(branch.clone(), branch, leaf)).unwrap();
+    assert_eq!(yaml, "---\n- &id001\n  - &id002 x\n  - *id002\n- *id001\n- *id002");
+    type Leaf = Shared<Arc<String>>;
+    type Branch = Shared<Arc<Vec<Leaf>>>;
+    let back: (Branch, Branch, Leaf) = serde_yaml::from_str(&yaml).unwrap();
+    assert!(Arc::ptr_eq(&back.0, &back.1));
+    assert!(Arc::ptr_eq(&back.0[0], &back.2));
+}
+
+#[test]
+fn test_value_is_transparent() {
+    let shared = Shared(Arc::new(vec![1, 2]));
+    let value = serde_yaml::to_value(&(shared.clone(), shared)).unwrap();
+    assert_eq!(value, serde_yaml::from_str::<serde_yaml::Value>("[[1, 2], [1, 2]]").unwrap());
+    let back: Shared<Arc<Vec<i32>>> = serde_yaml::from_value(value[0].clone()).unwrap();
+    assert_eq!(*back.0, vec![1, 2]);
+}
+
+#[test]
+fn test_anchors_are_per_document() {
+    let shared = Shared(Arc::new(1));
+    let mut writer = serde_yaml::Writer::new(Vec::new());
+    writer.write(&(shared.clone(), shared.clone())).unwrap();
+    writer.write(&shared).unwrap();
+    let out = String::from_utf8(writer.into_inner().unwrap()).unwrap();
+    assert_eq!(out, "---\n- &id001 1\n- *id001\n---\n1\n");
+}
+
+#[test]
+fn test_anchor_names() {
+    #[derive(Serialize)]
+    struct Host {
+        name: &'static str,
+        port: u16,
+    }
+
+    let db = Shared(Arc::new(Host { name: "db", port: 5432 }));
+    let other_db = Shared(Arc::new(Host { name: "db", port: 5433 }));
+    let spaced = Shared(Arc::new(Host { name: "a b", port: 1 }));
+    let plain = Shared(Arc::new(vec![1]));
+    let hosts = (&db, &db, &other_db, &other_db, &spaced, &spaced, &plain, &plain);
+
+    let mut writer = serde_yaml::Writer::new(Vec::new())
+        .anchor_names(|value| value["name"].as_str().map(str::to_owned));
+    writer.write(&hosts).unwrap();
+    let yaml = String::from_utf8(writer.into_inner().unwrap()).unwrap();
+    let expected = unindent("
+        ---
+        - &db
+          name: db
+          port: 5432
+        - *db
+        - &db_2
+          name: db
+          port: 5433
+        - *db_2
+        - &id003
+          name: a b
+          port: 1
+        - *id003
+        - &id004
+          - 1
+        - *id004
+    ");
+    assert_eq!(yaml, expected);
+
+    let value: serde_yaml::Value = serde_yaml::from_str(&yaml).unwrap();
+    assert_eq!(value[3]["port"], 5433);
+}
+
+#[test]
+fn test_temporaries_are_not_confused() {
+    use serde::ser::{Serialize, SerializeSeq, Serializer};
+
+    // Each element is a pointer that is dropped as soon as it is written,
+    // so the next one may be allocated at the same address.
+    struct Temporaries;
+
+    impl Serialize for Temporaries {
+        fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+            where S: Serializer
+        {
+            let mut seq = serializer.serialize_seq(Some(3))?;
+            for x in 1..4 {
+                seq.serialize_element(&Shared(Arc::new(vec![x, x])))?;
+            }
+            seq.end()
+        }
+    }
+
+    let yaml = serde_yaml::to_string(&Temporaries).unwrap();
+    assert_eq!(yaml, "---\n- - 1\n  - 1\n- - 2\n  - 2\n- - 3\n  - 3");
+}
+
+#[test]
+fn test_cycle() {
+    use std::cell::RefCell;
+
+    #[derive(Serialize)]
+    struct Node {
+        next: Option<Shared<Rc<RefCell<Node>>>>,
+    }
+
+    let node = Shared(Rc::new(RefCell::new(Node { next: None })));
+    node.borrow_mut().next = Some(node.clone());
+    let err = serde_yaml::to_string(&node).unwrap_err();
+    assert_eq!(err.to_string(), "next: shared value contains itself");
+    node.borrow_mut().next = None;
+}
+
+#[test]
+fn test_cycle_path() {
+    use std::cell::RefCell;
+
+    #[derive(Serialize)]
+    enum Entry {
+        Dir { children: Vec<Shared<Rc<RefCell<Entry>>>> },
+        File(String),
+    }
+
+    let root = Shared(Rc::new(RefCell::new(Entry::Dir { children: Vec::new() })));
+    let sub = Shared(Rc::new(RefCell::new(Entry::Dir { children: vec![root.clone()] })));
+    let file = Shared(Rc::new(RefCell::new(Entry::File("a".to_owned()))));
+    *root.borrow_mut() = Entry::Dir { children: vec![file, sub] };
+    let mut map = BTreeMap::new();
+    map.insert("root", root.clone());
+    let err = serde_yaml::to_string(&map).unwrap_err();
+    assert_eq!(err.to_string(),
+               "root.Dir.children[1].Dir.children[0]: shared value contains itself");
+    *root.borrow_mut() = Entry::File("b".to_owned());
+}
+
+#[test]
+fn test_with_attribute() {
+    #[derive(Serialize, Deserialize)]
+    struct Pair {
+        #[serde(with = "serde_yaml::shared")]
+        first: Arc<Vec<u8>>,
+        #[serde(with = "serde_yaml::shared")]
+        second: Arc<Vec<u8>>,
+    }
+
+    let inner = Arc::new(vec![1, 2]);
+    let pair = Pair {
+        first: inner.clone(),
+        second: inner,
+    };
+    let yaml = serde_yaml::to_string(&pair).unwrap();
+    assert_eq!(yaml, "---\nfirst: &id001\n  - 1\n  - 2\nsecond: *id001");
+    let back: Pair = serde_yaml::from_str(&yaml).unwrap();
+    assert!(Arc::ptr_eq(&back.first, &back.second));
+}
+
+#[test]
+fn test_compact() {
+    let key = Shared(Rc::new("k".to_owned()));
+    let list = Shared(Rc::new(vec![1, 2]));
+    let mut map = BTreeMap::new();
+    map.insert(key.clone(), list.clone());
+    let yaml = serde_yaml::to_string_compact(&(map, key, list)).unwrap();
+    assert_eq!(yaml, "[{&id001 k: &id002 [1, 2]}, *id001, *id002]");
+    let back: (BTreeMap<String, Vec<u8>>, String, Vec<u8>) = serde_yaml::from_str(&yaml).unwrap();
+    assert_eq!(back.2, [1, 2]);
+}
+
+#[test]
+fn test_events() {
+    use serde_yaml::event::Event;
+
+    let list = Shared(Rc::new(vec![1, 2]));
+    let single = Shared(Rc::new(3));
+    let events = serde_yaml::to_events(&(list.clone(), single, list)).unwrap();
+    assert_eq!(events, [
+        Event::SequenceStart(None),
+        Event::SequenceStart(Some("id001".to_owned())),
+        Event::plain("1"),
+        Event::plain("2"),
+        Event::SequenceEnd,
+        Event::plain("3"),
+        Event::Alias("id001".to_owned()),
+        Event::SequenceEnd,
+    ]);
+    let back: Vec<Vec<u8>> = serde_yaml::from_str("[[1, 2], [1, 2]]").unwrap();
+    let events = [events[..5].to_vec(), events[6..].to_vec()].concat();
+    assert_eq!(Vec::<Vec<u8>>::deserialize(serde_yaml::Deserializer::from_events(events)).unwrap(), back);
+}
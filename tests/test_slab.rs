@@ -0,0 +1,139 @@
+// Copyright 2018 Serde YAML Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+#![cfg(all(feature = "de", feature = "value"))]
+
+extern crate serde_yaml;
+
+use serde_yaml::Value;
+use serde_yaml::slab::Slab;
+
+fn slab(yaml: &str) -> Slab {
+    Slab::from_value(&serde_yaml::from_str(yaml).unwrap())
+}
+
+mod common;
+use common::yaml;
+
+#[test]
+fn test_round_trip() {
+    let value = yaml("a: [1, {b: x}]\nc: ~\n? [k]\n: v");
+    let slab = Slab::from_value(&value);
+    assert_eq!(slab.to_value(slab.root()), value);
+    assert_eq!(slab.len(), 7);
+}
+
+#[test]
+fn test_navigate() {
+    let slab = slab("a: [1, 2]\nb: x");
+    let root = slab.root();
+    let a = slab.get(root, &"a".into()).unwrap();
+    let elements = slab.elements(a).unwrap().to_vec();
+    assert_eq!(elements.len(), 2);
+    assert_eq!(slab.scalar(elements[1]), Some(&Value::from(2)));
+    assert_eq!(slab.parent(elements[1]), Some(a));
+    assert_eq!(slab.parent(a), Some(root));
+    assert_eq!(slab.parent(root), None);
+    let keys: Vec<_> = slab.entries(root).unwrap().map(|(k, _)| k.clone()).collect();
+    assert_eq!(keys, vec![Value::from("a"), Value::from("b")]);
+    assert!(slab.elements(root).is_none());
+    assert!(slab.entries(a).is_none());
+}
+
+#[test]
+fn test_move_keeps_ids() {
+    let mut slab = slab("from: [x, {deep: [1, 2, 3]}]\nto: []");
+    let root = slab.root();
+    let from = slab.get(root, &"from".into()).unwrap();
+    let to = slab.get(root, &"to".into()).unwrap();
+    let moved = slab.elements(from).unwrap()[1];
+    let deep = slab.get(moved, &"deep".into()).unwrap();
+
+    slab.detach(moved).unwrap();
+    assert_eq!(slab.parent(moved), None);
+    slab.push(to, moved).unwrap();
+    assert_eq!(slab.parent(moved), Some(to));
+    assert_eq!(slab.parent(deep), Some(moved));
+    assert_eq!(slab.to_value(root), yaml("from: [x]\nto: [{deep: [1, 2, 3]}]"));
+}
+
+#[test]
+fn test_insert_returns_previous() {
+    let mut slab = slab("a: 1");
+    let root = slab.root();
+    let old = slab.get(root, &"a".into()).unwrap();
+    let new = slab.add(&yaml("[2]"));
+    assert_eq!(slab.insert(root, "a".into(), new).unwrap(), Some(old));
+    assert_eq!(slab.parent(old), None);
+    assert!(slab.contains(old));
+    slab.remove(old).unwrap();
+    assert!(!slab.contains(old));
+    assert_eq!(slab.to_value(root), yaml("a: [2]"));
+}
+
+#[test]
+fn test_remove_frees_subtree() {
+    let mut slab = slab("a: {b: [1, 2]}\nc: 3");
+    let root = slab.root();
+    let a = slab.get(root, &"a".into()).unwrap();
+    let b = slab.get(a, &"b".into()).unwrap();
+    slab.remove(a).unwrap();
+    assert!(!slab.contains(a));
+    assert!(!slab.contains(b));
+    assert_eq!(slab.len(), 2);
+    assert_eq!(slab.to_value(root), yaml("c: 3"));
+
+    // Reused storage does not bring old ids back to life.
+    let added = slab.add(&Value::from("new"));
+    assert!(!slab.contains(a));
+    assert!(!slab.contains(b));
+    assert_eq!(slab.scalar(added), Some(&Value::from("new")));
+    assert_eq!(slab.to_value(a), Value::Null);
+}
+
+#[test]
+fn test_replace_keeps_place() {
+    let mut slab = slab("list: [1, [2, 3], 4]");
+    let root = slab.root();
+    let list = slab.get(root, &"list".into()).unwrap();
+    let inner = slab.elements(list).unwrap()[1];
+    let three = slab.elements(inner).unwrap()[1];
+    slab.replace(inner, &yaml("{k: v}")).unwrap();
+    assert_eq!(slab.elements(list).unwrap()[1], inner);
+    assert_eq!(slab.parent(inner), Some(list));
+    assert!(!slab.contains(three));
+    let v = slab.get(inner, &"k".into()).unwrap();
+    assert_eq!(slab.parent(v), Some(inner));
+    assert_eq!(slab.to_value(root), yaml("list: [1, {k: v}, 4]"));
+    assert_eq!(slab.len(), 6);
+}
+
+#[test]
+fn test_errors() {
+    let mut slab = slab("a: [1]\nb: {}");
+    let root = slab.root();
+    let a = slab.get(root, &"a".into()).unwrap();
+    let b = slab.get(root, &"b".into()).unwrap();
+    let one = slab.elements(a).unwrap()[0];
+
+    let check = |result: serde_yaml::Result<()>, expected: &str| {
+        assert_eq!(result.unwrap_err().to_string(), expected);
+    };
+    check(slab.push(b, one), "node is already attached; detach it first");
+    check(slab.detach(root), "the root cannot be detached");
+    check(slab.push(a, root), "the root cannot be attached");
+
+    slab.detach(a).unwrap();
+    check(slab.push(one, a).map(|_| ()), "node cannot be attached inside itself");
+    check(slab.push(b, a), "node is not a sequence");
+    let detached = slab.add(&Value::Null);
+    check(slab.insert(a, "k".into(), detached).map(|_| ()), "node is not a mapping");
+
+    slab.remove(a).unwrap();
+    check(slab.push(a, detached), "id does not refer to a node of this slab");
+}
@@ -0,0 +1,177 @@
+// Copyright 2018 Serde YAML Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+#![cfg(all(feature = "de", feature = "value"))]
+
+extern crate serde_yaml;
+
+extern crate unindent;
+use unindent::unindent;
+
+use serde_yaml::spanned::{Document, Node};
+use serde_yaml::{SpannedValue, Value};
+
+fn position(value: &SpannedValue) -> ((usize, usize), (usize, usize)) {
+    let span = value.span();
+    ((span.start().line(), span.start().column()), (span.end().line(), span.end().column()))
+}
+
+#[test]
+fn test_spans() {
+    let yaml = unindent("
+        ---
+        name: 'quoted'
+        ports:
+          - 80
+          - 443
+        nested: {a: b}");
+    let (doc, errors) = serde_yaml::parse_tolerant(&yaml);
+    assert!(errors.is_empty());
+
+    assert_eq!(position(doc.get("name").unwrap()), ((2, 7), (2, 15)));
+    assert_eq!(position(doc.get("ports").unwrap()), ((4, 3), (5, 8)));
+    assert_eq!(position(doc.get("nested").unwrap()), ((6, 9), (6, 15)));
+
+    let expected: Value = serde_yaml::from_str(&yaml).unwrap();
+    assert_eq!(doc.to_value(), expected);
+}
+
+#[test]
+fn test_duplicate_keys_preserved() {
+    let (doc, errors) = serde_yaml::parse_tolerant("a: 1\na: 2\n");
+    assert!(errors.is_empty());
+    match *doc.node() {
+        Node::Mapping(ref entries) => assert_eq!(entries.len(), 2),
+        ref other => panic!("expected mapping, got {:?}", other),
+    }
+    assert_eq!(doc.get("a").unwrap().to_value(), 2);
+}
+
+#[test]
+fn test_recover_unclosed_flow() {
+    let yaml = unindent("
+        - ok
+        - [bad
+        - fine
+        ");
+    let (doc, errors) = serde_yaml::parse_tolerant(&yaml);
+    assert_eq!(errors.len(), 1);
+    match *doc.node() {
+        Node::Sequence(ref seq) => {
+            assert_eq!(seq.len(), 3);
+            assert!(seq[1].is_error());
+            assert_eq!(position(&seq[1]), ((2, 3), (2, 7)));
+            assert_eq!(seq[2].to_value(), "fine");
+        }
+        ref other => panic!("expected sequence, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_recover_bad_indentation() {
+    let yaml = unindent("
+        a: 1
+          b: 2
+        c: 3
+        ");
+    let (doc, errors) = serde_yaml::parse_tolerant(&yaml);
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].location().unwrap().line(), 2);
+    assert_eq!(doc.get("a").unwrap().to_value(), 1);
+    assert_eq!(doc.get("c").unwrap().to_value(), 3);
+}
+
+#[test]
+fn test_invalid_tagged_scalar() {
+    let (doc, errors) = serde_yaml::parse_tolerant("a: !!int foo\nb: true\n");
+    assert_eq!(errors.len(), 1);
+    assert!(doc.get("a").unwrap().is_error());
+    assert_eq!(doc.get("b").unwrap().to_value(), Value::Bool(true));
+}
+
+#[test]
+fn test_empty() {
+    let (doc, errors) = serde_yaml::parse_tolerant("");
+    assert!(errors.is_empty());
+    assert_eq!(*doc.node(), Node::Null);
+}
+
+#[test]
+fn test_aliases() {
+    let yaml = unindent("
+        defaults: &defaults
+          image: &image app:1
+        web:
+          <<: *defaults
+          image: *image
+        jobs: [*defaults, {name: x}]
+        ");
+    let (doc, errors) = serde_yaml::parse_tolerant(&yaml);
+    assert!(errors.is_empty());
+
+    let aliases: Vec<(&str, usize, (usize, usize))> = doc.aliases()
+        .into_iter()
+        .map(|value| {
+            let alias = value.alias().unwrap();
+            (alias.name(), alias.anchor().start().line(), position(value).0)
+        })
+        .collect();
+    assert_eq!(aliases, [("defaults", 2, (4, 7)), ("image", 2, (5, 10)), ("defaults", 2, (6, 8))]);
+
+    let sources = doc.get("web").unwrap().merge_sources();
+    assert_eq!(sources.len(), 1);
+    assert_eq!(sources[0].to_value(), doc.get("defaults").unwrap().to_value());
+    assert!(doc.get("defaults").unwrap().merge_sources().is_empty());
+
+    let (doc, _) = serde_yaml::parse_tolerant("a: 1\nb: [2]\n");
+    assert!(doc.aliases().is_empty());
+}
+
+#[test]
+fn test_anchor_table() {
+    let yaml = unindent("
+        base: &base
+          image: app
+        list:
+          - &first 1
+          - [&nested x]
+        &key k: *base
+        again: &base 2
+        copy: *first
+        1: &number {}
+        ? &complex [a]
+        : v");
+    let (doc, errors) = Document::parse(&yaml);
+    assert!(errors.is_empty());
+    let mut anchors: Vec<(&str, &str)> =
+        doc.anchors().iter().map(|(k, v)| (k.as_str(), v.as_str())).collect();
+    anchors.sort();
+    assert_eq!(anchors,
+               vec![("base", "again"),
+                    ("complex", "?"),
+                    ("first", "list[0]"),
+                    ("key", "k"),
+                    ("nested", "list[1][0]"),
+                    ("number", "1")]);
+    let aliases = doc.root().aliases();
+    let references: Vec<&str> = aliases.iter().map(|alias| alias.alias().unwrap().name()).collect();
+    assert_eq!(references, ["base", "first"]);
+}
+
+#[test]
+fn test_anchor_table_root_and_recovery() {
+    let (doc, _) = Document::parse("&top [1, &one 2]");
+    assert_eq!(doc.anchors()["top"], ".");
+    assert_eq!(doc.anchors()["one"], ".[1]");
+
+    let (doc, errors) = Document::parse("a: &a 1\nb: [unclosed &lost\nc: &c 3\n");
+    assert_eq!(errors.len(), 1);
+    assert_eq!(doc.anchors().len(), 2);
+    assert_eq!(doc.anchors()["c"], "c");
+    assert_eq!(doc.into_root().get("a").unwrap().to_value(), 1);
+}
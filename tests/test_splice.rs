@@ -0,0 +1,194 @@
+// Copyright 2018 Serde YAML Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+#![cfg(all(feature = "de", feature = "value"))]
+
+extern crate serde_yaml;
+
+use serde_yaml::{splice, Value};
+
+fn test_splice<F>(original: &str, edit: F, expected: &str)
+    where F: FnOnce(&mut Value)
+{
+    let mut value: Value = serde_yaml::from_str(original).unwrap();
+    edit(&mut value);
+    assert_eq!(splice::to_string(original, &value).unwrap(), expected);
+}
+
+#[test]
+fn test_unchanged() {
+    let original = "# comment\na:   1  # one\nb: [x,   y]\nc: |\n  text\n";
+    test_splice(original, |_| {}, original);
+}
+
+#[test]
+fn test_scalars() {
+    test_splice("a: 1  # keep\nb:\n  c: 'old'\n  d: [1, 2]\n",
+                |v| {
+                    v["a"] = 2.into();
+                    v["b"]["c"] = "new value".into();
+                    v["b"]["d"][1] = "x, y".into();
+                },
+                "a: 2  # keep\nb:\n  c: new value\n  d: [1, \"x, y\"]\n");
+}
+
+#[test]
+fn test_mapping_entries() {
+    test_splice("a: 1\n# about b\nb: 2\nc:\n  d: 3\n",
+                |v| {
+                    v.as_mapping_mut().unwrap().remove(&"b".into());
+                    v["c"]["e"] = 4.into();
+                    v["f"] = 5.into();
+                },
+                "a: 1\n# about b\nc:\n  d: 3\n  e: 4\nf: 5\n");
+}
+
+#[test]
+fn test_entry_inserted_first() {
+    let original = "- a: 1\n  b: 2\n";
+    let mut value: Value = serde_yaml::from_str(original).unwrap();
+    let mut mapping = serde_yaml::Mapping::new();
+    mapping.insert("first".into(), 0.into());
+    for (k, v) in value[0].as_mapping().unwrap() {
+        mapping.insert(k.clone(), v.clone());
+    }
+    value[0] = Value::Mapping(mapping);
+    assert_eq!(splice::to_string(original, &value).unwrap(), "- first: 0\n  a: 1\n  b: 2\n");
+}
+
+#[test]
+fn test_sequence_elements() {
+    test_splice("list:\n  - a\n  - b  # bee\n  - c\n",
+                |v| {
+                    let seq = v["list"].as_sequence_mut().unwrap();
+                    seq.remove(0);
+                    seq.insert(1, "new".into());
+                    seq.push(Value::Sequence(vec![1.into(), 2.into()]));
+                },
+                "list:\n  - b  # bee\n  - new\n  - c\n  - - 1\n    - 2\n");
+}
+
+#[test]
+fn test_last_line_removed() {
+    test_splice("a: 1\nb: 2", |v| { v.as_mapping_mut().unwrap().remove(&"b".into()); }, "a: 1");
+}
+
+#[test]
+fn test_rewritten_whole() {
+    // A flow collection that changes length.
+    test_splice("a: [1, 2]  # flow\n", |v| v["a"][1] = 3.into(), "a: [1, 3]  # flow\n");
+    test_splice("a: [1, 2]  # flow\n",
+                |v| v["a"].as_sequence_mut().unwrap().push(3.into()),
+                "a: [1, 2, 3]  # flow\n");
+    // A block collection that takes a different shape.
+    test_splice("a:\n  b: 1\n  c: 2\nd: 3\n",
+                |v| v["a"] = Value::Sequence(vec!["x".into(), "y".into()]),
+                "a:\n  - x\n  - \"y\"\nd: 3\n");
+    // A scalar that becomes a collection.
+    test_splice("a: 1\n", |v| v["a"] = Value::Sequence(vec![1.into()]), "a: [1]\n");
+}
+
+#[test]
+fn test_aliases() {
+    test_splice("base: &b {x: 1}\nuse: *b\n", |_| {}, "base: &b {x: 1}\nuse: *b\n");
+    test_splice("base: &b {x: 1}\nuse: *b\nother: *b\n",
+                |v| v["base"]["x"] = 2.into(),
+                "base: &b {x: 2}\nuse: {x: 1}\nother: {x: 1}\n");
+    test_splice("base: &b {x: 1}\nuse: *b\n", |v| v["use"] = 5.into(), "base: &b {x: 1}\nuse: 5\n");
+}
+
+#[test]
+fn test_non_ascii() {
+    test_splice("ключ: значение\nß: 1\n", |v| v["ß"] = 2.into(), "ключ: значение\nß: 2\n");
+}
+
+#[test]
+fn test_block_scalars() {
+    test_splice("a: |\n  hello\nb: 1", |v| v["a"] = "bye".into(), "a: bye\nb: 1");
+    test_splice("a: >-  # folded\n\n  hello\n  world\nb: 1\n",
+                |v| v["a"] = "bye".into(),
+                "a: bye\nb: 1\n");
+    test_splice("- |\n  x\n- |+\n  y\n\n- z\n",
+                |v| {
+                    v[0] = "one\ntwo\n".into();
+                    v[1] = 2.into();
+                },
+                "- \"one\\ntwo\\n\"\n- 2\n\n- z\n");
+    test_splice("a: |\nb: 1\n", |v| v["a"] = "x".into(), "a: x\nb: 1\n");
+}
+
+#[test]
+fn test_errors() {
+    let value = Value::Null;
+    assert!(splice::to_string("a: [", &value).is_err());
+    let mut value: Value = serde_yaml::from_str("a: !!str 1").unwrap();
+    value["a"] = 2.into();
+    assert_eq!(splice::to_string("a: !!str 1", &value).unwrap_err().to_string(),
+               "the edits cannot be spliced into the original text");
+}
+
+#[test]
+fn test_large_document() {
+    let mut original = String::new();
+    for i in 0..20000 {
+        original.push_str(&format!("key{}:  # entry {}\n  value: {}\n", i, i, i));
+    }
+    let mut value: Value = serde_yaml::from_str(&original).unwrap();
+    value["key12345"]["value"] = "changed".into();
+    let expected = original.replace("  value: 12345\n", "  value: changed\n");
+    assert_eq!(splice::to_string(&original, &value).unwrap(), expected);
+}
+
+/// Applies edits the way an editor does, all against the original text.
+fn apply(original: &str, edits: &[splice::TextEdit]) -> String {
+    let chars: Vec<char> = original.chars().collect();
+    let mut out = String::new();
+    let mut copied = 0;
+    for edit in edits {
+        out.extend(&chars[copied..edit.start.index()]);
+        out.push_str(&edit.text);
+        copied = edit.end.index();
+    }
+    out.extend(&chars[copied..]);
+    out
+}
+
+#[test]
+fn test_edits() {
+    let original = "a: 1\nb:\n  - x\nc: done\n";
+    let mut value: Value = serde_yaml::from_str(original).unwrap();
+    value["a"] = 2.into();
+    value["b"].as_sequence_mut().unwrap().push("y".into());
+    value.as_mapping_mut().unwrap().remove(&"c".into());
+    let edits = splice::edits(original, &value).unwrap();
+    let positions: Vec<_> = edits.iter()
+        .map(|edit| (edit.start.line(), edit.start.column(), edit.end.line(), edit.end.column()))
+        .collect();
+    assert_eq!(positions, vec![(1, 4, 1, 5), (3, 6, 3, 6), (4, 1, 5, 1)]);
+    assert_eq!(apply(original, &edits), splice::to_string(original, &value).unwrap());
+}
+
+#[test]
+fn test_diff() {
+    let cases = [("a\nb\nc\n", "a\nx\nc\n"),
+                 ("a\nb\nc", "a\nb\nc\nd"),
+                 ("a\nb\nc\n", "b\n"),
+                 ("", "a: 1\n"),
+                 ("a: 1\n", ""),
+                 ("same\n", "same\n"),
+                 ("é\nb\n", "é\nβ\n")];
+    for &(original, modified) in &cases {
+        let edits = splice::diff(original, modified);
+        assert_eq!(apply(original, &edits), modified, "{:?} -> {:?}", original, modified);
+    }
+    assert!(splice::diff("same\n", "same\n").is_empty());
+    let edits = splice::diff("a\nb\nc\n", "b\n");
+    assert_eq!(edits.len(), 2);
+    assert_eq!(edits[1].start.line(), 3);
+    assert_eq!(edits[1].text, "");
+}
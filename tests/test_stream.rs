@@ -0,0 +1,94 @@
+// Copyright 2018 Serde YAML Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+#![cfg(all(feature = "de", feature = "value"))]
+
+extern crate serde_yaml;
+
+use serde_yaml::{Parser, Value};
+
+/// Feeds `input` one byte at a time and collects every document as soon as
+/// it is available.
+fn byte_by_byte(input: &[u8]) -> Vec<Value> {
+    collect(Parser::new(), input)
+}
+
+fn collect(mut parser: Parser, input: &[u8]) -> Vec<Value> {
+    let mut documents = Vec::new();
+    for byte in input {
+        parser.feed(&[*byte]);
+        while let Some(document) = parser.next_document().unwrap() {
+            documents.push(document);
+        }
+    }
+    parser.finish();
+    while let Some(document) = parser.next_document().unwrap() {
+        documents.push(document);
+    }
+    documents
+}
+
+mod common;
+use common::yaml;
+
+#[test]
+fn test_documents() {
+    let input = "# stream\n%YAML 1.2\n---\na: 1\n--- [2]\n...\n# between\n---\n---\n\"é\": |\n  ---\n";
+    let documents = byte_by_byte(input.as_bytes());
+    assert_eq!(documents, [yaml("a: 1"), yaml("[2]"), Value::Null, yaml("é: \"---\\n\"")]);
+}
+
+#[test]
+fn test_bare_documents() {
+    assert_eq!(byte_by_byte(b"a: 1\n...\nb: 2\r\n...\r\nc"), [yaml("a: 1"), yaml("b: 2"), yaml("c")]);
+    assert_eq!(byte_by_byte(b""), Vec::<Value>::new());
+    assert_eq!(byte_by_byte(b"# only\n\n...\n# comments\n"), Vec::<Value>::new());
+}
+
+#[test]
+fn test_skip_empty() {
+    let input = b"---\n# nothing\n---\n--- 1\n...\n%YAML 1.2\n---\n...\n--- |\n  x\n---";
+    let all = [Value::Null, Value::Null, yaml("1"), Value::Null, yaml("\"x\\n\""), Value::Null];
+    assert_eq!(byte_by_byte(input), all);
+    assert_eq!(collect(Parser::new().skip_empty(true), input), [yaml("1"), yaml("\"x\\n\"")]);
+    assert_eq!(collect(Parser::new().skip_empty(true), b"---\n---\n"), Vec::<Value>::new());
+}
+
+#[test]
+fn test_available_before_finish() {
+    let mut parser = Parser::new();
+    parser.feed(b"--- 1\n--- 2\n---");
+    assert_eq!(parser.next_document::<u8>().unwrap(), Some(1));
+    assert_eq!(parser.next_document::<u8>().unwrap(), None);
+    parser.feed(b" 3\n");
+    assert_eq!(parser.next_document::<u8>().unwrap(), Some(2));
+    assert_eq!(parser.next_document::<u8>().unwrap(), None);
+    parser.finish();
+    parser.feed(b"--- 4\n");
+    assert_eq!(parser.next_document::<u8>().unwrap(), Some(3));
+    assert_eq!(parser.next_document::<u8>().unwrap(), None);
+}
+
+#[test]
+fn test_error_skips_document() {
+    let mut parser = Parser::new();
+    parser.feed(b"--- 1\n--- [\n--- x\n--- 3\n");
+    parser.finish();
+    assert_eq!(parser.next_document::<u8>().unwrap(), Some(1));
+    assert!(parser.next_document::<u8>().is_err());
+    let err = parser.next_document::<u8>().unwrap_err();
+    assert_eq!(err.to_string(), "invalid type: string \"x\", expected u8 at line 1 column 5");
+    assert_eq!(parser.next_document::<u8>().unwrap(), Some(3));
+    assert_eq!(parser.next_document::<u8>().unwrap(), None);
+}
+
+#[test]
+fn test_send_sync() {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<Parser>();
+}
@@ -0,0 +1,178 @@
+// Copyright 2018 Serde YAML Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+#![cfg(all(feature = "de", feature = "value"))]
+
+#[macro_use]
+extern crate serde_derive;
+extern crate serde;
+extern crate serde_yaml;
+extern crate serde_yaml_derive;
+
+use std::collections::BTreeMap;
+
+use serde_yaml::comment::Commented;
+use serde_yaml::style::{Style, Styled};
+use serde_yaml::Value;
+use serde_yaml_derive::styled;
+
+#[test]
+fn test_styled() {
+    let mut labels = BTreeMap::new();
+    labels.insert("app", "web");
+    labels.insert("tier", "front");
+    let value = (
+        Styled::new(Style::Flow, labels),
+        Styled::new(Style::Flow, vec![vec![1, 2], vec![]]),
+        Styled::new(Style::Literal, "a\n  b\n"),
+    );
+    let expected = [
+        "---",
+        "- {app: web, tier: front}",
+        "- [[1, 2], []]",
+        "- |",
+        "  a",
+        "    b",
+        "",
+    ];
+    assert_eq!(serde_yaml::to_string(&value).unwrap(), expected.join("\n"));
+}
+
+#[test]
+fn test_styled_root() {
+    let yaml = serde_yaml::to_string(&Styled::new(Style::Flow, vec![1, 2])).unwrap();
+    assert_eq!(yaml, "---\n[1, 2]");
+}
+
+#[test]
+fn test_style_does_not_fit() {
+    let value = (
+        Styled::new(Style::Flow, 1),
+        Styled::new(Style::Literal, vec!["x"]),
+        Styled::new(Style::Literal, "one line"),
+        Styled::new(Style::Literal, " indented\nfirst line"),
+    );
+    let expected = "---\n- 1\n- - x\n- one line\n- \" indented\\nfirst line\"";
+    assert_eq!(serde_yaml::to_string(&value).unwrap(), expected);
+}
+
+#[test]
+fn test_styled_with_comment() {
+    let mut map = BTreeMap::new();
+    map.insert("outer", Commented::new("outer", Styled::new(Style::Flow, vec![1])));
+    map.insert("inner", Commented::from(Styled::new(Style::Flow, vec![2])));
+    let mut commented = BTreeMap::new();
+    commented.insert("styled", Styled::new(Style::Flow, Commented::new("inside", vec![3])));
+    let expected = [
+        "---",
+        "- inner: [2]",
+        "  # outer",
+        "  outer: [1]",
+        "- # inside",
+        "  styled: [3]",
+    ];
+    assert_eq!(serde_yaml::to_string(&(map, commented)).unwrap(), expected.join("\n"));
+}
+
+#[test]
+fn test_styled_to_value() {
+    let value = serde_yaml::to_value(Styled::new(Style::Flow, vec![1])).unwrap();
+    assert_eq!(value, Value::Sequence(vec![Value::Number(1.into())]));
+}
+
+#[test]
+fn test_with_adapters() {
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Step {
+        #[serde(with = "serde_yaml::with::flow")]
+        needs: Vec<String>,
+        #[serde(with = "serde_yaml::with::block_scalar")]
+        run: String,
+    }
+
+    let step = Step {
+        needs: vec!["build".to_owned(), "lint".to_owned()],
+        run: "cargo build\ncargo test".to_owned(),
+    };
+    let yaml = "---\nneeds: [build, lint]\nrun: |-\n  cargo build\n  cargo test";
+    assert_eq!(serde_yaml::to_string(&step).unwrap(), yaml);
+    assert_eq!(serde_yaml::from_str::<Step>(yaml).unwrap(), step);
+
+    // The final line break of the last value survives the round trip.
+    let step = Step {
+        needs: Vec::new(),
+        run: "cargo build\ncargo test\n".to_owned(),
+    };
+    let yaml = serde_yaml::to_string(&step).unwrap();
+    assert_eq!(yaml, "---\nneeds: []\nrun: |\n  cargo build\n  cargo test\n");
+    assert_eq!(serde_yaml::from_str::<Step>(&yaml).unwrap(), step);
+}
+
+#[styled]
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+struct Job {
+    #[yaml(comment = "Runs on every push.")]
+    name: String,
+    #[yaml(flow)]
+    ports: Vec<u16>,
+    #[yaml(block_scalar, comment = "Line one.\nLine two.")]
+    #[serde(rename = "run")]
+    script: String,
+    #[yaml(flow)]
+    #[yaml(comment = "Several attributes.")]
+    env: BTreeMap<String, String>,
+}
+
+#[styled]
+#[derive(Serialize)]
+#[serde(bound = "T: serde::Serialize")]
+enum Shape<T> {
+    Point(#[yaml(flow)] Vec<T>),
+    Text {
+        #[yaml(block_scalar)]
+        body: String,
+    },
+}
+
+#[test]
+fn test_styled_attribute() {
+    let mut env = BTreeMap::new();
+    env.insert("CI".to_owned(), "1".to_owned());
+    let job = Job {
+        name: "test".to_owned(),
+        ports: vec![80],
+        script: "make\nmake test".to_owned(),
+        env,
+    };
+    let expected = [
+        "---",
+        "# Runs on every push.",
+        "name: test",
+        "ports: [80]",
+        "# Line one.",
+        "# Line two.",
+        "run: |-",
+        "  make",
+        "  make test",
+        "# Several attributes.",
+        "env: {CI: \"1\"}",
+    ];
+    let yaml = serde_yaml::to_string(&job).unwrap();
+    assert_eq!(yaml, expected.join("\n"));
+    assert_eq!(serde_yaml::from_str::<Job>(&yaml).unwrap(), job);
+}
+
+#[test]
+fn test_styled_enum() {
+    let shapes = vec![
+        Shape::Point(vec![1.5, 2.0]),
+        Shape::Text { body: "a\nb".to_owned() },
+    ];
+    let expected = "---\n- Point: [1.5, 2.0]\n- Text:\n    body: |-\n      a\n      b";
+    assert_eq!(serde_yaml::to_string(&shapes).unwrap(), expected);
+}
@@ -0,0 +1,64 @@
+// Copyright 2018 Serde YAML Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+#![cfg(all(feature = "de", feature = "value"))]
+
+extern crate serde_yaml;
+
+use serde_yaml::Value;
+
+mod common;
+use common::yaml;
+
+fn resolve(s: &str) -> Value {
+    let mut value = yaml(s);
+    value.resolve_substitutions().unwrap();
+    value
+}
+
+fn resolve_err(s: &str) -> String {
+    let mut value = yaml(s);
+    let err = value.resolve_substitutions().unwrap_err();
+    assert_eq!(value, yaml(s));
+    err.to_string()
+}
+
+#[test]
+fn test_whole_values() {
+    assert_eq!(resolve("{a: 1, b: '${a}', c: '${d}', d: {e: [true, ~]}}"),
+               yaml("{a: 1, b: 1, c: {e: [true, ~]}, d: {e: [true, ~]}}"));
+    assert_eq!(resolve("{a: [x, y], b: '${a[1]}', c: '${ a }'}"), yaml("{a: [x, y], b: y, c: [x, y]}"));
+}
+
+#[test]
+fn test_in_strings() {
+    assert_eq!(resolve("{n: 8080, t: true, s: host, url: 'http://${s}:${n}/?debug=${t}'}")["url"],
+               "http://host:8080/?debug=true");
+    assert_eq!(resolve("{a: x, b: '$${a} is ${a}', c: 'no refs $ { }'}"),
+               yaml("{a: x, b: '${a} is x', c: 'no refs $ { }'}"));
+}
+
+#[test]
+fn test_chains() {
+    assert_eq!(resolve("{a: '${b}', b: '${c}-2', c: '${d.e}', d: {e: 1}}"),
+               yaml("{a: 1-2, b: 1-2, c: 1, d: {e: 1}}"));
+    // The path runs through a value that is a reference itself.
+    assert_eq!(resolve("{a: '${b}', b: {c: [x, '${d}']}, d: y, e: '${a.c[1]}'}")["e"], "y");
+}
+
+#[test]
+fn test_errors() {
+    assert_eq!(resolve_err("{a: '${b.c}', b: {}}"),
+               "reference `${b.c}` at `a` refers to a path that does not exist");
+    assert_eq!(resolve_err("{a: ['x ${b}'], b: [1]}"),
+               "reference `${b}` at `a[0]` is part of a string but refers to null or a collection");
+    assert_eq!(resolve_err("{a: '${a}'}"), "references form a cycle: `a` -> `a`");
+    assert_eq!(resolve_err("{a: {b: '${c}'}, c: '${a}'}"), "references form a cycle: `c` -> `a` -> `c`");
+    assert_eq!(resolve_err("a: '${.}'"), "references form a cycle: `.` -> `.`");
+    assert_eq!(resolve_err("a: 'x ${b'"), "unterminated reference in `x ${b`");
+}
@@ -0,0 +1,183 @@
+// Copyright 2018 Serde YAML Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+#![cfg(all(feature = "tags", feature = "de", feature = "value"))]
+
+extern crate rmp_serde;
+extern crate serde;
+extern crate serde_cbor;
+#[macro_use]
+extern crate serde_derive;
+extern crate serde_transcode;
+extern crate serde_yaml;
+
+extern crate unindent;
+use unindent::unindent;
+
+use std::collections::BTreeMap;
+
+use serde::de::Deserialize;
+use serde_cbor::Value as Cbor;
+use serde_yaml::{Deserializer, TagAction, TagPolicy};
+
+fn yaml_to_cbor(yaml: &str, tags: TagPolicy) -> serde_yaml::Result<Vec<u8>> {
+    let mut cbor = Vec::new();
+    {
+        let de = Deserializer::from_str(yaml).tag_policy(tags);
+        let mut ser = serde_cbor::Serializer::new(&mut cbor);
+        serde_transcode::transcode(de, &mut ser).map_err(serde::ser::Error::custom)?;
+    }
+    Ok(cbor)
+}
+
+fn yaml_to_msgpack(yaml: &str, tags: TagPolicy) -> serde_yaml::Result<Vec<u8>> {
+    let mut msgpack = Vec::new();
+    {
+        let de = Deserializer::from_str(yaml).tag_policy(tags);
+        let mut ser = rmp_serde::Serializer::new(&mut msgpack);
+        serde_transcode::transcode(de, &mut ser).map_err(serde::ser::Error::custom)?;
+    }
+    Ok(msgpack)
+}
+
+fn entry(value: &Cbor, key: &str) -> Cbor {
+    match *value {
+        Cbor::Map(ref map) => map[&Cbor::Text(key.to_owned())].clone(),
+        _ => panic!("expected a map, found {:?}", value),
+    }
+}
+
+#[test]
+fn test_binary_is_lost_by_default() {
+    let cbor = yaml_to_cbor("data: !!binary aGVsbG8=", TagPolicy::new()).unwrap();
+    let value: Cbor = serde_cbor::from_slice(&cbor).unwrap();
+    assert_eq!(entry(&value, "data"), Cbor::Text("aGVsbG8=".to_owned()));
+}
+
+#[test]
+fn test_binary_to_cbor() {
+    let yaml = unindent("
+        inline: !!binary aGVsbG8=
+        block: !!binary |
+          R0lGODlhDAAMAIQAAP//9/X17unp5WZmZgAAAOfn515eXvPz7Y6OjuDg4J+fn5
+          OTk6enp56enmleECcgggoBADs=
+        text: plain");
+    let cbor = yaml_to_cbor(&yaml, TagPolicy::binary()).unwrap();
+    let value: Cbor = serde_cbor::from_slice(&cbor).unwrap();
+    assert_eq!(entry(&value, "inline"), Cbor::Bytes(b"hello".to_vec()));
+    match entry(&value, "block") {
+        Cbor::Bytes(ref gif) => assert!(gif.starts_with(b"GIF89a")),
+        other => panic!("expected bytes, found {:?}", other),
+    }
+    assert_eq!(entry(&value, "text"), Cbor::Text("plain".to_owned()));
+}
+
+#[test]
+fn test_binary_to_msgpack() {
+    let msgpack = yaml_to_msgpack("[!!binary AAEC]", TagPolicy::binary()).unwrap();
+    // fixarray of one element, bin 8 of three bytes
+    assert_eq!(msgpack, [0x91, 0xc4, 0x03, 0x00, 0x01, 0x02]);
+}
+
+#[test]
+fn test_invalid_base64() {
+    let err = yaml_to_cbor("!!binary 'not base64'", TagPolicy::binary()).unwrap_err();
+    assert!(err.to_string().contains("base64"), "{}", err);
+}
+
+#[test]
+fn test_wrap_tags() {
+    let tags = TagPolicy::new().unknown(TagAction::Wrap);
+    let cbor = yaml_to_cbor("{a: !celsius 21.5, b: !!str 1}", tags).unwrap();
+    let value: Cbor = serde_cbor::from_slice(&cbor).unwrap();
+    let mut celsius = BTreeMap::new();
+    celsius.insert(Cbor::Text("!celsius".to_owned()), Cbor::Float(21.5));
+    assert_eq!(entry(&value, "a"), Cbor::Map(celsius));
+    assert_eq!(entry(&value, "b"), Cbor::Text("1".to_owned()));
+}
+
+#[test]
+fn test_wrap_enum() {
+    #[derive(Deserialize, PartialEq, Debug)]
+    enum Temperature {
+        #[serde(rename = "!celsius")]
+        Celsius(f64),
+        #[serde(rename = "!kelvin")]
+        Kelvin(f64),
+    }
+
+    let tags = TagPolicy::new().unknown(TagAction::Wrap);
+    let de = Deserializer::from_str("[!kelvin 300, !celsius 20]").tag_policy(tags);
+    let temperatures = Vec::<Temperature>::deserialize(de).unwrap();
+    assert_eq!(temperatures, [Temperature::Kelvin(300.0), Temperature::Celsius(20.0)]);
+}
+
+#[test]
+fn test_error_tags() {
+    let tags = TagPolicy::binary().unknown(TagAction::Error);
+    assert!(yaml_to_cbor("!!binary aGk=", tags.clone()).is_ok());
+    let de = Deserializer::from_str("x: !custom 1").tag_policy(tags);
+    let err = serde_yaml::Value::deserialize(de).unwrap_err();
+    assert!(err.to_string().starts_with("x: unsupported tag `!custom` at line 1"), "{}", err);
+}
+
+#[test]
+fn test_bytes_keys_from_cbor() {
+    let mut map = BTreeMap::new();
+    map.insert(Cbor::Bytes(b"hi".to_vec()), Cbor::Integer(1));
+    map.insert(Cbor::Text("text".to_owned()), Cbor::Integer(2));
+    let cbor = serde_cbor::to_vec(&Cbor::Map(map.clone())).unwrap();
+    let mut de = serde_cbor::Deserializer::from_slice(&cbor);
+    let yaml = serde_yaml::to_string(&serde_transcode::Transcoder::new(&mut de)).unwrap();
+    assert_eq!(yaml, "---\n!!binary aGk=: 1\ntext: 2");
+
+    let back = yaml_to_cbor(&yaml, TagPolicy::binary()).unwrap();
+    assert_eq!(serde_cbor::from_slice::<Cbor>(&back).unwrap(), Cbor::Map(map));
+}
+
+#[test]
+fn test_bytes_keys_without_tag_policy() {
+    #[derive(PartialEq, Eq, PartialOrd, Ord, Debug)]
+    struct Key(Vec<u8>);
+
+    impl<'de> Deserialize<'de> for Key {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+            where D: serde::Deserializer<'de>
+        {
+            struct KeyVisitor;
+
+            impl<'de> serde::de::Visitor<'de> for KeyVisitor {
+                type Value = Key;
+
+                fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                    formatter.write_str("bytes")
+                }
+
+                fn visit_byte_buf<E>(self, v: Vec<u8>) -> Result<Key, E> {
+                    Ok(Key(v))
+                }
+            }
+
+            deserializer.deserialize_byte_buf(KeyVisitor)
+        }
+    }
+
+    let map: BTreeMap<Key, i32> = serde_yaml::from_str("!!binary aGk=: 1").unwrap();
+    assert_eq!(map.into_iter().collect::<Vec<_>>(), [(Key(b"hi".to_vec()), 1)]);
+}
+
+#[test]
+fn test_cbor_to_value() {
+    let mut map = BTreeMap::new();
+    map.insert(Cbor::Text("ports".to_owned()), Cbor::Array(vec![Cbor::Integer(80), Cbor::Integer(443)]));
+    map.insert(Cbor::Text("ratio".to_owned()), Cbor::Float(0.5));
+    let cbor = serde_cbor::to_vec(&Cbor::Map(map)).unwrap();
+    let mut de = serde_cbor::Deserializer::from_slice(&cbor);
+    let value = serde_transcode::transcode(&mut de, serde_yaml::value::Serializer).unwrap();
+    assert_eq!(value, serde_yaml::from_str::<serde_yaml::Value>("{ports: [80, 443], ratio: 0.5}").unwrap());
+}
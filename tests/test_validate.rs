@@ -0,0 +1,74 @@
+// Copyright 2018 Serde YAML Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+#![cfg(all(feature = "limits", feature = "de"))]
+
+extern crate serde_yaml;
+
+use serde_yaml::{validate, validate_with, Limits};
+
+#[test]
+fn test_stats() {
+    let stats = validate("").unwrap();
+    assert_eq!((stats.documents(), stats.nodes(), stats.max_depth()), (0, 0, 0));
+
+    let stats = validate("--- 1\n--- [a, {b: [c]}]\n--- {}").unwrap();
+    assert_eq!(stats.documents(), 3);
+    assert_eq!(stats.nodes(), 8);
+    assert_eq!(stats.expanded_nodes(), 8);
+    assert_eq!(stats.max_depth(), 3);
+    assert_eq!((stats.anchors(), stats.aliases()), (0, 0));
+}
+
+#[test]
+fn test_aliases() {
+    let stats = validate("base: &base {x: 1, y: 2}\nweb: *base\napi: *base\nkey: &k k\n*k : 1").unwrap();
+    assert_eq!(stats.nodes(), 15);
+    // Each alias to `base` stands for its 5 nodes.
+    assert_eq!(stats.expanded_nodes(), 15 + 4 + 4);
+    assert_eq!((stats.anchors(), stats.aliases()), (2, 3));
+}
+
+#[test]
+fn test_billion_laughs() {
+    let mut yaml = String::from("a0: &a0 [lol, lol, lol, lol, lol, lol, lol, lol, lol]\n");
+    for i in 1..10 {
+        let refs = vec![format!("*a{}", i - 1); 9].join(", ");
+        yaml.push_str(&format!("a{}: &a{} [{}]\n", i, i, refs));
+    }
+    let err = validate(&yaml).unwrap_err();
+    assert_eq!(err.to_string(), "documents expand to more than 1000000 nodes at line 7 column 10");
+
+    let limits = Limits::new().max_expanded_nodes(u64::MAX);
+    let stats = validate_with(&yaml, &limits).unwrap();
+    assert_eq!(stats.nodes(), 1 + 10 * 11);
+    assert!(stats.expanded_nodes() > 1_000_000_000);
+}
+
+#[test]
+fn test_limits() {
+    let deep = format!("{}{}", "[".repeat(200), "]".repeat(200));
+    let err = validate(&deep).unwrap_err();
+    assert_eq!(err.to_string(), "collections nested more than 128 deep at line 1 column 129");
+    let limits = Limits::new().max_depth(200);
+    assert_eq!(validate_with(&deep, &limits).unwrap().max_depth(), 200);
+
+    let limits = Limits::new().max_documents(2);
+    let err = validate_with("--- 1\n--- 2\n--- 3", &limits).unwrap_err();
+    assert_eq!(err.to_string(), "more than 2 documents at line 3 column 1");
+
+    let limits = Limits::new().max_nodes(3);
+    let err = validate_with("[1, 2, 3]", &limits).unwrap_err();
+    assert_eq!(err.to_string(), "more than 3 nodes at line 1 column 8");
+}
+
+#[test]
+fn test_syntax_error() {
+    assert!(validate("a: [1").is_err());
+    assert!(validate("a: *missing").is_err());
+}
@@ -1,3 +1,5 @@
+#![cfg(feature = "de")]
+
 extern crate serde_yaml;
 extern crate serde;
 
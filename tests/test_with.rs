@@ -0,0 +1,368 @@
+// Copyright 2018 Serde YAML Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+#![cfg(all(feature = "de", feature = "value"))]
+
+#[macro_use]
+extern crate serde_derive;
+extern crate serde_yaml;
+
+extern crate linked_hash_map;
+use linked_hash_map::LinkedHashMap;
+
+use std::fmt;
+use std::net::{IpAddr, SocketAddr};
+use std::str::FromStr;
+use std::time::Duration;
+
+/// Stands in for a decimal type that keeps its scale, like
+/// `rust_decimal::Decimal`.
+#[derive(PartialEq, Debug)]
+struct Decimal {
+    units: i64,
+    scale: u32,
+}
+
+impl fmt::Display for Decimal {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let pow = 10i64.pow(self.scale);
+        let sign = if self.units < 0 { "-" } else { "" };
+        let units = self.units.abs();
+        if self.scale == 0 {
+            write!(f, "{}{}", sign, units)
+        } else {
+            write!(f, "{}{}.{:0width$}", sign, units / pow, units % pow, width = self.scale as usize)
+        }
+    }
+}
+
+impl FromStr for Decimal {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, ()> {
+        let scale = s.find('.').map_or(0, |point| s.len() - point - 1) as u32;
+        let units = s.replace('.', "").parse().map_err(|_| ())?;
+        Ok(Decimal { units, scale })
+    }
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+struct Invoice {
+    #[serde(with = "serde_yaml::with::decimal")]
+    total: Decimal,
+    #[serde(with = "serde_yaml::with::decimal")]
+    rate: Decimal,
+    #[serde(with = "serde_yaml::with::decimal")]
+    serial: u128,
+}
+
+#[test]
+fn test_decimal_round_trip() {
+    let yaml = "---\ntotal: 10.50\nrate: -0.000000000000000001\nserial: 123456789012345678901234567890";
+    let invoice: Invoice = serde_yaml::from_str(yaml).unwrap();
+    assert_eq!(invoice.total, Decimal { units: 1050, scale: 2 });
+    assert_eq!(invoice.rate, Decimal { units: -1, scale: 18 });
+    assert_eq!(invoice.serial, 123456789012345678901234567890);
+    assert_eq!(serde_yaml::to_string(&invoice).unwrap(), yaml);
+}
+
+#[test]
+fn test_decimal_quoted_and_aliased() {
+    let yaml = "{total: '1.0', rate: &r 2.50, serial: *r}";
+    let err = serde_yaml::from_str::<Invoice>(yaml).unwrap_err();
+    assert_eq!(err.to_string(),
+               "serial: invalid value: string \"2.50\", expected a number at line 1 column 25");
+    let invoice: Invoice = serde_yaml::from_str("{total: '1.0', rate: &r 2.50, serial: 3}").unwrap();
+    assert_eq!(invoice.total, Decimal { units: 10, scale: 1 });
+}
+
+#[test]
+fn test_decimal_from_value() {
+    let value: serde_yaml::Value = serde_yaml::from_str("{total: 2.5, rate: 1, serial: 7}").unwrap();
+    let invoice: Invoice = serde_yaml::from_value(value).unwrap();
+    assert_eq!(invoice.total, Decimal { units: 25, scale: 1 });
+    assert_eq!(invoice.rate, Decimal { units: 1, scale: 0 });
+    assert_eq!(invoice.serial, 7);
+}
+
+#[test]
+fn test_decimal_not_a_number() {
+    #[derive(Serialize)]
+    struct Label {
+        #[serde(with = "serde_yaml::with::decimal")]
+        text: &'static str,
+    }
+
+    let err = serde_yaml::to_string(&Label { text: "ten" }).unwrap_err();
+    assert_eq!(err.to_string(), "text: `ten` is not a number");
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+struct Endpoint {
+    #[serde(with = "serde_yaml::with::display_fromstr")]
+    ip: IpAddr,
+    #[serde(with = "serde_yaml::with::display_fromstr")]
+    socket: SocketAddr,
+    #[serde(with = "serde_yaml::with::display_fromstr")]
+    port: u16,
+}
+
+#[test]
+fn test_display_fromstr_round_trip() {
+    let endpoint = Endpoint {
+        ip: "::1".parse().unwrap(),
+        socket: "127.0.0.1:8080".parse().unwrap(),
+        port: 443,
+    };
+    let yaml = serde_yaml::to_string(&endpoint).unwrap();
+    assert_eq!(yaml, "---\nip: \"::1\"\nsocket: \"127.0.0.1:8080\"\nport: \"443\"");
+    assert_eq!(serde_yaml::from_str::<Endpoint>(&yaml).unwrap(), endpoint);
+    let plain: Endpoint = serde_yaml::from_str("{ip: '::1', socket: '127.0.0.1:8080', port: 443}").unwrap();
+    assert_eq!(plain, endpoint);
+    let value: serde_yaml::Value = serde_yaml::from_str(&yaml).unwrap();
+    assert_eq!(serde_yaml::from_value::<Endpoint>(value).unwrap(), endpoint);
+}
+
+#[test]
+fn test_display_fromstr_error() {
+    let yaml = "ip: 10.0.0.1\nsocket: localhost\nport: 1";
+    let err = serde_yaml::from_str::<Endpoint>(yaml).unwrap_err();
+    assert_eq!(err.to_string(),
+               "socket: invalid value `localhost`: invalid socket address syntax at line 2 column 9");
+    let yaml = "ip: 10.0.0.1\nsocket: 10.0.0.1:1\nport: 70000";
+    let err = serde_yaml::from_str::<Endpoint>(yaml).unwrap_err();
+    assert_eq!(err.to_string(),
+               "port: invalid value `70000`: number too large to fit in target type at line 3 column 7");
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+struct Timeout {
+    #[serde(with = "serde_yaml::with::humantime_duration")]
+    after: Duration,
+}
+
+fn timeout(yaml: &str) -> Result<Duration, String> {
+    serde_yaml::from_str::<Timeout>(&format!("after: {}", yaml)).map(|t| t.after).map_err(|e| e.to_string())
+}
+
+#[test]
+fn test_humantime_duration() {
+    assert_eq!(timeout("30s"), Ok(Duration::from_secs(30)));
+    assert_eq!(timeout("5m"), Ok(Duration::from_secs(300)));
+    assert_eq!(timeout("1h 30m"), Ok(Duration::from_secs(5400)));
+    assert_eq!(timeout("2 days 1sec"), Ok(Duration::from_secs(172801)));
+    assert_eq!(timeout("1w1d"), Ok(Duration::from_secs(8 * 86400)));
+    assert_eq!(timeout("1s 5ms 7us 9ns"), Ok(Duration::new(1, 5_007_009)));
+    assert_eq!(timeout("'250µs'"), Ok(Duration::from_micros(250)));
+
+    let expected = "expected a duration like `30s` or `1h 30m` at line 1 column 8";
+    assert_eq!(timeout("30"), Err(format!("after: invalid value: string \"30\", {}", expected)));
+    assert_eq!(timeout("1.5s"), Err(format!("after: invalid value: string \"1.5s\", {}", expected)));
+    assert_eq!(timeout("5 fortnights"),
+               Err(format!("after: invalid value: string \"5 fortnights\", {}", expected)));
+    assert!(timeout("99999999999999999999w").is_err());
+    assert!(timeout("40000000000000w").is_err());
+    assert!(timeout("18446744073709551615s 1s").is_err());
+}
+
+#[test]
+fn test_humantime_duration_serialize() {
+    let cases = [(Duration::new(0, 0), "0s"),
+                 (Duration::from_millis(1500), "1s 500ms"),
+                 (Duration::from_secs(90061), "1d 1h 1m 1s"),
+                 (Duration::new(86400, 1001), "1d 1us 1ns")];
+    for &(after, expected) in &cases {
+        let yaml = serde_yaml::to_string(&Timeout { after }).unwrap();
+        assert_eq!(yaml, format!("---\nafter: {}", expected));
+        assert_eq!(serde_yaml::from_str::<Timeout>(&yaml).unwrap().after, after);
+    }
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+struct Quota {
+    #[serde(with = "serde_yaml::with::byte_size")]
+    size: u64,
+}
+
+fn quota(yaml: &str) -> Option<u64> {
+    serde_yaml::from_str::<Quota>(&format!("size: {}", yaml)).map(|q| q.size).ok()
+}
+
+#[test]
+fn test_byte_size() {
+    assert_eq!(quota("512"), Some(512));
+    assert_eq!(quota("512B"), Some(512));
+    assert_eq!(quota("10MiB"), Some(10 << 20));
+    assert_eq!(quota("10 MB"), Some(10_000_000));
+    assert_eq!(quota("4KB"), Some(4000));
+    assert_eq!(quota("1.5GiB"), Some(3 << 29));
+    assert_eq!(quota(".5kB"), Some(500));
+    assert_eq!(quota("16EiB"), None);
+    assert_eq!(quota("15EiB"), Some(15 << 60));
+    assert_eq!(quota("1.5"), None);
+    assert_eq!(quota("1.0001kB"), None);
+    assert_eq!(quota("1.2.3MB"), None);
+    assert_eq!(quota("10 mb"), None);
+    assert_eq!(quota("MB"), None);
+
+    let err = serde_yaml::from_str::<Quota>("size: 10 mb").unwrap_err();
+    assert_eq!(err.to_string(),
+               "size: invalid value: string \"10 mb\", expected a byte size like `512`, `10MiB` or `1.5 GB` \
+                at line 1 column 7");
+}
+
+#[test]
+fn test_byte_size_serialize() {
+    let cases = [(0, "0B"), (512, "512B"), (2048, "2KiB"), (3000, "3kB"), (2048000, "2000KiB"),
+                 (5 << 40, "5TiB"), (u64::MAX, "18446744073709551615B")];
+    for &(size, expected) in &cases {
+        let yaml = serde_yaml::to_string(&Quota { size }).unwrap();
+        assert_eq!(yaml, format!("---\nsize: {}", expected));
+        assert_eq!(serde_yaml::from_str::<Quota>(&yaml).unwrap().size, size);
+    }
+    let value = serde_yaml::to_value(Quota { size: 1 << 20 }).unwrap();
+    assert_eq!(value, serde_yaml::from_str::<serde_yaml::Value>("size: 1MiB").unwrap());
+    let value: serde_yaml::Value = serde_yaml::from_str("size: 5").unwrap();
+    assert_eq!(serde_yaml::from_value::<Quota>(value).unwrap().size, 5);
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+struct Pipeline {
+    #[serde(with = "serde_yaml::with::seq_of_singletons")]
+    steps: Vec<(String, Step)>,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+struct Step {
+    jobs: u32,
+}
+
+#[test]
+fn test_seq_of_singletons() {
+    let yaml = "---\nsteps:\n  - build:\n      jobs: 1\n  - test:\n      jobs: 4\n  - build:\n      jobs: 2";
+    let pipeline: Pipeline = serde_yaml::from_str(yaml).unwrap();
+    let names: Vec<&str> = pipeline.steps.iter().map(|step| &*step.0).collect();
+    assert_eq!(names, ["build", "test", "build"]);
+    assert_eq!(pipeline.steps[1].1, Step { jobs: 4 });
+    assert_eq!(serde_yaml::to_string(&pipeline).unwrap(), yaml);
+
+    let value = serde_yaml::to_value(&pipeline).unwrap();
+    assert_eq!(serde_yaml::from_value::<Pipeline>(value).unwrap(), pipeline);
+
+    let empty: Pipeline = serde_yaml::from_str("steps: []").unwrap();
+    assert_eq!(empty.steps, []);
+}
+
+#[test]
+fn test_seq_of_singletons_map() {
+    #[derive(Serialize, Deserialize)]
+    struct Ordered {
+        #[serde(with = "serde_yaml::with::seq_of_singletons")]
+        steps: LinkedHashMap<String, u32>,
+    }
+
+    let yaml = "---\nsteps:\n  - zeta: 1\n  - alpha: 2";
+    let ordered: Ordered = serde_yaml::from_str(yaml).unwrap();
+    let keys: Vec<&str> = ordered.steps.keys().map(|key| &**key).collect();
+    assert_eq!(keys, ["zeta", "alpha"]);
+    assert_eq!(serde_yaml::to_string(&ordered).unwrap(), yaml);
+}
+
+#[test]
+fn test_sorted() {
+    use std::collections::HashSet;
+
+    #[derive(Serialize, Deserialize)]
+    struct Ports {
+        #[serde(with = "serde_yaml::with::sorted")]
+        open: HashSet<u16>,
+    }
+
+    let ports = Ports { open: [8080, 22, 443, 80].iter().cloned().collect() };
+    let yaml = "---\nopen:\n  - 22\n  - 80\n  - 443\n  - 8080";
+    assert_eq!(serde_yaml::to_string(&ports).unwrap(), yaml);
+    let ports: Ports = serde_yaml::from_str(yaml).unwrap();
+    assert_eq!(ports.open.len(), 4);
+    assert!(ports.open.contains(&443));
+}
+
+#[test]
+fn test_seq_of_singletons_errors() {
+    let err = serde_yaml::from_str::<Pipeline>("steps:\n  - build: {jobs: 1}\n    test: {jobs: 2}").unwrap_err();
+    assert_eq!(err.to_string(),
+               "steps[0]: invalid length 2, expected a mapping with a single key at line 2 column 10");
+    let err = serde_yaml::from_str::<Pipeline>("steps:\n  - {}").unwrap_err();
+    assert_eq!(err.to_string(),
+               "steps[0]: invalid length 0, expected a mapping with a single key at line 2 column 5");
+    let err = serde_yaml::from_str::<Pipeline>("steps: {build: {jobs: 1}}").unwrap_err();
+    assert_eq!(err.to_string(),
+               "steps: invalid type: map, expected a sequence of single-key mappings at line 1 column 8");
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+struct Port {
+    number: u16,
+    #[serde(default)]
+    udp: bool,
+}
+
+impl FromStr for Port {
+    type Err = std::num::ParseIntError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (number, udp) = match s.strip_suffix("/udp") {
+            Some(number) => (number, true),
+            None => (s, false),
+        };
+        Ok(Port {
+            number: number.parse()?,
+            udp,
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+struct Ports {
+    #[serde(with = "serde_yaml::with::string_or_struct")]
+    http: Port,
+    #[serde(with = "serde_yaml::with::string_or_struct")]
+    dns: Port,
+    #[serde(with = "serde_yaml::with::string_or_struct")]
+    admin: Port,
+}
+
+#[test]
+fn test_string_or_struct() {
+    let yaml = "http: 80\ndns: 53/udp\nadmin: {number: 9000}";
+    let ports: Ports = serde_yaml::from_str(yaml).unwrap();
+    assert_eq!(ports.http, Port { number: 80, udp: false });
+    assert_eq!(ports.dns, Port { number: 53, udp: true });
+    assert_eq!(ports.admin, Port { number: 9000, udp: false });
+
+    let written = serde_yaml::to_string(&ports).unwrap();
+    assert!(written.starts_with("---\nhttp:\n  number: 80\n  udp: false\n"), "{}", written);
+    assert_eq!(serde_yaml::from_str::<Ports>(&written).unwrap(), ports);
+
+    let value = serde_yaml::from_str::<serde_yaml::Value>(yaml).unwrap();
+    assert_eq!(serde_yaml::from_value::<Ports>(value).unwrap(), ports);
+
+    let yaml = "http: &p {number: 8080, udp: false}\ndns: '53'\nadmin: *p";
+    let ports: Ports = serde_yaml::from_str(yaml).unwrap();
+    assert_eq!(ports.admin, ports.http);
+}
+
+#[test]
+fn test_string_or_struct_errors() {
+    let err = serde_yaml::from_str::<Ports>("http: x\ndns: 53\nadmin: 1").unwrap_err();
+    assert_eq!(err.to_string(), "http: invalid value `x`: invalid digit found in string at line 1 column 7");
+    let err = serde_yaml::from_str::<Ports>("http: [80]\ndns: 53\nadmin: 1").unwrap_err();
+    assert_eq!(err.to_string(),
+               "http: invalid type: sequence, expected a scalar or a mapping at line 1 column 7");
+    let err = serde_yaml::from_str::<Ports>("http: {udp: true}\ndns: 53\nadmin: 1").unwrap_err();
+    assert_eq!(err.to_string(), "http: missing field `number` at line 1 column 7");
+}
@@ -0,0 +1,456 @@
+// Copyright 2018 Serde YAML Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+#![cfg(all(feature = "de", feature = "value"))]
+
+#[macro_use]
+extern crate serde_derive;
+extern crate serde_yaml;
+
+use std::io;
+
+/// Records the size of every write and the output length at every flush.
+#[derive(Default)]
+struct Recorder {
+    bytes: Vec<u8>,
+    writes: Vec<usize>,
+    flushes: Vec<usize>,
+}
+
+impl io::Write for Recorder {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.bytes.extend_from_slice(buf);
+        self.writes.push(buf.len());
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.flushes.push(self.bytes.len());
+        Ok(())
+    }
+}
+
+#[test]
+fn test_to_writer_chunks() {
+    let value: Vec<String> = (0..10000).map(|i| format!("item {}", i)).collect();
+    let mut recorder = Recorder::default();
+    serde_yaml::to_writer(&mut recorder, &value).unwrap();
+    assert_eq!(recorder.bytes, serde_yaml::to_vec(&value).unwrap());
+    assert!(recorder.writes.len() > 1);
+    assert!(recorder.writes.iter().all(|&len| len <= 8 * 1024));
+}
+
+#[test]
+fn test_to_writer_long_scalar() {
+    let value = "x".repeat(100000);
+    let mut recorder = Recorder::default();
+    serde_yaml::to_writer(&mut recorder, &value).unwrap();
+    assert_eq!(recorder.bytes.len(), 4 + value.len());
+}
+
+#[test]
+fn test_flush_every_document() {
+    let mut writer = serde_yaml::Writer::new(Recorder::default()).flush_every_document(true);
+    writer.write(&1).unwrap();
+    writer.write(&vec!["a", "b"]).unwrap();
+    let recorder = writer.into_inner().unwrap();
+    let yaml = "---\n1\n---\n- a\n- b\n";
+    assert_eq!(String::from_utf8(recorder.bytes).unwrap(), yaml);
+    assert_eq!(recorder.flushes, vec![6, yaml.len(), yaml.len()]);
+}
+
+#[test]
+fn test_buffered_documents() {
+    let mut writer = serde_yaml::Writer::new(Recorder::default());
+    writer.write(&1).unwrap();
+    writer.write(&2).unwrap();
+    let recorder = writer.into_inner().unwrap();
+    assert_eq!(recorder.writes, vec![12]);
+    assert_eq!(recorder.flushes, vec![12]);
+}
+
+#[test]
+fn test_to_writer_multi() {
+    let mut out = Vec::new();
+    serde_yaml::to_writer_multi(&mut out, vec![1, 2]).unwrap();
+    assert_eq!(String::from_utf8(out).unwrap(), "---\n1\n---\n2\n");
+
+    let mut out = Vec::new();
+    serde_yaml::to_writer_multi(&mut out, Vec::<u8>::new()).unwrap();
+    assert!(out.is_empty());
+
+    // Strings that look like markers stay inside their document.
+    let documents = vec!["---\n...\n", "...", ""];
+    let mut writer = serde_yaml::Writer::new(Vec::new()).end_markers(true);
+    for document in &documents {
+        writer.write(document).unwrap();
+    }
+    let yaml = String::from_utf8(writer.into_inner().unwrap()).unwrap();
+    assert_eq!(yaml, "---\n\"---\\n...\\n\"\n...\n---\n\"...\"\n...\n---\n\"\"\n...\n");
+    assert_eq!(serde_yaml::from_str_documents::<Vec<String>>(&yaml).unwrap(), documents);
+}
+
+#[test]
+fn test_io_error() {
+    struct Broken;
+
+    impl io::Write for Broken {
+        fn write(&mut self, _buf: &[u8]) -> io::Result<usize> {
+            Err(io::Error::new(io::ErrorKind::BrokenPipe, "reader went away"))
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let value: Vec<u32> = (0..10000).collect();
+    let err = serde_yaml::to_writer(Broken, &value).unwrap_err();
+    assert_eq!(err.to_string(), "reader went away");
+}
+
+#[test]
+fn test_to_fmt_writer() {
+    use std::fmt;
+
+    struct Yaml<'a>(&'a [u32]);
+
+    impl<'a> fmt::Display for Yaml<'a> {
+        fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            serde_yaml::to_fmt_writer(formatter, &self.0).map_err(|_| fmt::Error)
+        }
+    }
+
+    assert_eq!(format!("[{}]", Yaml(&[1, 2])), "[---\n- 1\n- 2]");
+
+    struct Full;
+
+    impl fmt::Write for Full {
+        fn write_str(&mut self, _s: &str) -> fmt::Result {
+            Err(fmt::Error)
+        }
+    }
+
+    assert!(serde_yaml::to_fmt_writer(Full, &1).is_err());
+}
+
+#[test]
+fn test_header_and_prologue() {
+    let header = "SPDX-License-Identifier: MIT\n\ngenerated";
+    let mut writer = serde_yaml::Writer::new(Vec::new()).header(header).finite_floats_only(true);
+    // A document that fails to serialize writes nothing, not even the header.
+    assert!(writer.write(&f64::NAN).is_err());
+    writer.write(&std::collections::BTreeMap::<u8, u8>::new()).unwrap();
+    let bytes = writer.into_inner().unwrap();
+    assert_eq!(String::from_utf8(bytes).unwrap(),
+               "# SPDX-License-Identifier: MIT\n#\n# generated\n---\n{}\n");
+
+    let mut writer = serde_yaml::Writer::new(Vec::new()).prologue("entry");
+    writer.write(&"a").unwrap();
+    writer.write(&"b").unwrap();
+    let bytes = writer.into_inner().unwrap();
+    assert_eq!(String::from_utf8(bytes).unwrap(), "# entry\n---\na\n# entry\n---\nb\n");
+    assert_eq!(serde_yaml::from_str::<String>("# entry\n---\na\n").unwrap(), "a");
+}
+
+#[test]
+fn test_blank_lines() {
+    use std::collections::BTreeMap;
+
+    fn write<T: serde::Serialize>(value: &T) -> String {
+        let mut writer = serde_yaml::Writer::new(Vec::new()).blank_lines(|_, _| true);
+        writer.write(value).unwrap();
+        String::from_utf8(writer.into_inner().unwrap()).unwrap()
+    }
+
+    let mut nested = BTreeMap::new();
+    nested.insert("x", 1);
+    nested.insert("y", 2);
+    let mut map = BTreeMap::new();
+    map.insert("a", nested.clone());
+    map.insert("b", nested.clone());
+    assert_eq!(write(&map), "---\na:\n  x: 1\n  \"y\": 2\n\nb:\n  x: 1\n  \"y\": 2\n");
+    assert_eq!(write(&vec![nested]), "---\n- x: 1\n  \"y\": 2\n");
+
+    let mut complex = BTreeMap::new();
+    complex.insert(vec![1], 1);
+    complex.insert(vec![2], 2);
+    assert_eq!(write(&complex), "---\n? - 1\n: 1\n? - 2\n: 2\n");
+
+    let mut map = BTreeMap::new();
+    map.insert(1, serde_yaml::comment::Commented::new("first", 1));
+    map.insert(2, 2.into());
+    assert_eq!(write(&map), "---\n# first\n1: 1\n\n2: 2\n");
+}
+
+#[test]
+fn test_block_strings() {
+    fn write<T: serde::Serialize>(value: &T) -> String {
+        let mut writer = serde_yaml::Writer::new(Vec::new()).block_strings(true);
+        writer.write(value).unwrap();
+        String::from_utf8(writer.into_inner().unwrap()).unwrap()
+    }
+
+    assert_eq!(write(&vec!["a\nb", "a\nb\n", "a\n\nb\n", "single"]),
+               "---\n- |-\n  a\n  b\n- |\n  a\n  b\n- |\n  a\n\n  b\n- single\n");
+    assert_eq!(write(&vec![vec!["x\ny"]]), "---\n- - |-\n    x\n    y\n");
+    assert_eq!(write(&vec!["x\ny\n"]), "---\n- |\n  x\n  y\n");
+
+    let mut nested = std::collections::BTreeMap::new();
+    nested.insert("key", "  indented\nsecond");
+    nested.insert("text", "one\n  two\n\tthree\n");
+    let mut map = std::collections::BTreeMap::new();
+    map.insert("nested", nested);
+    assert_eq!(write(&map),
+               "---\nnested:\n  key: \"  indented\\nsecond\"\n  text: |\n    one\n      two\n    \tthree\n");
+
+    // Strings that a block scalar would not give back.
+    for s in &["a\n\n", "\n", "a\n  \nb", "\n b", "a\r\nb"] {
+        assert!(write(&vec![s]).starts_with("---\n- \""), "{:?}", s);
+    }
+
+    // Everything reads back as written.
+    let strings = vec!["a\nb", "a\nb\n", "\na", "a\n\n\nb", "x: y\n- z\n# w", "a\n\n", "\t\na", "s"];
+    let yaml = write(&strings);
+    assert_eq!(serde_yaml::from_str::<Vec<String>>(&yaml).unwrap(), strings);
+}
+
+#[test]
+fn test_string_styles() {
+    use serde_yaml::{StringStyles, YamlFormat};
+
+    let format = YamlFormat::new().string_styles(StringStyles::smart().folded(Some(20)));
+    let mut map = std::collections::BTreeMap::new();
+    map.insert("it's", vec!["null", "a'b", "a'b\\c", "tab\there", "x\ny", "one two three four five six"]);
+    let expected = [
+        "---",
+        "\"it's\":",
+        "  - 'null'",
+        "  - \"a'b\"",
+        "  - 'a''b\\c'",
+        "  - \"tab\\there\"",
+        "  - |-",
+        "    x",
+        "    y",
+        "  - >-",
+        "    one two three four",
+        "    five six",
+    ];
+    assert_eq!(format.to_string(&map).unwrap(), expected.join("\n"));
+
+    // Strings that folding would not give back.
+    for s in &["a  b c d e f g h i j k", " a b c d e f g h i j k", "a b c d e f g h i j k ", "abcdefghijklmnopqrstuvwxyz"] {
+        assert!(!format.to_string(&vec![s]).unwrap().contains('>'), "{:?}", s);
+    }
+
+    // Everything reads back as written, folded at either width.
+    let words = "w ".repeat(50);
+    let strings = vec!["yes", "- a", "'", "\"'\"", "#", "a: b", "~", "é ü 日本 語 テ キ ス ト で す よ ね", words.trim()];
+    let yaml = format.to_string(&strings).unwrap();
+    assert_eq!(serde_yaml::from_str::<Vec<String>>(&yaml).unwrap(), strings);
+    let mut writer = serde_yaml::Writer::new(Vec::new()).string_styles(StringStyles::smart());
+    writer.write(&strings).unwrap();
+    let yaml = String::from_utf8(writer.into_inner().unwrap()).unwrap();
+    assert_eq!(serde_yaml::from_str::<Vec<String>>(&yaml).unwrap(), strings);
+}
+
+#[test]
+fn test_skip_null_values() {
+    use serde_yaml::Value;
+
+    let value: Value = serde_yaml::from_str("{a: ~, b: [~, {c: ~}], ~: 1, d: {e: ~}}").unwrap();
+    let mut writer = serde_yaml::Writer::new(Vec::new()).skip_null_values(true);
+    writer.write(&value).unwrap();
+    let bytes = writer.into_inner().unwrap();
+    assert_eq!(String::from_utf8(bytes).unwrap(), "---\nb:\n  - ~\n  - {}\n~: 1\nd: {}\n");
+}
+
+#[test]
+fn test_sort_maps() {
+    use std::collections::HashMap;
+
+    #[derive(Serialize)]
+    struct Service {
+        name: &'static str,
+        env: HashMap<String, u32>,
+    }
+
+    let env = (0..20).map(|i| (format!("VAR_{:02}", 19 - i), i)).collect();
+    let service = Service { name: "web", env };
+    let format = serde_yaml::YamlFormat::new().sort_maps(true);
+    let yaml = format.to_string(&service).unwrap();
+    assert!(yaml.starts_with("---\nname: web\nenv:\n  VAR_00: 19\n  VAR_01: 18\n"), "{}", yaml);
+    let keys: Vec<&str> = yaml.lines().skip(3).map(|line| line.trim().split(':').next().unwrap()).collect();
+    let mut sorted = keys.clone();
+    sorted.sort();
+    assert_eq!(keys, sorted);
+
+    // Other documents are not affected.
+    let value: serde_yaml::Value = serde_yaml::from_str("{b: 1, a: 2}").unwrap();
+    assert_eq!(serde_yaml::to_string(&value).unwrap(), "---\nb: 1\na: 2");
+    assert_eq!(format.to_string(&value).unwrap(), "---\na: 2\nb: 1");
+}
+
+#[test]
+fn test_to_string_pretty() {
+    #[derive(Serialize)]
+    struct Step {
+        name: &'static str,
+        when: Option<&'static str>,
+        run: &'static str,
+    }
+
+    let steps = vec![Step {
+                         name: "build",
+                         when: None,
+                         run: "cargo build\ncargo test",
+                     },
+                     Step {
+                         name: "deploy",
+                         when: Some("tag"),
+                         run: "deploy.sh",
+                     }];
+    let yaml = serde_yaml::to_string_pretty(&steps).unwrap();
+    let expected = "---\n- name: build\n  run: |-\n    cargo build\n    cargo test\n\
+                    - name: deploy\n  when: tag\n  run: deploy.sh\n";
+    assert_eq!(yaml, expected);
+    assert_eq!(serde_yaml::to_string_pretty(&()).unwrap(), "---\n~\n");
+}
+
+#[test]
+fn test_to_string_compact() {
+    use serde_yaml::Value;
+
+    let value: Value = serde_yaml::from_str("{a: 1, b: [2, 3], c: {}, d: [], e: ~, 'f g': 'x, y'}").unwrap();
+    let yaml = serde_yaml::to_string_compact(&value).unwrap();
+    assert_eq!(yaml, r#"{a: 1, b: [2, 3], c: {}, d: [], e: ~, f g: "x, y"}"#);
+    assert_eq!(serde_yaml::from_str::<Value>(&yaml).unwrap(), value);
+
+    let value: Value = serde_yaml::from_str("[{[1, 2]: x}, \"two\\nlines\", '{', .inf]").unwrap();
+    let yaml = serde_yaml::to_string_compact(&value).unwrap();
+    assert_eq!(yaml, r#"[{[1, 2]: x}, "two\nlines", "{", .inf]"#);
+    assert_eq!(serde_yaml::from_str::<Value>(&yaml).unwrap(), value);
+
+    assert_eq!(serde_yaml::to_string_compact("a").unwrap(), "a");
+
+    let commented = serde_yaml::comment::Commented::new("dropped", vec![1]);
+    assert_eq!(serde_yaml::to_string_compact(&vec![commented]).unwrap(), "[[1]]");
+}
+
+#[test]
+fn test_to_string_indented() {
+    use serde_yaml::comment::Commented;
+    use serde_yaml::Value;
+
+    let value: Value = serde_yaml::from_str("a: [1, {b: 2}]\nc: {}").unwrap();
+    assert_eq!(serde_yaml::to_string_indented(&value, 2).unwrap(), "  a:\n    - 1\n    - b: 2\n  c: {}");
+    assert_eq!(serde_yaml::to_string_indented(&vec![vec![1]], 0).unwrap(), "- - 1");
+    assert_eq!(serde_yaml::to_string_indented("x", 3).unwrap(), "   x");
+
+    let mut map = std::collections::BTreeMap::new();
+    map.insert("script", Commented::new("Runs first.", serde_yaml::style::Styled::new(serde_yaml::style::Style::Literal, "a\n\nb\n")));
+    let fragment = serde_yaml::to_string_indented(&map, 4).unwrap();
+    assert_eq!(fragment, "    # Runs first.\n    script: |\n      a\n\n      b");
+
+    // The fragment reads back under a handwritten key.
+    let template = format!("steps:\n  - name: build\n{}\n", serde_yaml::to_string_indented(&map, 4).unwrap());
+    let parsed: Value = serde_yaml::from_str(&template).unwrap();
+    assert_eq!(parsed["steps"][0]["script"], Value::String("a\n\nb\n".to_owned()));
+}
+
+#[test]
+fn test_format() {
+    use serde_yaml::YamlFormat;
+
+    fn assert_shareable<T: Clone + Send + Sync + 'static>() {}
+    assert_shareable::<YamlFormat>();
+
+    let value = vec![Some("a\nb"), None];
+    let format = YamlFormat::new().block_strings(true);
+    assert_eq!(format.to_string(&value).unwrap(), "---\n- |-\n  a\n  b\n- ~");
+    assert_eq!(format.to_vec(&value).unwrap(), format.to_string(&value).unwrap().into_bytes());
+
+    // Changing a clone leaves the original alone.
+    let newline = format.clone().trailing_newline(true).prologue("list");
+    assert_eq!(newline.to_string(&value).unwrap(), "# list\n---\n- |-\n  a\n  b\n- ~\n");
+    assert_eq!(newline.to_vec(&value).unwrap(), newline.to_string(&value).unwrap().into_bytes());
+    assert_eq!(format.to_string(&"x").unwrap(), "---\nx");
+
+    // A literal block at the end of the output keeps its final line break,
+    // whether or not the format asks for one.
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Text {
+        s: String,
+    }
+    let lines = vec!["a\nb\n".to_owned()];
+    assert_eq!(format.to_string(&lines).unwrap(), "---\n- |\n  a\n  b\n");
+    assert_eq!(newline.to_string(&lines).unwrap(), "# list\n---\n- |\n  a\n  b\n");
+    let yaml = format.to_string(&lines).unwrap();
+    assert_eq!(serde_yaml::from_str::<Vec<String>>(&yaml).unwrap(), lines);
+    let nested = vec![Text { s: "a\nb\n".to_owned() }];
+    let yaml = format.to_string(&nested).unwrap();
+    assert_eq!(yaml, "---\n- s: |\n    a\n    b\n");
+    assert_eq!(serde_yaml::from_str::<Vec<Text>>(&yaml).unwrap(), nested);
+
+    let mut writer = newline.writer(Vec::new());
+    writer.write(&1).unwrap();
+    writer.write(&2).unwrap();
+    let bytes = writer.into_inner().unwrap();
+    assert_eq!(String::from_utf8(bytes).unwrap(), "# list\n---\n1\n# list\n---\n2\n");
+
+    let mut map = std::collections::BTreeMap::new();
+    map.insert("a", None);
+    map.insert("b", Some("x\n"));
+    assert_eq!(YamlFormat::pretty().to_string(&map).unwrap(), serde_yaml::to_string_pretty(&map).unwrap());
+    assert_eq!(YamlFormat::new().to_string(&map).unwrap(), serde_yaml::to_string(&map).unwrap());
+}
+
+#[test]
+fn test_estimated_len() {
+    let yaml = "
+        name: app
+        tags: [a, b, '1']
+        servers:
+          - {host: example.com, port: 8080, weights: [0.5, -1, 18446744073709551615]}
+          - {host: '', port: 9090, empty: [], none: ~, on: true}
+    ";
+    let mut value: serde_yaml::Value = serde_yaml::from_str(yaml).unwrap();
+    if let serde_yaml::Value::Mapping(ref mut mapping) = value {
+        let entries: Vec<serde_yaml::Value> = (0..1000).map(|i| format!("entry {}", i).into()).collect();
+        mapping.insert("entries".into(), serde_yaml::Value::Sequence(entries));
+    }
+
+    let written = serde_yaml::to_string(&value).unwrap();
+    let estimate = value.estimated_yaml_len();
+    assert!(written.len() <= estimate, "{} > {}", written.len(), estimate);
+    assert!(estimate < written.len() * 3 / 2, "{} is far above {}", estimate, written.len());
+    assert!(written.capacity() < written.len() * 3 / 2);
+
+    let format = serde_yaml::YamlFormat::pretty().prologue("generated");
+    assert_eq!(format.to_vec(&value).unwrap(), format.to_string(&value).unwrap().into_bytes());
+
+    let controls: serde_yaml::Value = (0u8..32).map(|byte| (byte as char).to_string()).collect::<Vec<_>>().into();
+    let written = serde_yaml::to_string(&controls).unwrap();
+    assert!(written.len() <= controls.estimated_yaml_len(), "{} > {}", written.len(), controls.estimated_yaml_len());
+    assert!(written.capacity() < written.len() * 3 / 2);
+
+    let format = serde_yaml::YamlFormat::new().capacity(1 << 16);
+    assert_eq!(format.to_string(&controls).unwrap(), written);
+    assert!(format.to_string(&controls).unwrap().capacity() >= 1 << 16);
+}
+
+#[test]
+fn test_flush_on_drop() {
+    let mut out = Vec::new();
+    {
+        let mut writer = serde_yaml::Writer::new(&mut out);
+        writer.write(&1).unwrap();
+        writer.write(&2).unwrap();
+    }
+    assert_eq!(String::from_utf8(out).unwrap(), "---\n1\n---\n2\n");
+}
@@ -0,0 +1,68 @@
+// Copyright 2018 Serde YAML Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+#![cfg(all(feature = "de", feature = "value"))]
+
+extern crate serde_yaml;
+extern crate yaml_rust;
+
+extern crate unindent;
+use unindent::unindent;
+
+use std::f64;
+
+use serde_yaml::{Number, Value};
+use yaml_rust::{Yaml, YamlLoader};
+
+fn load(yaml: &str) -> Yaml {
+    YamlLoader::load_from_str(yaml).unwrap().remove(0)
+}
+
+#[test]
+fn test_round_trip() {
+    let yaml = unindent("
+        ---
+        name: demo
+        ratio: 0.5
+        enabled: true
+        missing: ~
+        1: integer key
+        [a, b]: sequence key
+        nested:
+          - x
+          - {y: 2}");
+    let value = Value::from_yaml(load(&yaml)).unwrap();
+    assert_eq!(value, serde_yaml::from_str::<Value>(&yaml).unwrap());
+    assert_eq!(Yaml::from(value.clone()), load(&yaml));
+    assert_eq!(Value::from_yaml(Yaml::from(value.clone())).unwrap(), value);
+}
+
+#[test]
+fn test_numbers() {
+    let value = Value::from_yaml(load("[18446744073709551615, -3, .inf, -.inf, .nan]")).unwrap();
+    assert_eq!(value[0], Value::Number(Number::from(u64::MAX)));
+    assert_eq!(value[1], -3);
+    assert_eq!(value[2].as_f64(), Some(f64::INFINITY));
+    assert_eq!(value[3].as_f64(), Some(f64::NEG_INFINITY));
+    assert!(value[4].as_f64().unwrap().is_nan());
+
+    let yaml = Yaml::from(value);
+    assert_eq!(yaml[0], Yaml::Real("18446744073709551615".to_owned()));
+    assert_eq!(yaml[1], Yaml::Integer(-3));
+    assert_eq!(yaml[2], Yaml::Real(".inf".to_owned()));
+    assert_eq!(yaml[3], Yaml::Real("-.inf".to_owned()));
+    assert_eq!(yaml[4], Yaml::Real(".nan".to_owned()));
+}
+
+#[test]
+fn test_unconvertible() {
+    assert!(Value::from_yaml(load("!!int abc")).is_err());
+    assert!(Value::from_yaml(Yaml::Array(vec![Yaml::Alias(1)])).is_err());
+    assert!(Value::from_yaml(Yaml::Real("abc".to_owned())).is_err());
+    assert!(Value::from_yaml(Yaml::Real("!!binary aGk=".to_owned())).is_err());
+}
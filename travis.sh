@@ -29,9 +29,12 @@ else
         cargo clean
         channel build
         channel test
+        for FEATURES in std,de std,ser std,ser,value; do
+            channel test --tests --no-default-features --features "${FEATURES}"
+        done
     done
 
-    CHANNEL=1.15.0
+    CHANNEL=1.70.0
     cargo clean
     channel build
 fi